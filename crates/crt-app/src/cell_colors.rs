@@ -0,0 +1,124 @@
+// ABOUTME: Final colors of a cell where inverse video, the selection and the cursor overlap.
+// ABOUTME: Each of them swaps foreground and background, applied in that order.
+
+/// What is drawn over a cell on top of its own colors
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Highlight {
+    /// The cell's own INVERSE attribute
+    pub inverse: bool,
+    pub selected: bool,
+    /// A block cursor sits on the cell
+    pub cursor: bool,
+}
+
+/// Foreground and background to draw. The cell's inverse attribute, the
+/// selection and the block cursor are applied in that order and each swaps
+/// the colors, so the cursor stays visible inside a selection and a selected
+/// inverse cell reads as normal text. `bg` is transparent for the default
+/// background, which becomes `default_bg` once it is drawn as text.
+pub fn resolve(
+    fg: [f32; 4],
+    bg: [f32; 4],
+    default_bg: [f32; 4],
+    highlight: Highlight,
+) -> ([f32; 4], [f32; 4]) {
+    let swaps = [highlight.inverse, highlight.selected, highlight.cursor]
+        .iter()
+        .filter(|&&on| on)
+        .count();
+    if swaps % 2 == 0 {
+        return (fg, bg);
+    }
+    let bg = if bg[3] < 0.01 { default_bg } else { bg };
+    (bg, fg)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FG: [f32; 4] = [1.0, 0.7, 0.0, 1.0];
+    const BG: [f32; 4] = [0.0, 0.0, 0.5, 1.0];
+    const DEFAULT_BG: [f32; 4] = [0.05, 0.05, 0.05, 1.0];
+    const TRANSPARENT: [f32; 4] = [0.0; 4];
+
+    fn highlight(inverse: bool, selected: bool, cursor: bool) -> Highlight {
+        Highlight {
+            inverse,
+            selected,
+            cursor,
+        }
+    }
+
+    #[test]
+    fn plain_cell_keeps_its_colors() {
+        assert_eq!(
+            resolve(FG, BG, DEFAULT_BG, highlight(false, false, false)),
+            (FG, BG)
+        );
+    }
+
+    #[test]
+    fn inverse_cell_swaps() {
+        assert_eq!(
+            resolve(FG, BG, DEFAULT_BG, highlight(true, false, false)),
+            (BG, FG)
+        );
+    }
+
+    #[test]
+    fn selected_cell_swaps() {
+        assert_eq!(
+            resolve(FG, BG, DEFAULT_BG, highlight(false, true, false)),
+            (BG, FG)
+        );
+    }
+
+    #[test]
+    fn cursor_cell_swaps() {
+        assert_eq!(
+            resolve(FG, BG, DEFAULT_BG, highlight(false, false, true)),
+            (BG, FG)
+        );
+    }
+
+    #[test]
+    fn selected_inverse_cell_reads_as_normal() {
+        assert_eq!(
+            resolve(FG, BG, DEFAULT_BG, highlight(true, true, false)),
+            (FG, BG)
+        );
+    }
+
+    #[test]
+    fn cursor_on_inverse_cell_reads_as_normal() {
+        assert_eq!(
+            resolve(FG, BG, DEFAULT_BG, highlight(true, false, true)),
+            (FG, BG)
+        );
+    }
+
+    #[test]
+    fn cursor_stands_out_inside_selection() {
+        assert_eq!(
+            resolve(FG, BG, DEFAULT_BG, highlight(false, true, true)),
+            (FG, BG)
+        );
+    }
+
+    #[test]
+    fn cursor_on_selected_inverse_cell_swaps() {
+        assert_eq!(
+            resolve(FG, BG, DEFAULT_BG, highlight(true, true, true)),
+            (BG, FG)
+        );
+    }
+
+    #[test]
+    fn default_background_becomes_opaque_only_when_swapped() {
+        let swapped = resolve(FG, TRANSPARENT, DEFAULT_BG, highlight(false, true, false));
+        assert_eq!(swapped, (DEFAULT_BG, FG));
+        let unswapped = resolve(FG, TRANSPARENT, DEFAULT_BG, highlight(false, true, true));
+        assert_eq!(unswapped, (FG, TRANSPARENT));
+    }
+}
@@ -0,0 +1,35 @@
+// ABOUTME: Reusable storage for per-pane render cell grids.
+// ABOUTME: Rebuilt grids take their rows from here instead of allocating.
+
+use crt_renderer::RenderCell;
+
+/// Spare cell grids, returned when a pane's cells are rebuilt or the pane
+/// closes and handed out again for the next rebuild. Grids keep their row
+/// allocations, so steady-state rendering doesn't touch the allocator.
+#[derive(Default)]
+pub struct RenderCellPool {
+    grids: Vec<Vec<Vec<RenderCell>>>,
+}
+
+impl RenderCellPool {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Take a grid of `rows` empty rows, each with room for `cols` cells.
+    /// Allocates only when the pool is empty or the grid needs to be larger.
+    pub fn acquire_grid(&mut self, rows: usize, cols: usize) -> Vec<Vec<RenderCell>> {
+        let mut grid = self.grids.pop().unwrap_or_default();
+        grid.resize_with(rows, Vec::new);
+        for row in &mut grid {
+            row.clear();
+            row.reserve(cols);
+        }
+        grid
+    }
+
+    /// Give a grid back for reuse
+    pub fn release(&mut self, grid: Vec<Vec<RenderCell>>) {
+        self.grids.push(grid);
+    }
+}
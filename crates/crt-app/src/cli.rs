@@ -0,0 +1,168 @@
+// ABOUTME: Command-line argument parsing.
+// ABOUTME: Handles -e/--command/--, --working-directory, --config, --perf, --list-schemes, --help and --version.
+
+use std::path::PathBuf;
+
+use crt_core::ColorScheme;
+
+pub const USAGE: &str = "\
+Usage: cool-rust-term [OPTIONS] [-e COMMAND [ARGS...]]
+
+Options:
+  -e, --command COMMAND [ARGS...]  Run COMMAND in the first pane instead of the shell
+                                   (must be last; everything after it is passed on)
+      -- COMMAND [ARGS...]         Same as -e
+      --working-directory PATH     Start the first pane in PATH
+      --config PATH                Use PATH instead of the default config file
+      --perf                       Show frame timing and glyph cache stats, and log them
+      --list-schemes               Print the built-in color scheme names and exit
+  -h, --help                       Print this help and exit
+  -V, --version                    Print the version and exit";
+
+/// Options given on the command line
+#[derive(Debug, Default)]
+pub struct CliArgs {
+    /// Program and arguments to run in the first pane
+    pub command: Option<Vec<String>>,
+    pub working_directory: Option<PathBuf>,
+    pub config_path: Option<PathBuf>,
+    /// Show the performance overlay regardless of the config
+    pub perf: bool,
+}
+
+/// What `main` should do after parsing
+#[derive(Debug)]
+pub enum CliAction {
+    Run(CliArgs),
+    /// Print the text to stdout and exit successfully
+    Print(String),
+}
+
+/// Parse arguments (without the program name)
+pub fn parse(args: impl IntoIterator<Item = String>) -> Result<CliAction, String> {
+    let mut cli = CliArgs::default();
+    let mut args = args.into_iter();
+
+    while let Some(arg) = args.next() {
+        // Accept both "--flag value" and "--flag=value"
+        let (flag, inline_value) = match arg.split_once('=') {
+            Some((flag, value)) if flag.starts_with("--") && flag.len() > 2 => {
+                (flag.to_string(), Some(value))
+            }
+            _ => (arg.clone(), None),
+        };
+        let mut value = |name: &str| -> Result<String, String> {
+            match inline_value {
+                Some(v) => Ok(v.to_string()),
+                None => args
+                    .next()
+                    .ok_or_else(|| format!("{} requires a value", name)),
+            }
+        };
+
+        match flag.as_str() {
+            "--help" | "--version" | "--perf" | "--list-schemes" if inline_value.is_some() => {
+                return Err(format!("{} does not take a value", flag));
+            }
+            "-h" | "--help" => return Ok(CliAction::Print(USAGE.to_string())),
+            "-V" | "--version" => {
+                return Ok(CliAction::Print(format!(
+                    "cool-rust-term {}",
+                    env!("CARGO_PKG_VERSION")
+                )))
+            }
+            "--perf" => cli.perf = true,
+            "--list-schemes" => {
+                let names: Vec<String> = ColorScheme::presets()
+                    .into_iter()
+                    .map(|scheme| scheme.name)
+                    .collect();
+                return Ok(CliAction::Print(names.join("\n")));
+            }
+            "--working-directory" => {
+                cli.working_directory = Some(PathBuf::from(value("--working-directory")?));
+            }
+            "--config" => cli.config_path = Some(PathBuf::from(value("--config")?)),
+            "-e" | "--command" | "--" => {
+                let mut command: Vec<String> =
+                    inline_value.map(str::to_string).into_iter().collect();
+                command.extend(args.by_ref());
+                // `-e -- cmd` is the same as `-e cmd`
+                if flag != "--"
+                    && inline_value.is_none()
+                    && command.first().is_some_and(|c| c == "--")
+                {
+                    command.remove(0);
+                }
+                if command.is_empty() {
+                    return Err(format!("{} requires a command", flag));
+                }
+                cli.command = Some(command);
+            }
+            _ => return Err(format!("unrecognized argument '{}'", arg)),
+        }
+    }
+
+    Ok(CliAction::Run(cli))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(args: &[&str]) -> Result<CliArgs, String> {
+        match parse(args.iter().map(|arg| arg.to_string()))? {
+            CliAction::Run(cli) => Ok(cli),
+            CliAction::Print(text) => panic!("unexpected print: {}", text),
+        }
+    }
+
+    #[test]
+    fn command_takes_the_rest_of_the_arguments() {
+        let cli = run(&["--perf", "-e", "vim", "--config", "x", "-e"]).unwrap();
+        assert!(cli.perf);
+        assert_eq!(cli.command.unwrap(), ["vim", "--config", "x", "-e"]);
+        assert_eq!(cli.config_path, None);
+
+        let cli = run(&["--command=htop", "-d", "5"]).unwrap();
+        assert_eq!(cli.command.unwrap(), ["htop", "-d", "5"]);
+    }
+
+    #[test]
+    fn double_dash_starts_the_command() {
+        let cli = run(&["--working-directory", "/tmp", "--", "ls", "-l"]).unwrap();
+        assert_eq!(cli.working_directory, Some(PathBuf::from("/tmp")));
+        assert_eq!(cli.command.unwrap(), ["ls", "-l"]);
+
+        let cli = run(&["-e", "--", "ls", "--"]).unwrap();
+        assert_eq!(cli.command.unwrap(), ["ls", "--"]);
+        assert!(run(&["--"]).is_err());
+    }
+
+    #[test]
+    fn missing_values_are_errors() {
+        assert_eq!(run(&["--config"]).unwrap_err(), "--config requires a value");
+        assert_eq!(
+            run(&["--working-directory"]).unwrap_err(),
+            "--working-directory requires a value"
+        );
+        assert_eq!(run(&["-e"]).unwrap_err(), "-e requires a command");
+        assert_eq!(
+            run(&["--config=/etc/crt.toml"]).unwrap().config_path,
+            Some(PathBuf::from("/etc/crt.toml"))
+        );
+    }
+
+    #[test]
+    fn unknown_flags_are_errors() {
+        assert_eq!(
+            run(&["--bogus"]).unwrap_err(),
+            "unrecognized argument '--bogus'"
+        );
+        assert_eq!(
+            run(&["--perf=yes"]).unwrap_err(),
+            "--perf does not take a value"
+        );
+        assert!(run(&["htop"]).is_err());
+    }
+}
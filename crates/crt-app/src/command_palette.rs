@@ -0,0 +1,363 @@
+// ABOUTME: Command palette overlay: run any action by typing part of its name.
+// ABOUTME: Fuzzy-filters keybinding actions plus pane and font commands.
+
+use crt_core::{Action, ColorScheme, Font, LineSize};
+use crt_renderer::{GlyphStyle, RenderCell};
+
+/// Panel width in cells
+const PANEL_WIDTH: usize = 56;
+/// Most commands listed below the search field
+const MAX_VISIBLE: usize = 10;
+
+/// What a palette entry does
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PaletteAction {
+    Run(Action),
+    /// Focus the pane at this index in layout order
+    FocusPane(usize),
+    SetFont(Font),
+}
+
+#[derive(Debug, Clone)]
+pub struct Command {
+    pub name: String,
+    pub description: &'static str,
+    pub action: PaletteAction,
+}
+
+/// Every command on offer, given how many panes are open
+pub fn commands(pane_count: usize) -> Vec<Command> {
+    // Beam stepping only works while its key is held down
+    let mut commands: Vec<Command> = Action::all()
+        .iter()
+        .filter(|&&action| !matches!(action, Action::CommandPalette | Action::BeamStep))
+        .map(|&action| Command {
+            name: action_title(action).to_string(),
+            description: action_description(action),
+            action: PaletteAction::Run(action),
+        })
+        .collect();
+    commands.extend((0..pane_count).map(|index| Command {
+        name: format!("Focus pane {}", index + 1),
+        description: "Move keyboard focus to this pane",
+        action: PaletteAction::FocusPane(index),
+    }));
+    commands.extend(Font::all().iter().map(|&font| Command {
+        name: format!("Set font to {}", font.label()),
+        description: "Switch to this bundled font and save it",
+        action: PaletteAction::SetFont(font),
+    }));
+    commands
+}
+
+fn action_title(action: Action) -> &'static str {
+    match action {
+        Action::NewPane => "New pane",
+        Action::OpenSettings => "Open settings",
+        Action::ToggleDebugGrid => "Toggle debug grid",
+        Action::ToggleBeamPause => "Toggle beam pause",
+        Action::BeamStep => "Step beam",
+        Action::BeamStepFaster => "Step beam faster",
+        Action::BeamStepSlower => "Step beam slower",
+        Action::Copy => "Copy",
+        Action::CopyFormatted => "Copy with formatting",
+        Action::Paste => "Paste",
+        Action::ReplayPowerOn => "Replay power-on",
+        Action::ScrollPageUp => "Scroll page up",
+        Action::ScrollPageDown => "Scroll page down",
+        Action::PreviousPrompt => "Previous prompt",
+        Action::NextPrompt => "Next prompt",
+        Action::CycleMonitor => "Cycle pane monitoring",
+        Action::Screenshot => "Screenshot",
+        Action::CopyMode => "Copy mode",
+        Action::HintMode => "Hint mode",
+        Action::ToggleRecording => "Toggle recording",
+        Action::ToggleEffects => "Toggle CRT effects",
+        Action::CommandPalette => "Command palette",
+        Action::Search => "Search",
+        Action::SelectAll => "Select all",
+        Action::ClearSelection => "Clear selection",
+        Action::ScrollToBottom => "Scroll to bottom",
+        Action::SwapPane => "Swap pane",
+        Action::ToggleFloating => "Toggle floating pane",
+    }
+}
+
+fn action_description(action: Action) -> &'static str {
+    match action {
+        Action::NewPane => "Open a new pane with a shell",
+        Action::OpenSettings => "Show the settings panel",
+        Action::ToggleDebugGrid => "Show the cell grid, FPS and GPU timings",
+        Action::ToggleBeamPause => "Freeze the simulated electron beam",
+        Action::BeamStep => "Advance the paused beam by one frame",
+        Action::BeamStepFaster => "Step the paused beam more often",
+        Action::BeamStepSlower => "Step the paused beam less often",
+        Action::Copy => "Copy the selection to the clipboard",
+        Action::CopyFormatted => "Copy the selection as HTML and ANSI, keeping its colors",
+        Action::Paste => "Paste from the clipboard",
+        Action::ReplayPowerOn => "Play the power-on animation again",
+        Action::ScrollPageUp => "Scroll back one page",
+        Action::ScrollPageDown => "Scroll forward one page",
+        Action::PreviousPrompt => "Jump to the previous shell prompt",
+        Action::NextPrompt => "Jump to the next shell prompt",
+        Action::CycleMonitor => "Watch the pane for activity or silence",
+        Action::Screenshot => "Save a PNG of the window",
+        Action::CopyMode => "Select text with the keyboard",
+        Action::HintMode => "Open or copy URLs and paths on screen",
+        Action::ToggleRecording => "Record the pane as an asciicast",
+        Action::ToggleEffects => "Switch between the CRT look and plain, crisp text",
+        Action::CommandPalette => "Run any command by name",
+        Action::Search => "Find text in the pane's scrollback",
+        Action::SelectAll => "Select the pane's screen and scrollback",
+        Action::ClearSelection => "Remove the text selection",
+        Action::ScrollToBottom => "Jump back to the newest output",
+        Action::SwapPane => "Trade places with the pane in an arrow key's direction",
+        Action::ToggleFloating => "Float the pane over the others, or show a hidden one",
+    }
+}
+
+/// How well `query` matches `name`: the number of query characters found
+/// in order (case-insensitive), or None if some are missing. Ties go to the
+/// tighter match, so the second value is the span the matches cover.
+pub fn fuzzy_score(query: &str, name: &str) -> Option<(usize, usize)> {
+    let name: Vec<char> = name.chars().flat_map(char::to_lowercase).collect();
+    let mut matched = 0;
+    let mut first = None;
+    let mut last = 0;
+    let mut pos = 0;
+    for q in query.chars().flat_map(char::to_lowercase) {
+        if q == ' ' {
+            continue;
+        }
+        let offset = name[pos..].iter().position(|&c| c == q)?;
+        pos += offset;
+        first.get_or_insert(pos);
+        last = pos;
+        matched += 1;
+        pos += 1;
+    }
+    Some((matched, last - first.unwrap_or(0)))
+}
+
+/// Palette state: the search text and which filtered entry is highlighted
+#[derive(Default)]
+pub struct CommandPalette {
+    pub visible: bool,
+    query: String,
+    selected: usize,
+    commands: Vec<Command>,
+}
+
+impl CommandPalette {
+    pub fn open(&mut self, commands: Vec<Command>) {
+        self.visible = true;
+        self.query.clear();
+        self.selected = 0;
+        self.commands = commands;
+    }
+
+    pub fn close(&mut self) {
+        self.visible = false;
+    }
+
+    pub fn type_text(&mut self, text: &str) {
+        self.query.extend(text.chars().filter(|c| !c.is_control()));
+        self.selected = 0;
+    }
+
+    pub fn backspace(&mut self) {
+        self.query.pop();
+        self.selected = 0;
+    }
+
+    pub fn move_up(&mut self) {
+        self.selected = self.selected.saturating_sub(1);
+    }
+
+    pub fn move_down(&mut self) {
+        let count = self.filtered().len();
+        if self.selected + 1 < count {
+            self.selected += 1;
+        }
+    }
+
+    /// Commands matching the query, best first
+    pub fn filtered(&self) -> Vec<&Command> {
+        let mut scored: Vec<((usize, usize), &Command)> = self
+            .commands
+            .iter()
+            .filter_map(|command| Some((fuzzy_score(&self.query, &command.name)?, command)))
+            .collect();
+        // Stable sort keeps the list order among equal matches
+        scored.sort_by(|(a, _), (b, _)| b.0.cmp(&a.0).then(a.1.cmp(&b.1)));
+        scored.into_iter().map(|(_, command)| command).collect()
+    }
+
+    /// Close the palette and return the highlighted command's action
+    pub fn accept(&mut self) -> Option<PaletteAction> {
+        let action = self
+            .filtered()
+            .get(self.selected)
+            .map(|command| command.action);
+        self.close();
+        action
+    }
+
+    /// Panel width in cells
+    pub fn width(&self) -> usize {
+        PANEL_WIDTH
+    }
+
+    /// Panel cells: search field, separator, then the filtered commands
+    pub fn render(&self, scheme: &ColorScheme) -> Vec<Vec<RenderCell>> {
+        let fg = scheme.foreground;
+        let bright = scheme.colors[15];
+        let border = scheme.colors[6];
+        let bg = [
+            scheme.background[0],
+            scheme.background[1],
+            scheme.background[2],
+            1.0,
+        ];
+        let dim = scheme.dim_color();
+        let highlight = [fg[0] * 0.15, fg[1] * 0.15, fg[2] * 0.15, 1.0];
+        let inner = PANEL_WIDTH - 2;
+
+        let filtered = self.filtered();
+        // Scroll the list so the selection stays in view
+        let first = self.selected.saturating_sub(MAX_VISIBLE - 1);
+        let shown: Vec<&Command> = filtered
+            .iter()
+            .skip(first)
+            .take(MAX_VISIBLE)
+            .copied()
+            .collect();
+
+        let mut rows = Vec::new();
+        rows.push(frame_row('┌', " Commands ", '┐', border, bright, bg));
+        let search = format!(" > {}_", self.query);
+        rows.push(text_row(&search, inner, border, bright, bg));
+        rows.push(frame_row('├', "", '┤', border, bright, bg));
+        if shown.is_empty() {
+            rows.push(text_row("  No matching commands", inner, border, dim, bg));
+        }
+        for (i, command) in shown.iter().enumerate() {
+            let is_selected = first + i == self.selected;
+            let name_width = 26;
+            let name: String = command.name.chars().take(name_width).collect();
+            let line = format!("  {:name_width$}", name);
+            let mut row = text_row(
+                &line,
+                inner,
+                border,
+                if is_selected { bright } else { fg },
+                bg,
+            );
+            // Description fills the rest, dimmed
+            for (cell, c) in row[name_width + 4..inner]
+                .iter_mut()
+                .zip(command.description.chars())
+            {
+                cell.c = c;
+                cell.fg = dim;
+            }
+            if is_selected {
+                for cell in &mut row[1..=inner] {
+                    cell.bg = highlight;
+                }
+            }
+            rows.push(row);
+        }
+        rows.push(frame_row('└', "", '┘', border, bright, bg));
+        rows
+    }
+}
+
+fn cell(c: char, fg: [f32; 4], bg: [f32; 4]) -> RenderCell {
+    RenderCell {
+        c,
+        fg,
+        bg,
+        is_wide: false,
+        style: GlyphStyle::Regular,
+        line_size: LineSize::Normal,
+    }
+}
+
+/// A border row with an optional centered title
+fn frame_row(
+    left: char,
+    title: &str,
+    right: char,
+    border: [f32; 4],
+    title_fg: [f32; 4],
+    bg: [f32; 4],
+) -> Vec<RenderCell> {
+    let mut row: Vec<RenderCell> = (0..PANEL_WIDTH).map(|_| cell('─', border, bg)).collect();
+    row[0].c = left;
+    row[PANEL_WIDTH - 1].c = right;
+    let start = (PANEL_WIDTH - title.chars().count()) / 2;
+    for (cell, c) in row[start..].iter_mut().zip(title.chars()) {
+        cell.c = c;
+        cell.fg = title_fg;
+    }
+    row
+}
+
+/// A content row between side borders, text cut to `inner` cells
+fn text_row(
+    text: &str,
+    inner: usize,
+    border: [f32; 4],
+    fg: [f32; 4],
+    bg: [f32; 4],
+) -> Vec<RenderCell> {
+    let mut row = vec![cell('│', border, bg)];
+    let chars = text.chars().chain(std::iter::repeat(' ')).take(inner);
+    row.extend(chars.map(|c| cell(c, fg, bg)));
+    row.push(cell('│', border, bg));
+    row
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn query_matches_as_a_subsequence() {
+        assert_eq!(fuzzy_score("cpy", "Copy"), Some((3, 3)));
+        assert_eq!(fuzzy_score("NEW pane", "New pane"), Some((7, 7)));
+        assert_eq!(fuzzy_score("", "Copy"), Some((0, 0)));
+        // Matching is greedy from the left: "s" is the first letter here
+        assert_eq!(fuzzy_score("ss", "Screenshot"), Some((2, 6)));
+    }
+
+    #[test]
+    fn missing_or_out_of_order_characters_do_not_match() {
+        assert_eq!(fuzzy_score("xyz", "Copy"), None);
+        assert_eq!(fuzzy_score("ypoc", "Copy"), None);
+        assert_eq!(fuzzy_score("copyy", "Copy"), None);
+    }
+
+    #[test]
+    fn tighter_matches_rank_first() {
+        let mut palette = CommandPalette::default();
+        palette.open(commands(2));
+        palette.type_text("paste");
+        let names: Vec<&str> = palette.filtered().iter().map(|c| c.name.as_str()).collect();
+        // Both contain p-a-s-t-e in order, but "Paste" far more tightly
+        assert_eq!(names, ["Paste", "Step beam faster"]);
+
+        palette.move_down();
+        assert_eq!(
+            palette.accept(),
+            Some(PaletteAction::Run(Action::BeamStepFaster))
+        );
+        assert!(!palette.visible);
+
+        palette.open(commands(2));
+        palette.type_text("pas");
+        assert_eq!(palette.accept(), Some(PaletteAction::Run(Action::Paste)));
+        assert!(!palette.visible);
+    }
+}
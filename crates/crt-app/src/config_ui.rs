@@ -2,8 +2,53 @@
 // ABOUTME: Renders a text-based settings panel with keyboard navigation.
 // ABOUTME: Uses tabs to organize settings into Effects and Appearance categories.
 
-use crt_core::{BdfFont, ColorScheme, Config, ScanlineMode};
-use crt_renderer::RenderCell;
+use std::path::PathBuf;
+
+use crt_core::{BdfFont, ColorScheme, Config, Font, LineSize, ScanlineMode};
+use crt_renderer::{find_bdf_fonts, GlyphStyle, RenderCell};
+
+use crate::hints;
+
+/// Palette entries, foreground and background
+const EDITABLE_COLORS: usize = 18;
+
+const COLOR_NAMES: [&str; EDITABLE_COLORS] = [
+    "Black",
+    "Red",
+    "Green",
+    "Yellow",
+    "Blue",
+    "Magenta",
+    "Cyan",
+    "White",
+    "Br. Black",
+    "Br. Red",
+    "Br. Green",
+    "Br. Yellow",
+    "Br. Blue",
+    "Br. Magenta",
+    "Br. Cyan",
+    "Br. White",
+    "Foreground",
+    "Background",
+];
+
+/// Cells of color preview at the end of color editor lines
+const SWATCH_WIDTH: usize = 2;
+
+/// Color temperatures named on the slider: office monitors ran cool, amber
+/// and home units warm
+const TEMPERATURE_PRESETS: &[(f32, &str)] =
+    &[(3200.0, "Warm"), (6500.0, "Standard"), (9300.0, "Cool")];
+
+/// Longest custom font file name shown before it's cut off
+const CUSTOM_FONT_NAME_WIDTH: usize = 11;
+
+/// Longest BDF file name shown before it's cut off
+const BDF_FILE_NAME_WIDTH: usize = 19;
+
+/// Directory levels below `bdf_font_dir` searched for fonts
+const BDF_SCAN_DEPTH: u32 = 2;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum ConfigTab {
@@ -41,19 +86,33 @@ impl ConfigTab {
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum ConfigField {
     // Effects tab
+    /// Master switch: off skips every post-processing pass
+    CrtEffects,
     Curvature,
     Scanlines,
     ScanlineMode,
+    ScanlineGamma,
+    CompositeBleed,
+    ColorTemperature,
     Bloom,
     BurnIn,
     StaticNoise,
     Flicker,
     Vignette,
+    CornerRadius,
+    RgbMask,
+    Aberration,
     Brightness,
+    BackgroundOpacity,
     PerPaneCrt,
     FocusGlowRadius,
     FocusGlowWidth,
     FocusGlowIntensity,
+    // Custom phosphor glow color (channels hidden when off)
+    CustomGlow,
+    GlowRed,
+    GlowGreen,
+    GlowBlue,
     // Bezel settings
     BezelEnabled,
     ContentScaleX,
@@ -65,13 +124,28 @@ pub enum ConfigField {
     FontType,      // Toggle between TTF and BDF
     FontFamily,    // TTF font selector (hidden when BDF selected)
     FontSize,      // TTF font size (hidden when BDF selected)
+    PaneFontSize,  // Font size of the pane the settings were opened from
     UiScale,       // UI scaling for TTF fonts (hidden when BDF selected)
+    LineHeight,    // Row height multiplier
+    LetterSpacing, // Extra pixels between columns
     BdfFontFamily, // BDF font selector (hidden when TTF selected)
+    PanePadding,   // Pixels around each pane's content
+    PaneBorder,    // Lines between panes
     ColorSchemeField,
+    /// Color being edited: palette index 0-15, then foreground and background
+    ColorEditor(usize),
+    /// One channel (0 = red, 1 = green, 2 = blue) of an edited color,
+    /// shown below the editor while it's open
+    ColorChannel(usize, usize),
     // Behavior tab
     AutoCopySelection,
     ShowStartupHint,
     ShowKittyMessage,
+    PowerSave,
+    BoldIsBright,
+    ConfirmMultilinePaste,
+    PrimarySelection,
+    StatusBar,
     // Common
     Save,
     Cancel,
@@ -81,18 +155,30 @@ impl ConfigField {
     fn all() -> &'static [ConfigField] {
         &[
             // Effects tab
+            ConfigField::CrtEffects,
             ConfigField::Curvature,
             ConfigField::Scanlines,
             ConfigField::ScanlineMode,
+            ConfigField::ScanlineGamma,
+            ConfigField::CompositeBleed,
+            ConfigField::ColorTemperature,
             ConfigField::Bloom,
             ConfigField::BurnIn,
             ConfigField::StaticNoise,
             ConfigField::Flicker,
             ConfigField::Vignette,
+            ConfigField::CornerRadius,
+            ConfigField::RgbMask,
+            ConfigField::Aberration,
             ConfigField::Brightness,
+            ConfigField::BackgroundOpacity,
             ConfigField::FocusGlowRadius,
             ConfigField::FocusGlowWidth,
             ConfigField::FocusGlowIntensity,
+            ConfigField::CustomGlow,
+            ConfigField::GlowRed,
+            ConfigField::GlowGreen,
+            ConfigField::GlowBlue,
             ConfigField::PerPaneCrt,
             ConfigField::BezelEnabled,
             ConfigField::ContentScaleX,
@@ -103,13 +189,24 @@ impl ConfigField {
             ConfigField::FontType,
             ConfigField::FontFamily,
             ConfigField::FontSize,
+            ConfigField::PaneFontSize,
             ConfigField::UiScale,
             ConfigField::BdfFontFamily,
+            ConfigField::LineHeight,
+            ConfigField::LetterSpacing,
+            ConfigField::PanePadding,
+            ConfigField::PaneBorder,
             ConfigField::ColorSchemeField,
+            ConfigField::ColorEditor(0),
             // Behavior tab
             ConfigField::AutoCopySelection,
             ConfigField::ShowStartupHint,
             ConfigField::ShowKittyMessage,
+            ConfigField::PowerSave,
+            ConfigField::BoldIsBright,
+            ConfigField::ConfirmMultilinePaste,
+            ConfigField::PrimarySelection,
+            ConfigField::StatusBar,
             // Common
             ConfigField::Save,
             ConfigField::Cancel,
@@ -120,25 +217,40 @@ impl ConfigField {
     fn has_separator_before(&self) -> bool {
         matches!(
             self,
-            ConfigField::PerPaneCrt | ConfigField::BezelEnabled | ConfigField::BeamSimulation
+            ConfigField::Curvature
+                | ConfigField::PerPaneCrt
+                | ConfigField::BezelEnabled
+                | ConfigField::BeamSimulation
         )
     }
 
     fn label(&self) -> &'static str {
         match self {
+            ConfigField::CrtEffects => "CRT Effects",
             ConfigField::Curvature => "Curvature",
             ConfigField::Scanlines => "Scanlines",
+            ConfigField::ScanlineGamma => "Scan Gamma",
+            ConfigField::CompositeBleed => "Color Bleed",
+            ConfigField::ColorTemperature => "Color Temp",
             ConfigField::ScanlineMode => "Scanline Type",
             ConfigField::Bloom => "Bloom",
             ConfigField::BurnIn => "Burn-in",
             ConfigField::StaticNoise => "Static",
             ConfigField::Flicker => "Flicker",
             ConfigField::Vignette => "Vignette",
+            ConfigField::CornerRadius => "Corners",
+            ConfigField::RgbMask => "RGB Mask",
+            ConfigField::Aberration => "Aberration",
             ConfigField::Brightness => "Brightness",
+            ConfigField::BackgroundOpacity => "Opacity",
             ConfigField::PerPaneCrt => "Per-pane CRT",
             ConfigField::FocusGlowRadius => "Glow Radius",
             ConfigField::FocusGlowWidth => "Glow Width",
             ConfigField::FocusGlowIntensity => "Glow Bright",
+            ConfigField::CustomGlow => "Custom Glow",
+            ConfigField::GlowRed => "Glow Red",
+            ConfigField::GlowGreen => "Glow Green",
+            ConfigField::GlowBlue => "Glow Blue",
             ConfigField::BezelEnabled => "Bezel",
             ConfigField::ContentScaleX => "H-Size",
             ConfigField::ContentScaleY => "V-Size",
@@ -147,12 +259,26 @@ impl ConfigField {
             ConfigField::FontType => "Font Type",
             ConfigField::FontFamily => "TTF Font",
             ConfigField::FontSize => "Font Size",
+            ConfigField::PaneFontSize => "Font Size (pane)",
             ConfigField::UiScale => "UI Scale",
+            ConfigField::LineHeight => "Line Height",
+            ConfigField::LetterSpacing => "Char Spacing",
             ConfigField::BdfFontFamily => "BDF Font",
+            ConfigField::PanePadding => "Pane Padding",
+            ConfigField::PaneBorder => "Pane Border",
             ConfigField::ColorSchemeField => "Colors",
+            ConfigField::ColorEditor(_) => "Edit color",
+            ConfigField::ColorChannel(_, 0) => "  Red",
+            ConfigField::ColorChannel(_, 1) => "  Green",
+            ConfigField::ColorChannel(_, _) => "  Blue",
             ConfigField::AutoCopySelection => "Auto-copy",
             ConfigField::ShowStartupHint => "Startup hint",
             ConfigField::ShowKittyMessage => "Kitty msg",
+            ConfigField::PowerSave => "Power save",
+            ConfigField::BoldIsBright => "Bold bright",
+            ConfigField::ConfirmMultilinePaste => "Confirm paste",
+            ConfigField::PrimarySelection => "Primary sel",
+            ConfigField::StatusBar => "Status bar",
             ConfigField::Save => "[ Save ]",
             ConfigField::Cancel => "[ Cancel ]",
         }
@@ -163,30 +289,52 @@ impl ConfigField {
             self,
             ConfigField::Curvature
                 | ConfigField::Scanlines
+                | ConfigField::ScanlineGamma
+                | ConfigField::CompositeBleed
+                | ConfigField::ColorTemperature
                 | ConfigField::Bloom
                 | ConfigField::BurnIn
                 | ConfigField::StaticNoise
                 | ConfigField::Flicker
                 | ConfigField::Vignette
+                | ConfigField::CornerRadius
+                | ConfigField::RgbMask
+                | ConfigField::Aberration
                 | ConfigField::Brightness
+                | ConfigField::BackgroundOpacity
                 | ConfigField::FocusGlowRadius
                 | ConfigField::FocusGlowWidth
                 | ConfigField::FocusGlowIntensity
+                | ConfigField::GlowRed
+                | ConfigField::GlowGreen
+                | ConfigField::GlowBlue
                 | ConfigField::ContentScaleX
                 | ConfigField::ContentScaleY
                 | ConfigField::FontSize
+                | ConfigField::PaneFontSize
                 | ConfigField::UiScale
+                | ConfigField::LineHeight
+                | ConfigField::LetterSpacing
+                | ConfigField::PanePadding
+                | ConfigField::ColorChannel(..)
         )
     }
 
     fn is_toggle(&self) -> bool {
         matches!(
             self,
-            ConfigField::PerPaneCrt
+            ConfigField::CrtEffects
+                | ConfigField::PerPaneCrt
                 | ConfigField::BezelEnabled
+                | ConfigField::CustomGlow
                 | ConfigField::AutoCopySelection
                 | ConfigField::ShowStartupHint
                 | ConfigField::ShowKittyMessage
+                | ConfigField::PowerSave
+                | ConfigField::BoldIsBright
+                | ConfigField::ConfirmMultilinePaste
+                | ConfigField::PrimarySelection
+                | ConfigField::StatusBar
                 | ConfigField::FontType
                 | ConfigField::ScanlineMode
                 | ConfigField::BeamSimulation
@@ -197,7 +345,11 @@ impl ConfigField {
     fn is_selector(&self) -> bool {
         matches!(
             self,
-            ConfigField::FontFamily | ConfigField::BdfFontFamily | ConfigField::ColorSchemeField
+            ConfigField::FontFamily
+                | ConfigField::BdfFontFamily
+                | ConfigField::PaneBorder
+                | ConfigField::ColorSchemeField
+                | ConfigField::ColorEditor(_)
         )
     }
 
@@ -208,18 +360,30 @@ impl ConfigField {
     fn tab(&self) -> Option<ConfigTab> {
         match self {
             // Effects tab
-            ConfigField::Curvature
+            ConfigField::CrtEffects
+            | ConfigField::Curvature
             | ConfigField::Scanlines
             | ConfigField::ScanlineMode
+            | ConfigField::ScanlineGamma
+            | ConfigField::CompositeBleed
+            | ConfigField::ColorTemperature
             | ConfigField::Bloom
             | ConfigField::BurnIn
             | ConfigField::StaticNoise
             | ConfigField::Flicker
             | ConfigField::Vignette
+            | ConfigField::CornerRadius
+            | ConfigField::RgbMask
+            | ConfigField::Aberration
             | ConfigField::Brightness
+            | ConfigField::BackgroundOpacity
             | ConfigField::FocusGlowRadius
             | ConfigField::FocusGlowWidth
             | ConfigField::FocusGlowIntensity
+            | ConfigField::CustomGlow
+            | ConfigField::GlowRed
+            | ConfigField::GlowGreen
+            | ConfigField::GlowBlue
             | ConfigField::PerPaneCrt
             | ConfigField::BezelEnabled
             | ConfigField::ContentScaleX
@@ -230,13 +394,25 @@ impl ConfigField {
             ConfigField::FontType
             | ConfigField::FontFamily
             | ConfigField::FontSize
+            | ConfigField::PaneFontSize
             | ConfigField::UiScale
             | ConfigField::BdfFontFamily
-            | ConfigField::ColorSchemeField => Some(ConfigTab::Appearance),
+            | ConfigField::LineHeight
+            | ConfigField::LetterSpacing
+            | ConfigField::PanePadding
+            | ConfigField::PaneBorder
+            | ConfigField::ColorSchemeField
+            | ConfigField::ColorEditor(_)
+            | ConfigField::ColorChannel(..) => Some(ConfigTab::Appearance),
             // Behavior tab
             ConfigField::AutoCopySelection
             | ConfigField::ShowStartupHint
-            | ConfigField::ShowKittyMessage => Some(ConfigTab::Behavior),
+            | ConfigField::ShowKittyMessage
+            | ConfigField::PowerSave
+            | ConfigField::BoldIsBright
+            | ConfigField::ConfirmMultilinePaste
+            | ConfigField::PrimarySelection
+            | ConfigField::StatusBar => Some(ConfigTab::Behavior),
             // Save/Cancel are on all tabs
             ConfigField::Save | ConfigField::Cancel => None,
         }
@@ -258,13 +434,18 @@ impl ConfigField {
     fn should_show(&self, config: &Config) -> bool {
         match self {
             // TTF-specific fields: only show when BDF is not selected
-            ConfigField::FontFamily | ConfigField::FontSize | ConfigField::UiScale => {
-                config.bdf_font.is_none()
-            }
+            ConfigField::FontFamily
+            | ConfigField::FontSize
+            | ConfigField::PaneFontSize
+            | ConfigField::UiScale => config.bdf_font.is_none(),
             // BDF-specific fields: only show when BDF is selected
             ConfigField::BdfFontFamily => config.bdf_font.is_some(),
             // Interlace only shows when beam simulation is enabled
             ConfigField::Interlace => config.effects.beam_simulation_enabled,
+            // Glow channels only show when the custom glow color is on
+            ConfigField::GlowRed | ConfigField::GlowGreen | ConfigField::GlowBlue => {
+                config.effects.use_custom_glow
+            }
             // All other fields always show
             _ => true,
         }
@@ -277,19 +458,61 @@ pub struct ConfigUI {
     pub current_tab: ConfigTab,
     pub config: Config,
     original_config: Config,
+    /// The color scheme selector is past the presets, on "Load from file..."
+    scheme_file_entry: bool,
+    /// Path being typed for a color scheme file, while the prompt is open
+    scheme_path: Option<String>,
+    /// Why the last color scheme file couldn't be loaded
+    scheme_error: Option<String>,
+    /// Color shown in the color editor row (see `ConfigField::ColorEditor`)
+    color_index: usize,
+    /// The color editor's R, G and B sliders are showing
+    color_editor_open: bool,
+    /// 1-based number of the pane the settings were opened from, when the
+    /// window is split; its font size can be set apart from the others
+    pane: Option<usize>,
+    /// BDF font files found in `bdf_font_dir` at startup
+    bdf_files: Vec<PathBuf>,
 }
 
 impl ConfigUI {
     pub fn new(config: Config) -> Self {
+        let bdf_files = match config.bdf_font_dir.as_deref() {
+            Some(dir) if !dir.is_dir() => {
+                tracing::warn!("bdf_font_dir {} isn't a directory", dir.display());
+                Vec::new()
+            }
+            Some(dir) => find_bdf_fonts(dir, BDF_SCAN_DEPTH),
+            None => Vec::new(),
+        };
         Self {
             visible: false,
             selected: 0,
             current_tab: ConfigTab::Effects,
             config: config.clone(),
             original_config: config,
+            scheme_file_entry: false,
+            scheme_path: None,
+            scheme_error: None,
+            color_index: 0,
+            color_editor_open: false,
+            pane: None,
+            bdf_files,
         }
     }
 
+    /// The BDF files the selector offers after the bundled fonts, including
+    /// the one the settings were opened with if it's outside `bdf_font_dir`
+    fn bdf_file_choices(&self) -> Vec<PathBuf> {
+        let mut files = self.bdf_files.clone();
+        if let Some(path) = &self.original_config.bdf_font_path {
+            if !files.contains(path) {
+                files.push(path.clone());
+            }
+        }
+        files
+    }
+
     /// Get the foreground color from the current color scheme
     fn fg_color(&self) -> [f32; 4] {
         self.config.color_scheme.foreground
@@ -308,8 +531,7 @@ impl ConfigUI {
 
     /// Get a dim version of the foreground color
     fn dim_color(&self) -> [f32; 4] {
-        let fg = self.config.color_scheme.foreground;
-        [fg[0] * 0.6, fg[1] * 0.6, fg[2] * 0.6, fg[3]]
+        self.config.color_scheme.dim_color()
     }
 
     /// Get the background color (transparent - let CRT show through)
@@ -324,15 +546,131 @@ impl ConfigUI {
         [fg[0] * 0.15, fg[1] * 0.15, fg[2] * 0.15, 1.0]
     }
 
-    pub fn show(&mut self, config: &Config) {
+    /// Open the settings; `pane` is the focused pane's number when the
+    /// window has more than one
+    pub fn show(&mut self, config: &Config, pane: Option<usize>) {
+        self.pane = pane;
         self.config = config.clone();
         self.original_config = config.clone();
         self.visible = true;
         self.selected = 0;
         self.current_tab = ConfigTab::Effects;
+        self.scheme_file_entry = false;
+        self.scheme_path = None;
+        self.scheme_error = None;
+        self.color_editor_open = false;
+    }
+
+    /// The color temperature in Kelvin, named when it's on a preset
+    fn color_temperature_text(&self) -> String {
+        let effects = &self.config.effects;
+        if effects.color_tint.is_some() {
+            return "Tint".to_string();
+        }
+        let kelvin = effects.color_temperature.round();
+        match TEMPERATURE_PRESETS
+            .iter()
+            .find(|(preset, _)| *preset == kelvin)
+        {
+            Some((_, name)) => format!("{} {:.0}K", name, kelvin),
+            None => format!("{:.0}K", kelvin),
+        }
+    }
+
+    /// Font size of the pane the settings were opened from
+    fn pane_font_size(&self) -> f32 {
+        self.pane.map_or(self.config.font_size, |pane| {
+            self.config.font_size_for_pane(pane)
+        })
+    }
+
+    /// Whether the color editor's sliders are open, taking Tab and Escape
+    pub fn color_editor_open(&self) -> bool {
+        self.color_editor_open && self.current_tab == ConfigTab::Appearance
+    }
+
+    /// Move the open color editor to the next or previous color
+    pub fn next_color(&mut self, forward: bool) {
+        let step = if forward { 1 } else { EDITABLE_COLORS - 1 };
+        self.color_index = (self.color_index + step) % EDITABLE_COLORS;
+    }
+
+    /// Hide the sliders, moving the selection back to the editor row
+    pub fn close_color_editor(&mut self) {
+        self.color_editor_open = false;
+        if let Some(idx) = self
+            .current_fields()
+            .iter()
+            .position(|f| matches!(f, ConfigField::ColorEditor(_)))
+        {
+            self.selected = idx;
+        }
+    }
+
+    fn editable_color(&self, index: usize) -> [f32; 4] {
+        let scheme = &self.config.color_scheme;
+        match index {
+            16 => scheme.foreground,
+            17 => scheme.background,
+            _ => scheme.colors[index],
+        }
+    }
+
+    fn editable_color_mut(&mut self, index: usize) -> &mut [f32; 4] {
+        let scheme = &mut self.config.color_scheme;
+        match index {
+            16 => &mut scheme.foreground,
+            17 => &mut scheme.background,
+            _ => &mut scheme.colors[index],
+        }
+    }
+
+    /// Whether the color scheme path prompt is taking keystrokes
+    pub fn editing_scheme_path(&self) -> bool {
+        self.scheme_path.is_some()
+    }
+
+    pub fn type_scheme_path(&mut self, text: &str) {
+        if let Some(path) = &mut self.scheme_path {
+            path.extend(text.chars().filter(|c| !c.is_control()));
+        }
+    }
+
+    pub fn scheme_path_backspace(&mut self) {
+        if let Some(path) = &mut self.scheme_path {
+            path.pop();
+        }
+    }
+
+    pub fn cancel_scheme_path(&mut self) {
+        self.scheme_path = None;
+        self.scheme_error = None;
+    }
+
+    /// Load the typed path as the color scheme. On failure the prompt
+    /// stays open with the error shown.
+    pub fn accept_scheme_path(&mut self) {
+        let Some(path) = self.scheme_path.as_deref() else {
+            return;
+        };
+        let path = hints::resolve_path(path.trim(), None);
+        match ColorScheme::load(&path) {
+            Ok(scheme) => {
+                self.config.color_scheme = scheme;
+                self.config.color_scheme_file = Some(path);
+                self.scheme_file_entry = false;
+                self.scheme_path = None;
+                self.scheme_error = None;
+            }
+            Err(e) => {
+                tracing::warn!("Failed to load color scheme {}: {}", path.display(), e);
+                self.scheme_error = Some(e.to_string());
+            }
+        }
     }
 
     pub fn next_tab(&mut self) {
+        self.color_editor_open = false;
         let tabs = ConfigTab::all();
         let current_idx = self.current_tab.index();
         let next_idx = (current_idx + 1) % tabs.len();
@@ -341,6 +679,7 @@ impl ConfigUI {
     }
 
     pub fn prev_tab(&mut self) {
+        self.color_editor_open = false;
         let tabs = ConfigTab::all();
         let current_idx = self.current_tab.index();
         let prev_idx = if current_idx == 0 {
@@ -353,7 +692,21 @@ impl ConfigUI {
     }
 
     fn current_fields(&self) -> Vec<ConfigField> {
-        ConfigField::fields_for_tab(self.current_tab, &self.config)
+        let mut fields = ConfigField::fields_for_tab(self.current_tab, &self.config);
+        if self.pane.is_none() {
+            fields.retain(|f| *f != ConfigField::PaneFontSize);
+        }
+        if let Some(pos) = fields
+            .iter()
+            .position(|f| matches!(f, ConfigField::ColorEditor(_)))
+        {
+            fields[pos] = ConfigField::ColorEditor(self.color_index);
+            if self.color_editor_open {
+                let channels = (0..3).map(|c| ConfigField::ColorChannel(self.color_index, c));
+                fields.splice(pos + 1..pos + 1, channels);
+            }
+        }
+        fields
     }
 
     pub fn hide(&mut self) {
@@ -409,10 +762,18 @@ impl ConfigUI {
                 self.config.per_pane_crt = !self.config.per_pane_crt;
                 None
             }
+            ConfigField::CrtEffects => {
+                self.config.effects.enabled = !self.config.effects.enabled;
+                None
+            }
             ConfigField::BezelEnabled => {
                 self.config.effects.bezel_enabled = !self.config.effects.bezel_enabled;
                 None
             }
+            ConfigField::CustomGlow => {
+                self.config.effects.use_custom_glow = !self.config.effects.use_custom_glow;
+                None
+            }
             ConfigField::AutoCopySelection => {
                 self.config.behavior.auto_copy_selection =
                     !self.config.behavior.auto_copy_selection;
@@ -426,10 +787,33 @@ impl ConfigUI {
                 self.config.behavior.show_kitty_message = !self.config.behavior.show_kitty_message;
                 None
             }
+            ConfigField::PowerSave => {
+                self.config.behavior.power_save = !self.config.behavior.power_save;
+                None
+            }
+            ConfigField::BoldIsBright => {
+                self.config.behavior.bold_is_bright = !self.config.behavior.bold_is_bright;
+                None
+            }
+            ConfigField::StatusBar => {
+                self.config.behavior.status_bar = !self.config.behavior.status_bar;
+                None
+            }
+            ConfigField::ConfirmMultilinePaste => {
+                self.config.behavior.confirm_multiline_paste =
+                    !self.config.behavior.confirm_multiline_paste;
+                None
+            }
+            ConfigField::PrimarySelection => {
+                self.config.behavior.use_primary_selection =
+                    !self.config.behavior.use_primary_selection;
+                None
+            }
             ConfigField::FontType => {
                 // Toggle between TTF and BDF
                 if self.config.bdf_font.is_some() {
                     self.config.bdf_font = None;
+                    self.config.bdf_font_path = None;
                 } else {
                     // Default to Fixed 9x18 when enabling BDF
                     self.config.bdf_font = Some(BdfFont::Fixed9x18);
@@ -453,6 +837,17 @@ impl ConfigUI {
                 self.config.effects.interlace_enabled = !self.config.effects.interlace_enabled;
                 None
             }
+            ConfigField::ColorEditor(_) => {
+                self.color_editor_open = !self.color_editor_open;
+                None
+            }
+            ConfigField::ColorSchemeField if self.scheme_file_entry => {
+                let current = self.config.color_scheme_file.as_ref();
+                self.scheme_path =
+                    Some(current.map(|p| p.display().to_string()).unwrap_or_default());
+                self.scheme_error = None;
+                None
+            }
             ConfigField::Save => Some(ConfigAction::Save),
             ConfigField::Cancel => Some(ConfigAction::Cancel),
             _ => None,
@@ -477,6 +872,21 @@ impl ConfigUI {
                     ScanlineMode::Pixel => ScanlineMode::RowBased,
                 };
             }
+            ConfigField::ScanlineGamma => {
+                let change = if delta > 0.0 { 0.1 } else { -0.1 };
+                effects.scanline_gamma = (effects.scanline_gamma + change).clamp(0.0, 3.0);
+            }
+            ConfigField::CompositeBleed => {
+                let change = if delta > 0.0 { 0.05 } else { -0.05 };
+                effects.composite_bleed = (effects.composite_bleed + change).clamp(0.0, 1.0);
+            }
+            ConfigField::ColorTemperature => {
+                // The slider takes over from a manual tint
+                effects.color_tint = None;
+                let change = if delta > 0.0 { 100.0 } else { -100.0 };
+                effects.color_temperature =
+                    (effects.color_temperature + change).clamp(2000.0, 10000.0);
+            }
             ConfigField::Bloom => {
                 let change = if delta > 0.0 { 0.01 } else { -0.01 };
                 effects.bloom = (effects.bloom + change).clamp(0.0, 1.0);
@@ -497,10 +907,26 @@ impl ConfigUI {
                 let change = if delta > 0.0 { 0.01 } else { -0.01 };
                 effects.vignette = (effects.vignette + change).clamp(0.0, 1.0);
             }
+            ConfigField::CornerRadius => {
+                let change = if delta > 0.0 { 0.01 } else { -0.01 };
+                effects.corner_radius = (effects.corner_radius + change).clamp(0.0, 0.25);
+            }
+            ConfigField::RgbMask => {
+                let change = if delta > 0.0 { 0.05 } else { -0.05 };
+                effects.rgb_mask = (effects.rgb_mask + change).clamp(0.0, 1.0);
+            }
+            ConfigField::Aberration => {
+                let change = if delta > 0.0 { 0.25 } else { -0.25 };
+                effects.rgb_shift = (effects.rgb_shift + change).clamp(0.0, 3.0);
+            }
             ConfigField::Brightness => {
                 let change = if delta > 0.0 { 0.01 } else { -0.01 };
                 effects.brightness = (effects.brightness + change).clamp(0.1, 2.0);
             }
+            ConfigField::BackgroundOpacity => {
+                let change = if delta > 0.0 { 0.05 } else { -0.05 };
+                effects.background_opacity = (effects.background_opacity + change).clamp(0.0, 1.0);
+            }
             ConfigField::PerPaneCrt => {
                 self.config.per_pane_crt = delta > 0.0;
             }
@@ -529,16 +955,39 @@ impl ConfigUI {
                 effects.focus_glow_intensity =
                     (effects.focus_glow_intensity + change).clamp(0.0, 1.0);
             }
+            ConfigField::GlowRed | ConfigField::GlowGreen | ConfigField::GlowBlue => {
+                let channel = match field {
+                    ConfigField::GlowRed => 0,
+                    ConfigField::GlowGreen => 1,
+                    _ => 2,
+                };
+                let change = if delta > 0.0 { 0.05 } else { -0.05 };
+                effects.phosphor_tint[channel] =
+                    (effects.phosphor_tint[channel] + change).clamp(0.0, 1.0);
+            }
             ConfigField::FontType => {
                 // Toggle between TTF and BDF via left/right arrows
                 if self.config.bdf_font.is_some() {
                     self.config.bdf_font = None;
+                    self.config.bdf_font_path = None;
                 } else {
                     self.config.bdf_font = Some(BdfFont::Fixed9x18);
                 }
             }
             ConfigField::FontFamily => {
-                if delta > 0.0 {
+                // A custom font file sits between the last and first bundled fonts
+                let all = Font::all();
+                let (edge, other_edge) = if delta > 0.0 {
+                    (all[all.len() - 1], all[0])
+                } else {
+                    (all[0], all[all.len() - 1])
+                };
+                if self.config.font_path.is_some() {
+                    self.config.font_path = None;
+                    self.config.font = other_edge;
+                } else if self.config.font == edge && self.original_config.font_path.is_some() {
+                    self.config.font_path = self.original_config.font_path.clone();
+                } else if delta > 0.0 {
                     self.config.font = self.config.font.next();
                 } else {
                     self.config.font = self.config.font.prev();
@@ -548,38 +997,114 @@ impl ConfigUI {
                 let change = if delta > 0.0 { 1.0 } else { -1.0 };
                 self.config.font_size = (self.config.font_size + change).clamp(8.0, 32.0);
             }
+            ConfigField::PaneFontSize => {
+                if let Some(pane) = self.pane {
+                    let change = if delta > 0.0 { 1.0 } else { -1.0 };
+                    let size = (self.pane_font_size() + change).clamp(8.0, 32.0);
+                    // Back at the main size, the pane just follows it again
+                    if size == self.config.font_size {
+                        self.config.per_pane_font_size.remove(&pane.to_string());
+                    } else {
+                        self.config
+                            .per_pane_font_size
+                            .insert(pane.to_string(), size);
+                    }
+                }
+            }
             ConfigField::UiScale => {
                 let change = if delta > 0.0 { 0.25 } else { -0.25 };
-                self.config.ui_scale = (self.config.ui_scale + change).clamp(1.0, 3.0);
+                self.config.ui_scale = (self.config.ui_scale + change).clamp(0.5, 3.0);
+            }
+            ConfigField::LineHeight => {
+                let change = if delta > 0.0 { 0.05 } else { -0.05 };
+                self.config.line_height_mult =
+                    (self.config.line_height_mult + change).clamp(1.0, 1.5);
+            }
+            ConfigField::LetterSpacing => {
+                let change = if delta > 0.0 { 1.0 } else { -1.0 };
+                self.config.letter_spacing = (self.config.letter_spacing + change).clamp(0.0, 4.0);
+            }
+            ConfigField::PanePadding => {
+                let change = if delta > 0.0 { 1.0 } else { -1.0 };
+                self.config.pane_padding = (self.config.pane_padding + change).clamp(0.0, 32.0);
+            }
+            ConfigField::PaneBorder => {
+                let style = self.config.pane_border_style;
+                self.config.pane_border_style = if delta > 0.0 {
+                    style.next()
+                } else {
+                    style.prev()
+                };
             }
             ConfigField::BdfFontFamily => {
-                if let Some(ref mut bdf) = self.config.bdf_font {
-                    if delta > 0.0 {
-                        *bdf = bdf.next();
+                // The bundled fonts, then the font files found on disk
+                if let Some(bdf) = self.config.bdf_font {
+                    let bundled = BdfFont::all();
+                    let files = self.bdf_file_choices();
+                    let count = bundled.len() + files.len();
+                    let current = match &self.config.bdf_font_path {
+                        Some(path) => {
+                            bundled.len() + files.iter().position(|f| f == path).unwrap_or(0)
+                        }
+                        None => bundled.iter().position(|&f| f == bdf).unwrap_or(0),
+                    };
+                    let next = if delta > 0.0 {
+                        (current + 1) % count
                     } else {
-                        *bdf = bdf.prev();
+                        (current + count - 1) % count
+                    };
+                    match bundled.get(next) {
+                        Some(&font) => {
+                            self.config.bdf_font = Some(font);
+                            self.config.bdf_font_path = None;
+                        }
+                        None => {
+                            self.config.bdf_font_path = Some(files[next - bundled.len()].clone())
+                        }
                     }
                 }
             }
             ConfigField::ColorSchemeField => {
+                // The presets, then "Load from file..." as one more entry
                 let presets = ColorScheme::presets();
+                let entries = presets.len() + 1;
                 let current_name = &self.config.color_scheme.name;
-                let current_idx = presets
-                    .iter()
-                    .position(|s| &s.name == current_name)
-                    .unwrap_or(0);
+                let current_idx = if self.scheme_file_entry {
+                    presets.len()
+                } else {
+                    presets
+                        .iter()
+                        .position(|s| &s.name == current_name)
+                        .unwrap_or(0)
+                };
                 let new_idx = if delta > 0.0 {
-                    (current_idx + 1) % presets.len()
-                } else if current_idx == 0 {
-                    presets.len() - 1
+                    (current_idx + 1) % entries
                 } else {
-                    current_idx - 1
+                    (current_idx + entries - 1) % entries
                 };
-                self.config.color_scheme = presets[new_idx].clone();
+                self.scheme_file_entry = new_idx == presets.len();
+                if !self.scheme_file_entry {
+                    self.config.color_scheme = presets[new_idx].clone();
+                    self.config.color_scheme_file = None;
+                }
+            }
+            ConfigField::ColorEditor(_) => self.next_color(delta > 0.0),
+            ConfigField::ColorChannel(index, channel) => {
+                let change = if delta > 0.0 { 0.01 } else { -0.01 };
+                let color = self.editable_color_mut(index);
+                color[channel] = (color[channel] + change).clamp(0.0, 1.0);
+                // An edited scheme is saved inline, not reloaded from its file
+                self.config.color_scheme_file = None;
+            }
+            ConfigField::CrtEffects => {
+                self.config.effects.enabled = delta > 0.0;
             }
             ConfigField::BezelEnabled => {
                 self.config.effects.bezel_enabled = delta > 0.0;
             }
+            ConfigField::CustomGlow => {
+                self.config.effects.use_custom_glow = delta > 0.0;
+            }
             ConfigField::AutoCopySelection => {
                 self.config.behavior.auto_copy_selection = delta > 0.0;
             }
@@ -589,6 +1114,21 @@ impl ConfigUI {
             ConfigField::ShowKittyMessage => {
                 self.config.behavior.show_kitty_message = delta > 0.0;
             }
+            ConfigField::PowerSave => {
+                self.config.behavior.power_save = delta > 0.0;
+            }
+            ConfigField::BoldIsBright => {
+                self.config.behavior.bold_is_bright = delta > 0.0;
+            }
+            ConfigField::StatusBar => {
+                self.config.behavior.status_bar = delta > 0.0;
+            }
+            ConfigField::ConfirmMultilinePaste => {
+                self.config.behavior.confirm_multiline_paste = delta > 0.0;
+            }
+            ConfigField::PrimarySelection => {
+                self.config.behavior.use_primary_selection = delta > 0.0;
+            }
             ConfigField::ContentScaleX => {
                 let change = if delta > 0.0 { 0.01 } else { -0.01 };
                 effects.content_scale_x = (effects.content_scale_x + change).clamp(0.8, 1.2);
@@ -611,19 +1151,36 @@ impl ConfigUI {
         match field {
             ConfigField::Curvature => self.config.effects.screen_curvature / 0.5,
             ConfigField::Scanlines => self.config.effects.scanline_intensity,
+            ConfigField::ScanlineGamma => self.config.effects.scanline_gamma / 3.0,
+            ConfigField::CompositeBleed => self.config.effects.composite_bleed,
+            ConfigField::ColorTemperature => {
+                (self.config.effects.color_temperature - 2000.0) / 8000.0
+            }
             ConfigField::Bloom => self.config.effects.bloom,
             ConfigField::BurnIn => self.config.effects.burn_in,
             ConfigField::StaticNoise => self.config.effects.static_noise / 0.5,
             ConfigField::Flicker => self.config.effects.flicker / 0.5,
             ConfigField::Vignette => self.config.effects.vignette,
+            ConfigField::CornerRadius => self.config.effects.corner_radius / 0.25,
+            ConfigField::RgbMask => self.config.effects.rgb_mask,
+            ConfigField::Aberration => self.config.effects.rgb_shift / 3.0,
             ConfigField::Brightness => (self.config.effects.brightness - 0.1) / 1.9,
+            ConfigField::BackgroundOpacity => self.config.effects.background_opacity,
             ConfigField::FocusGlowRadius => self.config.effects.focus_glow_radius / 0.3,
             ConfigField::FocusGlowWidth => (self.config.effects.focus_glow_width - 0.001) / 0.299,
             ConfigField::FocusGlowIntensity => self.config.effects.focus_glow_intensity,
+            ConfigField::GlowRed => self.config.effects.phosphor_tint[0],
+            ConfigField::GlowGreen => self.config.effects.phosphor_tint[1],
+            ConfigField::GlowBlue => self.config.effects.phosphor_tint[2],
             ConfigField::ContentScaleX => (self.config.effects.content_scale_x - 0.8) / 0.4, // 0.8 to 1.2 range
             ConfigField::ContentScaleY => (self.config.effects.content_scale_y - 0.8) / 0.4, // 0.8 to 1.2 range
             ConfigField::FontSize => (self.config.font_size - 8.0) / 24.0, // 8-32 range
-            ConfigField::UiScale => (self.config.ui_scale - 1.0) / 2.0,    // 1.0-3.0 range
+            ConfigField::PaneFontSize => (self.pane_font_size() - 8.0) / 24.0,
+            ConfigField::UiScale => (self.config.ui_scale - 0.5) / 2.5, // 0.5-3.0 range
+            ConfigField::LineHeight => (self.config.line_height_mult - 1.0) / 0.5, // 1.0-1.5 range
+            ConfigField::LetterSpacing => self.config.letter_spacing / 4.0, // 0-4 range
+            ConfigField::PanePadding => self.config.pane_padding / 32.0, // 0-32 range
+            ConfigField::ColorChannel(index, channel) => self.editable_color(index)[channel],
             _ => 0.0,
         }
     }
@@ -634,7 +1191,11 @@ impl ConfigUI {
         // Use a "maximal" config to get the maximum possible field count
         let mut max_rows = 0;
         for tab in ConfigTab::all() {
-            let fields = ConfigField::fields_for_tab(*tab, &self.config);
+            let fields = if *tab == self.current_tab {
+                self.current_fields()
+            } else {
+                ConfigField::fields_for_tab(*tab, &self.config)
+            };
             let mut rows = 0;
             for (i, field) in fields.iter().enumerate() {
                 if i > 0 && field.has_separator_before() {
@@ -679,6 +1240,8 @@ impl ConfigUI {
                         fg: [0.0; 4],
                         bg: [0.0, 0.0, 0.0, 0.0],
                         is_wide: false,
+                        style: GlyphStyle::Regular,
+                        line_size: LineSize::Normal,
                     });
                     continue;
                 }
@@ -693,6 +1256,8 @@ impl ConfigUI {
                     fg,
                     bg,
                     is_wide: false,
+                    style: GlyphStyle::Regular,
+                    line_size: LineSize::Normal,
                 });
             }
 
@@ -795,6 +1360,13 @@ impl ConfigUI {
             let is_selected = field_idx == self.selected;
 
             let line = self.format_field_line(field, width - 6, is_selected);
+            // The last two columns of a color line preview the color
+            if let ConfigField::ColorEditor(index) | ConfigField::ColorChannel(index, _) = field {
+                let swatch_start = line.len().saturating_sub(SWATCH_WIDTH);
+                if (swatch_start..line.len()).contains(&content_col) {
+                    return (' ', fg, self.editable_color(index));
+                }
+            }
             if content_col < line.len() {
                 let c = line.chars().nth(content_col).unwrap_or(' ');
                 let text_fg = if is_selected { bright } else { fg };
@@ -859,7 +1431,13 @@ impl ConfigUI {
 
         if field.is_slider() {
             let value = self.get_field_value(field);
-            let bar_width = 12;
+            // The color temperature's bar is shorter to leave room for its
+            // preset names
+            let bar_width = if field == ConfigField::ColorTemperature {
+                6
+            } else {
+                12
+            };
             let filled = ((value * bar_width as f32).round() as usize).min(bar_width);
             let empty = bar_width - filled;
 
@@ -868,12 +1446,27 @@ impl ConfigUI {
             let value_str = match field {
                 ConfigField::Curvature => format!("{:.2}", self.config.effects.screen_curvature),
                 ConfigField::Scanlines => format!("{:.2}", self.config.effects.scanline_intensity),
+                ConfigField::ScanlineGamma => {
+                    format!("{:.2}", self.config.effects.scanline_gamma)
+                }
+                ConfigField::CompositeBleed => {
+                    format!("{:.2}", self.config.effects.composite_bleed)
+                }
+                ConfigField::ColorTemperature => self.color_temperature_text(),
                 ConfigField::Bloom => format!("{:.2}", self.config.effects.bloom),
                 ConfigField::BurnIn => format!("{:.2}", self.config.effects.burn_in),
                 ConfigField::StaticNoise => format!("{:.2}", self.config.effects.static_noise),
                 ConfigField::Flicker => format!("{:.2}", self.config.effects.flicker),
                 ConfigField::Vignette => format!("{:.2}", self.config.effects.vignette),
+                ConfigField::CornerRadius => {
+                    format!("{:.2}", self.config.effects.corner_radius)
+                }
+                ConfigField::RgbMask => format!("{:.2}", self.config.effects.rgb_mask),
+                ConfigField::Aberration => format!("{:.2}px", self.config.effects.rgb_shift),
                 ConfigField::Brightness => format!("{:.2}", self.config.effects.brightness),
+                ConfigField::BackgroundOpacity => {
+                    format!("{:.2}", self.config.effects.background_opacity)
+                }
                 ConfigField::FocusGlowRadius => {
                     format!("{:.4}", self.config.effects.focus_glow_radius)
                 }
@@ -883,23 +1476,63 @@ impl ConfigUI {
                 ConfigField::FocusGlowIntensity => {
                     format!("{:.2}", self.config.effects.focus_glow_intensity)
                 }
+                ConfigField::GlowRed => format!("{:.2}", self.config.effects.phosphor_tint[0]),
+                ConfigField::GlowGreen => format!("{:.2}", self.config.effects.phosphor_tint[1]),
+                ConfigField::GlowBlue => format!("{:.2}", self.config.effects.phosphor_tint[2]),
                 ConfigField::FontSize => format!("{:.0}px", self.config.font_size),
+                ConfigField::PaneFontSize => format!("{:.0}px", self.pane_font_size()),
                 ConfigField::UiScale => format!("{:.2}x", self.config.ui_scale),
+                ConfigField::LineHeight => format!("{:.2}x", self.config.line_height_mult),
+                ConfigField::LetterSpacing => format!("{:.0}px", self.config.letter_spacing),
+                ConfigField::PanePadding => format!("{:.0}px", self.config.pane_padding),
+                ConfigField::ColorChannel(..) => format!("{:3}", (value * 255.0).round() as u8),
                 _ => String::new(),
             };
 
             let prefix = if selected { "> " } else { "  " };
-            format!("{}{:12} {} {}", prefix, label, bar, value_str)
+            let line = format!("{}{:12} {} {}", prefix, label, bar, value_str);
+            if matches!(field, ConfigField::ColorChannel(..)) {
+                return format!("{}  {}", line, " ".repeat(SWATCH_WIDTH));
+            }
+            line
         } else if field.is_selector() {
             let value_name = match field {
-                ConfigField::FontFamily => self.config.font.label().to_string(),
-                ConfigField::BdfFontFamily => self
-                    .config
-                    .bdf_font
-                    .map(|f| f.label())
-                    .unwrap_or("?")
-                    .to_string(),
-                ConfigField::ColorSchemeField => self.config.color_scheme.name.clone(),
+                ConfigField::FontFamily => match self.config.custom_font_name() {
+                    Some(name) => format!("Custom: {}", shorten(&name, CUSTOM_FONT_NAME_WIDTH)),
+                    None => self.config.font.label().to_string(),
+                },
+                ConfigField::PaneBorder => self.config.pane_border_style.label().to_string(),
+                ConfigField::BdfFontFamily => match self.config.custom_bdf_font_name() {
+                    Some(name) => shorten(&name, BDF_FILE_NAME_WIDTH),
+                    None => self
+                        .config
+                        .bdf_font
+                        .map(|f| f.label())
+                        .unwrap_or("?")
+                        .to_string(),
+                },
+                ConfigField::ColorEditor(index) => {
+                    let prefix = if selected { "> " } else { "  " };
+                    let name = COLOR_NAMES[index];
+                    let swatch = " ".repeat(SWATCH_WIDTH);
+                    return format!("{}{:12} < {:^13} >  {}", prefix, label, name, swatch);
+                }
+                ConfigField::ColorSchemeField => {
+                    if let Some(path) = &self.scheme_path {
+                        let prefix = if selected { "> " } else { "  " };
+                        let error = self
+                            .scheme_error
+                            .as_ref()
+                            .map(|e| format!("  ({})", e))
+                            .unwrap_or_default();
+                        return format!("{}{:12} File: {}_{}", prefix, label, path, error);
+                    }
+                    if self.scheme_file_entry {
+                        "Load from file...".to_string()
+                    } else {
+                        self.config.color_scheme.name.clone()
+                    }
+                }
                 _ => "?".to_string(),
             };
             let prefix = if selected { "> " } else { "  " };
@@ -934,11 +1567,18 @@ impl ConfigUI {
                 }
             }
             let is_on = match field {
+                ConfigField::CrtEffects => self.config.effects.enabled,
                 ConfigField::PerPaneCrt => self.config.per_pane_crt,
                 ConfigField::BezelEnabled => self.config.effects.bezel_enabled,
+                ConfigField::CustomGlow => self.config.effects.use_custom_glow,
                 ConfigField::AutoCopySelection => self.config.behavior.auto_copy_selection,
                 ConfigField::ShowStartupHint => self.config.behavior.show_startup_hint,
                 ConfigField::ShowKittyMessage => self.config.behavior.show_kitty_message,
+                ConfigField::PowerSave => self.config.behavior.power_save,
+                ConfigField::BoldIsBright => self.config.behavior.bold_is_bright,
+                ConfigField::ConfirmMultilinePaste => self.config.behavior.confirm_multiline_paste,
+                ConfigField::PrimarySelection => self.config.behavior.use_primary_selection,
+                ConfigField::StatusBar => self.config.behavior.status_bar,
                 ConfigField::Interlace => self.config.effects.interlace_enabled,
                 _ => false,
             };
@@ -959,3 +1599,12 @@ pub enum ConfigAction {
     Save,
     Cancel,
 }
+
+/// `text` cut to at most `width` characters, ending in `…` when shortened
+fn shorten(text: &str, width: usize) -> String {
+    if text.chars().count() <= width {
+        return text.to_string();
+    }
+    let short: String = text.chars().take(width - 1).collect();
+    format!("{}…", short)
+}
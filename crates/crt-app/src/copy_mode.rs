@@ -0,0 +1,87 @@
+// ABOUTME: Keyboard-driven copy mode for selecting text without the mouse.
+// ABOUTME: Maps vi-style keys to cursor motions, selection and yank commands.
+
+use crt_layout::PaneId;
+use crt_terminal::ViMotion;
+use winit::keyboard::{Key, NamedKey};
+
+/// Copy mode state; the cursor itself lives in the pane's terminal
+#[derive(Debug, Clone, Copy)]
+pub struct CopyMode {
+    pub pane: PaneId,
+    /// Whether `v` or Space started a selection at some earlier cursor position
+    pub selecting: bool,
+}
+
+impl CopyMode {
+    pub fn new(pane: PaneId) -> Self {
+        Self {
+            pane,
+            selecting: false,
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        if self.selecting {
+            "-- VISUAL --"
+        } else {
+            "-- COPY --"
+        }
+    }
+}
+
+/// What a key does in copy mode
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CopyCommand {
+    Motion(ViMotion),
+    /// To the start of the next word, as double-click selects words
+    WordForward,
+    /// To the start of this word, or of the one before
+    WordBackward,
+    HalfPageUp,
+    HalfPageDown,
+    /// Start a selection at the cursor, or drop the current one
+    ToggleSelection,
+    /// Copy the selection and leave copy mode
+    Yank,
+    Exit,
+}
+
+/// The copy mode command for a key press, if it has one
+pub fn command_for_key(key: &Key, ctrl: bool) -> Option<CopyCommand> {
+    let command = match key {
+        Key::Character(s) if ctrl => match s.to_lowercase().as_str() {
+            "u" => CopyCommand::HalfPageUp,
+            "d" => CopyCommand::HalfPageDown,
+            _ => return None,
+        },
+        Key::Character(s) => match s.as_str() {
+            "h" => CopyCommand::Motion(ViMotion::Left),
+            "j" => CopyCommand::Motion(ViMotion::Down),
+            "k" => CopyCommand::Motion(ViMotion::Up),
+            "l" => CopyCommand::Motion(ViMotion::Right),
+            "w" => CopyCommand::WordForward,
+            "b" => CopyCommand::WordBackward,
+            "0" => CopyCommand::Motion(ViMotion::First),
+            "$" => CopyCommand::Motion(ViMotion::Last),
+            "v" => CopyCommand::ToggleSelection,
+            "y" => CopyCommand::Yank,
+            "q" => CopyCommand::Exit,
+            _ => return None,
+        },
+        Key::Named(named) => match named {
+            NamedKey::ArrowLeft => CopyCommand::Motion(ViMotion::Left),
+            NamedKey::ArrowDown => CopyCommand::Motion(ViMotion::Down),
+            NamedKey::ArrowUp => CopyCommand::Motion(ViMotion::Up),
+            NamedKey::ArrowRight => CopyCommand::Motion(ViMotion::Right),
+            NamedKey::PageUp => CopyCommand::HalfPageUp,
+            NamedKey::PageDown => CopyCommand::HalfPageDown,
+            NamedKey::Space => CopyCommand::ToggleSelection,
+            NamedKey::Enter => CopyCommand::Yank,
+            NamedKey::Escape => CopyCommand::Exit,
+            _ => return None,
+        },
+        _ => return None,
+    };
+    Some(command)
+}
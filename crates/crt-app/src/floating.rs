@@ -0,0 +1,119 @@
+// ABOUTME: Floating panes: the title bar drawn above one and dragging it around by that bar.
+// ABOUTME: Geometry is kept in normalized window coordinates, like the layout's rectangles.
+
+use crt_core::{ColorScheme, FloatConfig, LineSize};
+use crt_layout::Rect;
+use crt_renderer::{GlyphStyle, RenderCell};
+
+/// Smallest floating pane, as a fraction of the window on each side
+const MIN_SIZE: f32 = 0.1;
+
+/// The layout rectangle for saved geometry, kept inside the window
+pub fn rect_from_config(config: &FloatConfig) -> Rect {
+    let width = config.width.clamp(MIN_SIZE, 1.0);
+    let height = config.height.clamp(MIN_SIZE, 1.0);
+    Rect {
+        x: config.x.clamp(0.0, 1.0 - width),
+        y: config.y.clamp(0.0, 1.0 - height),
+        width,
+        height,
+    }
+}
+
+pub fn config_from_rect(rect: Rect) -> FloatConfig {
+    FloatConfig {
+        x: rect.x,
+        y: rect.y,
+        width: rect.width,
+        height: rect.height,
+    }
+}
+
+/// Where `rect` goes when its title bar, grabbed at `grab` from its top
+/// left corner, is dragged to `pointer`. The title bar, `header` high,
+/// stays inside the window.
+pub fn dragged_rect(rect: Rect, grab: (f32, f32), pointer: (f32, f32), header: f32) -> Rect {
+    let max_y = (1.0 - rect.height).max(header);
+    Rect {
+        x: (pointer.0 - grab.0).clamp(0.0, (1.0 - rect.width).max(0.0)),
+        y: (pointer.1 - grab.1).clamp(header, max_y),
+        ..rect
+    }
+}
+
+/// Top of a floating pane's title bar, which sits just above the pane
+/// (in logical pixels, for a window `window_height` high)
+pub fn header_top(rect: Rect, window_height: f32, cell_h: f32) -> f32 {
+    (rect.y * window_height - cell_h).max(0.0)
+}
+
+/// The title bar row: the pane's title on a dim bar `columns` cells wide
+pub fn header_row(title: &str, columns: usize, scheme: &ColorScheme) -> Vec<RenderCell> {
+    let fg = scheme.foreground;
+    let bg = [fg[0] * 0.3, fg[1] * 0.3, fg[2] * 0.3, 1.0];
+    let mut chars = std::iter::once(' ').chain(title.chars());
+    (0..columns)
+        .map(|_| RenderCell {
+            c: chars.next().unwrap_or(' '),
+            fg,
+            bg,
+            is_wide: false,
+            style: GlyphStyle::Bold,
+            line_size: LineSize::Normal,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rect(x: f32, y: f32) -> Rect {
+        Rect {
+            x,
+            y,
+            width: 0.5,
+            height: 0.4,
+        }
+    }
+
+    #[test]
+    fn dragging_moves_by_the_grab_point() {
+        let moved = dragged_rect(rect(0.1, 0.1), (0.2, -0.01), (0.5, 0.3), 0.02);
+        assert!((moved.x - 0.3).abs() < 1e-6);
+        assert!((moved.y - 0.31).abs() < 1e-6);
+        assert_eq!(moved.width, 0.5);
+    }
+
+    #[test]
+    fn dragging_keeps_the_pane_in_the_window() {
+        let header = 0.02;
+        let moved = dragged_rect(rect(0.1, 0.1), (0.0, 0.0), (-0.5, -0.5), header);
+        assert_eq!((moved.x, moved.y), (0.0, header));
+        let moved = dragged_rect(rect(0.1, 0.1), (0.0, 0.0), (2.0, 2.0), header);
+        assert_eq!((moved.x, moved.y), (0.5, 0.6));
+    }
+
+    #[test]
+    fn saved_geometry_is_clamped() {
+        let rect = rect_from_config(&FloatConfig {
+            x: 0.9,
+            y: -1.0,
+            width: 0.5,
+            height: 0.0,
+        });
+        assert_eq!(rect.x, 0.5);
+        assert_eq!(rect.y, 0.0);
+        assert_eq!(rect.height, MIN_SIZE);
+    }
+
+    #[test]
+    fn header_shows_the_title() {
+        let row = header_row("tail -f log", 20, &ColorScheme::amber());
+        assert_eq!(row.len(), 20);
+        let text: String = row.iter().map(|cell| cell.c).collect();
+        assert_eq!(text.trim_end(), " tail -f log");
+        let short = header_row("a long title", 4, &ColorScheme::amber());
+        assert_eq!(short.iter().map(|cell| cell.c).collect::<String>(), " a l");
+    }
+}
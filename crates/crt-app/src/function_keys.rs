@@ -0,0 +1,109 @@
+// ABOUTME: Legacy (xterm/VT220) sequences for F1–F24 and the Menu key.
+// ABOUTME: Modifiers are added xterm-style as CSI n ; modifier ~.
+
+use winit::keyboard::{ModifiersState, NamedKey};
+
+use crate::kitty_keyboard;
+
+/// How a function key is written without modifiers
+enum Legacy {
+    /// SS3 letter, or CSI 1 ; modifier letter once modified (F1–F4)
+    Letter(u8),
+    /// CSI number ~
+    Tilde(u8),
+}
+
+/// Encode a function key without the Kitty protocol. F13–F20 use the VT220
+/// codes; F21–F24 have none, so they are sent as xterm's Shift+F9–F12.
+/// Returns None for keys that aren't function keys.
+pub fn encode_legacy(named: &NamedKey, modifiers: ModifiersState) -> Option<Vec<u8>> {
+    let mut mod_flags = kitty_keyboard::modifier_flags(modifiers);
+    let legacy = match named {
+        NamedKey::F1 => Legacy::Letter(b'P'),
+        NamedKey::F2 => Legacy::Letter(b'Q'),
+        NamedKey::F3 => Legacy::Letter(b'R'),
+        NamedKey::F4 => Legacy::Letter(b'S'),
+        NamedKey::F5 => Legacy::Tilde(15),
+        NamedKey::F6 => Legacy::Tilde(17),
+        NamedKey::F7 => Legacy::Tilde(18),
+        NamedKey::F8 => Legacy::Tilde(19),
+        NamedKey::F9 => Legacy::Tilde(20),
+        NamedKey::F10 => Legacy::Tilde(21),
+        NamedKey::F11 => Legacy::Tilde(23),
+        NamedKey::F12 => Legacy::Tilde(24),
+        NamedKey::F13 => Legacy::Tilde(25),
+        NamedKey::F14 => Legacy::Tilde(26),
+        NamedKey::F15 => Legacy::Tilde(28),
+        NamedKey::F16 => Legacy::Tilde(29),
+        NamedKey::F17 => Legacy::Tilde(31),
+        NamedKey::F18 => Legacy::Tilde(32),
+        NamedKey::F19 => Legacy::Tilde(33),
+        NamedKey::F20 => Legacy::Tilde(34),
+        NamedKey::F21 | NamedKey::F22 | NamedKey::F23 | NamedKey::F24 => {
+            mod_flags |= 1;
+            let number = match named {
+                NamedKey::F21 => 20,
+                NamedKey::F22 => 21,
+                NamedKey::F23 => 23,
+                _ => 24,
+            };
+            Legacy::Tilde(number)
+        }
+        // VT220 "Do", where xterm puts the Menu key
+        NamedKey::ContextMenu => Legacy::Tilde(29),
+        _ => return None,
+    };
+    let bytes = match (legacy, mod_flags) {
+        (Legacy::Letter(letter), 0) => format!("\x1bO{}", letter as char),
+        (Legacy::Letter(letter), m) => format!("\x1b[1;{}{}", m + 1, letter as char),
+        (Legacy::Tilde(number), 0) => format!("\x1b[{}~", number),
+        (Legacy::Tilde(number), m) => format!("\x1b[{};{}~", number, m + 1),
+    };
+    Some(bytes.into_bytes())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn encode_str(named: NamedKey, modifiers: ModifiersState) -> String {
+        String::from_utf8(encode_legacy(&named, modifiers).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn plain_function_keys() {
+        let none = ModifiersState::empty();
+        assert_eq!(encode_str(NamedKey::F1, none), "\x1bOP");
+        assert_eq!(encode_str(NamedKey::F5, none), "\x1b[15~");
+        assert_eq!(encode_str(NamedKey::F12, none), "\x1b[24~");
+    }
+
+    #[test]
+    fn high_function_keys_use_vt220_codes() {
+        let none = ModifiersState::empty();
+        assert_eq!(encode_str(NamedKey::F13, none), "\x1b[25~");
+        assert_eq!(encode_str(NamedKey::F16, none), "\x1b[29~");
+        assert_eq!(encode_str(NamedKey::F20, none), "\x1b[34~");
+        assert_eq!(encode_str(NamedKey::F21, none), "\x1b[20;2~");
+        assert_eq!(encode_str(NamedKey::F24, none), "\x1b[24;2~");
+    }
+
+    #[test]
+    fn shifted_function_keys_match_xterm() {
+        let shift = ModifiersState::SHIFT;
+        assert_eq!(encode_str(NamedKey::F1, shift), "\x1b[1;2P");
+        assert_eq!(encode_str(NamedKey::F4, shift), "\x1b[1;2S");
+        assert_eq!(encode_str(NamedKey::F5, shift), "\x1b[15;2~");
+        assert_eq!(
+            encode_str(NamedKey::F12, ModifiersState::CONTROL),
+            "\x1b[24;5~"
+        );
+    }
+
+    #[test]
+    fn other_keys_are_not_function_keys() {
+        let none = ModifiersState::empty();
+        assert_eq!(encode_legacy(&NamedKey::ArrowUp, none), None);
+        assert_eq!(encode_legacy(&NamedKey::PrintScreen, none), None);
+    }
+}
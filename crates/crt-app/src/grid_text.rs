@@ -0,0 +1,223 @@
+// ABOUTME: Walks the grid cells covered by a selection, one output line per logical line.
+// ABOUTME: Wide characters are taken once and tabs stay tabs; spacer and padding cells are skipped.
+
+use alacritty_terminal::grid::{Dimensions, Grid};
+use alacritty_terminal::index::{Column, Line};
+use alacritty_terminal::term::cell::{Cell, Flags};
+
+/// Columns between tab stops; alacritty only ever sets the default stops
+const TAB_WIDTH: usize = 8;
+
+/// Map every selected cell that holds a character of its own, from the
+/// (row, col) `start` to `end` inclusive, in buffer-relative rows.
+/// Soft-wrapped rows are joined into one line; block selections take the
+/// same columns from every row and keep rows apart. A selection starting on
+/// the right half of a wide character still includes that character. A tab
+/// is taken as itself, without the blank cells it moved over.
+pub fn selected_lines<T>(
+    grid: &Grid<Cell>,
+    start: (i32, usize),
+    end: (i32, usize),
+    block: bool,
+    mut map: impl FnMut(&Cell) -> T,
+) -> Vec<Vec<T>> {
+    let last_col = grid.columns().saturating_sub(1);
+    let mut lines = vec![Vec::new()];
+
+    for row in start.0..=end.0 {
+        let line = Line(row);
+        let (mut col_start, col_end) = if block {
+            (start.1.min(end.1), start.1.max(end.1).min(last_col))
+        } else {
+            let col_start = if row == start.0 { start.1 } else { 0 };
+            let col_end = if row == end.0 {
+                end.1.min(last_col)
+            } else {
+                last_col
+            };
+            (col_start, col_end)
+        };
+        if col_start > 0
+            && col_start <= last_col
+            && grid[line][Column(col_start)]
+                .flags
+                .contains(Flags::WIDE_CHAR_SPACER)
+        {
+            col_start -= 1;
+        }
+
+        let current = lines.last_mut().expect("lines starts non-empty");
+        let mut in_tab = false;
+        for col in col_start..=col_end {
+            let cell = &grid[line][Column(col)];
+            // alacritty keeps a tab in the cell it started from; the cells up
+            // to the next tab stop stay blank unless written over later
+            if in_tab {
+                if col % TAB_WIDTH != 0 && cell.c == ' ' {
+                    continue;
+                }
+                in_tab = false;
+            }
+            if cell.c == '\0'
+                || cell
+                    .flags
+                    .intersects(Flags::WIDE_CHAR_SPACER | Flags::LEADING_WIDE_CHAR_SPACER)
+            {
+                continue;
+            }
+            in_tab = cell.c == '\t';
+            current.push(map(cell));
+        }
+        // Only start a new line if this row wasn't soft-wrapped
+        // (block rows are always independent)
+        if row != end.0 && (block || !grid[line][Column(last_col)].flags.contains(Flags::WRAPLINE))
+        {
+            lines.push(Vec::new());
+        }
+    }
+    lines
+}
+
+/// Everything in the grid as (row, col) start and end for a selection: from
+/// the top of the history to the last cell holding a character. None if the
+/// grid is blank.
+pub fn whole_buffer(grid: &Grid<Cell>) -> Option<((i32, usize), (i32, usize))> {
+    let top = grid.topmost_line().0;
+    let bottom = grid.bottommost_line().0;
+    (top..=bottom).rev().find_map(|row| {
+        let cells = &grid[Line(row)];
+        let col = (0..grid.columns()).rev().find(|&col| {
+            let c = cells[Column(col)].c;
+            c != ' ' && c != '\0'
+        })?;
+        Some(((top, 0), (row, col)))
+    })
+}
+
+/// Whether a cell separates words, as in double-click word selection
+pub fn is_blank(c: char) -> bool {
+    c.is_whitespace() || c == '\0'
+}
+
+/// First non-blank cell after (row, col), reading on across rows
+pub fn next_non_blank(grid: &Grid<Cell>, (row, col): (i32, usize)) -> Option<(i32, usize)> {
+    let columns = grid.columns();
+    let mut cells = (row..=grid.bottommost_line().0)
+        .flat_map(|row| (0..columns).map(move |col| (row, col)))
+        .skip_while(|&pos| pos <= (row, col));
+    cells.find(|&(row, col)| !is_blank(grid[Line(row)][Column(col)].c))
+}
+
+/// Last non-blank cell before (row, col), reading back across rows
+pub fn previous_non_blank(grid: &Grid<Cell>, (row, col): (i32, usize)) -> Option<(i32, usize)> {
+    let columns = grid.columns();
+    let mut cells = (grid.topmost_line().0..=row)
+        .rev()
+        .flat_map(|row| (0..columns).rev().map(move |col| (row, col)))
+        .skip_while(|&pos| pos >= (row, col));
+    cells.find(|&(row, col)| !is_blank(grid[Line(row)][Column(col)].c))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alacritty_terminal::event::VoidListener;
+    use alacritty_terminal::term::test::TermSize;
+    use alacritty_terminal::term::{Config, Term};
+    use alacritty_terminal::vte::ansi::Processor;
+
+    fn term_with(columns: usize, output: &str) -> Term<VoidListener> {
+        let size = TermSize::new(columns, 4);
+        let mut term = Term::new(Config::default(), &size, VoidListener);
+        let mut parser: Processor = Processor::new();
+        parser.advance(&mut term, output.as_bytes());
+        term
+    }
+
+    fn text(term: &Term<VoidListener>, start: (i32, usize), end: (i32, usize)) -> String {
+        selected_lines(term.grid(), start, end, false, |cell| cell.c)
+            .into_iter()
+            .map(|line| line.into_iter().collect::<String>())
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    #[test]
+    fn wide_characters_copy_once() {
+        let term = term_with(20, "日本語");
+        assert_eq!(text(&term, (0, 0), (0, 5)), "日本語");
+    }
+
+    #[test]
+    fn selection_from_right_half_includes_wide_character() {
+        let term = term_with(20, "日本語");
+        assert_eq!(text(&term, (0, 1), (0, 2)), "日本");
+    }
+
+    #[test]
+    fn wide_character_wrapped_early_is_not_split() {
+        // The third character doesn't fit in the last column, which is left
+        // as a leading spacer
+        let term = term_with(5, "日本語");
+        assert_eq!(text(&term, (0, 0), (1, 1)), "日本語");
+    }
+
+    #[test]
+    fn mixed_lines_copy_as_written() {
+        let lines = ["ls -l 日本語.txt", "done 🎉 ok", "中a文b"];
+        let term = term_with(20, &lines.join("\r\n"));
+        let copied = text(&term, (0, 0), (2, 19));
+        let copied: Vec<&str> = copied.lines().map(str::trim_end).collect();
+        assert_eq!(copied, lines);
+    }
+
+    #[test]
+    fn selection_ending_on_left_half_includes_wide_character() {
+        let term = term_with(20, "a🎉b");
+        assert_eq!(text(&term, (0, 0), (0, 1)), "a🎉");
+        assert_eq!(text(&term, (0, 2), (0, 3)), "🎉b");
+    }
+
+    #[test]
+    fn block_selection_keeps_wide_characters_whole() {
+        let term = term_with(20, "ab日本\r\n日本ab");
+        let block: Vec<String> = selected_lines(term.grid(), (0, 1), (1, 2), true, |cell| cell.c)
+            .into_iter()
+            .map(|line| line.into_iter().collect())
+            .collect();
+        assert_eq!(block, ["b日", "日本"]);
+    }
+
+    #[test]
+    fn whole_buffer_ends_at_the_last_character() {
+        let term = term_with(20, "one\r\ntwo\r\n");
+        assert_eq!(whole_buffer(term.grid()), Some(((0, 0), (1, 2))));
+        assert_eq!(whole_buffer(term_with(20, "").grid()), None);
+
+        // History rows come first
+        let term = term_with(20, "1\r\n2\r\n3\r\n4\r\n5\r\n6");
+        assert_eq!(whole_buffer(term.grid()), Some(((-2, 0), (3, 0))));
+    }
+
+    #[test]
+    fn non_blank_cells_across_rows() {
+        let term = term_with(10, "ab  cd\r\n\r\n  ef");
+        let grid = term.grid();
+        assert_eq!(next_non_blank(grid, (0, 1)), Some((0, 4)));
+        assert_eq!(next_non_blank(grid, (0, 5)), Some((2, 2)));
+        assert_eq!(next_non_blank(grid, (2, 3)), None);
+        assert_eq!(previous_non_blank(grid, (2, 2)), Some((0, 5)));
+        assert_eq!(previous_non_blank(grid, (0, 4)), Some((0, 1)));
+        assert_eq!(previous_non_blank(grid, (0, 0)), None);
+    }
+
+    #[test]
+    fn tabs_copy_as_tabs() {
+        let term = term_with(30, "a\tb\tc\r\n1234567\tx");
+        let copied = text(&term, (0, 0), (1, 29));
+        let copied: Vec<&str> = copied.lines().map(str::trim_end).collect();
+        assert_eq!(copied, ["a\tb\tc", "1234567\tx"]);
+        // Starting partway through a tab keeps the rest of it as blanks
+        assert_eq!(text(&term, (0, 4), (0, 8)), "    b");
+    }
+}
@@ -0,0 +1,340 @@
+// ABOUTME: Hint mode: labels URLs, paths and file:line:col locations on screen.
+// ABOUTME: Typing a label opens the target (or copies it), like vimium or tmux-fingers.
+
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use crt_layout::PaneId;
+use crt_terminal::TextMatch;
+
+/// URLs, then compiler-style file:line[:col] locations, then plain paths
+pub const HINT_PATTERN: &str = concat!(
+    r#"(?:https?|ftp|file)://[^\s<>"'`]+"#,
+    r"|[A-Za-z0-9_./~+-]*[A-Za-z0-9_~+-]\.[A-Za-z0-9]+:[0-9]+(?::[0-9]+)?",
+    r"|[A-Za-z0-9_.~+-]*/[A-Za-z0-9_.~+-][A-Za-z0-9_./~+-]*",
+);
+
+/// Label characters, home row first
+const LABEL_CHARS: &str = "asdfghjklqwertyuiopzxcvbnm";
+
+/// Punctuation that usually ends the sentence around a URL, not the URL itself
+const TRAILING_PUNCTUATION: &[char] = &['.', ',', ';', ':', '!', '?', ')', ']', '\'', '"'];
+
+/// What a hint points at
+#[derive(Debug, Clone, PartialEq)]
+pub enum HintTarget {
+    Url(String),
+    File {
+        path: String,
+        line: Option<u32>,
+        column: Option<u32>,
+    },
+}
+
+impl HintTarget {
+    pub fn parse(text: &str) -> Self {
+        if text.contains("://") {
+            return HintTarget::Url(text.to_string());
+        }
+        // Peel up to two trailing :number parts off, right to left
+        let mut numbers = Vec::new();
+        let mut path = text;
+        while numbers.len() < 2 {
+            match path.rsplit_once(':') {
+                Some((rest, n)) if !n.is_empty() && n.bytes().all(|b| b.is_ascii_digit()) => {
+                    numbers.push(n.parse().ok());
+                    path = rest;
+                }
+                _ => break,
+            }
+        }
+        numbers.reverse();
+        HintTarget::File {
+            path: path.to_string(),
+            line: numbers.first().copied().flatten(),
+            column: numbers.get(1).copied().flatten(),
+        }
+    }
+}
+
+/// A labeled match on screen
+#[derive(Debug, Clone)]
+pub struct Hint {
+    pub label: String,
+    /// First cell as (column, buffer line)
+    pub start: (usize, i32),
+    pub text: String,
+    pub target: HintTarget,
+}
+
+/// Hint mode state for one pane
+#[derive(Debug)]
+pub struct HintMode {
+    pub pane: PaneId,
+    pub hints: Vec<Hint>,
+    /// Label characters typed so far
+    pub typed: String,
+}
+
+/// Result of typing a label character
+pub enum HintInput {
+    /// More characters are needed
+    Pending,
+    Chosen(Hint),
+    /// No label starts with what was typed
+    NoMatch,
+}
+
+impl HintMode {
+    /// None if nothing on screen can be hinted
+    pub fn new(pane: PaneId, matches: Vec<TextMatch>) -> Option<Self> {
+        let matches: Vec<(TextMatch, String)> = matches
+            .into_iter()
+            .filter_map(|m| {
+                let text = m.text.trim_end_matches(TRAILING_PUNCTUATION).to_string();
+                (!text.is_empty()).then_some((m, text))
+            })
+            .collect();
+        if matches.is_empty() {
+            return None;
+        }
+        let hints = labels(matches.len())
+            .into_iter()
+            .zip(matches)
+            .map(|(label, (m, text))| Hint {
+                label,
+                start: m.start,
+                target: HintTarget::parse(&text),
+                text,
+            })
+            .collect();
+        Some(Self {
+            pane,
+            hints,
+            typed: String::new(),
+        })
+    }
+
+    pub fn type_char(&mut self, c: char) -> HintInput {
+        self.typed.push(c.to_ascii_lowercase());
+        if let Some(hint) = self.hints.iter().find(|hint| hint.label == self.typed) {
+            return HintInput::Chosen(hint.clone());
+        }
+        if self.visible().next().is_some() {
+            HintInput::Pending
+        } else {
+            HintInput::NoMatch
+        }
+    }
+
+    /// Hints whose label still matches what was typed
+    pub fn visible(&self) -> impl Iterator<Item = &Hint> {
+        self.hints
+            .iter()
+            .filter(|hint| hint.label.starts_with(&self.typed))
+    }
+
+    /// Labels still to type as (column, display line, label), for hints
+    /// inside a `rows` tall screen scrolled back by `display_offset`
+    pub fn labels_on_screen(
+        &self,
+        display_offset: usize,
+        rows: usize,
+    ) -> Vec<(usize, usize, String)> {
+        self.visible()
+            .filter_map(|hint| {
+                let (col, line) = hint.start;
+                let line = usize::try_from(line + display_offset as i32).ok()?;
+                let label = hint.label[self.typed.len()..].to_string();
+                (line < rows).then_some((col, line, label))
+            })
+            .collect()
+    }
+}
+
+/// Labels for `count` hints: single letters when they suffice, otherwise
+/// two letters each so no label is a prefix of another
+fn labels(count: usize) -> Vec<String> {
+    let chars: Vec<char> = LABEL_CHARS.chars().collect();
+    if count <= chars.len() {
+        return chars.iter().take(count).map(|c| c.to_string()).collect();
+    }
+    chars
+        .iter()
+        .flat_map(|&a| chars.iter().map(move |&b| format!("{a}{b}")))
+        .take(count)
+        .collect()
+}
+
+/// Command that opens a hint target. File locations use `editor_template`
+/// if set, with `{file}`, `{line}` and `{col}` substituted; everything else
+/// goes to the system opener. Relative paths resolve against `cwd`.
+pub fn open_command(
+    target: &HintTarget,
+    editor_template: Option<&str>,
+    cwd: Option<&Path>,
+) -> Option<Command> {
+    let (file, line, column) = match target {
+        HintTarget::Url(url) => return Some(system_opener(url)),
+        HintTarget::File { path, line, column } => (resolve_path(path, cwd), line, column),
+    };
+    let Some(template) = editor_template else {
+        return Some(system_opener(&file.to_string_lossy()));
+    };
+    let file = file.to_string_lossy();
+    let line = line.unwrap_or(1).to_string();
+    let column = column.unwrap_or(1).to_string();
+    let mut args = template.split_whitespace().map(|arg| {
+        arg.replace("{file}", &file)
+            .replace("{line}", &line)
+            .replace("{col}", &column)
+    });
+    let mut command = Command::new(args.next()?);
+    command.args(args);
+    if let Some(cwd) = cwd {
+        command.current_dir(cwd);
+    }
+    Some(command)
+}
+
+/// Expand a leading `~/` and make relative paths relative to `cwd`
+pub fn resolve_path(path: &str, cwd: Option<&Path>) -> PathBuf {
+    if let Some(rest) = path.strip_prefix("~/") {
+        if let Some(home) = dirs::home_dir() {
+            return home.join(rest);
+        }
+    }
+    match cwd {
+        Some(cwd) => cwd.join(path),
+        None => PathBuf::from(path),
+    }
+}
+
+fn system_opener(target: &str) -> Command {
+    #[cfg(target_os = "macos")]
+    let mut command = Command::new("open");
+    // Explorer hands the target to its handler as-is; `cmd /C start`
+    // would treat `&` in a URL as a command separator
+    #[cfg(windows)]
+    let mut command = Command::new("explorer");
+    #[cfg(not(any(target_os = "macos", windows)))]
+    let mut command = Command::new("xdg-open");
+    command.arg(target);
+    command
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn mode(texts: &[&str]) -> Option<HintMode> {
+        let matches = texts
+            .iter()
+            .enumerate()
+            .map(|(i, text)| TextMatch {
+                start: (0, i as i32),
+                end: (text.len() - 1, i as i32),
+                text: text.to_string(),
+            })
+            .collect();
+        HintMode::new(PaneId(1), matches)
+    }
+
+    fn file(path: &str, line: Option<u32>, column: Option<u32>) -> HintTarget {
+        HintTarget::File {
+            path: path.to_string(),
+            line,
+            column,
+        }
+    }
+
+    #[test]
+    fn urls_and_locations_parse() {
+        assert_eq!(
+            HintTarget::parse("https://example.com:8080/a"),
+            HintTarget::Url("https://example.com:8080/a".to_string())
+        );
+        assert_eq!(
+            HintTarget::parse("src/main.rs:12:5"),
+            file("src/main.rs", Some(12), Some(5))
+        );
+        assert_eq!(
+            HintTarget::parse("src/main.rs:12"),
+            file("src/main.rs", Some(12), None)
+        );
+        assert_eq!(
+            HintTarget::parse("/etc/hosts"),
+            file("/etc/hosts", None, None)
+        );
+        // Only two numbers are peeled off, and only all-digit ones
+        assert_eq!(HintTarget::parse("a:1:2:3"), file("a:1", Some(2), Some(3)));
+        assert_eq!(HintTarget::parse("a.rs:x"), file("a.rs:x", None, None));
+    }
+
+    #[test]
+    fn labels_are_never_prefixes_of_each_other() {
+        assert_eq!(labels(3), ["a", "s", "d"]);
+        let many = labels(30);
+        assert_eq!(many.len(), 30);
+        assert_eq!(&many[..2], ["aa", "as"]);
+        for a in &many {
+            assert_eq!(many.iter().filter(|b| b.starts_with(a.as_str())).count(), 1);
+        }
+    }
+
+    #[test]
+    fn typing_a_label_chooses_its_hint() {
+        let mut hints = mode(&["https://a.example/x.", "src/lib.rs:3"]).unwrap();
+        // Trailing punctuation isn't part of the target
+        assert_eq!(hints.hints[0].text, "https://a.example/x");
+        match hints.type_char('S') {
+            HintInput::Chosen(hint) => assert_eq!(hint.target, file("src/lib.rs", Some(3), None)),
+            _ => panic!("expected the second hint"),
+        }
+
+        let texts: Vec<String> = (0..30).map(|i| format!("/tmp/{i}")).collect();
+        let texts: Vec<&str> = texts.iter().map(String::as_str).collect();
+        let mut hints = mode(&texts).unwrap();
+        assert!(matches!(hints.type_char('a'), HintInput::Pending));
+        assert_eq!(hints.visible().count(), 26);
+        assert_eq!(
+            hints.labels_on_screen(0, 3),
+            [
+                (0, 0, "a".to_string()),
+                (0, 1, "s".to_string()),
+                (0, 2, "d".to_string())
+            ]
+        );
+        assert!(matches!(hints.type_char('s'), HintInput::Chosen(hint) if hint.text == "/tmp/1"));
+
+        let mut hints = mode(&["/tmp/a", "/tmp/b"]).unwrap();
+        assert!(matches!(hints.type_char('z'), HintInput::NoMatch));
+        assert!(mode(&["."]).is_none());
+    }
+
+    #[test]
+    fn editor_template_gets_the_location() {
+        let target = file("src/main.rs", Some(12), None);
+        let command = open_command(
+            &target,
+            Some("code --goto {file}:{line}:{col}"),
+            Some(Path::new("/work")),
+        )
+        .unwrap();
+        assert_eq!(command.get_program(), "code");
+        let args: Vec<_> = command.get_args().collect();
+        let location = format!("{}:12:1", Path::new("/work").join("src/main.rs").display());
+        assert_eq!(args, ["--goto", location.as_str()]);
+        assert_eq!(command.get_current_dir(), Some(Path::new("/work")));
+
+        assert!(open_command(&target, Some("  "), None).is_none());
+    }
+
+    #[test]
+    fn urls_go_to_the_system_opener_unchanged() {
+        let url = "https://example.com/search?q=a&b=c";
+        let command =
+            open_command(&HintTarget::Url(url.to_string()), Some("vim {file}"), None).unwrap();
+        assert_eq!(command.get_args().last(), Some(url.as_ref()));
+    }
+}
@@ -0,0 +1,186 @@
+// ABOUTME: Numeric keypad encoding: digits, SS3 application keypad sequences (DECKPAM)
+// ABOUTME: and the Kitty protocol's dedicated KP_* codepoints.
+
+use crt_terminal::TermMode;
+use winit::keyboard::{Key, ModifiersState, NamedKey};
+
+use crate::kitty_keyboard::{self, KeyEventType};
+
+/// Kitty protocol codepoint for a key on the numeric keypad. NumLock
+/// decides whether the digit keys arrive as digits or as navigation keys;
+/// either way they get their own KP_* code.
+fn kitty_codepoint(key: &Key) -> Option<u32> {
+    let codepoint = match key {
+        Key::Character(s) => match s.as_str() {
+            d @ ("0" | "1" | "2" | "3" | "4" | "5" | "6" | "7" | "8" | "9") => {
+                57399 + d.parse::<u32>().ok()?
+            }
+            "." => 57409,
+            "/" => 57410,
+            "*" => 57411,
+            "-" => 57412,
+            "+" => 57413,
+            "=" => 57415,
+            "," => 57416,
+            _ => return None,
+        },
+        Key::Named(named) => match named {
+            NamedKey::Enter => 57414,
+            NamedKey::ArrowLeft => 57417,
+            NamedKey::ArrowRight => 57418,
+            NamedKey::ArrowUp => 57419,
+            NamedKey::ArrowDown => 57420,
+            NamedKey::PageUp => 57421,
+            NamedKey::PageDown => 57422,
+            NamedKey::Home => 57423,
+            NamedKey::End => 57424,
+            NamedKey::Insert => 57425,
+            NamedKey::Delete => 57426,
+            // The middle key with NumLock off
+            NamedKey::Clear => 57427,
+            _ => return None,
+        },
+        _ => return None,
+    };
+    Some(codepoint)
+}
+
+/// Encode a keypad key for the Kitty protocol. Any enabled flag reports
+/// keypad keys with their own codes, so they can be told apart from the
+/// main keyboard.
+pub fn encode_kitty(
+    key: &Key,
+    modifiers: ModifiersState,
+    mode: TermMode,
+    event: KeyEventType,
+) -> Option<Vec<u8>> {
+    let event = match event {
+        _ if mode.contains(TermMode::REPORT_EVENT_TYPES) => event,
+        KeyEventType::Release => return None,
+        _ => KeyEventType::Press,
+    };
+    let codepoint = kitty_codepoint(key)?;
+    let mod_flags = kitty_keyboard::modifier_flags(modifiers);
+    Some(kitty_keyboard::csi_u(codepoint, mod_flags, event))
+}
+
+/// Encode a keypad key without the Kitty protocol. In application keypad
+/// mode (DECKPAM) digits, operators and Enter send SS3 sequences; otherwise
+/// they type their characters. Returns None for keys the main keyboard
+/// handling already covers, such as the arrows NumLock-off digits turn into,
+/// and for modified keys.
+pub fn encode_legacy(key: &Key, modifiers: ModifiersState, mode: TermMode) -> Option<Vec<u8>> {
+    if modifiers.control_key() || modifiers.alt_key() || modifiers.super_key() {
+        return None;
+    }
+    let app_keypad = mode.contains(TermMode::APP_KEYPAD);
+    let (text, ss3): (&[u8], u8) = match key {
+        Key::Character(s) => match s.as_str() {
+            "0" => (b"0", b'p'),
+            "1" => (b"1", b'q'),
+            "2" => (b"2", b'r'),
+            "3" => (b"3", b's'),
+            "4" => (b"4", b't'),
+            "5" => (b"5", b'u'),
+            "6" => (b"6", b'v'),
+            "7" => (b"7", b'w'),
+            "8" => (b"8", b'x'),
+            "9" => (b"9", b'y'),
+            "*" => (b"*", b'j'),
+            "+" => (b"+", b'k'),
+            "," => (b",", b'l'),
+            "-" => (b"-", b'm'),
+            "." => (b".", b'n'),
+            "/" => (b"/", b'o'),
+            "=" => (b"=", b'X'),
+            _ => return None,
+        },
+        Key::Named(NamedKey::Enter) => (b"\r", b'M'),
+        // Keypad 5 with NumLock off: xterm's KP_Begin
+        Key::Named(NamedKey::Clear) => {
+            let app_cursor = mode.contains(TermMode::APP_CURSOR);
+            return Some(if app_cursor { b"\x1bOE" } else { b"\x1b[E" }.to_vec());
+        }
+        _ => return None,
+    };
+    if app_keypad {
+        Some(vec![0x1b, b'O', ss3])
+    } else {
+        Some(text.to_vec())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn digit(d: &str) -> Key {
+        Key::Character(d.into())
+    }
+
+    #[test]
+    fn numeric_mode_types_characters() {
+        let none = ModifiersState::empty();
+        let mode = TermMode::empty();
+        assert_eq!(encode_legacy(&digit("7"), none, mode).unwrap(), b"7");
+        assert_eq!(encode_legacy(&digit("+"), none, mode).unwrap(), b"+");
+        let enter = Key::Named(NamedKey::Enter);
+        assert_eq!(encode_legacy(&enter, none, mode).unwrap(), b"\r");
+    }
+
+    #[test]
+    fn application_keypad_sends_ss3() {
+        let none = ModifiersState::empty();
+        let mode = TermMode::APP_KEYPAD;
+        assert_eq!(encode_legacy(&digit("0"), none, mode).unwrap(), b"\x1bOp");
+        assert_eq!(encode_legacy(&digit("9"), none, mode).unwrap(), b"\x1bOy");
+        assert_eq!(encode_legacy(&digit("-"), none, mode).unwrap(), b"\x1bOm");
+        let enter = Key::Named(NamedKey::Enter);
+        assert_eq!(encode_legacy(&enter, none, mode).unwrap(), b"\x1bOM");
+    }
+
+    #[test]
+    fn numlock_off_keys_navigate() {
+        let none = ModifiersState::empty();
+        // Keypad 8 with NumLock off is an arrow, handled like the main arrows
+        let up = Key::Named(NamedKey::ArrowUp);
+        assert_eq!(encode_legacy(&up, none, TermMode::APP_KEYPAD), None);
+        // Keypad 5 has no main keyboard twin
+        let begin = Key::Named(NamedKey::Clear);
+        assert_eq!(
+            encode_legacy(&begin, none, TermMode::empty()).unwrap(),
+            b"\x1b[E"
+        );
+        assert_eq!(
+            encode_legacy(&begin, none, TermMode::APP_CURSOR).unwrap(),
+            b"\x1bOE"
+        );
+    }
+
+    #[test]
+    fn kitty_uses_keypad_codepoints_with_numlock_on_or_off() {
+        let none = ModifiersState::empty();
+        let mode = TermMode::DISAMBIGUATE_ESC_CODES;
+        let press = KeyEventType::Press;
+        // NumLock on: keypad 8 is a digit
+        assert_eq!(
+            encode_kitty(&digit("8"), none, mode, press).unwrap(),
+            b"\x1b[57407;1u"
+        );
+        // NumLock off: the same key is KP_UP
+        let up = Key::Named(NamedKey::ArrowUp);
+        assert_eq!(
+            encode_kitty(&up, none, mode, press).unwrap(),
+            b"\x1b[57419;1u"
+        );
+        let enter = Key::Named(NamedKey::Enter);
+        assert_eq!(
+            encode_kitty(&enter, ModifiersState::SHIFT, mode, press).unwrap(),
+            b"\x1b[57414;2u"
+        );
+        assert_eq!(
+            encode_kitty(&digit("8"), none, mode, KeyEventType::Release),
+            None
+        );
+    }
+}
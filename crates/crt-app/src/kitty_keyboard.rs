@@ -0,0 +1,338 @@
+// ABOUTME: Kitty keyboard protocol encoder for key press, repeat and release events.
+// ABOUTME: Falls back to legacy sequences where the spec (or crossterm) expects them.
+
+use crt_terminal::TermMode;
+use winit::keyboard::{Key, ModifiersState, NamedKey};
+
+/// Kind of key event, numbered as in the protocol's event type field
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeyEventType {
+    Press = 1,
+    Repeat = 2,
+    Release = 3,
+}
+
+/// Encode a key event in Kitty keyboard protocol format.
+/// Returns None if the key shouldn't be sent (e.g., modifier-only keys, or
+/// releases when the application didn't ask for event types).
+pub fn encode(
+    key: &Key,
+    modifiers: ModifiersState,
+    mode: TermMode,
+    event: KeyEventType,
+) -> Option<Vec<u8>> {
+    // Without REPORT_EVENT_TYPES repeats look like presses and releases are dropped
+    let event = match event {
+        _ if mode.contains(TermMode::REPORT_EVENT_TYPES) => event,
+        KeyEventType::Release => return None,
+        _ => KeyEventType::Press,
+    };
+
+    // Calculate modifier parameter: (flags + 1) where flags = shift*1 + alt*2 + ctrl*4 + super*8
+    let mod_flags = modifier_flags(modifiers);
+    let report_all = mode.contains(TermMode::REPORT_ALL_KEYS_AS_ESC);
+    let app_cursor = mode.contains(TermMode::APP_CURSOR);
+
+    match key {
+        Key::Character(s) => {
+            let c = s.chars().next()?;
+            // Keys that only add shift still type text, except for releases
+            let types_text = mod_flags & !SHIFT == 0 && !report_all;
+            if types_text && event != KeyEventType::Release {
+                Some(s.as_bytes().to_vec())
+            } else {
+                // The key code is the unshifted key
+                let codepoint = c.to_lowercase().next().unwrap_or(c) as u32;
+                Some(csi_u(codepoint, mod_flags, event))
+            }
+        }
+        Key::Named(named) => {
+            encode_named_key(named, mod_flags, report_all, app_cursor, mode, event)
+        }
+        _ => None,
+    }
+}
+
+const SHIFT: u8 = 1;
+
+pub(crate) fn modifier_flags(modifiers: ModifiersState) -> u8 {
+    let mut flags = 0u8;
+    if modifiers.shift_key() {
+        flags |= SHIFT;
+    }
+    if modifiers.alt_key() {
+        flags |= 2;
+    }
+    if modifiers.control_key() {
+        flags |= 4;
+    }
+    if modifiers.super_key() {
+        flags |= 8;
+    }
+    flags
+}
+
+/// Modifier parameter, with the event type appended unless it's a press
+fn modifier_param(mod_flags: u8, event: KeyEventType) -> String {
+    match event {
+        KeyEventType::Press => format!("{}", mod_flags + 1),
+        _ => format!("{}:{}", mod_flags + 1, event as u8),
+    }
+}
+
+/// CSI codepoint ; modifiers u
+pub(crate) fn csi_u(codepoint: u32, mod_flags: u8, event: KeyEventType) -> Vec<u8> {
+    format!("\x1b[{};{}u", codepoint, modifier_param(mod_flags, event)).into_bytes()
+}
+
+fn encode_named_key(
+    named: &NamedKey,
+    mod_flags: u8,
+    report_all: bool,
+    app_cursor: bool,
+    mode: TermMode,
+    event: KeyEventType,
+) -> Option<Vec<u8>> {
+    // Kitty protocol functional key codepoints and legacy suffixes
+    // For cursor keys: suffix is the letter (A/B/C/D), ss3_key indicates if it can use SS3 format
+    let (codepoint, legacy_suffix, is_cursor_key): (Option<u32>, Option<&[u8]>, bool) = match named
+    {
+        NamedKey::Enter => (Some(13), None, false),
+        NamedKey::Tab => (Some(9), None, false),
+        NamedKey::Backspace => (Some(127), None, false),
+        NamedKey::Escape => (Some(27), None, false),
+        NamedKey::Space => (Some(32), None, false),
+        NamedKey::Delete => (Some(57423), Some(b"3~"), false),
+        NamedKey::Insert => (Some(57425), Some(b"2~"), false),
+        NamedKey::Home => (Some(57419), Some(b"H"), true),
+        NamedKey::End => (Some(57420), Some(b"F"), true),
+        NamedKey::PageUp => (Some(57421), Some(b"5~"), false),
+        NamedKey::PageDown => (Some(57422), Some(b"6~"), false),
+        NamedKey::ArrowUp => (Some(57352), Some(b"A"), true),
+        NamedKey::ArrowDown => (Some(57353), Some(b"B"), true),
+        NamedKey::ArrowRight => (Some(57354), Some(b"C"), true),
+        NamedKey::ArrowLeft => (Some(57351), Some(b"D"), true),
+        NamedKey::F1 => (Some(57364), Some(b"P"), true),
+        NamedKey::F2 => (Some(57365), Some(b"Q"), true),
+        NamedKey::F3 => (Some(57366), Some(b"R"), true),
+        NamedKey::F4 => (Some(57367), Some(b"S"), true),
+        NamedKey::F5 => (Some(57368), Some(b"15~"), false),
+        NamedKey::F6 => (Some(57369), Some(b"17~"), false),
+        NamedKey::F7 => (Some(57370), Some(b"18~"), false),
+        NamedKey::F8 => (Some(57371), Some(b"19~"), false),
+        NamedKey::F9 => (Some(57372), Some(b"20~"), false),
+        NamedKey::F10 => (Some(57373), Some(b"21~"), false),
+        NamedKey::F11 => (Some(57374), Some(b"23~"), false),
+        NamedKey::F12 => (Some(57375), Some(b"24~"), false),
+        NamedKey::F13 => (Some(57376), None, false),
+        NamedKey::F14 => (Some(57377), None, false),
+        NamedKey::F15 => (Some(57378), None, false),
+        NamedKey::F16 => (Some(57379), None, false),
+        NamedKey::F17 => (Some(57380), None, false),
+        NamedKey::F18 => (Some(57381), None, false),
+        NamedKey::F19 => (Some(57382), None, false),
+        NamedKey::F20 => (Some(57383), None, false),
+        NamedKey::F21 => (Some(57384), None, false),
+        NamedKey::F22 => (Some(57385), None, false),
+        NamedKey::F23 => (Some(57386), None, false),
+        NamedKey::F24 => (Some(57387), None, false),
+        NamedKey::F25 => (Some(57388), None, false),
+        NamedKey::F26 => (Some(57389), None, false),
+        NamedKey::F27 => (Some(57390), None, false),
+        NamedKey::F28 => (Some(57391), None, false),
+        NamedKey::F29 => (Some(57392), None, false),
+        NamedKey::F30 => (Some(57393), None, false),
+        NamedKey::F31 => (Some(57394), None, false),
+        NamedKey::F32 => (Some(57395), None, false),
+        NamedKey::F33 => (Some(57396), None, false),
+        NamedKey::F34 => (Some(57397), None, false),
+        NamedKey::F35 => (Some(57398), None, false),
+        NamedKey::CapsLock => (Some(57358), None, false),
+        NamedKey::ScrollLock => (Some(57359), None, false),
+        NamedKey::NumLock => (Some(57360), None, false),
+        NamedKey::PrintScreen => (Some(57361), None, false),
+        NamedKey::Pause => (Some(57362), None, false),
+        NamedKey::ContextMenu => (Some(57363), None, false),
+        _ => (None, None, false),
+    };
+    let cp = codepoint?;
+
+    // Lock keys act like modifiers: only reported along with all other keys
+    let lock_key = matches!(
+        named,
+        NamedKey::CapsLock | NamedKey::ScrollLock | NamedKey::NumLock
+    );
+    if lock_key && !report_all {
+        return None;
+    }
+
+    // Unmodified Enter, Tab and Backspace keep their legacy bytes so a shell
+    // stays usable if a program dies without resetting the mode; their
+    // releases are only reported along with all other keys
+    let unmodified_legacy = mod_flags == 0 && !report_all;
+    if unmodified_legacy {
+        let legacy = match named {
+            NamedKey::Enter => Some(b'\r'),
+            NamedKey::Tab => Some(b'\t'),
+            NamedKey::Backspace => Some(0x7f),
+            _ => None,
+        };
+        if let Some(byte) = legacy {
+            return (event != KeyEventType::Release).then(|| vec![byte]);
+        }
+        // Space types text like any character key
+        if *named == NamedKey::Space && event != KeyEventType::Release {
+            return Some(vec![b' ']);
+        }
+    }
+
+    // Detect if the app is likely a proper Kitty protocol implementation or crossterm.
+    // Crossterm doesn't support REPORT_ASSOCIATED_TEXT, so if it's requested,
+    // the app is probably spec-compliant and expects proper CSI u codepoints.
+    // Otherwise, use legacy format for functional keys since crossterm doesn't
+    // correctly parse Kitty's functional key codepoints (57351-57354 for arrows).
+    let report_associated_text = mode.contains(TermMode::REPORT_ASSOCIATED_TEXT);
+    let is_functional_key = legacy_suffix.is_some();
+    let use_legacy_for_functional = is_functional_key && !report_associated_text;
+
+    if report_all && !use_legacy_for_functional {
+        // Full Kitty mode with spec-compliant app: use CSI u format
+        Some(csi_u(cp, mod_flags, event))
+    } else if mod_flags > 0 || event != KeyEventType::Press {
+        // Modifiers or an event type to report: legacy format with parameters
+        let param = modifier_param(mod_flags, event);
+        if let Some(suffix) = legacy_suffix {
+            let suffix_str = String::from_utf8_lossy(suffix);
+            if let Some(number) = suffix_str.strip_suffix('~') {
+                // For keys with ~ suffix: CSI number ; modifiers ~
+                Some(format!("\x1b[{};{}~", number, param).into_bytes())
+            } else {
+                // For single-letter suffix: CSI 1 ; modifiers letter
+                Some(format!("\x1b[1;{}{}", param, suffix_str).into_bytes())
+            }
+        } else {
+            // No legacy suffix (Enter, Tab, etc. with modifiers), use CSI u
+            Some(csi_u(cp, mod_flags, event))
+        }
+    } else {
+        // No modifiers: use legacy format for compatibility
+        match named {
+            NamedKey::Escape => Some(vec![0x1b]),
+            _ => {
+                // Keys without a legacy form (F13 and up, Menu...) always use CSI u
+                let Some(suffix) = legacy_suffix else {
+                    return Some(csi_u(cp, mod_flags, event));
+                };
+                // When APP_CURSOR (DECCKM) is set, cursor keys use SS3 format
+                let mut seq = if app_cursor && is_cursor_key && suffix.len() == 1 {
+                    vec![0x1b, b'O']
+                } else {
+                    vec![0x1b, b'[']
+                };
+                seq.extend_from_slice(suffix);
+                Some(seq)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event_types_mode() -> TermMode {
+        TermMode::DISAMBIGUATE_ESC_CODES | TermMode::REPORT_EVENT_TYPES
+    }
+
+    fn encode_str(key: Key, modifiers: ModifiersState, event: KeyEventType) -> Option<String> {
+        encode(&key, modifiers, event_types_mode(), event)
+            .map(|bytes| String::from_utf8(bytes).unwrap())
+    }
+
+    #[test]
+    fn plain_letter_types_text_and_reports_release() {
+        let a = || Key::Character("a".into());
+        let none = ModifiersState::empty();
+        assert_eq!(encode_str(a(), none, KeyEventType::Press).unwrap(), "a");
+        assert_eq!(encode_str(a(), none, KeyEventType::Repeat).unwrap(), "a");
+        assert_eq!(
+            encode_str(a(), none, KeyEventType::Release).unwrap(),
+            "\x1b[97;1:3u"
+        );
+    }
+
+    #[test]
+    fn shifted_letter_types_text_and_reports_unshifted_release() {
+        let shift_a = || Key::Character("A".into());
+        let shift = ModifiersState::SHIFT;
+        assert_eq!(
+            encode_str(shift_a(), shift, KeyEventType::Press).unwrap(),
+            "A"
+        );
+        assert_eq!(
+            encode_str(shift_a(), shift, KeyEventType::Repeat).unwrap(),
+            "A"
+        );
+        assert_eq!(
+            encode_str(shift_a(), shift, KeyEventType::Release).unwrap(),
+            "\x1b[97;2:3u"
+        );
+    }
+
+    #[test]
+    fn ctrl_left_reports_event_type() {
+        let left = || Key::Named(NamedKey::ArrowLeft);
+        let ctrl = ModifiersState::CONTROL;
+        assert_eq!(
+            encode_str(left(), ctrl, KeyEventType::Press).unwrap(),
+            "\x1b[1;5D"
+        );
+        assert_eq!(
+            encode_str(left(), ctrl, KeyEventType::Repeat).unwrap(),
+            "\x1b[1;5:2D"
+        );
+        assert_eq!(
+            encode_str(left(), ctrl, KeyEventType::Release).unwrap(),
+            "\x1b[1;5:3D"
+        );
+    }
+
+    #[test]
+    fn without_event_types_repeats_are_presses_and_releases_dropped() {
+        let mode = TermMode::DISAMBIGUATE_ESC_CODES;
+        let left = Key::Named(NamedKey::ArrowLeft);
+        let ctrl = ModifiersState::CONTROL;
+        assert_eq!(
+            encode(&left, ctrl, mode, KeyEventType::Repeat).unwrap(),
+            b"\x1b[1;5D"
+        );
+        assert_eq!(encode(&left, ctrl, mode, KeyEventType::Release), None);
+    }
+
+    #[test]
+    fn high_function_keys_use_csi_u() {
+        let none = ModifiersState::empty();
+        let press = KeyEventType::Press;
+        let f13 = Key::Named(NamedKey::F13);
+        assert_eq!(encode_str(f13, none, press).unwrap(), "\x1b[57376;1u");
+        let menu = Key::Named(NamedKey::ContextMenu);
+        assert_eq!(encode_str(menu, none, press).unwrap(), "\x1b[57363;1u");
+        let caps = Key::Named(NamedKey::CapsLock);
+        assert_eq!(encode_str(caps, none, press), None);
+    }
+
+    #[test]
+    fn enter_release_only_reported_with_all_keys() {
+        let enter = Key::Named(NamedKey::Enter);
+        let none = ModifiersState::empty();
+        assert_eq!(
+            encode(&enter, none, event_types_mode(), KeyEventType::Release),
+            None
+        );
+        let all = event_types_mode() | TermMode::REPORT_ALL_KEYS_AS_ESC;
+        assert_eq!(
+            encode(&enter, none, all, KeyEventType::Release).unwrap(),
+            b"\x1b[13;1:3u"
+        );
+    }
+}
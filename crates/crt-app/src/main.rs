@@ -1,27 +1,76 @@
 // ABOUTME: Main application entry point.
 // ABOUTME: Sets up window, event loop, and coordinates terminal/rendering.
 
+mod cell_colors;
+mod cell_pool;
+mod cli;
+mod command_palette;
 mod config_ui;
-
-use std::collections::HashMap;
+mod copy_mode;
+mod floating;
+mod function_keys;
+mod grid_text;
+mod hints;
+mod keypad;
+mod kitty_keyboard;
+mod modify_other_keys;
+mod monitor;
+mod mouse_reporting;
+mod pane_fit;
+mod paste;
+mod perf;
+mod rich_text;
+mod screenshot;
+mod search_bar;
+mod status_bar;
+
+use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 use std::time::{Duration, Instant};
 
-use anyhow::Result;
+use anyhow::{Context, Result};
 use arboard::Clipboard;
 use winit::application::ApplicationHandler;
-use winit::dpi::LogicalSize;
-use winit::event::{ElementState, MouseButton, MouseScrollDelta, WindowEvent};
-use winit::event_loop::{ActiveEventLoop, EventLoop};
-use winit::keyboard::{Key, ModifiersState, NamedKey};
-use winit::window::{Icon, Window, WindowAttributes, WindowId};
+use winit::dpi::PhysicalSize;
+use winit::event::{ElementState, Ime, MouseButton, MouseScrollDelta, WindowEvent};
+use winit::event_loop::{ActiveEventLoop, ControlFlow, EventLoop, EventLoopProxy};
+use winit::keyboard::{Key, KeyLocation, ModifiersState, NamedKey, PhysicalKey};
+use winit::window::{CursorIcon, Icon, UserAttentionType, Window, WindowAttributes, WindowId};
 
+use alacritty_terminal::term::cell::{Cell, Flags};
 use alacritty_terminal::vte::ansi::{Color as AnsiColor, NamedColor, Rgb as AnsiRgb};
+use cell_colors::Highlight;
+use cell_pool::RenderCellPool;
+use cli::{CliAction, CliArgs};
+use command_palette::{CommandPalette, PaletteAction};
 use config_ui::{ConfigAction, ConfigUI};
-use crt_core::{ColorScheme, Config, ScanlineMode, SessionData};
-use crt_layout::{LayoutTree, PaneId};
-use crt_renderer::{EffectParams, RenderCell, Renderer};
-use crt_terminal::{TermMode, Terminal};
+use copy_mode::{CopyCommand, CopyMode};
+use crt_core::{
+    Action, BdfFont, ColorScheme, Config, ConfigError, CopyFormat, KeyCombo, Keybindings,
+    Osc52Clipboard, ScanlineMode, SessionData,
+};
+use crt_layout::{Direction, LayoutTree, PaneId};
+use crt_renderer::{
+    burn_in_fade_time, AtlasCompressionMode, EffectParams, GlyphStyle, ImageStrip, RenderCell,
+    RenderError, Renderer,
+};
+use crt_terminal::sixel::SixelImage;
+use crt_terminal::{
+    ClipboardRequest, ClipboardType, CursorShape, CursorStyle, Osc52, RegexSearch, SessionRecorder,
+    ShellCommand, TermMode, Terminal,
+};
+use hints::{HintInput, HintMode};
+use kitty_keyboard::KeyEventType;
+use monitor::{MonitorMode, PaneMonitor};
+use mouse_reporting::MouseEventKind;
+use pane_fit::PaneFit;
+use paste::PendingPaste;
+use perf::{PerfSnapshot, PerfStats};
+use rich_text::StyledChar;
+use search_bar::{MatchSpan, SearchBar};
+use status_bar::StatusBar;
 
 /// Convert an ANSI color from alacritty_terminal to our [f32; 4] format
 fn ansi_color_to_rgba(color: AnsiColor, scheme: &ColorScheme, is_dim: bool) -> [f32; 4] {
@@ -47,17 +96,17 @@ fn ansi_color_to_rgba(color: AnsiColor, scheme: &ColorScheme, is_dim: bool) -> [
                 NamedColor::BrightCyan => scheme.colors[14],
                 NamedColor::BrightWhite => scheme.colors[15],
                 // Dim colors - use the base color at 60%
-                NamedColor::DimBlack => dim_color(scheme.colors[0]),
-                NamedColor::DimRed => dim_color(scheme.colors[1]),
-                NamedColor::DimGreen => dim_color(scheme.colors[2]),
-                NamedColor::DimYellow => dim_color(scheme.colors[3]),
-                NamedColor::DimBlue => dim_color(scheme.colors[4]),
-                NamedColor::DimMagenta => dim_color(scheme.colors[5]),
-                NamedColor::DimCyan => dim_color(scheme.colors[6]),
-                NamedColor::DimWhite => dim_color(scheme.colors[7]),
+                NamedColor::DimBlack => ColorScheme::dim(scheme.colors[0]),
+                NamedColor::DimRed => ColorScheme::dim(scheme.colors[1]),
+                NamedColor::DimGreen => ColorScheme::dim(scheme.colors[2]),
+                NamedColor::DimYellow => ColorScheme::dim(scheme.colors[3]),
+                NamedColor::DimBlue => ColorScheme::dim(scheme.colors[4]),
+                NamedColor::DimMagenta => ColorScheme::dim(scheme.colors[5]),
+                NamedColor::DimCyan => ColorScheme::dim(scheme.colors[6]),
+                NamedColor::DimWhite => ColorScheme::dim(scheme.colors[7]),
                 // Special colors
                 NamedColor::Foreground | NamedColor::BrightForeground => scheme.foreground,
-                NamedColor::DimForeground => dim_color(scheme.foreground),
+                NamedColor::DimForeground => ColorScheme::dim(scheme.foreground),
                 NamedColor::Background => scheme.background,
                 NamedColor::Cursor => scheme.foreground, // Use foreground for cursor
             }
@@ -70,182 +119,303 @@ fn ansi_color_to_rgba(color: AnsiColor, scheme: &ColorScheme, is_dim: bool) -> [
     };
 
     if is_dim {
-        dim_color(base)
+        ColorScheme::dim(base)
     } else {
         base
     }
 }
 
-/// Apply dim effect to a color (60% brightness)
-fn dim_color(color: [f32; 4]) -> [f32; 4] {
-    [color[0] * 0.6, color[1] * 0.6, color[2] * 0.6, color[3]]
-}
+/// A cell's own foreground and background, after bold-is-bright and dim
+/// but before inverse video. The background is transparent when it's the default.
+fn cell_base_colors(
+    cell: &Cell,
+    scheme: &ColorScheme,
+    bold_is_bright: bool,
+) -> ([f32; 4], [f32; 4]) {
+    let fg_color = if cell.flags.contains(Flags::BOLD) && bold_is_bright {
+        bright_variant(cell.fg)
+    } else {
+        cell.fg
+    };
+    let fg = ansi_color_to_rgba(fg_color, scheme, cell.flags.contains(Flags::DIM));
 
-/// Kitty keyboard protocol encoder
-mod kitty_keyboard {
-    use winit::keyboard::{Key, ModifiersState, NamedKey};
+    // Check if cell has an explicit background (not the default Background)
+    let has_explicit_bg = !matches!(cell.bg, AnsiColor::Named(NamedColor::Background));
+    let bg = if has_explicit_bg {
+        ansi_color_to_rgba(cell.bg, scheme, false)
+    } else {
+        [0.0, 0.0, 0.0, 0.0] // Transparent for default background
+    };
+    (fg, bg)
+}
 
-    /// Encode a key event in Kitty keyboard protocol format.
-    /// Returns None if the key shouldn't be sent (e.g., modifier-only keys).
-    pub fn encode(key: &Key, modifiers: ModifiersState, mode: crate::TermMode) -> Option<Vec<u8>> {
-        // Calculate modifier parameter: (flags + 1) where flags = shift*1 + alt*2 + ctrl*4 + super*8
-        let mod_flags = modifier_flags(modifiers);
-        let report_all = mode.contains(crate::TermMode::REPORT_ALL_KEYS_AS_ESC);
-        let app_cursor = mode.contains(crate::TermMode::APP_CURSOR);
+/// Map colors 0-7 to their bright counterparts 8-15 (for bold text)
+fn bright_variant(color: AnsiColor) -> AnsiColor {
+    match color {
+        AnsiColor::Named(named) if (named as usize) < 8 => AnsiColor::Named(named.to_bright()),
+        AnsiColor::Indexed(idx) if idx < 8 => AnsiColor::Indexed(idx + 8),
+        other => other,
+    }
+}
 
-        match key {
-            Key::Character(s) => {
-                if let Some(c) = s.chars().next() {
-                    // For single characters, use CSI codepoint ; modifiers u
-                    let codepoint = c as u32;
-
-                    if mod_flags > 0 || report_all {
-                        // With modifiers: CSI codepoint ; modifiers u
-                        Some(format!("\x1b[{};{}u", codepoint, mod_flags + 1).into_bytes())
-                    } else {
-                        // No modifiers and not reporting all: just send the character
-                        Some(s.as_bytes().to_vec())
-                    }
-                } else {
-                    None
-                }
-            }
-            Key::Named(named) => encode_named_key(named, mod_flags, report_all, app_cursor, mode),
-            _ => None,
+/// Append line segments that draw a non-block cursor shape.
+/// `cell` is (x, y, width, height) of the cursor cell in pixels.
+#[allow(clippy::type_complexity)]
+fn push_cursor_lines(
+    lines: &mut Vec<(f32, f32, f32, f32, f32, [f32; 4])>,
+    shape: CursorShape,
+    cell: (f32, f32, f32, f32),
+    color: [f32; 4],
+) {
+    const THICKNESS: f32 = 2.0;
+    let (x, y, w, h) = cell;
+    match shape {
+        CursorShape::Underline => {
+            let line_y = y + h - THICKNESS / 2.0;
+            lines.push((x, line_y, x + w, line_y, THICKNESS, color));
+        }
+        CursorShape::Beam => {
+            let line_x = x + THICKNESS / 2.0;
+            lines.push((line_x, y, line_x, y + h, THICKNESS, color));
         }
+        CursorShape::HollowBlock => {
+            let half = 0.5;
+            lines.push((x, y + half, x + w, y + half, 1.0, color));
+            lines.push((x, y + h - half, x + w, y + h - half, 1.0, color));
+            lines.push((x + half, y, x + half, y + h, 1.0, color));
+            lines.push((x + w - half, y, x + w - half, y + h, 1.0, color));
+        }
+        CursorShape::Block | CursorShape::Hidden => {}
     }
+}
 
-    fn modifier_flags(modifiers: ModifiersState) -> u8 {
-        let mut flags = 0u8;
-        if modifiers.shift_key() {
-            flags |= 1;
+/// Store text in the X11/Wayland primary selection (no-op elsewhere)
+fn set_primary_selection(clipboard: &mut Clipboard, text: &str) {
+    #[cfg(all(
+        unix,
+        not(any(target_os = "macos", target_os = "android", target_os = "emscripten"))
+    ))]
+    {
+        use arboard::{LinuxClipboardKind, SetExtLinux};
+        if let Err(e) = clipboard
+            .set()
+            .clipboard(LinuxClipboardKind::Primary)
+            .text(text)
+        {
+            tracing::warn!("Failed to set primary selection: {}", e);
         }
-        if modifiers.alt_key() {
-            flags |= 2;
+    }
+    #[cfg(not(all(
+        unix,
+        not(any(target_os = "macos", target_os = "android", target_os = "emscripten"))
+    )))]
+    let _ = (clipboard, text);
+}
+
+/// Read the X11/Wayland primary selection (empty elsewhere)
+fn get_primary_selection(clipboard: &mut Clipboard) -> String {
+    #[cfg(all(
+        unix,
+        not(any(target_os = "macos", target_os = "android", target_os = "emscripten"))
+    ))]
+    {
+        use arboard::{GetExtLinux, LinuxClipboardKind};
+        clipboard
+            .get()
+            .clipboard(LinuxClipboardKind::Primary)
+            .text()
+            .unwrap_or_default()
+    }
+    #[cfg(not(all(
+        unix,
+        not(any(target_os = "macos", target_os = "android", target_os = "emscripten"))
+    )))]
+    {
+        let _ = clipboard;
+        String::new()
+    }
+}
+
+/// Draw IME preedit text into a row of cells starting at `col`.
+/// Uses the scheme's bright black/white pair so it stands apart from both
+/// regular text and the inverted block cursor.
+fn overlay_preedit(row: &mut [RenderCell], col: usize, preedit: &str, scheme: &ColorScheme) {
+    overlay_text(row, col, preedit, scheme.colors[15], scheme.colors[8]);
+}
+
+/// Font size in physical pixels of a pane set to its own size in
+/// `per_pane_font_size`, or None when it uses the main font. BDF fonts only
+/// come in their native size.
+fn pane_font_size(
+    config: &Config,
+    layout: &LayoutTree,
+    pane_id: PaneId,
+    display_scale: f32,
+) -> Option<f32> {
+    if config.bdf_font.is_some() {
+        return None;
+    }
+    let number = layout.panes().iter().position(|&id| id == pane_id)? + 1;
+    let size = config.font_size_for_pane(number);
+    (size != config.font_size).then(|| size * config.effective_scale(display_scale))
+}
+
+/// Every font size other than the main one that a pane uses
+fn pane_font_sizes(config: &Config, layout: &LayoutTree, display_scale: f32) -> Vec<f32> {
+    layout
+        .panes()
+        .iter()
+        .filter_map(|&pane_id| pane_font_size(config, layout, pane_id, display_scale))
+        .collect()
+}
+
+/// Use the config's custom font file, if any. Returns a warning to show
+/// when it can't be loaded and the bundled font stands in for it.
+fn apply_font_path(renderer: &mut Renderer, config: &Config) -> Option<String> {
+    let e = renderer.set_font_path(config.font_path.as_deref()).err()?;
+    tracing::error!("{}", e);
+    Some(format!("{} - using {}", e, config.font.label()))
+}
+
+/// Use the config's BDF font file, if any, in place of the bundled BDF font.
+/// Returns a warning to show when it can't be loaded.
+fn apply_bdf_font_path(
+    renderer: &mut Renderer,
+    config: &Config,
+    bdf_font: BdfFont,
+) -> Option<String> {
+    let e = renderer
+        .set_bdf_font_path(config.bdf_font_path.as_deref())
+        .err()?;
+    tracing::error!("{}", e);
+    Some(format!("{} - using {}", e, bdf_font.label()))
+}
+
+/// Load the saved config's font, scale, spacing and pane borders, undoing
+/// a settings preview. BDF fonts take priority over TTF fonts. Returns a warning if
+/// the custom font file can't be loaded.
+fn apply_saved_font(
+    renderer: &mut Renderer,
+    config: &Config,
+    layout: &LayoutTree,
+    display_scale: f32,
+) -> Option<String> {
+    let warning;
+    if let Some(bdf_font) = config.bdf_font {
+        warning = apply_bdf_font_path(renderer, config, bdf_font);
+        if let Err(e) = renderer.set_bdf_font(bdf_font) {
+            tracing::error!("Failed to restore BDF font: {}", e);
         }
-        if modifiers.control_key() {
-            flags |= 4;
+    } else {
+        warning = apply_font_path(renderer, config);
+        if let Err(e) = renderer.set_font(
+            config.font,
+            config.font_size * config.effective_scale(display_scale),
+        ) {
+            tracing::error!("Failed to restore font: {}", e);
         }
-        if modifiers.super_key() {
-            flags |= 8;
+        let sizes = pane_font_sizes(config, layout, display_scale);
+        if let Err(e) = renderer.set_pane_font_sizes(&sizes) {
+            tracing::error!("Failed to load pane font sizes: {}", e);
         }
-        flags
     }
+    renderer.set_scale_factor(config.effective_scale(display_scale));
+    renderer.set_cell_spacing(config.line_height_mult, config.letter_spacing);
+    renderer.set_pane_border_style(config.pane_border_style);
+    warning
+}
 
-    fn encode_named_key(
-        named: &NamedKey,
-        mod_flags: u8,
-        report_all: bool,
-        app_cursor: bool,
-        mode: crate::TermMode,
-    ) -> Option<Vec<u8>> {
-        // Kitty protocol functional key codepoints and legacy suffixes
-        // For cursor keys: suffix is the letter (A/B/C/D), ss3_key indicates if it can use SS3 format
-        let (codepoint, legacy_suffix, is_cursor_key): (Option<u32>, Option<&[u8]>, bool) =
-            match named {
-                NamedKey::Enter => (Some(13), None, false),
-                NamedKey::Tab => (Some(9), None, false),
-                NamedKey::Backspace => (Some(127), None, false),
-                NamedKey::Escape => (Some(27), None, false),
-                NamedKey::Space => (Some(32), None, false),
-                NamedKey::Delete => (Some(57423), Some(b"3~"), false),
-                NamedKey::Insert => (Some(57425), Some(b"2~"), false),
-                NamedKey::Home => (Some(57419), Some(b"H"), true),
-                NamedKey::End => (Some(57420), Some(b"F"), true),
-                NamedKey::PageUp => (Some(57421), Some(b"5~"), false),
-                NamedKey::PageDown => (Some(57422), Some(b"6~"), false),
-                NamedKey::ArrowUp => (Some(57352), Some(b"A"), true),
-                NamedKey::ArrowDown => (Some(57353), Some(b"B"), true),
-                NamedKey::ArrowRight => (Some(57354), Some(b"C"), true),
-                NamedKey::ArrowLeft => (Some(57351), Some(b"D"), true),
-                NamedKey::F1 => (Some(57364), Some(b"P"), true),
-                NamedKey::F2 => (Some(57365), Some(b"Q"), true),
-                NamedKey::F3 => (Some(57366), Some(b"R"), true),
-                NamedKey::F4 => (Some(57367), Some(b"S"), true),
-                NamedKey::F5 => (Some(57368), Some(b"15~"), false),
-                NamedKey::F6 => (Some(57369), Some(b"17~"), false),
-                NamedKey::F7 => (Some(57370), Some(b"18~"), false),
-                NamedKey::F8 => (Some(57371), Some(b"19~"), false),
-                NamedKey::F9 => (Some(57372), Some(b"20~"), false),
-                NamedKey::F10 => (Some(57373), Some(b"21~"), false),
-                NamedKey::F11 => (Some(57374), Some(b"23~"), false),
-                NamedKey::F12 => (Some(57375), Some(b"24~"), false),
-                _ => (None, None, false),
-            };
+/// Search matches overlapping the `rows` buffer lines from `top` down,
+/// flagging the current one
+fn search_matches_on_screen(
+    search_bar: &SearchBar,
+    top: i32,
+    rows: usize,
+) -> Vec<(MatchSpan, bool)> {
+    let bottom = top + rows as i32 - 1;
+    let current = search_bar.current();
+    search_bar
+        .matches
+        .iter()
+        .filter(|span| span.end.1 >= top && span.start.1 <= bottom)
+        .map(|span| (*span, Some(*span) == current))
+        .collect()
+}
 
-        if let Some(cp) = codepoint {
-            // Detect if the app is likely a proper Kitty protocol implementation or crossterm.
-            // Crossterm doesn't support REPORT_ASSOCIATED_TEXT, so if it's requested,
-            // the app is probably spec-compliant and expects proper CSI u codepoints.
-            // Otherwise, use legacy format for functional keys since crossterm doesn't
-            // correctly parse Kitty's functional key codepoints (57351-57354 for arrows).
-            let report_associated_text = mode.contains(crate::TermMode::REPORT_ASSOCIATED_TEXT);
-            let is_functional_key = legacy_suffix.is_some();
-            let use_legacy_for_functional = is_functional_key && !report_associated_text;
-
-            if report_all && !use_legacy_for_functional {
-                // Full Kitty mode with spec-compliant app: use CSI u format
-                Some(format!("\x1b[{};{}u", cp, mod_flags + 1).into_bytes())
-            } else if mod_flags > 0 {
-                // Disambiguate mode with modifiers: use legacy format with modifiers
-                if let Some(suffix) = legacy_suffix {
-                    if suffix.ends_with(b"~") {
-                        // For keys with ~ suffix: CSI number ; modifiers ~
-                        let suffix_str = String::from_utf8_lossy(suffix);
-                        let number = suffix_str.trim_end_matches('~');
-                        Some(format!("\x1b[{};{}~", number, mod_flags + 1).into_bytes())
-                    } else {
-                        // For single-letter suffix: CSI 1 ; modifiers letter
-                        Some(
-                            format!(
-                                "\x1b[1;{}{}",
-                                mod_flags + 1,
-                                String::from_utf8_lossy(suffix)
-                            )
-                            .into_bytes(),
-                        )
-                    }
-                } else {
-                    // No legacy suffix (Enter, Tab, etc. with modifiers), use CSI u
-                    Some(format!("\x1b[{};{}u", cp, mod_flags + 1).into_bytes())
-                }
-            } else {
-                // No modifiers: use legacy format for compatibility
-                match named {
-                    NamedKey::Enter => Some(vec![b'\r']),
-                    NamedKey::Tab => Some(vec![b'\t']),
-                    NamedKey::Backspace => Some(vec![0x7f]),
-                    NamedKey::Escape => Some(vec![0x1b]),
-                    NamedKey::Space => Some(vec![b' ']),
-                    _ => {
-                        // Use legacy escape sequence
-                        if let Some(suffix) = legacy_suffix {
-                            // When APP_CURSOR (DECCKM) is set, cursor keys use SS3 format
-                            if app_cursor && is_cursor_key && suffix.len() == 1 {
-                                let mut seq = vec![0x1b, b'O'];
-                                seq.extend_from_slice(suffix);
-                                Some(seq)
-                            } else {
-                                let mut seq = vec![0x1b, b'['];
-                                seq.extend_from_slice(suffix);
-                                Some(seq)
-                            }
-                        } else {
-                            None
-                        }
-                    }
-                }
-            }
-        } else {
-            None
+/// Size in logical pixels and texture coordinates of row `image_row` of a
+/// sixel image, drawn so that `image_cell` pixels of it fill a `cell` and
+/// cut off after `max_width`. None past the bottom of the image.
+fn image_strip(
+    image: &SixelImage,
+    image_row: usize,
+    image_cell: (u32, u32),
+    cell: (f32, f32),
+    max_width: f32,
+) -> Option<(f32, f32, [f32; 4])> {
+    let top = image_row as u32 * image_cell.1;
+    if top >= image.height || max_width <= 0.0 {
+        return None;
+    }
+    let bottom = (top + image_cell.1).min(image.height);
+    let full_width = image.width as f32 * cell.0 / image_cell.0 as f32;
+    let width = full_width.min(max_width);
+    let height = (bottom - top) as f32 * cell.1 / image_cell.1 as f32;
+    let uv = [
+        0.0,
+        top as f32 / image.height as f32,
+        width / full_width,
+        bottom as f32 / image.height as f32,
+    ];
+    Some((width, height, uv))
+}
+
+/// Draw a hint label over the first cells of its match, in the background
+/// color on bright yellow so it reads against any text.
+fn overlay_hint_label(row: &mut [RenderCell], col: usize, label: &str, scheme: &ColorScheme) {
+    overlay_text(row, col, label, scheme.background, scheme.colors[11]);
+}
+
+fn overlay_text(row: &mut [RenderCell], col: usize, text: &str, fg: [f32; 4], bg: [f32; 4]) {
+    use unicode_width::UnicodeWidthChar;
+
+    let line_size = row.first().map(|cell| cell.line_size).unwrap_or_default();
+    let mut col = col;
+    for c in text.chars() {
+        let width = c.width().unwrap_or(0);
+        if width == 0 {
+            continue;
+        }
+        if col + width > row.len() {
+            break;
+        }
+        row[col] = RenderCell {
+            c,
+            fg,
+            bg,
+            is_wide: width == 2,
+            style: GlyphStyle::Regular,
+            line_size,
+        };
+        if width == 2 {
+            // Spacer cell: the wide glyph's background already covers it
+            row[col + 1] = RenderCell {
+                c: ' ',
+                fg: [0.0, 0.0, 0.0, 0.0],
+                bg: [0.0, 0.0, 0.0, 0.0],
+                is_wide: false,
+                style: GlyphStyle::Regular,
+                line_size,
+            };
         }
+        col += width;
     }
 }
 
-const PANE_PADDING: f32 = 8.0; // Pixels of padding around each pane's content
+/// Window title when the focused pane's program hasn't set one
+const WINDOW_TITLE: &str = "cool-rust-term";
 
 /// Buffer-relative cell position (row can be negative for scrollback history)
-#[derive(Clone, Copy, Debug, Default)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
 struct CellPos {
     col: usize,
     /// Buffer-relative row: 0 = first screen line when not scrolled,
@@ -253,6 +423,43 @@ struct CellPos {
     row: i32,
 }
 
+/// How a selection's start/end cells map to selected cells
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+enum SelectionMode {
+    /// Text flow: whole lines between start and end rows
+    #[default]
+    Linear,
+    /// Rectangle spanned by start and end (hold Alt while dragging)
+    Block,
+}
+
+/// Everything a pane's cell grid depends on; unchanged key = reusable cells
+#[derive(PartialEq)]
+struct PaneCellsKey {
+    generation: u64,
+    display_offset: usize,
+    disk_offset: usize,
+    size: (u16, u16),
+    is_focused: bool,
+    block_cursor: Option<(usize, usize)>,
+    selection: Option<(CellPos, CellPos, SelectionMode)>,
+    preedit: Option<String>,
+    /// Hint labels as (column, display line, untyped part of the label)
+    hints: Vec<(usize, usize, String)>,
+    /// Search matches on screen, with whether each is the current one
+    search: Vec<(MatchSpan, bool)>,
+    color_scheme: ColorScheme,
+    bold_is_bright: bool,
+}
+
+/// Cell grid built for a pane on an earlier frame
+struct CachedPaneCells {
+    key: PaneCellsKey,
+    cells: Vec<Vec<RenderCell>>,
+    /// Sixel image rows on screen: (column, screen row, image id, image row)
+    images: Vec<(usize, usize, u64, usize)>,
+}
+
 #[derive(Default)]
 struct Selection {
     start: CellPos,
@@ -261,6 +468,14 @@ struct Selection {
 }
 
 impl Selection {
+    /// Leftmost and rightmost selected columns for block selections
+    fn column_span(&self) -> (usize, usize) {
+        (
+            self.start.col.min(self.end.col),
+            self.start.col.max(self.end.col),
+        )
+    }
+
     fn normalized(&self) -> (CellPos, CellPos) {
         let (start_row, end_row, start_col, end_col) = if self.start.row < self.end.row
             || (self.start.row == self.end.row && self.start.col <= self.end.col)
@@ -281,33 +496,111 @@ impl Selection {
         )
     }
 
-    /// Check if a buffer-relative position is within the selection
-    fn contains(&self, col: usize, row: i32) -> bool {
+    /// First and last selected columns on a buffer-relative row, if any.
+    /// Works out the row once, so drawing doesn't test cell by cell.
+    fn row_span(&self, row: i32, mode: SelectionMode) -> Option<(usize, usize)> {
         // Never highlight a single cell (click without drag)
         if self.start.row == self.end.row && self.start.col == self.end.col {
-            return false;
+            return None;
+        }
+        if mode == SelectionMode::Block {
+            let (min_row, max_row) = (
+                self.start.row.min(self.end.row),
+                self.start.row.max(self.end.row),
+            );
+            return (row >= min_row && row <= max_row).then(|| self.column_span());
         }
         let (start, end) = self.normalized();
         if row < start.row || row > end.row {
-            return false;
+            return None;
         }
-        if start.row == end.row {
-            col >= start.col && col <= end.col
-        } else if row == start.row {
-            col >= start.col
-        } else if row == end.row {
-            col <= end.col
-        } else {
-            true
+        let first = if row == start.row { start.col } else { 0 };
+        let last = if row == end.row { end.col } else { usize::MAX };
+        Some((first, last))
+    }
+}
+
+/// Mouse wheel momentum for one pane. Each notch adds speed, which decays
+/// every frame, so the content glides instead of jumping three lines.
+struct SmoothScroll {
+    /// Lines per second; positive scrolls up into the history
+    velocity: f32,
+    /// Distance travelled that hasn't added up to a whole line yet
+    fractional_offset: f32,
+    last_step: Instant,
+}
+
+impl SmoothScroll {
+    fn new() -> Self {
+        Self {
+            velocity: 0.0,
+            fractional_offset: 0.0,
+            last_step: Instant::now(),
+        }
+    }
+
+    /// Add `notches` wheel notches at `speed` lines per second each.
+    /// Turning the wheel the other way cancels the current glide.
+    fn add_notches(&mut self, notches: f32, speed: f32) {
+        if self.velocity * notches < 0.0 {
+            self.velocity = 0.0;
+            self.fractional_offset = 0.0;
+        }
+        if self.velocity == 0.0 {
+            self.last_step = Instant::now();
         }
+        self.velocity = (self.velocity + notches * speed)
+            .clamp(-WHEEL_SCROLL_MAX_VELOCITY, WHEEL_SCROLL_MAX_VELOCITY);
+    }
+
+    /// Advance to `now` and return the whole lines to scroll. Once the
+    /// glide is slow enough it stops, finishing a line that is mostly done.
+    fn step(&mut self, now: Instant) -> i32 {
+        // A long gap between frames shouldn't turn into one big jump
+        let dt = now.duration_since(self.last_step).as_secs_f32().min(0.1);
+        self.last_step = now;
+        self.fractional_offset += self.velocity * dt;
+        self.velocity *= (1.0 - WHEEL_SCROLL_FRICTION).powf(dt * 60.0);
+        if self.velocity.abs() < WHEEL_SCROLL_MIN_VELOCITY {
+            self.velocity = 0.0;
+            self.fractional_offset = self.fractional_offset.round();
+        }
+        let lines = self.fractional_offset.trunc();
+        self.fractional_offset -= lines;
+        lines as i32
+    }
+
+    fn is_moving(&self) -> bool {
+        self.velocity != 0.0
     }
 }
 
 const RESIZE_INDICATOR_DURATION: Duration = Duration::from_millis(1000);
 const SCROLLBAR_FADE_DURATION: Duration = Duration::from_millis(1500);
 const SCROLLBAR_VISIBLE_DURATION: Duration = Duration::from_millis(800);
+/// Touchpad quiet time before a part-scrolled line settles
+const SMOOTH_SCROLL_IDLE: Duration = Duration::from_millis(120);
+/// Fraction of the leftover smooth-scroll offset kept each frame while settling
+const SMOOTH_SCROLL_SETTLE: f32 = 0.6;
+/// Share of the mouse wheel glide speed lost per 60 Hz frame
+const WHEEL_SCROLL_FRICTION: f32 = 0.15;
+/// Fastest mouse wheel glide, in lines per second
+const WHEEL_SCROLL_MAX_VELOCITY: f32 = 400.0;
+/// Mouse wheel glide speed below which it stops, in lines per second
+const WHEEL_SCROLL_MIN_VELOCITY: f32 = 2.0;
+/// Time between auto-scroll steps while a selection is dragged past a pane edge
+const SELECTION_AUTOSCROLL_INTERVAL: Duration = Duration::from_millis(16);
+/// Most lines one auto-scroll step moves, reached this many rows past the edge
+const SELECTION_AUTOSCROLL_MAX_LINES: i32 = 3;
 const DEFAULT_FPS: u32 = 60; // Fallback if we can't detect refresh rate
 const DOUBLE_CLICK_THRESHOLD: Duration = Duration::from_millis(500);
+const CURSOR_BLINK_INTERVAL: Duration = Duration::from_millis(530);
+const SPAWN_ERROR_DURATION: Duration = Duration::from_secs(6);
+const MONITOR_MESSAGE_DURATION: Duration = Duration::from_millis(1500);
+const TOAST_DURATION: Duration = Duration::from_secs(3);
+const BELL_FLASH_DURATION: Duration = Duration::from_millis(100);
+/// Time between attempts to recreate the renderer after losing the GPU
+const GPU_RECOVERY_INTERVAL: Duration = Duration::from_secs(1);
 
 // Startup hint timing (after power-on animation)
 const POWERON_DURATION: f32 = 1.05; // Must match shader's POWERON_TOTAL
@@ -315,26 +608,49 @@ const STARTUP_HINT_DELAY: f32 = POWERON_DURATION;
 const STARTUP_HINT_DURATION: f32 = 2.0;
 const STARTUP_HINT_FADE: f32 = 0.5;
 
+/// Events delivered to the winit event loop from other threads
+#[derive(Debug)]
+enum AppEvent {
+    /// A terminal parsed new PTY output or its shell exited
+    PtyOutput,
+}
+
 struct App {
     window: Option<Arc<Window>>,
+    event_proxy: EventLoopProxy<AppEvent>,
+    /// Set while a PtyOutput event is queued, so floods of output send only one
+    pty_wakeup_pending: Arc<AtomicBool>,
     renderer: Option<Renderer>,
+    /// Scale factor of the display the window is on (1.0 before it opens)
+    display_scale: f32,
     layout: LayoutTree,
     terminals: HashMap<PaneId, Terminal>,
     modifiers: ModifiersState,
     selection: Selection,
+    selection_mode: SelectionMode,
     mouse_pos: (f64, f64),
     clipboard: Option<Clipboard>,
+    /// Button whose press went to the focused pane's program, so its
+    /// release and drags go there too
+    mouse_report_button: Option<u8>,
+    /// Cell of the last reported mouse event, to report motion once per cell
+    last_mouse_report_cell: Option<(usize, usize)>,
     last_grid: Vec<Vec<char>>,
     last_resize: Option<Instant>,
     last_scroll: HashMap<PaneId, Instant>,
     last_frame: Instant,
     frame_duration: Duration,
+    /// One monitor refresh; frames are capped to this while unfocused
+    refresh_duration: Duration,
     fps_samples: [f32; 60],
     fps_sample_idx: usize,
     app_start: Instant,
     config: Config,
     config_ui: ConfigUI,
+    keybindings: Keybindings,
     debug_grid: bool,
+    /// Counters for the performance overlay; Some while it's shown
+    perf: Option<PerfStats>,
     beam_paused: bool,
     beam_step_held: bool,    // Is step key currently held
     beam_step_delay_ms: u32, // Delay between steps when holding (in ms)
@@ -342,39 +658,137 @@ struct App {
     last_click_time: Option<Instant>,
     last_click_pos: Option<CellPos>,
     click_count: u8,
-    /// Track Kitty keyboard protocol state per pane for change detection
-    kitty_mode_state: HashMap<PaneId, bool>,
-    /// When to show the Kitty protocol message (pane_id, start_time, enabled, crossterm_compat)
-    kitty_mode_message: Option<(PaneId, Instant, bool, bool)>,
+    /// Active Kitty keyboard protocol flags per pane, for change detection
+    kitty_mode_state: HashMap<PaneId, u8>,
+    /// When to show the Kitty protocol message (pane_id, start_time, flags, crossterm_compat)
+    kitty_mode_message: Option<(PaneId, Instant, u8, bool)>,
+    /// Panes whose configured shell failed to start (message, when)
+    spawn_errors: HashMap<PaneId, (String, Instant)>,
+    /// Panes kept open after their process exited, with its exit code
+    held_panes: HashMap<PaneId, Option<i32>>,
+    /// Activity/silence monitoring for panes that have it enabled
+    monitors: HashMap<PaneId, PaneMonitor>,
+    /// Monitoring mode just selected for a pane, shown briefly (pane, mode, when)
+    monitor_message: Option<(PaneId, MonitorMode, Instant)>,
+    /// Short window-wide notification, e.g. where a screenshot was saved
+    toast: Option<(String, Instant)>,
+    /// When recreating the renderer after losing the GPU last failed
+    gpu_lost: Option<Instant>,
+    /// Keyboard selection in the focused pane, if active
+    copy_mode: Option<CopyMode>,
+    /// Keys whose press was sent with the Kitty protocol, so their release can be too
+    kitty_pressed_keys: HashSet<PhysicalKey>,
+    /// When the visual bell last started flashing
+    bell_flash: Option<Instant>,
+    /// Asciicast recording of a pane's output, while one is running
+    recorder: Option<(PaneId, SessionRecorder)>,
+    /// Files dropped so far in the current drag, to space-separate them
+    dropped_files: usize,
+    /// Title last given to the window, to skip redundant set_title calls
+    window_title: String,
+    command_palette: CommandPalette,
+    /// Labels over URLs and file locations in a pane, while picking one
+    hint_mode: Option<HintMode>,
+    /// Find bar along the bottom of the window, while searching a pane
+    search_bar: Option<SearchBar>,
+    /// Focused pane's program and directory for the status bar
+    status_bar: StatusBar,
+    /// Paste held back until the user confirms it
+    pending_paste: Option<PendingPaste>,
+    /// Swap pane was pressed; the next arrow key picks the pane to swap with
+    swap_pending: bool,
+    /// Where a floating pane's title bar was grabbed, relative to the pane's
+    /// top left corner (normalized), while it's dragged
+    float_drag: Option<(f32, f32)>,
     /// Accumulator for pixel-based scroll deltas (touchpad)
     scroll_accumulator: f64,
+    /// Smooth scrolling: how far each pane's content is drawn below its
+    /// line-aligned position, in logical pixels (positive = partly scrolled up)
+    scroll_px_offset: HashMap<PaneId, f32>,
+    /// Mouse wheel glides still under way
+    wheel_scroll: HashMap<PaneId, SmoothScroll>,
+    /// Pane and selection start the terminal's scroll anchor was last set
+    /// for, to keep the selection on its text as output scrolls
+    selection_anchor: Option<(PaneId, CellPos)>,
+    /// Last auto-scroll step while a selection is dragged above or below
+    /// the focused pane; None when the pointer is inside it
+    selection_autoscroll: Option<Instant>,
+    /// In-progress IME composition text, drawn over the cursor until committed
+    ime_preedit: Option<String>,
+    /// Last cursor rect reported to the IME (x, y, width, height in pixels)
+    ime_cursor_area: Option<(u32, u32, u32, u32)>,
+    /// Blink phase origin; reset on input so the cursor is visible while typing
+    cursor_blink_reset: Instant,
+    /// Render cells from the previous frame, reused while the pane is unchanged
+    pane_cells: HashMap<PaneId, CachedPaneCells>,
+    /// Row storage recycled between cell grid rebuilds
+    cell_pool: RenderCellPool,
+    /// Whether the window has keyboard focus (for focus reporting)
+    window_focused: bool,
+    /// The window is covered by others or offscreen, per the compositor
+    occluded: bool,
+    /// The window is minimized (reported as a zero size)
+    minimized: bool,
+    /// Power save: a window event arrived that may change what is on screen
+    needs_redraw: bool,
+    /// Power save: blink phase of the last rendered frame
+    last_cursor_blink_on: bool,
+    /// Power save: whether a time-based effect was running at the last check
+    was_animating: bool,
+    /// Power save: keep rendering until then, while burn-in trails fade
+    burn_in_settle: Option<Instant>,
+    /// Config file given with --config; saves go here instead of the default path
+    config_path: Option<PathBuf>,
+    /// Command from -e to run in the first pane; consumed when it is created
+    initial_command: Option<ShellCommand>,
+    /// Working directory from --working-directory for the first pane
+    initial_working_directory: Option<PathBuf>,
+    /// Started with -e: don't restore or overwrite the saved session and pane count
+    one_off: bool,
 }
 
 impl App {
-    fn new() -> Self {
-        let config = Config::load_or_default();
+    fn new(event_proxy: EventLoopProxy<AppEvent>, config: Config, cli: CliArgs) -> Self {
         tracing::info!("Loaded config: per_pane_crt={}", config.per_pane_crt);
 
+        let (keybindings, errors) = Keybindings::from_config(&config.keybindings);
+        for e in errors {
+            tracing::warn!("Ignoring keybinding: {}", e);
+        }
+        let perf = (cli.perf || config.behavior.show_perf_overlay).then(PerfStats::new);
+        let mut layout = LayoutTree::new();
+        layout.set_min_pane_size(config.min_pane_cols, config.min_pane_rows);
+        layout.set_float_rect(floating::rect_from_config(&config.floating_pane));
+
         Self {
             window: None,
+            event_proxy,
+            pty_wakeup_pending: Arc::new(AtomicBool::new(false)),
             renderer: None,
-            layout: LayoutTree::new(),
+            layout,
             terminals: HashMap::new(),
             modifiers: ModifiersState::empty(),
             selection: Selection::default(),
+            selection_mode: SelectionMode::Linear,
+            display_scale: 1.0,
             mouse_pos: (0.0, 0.0),
             clipboard: Clipboard::new().ok(),
+            mouse_report_button: None,
+            last_mouse_report_cell: None,
             last_grid: Vec::new(),
             last_resize: None,
             last_scroll: HashMap::new(),
             last_frame: Instant::now(),
             frame_duration: Duration::from_nanos(1_000_000_000 / (DEFAULT_FPS * 2) as u64),
+            refresh_duration: Duration::from_nanos(1_000_000_000 / DEFAULT_FPS as u64),
             fps_samples: [0.0; 60],
             fps_sample_idx: 0,
             app_start: Instant::now(),
             config_ui: ConfigUI::new(config.clone()),
+            keybindings,
             config,
             debug_grid: false,
+            perf,
             beam_paused: false,
             beam_step_held: false,
             beam_step_delay_ms: 100, // Start at 100ms between steps
@@ -383,8 +797,51 @@ impl App {
             last_click_pos: None,
             kitty_mode_state: HashMap::new(),
             kitty_mode_message: None,
+            spawn_errors: HashMap::new(),
+            held_panes: HashMap::new(),
+            monitors: HashMap::new(),
+            monitor_message: None,
+            toast: None,
+            gpu_lost: None,
+            copy_mode: None,
+            kitty_pressed_keys: HashSet::new(),
+            bell_flash: None,
+            recorder: None,
+            dropped_files: 0,
+            window_title: WINDOW_TITLE.to_string(),
+            command_palette: CommandPalette::default(),
+            hint_mode: None,
+            search_bar: None,
+            status_bar: StatusBar::default(),
+            pending_paste: None,
+            swap_pending: false,
+            float_drag: None,
             click_count: 0,
             scroll_accumulator: 0.0,
+            scroll_px_offset: HashMap::new(),
+            wheel_scroll: HashMap::new(),
+            selection_anchor: None,
+            selection_autoscroll: None,
+            ime_preedit: None,
+            ime_cursor_area: None,
+            cursor_blink_reset: Instant::now(),
+            pane_cells: HashMap::new(),
+            cell_pool: RenderCellPool::new(),
+            window_focused: true,
+            occluded: false,
+            minimized: false,
+            needs_redraw: true,
+            last_cursor_blink_on: true,
+            was_animating: false,
+            burn_in_settle: None,
+            config_path: cli.config_path,
+            one_off: cli.command.is_some(),
+            initial_command: cli.command.map(|mut command| ShellCommand {
+                program: Some(command.remove(0)),
+                args: command,
+                login: false,
+            }),
+            initial_working_directory: cli.working_directory,
         }
     }
 
@@ -402,6 +859,222 @@ impl App {
         }
     }
 
+    /// Whether anything on screen changed or is animating since the last
+    /// rendered frame. Always true unless power save is enabled.
+    fn frame_needed(&mut self) -> bool {
+        if !self.config.behavior.power_save {
+            return true;
+        }
+
+        let mut changed = std::mem::take(&mut self.needs_redraw);
+        // Drain damage from every pane so stale damage doesn't trigger later
+        for terminal in self.terminals.values() {
+            changed |= terminal.take_damage();
+        }
+
+        let focused_blinking = self
+            .terminals
+            .get(&self.layout.focused_pane())
+            .is_some_and(|terminal| terminal.cursor_style().blinking);
+        if focused_blinking {
+            let blink_on = (self.cursor_blink_reset.elapsed().as_millis()
+                / CURSOR_BLINK_INTERVAL.as_millis())
+            .is_multiple_of(2);
+            changed |= blink_on != self.last_cursor_blink_on;
+            self.last_cursor_blink_on = blink_on;
+        }
+
+        // One last frame when an animation ends, so its final state is shown
+        let animating = self.animation_active();
+        changed |= self.was_animating && !animating;
+        self.was_animating = animating;
+
+        let effects = &self.config.effects;
+        if changed && effects.enabled && effects.burn_in > 0.0 {
+            self.burn_in_settle = Some(Instant::now() + burn_in_fade_time(effects.burn_in));
+        }
+        if self.burn_in_settling() {
+            return true;
+        }
+
+        changed || animating
+    }
+
+    /// Shortest time between frames: twice the refresh rate while focused,
+    /// the refresh rate otherwise
+    fn frame_interval(&self) -> Duration {
+        if self.window_focused {
+            self.frame_duration
+        } else {
+            self.refresh_duration.max(self.frame_duration)
+        }
+    }
+
+    /// Whether nothing of the window can be seen, so frames are skipped
+    fn hidden(&self) -> bool {
+        self.occluded || self.minimized
+    }
+
+    /// Come back from being hidden with a fresh frame. The frame clock
+    /// restarts so the time spent hidden doesn't count as one long frame.
+    fn set_hidden(&mut self, occluded: bool, minimized: bool) {
+        let was_hidden = self.hidden();
+        self.occluded = occluded;
+        self.minimized = minimized;
+        if was_hidden && !self.hidden() {
+            tracing::debug!("Window visible again, resuming rendering");
+            self.last_frame = Instant::now() - self.frame_interval();
+            self.needs_redraw = true;
+            if let Some(window) = &self.window {
+                window.request_redraw();
+            }
+        } else if !was_hidden && self.hidden() {
+            tracing::debug!("Window hidden, pausing rendering");
+        }
+    }
+
+    /// When the next frame should be drawn, or None to sleep until an event
+    /// arrives. Frames are capped at `frame_interval` apart.
+    fn next_frame_deadline(&self) -> Option<Instant> {
+        if self.hidden() {
+            return None;
+        }
+        let next_frame = self.last_frame + self.frame_interval();
+        if let Some(failed) = self.gpu_lost {
+            return Some((failed + GPU_RECOVERY_INTERVAL).max(next_frame));
+        }
+        if !self.config.behavior.power_save
+            || self.needs_redraw
+            || self.was_animating
+            || self.burn_in_settling()
+            || self.animation_active()
+        {
+            return Some(next_frame);
+        }
+
+        // A blinking cursor only needs a frame when its phase flips
+        let focused_blinking = self
+            .terminals
+            .get(&self.layout.focused_pane())
+            .is_some_and(|terminal| terminal.cursor_style().blinking);
+        if focused_blinking {
+            let interval = CURSOR_BLINK_INTERVAL.as_millis();
+            let phases = self.cursor_blink_reset.elapsed().as_millis() / interval + 1;
+            let next_blink =
+                self.cursor_blink_reset + Duration::from_millis((phases * interval) as u64);
+            return Some(next_blink.max(next_frame));
+        }
+
+        // Wake up in time to flag a silent pane, or to catch the status bar
+        // up with output it held back on
+        let silence = Duration::from_secs(self.config.behavior.silence_timeout_secs);
+        let status_refresh = self
+            .config
+            .behavior
+            .status_bar
+            .then(|| {
+                let focused = self.layout.focused_pane();
+                let terminal = self.terminals.get(&focused)?;
+                self.status_bar.next_refresh(focused, terminal.generation())
+            })
+            .flatten();
+        self.monitors
+            .values()
+            .filter_map(|monitor| monitor.silence_deadline(silence))
+            .chain(status_refresh)
+            .min()
+            .map(|deadline| deadline.max(next_frame))
+    }
+
+    /// Recreate the renderer on a new GPU device after the old one was lost
+    /// (driver reset, resume from sleep), at most once a second
+    fn recover_renderer(&mut self) {
+        if self
+            .gpu_lost
+            .is_some_and(|failed| failed.elapsed() < GPU_RECOVERY_INTERVAL)
+        {
+            return;
+        }
+        let Some(renderer) = &mut self.renderer else {
+            return;
+        };
+        tracing::warn!("GPU device lost, recreating the renderer");
+        match pollster::block_on(renderer.recover()) {
+            Ok(()) => {
+                self.gpu_lost = None;
+                self.needs_redraw = true;
+            }
+            Err(e) => {
+                tracing::error!("Failed to recreate the renderer: {}", e);
+                self.gpu_lost = Some(Instant::now());
+            }
+        }
+    }
+
+    fn burn_in_settling(&self) -> bool {
+        self.burn_in_settle
+            .is_some_and(|until| Instant::now() < until)
+    }
+
+    /// Whether any time-varying effect or overlay needs continuous frames
+    fn animation_active(&self) -> bool {
+        // Animated effects only run in the CRT pass
+        let effects = &self.config.effects;
+        if effects.enabled
+            && (effects.flicker > 0.0
+                || effects.static_noise > 0.0
+                || effects.scanline_intensity > 0.0 // Scanlines slowly drift
+                || effects.beam_simulation_enabled)
+        {
+            return true;
+        }
+
+        if self.config_ui.visible
+            || self.debug_grid
+            || self.perf.is_some()
+            || self.kitty_mode_message.is_some()
+            || self.monitor_message.is_some()
+            || self.toast.is_some()
+            || self.pending_paste.is_some()
+            || self.swap_pending
+            || self.bell_flash.is_some()
+            || self.recorder.is_some()
+            || !self.scroll_px_offset.is_empty()
+            || !self.wheel_scroll.is_empty()
+            || self.selection_autoscroll.is_some()
+            || !self.spawn_errors.is_empty()
+        {
+            return true;
+        }
+
+        if self
+            .renderer
+            .as_ref()
+            .is_some_and(|renderer| renderer.power_on_active())
+        {
+            return true;
+        }
+
+        if self
+            .last_resize
+            .is_some_and(|t| t.elapsed() < RESIZE_INDICATOR_DURATION)
+        {
+            return true;
+        }
+
+        let scrollbar_fade = SCROLLBAR_VISIBLE_DURATION + SCROLLBAR_FADE_DURATION;
+        if self
+            .last_scroll
+            .values()
+            .any(|t| t.elapsed() < scrollbar_fade)
+        {
+            return true;
+        }
+
+        let hint_end = STARTUP_HINT_DELAY + STARTUP_HINT_DURATION + STARTUP_HINT_FADE;
+        self.config.behavior.show_startup_hint && self.app_start.elapsed().as_secs_f32() < hint_end
+    }
+
     /// Returns the currently active config - either the preview config if
     /// the settings UI is open, or the saved config otherwise.
     fn current_config(&self) -> &Config {
@@ -434,10 +1107,11 @@ impl App {
         let rect = rects.get(&focused)?;
 
         // Pane bounds in pixels (with padding)
-        let pane_x = (rect.x * win_width as f32 + PANE_PADDING) as f64;
-        let pane_y = (rect.y * win_height as f32 + PANE_PADDING) as f64;
-        let pane_w = (rect.width * win_width as f32 - PANE_PADDING * 2.0) as f64;
-        let pane_h = (rect.height * win_height as f32 - PANE_PADDING * 2.0) as f64;
+        let pane_padding = self.config.pane_padding;
+        let pane_x = (rect.x * win_width as f32 + pane_padding) as f64;
+        let pane_y = (rect.y * win_height as f32 + pane_padding) as f64;
+        let pane_w = (rect.width * win_width as f32 - pane_padding * 2.0) as f64;
+        let pane_h = (rect.height * win_height as f32 - pane_padding * 2.0) as f64;
 
         let (content_x, content_y) = if curvature.abs() < 0.0001 {
             // No distortion
@@ -498,7 +1172,12 @@ impl App {
             )
         };
 
-        let (cell_w, cell_h) = renderer.cell_size();
+        let (cell_w, cell_h) = renderer.cell_size_for(pane_font_size(
+            self.current_config(),
+            &self.layout,
+            focused,
+            self.display_scale,
+        ));
         let local_x = content_x - pane_x;
         let local_y = content_y - pane_y;
         let col = (local_x / cell_w as f64).floor().max(0.0) as usize;
@@ -535,89 +1214,273 @@ impl App {
         )
     }
 
-    fn copy_selection(&mut self) {
-        let focused = self.layout.focused_pane();
-        let Some(terminal) = self.terminals.get(&focused) else {
+    /// Whether mouse events go to the focused pane's program instead of
+    /// selecting and scrolling. Holding Shift keeps them for us.
+    fn mouse_reporting(&self) -> bool {
+        !self.modifiers.shift_key()
+            && self.copy_mode.is_none()
+            && self
+                .terminals
+                .get(&self.layout.focused_pane())
+                .is_some_and(|terminal| mouse_reporting::wants_mouse(terminal.term_mode()))
+    }
+
+    /// Whether the wheel sends arrow keys to the focused pane's program
+    fn alternate_scroll(&self) -> bool {
+        self.copy_mode.is_none()
+            && self
+                .terminals
+                .get(&self.layout.focused_pane())
+                .is_some_and(|terminal| mouse_reporting::alternate_scroll(terminal.term_mode()))
+    }
+
+    /// Screen cell under the mouse in the focused pane, clamped to its grid
+    fn mouse_report_cell(&self) -> Option<(usize, usize)> {
+        let pos = self.pixel_to_cell(self.mouse_pos.0, self.mouse_pos.1)?;
+        let terminal = self.terminals.get(&self.layout.focused_pane())?;
+        let (cols, rows) = terminal.size();
+        let col = pos.col.min(cols.saturating_sub(1) as usize);
+        let row = (pos.row + terminal.display_offset() as i32).clamp(0, rows as i32 - 1);
+        Some((col, row as usize))
+    }
+
+    /// Send a mouse event to the focused pane's program
+    fn send_mouse_report(&mut self, button: u8, kind: MouseEventKind, cell: (usize, usize)) {
+        self.last_mouse_report_cell = Some(cell);
+        let Some(terminal) = self.terminals.get(&self.layout.focused_pane()) else {
             return;
         };
+        let (col, row) = cell;
+        let mode = terminal.term_mode();
+        if let Some(bytes) = mouse_reporting::encode(button, kind, col, row, self.modifiers, mode) {
+            terminal.input(&bytes);
+        }
+    }
 
-        let (start, end) = self.selection.normalized();
+    /// Report a button press or release to a program that asked for mouse
+    /// events. Returns true if the program took it.
+    fn report_mouse_button(&mut self, button: MouseButton, state: ElementState) -> bool {
+        let Some(code) = mouse_reporting::button_code(button) else {
+            return false;
+        };
+        let kind = match state {
+            ElementState::Pressed if self.mouse_reporting() => {
+                self.mouse_report_button = Some(code);
+                MouseEventKind::Press
+            }
+            // Only releases of presses the program saw
+            ElementState::Released if self.mouse_report_button == Some(code) => {
+                self.mouse_report_button = None;
+                MouseEventKind::Release
+            }
+            _ => return false,
+        };
+        if let Some(cell) = self.mouse_report_cell() {
+            self.send_mouse_report(code, kind, cell);
+        }
+        true
+    }
 
-        // Read directly from terminal grid using buffer-relative coordinates
-        let text = terminal.with_grid(|grid| {
-            use alacritty_terminal::grid::Dimensions;
-            use alacritty_terminal::index::{Column, Line};
-            use alacritty_terminal::term::cell::Flags;
-            let cols = grid.columns();
-            let mut text = String::new();
+    /// Report pointer movement to a program tracking it. Returns true if
+    /// the program owns the mouse, whether or not this move was reported.
+    fn report_mouse_motion(&mut self) -> bool {
+        let held = self.mouse_report_button;
+        if held.is_none() && !self.mouse_reporting() {
+            return false;
+        }
+        let Some(terminal) = self.terminals.get(&self.layout.focused_pane()) else {
+            return false;
+        };
+        if !mouse_reporting::reports_motion(terminal.term_mode(), held.is_some()) {
+            return true;
+        }
+        if let Some(cell) = self.mouse_report_cell() {
+            if self.last_mouse_report_cell != Some(cell) {
+                let button = held.unwrap_or(mouse_reporting::NO_BUTTON);
+                self.send_mouse_report(button, MouseEventKind::Motion, cell);
+            }
+        }
+        true
+    }
 
-            for row in start.row..=end.row {
-                let line = Line(row);
-                let col_start = if row == start.row { start.col } else { 0 };
-                let col_end = if row == end.row {
-                    end.col.min(cols.saturating_sub(1))
-                } else {
-                    cols.saturating_sub(1)
-                };
+    /// Cells under the current selection in the focused pane, one entry per
+    /// copied line (soft-wrapped rows are joined), colored as drawn
+    fn selected_cells(&self) -> Option<Vec<Vec<StyledChar>>> {
+        let focused = self.layout.focused_pane();
+        let terminal = self.terminals.get(&focused)?;
 
-                for col in col_start..=col_end {
-                    let cell = &grid[line][Column(col)];
-                    let c = cell.c;
-                    if c != ' ' && c != '\0' {
-                        text.push(c);
-                    } else if c == ' ' {
-                        text.push(' ');
-                    }
-                }
-                // Only add newline if this row wasn't soft-wrapped
-                if row != end.row {
-                    let last_cell = &grid[line][Column(cols - 1)];
-                    if !last_cell.flags.contains(Flags::WRAPLINE) {
-                        text.push('\n');
+        let (start, end) = self.selection.normalized();
+        let block = self.selection_mode == SelectionMode::Block;
+        let scheme = &self.config.color_scheme;
+        let bold_is_bright = self.config.behavior.bold_is_bright;
+
+        // Read directly from terminal grid using buffer-relative coordinates
+        let lines = terminal.with_grid(|grid| {
+            grid_text::selected_lines(
+                grid,
+                (start.row, start.col),
+                (end.row, end.col),
+                block,
+                |cell| {
+                    let (fg, bg) = cell_base_colors(cell, scheme, bold_is_bright);
+                    let highlight = Highlight {
+                        inverse: cell.flags.contains(Flags::INVERSE),
+                        ..Highlight::default()
+                    };
+                    let (fg, bg) = cell_colors::resolve(fg, bg, scheme.background, highlight);
+                    StyledChar {
+                        c: cell.c,
+                        fg,
+                        bg: (bg[3] > 0.01).then_some(bg),
+                        bold: cell.flags.contains(Flags::BOLD),
+                        italic: cell.flags.contains(Flags::ITALIC),
+                        underline: cell.flags.intersects(Flags::ALL_UNDERLINES),
                     }
-                }
-            }
-            text
+                },
+            )
         });
+        Some(lines)
+    }
 
+    /// Text under the current selection in the focused pane
+    fn selected_text(&self) -> Option<String> {
+        let lines = self.selected_cells()?;
         // Trim trailing whitespace from each line but keep structure
-        let trimmed: String = text
+        let trimmed: String = rich_text::to_plain(&lines)
             .lines()
             .map(|l| l.trim_end())
             .collect::<Vec<_>>()
             .join("\n");
+        Some(trimmed)
+    }
 
+    fn copy_selection(&mut self) {
+        let Some(trimmed) = self.selected_text() else {
+            return;
+        };
+        let format = self.config.behavior.copy_format;
+        let rich = match format {
+            CopyFormat::Plain => None,
+            CopyFormat::Html | CopyFormat::Ansi => self.selected_cells().map(|mut lines| {
+                rich_text::trim_lines(&mut lines);
+                lines
+            }),
+        };
+        let scheme = &self.config.color_scheme;
         if let Some(clipboard) = &mut self.clipboard {
-            if let Err(e) = clipboard.set_text(&trimmed) {
+            let result = match (format, rich) {
+                (CopyFormat::Html, Some(lines)) => {
+                    let html = rich_text::to_html(&lines, scheme.foreground, scheme.background);
+                    clipboard.set_html(html, Some(trimmed.clone()))
+                }
+                (CopyFormat::Ansi, Some(lines)) => clipboard.set_text(rich_text::to_ansi(&lines)),
+                _ => clipboard.set_text(&trimmed),
+            };
+            if let Err(e) = result {
                 tracing::error!("Failed to copy to clipboard: {}", e);
             } else {
                 tracing::info!("Copied {} chars to clipboard", trimmed.len());
             }
+            if self.config.behavior.use_primary_selection {
+                set_primary_selection(clipboard, &trimmed);
+            }
         }
     }
 
-    /// Find word boundaries around the given position.
-    /// Returns (start, end) positions that encompass the word.
-    fn find_word_boundaries(&self, pos: CellPos) -> Option<(CellPos, CellPos)> {
-        let focused = self.layout.focused_pane();
-        let terminal = self.terminals.get(&focused)?;
-
-        terminal.with_grid(|grid| {
-            use alacritty_terminal::grid::Dimensions;
-            use alacritty_terminal::index::{Column, Line};
-            let cols = grid.columns();
-            let line = Line(pos.row);
-
-            // Check if the clicked position has a non-whitespace character
-            let clicked_char = grid[line][Column(pos.col)].c;
-            if clicked_char.is_whitespace() || clicked_char == '\0' {
-                return None;
+    /// Copy the selection with its colors whatever `copy_format` says: HTML
+    /// for rich text targets, with the ANSI version as the plain text
+    fn copy_selection_formatted(&mut self) {
+        let Some(mut lines) = self.selected_cells() else {
+            return;
+        };
+        rich_text::trim_lines(&mut lines);
+        let plain = rich_text::to_plain(&lines);
+        let ansi = rich_text::to_ansi(&lines);
+        let scheme = &self.config.color_scheme;
+        let html = rich_text::to_html(&lines, scheme.foreground, scheme.background);
+        if let Some(clipboard) = &mut self.clipboard {
+            // Not every clipboard takes HTML; the colors still survive as ANSI
+            let result = clipboard
+                .set_html(html, Some(ansi.clone()))
+                .or_else(|_| clipboard.set_text(ansi));
+            if let Err(e) = result {
+                tracing::error!("Failed to copy to clipboard: {}", e);
+            } else {
+                tracing::info!("Copied {} chars with formatting", plain.len());
+            }
+            if self.config.behavior.use_primary_selection {
+                set_primary_selection(clipboard, &plain);
             }
+        }
+    }
 
-            // Scan left to find word start
-            let mut start_col = pos.col;
-            while start_col > 0 {
-                let c = grid[line][Column(start_col - 1)].c;
+    /// Select the focused pane's whole scrollback and screen, copying it as a
+    /// finished mouse selection would be
+    fn select_all(&mut self) {
+        let Some(terminal) = self.terminals.get(&self.layout.focused_pane()) else {
+            return;
+        };
+        let Some((start, end)) = terminal.with_grid(grid_text::whole_buffer) else {
+            return;
+        };
+        self.selection = Selection {
+            start: CellPos {
+                col: start.1,
+                row: start.0,
+            },
+            end: CellPos {
+                col: end.1,
+                row: end.0,
+            },
+            active: false,
+        };
+        self.selection_mode = SelectionMode::Linear;
+        if self.config.behavior.auto_copy_selection {
+            self.copy_selection();
+        } else {
+            self.select_to_primary();
+        }
+    }
+
+    /// Put the selection in the primary selection only, leaving the
+    /// clipboard alone
+    fn select_to_primary(&mut self) {
+        if !self.config.behavior.use_primary_selection {
+            return;
+        }
+        let Some(text) = self.selected_text() else {
+            return;
+        };
+        if text.is_empty() {
+            return;
+        }
+        if let Some(clipboard) = &mut self.clipboard {
+            set_primary_selection(clipboard, &text);
+        }
+    }
+
+    /// Find word boundaries around the given position.
+    /// Returns (start, end) positions that encompass the word.
+    fn find_word_boundaries(&self, pos: CellPos) -> Option<(CellPos, CellPos)> {
+        let focused = self.layout.focused_pane();
+        let terminal = self.terminals.get(&focused)?;
+
+        terminal.with_grid(|grid| {
+            use alacritty_terminal::grid::Dimensions;
+            use alacritty_terminal::index::{Column, Line};
+            let cols = grid.columns();
+            let line = Line(pos.row);
+
+            // Check if the clicked position has a non-whitespace character
+            let clicked_char = grid[line][Column(pos.col)].c;
+            if clicked_char.is_whitespace() || clicked_char == '\0' {
+                return None;
+            }
+
+            // Scan left to find word start
+            let mut start_col = pos.col;
+            while start_col > 0 {
+                let c = grid[line][Column(start_col - 1)].c;
                 if c.is_whitespace() || c == '\0' {
                     break;
                 }
@@ -682,37 +1545,95 @@ impl App {
     }
 
     fn create_terminal_for_pane(&mut self, pane_id: PaneId) {
-        self.create_terminal_for_pane_with_session(pane_id, None, None);
+        self.create_terminal_for_pane_with_session(pane_id, None, None, None);
+    }
+
+    /// Save the config to the --config path, or the default location
+    fn save_config(&self) -> Result<PathBuf, ConfigError> {
+        match &self.config_path {
+            Some(path) => self.config.save(path).map(|_| path.clone()),
+            None => self.config.save_to_default(),
+        }
     }
 
     fn create_terminal_for_pane_with_session(
         &mut self,
         pane_id: PaneId,
-        working_directory: Option<std::path::PathBuf>,
+        working_directory: Option<PathBuf>,
         scrollback: Option<&[u8]>,
+        command: Option<ShellCommand>,
     ) {
+        self.load_pane_fonts();
         let Some(renderer) = &self.renderer else {
             return;
         };
 
         let (win_width, win_height) = renderer.window_size();
         let rects = self.layout.pane_rects(win_width as f32, win_height as f32);
+        let font_size = pane_font_size(&self.config, &self.layout, pane_id, self.display_scale);
 
         if let Some(rect) = rects.get(&pane_id) {
-            // Subtract padding from usable area
-            let pane_width = ((rect.width * win_width as f32) - PANE_PADDING * 2.0).max(1.0) as u32;
-            let pane_height =
-                ((rect.height * win_height as f32) - PANE_PADDING * 2.0).max(1.0) as u32;
-            let (cols, rows) = renderer.grid_size_for_region(pane_width, pane_height);
-
-            let result = if working_directory.is_some() {
-                Terminal::with_working_directory(cols, rows, working_directory)
-            } else {
-                Terminal::new(cols, rows)
-            };
+            let (cols, rows) = self
+                .pane_fit((win_width, win_height))
+                .grid_size(renderer, rect, font_size);
+            // Restored sessions can bring more panes than fit comfortably
+            if cols < self.config.min_pane_cols || rows < self.config.min_pane_rows {
+                tracing::warn!(
+                    "Pane {:?} is only {}x{} cells, below the minimum of {}x{}",
+                    pane_id,
+                    cols,
+                    rows,
+                    self.config.min_pane_cols,
+                    self.config.min_pane_rows
+                );
+            }
+
+            let behavior = &self.config.behavior;
+            let shell = command.unwrap_or_else(|| ShellCommand {
+                program: behavior.shell.clone(),
+                args: behavior.shell_args.clone(),
+                login: behavior.login_shell,
+            });
+            let number = self.layout.panes().iter().position(|&id| id == pane_id);
+            let scrollback_lines = behavior.scrollback_for_pane(number.map_or(1, |i| i + 1));
+            let disk_scrollback = behavior.disk_scrollback.then(|| {
+                behavior
+                    .disk_scrollback_path
+                    .clone()
+                    .unwrap_or_else(std::env::temp_dir)
+            });
+            let result = Terminal::with_shell(
+                cols,
+                rows,
+                working_directory,
+                &shell,
+                scrollback_lines,
+                disk_scrollback.as_deref(),
+            );
 
             match result {
                 Ok(terminal) => {
+                    // The PTY starts without a pixel size; report it right away
+                    let (cell_width, cell_height) = renderer.cell_pixel_size_for(font_size);
+                    terminal.resize(cols, rows, cell_width, cell_height);
+                    terminal.set_osc52(match behavior.allow_osc52_clipboard {
+                        Osc52Clipboard::Disabled => Osc52::Disabled,
+                        Osc52Clipboard::WriteOnly => Osc52::OnlyCopy,
+                        Osc52Clipboard::ReadWrite => Osc52::CopyPaste,
+                    });
+                    if let Some(error) = terminal.spawn_error() {
+                        self.spawn_errors
+                            .insert(pane_id, (error.to_string(), Instant::now()));
+                    }
+
+                    let proxy = self.event_proxy.clone();
+                    let pending = Arc::clone(&self.pty_wakeup_pending);
+                    terminal.set_wakeup_callback(move || {
+                        if !pending.swap(true, Ordering::AcqRel) {
+                            let _ = proxy.send_event(AppEvent::PtyOutput);
+                        }
+                    });
+
                     // Note: Scrollback data is captured but not restored to display.
                     // Proper scrollback restore would require direct grid manipulation,
                     // which alacritty_terminal doesn't easily expose. For now we just
@@ -739,31 +1660,83 @@ impl App {
         }
     }
 
-    fn resize_terminals(&mut self) {
-        let Some(renderer) = &self.renderer else {
+    /// Re-rasterize the font for the current scale and refit the panes to it
+    fn apply_scale(&mut self) {
+        let Some(renderer) = &mut self.renderer else {
             return;
         };
+        let scale = self.config.effective_scale(self.display_scale);
+        renderer.set_scale_factor(scale);
+        // BDF fonts keep their native size
+        if self.config.bdf_font.is_none() {
+            if let Err(e) = renderer.set_font(self.config.font, self.config.font_size * scale) {
+                tracing::error!("Failed to rescale font: {}", e);
+            }
+        }
+        self.resize_terminals();
+    }
 
-        let (win_width, win_height) = renderer.window_size();
-        let rects = self.layout.pane_rects(win_width as f32, win_height as f32);
+    /// Rasterize the font at the sizes panes have of their own
+    fn load_pane_fonts(&mut self) {
+        let Some(renderer) = &mut self.renderer else {
+            return;
+        };
+        let sizes = pane_font_sizes(&self.config, &self.layout, self.display_scale);
+        if let Err(e) = renderer.set_pane_font_sizes(&sizes) {
+            tracing::error!("Failed to load pane font sizes: {}", e);
+        }
+    }
 
-        for (pane_id, terminal) in &self.terminals {
-            if let Some(rect) = rects.get(pane_id) {
-                // Subtract padding from usable area
-                let pane_width =
-                    ((rect.width * win_width as f32) - PANE_PADDING * 2.0).max(1.0) as u32;
-                let pane_height =
-                    ((rect.height * win_height as f32) - PANE_PADDING * 2.0).max(1.0) as u32;
-                let (cols, rows) = renderer.grid_size_for_region(pane_width, pane_height);
-                terminal.resize(cols, rows);
+    /// Put the saved font back after the settings overlay previewed another
+    /// and refit the panes to it
+    fn settings_closed(&mut self) {
+        if let Some(renderer) = &mut self.renderer {
+            if let Some(warning) =
+                apply_saved_font(renderer, &self.config, &self.layout, self.display_scale)
+            {
+                self.toast = Some((warning, Instant::now()));
             }
         }
+        self.resize_terminals();
+    }
+
+    /// The window panes are fitted into, with the configured spacing
+    fn pane_fit(&self, window: (u32, u32)) -> PaneFit {
+        PaneFit {
+            window,
+            padding: self.config.pane_padding,
+            status_bar: self.config.behavior.status_bar,
+        }
+    }
+
+    fn resize_terminals(&mut self) {
+        let settings_open = self.config_ui.visible;
+        if !settings_open {
+            self.load_pane_fonts();
+        }
+        let Some(renderer) = &self.renderer else {
+            return;
+        };
+
+        let window = renderer.window_size();
+        let rects = self.layout.pane_rects(window.0 as f32, window.1 as f32);
+        pane_fit::fit_terminals(
+            renderer,
+            &self.pane_fit(window),
+            &self.terminals,
+            &rects,
+            |pane_id| pane_font_size(&self.config, &self.layout, pane_id, self.display_scale),
+            settings_open,
+        );
     }
 
     fn render_terminals(&mut self, dt: f32) {
         // Record frame time for FPS display
         let fps = self.record_frame_time(dt);
 
+        self.sync_copy_mode();
+        self.sync_selection_scroll();
+
         // Get mouse debug info before mutable borrow (None if in the void or debug disabled)
         let mouse_debug = if self.debug_grid {
             self.pixel_to_cell_debug(self.mouse_pos.0, self.mouse_pos.1)
@@ -775,6 +1748,30 @@ impl App {
         let current_cfg = self.current_config();
         let color_scheme = current_cfg.color_scheme.clone();
         let per_pane_crt = current_cfg.per_pane_crt;
+        let bold_is_bright = current_cfg.behavior.bold_is_bright;
+        let cursor_color =
+            ansi_color_to_rgba(AnsiColor::Named(NamedColor::Cursor), &color_scheme, false);
+        let cursor_blink_on = (self.cursor_blink_reset.elapsed().as_millis()
+            / CURSOR_BLINK_INTERVAL.as_millis())
+        .is_multiple_of(2);
+        let flash_color = self.bell_flash_color();
+        let font_label = self.config.font_label();
+        let pane_padding = self.config.pane_padding;
+        let status_size = self.config.behavior.status_bar.then(|| {
+            let focused = self.layout.focused_pane();
+            let terminal = self.terminals.get(&focused)?;
+            let now = Instant::now();
+            if self.status_bar.is_due(focused, terminal.generation(), now) {
+                self.status_bar.update(
+                    focused,
+                    terminal.generation(),
+                    terminal.foreground_name(),
+                    terminal.working_directory(),
+                    now,
+                );
+            }
+            Some(terminal.size())
+        });
 
         let Some(renderer) = &mut self.renderer else {
             return;
@@ -785,32 +1782,47 @@ impl App {
         let rects = self.layout.pane_rects(win_width as f32, win_height as f32);
         let focused_pane = self.layout.focused_pane();
 
-        let mut pane_renders: Vec<(f32, f32, Vec<Vec<RenderCell>>)> = Vec::new();
+        let mut pane_renders: Vec<(f32, f32, PaneId)> = Vec::new();
+        // Non-block cursors are drawn as lines over the cell backgrounds
+        let mut cursor_lines: Vec<(f32, f32, f32, f32, f32, [f32; 4])> = Vec::new();
 
-        for pane_id in self.layout.panes() {
+        // Floating panes come last so they're drawn over the grid
+        for pane_id in &self.layout.draw_order() {
             let Some(rect) = rects.get(pane_id) else {
                 continue;
             };
             let Some(terminal) = self.terminals.get(pane_id) else {
                 continue;
             };
+            let (cell_w, cell_h) = renderer.cell_size_for(pane_font_size(
+                &self.config,
+                &self.layout,
+                *pane_id,
+                self.display_scale,
+            ));
 
             // Check for Kitty keyboard protocol state changes
-            let term_mode = terminal.term_mode();
-            let kitty_enabled = term_mode.contains(TermMode::DISAMBIGUATE_ESC_CODES);
+            // (the active flags change with every push and pop of the stack)
+            let kitty_flags = terminal.kitty_keyboard_flags();
             let prev_state = self.kitty_mode_state.get(pane_id).copied();
-            if prev_state != Some(kitty_enabled) {
-                self.kitty_mode_state.insert(*pane_id, kitty_enabled);
+            if prev_state != Some(kitty_flags) {
+                self.kitty_mode_state.insert(*pane_id, kitty_flags);
                 // Only show message if this isn't the initial state detection
                 if prev_state.is_some() {
                     // Crossterm compat mode: REPORT_ASSOCIATED_TEXT not requested
-                    let crossterm_compat =
-                        kitty_enabled && !term_mode.contains(TermMode::REPORT_ASSOCIATED_TEXT);
+                    let crossterm_compat = kitty_flags != 0
+                        && !terminal
+                            .term_mode()
+                            .contains(TermMode::REPORT_ASSOCIATED_TEXT);
                     self.kitty_mode_message =
-                        Some((*pane_id, Instant::now(), kitty_enabled, crossterm_compat));
+                        Some((*pane_id, Instant::now(), kitty_flags, crossterm_compat));
                     tracing::info!(
                         "Kitty keyboard protocol {} for pane {:?}{}",
-                        if kitty_enabled { "enabled" } else { "disabled" },
+                        if kitty_flags != 0 {
+                            format!("flags {}", kitty_flags)
+                        } else {
+                            "disabled".to_string()
+                        },
                         pane_id,
                         if crossterm_compat {
                             " (crossterm compat)"
@@ -822,131 +1834,311 @@ impl App {
             }
 
             // Add padding offset, rounded to integer pixels for crisp bitmap font rendering
-            let x_offset = (rect.x * win_width as f32 + PANE_PADDING).floor();
-            let y_offset = (rect.y * win_height as f32 + PANE_PADDING).floor();
+            let x_offset = (rect.x * win_width as f32 + pane_padding).floor();
+            let scroll_px = self.scroll_px_offset.get(pane_id).copied().unwrap_or(0.0);
+            let y_offset = (rect.y * win_height as f32 + pane_padding + scroll_px).floor();
 
             // Only show cursor in focused pane
             let is_focused = *pane_id == focused_pane;
 
-            let cursor_pos = terminal.cursor_position();
+            // Cursor cell as (column, row on screen); copy mode replaces the
+            // shell's cursor with its own steady block
+            let display_offset = terminal.display_offset();
+            let disk_offset = terminal.disk_view_offset();
+            let rows = terminal.size().1 as usize;
+            let copy_cursor = self
+                .copy_mode
+                .filter(|copy_mode| copy_mode.pane == *pane_id)
+                .and_then(|_| terminal.copy_mode_cursor());
+            let (cursor_pos, cursor_style) = match copy_cursor {
+                Some((col, line)) => (
+                    usize::try_from(line + display_offset as i32)
+                        .ok()
+                        .map(|display_line| (col, display_line)),
+                    CursorStyle {
+                        shape: CursorShape::Block,
+                        blinking: false,
+                    },
+                ),
+                None => (
+                    terminal
+                        .cursor_position()
+                        .map(|(col, line)| (col, line + display_offset + disk_offset)),
+                    terminal.cursor_style(),
+                ),
+            };
+            let cursor_pos = cursor_pos.filter(|&(_, display_line)| display_line < rows);
+            let cursor_visible = is_focused
+                && cursor_style.shape != CursorShape::Hidden
+                && (!cursor_style.blinking || cursor_blink_on);
+            // Block cursors invert the cell; other shapes are drawn after the cells
+            let block_cursor = cursor_visible && cursor_style.shape == CursorShape::Block;
             let selection = &self.selection;
-
-            let cells = terminal.with_grid(|grid| {
-                use alacritty_terminal::grid::Dimensions;
-                use alacritty_terminal::index::{Column, Line};
-                use alacritty_terminal::term::cell::Flags;
-
-                let grid_cols = grid.columns();
-                let grid_lines = grid.screen_lines();
-                let display_offset = grid.display_offset() as i32;
-
-                let mut rows: Vec<Vec<RenderCell>> = Vec::with_capacity(grid_lines);
-
-                for line_idx in 0..grid_lines {
-                    let mut row = Vec::with_capacity(grid_cols);
-                    // When scrolled (display_offset > 0), access history with negative line indices
-                    let line = Line(line_idx as i32 - display_offset);
-
-                    for col_idx in 0..grid_cols {
-                        let cell = &grid[line][Column(col_idx)];
-                        let c = cell.c;
-                        let flags = cell.flags;
-
-                        // Skip wide char spacer cells - the wide char in the adjacent cell
-                        // visually extends into this space
-                        if flags.contains(Flags::WIDE_CHAR_SPACER)
-                            || flags.contains(Flags::LEADING_WIDE_CHAR_SPACER)
-                        {
-                            row.push(RenderCell {
-                                c: ' ',
-                                fg: [0.0, 0.0, 0.0, 0.0],
-                                bg: [0.0, 0.0, 0.0, 0.0],
-                                is_wide: false,
-                            });
-                            continue;
+            let selection_mode = self.selection_mode;
+
+            // Reuse last frame's cells unless output, scroll position, cursor,
+            // selection or colors changed
+            let cells_key = PaneCellsKey {
+                generation: terminal.generation(),
+                display_offset: terminal.display_offset(),
+                disk_offset,
+                size: terminal.size(),
+                is_focused,
+                block_cursor: if block_cursor { cursor_pos } else { None },
+                selection: is_focused.then_some((selection.start, selection.end, selection_mode)),
+                preedit: if is_focused {
+                    self.ime_preedit.clone()
+                } else {
+                    None
+                },
+                hints: match &self.hint_mode {
+                    Some(hint_mode) if hint_mode.pane == *pane_id => {
+                        hint_mode.labels_on_screen(display_offset, rows)
+                    }
+                    _ => Vec::new(),
+                },
+                search: match &self.search_bar {
+                    Some(search_bar) if search_bar.pane == *pane_id => {
+                        let top = -((display_offset + disk_offset) as i32);
+                        search_matches_on_screen(search_bar, top, rows)
+                    }
+                    _ => Vec::new(),
+                },
+                color_scheme: color_scheme.clone(),
+                bold_is_bright,
+            };
+            let cached = self
+                .pane_cells
+                .get(pane_id)
+                .is_some_and(|cached| cached.key == cells_key);
+
+            if !cached {
+                // Recycle the stale grid's rows for the rebuild
+                if let Some(stale) = self.pane_cells.remove(pane_id) {
+                    self.cell_pool.release(stale.cells);
+                }
+                let pool = &mut self.cell_pool;
+                // Rows paged in from disk fill the top while scrolled past the history
+                let paged_in = terminal.paged_in_rows();
+                let search_matches = &cells_key.search;
+                let search_highlight = color_scheme.search_highlight();
+
+                let (mut cells, images) = terminal.with_tagged_grid(|grid, tags| {
+                    use alacritty_terminal::grid::Dimensions;
+                    use alacritty_terminal::index::{Column, Line};
+                    use alacritty_terminal::term::cell::Flags;
+
+                    let grid_cols = grid.columns();
+                    let grid_lines = grid.screen_lines();
+                    let display_offset = grid.display_offset() as i32;
+
+                    let mut rows = pool.acquire_grid(grid_lines, grid_cols);
+                    let mut images = Vec::new();
+                    // Disk rows paged out before a resize can be narrower
+                    let blank = Cell::default();
+
+                    for (line_idx, row) in rows.iter_mut().enumerate() {
+                        // When scrolled (display_offset > 0), access history with negative line indices
+                        let line = Line(line_idx as i32 - disk_offset as i32 - display_offset);
+                        let grid_row = paged_in.get(line_idx).unwrap_or_else(|| &grid[line]);
+                        let line_size = tags.line_size(line);
+                        for image in tags.images(line) {
+                            images.push((image.column, line_idx, image.id, image.row));
                         }
-
-                        let is_wide = flags.contains(Flags::WIDE_CHAR);
-
-                        // Check if this cell is the cursor position
-                        let is_cursor = if let Some((cursor_col, cursor_line)) = cursor_pos {
-                            // Cursor is at grid Line(cursor_line). We're displaying Line(line_idx - display_offset).
-                            // So cursor appears when line_idx - display_offset == cursor_line, i.e., line_idx == cursor_line + display_offset
-                            let cursor_display_line = cursor_line as i32 + display_offset;
-                            is_focused
-                                && cursor_display_line >= 0
-                                && line_idx == cursor_display_line as usize
-                                && col_idx == cursor_col
-                        } else {
-                            false
-                        };
                         // Selection uses buffer-relative rows (screen_row - display_offset)
-                        let buffer_row = line_idx as i32 - display_offset;
-                        let is_selected = is_focused && selection.contains(col_idx, buffer_row);
-                        let is_dim = cell.flags.contains(Flags::DIM);
-                        let is_inverse = cell.flags.contains(Flags::INVERSE);
-
-                        // Get the cell's actual colors from terminal state
-                        let mut cell_fg = ansi_color_to_rgba(cell.fg, &color_scheme, is_dim);
-
-                        // Check if cell has an explicit background (not the default Background)
-                        let has_explicit_bg =
-                            !matches!(cell.bg, AnsiColor::Named(NamedColor::Background));
-                        let mut cell_bg = if has_explicit_bg {
-                            ansi_color_to_rgba(cell.bg, &color_scheme, false)
+                        let buffer_row = line.0;
+                        let selected_cols = if is_focused {
+                            selection.row_span(buffer_row, selection_mode)
                         } else {
-                            [0.0, 0.0, 0.0, 0.0] // Transparent for default background
+                            None
                         };
 
-                        // Handle inverse video (swap fg/bg)
-                        if is_inverse {
-                            // For inverse, if bg was transparent, use actual background color
-                            if !has_explicit_bg {
-                                cell_bg = color_scheme.background;
+                        for col_idx in 0..grid_cols {
+                            let cell = if col_idx < grid_row.len() {
+                                &grid_row[Column(col_idx)]
+                            } else {
+                                &blank
+                            };
+                            let c = cell.c;
+                            let flags = cell.flags;
+
+                            // Skip wide char spacer cells - the wide char in the adjacent cell
+                            // visually extends into this space
+                            if flags.contains(Flags::WIDE_CHAR_SPACER)
+                                || flags.contains(Flags::LEADING_WIDE_CHAR_SPACER)
+                            {
+                                row.push(RenderCell {
+                                    c: ' ',
+                                    fg: [0.0, 0.0, 0.0, 0.0],
+                                    bg: [0.0, 0.0, 0.0, 0.0],
+                                    is_wide: false,
+                                    style: GlyphStyle::Regular,
+                                    line_size,
+                                });
+                                continue;
+                            }
+
+                            let is_wide = flags.contains(Flags::WIDE_CHAR);
+
+                            // Check if this cell is the cursor position
+                            let is_cursor = block_cursor && cursor_pos == Some((col_idx, line_idx));
+                            let is_selected = selected_cols
+                                .is_some_and(|(first, last)| col_idx >= first && col_idx <= last);
+                            let is_bold = cell.flags.contains(Flags::BOLD);
+                            let style =
+                                GlyphStyle::from_flags(is_bold, cell.flags.contains(Flags::ITALIC));
+
+                            // Get the cell's actual colors from terminal state
+                            let (cell_fg, cell_bg) =
+                                cell_base_colors(cell, &color_scheme, bold_is_bright);
+                            let highlight = Highlight {
+                                inverse: cell.flags.contains(Flags::INVERSE),
+                                selected: is_selected,
+                                cursor: is_cursor,
+                            };
+                            let (mut fg, mut bg) = cell_colors::resolve(
+                                cell_fg,
+                                cell_bg,
+                                color_scheme.background,
+                                highlight,
+                            );
+                            // Search matches, the current one in the foreground color
+                            if !is_selected && !is_cursor {
+                                let found = search_matches
+                                    .iter()
+                                    .find(|(span, _)| span.contains(col_idx, buffer_row));
+                                if let Some((_, is_current)) = found {
+                                    fg = color_scheme.background;
+                                    bg = if *is_current {
+                                        color_scheme.foreground
+                                    } else {
+                                        search_highlight
+                                    };
+                                }
                             }
-                            std::mem::swap(&mut cell_fg, &mut cell_bg);
+
+                            row.push(RenderCell {
+                                c,
+                                fg,
+                                bg,
+                                is_wide,
+                                style,
+                                line_size,
+                            });
                         }
+                    }
 
-                        // Apply special rendering states (cursor and selection invert colors)
-                        // Resolve transparent background to scheme background for inversion
-                        let resolved_bg = if cell_bg[3] < 0.01 {
-                            color_scheme.background
-                        } else {
-                            cell_bg
-                        };
+                    (rows, images)
+                });
 
-                        let (fg, bg) = if is_cursor || is_selected {
-                            // Invert: swap fg and bg
-                            (resolved_bg, cell_fg)
-                        } else {
-                            (cell_fg, cell_bg)
-                        };
+                // Overlay IME composition text at the cursor so the user sees what
+                // they are composing before it is committed to the shell
+                if let (Some(preedit), Some((cursor_col, cursor_line))) =
+                    (&cells_key.preedit, cursor_pos)
+                {
+                    if let Some(row) = cells.get_mut(cursor_line) {
+                        overlay_preedit(row, cursor_col, preedit, &color_scheme);
+                    }
+                }
 
-                        row.push(RenderCell { c, fg, bg, is_wide });
+                // Update last_grid for copy operations on the focused pane
+                if is_focused {
+                    self.last_grid.resize_with(cells.len(), Vec::new);
+                    for (chars, row) in self.last_grid.iter_mut().zip(&cells) {
+                        chars.clear();
+                        chars.extend(row.iter().map(|cell| cell.c));
                     }
+                }
 
-                    rows.push(row);
+                // Hint labels go on after last_grid so copies never include them
+                for (col, line, label) in &cells_key.hints {
+                    if let Some(row) = cells.get_mut(*line) {
+                        overlay_hint_label(row, *col, label, &color_scheme);
+                    }
                 }
 
-                rows
-            });
+                self.pane_cells.insert(
+                    *pane_id,
+                    CachedPaneCells {
+                        key: cells_key,
+                        cells,
+                        images,
+                    },
+                );
+            }
+            let visible_rows = self
+                .pane_cells
+                .get(pane_id)
+                .map_or(0, |cached| cached.cells.len());
+
+            if cursor_visible && !block_cursor {
+                if let Some((cursor_col, display_line)) = cursor_pos {
+                    if display_line < visible_rows {
+                        let x = x_offset + cursor_col as f32 * cell_w;
+                        let y = y_offset + display_line as f32 * cell_h;
+                        push_cursor_lines(
+                            &mut cursor_lines,
+                            cursor_style.shape,
+                            (x, y, cell_w, cell_h),
+                            cursor_color,
+                        );
+                    }
+                }
+            }
 
-            // Update last_grid for copy operations on the focused pane
             if is_focused {
-                self.last_grid = cells
-                    .iter()
-                    .map(|row| row.iter().map(|cell| cell.c).collect())
-                    .collect();
+                if let Some((cursor_col, display_line)) = cursor_pos {
+                    // Underline the composition, as text fields do
+                    if let Some(preedit) = &self.ime_preedit {
+                        use unicode_width::UnicodeWidthStr;
+                        let columns = self
+                            .pane_cells
+                            .get(pane_id)
+                            .and_then(|cached| cached.cells.get(display_line))
+                            .map_or(0, |row| row.len());
+                        let width = preedit.width().min(columns.saturating_sub(cursor_col));
+                        if width > 0 {
+                            let x = x_offset + cursor_col as f32 * cell_w;
+                            let y = y_offset + (display_line + 1) as f32 * cell_h - 1.0;
+                            let end = x + width as f32 * cell_w;
+                            cursor_lines.push((x, y, end, y, 2.0, color_scheme.colors[15]));
+                        }
+                    }
+
+                    // Keep the IME candidate window next to the cursor
+                    // (winit wants physical pixels)
+                    let scale = renderer.scale_factor();
+                    let area = (
+                        ((x_offset + cursor_col as f32 * cell_w) * scale) as u32,
+                        ((y_offset + display_line as f32 * cell_h) * scale) as u32,
+                        (cell_w * scale) as u32,
+                        (cell_h * scale) as u32,
+                    );
+                    if self.ime_cursor_area != Some(area) {
+                        self.ime_cursor_area = Some(area);
+                        if let Some(window) = &self.window {
+                            window.set_ime_cursor_area(
+                                winit::dpi::PhysicalPosition::new(area.0, area.1),
+                                winit::dpi::PhysicalSize::new(area.2, area.3),
+                            );
+                        }
+                    }
+                }
             }
 
-            pane_renders.push((x_offset, y_offset, cells));
+            pane_renders.push((x_offset, y_offset, *pane_id));
         }
 
         // Calculate separators from pane boundaries
         // Format: (x, y, length, is_vertical)
         let mut separators: Vec<(f32, f32, f32, bool)> = Vec::new();
         if self.layout.panes().len() > 1 {
-            let rect_list: Vec<_> = rects.values().collect();
+            // Floating panes sit over the grid and get no separators
+            let rect_list: Vec<_> = rects
+                .iter()
+                .filter(|(pane_id, _)| !self.layout.is_floating(**pane_id))
+                .map(|(_, rect)| rect)
+                .collect();
 
             // For each pair of panes, check if they share an edge
             for i in 0..rect_list.len() {
@@ -1012,12 +2204,106 @@ impl App {
             }
         }
 
-        // Convert to the format render_panes expects
-        let panes: Vec<(f32, f32, &[Vec<RenderCell>])> = pane_renders
-            .iter()
-            .map(|(x, y, cells)| (*x, *y, cells.as_slice()))
+        // Title bars above floating panes
+        let float_headers: Vec<(f32, f32, Vec<Vec<RenderCell>>)> = self
+            .layout
+            .draw_order()
+            .into_iter()
+            .filter(|pane_id| self.layout.is_floating(*pane_id))
+            .filter_map(|pane_id| {
+                let rect = rects.get(&pane_id)?;
+                let title = self
+                    .terminals
+                    .get(&pane_id)
+                    .and_then(|terminal| terminal.title())
+                    .unwrap_or_else(|| "floating".to_string());
+                let columns = (rect.width * win_width as f32 / cell_w) as usize;
+                let x = (rect.x * win_width as f32).floor();
+                let y = floating::header_top(*rect, win_height as f32, cell_h).floor();
+                Some((
+                    x,
+                    y,
+                    vec![floating::header_row(&title, columns, &color_scheme)],
+                ))
+            })
             .collect();
 
+        // Command palette panel, centered near the top of the window
+        let palette_cells = self
+            .command_palette
+            .visible
+            .then(|| self.command_palette.render(&color_scheme));
+        // Status bar along the bottom row, under the search bar when both show
+        let status_cells = status_size.flatten().map(|size| {
+            let columns = (win_width as f32 / cell_w) as usize;
+            let home = dirs::home_dir();
+            vec![self
+                .status_bar
+                .render(columns, size, &color_scheme, &font_label, home.as_deref())]
+        });
+        // Search bar over the bottom row of the window
+        let search_cells = self.search_bar.as_ref().map(|search_bar| {
+            let columns = (win_width as f32 / cell_w) as usize;
+            vec![search_bar.render(columns, &color_scheme)]
+        });
+
+        // Convert to the format render_panes expects, with the sixel images
+        // each pane shows
+        let mut panes: Vec<(f32, f32, &[Vec<RenderCell>])> = Vec::new();
+        let mut font_sizes: Vec<Option<f32>> = Vec::new();
+        let mut images: Vec<ImageStrip> = Vec::new();
+        for (x, y, pane_id) in &pane_renders {
+            let Some(cached) = self.pane_cells.get(pane_id) else {
+                continue;
+            };
+            let font_size =
+                pane_font_size(&self.config, &self.layout, *pane_id, self.display_scale);
+            let (cell_w, cell_h) = renderer.cell_size_for(font_size);
+            let image_cell = renderer.cell_pixel_size_for(font_size);
+            let columns = cached.cells.first().map_or(0, Vec::len);
+            for &(col, line, id, image_row) in &cached.images {
+                let Some(image) = self.terminals.get(pane_id).and_then(|t| t.image(id)) else {
+                    continue;
+                };
+                if !renderer.has_image(id) {
+                    renderer.upload_image(id, image.width, image.height, &image.rgba);
+                }
+                images.extend(
+                    image_strip(
+                        &image,
+                        image_row,
+                        (image_cell.0 as u32, image_cell.1 as u32),
+                        (cell_w, cell_h),
+                        columns.saturating_sub(col) as f32 * cell_w,
+                    )
+                    .map(|(width, height, uv)| ImageStrip {
+                        image: id,
+                        pane: panes.len(),
+                        x: x + col as f32 * cell_w,
+                        y: y + line as f32 * cell_h,
+                        width,
+                        height,
+                        uv,
+                    }),
+                );
+            }
+            panes.push((*x, *y, cached.cells.as_slice()));
+            font_sizes.push(font_size);
+        }
+        for (x, y, cells) in &float_headers {
+            panes.push((*x, *y, cells.as_slice()));
+        }
+        for cells in [&status_cells, &search_cells].into_iter().flatten() {
+            let y = (win_height as f32 - cell_h).max(0.0).floor();
+            panes.push((0.0, y, cells.as_slice()));
+        }
+        if let Some(cells) = &palette_cells {
+            let width = self.command_palette.width() as f32 * cell_w;
+            let x = ((win_width as f32 - width) / 2.0).max(0.0).floor();
+            let y = (win_height as f32 / 5.0).floor();
+            panes.push((x, y, cells.as_slice()));
+        }
+
         // Calculate focus rectangle (only show when multiple panes)
         let focus_rect = if self.layout.panes().len() > 1 {
             rects.get(&focused_pane).map(|rect| {
@@ -1055,9 +2341,17 @@ impl App {
             Vec::new()
         };
 
-        // Add FPS counter in bottom-left when debug grid is enabled
+        // Add FPS counter in bottom-left when debug grid is enabled, with GPU
+        // pass times when the adapter can measure them
+        renderer.set_pass_timing(self.debug_grid || self.perf.is_some());
         if self.debug_grid {
-            let fps_text = format!("{:.0} FPS", fps);
+            let fps_text = match renderer.last_pass_timings() {
+                Some(timings) => format!(
+                    "{:.0} FPS  text {:.2}ms  burn-in {:.2}ms  crt {:.2}ms",
+                    fps, timings.text_ms, timings.burnin_ms, timings.crt_ms
+                ),
+                None => format!("{:.0} FPS", fps),
+            };
             let text_width = fps_text.len() as f32 * cell_w;
             // Position: bottom-left, with some padding
             let x = text_width / 2.0 + cell_w;
@@ -1065,6 +2359,29 @@ impl App {
             size_indicators.push((x, y, fps_text));
         }
 
+        // Performance overlay in the top-left corner, one line per row
+        if let Some(perf) = &mut self.perf {
+            let atlas = renderer.atlas();
+            let snapshot = PerfSnapshot {
+                fps,
+                frame_ms: dt * 1000.0,
+                gpu_ms: renderer.last_pass_timings().map(|t| t.total_ms()),
+                glyphs: atlas.glyph_count(),
+                atlas_size: atlas.atlas_dimensions(),
+                atlas_fill: atlas.fill_ratio(),
+                dropped_frames: perf.dropped_frames(),
+            };
+            let lines = snapshot.lines();
+            if perf.should_log(Instant::now()) {
+                tracing::info!("Perf: {}", lines.join("  "));
+            }
+            for (row, line) in lines.into_iter().enumerate() {
+                let x = line.len() as f32 * cell_w / 2.0 + cell_w;
+                let y = cell_h * (row as f32 + 1.5);
+                size_indicators.push((x, y, line));
+            }
+        }
+
         // Add startup hint after power-on animation
         if self.config.behavior.show_startup_hint && !self.config_ui.visible {
             let elapsed = self.app_start.elapsed().as_secs_f32();
@@ -1095,23 +2412,22 @@ impl App {
         // Show Kitty keyboard protocol status message (top right of pane)
         const KITTY_MSG_DURATION: f32 = 1.5;
         if self.config.behavior.show_kitty_message {
-            if let Some((pane_id, start_time, enabled, crossterm_compat)) = self.kitty_mode_message
-            {
+            if let Some((pane_id, start_time, flags, crossterm_compat)) = self.kitty_mode_message {
                 let elapsed = start_time.elapsed().as_secs_f32();
                 if elapsed < KITTY_MSG_DURATION {
                     if let Some(rect) = rects.get(&pane_id) {
-                        let msg = if enabled {
-                            "Kitty keyboard protocol enabled"
+                        let msg = if flags != 0 {
+                            format!("Kitty keyboard protocol enabled (flags {})", flags)
                         } else {
-                            "Kitty keyboard protocol disabled"
+                            "Kitty keyboard protocol disabled".to_string()
                         };
                         // Position at top right, accounting for message width
                         let msg_width = msg.len() as f32 * cell_w;
                         let x = (rect.x + rect.width) * win_width as f32
                             - msg_width / 2.0
-                            - PANE_PADDING;
-                        let y = rect.y * win_height as f32 + cell_h + PANE_PADDING;
-                        size_indicators.push((x, y, msg.to_string()));
+                            - pane_padding;
+                        let y = rect.y * win_height as f32 + cell_h + pane_padding;
+                        size_indicators.push((x, y, msg));
 
                         // Show crossterm compat indicator on second line
                         if crossterm_compat {
@@ -1119,7 +2435,7 @@ impl App {
                             let compat_width = compat_msg.len() as f32 * cell_w;
                             let compat_x = (rect.x + rect.width) * win_width as f32
                                 - compat_width / 2.0
-                                - PANE_PADDING;
+                                - pane_padding;
                             let compat_y = y + cell_h * 1.2;
                             size_indicators.push((compat_x, compat_y, compat_msg.to_string()));
                         }
@@ -1131,6 +2447,134 @@ impl App {
             }
         }
 
+        // Show shell spawn failures at the top of the pane, since it's running
+        // the default shell instead of the configured one
+        self.spawn_errors
+            .retain(|_, (_, since)| since.elapsed() < SPAWN_ERROR_DURATION);
+        for (pane_id, (msg, _)) in &self.spawn_errors {
+            if let Some(rect) = rects.get(pane_id) {
+                let x = (rect.x + rect.width / 2.0) * win_width as f32;
+                let y = rect.y * win_height as f32 + cell_h + pane_padding;
+                size_indicators.push((x, y, msg.clone()));
+            }
+        }
+
+        // Monitoring mode changes, top right like the Kitty message
+        if let Some((pane_id, mode, since)) = self.monitor_message {
+            if since.elapsed() < MONITOR_MESSAGE_DURATION {
+                if let Some(rect) = rects.get(&pane_id) {
+                    let msg = mode.label();
+                    let x = (rect.x + rect.width) * win_width as f32
+                        - msg.len() as f32 * cell_w / 2.0
+                        - pane_padding;
+                    let y = rect.y * win_height as f32 + cell_h + pane_padding;
+                    size_indicators.push((x, y, msg.to_string()));
+                }
+            } else {
+                self.monitor_message = None;
+            }
+        }
+
+        // Copy mode indicator, top right like the monitoring message
+        if let Some(copy_mode) = self.copy_mode {
+            if let Some(rect) = rects.get(&copy_mode.pane) {
+                let msg = copy_mode.label();
+                let x = (rect.x + rect.width) * win_width as f32
+                    - msg.len() as f32 * cell_w / 2.0
+                    - pane_padding;
+                let y = rect.y * win_height as f32 + cell_h + pane_padding;
+                size_indicators.push((x, y, msg.to_string()));
+            }
+        }
+
+        // Reminder that the focused pane shows history, top right like the
+        // monitoring message; copy mode has its own label there
+        if self.copy_mode.is_none() {
+            let scrolled = self.terminals.get(&focused_pane).and_then(|terminal| {
+                let offset = terminal.display_offset() + terminal.disk_view_offset();
+                (offset > 0).then(|| (offset, terminal.scrollback_len()))
+            });
+            if let (Some((offset, total)), Some(rect)) = (scrolled, rects.get(&focused_pane)) {
+                let msg = format!("-- SCROLLBACK {}/{} --", offset, total);
+                let x = (rect.x + rect.width) * win_width as f32
+                    - msg.len() as f32 * cell_w / 2.0
+                    - pane_padding;
+                let y = rect.y * win_height as f32 + cell_h + pane_padding;
+                size_indicators.push((x, y, msg));
+            }
+        }
+
+        // Blinking recording dot, top left of the recorded pane
+        if let Some((pane, _)) = &self.recorder {
+            let blink_on = self.app_start.elapsed().as_millis() % 1000 < 500;
+            if let (true, Some(rect)) = (blink_on, rects.get(pane)) {
+                let msg = "● REC";
+                let x = rect.x * win_width as f32
+                    + msg.chars().count() as f32 * cell_w / 2.0
+                    + pane_padding;
+                let y = rect.y * win_height as f32 + cell_h + pane_padding;
+                size_indicators.push((x, y, msg.to_string()));
+            }
+        }
+
+        // Window-wide notifications, centered near the bottom
+        if let Some((msg, since)) = &self.toast {
+            if since.elapsed() < TOAST_DURATION {
+                let y = win_height as f32 - cell_h * 2.0 - pane_padding;
+                size_indicators.push((win_width as f32 / 2.0, y, msg.clone()));
+            } else {
+                self.toast = None;
+            }
+        }
+
+        // Paste confirmation, centered in the window
+        if let Some(pending) = &self.pending_paste {
+            let lines = paste::preview(&pending.text);
+            let top = win_height as f32 / 2.0 - lines.len() as f32 * cell_h / 2.0;
+            for (i, line) in lines.into_iter().enumerate() {
+                let y = top + i as f32 * cell_h;
+                size_indicators.push((win_width as f32 / 2.0, y, line));
+            }
+        }
+
+        // Swap prompt, centered in the focused pane
+        if self.swap_pending {
+            if let Some(rect) = rects.get(&self.layout.focused_pane()) {
+                let x = (rect.x + rect.width / 2.0) * win_width as f32;
+                let y = (rect.y + rect.height / 2.0) * win_height as f32;
+                size_indicators.push((x, y, "Swap with: arrow key".to_string()));
+            }
+        }
+
+        // Flagged monitored panes get a "!" in their top right corner
+        for (pane_id, monitor) in &self.monitors {
+            if !monitor.flagged {
+                continue;
+            }
+            if let Some(rect) = rects.get(pane_id) {
+                let x = (rect.x + rect.width) * win_width as f32 - cell_w - pane_padding;
+                let y = rect.y * win_height as f32 + cell_h / 2.0 + pane_padding;
+                size_indicators.push((x, y, "!".to_string()));
+            }
+        }
+
+        // Held panes show how their process ended at the bottom
+        for (pane_id, code) in &self.held_panes {
+            if let Some(rect) = rects.get(pane_id) {
+                let x = (rect.x + rect.width / 2.0) * win_width as f32;
+                let y = (rect.y + rect.height) * win_height as f32 - cell_h - pane_padding;
+                let status = match code {
+                    Some(code) => format!("exited with code {}", code),
+                    None => "was terminated by a signal".to_string(),
+                };
+                size_indicators.push((
+                    x,
+                    y,
+                    format!("[process {} — press Enter to close]", status),
+                ));
+            }
+        }
+
         // Collect normalized pane rects for CRT shader and find focused pane index
         let mut focused_pane_index: i32 = -1;
         let pane_rects_normalized: Vec<(f32, f32, f32, f32)> = self
@@ -1190,11 +2634,11 @@ impl App {
 
                 // Scrollbar position (right edge of pane, with some margin)
                 let pane_x = rect.x * win_width as f32;
-                let pane_y = rect.y * win_height as f32 + PANE_PADDING;
-                let pane_h = rect.height * win_height as f32 - PANE_PADDING * 2.0;
+                let pane_y = rect.y * win_height as f32 + pane_padding;
+                let pane_h = rect.height * win_height as f32 - pane_padding * 2.0;
                 let pane_w = rect.width * win_width as f32;
 
-                let scrollbar_x = pane_x + pane_w - PANE_PADDING - 2.0; // 2px from right edge
+                let scrollbar_x = pane_x + pane_w - pane_padding - 2.0; // 2px from right edge
                 let track_height = pane_h;
 
                 // Thumb size proportional to visible portion
@@ -1223,21 +2667,37 @@ impl App {
             .collect();
 
         // If config UI is visible, render it instead of terminals
+        let mut device_lost = false;
         if self.config_ui.visible {
             // Live preview font changes - handle both BDF and TTF
             if let Some(bdf_font) = self.config_ui.config.bdf_font {
+                if let Some(warning) =
+                    apply_bdf_font_path(renderer, &self.config_ui.config, bdf_font)
+                {
+                    self.toast = Some((warning, Instant::now()));
+                }
                 if let Err(e) = renderer.set_bdf_font(bdf_font) {
                     tracing::error!("Failed to preview BDF font: {}", e);
                 }
             } else {
+                if let Some(warning) = apply_font_path(renderer, &self.config_ui.config) {
+                    self.toast = Some((warning, Instant::now()));
+                }
                 let preview_font = self.config_ui.config.font;
-                let preview_font_size =
-                    self.config_ui.config.font_size * self.config_ui.config.ui_scale;
+                let preview_font_size = self.config_ui.config.font_size
+                    * self.config_ui.config.effective_scale(self.display_scale);
                 if let Err(e) = renderer.set_font(preview_font, preview_font_size) {
                     tracing::error!("Failed to preview font: {}", e);
                 }
             }
 
+            renderer.set_scale_factor(self.config_ui.config.effective_scale(self.display_scale));
+            renderer.set_cell_spacing(
+                self.config_ui.config.line_height_mult,
+                self.config_ui.config.letter_spacing,
+            );
+            renderer.set_pane_border_style(self.config_ui.config.pane_border_style);
+
             let (cell_w, cell_h) = renderer.cell_size();
             let width_cells = (win_width as f32 / cell_w) as usize;
             let height_cells = (win_height as f32 / cell_h) as usize;
@@ -1248,8 +2708,10 @@ impl App {
             // Use config_ui settings for live preview
             let fg = self.config_ui.config.color_scheme.foreground;
             let effects = EffectParams {
+                enabled: self.config_ui.config.effects.enabled,
                 curvature: self.config_ui.config.effects.screen_curvature,
                 scanline_intensity: self.config_ui.config.effects.scanline_intensity,
+                scanline_gamma: self.config_ui.config.effects.scanline_gamma,
                 scanline_mode: match self.config_ui.config.effects.scanline_mode {
                     ScanlineMode::RowBased => 0,
                     ScanlineMode::Pixel => 1,
@@ -1263,10 +2725,17 @@ impl App {
                 flicker: self.config_ui.config.effects.flicker,
                 brightness: self.config_ui.config.effects.brightness,
                 vignette: self.config_ui.config.effects.vignette,
+                rgb_mask: self.config_ui.config.effects.rgb_mask,
+                aberration: self.config_ui.config.effects.rgb_shift,
+                corner_radius: self.config_ui.config.effects.corner_radius,
+                composite_bleed: self.config_ui.config.effects.composite_bleed,
+                white_point: self.config_ui.config.effects.white_point(),
                 bezel_enabled: self.config_ui.config.effects.bezel_enabled,
                 content_scale_x: self.config_ui.config.effects.content_scale_x,
                 content_scale_y: self.config_ui.config.effects.content_scale_y,
-                glow_color: [fg[0], fg[1], fg[2], 1.0],
+                glow_color: self.config_ui.config.effects.glow_color(fg),
+                flash_color: [0.0; 4],
+                background_opacity: self.config_ui.config.effects.background_opacity,
                 // Beam sweep / interlacing (disabled in config UI preview for now)
                 interlace_enabled: false,
                 beam_speed_divisor: 0,
@@ -1277,10 +2746,13 @@ impl App {
             // Use per_pane_crt from config UI so user can preview glow while adjusting
             let ui_per_pane_crt = self.config_ui.config.per_pane_crt;
 
-            if let Err(e) = renderer.render_panes(
+            match renderer.render_panes(
                 &ui_panes,
+                &[], // The config UI uses the main font
+                &[], // No images in config UI
                 &[],
                 None,
+                &[], // No terminal cursor in config UI
                 &[],
                 &[], // No scrollbars in config UI
                 &[(0.0, 0.0, 1.0, 1.0)],
@@ -1290,24 +2762,24 @@ impl App {
                 0,   // pane 0 is focused (the whole screen) so glow shows
                 effects,
             ) {
-                tracing::error!("Config UI render error: {}", e);
+                Err(RenderError::DeviceLost) => device_lost = true,
+                Err(e) => tracing::error!("Config UI render error: {}", e),
+                Ok(()) => {}
             }
         } else {
             // Ensure we're using the saved config's font (in case preview changed it)
-            // BDF fonts take priority over TTF fonts
-            if self.config.bdf_font.is_none() {
-                if let Err(e) = renderer.set_font(
-                    self.config.font,
-                    self.config.font_size * self.config.ui_scale,
-                ) {
-                    tracing::error!("Failed to restore font: {}", e);
-                }
+            if let Some(warning) =
+                apply_saved_font(renderer, &self.config, &self.layout, self.display_scale)
+            {
+                self.toast = Some((warning, Instant::now()));
             }
 
             let fg = self.config.color_scheme.foreground;
             let effects = EffectParams {
+                enabled: self.config.effects.enabled,
                 curvature: self.config.effects.screen_curvature,
                 scanline_intensity: self.config.effects.scanline_intensity,
+                scanline_gamma: self.config.effects.scanline_gamma,
                 scanline_mode: match self.config.effects.scanline_mode {
                     ScanlineMode::RowBased => 0,
                     ScanlineMode::Pixel => 1,
@@ -1321,10 +2793,17 @@ impl App {
                 flicker: self.config.effects.flicker,
                 brightness: self.config.effects.brightness,
                 vignette: self.config.effects.vignette,
+                rgb_mask: self.config.effects.rgb_mask,
+                aberration: self.config.effects.rgb_shift,
+                corner_radius: self.config.effects.corner_radius,
+                composite_bleed: self.config.effects.composite_bleed,
+                white_point: self.config.effects.white_point(),
                 bezel_enabled: self.config.effects.bezel_enabled,
                 content_scale_x: self.config.effects.content_scale_x,
                 content_scale_y: self.config.effects.content_scale_y,
-                glow_color: [fg[0], fg[1], fg[2], 1.0],
+                glow_color: self.config.effects.glow_color(fg),
+                flash_color,
+                background_opacity: self.config.effects.background_opacity,
                 // Beam sweep / interlacing simulation
                 // At 240Hz with divisor 4: 60 fields/sec (NTSC timing)
                 // beam_speed_divisor 0 disables beam simulation
@@ -1381,10 +2860,13 @@ impl App {
                     Vec::new()
                 };
 
-            if let Err(e) = renderer.render_panes(
+            match renderer.render_panes(
                 &panes,
+                &font_sizes,
+                &images,
                 &separators,
                 focus_rect,
+                &cursor_lines,
                 &size_indicators,
                 &scrollbars,
                 &pane_rects_normalized,
@@ -1394,20 +2876,1082 @@ impl App {
                 focused_pane_index,
                 effects,
             ) {
-                tracing::error!("Render error: {}", e);
+                Err(RenderError::DeviceLost) => device_lost = true,
+                Err(e) => tracing::error!("Render error: {}", e),
+                Ok(()) => {}
             }
         }
+        if device_lost {
+            self.recover_renderer();
+        }
     }
 
-    fn add_pane(&mut self) {
-        const MAX_PANES: usize = 16;
-        if self.layout.panes().len() >= MAX_PANES {
-            tracing::warn!("Maximum pane limit ({}) reached", MAX_PANES);
+    /// Move focus to another pane, reporting the change to both panes
+    fn focus_pane(&mut self, pane_id: PaneId) {
+        let previous = self.layout.focused_pane();
+        self.layout.set_focus(pane_id);
+        self.report_focus_change(previous, self.layout.focused_pane());
+    }
+
+    /// Send focus out/in events for an intra-window focus change, and drop
+    /// any IME composition meant for the previous pane
+    fn report_focus_change(&mut self, previous: PaneId, current: PaneId) {
+        if previous == current {
+            return;
+        }
+        if self.ime_preedit.take().is_some() {
+            // Toggling IME makes the input method discard its composition
+            if let Some(window) = &self.window {
+                window.set_ime_allowed(false);
+                window.set_ime_allowed(true);
+            }
+        }
+        if !self.window_focused {
             return;
         }
-        let new_pane_id = self.layout.add_pane();
+        if let Some(terminal) = self.terminals.get(&previous) {
+            terminal.report_focus(false);
+        }
+        if let Some(terminal) = self.terminals.get(&current) {
+            terminal.report_focus(true);
+        }
+    }
+
+    /// Run a bound shortcut. Returns false if the action doesn't apply right
+    /// now and the key should go to the terminal instead.
+    fn run_action(&mut self, action: Action) -> bool {
+        match action {
+            Action::NewPane => self.add_pane(),
+            Action::CycleMonitor => self.cycle_monitor(),
+            Action::Screenshot => self.take_screenshot(),
+            Action::CommandPalette => {
+                if self.command_palette.visible {
+                    self.command_palette.close();
+                } else if !self.config_ui.visible {
+                    let commands = command_palette::commands(self.layout.panes().len());
+                    self.command_palette.open(commands);
+                }
+            }
+            Action::ToggleRecording => {
+                if self.recorder.is_some() {
+                    self.stop_recording();
+                } else {
+                    self.start_recording();
+                }
+            }
+            Action::Search => {
+                if self.search_bar.is_some() {
+                    self.search_bar = None;
+                } else if !self.config_ui.visible {
+                    self.search_bar = Some(SearchBar::new(self.layout.focused_pane()));
+                }
+            }
+            Action::HintMode => {
+                if self.hint_mode.is_some() {
+                    self.hint_mode = None;
+                } else if !self.config_ui.visible {
+                    self.enter_hint_mode();
+                }
+            }
+            Action::CopyMode => {
+                if self.copy_mode.is_some() {
+                    self.exit_copy_mode();
+                } else if !self.config_ui.visible {
+                    self.enter_copy_mode();
+                }
+            }
+            Action::OpenSettings => {
+                if self.config_ui.visible {
+                    self.config_ui.hide();
+                    self.settings_closed();
+                } else {
+                    let panes = self.layout.panes();
+                    let focused = self.layout.focused_pane();
+                    let pane = panes
+                        .iter()
+                        .position(|&id| id == focused)
+                        .filter(|_| panes.len() > 1)
+                        .map(|i| i + 1);
+                    self.config_ui.show(&self.config, pane);
+                }
+            }
+            Action::ToggleEffects => {
+                // While the settings are open, flip the previewed value so
+                // Save or Cancel still apply to it
+                let effects = if self.config_ui.visible {
+                    &mut self.config_ui.config.effects
+                } else {
+                    &mut self.config.effects
+                };
+                effects.enabled = !effects.enabled;
+                tracing::info!("CRT effects: {}", effects.enabled);
+            }
+            Action::ToggleDebugGrid => {
+                self.debug_grid = !self.debug_grid;
+                tracing::info!("Debug grid: {}", self.debug_grid);
+            }
+            Action::ToggleBeamPause => {
+                // Freeze beam position for debugging
+                self.beam_paused = !self.beam_paused;
+                tracing::info!("Beam paused: {}", self.beam_paused);
+            }
+            Action::BeamStep => {
+                // Hold to step frames forward (when beam is paused)
+                if self.beam_paused {
+                    self.beam_step_held = true;
+                    // Immediate first step
+                    self.beam_step_last =
+                        Instant::now() - Duration::from_millis(self.beam_step_delay_ms as u64);
+                }
+            }
+            Action::BeamStepFaster | Action::BeamStepSlower => {
+                self.beam_step_delay_ms = if action == Action::BeamStepFaster {
+                    self.beam_step_delay_ms.saturating_sub(10).max(4)
+                } else {
+                    (self.beam_step_delay_ms + 10).min(500)
+                };
+                tracing::info!(
+                    "Beam step delay: {}ms ({:.1} fps)",
+                    self.beam_step_delay_ms,
+                    1000.0 / self.beam_step_delay_ms as f32
+                );
+            }
+            Action::Copy => self.copy_selection(),
+            Action::SelectAll => {
+                if self.config_ui.visible || self.copy_mode.is_some() {
+                    return false;
+                }
+                self.select_all();
+            }
+            Action::ClearSelection => {
+                // Escape belongs to whatever overlay is open
+                let overlay_open = self.config_ui.visible
+                    || self.command_palette.visible
+                    || self.search_bar.is_some()
+                    || self.pending_paste.is_some()
+                    || self.swap_pending
+                    || self.hint_mode.is_some()
+                    || self.copy_mode.is_some();
+                if !overlay_open {
+                    self.selection = Selection::default();
+                }
+                // The key still goes on to the program
+                return false;
+            }
+            Action::CopyFormatted => self.copy_selection_formatted(),
+            Action::Paste => {
+                if let Some(text) = self.clipboard.as_mut().and_then(|c| c.get_text().ok()) {
+                    self.paste(text);
+                }
+            }
+            Action::ReplayPowerOn => {
+                if let Some(renderer) = &mut self.renderer {
+                    renderer.replay_power_on();
+                }
+            }
+            Action::ScrollPageUp | Action::ScrollPageDown => {
+                let focused = self.layout.focused_pane();
+                if let Some(terminal) = self.terminals.get(&focused) {
+                    if action == Action::ScrollPageUp {
+                        terminal.scroll_page_up();
+                    } else {
+                        terminal.scroll_page_down();
+                    }
+                    self.last_scroll.insert(focused, Instant::now());
+                }
+            }
+            Action::ToggleFloating => {
+                if self.config_ui.visible {
+                    return false;
+                }
+                self.toggle_floating();
+            }
+            Action::SwapPane => {
+                if self.config_ui.visible || self.layout.panes().len() < 2 {
+                    return false;
+                }
+                self.swap_pending = !self.swap_pending;
+            }
+            Action::ScrollToBottom => {
+                // Only while scrolled back; otherwise End belongs to the program
+                let focused = self.layout.focused_pane();
+                let Some(terminal) = self.terminals.get(&focused) else {
+                    return false;
+                };
+                if self.copy_mode.is_some()
+                    || terminal.display_offset() + terminal.disk_view_offset() == 0
+                {
+                    return false;
+                }
+                terminal.scroll_to_bottom();
+                self.last_scroll.insert(focused, Instant::now());
+            }
+            Action::PreviousPrompt | Action::NextPrompt => {
+                // Jump between shell prompts (OSC 133). Full-screen programs in
+                // app-cursor mode get the keys instead.
+                if self.config_ui.visible {
+                    return false;
+                }
+                let focused = self.layout.focused_pane();
+                let Some(terminal) = self.terminals.get(&focused) else {
+                    return false;
+                };
+                if terminal.term_mode().contains(TermMode::APP_CURSOR) {
+                    return false;
+                }
+                let jumped = if action == Action::PreviousPrompt {
+                    terminal.scroll_to_previous_prompt()
+                } else {
+                    terminal.scroll_to_next_prompt()
+                };
+                if jumped {
+                    self.last_scroll.insert(focused, Instant::now());
+                }
+            }
+        }
+        true
+    }
+
+    fn enter_copy_mode(&mut self) {
+        let focused = self.layout.focused_pane();
+        let Some(terminal) = self.terminals.get(&focused) else {
+            return;
+        };
+        terminal.set_copy_mode(true);
+        self.selection = Selection::default();
+        self.selection_mode = SelectionMode::Linear;
+        self.copy_mode = Some(CopyMode::new(focused));
+    }
+
+    fn exit_copy_mode(&mut self) {
+        let Some(copy_mode) = self.copy_mode.take() else {
+            return;
+        };
+        if let Some(terminal) = self.terminals.get(&copy_mode.pane) {
+            terminal.set_copy_mode(false);
+            terminal.scroll_to_bottom();
+        }
+        self.selection = Selection::default();
+    }
+
+    /// Leave copy mode if its pane lost focus or closed, and keep its view
+    /// still while output arrives
+    fn sync_copy_mode(&mut self) {
+        let Some(copy_mode) = self.copy_mode else {
+            return;
+        };
+        if copy_mode.pane != self.layout.focused_pane() {
+            self.exit_copy_mode();
+            return;
+        }
+        let Some(terminal) = self.terminals.get(&copy_mode.pane) else {
+            self.copy_mode = None;
+            return;
+        };
+        // Buffer rows of the text under the selection move up with it
+        let pushed = terminal.hold_copy_mode_view() as i32;
+        if copy_mode.selecting && pushed > 0 {
+            self.selection.start.row -= pushed;
+            if let Some((col, row)) = terminal.copy_mode_cursor() {
+                self.selection.end = CellPos { col, row };
+            }
+        }
+    }
+
+    /// Move the selection up with its text as output scrolls the screen, and
+    /// drop it once that text has left the history
+    fn sync_selection_scroll(&mut self) {
+        // Copy mode holds its view still and moves its own selection
+        if self.copy_mode.is_some() {
+            return;
+        }
+        let focused = self.layout.focused_pane();
+        let Some(terminal) = self.terminals.get(&focused) else {
+            return;
+        };
+        if !self.selection.active && self.selection.start == self.selection.end {
+            if self.selection_anchor.take().is_some() {
+                terminal.clear_scroll_anchor();
+            }
+            return;
+        }
+        if self.selection_anchor != Some((focused, self.selection.start)) {
+            terminal.set_scroll_anchor(self.selection.start.row);
+            self.selection_anchor = Some((focused, self.selection.start));
+            return;
+        }
+        match terminal.take_anchor_scroll() {
+            Some(0) => {}
+            Some(lines) => {
+                self.selection.start.row -= lines;
+                self.selection.end.row -= lines;
+                self.selection_anchor = Some((focused, self.selection.start));
+            }
+            None => {
+                self.selection = Selection::default();
+                self.selection_anchor = None;
+                return;
+            }
+        }
+        // The other end may have left the history first
+        let top = -(terminal.history_size() as i32);
+        if self.selection.start.row.min(self.selection.end.row) < top {
+            terminal.clear_scroll_anchor();
+            self.selection = Selection::default();
+            self.selection_anchor = None;
+        }
+    }
+
+    fn handle_copy_mode_key(&mut self, command: CopyCommand) {
+        let Some(pane) = self.copy_mode.as_ref().map(|copy_mode| copy_mode.pane) else {
+            return;
+        };
+        let word_target = match command {
+            CopyCommand::WordForward => self.copy_mode_word_target(pane, true),
+            CopyCommand::WordBackward => self.copy_mode_word_target(pane, false),
+            _ => None,
+        };
+        let Some(copy_mode) = &mut self.copy_mode else {
+            return;
+        };
+        let Some(terminal) = self.terminals.get(&pane) else {
+            return;
+        };
+        match command {
+            CopyCommand::Motion(motion) => terminal.copy_mode_motion(motion),
+            CopyCommand::WordForward | CopyCommand::WordBackward => {
+                if let Some(target) = word_target {
+                    terminal.set_copy_mode_cursor(target.col, target.row);
+                }
+            }
+            CopyCommand::HalfPageUp | CopyCommand::HalfPageDown => {
+                let half_page = (terminal.size().1 / 2).max(1) as i32;
+                terminal.copy_mode_scroll(if command == CopyCommand::HalfPageUp {
+                    half_page
+                } else {
+                    -half_page
+                });
+            }
+            CopyCommand::ToggleSelection => {
+                copy_mode.selecting = !copy_mode.selecting;
+                self.selection = Selection::default();
+                if let Some((col, row)) = terminal.copy_mode_cursor() {
+                    if copy_mode.selecting {
+                        self.selection.start = CellPos { col, row };
+                    }
+                }
+            }
+            CopyCommand::Yank => {
+                if copy_mode.selecting {
+                    self.copy_selection();
+                }
+                self.exit_copy_mode();
+                return;
+            }
+            CopyCommand::Exit => {
+                self.exit_copy_mode();
+                return;
+            }
+        }
+
+        // The selection runs from where `v` or Space was pressed to the cursor
+        if copy_mode.selecting {
+            if let Some((col, row)) = terminal.copy_mode_cursor() {
+                self.selection.end = CellPos { col, row };
+            }
+        }
+        self.last_scroll.insert(pane, Instant::now());
+    }
+
+    /// Where `w` (forward) or `b` moves the copy mode cursor, with words
+    /// split the way double-click splits them
+    fn copy_mode_word_target(&self, pane: PaneId, forward: bool) -> Option<CellPos> {
+        let terminal = self.terminals.get(&pane)?;
+        let (col, row) = terminal.copy_mode_cursor()?;
+        let cursor = CellPos { col, row };
+        let word = self.find_word_boundaries(cursor);
+        if forward {
+            let end = word.map_or(cursor, |(_, end)| end);
+            let (row, col) =
+                terminal.with_grid(|grid| grid_text::next_non_blank(grid, (end.row, end.col)))?;
+            return Some(CellPos { col, row });
+        }
+        if let Some((start, _)) = word.filter(|(start, _)| *start != cursor) {
+            return Some(start);
+        }
+        let (row, col) =
+            terminal.with_grid(|grid| grid_text::previous_non_blank(grid, (row, col)))?;
+        let previous = CellPos { col, row };
+        Some(
+            self.find_word_boundaries(previous)
+                .map_or(previous, |(start, _)| start),
+        )
+    }
+
+    /// Paste into the focused pane, asking first if the text contains line
+    /// breaks or control characters and the application can't tell a paste
+    /// from typing
+    fn paste(&mut self, text: String) {
+        let focused = self.layout.focused_pane();
+        let Some(terminal) = self.terminals.get(&focused) else {
+            return;
+        };
+        let bracketed = terminal.term_mode().contains(TermMode::BRACKETED_PASTE);
+        if !bracketed
+            && self.config.behavior.confirm_multiline_paste
+            && paste::needs_confirmation(&text)
+        {
+            self.pending_paste = Some(PendingPaste {
+                pane: focused,
+                text,
+            });
+            return;
+        }
+        self.send_paste(focused, &text);
+    }
+
+    /// Write pasted text to a pane, wrapped in bracketed paste markers if
+    /// the application asked for them
+    fn send_paste(&mut self, pane: PaneId, text: &str) {
+        let Some(terminal) = self.terminals.get(&pane) else {
+            return;
+        };
+        terminal.scroll_to_bottom();
+        if terminal.term_mode().contains(TermMode::BRACKETED_PASTE) {
+            // Drop escapes so the paste can't end the bracket early
+            let text = text.replace('\x1b', "");
+            terminal.input(b"\x1b[200~");
+            terminal.input(text.as_bytes());
+            terminal.input(b"\x1b[201~");
+        } else {
+            terminal.input(text.as_bytes());
+        }
+        self.cursor_blink_reset = Instant::now();
+    }
+
+    /// Edit the palette's search, move through its list or run a command
+    fn handle_palette_key(&mut self, key: &Key) {
+        match key {
+            Key::Named(NamedKey::Escape) => self.command_palette.close(),
+            Key::Named(NamedKey::Enter) => {
+                if let Some(action) = self.command_palette.accept() {
+                    self.run_palette_action(action);
+                }
+            }
+            Key::Named(NamedKey::ArrowUp) => self.command_palette.move_up(),
+            Key::Named(NamedKey::ArrowDown) => self.command_palette.move_down(),
+            Key::Named(NamedKey::Backspace) => self.command_palette.backspace(),
+            Key::Named(NamedKey::Space) => self.command_palette.type_text(" "),
+            Key::Character(s) if !self.modifiers.control_key() => self.command_palette.type_text(s),
+            _ => {}
+        }
+    }
+
+    fn run_palette_action(&mut self, action: PaletteAction) {
+        match action {
+            PaletteAction::Run(action) => {
+                self.run_action(action);
+            }
+            PaletteAction::FocusPane(index) => {
+                if let Some(&pane) = self.layout.panes().get(index) {
+                    self.focus_pane(pane);
+                }
+            }
+            PaletteAction::SetFont(font) => {
+                if let Some(renderer) = &mut self.renderer {
+                    let size =
+                        self.config.font_size * self.config.effective_scale(self.display_scale);
+                    let _ = renderer.set_font_path(None);
+                    if let Err(e) = renderer.set_font(font, size) {
+                        tracing::error!("Failed to change font: {}", e);
+                        return;
+                    }
+                }
+                self.config.font = font;
+                self.config.font_path = None;
+                self.config.bdf_font = None;
+                self.config.bdf_font_path = None;
+                self.resize_terminals();
+                if let Err(e) = self.save_config() {
+                    tracing::error!("Failed to save config: {}", e);
+                }
+            }
+        }
+    }
+
+    /// Edit the search query or step through its matches
+    fn handle_search_key(&mut self, key: &Key) {
+        let Some(search_bar) = &mut self.search_bar else {
+            return;
+        };
+        let shift = self.modifiers.shift_key();
+        match key {
+            Key::Named(NamedKey::Escape) => {
+                self.search_bar = None;
+                return;
+            }
+            Key::Named(NamedKey::Enter | NamedKey::F3) if shift => search_bar.previous(),
+            Key::Named(NamedKey::Enter | NamedKey::F3) => search_bar.next(),
+            Key::Named(NamedKey::Backspace) => {
+                search_bar.backspace();
+                self.refresh_search(false);
+            }
+            Key::Named(NamedKey::Space) => {
+                search_bar.type_text(" ");
+                self.refresh_search(false);
+            }
+            Key::Character(s) if !self.modifiers.control_key() => {
+                search_bar.type_text(s);
+                self.refresh_search(false);
+            }
+            _ => return,
+        }
+        self.scroll_to_search_match();
+    }
+
+    /// Search the pane again, e.g. after the query changed or output arrived
+    fn refresh_search(&mut self, keep_current: bool) {
+        let Some(search_bar) = &mut self.search_bar else {
+            return;
+        };
+        let Some(terminal) = self.terminals.get(&search_bar.pane) else {
+            self.search_bar = None;
+            return;
+        };
+        search_bar.generation = terminal.generation();
+        let found = match search_bar.pattern().map(|p| RegexSearch::new(&p)) {
+            Some(Ok(mut regex)) => terminal.search(&mut regex),
+            Some(Err(e)) => {
+                tracing::warn!("Invalid search pattern: {}", e);
+                Vec::new()
+            }
+            None => Vec::new(),
+        };
+        search_bar.set_matches(found, keep_current);
+    }
+
+    /// Bring the current match on screen
+    fn scroll_to_search_match(&mut self) {
+        let Some(search_bar) = &self.search_bar else {
+            return;
+        };
+        let Some(current) = search_bar.current() else {
+            return;
+        };
+        if let Some(terminal) = self.terminals.get(&search_bar.pane) {
+            terminal.scroll_to_line(current.start.1);
+            self.last_scroll.insert(search_bar.pane, Instant::now());
+        }
+    }
+
+    /// Keep the matches in step with new output in the searched pane
+    fn update_search(&mut self) {
+        let Some(search_bar) = &self.search_bar else {
+            return;
+        };
+        let changed = self
+            .terminals
+            .get(&search_bar.pane)
+            .is_none_or(|terminal| terminal.generation() != search_bar.generation);
+        if changed {
+            self.refresh_search(true);
+            self.needs_redraw = true;
+        }
+    }
+
+    fn enter_hint_mode(&mut self) {
+        let focused = self.layout.focused_pane();
+        let Some(terminal) = self.terminals.get(&focused) else {
+            return;
+        };
+        let matches = match RegexSearch::new(hints::HINT_PATTERN) {
+            Ok(mut regex) => terminal.visible_matches(&mut regex),
+            Err(e) => {
+                tracing::error!("Invalid hint pattern: {}", e);
+                return;
+            }
+        };
+        self.hint_mode = HintMode::new(focused, matches);
+        if self.hint_mode.is_none() {
+            self.toast = Some(("Nothing to hint on screen".to_string(), Instant::now()));
+        }
+    }
+
+    /// Type a hint label: a chosen lowercase label opens its target, an
+    /// uppercase one copies it. Escape or a label that matches nothing leaves.
+    fn handle_hint_key(&mut self, key: &Key) {
+        let Some(hint_mode) = &mut self.hint_mode else {
+            return;
+        };
+        let input = match key {
+            Key::Named(NamedKey::Escape) => HintInput::NoMatch,
+            Key::Named(NamedKey::Backspace) => {
+                hint_mode.typed.pop();
+                HintInput::Pending
+            }
+            Key::Character(s) => match s.chars().next() {
+                Some(c) if c.is_ascii_alphabetic() => hint_mode.type_char(c),
+                _ => HintInput::Pending,
+            },
+            _ => HintInput::Pending,
+        };
+        let hint = match input {
+            HintInput::Pending => return,
+            HintInput::NoMatch => {
+                self.hint_mode = None;
+                return;
+            }
+            HintInput::Chosen(hint) => hint,
+        };
+        let pane = hint_mode.pane;
+        self.hint_mode = None;
+
+        let copy = matches!(key, Key::Character(s) if s.chars().all(|c| c.is_ascii_uppercase()));
+        if copy {
+            if let Some(clipboard) = &mut self.clipboard {
+                if let Err(e) = clipboard.set_text(hint.text.clone()) {
+                    tracing::warn!("Failed to copy hint: {}", e);
+                }
+            }
+            self.toast = Some((format!("Copied {}", hint.text), Instant::now()));
+            return;
+        }
+
+        let cwd = self
+            .terminals
+            .get(&pane)
+            .and_then(|terminal| terminal.working_directory());
+        let editor = self.config.behavior.hint_editor_command.as_deref();
+        let spawned = hints::open_command(&hint.target, editor, cwd.as_deref())
+            .map(|mut command| command.spawn());
+        if let Some(Err(e)) = spawned {
+            tracing::warn!("Failed to open {}: {}", hint.text, e);
+            self.toast = Some((format!("Failed to open {}", hint.text), Instant::now()));
+        }
+    }
+
+    /// Answer the paste confirmation: Enter pastes, S pastes as a single
+    /// line, Escape cancels and any other key is ignored
+    fn handle_pending_paste_key(&mut self, key: &Key) {
+        let single_line = match key {
+            Key::Named(NamedKey::Enter) => false,
+            Key::Character(s) if s.eq_ignore_ascii_case("s") => true,
+            Key::Named(NamedKey::Escape) => {
+                self.pending_paste = None;
+                return;
+            }
+            _ => return,
+        };
+        if let Some(pending) = self.pending_paste.take() {
+            let text = if single_line {
+                paste::single_line(&pending.text)
+            } else {
+                pending.text
+            };
+            self.send_paste(pending.pane, &text);
+        }
+    }
+
+    fn take_screenshot(&mut self) {
+        let Some(image) = self.renderer.as_ref().and_then(|r| r.screenshot()) else {
+            self.toast = Some(("Screenshot failed".to_string(), Instant::now()));
+            return;
+        };
+        let message = match screenshot::save(&image) {
+            Ok(path) => {
+                tracing::info!("Saved screenshot to {}", path.display());
+                format!("Saved {}", path.display())
+            }
+            Err(e) => {
+                tracing::error!("Failed to save screenshot: {:#}", e);
+                "Screenshot failed".to_string()
+            }
+        };
+        self.toast = Some((message, Instant::now()));
+        self.needs_redraw = true;
+    }
+
+    /// Record the focused pane's output to an asciicast file
+    fn start_recording(&mut self) {
+        let focused = self.layout.focused_pane();
+        let Some(terminal) = self.terminals.get(&focused) else {
+            return;
+        };
+        let (cols, rows) = terminal.size();
+        let started = screenshot::recording_path().and_then(|path| {
+            let recorder = SessionRecorder::new(&path, cols, rows)
+                .with_context(|| format!("Creating {}", path.display()))?;
+            Ok((path, recorder))
+        });
+        match started {
+            Ok((path, recorder)) => {
+                terminal.set_output_capture(true);
+                tracing::info!("Recording to {}", path.display());
+                self.toast = Some((format!("Recording to {}", path.display()), Instant::now()));
+                self.recorder = Some((focused, recorder));
+            }
+            Err(e) => {
+                tracing::error!("Failed to start recording: {:#}", e);
+                self.toast = Some(("Recording failed".to_string(), Instant::now()));
+            }
+        }
+    }
+
+    /// Write out what the recorded pane printed since the last frame
+    fn update_recording(&mut self) {
+        let Some((pane, recorder)) = &mut self.recorder else {
+            return;
+        };
+        let Some(terminal) = self.terminals.get(pane) else {
+            // The pane closed; keep what was recorded
+            self.stop_recording();
+            return;
+        };
+        let (cols, rows) = terminal.size();
+        let mut written = Ok(());
+        if recorder.size() != (cols, rows) {
+            written = recorder.record_resize(cols, rows);
+        }
+        let output = terminal.take_output();
+        if written.is_ok() && !output.is_empty() {
+            written = recorder.record_output(&output);
+        }
+        if let Err(e) = written {
+            tracing::error!("Failed to write recording: {}", e);
+            self.toast = Some(("Recording failed".to_string(), Instant::now()));
+            self.stop_recording();
+        }
+    }
+
+    fn stop_recording(&mut self) {
+        let Some((pane, recorder)) = self.recorder.take() else {
+            return;
+        };
+        if let Some(terminal) = self.terminals.get(&pane) {
+            terminal.set_output_capture(false);
+        }
+        match recorder.finish() {
+            Ok(()) => self.toast = Some(("Recording saved".to_string(), Instant::now())),
+            Err(e) => tracing::error!("Failed to finish recording: {}", e),
+        }
+    }
+
+    /// Cycle the focused pane through off, activity and silence monitoring
+    fn cycle_monitor(&mut self) {
+        let focused = self.layout.focused_pane();
+        let Some(terminal) = self.terminals.get(&focused) else {
+            return;
+        };
+        let mode = self
+            .monitors
+            .get(&focused)
+            .map_or(MonitorMode::Off, |monitor| monitor.mode)
+            .next();
+        if mode == MonitorMode::Off {
+            self.monitors.remove(&focused);
+        } else {
+            self.monitors
+                .insert(focused, PaneMonitor::new(mode, terminal.generation()));
+        }
+        self.monitor_message = Some((focused, mode, Instant::now()));
+        tracing::info!("{} for pane {:?}", mode.label(), focused);
+    }
+
+    /// Start the visual bell if any pane rang the bell
+    fn update_bell(&mut self) {
+        let mut rang = false;
+        for terminal in self.terminals.values() {
+            rang |= terminal.take_bell();
+        }
+        if rang && self.config.behavior.visual_bell {
+            self.bell_flash = Some(Instant::now());
+            self.needs_redraw = true;
+        }
+    }
+
+    /// Show the focused pane's OSC 0/2 title in the window title
+    fn update_window_title(&mut self) {
+        let Some(window) = &self.window else {
+            return;
+        };
+        let focused = self.layout.focused_pane();
+        let Some(terminal) = self.terminals.get(&focused) else {
+            return;
+        };
+        let title = match terminal.title().filter(|title| !title.is_empty()) {
+            Some(title) => {
+                let format = &self.config.behavior.title_format;
+                let pane = self
+                    .layout
+                    .panes()
+                    .iter()
+                    .position(|&pane| pane == focused)
+                    .map_or(0, |index| index + 1);
+                // Only look up the directory when the format uses it
+                let cwd = if format.contains("{cwd}") {
+                    terminal
+                        .working_directory()
+                        .map(|cwd| cwd.display().to_string())
+                        .unwrap_or_default()
+                } else {
+                    String::new()
+                };
+                // Title last, so placeholders inside it stay as typed
+                format
+                    .replace("{pane}", &pane.to_string())
+                    .replace("{cwd}", &cwd)
+                    .replace("{title}", &title)
+            }
+            None => WINDOW_TITLE.to_string(),
+        };
+        if title != self.window_title {
+            window.set_title(&title);
+            self.window_title = title;
+        }
+    }
+
+    /// Carry out OSC 52 clipboard requests from the panes. The terminal
+    /// already dropped any that `allow_osc52_clipboard` doesn't permit.
+    fn update_clipboard_requests(&mut self) {
+        let Some(clipboard) = &mut self.clipboard else {
+            return;
+        };
+        for terminal in self.terminals.values() {
+            for request in terminal.take_clipboard_requests() {
+                match request {
+                    ClipboardRequest::Store(ClipboardType::Clipboard, text) => {
+                        if let Err(e) = clipboard.set_text(text) {
+                            tracing::warn!("Failed to set clipboard from OSC 52: {}", e);
+                        }
+                    }
+                    ClipboardRequest::Store(ClipboardType::Selection, text) => {
+                        set_primary_selection(clipboard, &text);
+                    }
+                    ClipboardRequest::Load(kind, format) => {
+                        let text = match kind {
+                            ClipboardType::Clipboard => clipboard.get_text().unwrap_or_default(),
+                            ClipboardType::Selection => get_primary_selection(clipboard),
+                        };
+                        terminal.input(format(&text).as_bytes());
+                    }
+                }
+            }
+        }
+    }
+
+    /// The bell flash for this frame, fading out linearly
+    fn bell_flash_color(&mut self) -> [f32; 4] {
+        let Some(started) = self.bell_flash else {
+            return [0.0; 4];
+        };
+        let elapsed = started.elapsed();
+        if elapsed >= BELL_FLASH_DURATION {
+            self.bell_flash = None;
+            return [0.0; 4];
+        }
+        let fade = 1.0 - elapsed.as_secs_f32() / BELL_FLASH_DURATION.as_secs_f32();
+        let [r, g, b, a] = self.config.behavior.visual_bell_color;
+        [r, g, b, a * fade]
+    }
+
+    /// Once the touchpad goes quiet, finish or undo the partly scrolled line
+    fn update_smooth_scroll(&mut self) {
+        if self.scroll_px_offset.is_empty() {
+            return;
+        }
+        let Some(renderer) = &self.renderer else {
+            return;
+        };
+        let (last_scroll, terminals) = (&self.last_scroll, &self.terminals);
+        let (config, layout, display_scale) = (&self.config, &self.layout, self.display_scale);
+        self.scroll_px_offset.retain(|pane_id, offset| {
+            let Some(terminal) = terminals.get(pane_id) else {
+                return false;
+            };
+            let font_size = pane_font_size(config, layout, *pane_id, display_scale);
+            let (_, cell_h) = renderer.cell_size_for(font_size);
+            if last_scroll
+                .get(pane_id)
+                .is_some_and(|t| t.elapsed() < SMOOTH_SCROLL_IDLE)
+            {
+                return true;
+            }
+            // Past halfway, scroll the rest of the line instead of back
+            let display_offset = terminal.display_offset();
+            let can_move = if *offset > 0.0 {
+                display_offset < terminal.history_size()
+            } else {
+                display_offset > 0
+            };
+            if offset.abs() > cell_h / 2.0 && can_move {
+                let step = offset.signum() as i32;
+                terminal.scroll(step);
+                *offset -= step as f32 * cell_h;
+            }
+            *offset *= SMOOTH_SCROLL_SETTLE;
+            self.needs_redraw = true;
+            offset.abs() >= 0.5
+        });
+    }
+
+    /// Move each pane along its mouse wheel glide, stopping at either end of
+    /// the scrollback
+    fn update_wheel_scroll(&mut self) {
+        if self.wheel_scroll.is_empty() {
+            return;
+        }
+        let now = Instant::now();
+        let mut scrolled = false;
+        let (terminals, last_scroll) = (&self.terminals, &mut self.last_scroll);
+        self.wheel_scroll.retain(|pane_id, glide| {
+            let Some(terminal) = terminals.get(pane_id) else {
+                return false;
+            };
+            let lines = glide.step(now);
+            if lines != 0 {
+                let position = |t: &Terminal| (t.display_offset(), t.disk_view_offset());
+                let before = position(terminal);
+                terminal.scroll(lines);
+                if position(terminal) == before {
+                    return false;
+                }
+                last_scroll.insert(*pane_id, now);
+                scrolled = true;
+            }
+            glide.is_moving()
+        });
+        if scrolled {
+            self.needs_redraw = true;
+            // Keep a drag selection's end under the pointer
+            if self.selection.active {
+                if let Some(pos) = self.pixel_to_cell(self.mouse_pos.0, self.mouse_pos.1) {
+                    self.selection.end = pos;
+                }
+            }
+        }
+    }
+
+    /// While a selection is dragged above or below the focused pane, scroll
+    /// it and extend the selection to the edge row, faster the further the
+    /// pointer is from the edge
+    fn update_selection_autoscroll(&mut self) {
+        if !self.selection.active || self.report_mouse_motion() {
+            self.selection_autoscroll = None;
+            return;
+        }
+        let Some(renderer) = &self.renderer else {
+            return;
+        };
+        let focused = self.layout.focused_pane();
+        let Some(terminal) = self.terminals.get(&focused) else {
+            return;
+        };
+        let (win_width, win_height) = renderer.window_size();
+        let Some(rect) = self
+            .layout
+            .pane_rects(win_width as f32, win_height as f32)
+            .remove(&focused)
+        else {
+            return;
+        };
+        let font_size = pane_font_size(&self.config, &self.layout, focused, self.display_scale);
+        let (cell_w, cell_h) = renderer.cell_size_for(font_size);
+        let pane_padding = self.config.pane_padding;
+        let pane_x = (rect.x * win_width as f32 + pane_padding) as f64;
+        let pane_top = (rect.y * win_height as f32 + pane_padding) as f64;
+        let pane_bottom = pane_top + (rect.height * win_height as f32 - pane_padding * 2.0) as f64;
+
+        // Positive scrolls back into history
+        let (x, y) = self.mouse_pos;
+        let (past_edge, direction) = if y < pane_top {
+            (pane_top - y, 1)
+        } else if y > pane_bottom {
+            (y - pane_bottom, -1)
+        } else {
+            self.selection_autoscroll = None;
+            return;
+        };
+        let now = Instant::now();
+        if self
+            .selection_autoscroll
+            .is_some_and(|last| now.duration_since(last) < SELECTION_AUTOSCROLL_INTERVAL)
+        {
+            return;
+        }
+        self.selection_autoscroll = Some(now);
+
+        let lines = ((past_edge / cell_h as f64) as i32 + 1).min(SELECTION_AUTOSCROLL_MAX_LINES);
+        terminal.scroll(direction * lines);
+        self.last_scroll.insert(focused, now);
+
+        let (cols, rows) = terminal.size();
+        let col = ((x - pane_x) / cell_w as f64).clamp(0.0, cols.saturating_sub(1) as f64) as usize;
+        let screen_row = if direction > 0 { 0 } else { rows as i32 - 1 };
+        self.selection.end = CellPos {
+            col,
+            row: screen_row - terminal.display_offset() as i32,
+        };
+        self.needs_redraw = true;
+    }
+
+    /// Page each pane's history past its scrollback limit out to disk
+    fn update_disk_scrollback(&self) {
+        if !self.config.behavior.disk_scrollback {
+            return;
+        }
+        for terminal in self.terminals.values() {
+            terminal.page_out_scrollback();
+        }
+    }
+
+    /// Check monitored panes for new output or silence, flagging background ones
+    fn update_monitors(&mut self) {
+        if self.monitors.is_empty() {
+            return;
+        }
+        let focused = self.layout.focused_pane();
+        let silence = Duration::from_secs(self.config.behavior.silence_timeout_secs);
+        let mut newly_flagged = false;
+        for (pane_id, monitor) in &mut self.monitors {
+            if let Some(terminal) = self.terminals.get(pane_id) {
+                newly_flagged |=
+                    monitor.update(terminal.generation(), *pane_id == focused, silence);
+            }
+        }
+
+        if newly_flagged {
+            self.needs_redraw = true;
+            if self.config.behavior.monitor_requests_attention {
+                if let Some(window) = &self.window {
+                    window.request_user_attention(Some(UserAttentionType::Informational));
+                }
+            }
+        }
+    }
+
+    fn add_pane(&mut self) {
+        let Some(renderer) = &self.renderer else {
+            return;
+        };
+        let window = renderer.window_size();
+        let previous = self.layout.focused_pane();
+        let fit = self.pane_fit(window);
+        let added = self
+            .layout
+            .try_add_pane(window.0 as f32, window.1 as f32, |rect| {
+                fit.grid_size(renderer, rect, None)
+            });
+        let new_pane_id = match added {
+            Ok(pane) => pane,
+            Err(e) => {
+                tracing::warn!("Not adding a pane: {}", e);
+                self.toast = Some((format!("Can't add a pane: {}", e), Instant::now()));
+                return;
+            }
+        };
         self.resize_terminals(); // Existing terminals need to shrink
         self.create_terminal_for_pane(new_pane_id);
+        self.report_focus_change(previous, self.layout.focused_pane());
         tracing::info!(
             "Added pane {:?}, total panes: {}",
             new_pane_id,
@@ -1415,10 +3959,104 @@ impl App {
         );
     }
 
+    /// Show a hidden floating pane, or else float the focused pane over the
+    /// grid or put it back in
+    fn toggle_floating(&mut self) {
+        let previous = self.layout.focused_pane();
+        if let Some(hidden) = self.layout.hidden_floating() {
+            self.layout.show_floating(hidden);
+        } else if self.layout.is_floating(previous) {
+            self.layout.set_floating(previous, false);
+        } else if !self.layout.set_floating(previous, true) {
+            self.toast = Some((
+                "Can't float the only pane in the grid".to_string(),
+                Instant::now(),
+            ));
+            return;
+        }
+        // Floating and docking change the size of the grid's panes too
+        self.resize_terminals();
+        self.report_focus_change(previous, self.layout.focused_pane());
+    }
+
+    /// Put the focused floating pane away without closing it
+    fn hide_floating(&mut self) {
+        let previous = self.layout.focused_pane();
+        self.layout.hide_floating(previous);
+        self.report_focus_change(previous, self.layout.focused_pane());
+        tracing::info!("Hid floating pane {:?}", previous);
+    }
+
+    /// The floating pane whose title bar is under the pointer
+    fn float_header_at(&self, x: f64, y: f64) -> Option<PaneId> {
+        let renderer = self.renderer.as_ref()?;
+        let (win_width, win_height) = renderer.window_size();
+        let (_, cell_h) = renderer.cell_size();
+        let rects = self.layout.pane_rects(win_width as f32, win_height as f32);
+        let (x, y) = (x as f32, y as f32);
+        self.layout
+            .draw_order()
+            .into_iter()
+            .rev()
+            .filter(|pane_id| self.layout.is_floating(*pane_id))
+            .find(|pane_id| {
+                let rect = rects[pane_id];
+                let top = floating::header_top(rect, win_height as f32, cell_h);
+                let left = rect.x * win_width as f32;
+                let right = (rect.x + rect.width) * win_width as f32;
+                x >= left && x < right && y >= top && y < top + cell_h
+            })
+    }
+
+    /// Move the floating panes with the pointer while a title bar is dragged
+    fn drag_float(&mut self, grab: (f32, f32)) {
+        let Some(renderer) = &self.renderer else {
+            return;
+        };
+        let (_, win_height) = renderer.window_size();
+        let header = renderer.cell_size().1 / win_height as f32;
+        let pointer = self.pixel_to_normalized(self.mouse_pos.0, self.mouse_pos.1);
+        let rect = floating::dragged_rect(self.layout.float_rect(), grab, pointer, header);
+        self.layout.set_float_rect(rect);
+        // Saved with the rest of the window state on exit
+        self.config.floating_pane = floating::config_from_rect(rect);
+    }
+
+    /// Trade places with the neighboring pane in `direction`. Terminals are
+    /// keyed by pane, so only the layout changes and focus stays with the
+    /// same terminal.
+    fn swap_focused_pane(&mut self, direction: Direction) {
+        let Some(renderer) = &self.renderer else {
+            return;
+        };
+        let (width, height) = renderer.window_size();
+        let focused = self.layout.focused_pane();
+        let Some(other) = self
+            .layout
+            .neighbor(focused, direction, width as f32, height as f32)
+        else {
+            return;
+        };
+        self.layout.swap_panes(focused, other);
+        // Panes in different grid slots can differ in size
+        self.resize_terminals();
+        tracing::info!("Swapped pane {:?} with {:?}", focused, other);
+    }
+
     fn close_pane(&mut self, pane_id: PaneId) {
+        let previous = self.layout.focused_pane();
         self.terminals.remove(&pane_id);
+        if let Some(stale) = self.pane_cells.remove(&pane_id) {
+            self.cell_pool.release(stale.cells);
+        }
+        self.spawn_errors.remove(&pane_id);
+        self.held_panes.remove(&pane_id);
+        self.monitors.remove(&pane_id);
         self.layout.close(pane_id);
-        self.resize_terminals(); // Remaining terminals expand
+        // Remaining terminals expand
+        self.resize_terminals();
+        // The closed pane is gone, so only the newly focused one hears about it
+        self.report_focus_change(previous, self.layout.focused_pane());
         tracing::info!(
             "Closed pane {:?}, remaining panes: {}",
             pane_id,
@@ -1426,10 +4064,11 @@ impl App {
         );
     }
 
+    /// Panes whose process exited since the last check (held panes excluded)
     fn check_exited_terminals(&mut self) -> Vec<PaneId> {
         let mut exited = Vec::new();
         for (pane_id, terminal) in &self.terminals {
-            if terminal.has_exited() {
+            if terminal.has_exited() && !self.held_panes.contains_key(pane_id) {
                 exited.push(*pane_id);
             }
         }
@@ -1437,7 +4076,7 @@ impl App {
     }
 }
 
-impl ApplicationHandler for App {
+impl ApplicationHandler<AppEvent> for App {
     fn resumed(&mut self, event_loop: &ActiveEventLoop) {
         if self.window.is_some() {
             return;
@@ -1447,12 +4086,15 @@ impl ApplicationHandler for App {
         let icon = load_icon();
 
         let mut window_attrs = WindowAttributes::default()
-            .with_title("cool-rust-term")
-            .with_inner_size(LogicalSize::new(
+            .with_title(WINDOW_TITLE)
+            // Saved from Resized events, so already physical pixels
+            .with_inner_size(PhysicalSize::new(
                 self.config.window_width,
                 self.config.window_height,
             ))
-            .with_window_icon(icon);
+            .with_window_icon(icon)
+            // Only ask for an alpha channel when it's used
+            .with_transparent(self.config.effects.background_opacity < 1.0);
 
         // Restore window position if saved
         if let (Some(x), Some(y)) = (self.config.window_x, self.config.window_y) {
@@ -1465,17 +4107,44 @@ impl ApplicationHandler for App {
                 .expect("Failed to create window"),
         );
 
+        // Follow the display's scale factor, fractional ones included
+        self.display_scale = window.scale_factor() as f32;
+        let scale = self.config.effective_scale(self.display_scale);
+
         // Initialize renderer with font from config
-        // Apply ui_scale to font_size for TTF fonts (BDF fonts ignore scaling)
+        // Apply the scale to font_size for TTF fonts (BDF fonts ignore scaling)
         let mut renderer = pollster::block_on(Renderer::new(
             Arc::clone(&window),
             self.config.font,
-            self.config.font_size * self.config.ui_scale,
+            self.config.font_size * scale,
+            &self.config.fallback_fonts,
         ))
         .expect("Failed to create renderer");
+        if let Some(warning) = apply_font_path(&mut renderer, &self.config) {
+            self.toast = Some((warning, Instant::now()));
+        }
+        renderer.set_max_cached_glyphs(self.config.max_cached_glyphs);
+        renderer.set_max_atlas_size(self.config.max_atlas_size);
+        renderer.set_atlas_eviction_policy(self.config.atlas_eviction_policy);
+        renderer.set_scale_factor(scale);
+        renderer.set_cell_spacing(self.config.line_height_mult, self.config.letter_spacing);
+        renderer.set_pane_border_style(self.config.pane_border_style);
+        renderer.set_atlas_compression(if self.config.compress_glyph_atlas {
+            AtlasCompressionMode::Bc4
+        } else {
+            AtlasCompressionMode::Uncompressed
+        });
+        if self.config.effects.background_opacity < 1.0 && !renderer.supports_transparency() {
+            tracing::warn!(
+                "This display doesn't support transparent windows; background_opacity is ignored"
+            );
+        }
 
         // If BDF font is configured, load and apply it
         if let Some(bdf_font) = self.config.bdf_font {
+            if let Some(warning) = apply_bdf_font_path(&mut renderer, &self.config, bdf_font) {
+                self.toast = Some((warning, Instant::now()));
+            }
             if let Err(e) = renderer.set_bdf_font(bdf_font) {
                 tracing::error!("Failed to load BDF font {:?}: {}", bdf_font, e);
             } else {
@@ -1501,18 +4170,22 @@ impl ApplicationHandler for App {
             .unwrap_or(DEFAULT_FPS);
         let target_fps = (refresh_hz * 2).min(240); // 2x refresh rate, capped at 240fps
         self.frame_duration = Duration::from_nanos(1_000_000_000 / target_fps as u64);
+        self.refresh_duration = Duration::from_nanos(1_000_000_000 / refresh_hz.max(1) as u64);
         tracing::info!(
             "Monitor refresh rate: {}Hz, targeting {}fps",
             refresh_hz,
             target_fps
         );
 
+        // Receive composed text from input methods (CJK, dead keys)
+        window.set_ime_allowed(true);
+
         self.window = Some(window);
         self.renderer = Some(renderer);
 
         // Try to load session data for restoration (Unix only, if enabled)
         #[cfg(not(windows))]
-        let session = if self.config.behavior.restore_session {
+        let session = if self.config.behavior.restore_session && !self.one_off {
             SessionData::load_from_default()
         } else {
             None
@@ -1520,46 +4193,64 @@ impl ApplicationHandler for App {
         #[cfg(windows)]
         let session: Option<SessionData> = None;
 
-        // Create terminal for the initial pane
-        let initial_pane = self.layout.focused_pane();
-        if let Some(ref sess) = session {
-            if let Some(pane_session) = sess.panes.first() {
-                self.create_terminal_for_pane_with_session(
-                    initial_pane,
-                    pane_session.cwd.clone(),
-                    Some(&pane_session.scrollback),
-                );
-            } else {
-                self.create_terminal_for_pane(initial_pane);
-            }
-        } else {
-            self.create_terminal_for_pane(initial_pane);
-        }
-
-        // Restore additional panes from saved config (use session data if available)
-        let panes_to_restore = self.config.pane_count.saturating_sub(1);
-        for i in 0..panes_to_restore {
-            let new_pane_id = self.layout.add_pane();
-            self.resize_terminals();
-
-            // Get session data for this pane index (i+1 because first pane is index 0)
-            let pane_idx = (i + 1) as usize;
-            if let Some(ref sess) = session {
-                if let Some(pane_session) = sess.panes.get(pane_idx) {
-                    self.create_terminal_for_pane_with_session(
-                        new_pane_id,
-                        pane_session.cwd.clone(),
-                        Some(&pane_session.scrollback),
-                    );
-                } else {
-                    self.create_terminal_for_pane(new_pane_id);
+        // Lay out every pane before spawning shells so each starts at its
+        // final size: from the saved session, else the saved pane count
+        if !self.one_off {
+            match session.as_ref().and_then(|sess| sess.layout) {
+                Some(snapshot) => {
+                    self.layout = LayoutTree::from_snapshot(&snapshot);
+                    self.layout
+                        .set_min_pane_size(self.config.min_pane_cols, self.config.min_pane_rows);
+                    self.layout
+                        .set_float_rect(floating::rect_from_config(&self.config.floating_pane));
                 }
-            } else {
-                self.create_terminal_for_pane(new_pane_id);
+                None => {
+                    let renderer = self.renderer.as_ref().unwrap();
+                    let window = renderer.window_size();
+                    let fit = self.pane_fit(window);
+                    for _ in 1..self.config.pane_count.max(1) {
+                        let added =
+                            self.layout
+                                .try_add_pane(window.0 as f32, window.1 as f32, |rect| {
+                                    fit.grid_size(renderer, rect, None)
+                                });
+                        if let Err(e) = added {
+                            tracing::warn!("Not restoring every pane: {}", e);
+                            break;
+                        }
+                    }
+                }
+            }
+        }
+
+        // Command-line options take precedence over the saved session for
+        // the first pane
+        let panes = self.layout.panes().to_vec();
+        for (idx, pane_id) in panes.into_iter().enumerate() {
+            let pane_session = session.as_ref().and_then(|sess| sess.pane_at(idx));
+            let mut cwd = pane_session.and_then(|p| p.cwd.clone());
+            let mut command = pane_session
+                .filter(|_| self.config.behavior.restore_commands)
+                .and_then(|p| p.command.as_deref())
+                .and_then(|argv| argv.split_first())
+                .map(|(program, args)| ShellCommand {
+                    program: Some(program.clone()),
+                    args: args.to_vec(),
+                    login: false,
+                });
+            if idx == 0 {
+                cwd = self.initial_working_directory.take().or(cwd);
+                command = self.initial_command.take().or(command);
             }
+            self.create_terminal_for_pane_with_session(
+                pane_id,
+                cwd,
+                pane_session.map(|p| p.scrollback.as_slice()),
+                command,
+            );
         }
-        if panes_to_restore > 0 {
-            tracing::info!("Restored {} additional panes from config", panes_to_restore);
+        if self.layout.panes().len() > 1 {
+            tracing::info!("Restored {} panes", self.layout.panes().len());
         }
         if session.is_some() {
             tracing::info!("Session data restored");
@@ -1569,24 +4260,64 @@ impl ApplicationHandler for App {
         tracing::info!("Window and renderer initialized ({}x{} cells)", cols, rows);
     }
 
+    fn user_event(&mut self, _event_loop: &ActiveEventLoop, event: AppEvent) {
+        match event {
+            AppEvent::PtyOutput => {
+                self.pty_wakeup_pending.store(false, Ordering::Release);
+                self.needs_redraw = true;
+            }
+        }
+    }
+
+    fn about_to_wait(&mut self, event_loop: &ActiveEventLoop) {
+        self.update_bell();
+        self.update_clipboard_requests();
+        self.update_window_title();
+        self.update_recording();
+        self.update_monitors();
+        self.update_disk_scrollback();
+        self.update_search();
+        self.update_smooth_scroll();
+        self.update_wheel_scroll();
+        self.update_selection_autoscroll();
+
+        // Sleep until the next frame is due; PTY output and input wake us early
+        match self.next_frame_deadline() {
+            Some(deadline) if deadline <= Instant::now() => {
+                if let Some(window) = &self.window {
+                    window.request_redraw();
+                }
+                event_loop.set_control_flow(ControlFlow::Wait);
+            }
+            Some(deadline) => event_loop.set_control_flow(ControlFlow::WaitUntil(deadline)),
+            None => event_loop.set_control_flow(ControlFlow::Wait),
+        }
+    }
+
     fn window_event(
         &mut self,
         event_loop: &ActiveEventLoop,
         _window_id: WindowId,
         event: WindowEvent,
     ) {
+        if !matches!(event, WindowEvent::RedrawRequested) {
+            self.needs_redraw = true;
+        }
+
         match event {
             WindowEvent::CloseRequested => {
                 // Save session data (scrollback + cwd for each pane) if enabled
                 #[cfg(not(windows))]
-                if self.config.behavior.restore_session {
+                if self.config.behavior.restore_session && !self.one_off {
                     let mut session = SessionData::new();
+                    session.layout = Some(self.layout.snapshot());
                     for (idx, pane_id) in self.layout.panes().iter().enumerate() {
                         if let Some(terminal) = self.terminals.get(pane_id) {
                             let scrollback = terminal.capture_scrollback();
                             let compressed = scrollback.compress().unwrap_or_default();
                             let cwd = terminal.working_directory();
-                            session.add_pane(compressed, cwd, idx);
+                            let command = terminal.foreground_command();
+                            session.add_pane(compressed, cwd, idx, command);
                         }
                     }
                     if let Err(e) = session.save_to_default() {
@@ -1597,8 +4328,10 @@ impl ApplicationHandler for App {
                 }
 
                 // Save window state before exiting
-                self.config.pane_count = self.layout.panes().len() as u32;
-                if let Err(e) = self.config.save_to_default() {
+                if !self.one_off {
+                    self.config.pane_count = self.layout.panes().len() as u32;
+                }
+                if let Err(e) = self.save_config() {
                     tracing::error!("Failed to save window state: {}", e);
                 } else {
                     tracing::info!("Window state saved");
@@ -1611,7 +4344,63 @@ impl ApplicationHandler for App {
                 self.config.window_x = Some(position.x);
                 self.config.window_y = Some(position.y);
             }
+            WindowEvent::HoveredFile(_) => {
+                // winit sends one of these per file before any are dropped
+                self.dropped_files = 0;
+                if let Some(window) = &self.window {
+                    window.set_cursor(CursorIcon::Copy);
+                }
+            }
+            WindowEvent::HoveredFileCancelled => {
+                if let Some(window) = &self.window {
+                    window.set_cursor(CursorIcon::Default);
+                }
+            }
+            WindowEvent::DroppedFile(path) => {
+                if let Some(window) = &self.window {
+                    window.set_cursor(CursorIcon::Default);
+                }
+                let path = path.to_string_lossy();
+                let mut text = if self.config.behavior.quote_dropped_paths {
+                    paste::shell_quote(&path)
+                } else {
+                    path.into_owned()
+                };
+                if self.dropped_files > 0 {
+                    text.insert(0, ' ');
+                }
+                self.dropped_files += 1;
+                if let Some(terminal) = self.terminals.get(&self.layout.focused_pane()) {
+                    terminal.scroll_to_bottom();
+                    terminal.input(text.as_bytes());
+                }
+            }
+            WindowEvent::Occluded(occluded) => {
+                self.set_hidden(occluded, self.minimized);
+            }
+            WindowEvent::Focused(focused) => {
+                self.window_focused = focused;
+                // Only the focused pane sees the window gain or lose focus
+                if let Some(terminal) = self.terminals.get(&self.layout.focused_pane()) {
+                    terminal.report_focus(focused);
+                }
+            }
+            WindowEvent::ScaleFactorChanged { scale_factor, .. } => {
+                // The window's new physical size follows in a Resized event
+                tracing::info!("Display scale factor changed to {}", scale_factor);
+                self.display_scale = scale_factor as f32;
+                if self.config.auto_scale {
+                    self.apply_scale();
+                }
+            }
             WindowEvent::Resized(new_size) => {
+                // Minimizing shrinks the window to nothing on some platforms;
+                // keep the terminals at their size until it comes back
+                let minimized = new_size.width == 0 || new_size.height == 0;
+                self.set_hidden(self.occluded, minimized);
+                if minimized {
+                    return;
+                }
                 if let Some(renderer) = &mut self.renderer {
                     renderer.resize(new_size.width, new_size.height);
                     self.resize_terminals();
@@ -1622,11 +4411,20 @@ impl ApplicationHandler for App {
                 self.config.window_height = new_size.height;
             }
             WindowEvent::RedrawRequested => {
-                // Check for exited terminals and close their panes
+                // Check for exited terminals and close or hold their panes
                 let exited = self.check_exited_terminals();
                 for pane_id in exited {
-                    tracing::info!("Shell in pane {:?} exited", pane_id);
-                    self.close_pane(pane_id);
+                    let code = self
+                        .terminals
+                        .get(&pane_id)
+                        .and_then(|terminal| terminal.exit_code());
+                    tracing::info!("Shell in pane {:?} exited with {:?}", pane_id, code);
+                    if self.config.behavior.exit_behavior.holds(code) {
+                        self.held_panes.insert(pane_id, code);
+                        self.needs_redraw = true;
+                    } else {
+                        self.close_pane(pane_id);
+                    }
                 }
 
                 // Exit if no panes remain
@@ -1636,38 +4434,107 @@ impl ApplicationHandler for App {
                     return;
                 }
 
+                // Nothing would be seen; PTY output is still read meanwhile
+                if self.hidden() {
+                    return;
+                }
+
                 // Frame rate limiting - skip render if too soon
                 let now = Instant::now();
                 let elapsed = now.duration_since(self.last_frame);
-                if elapsed >= self.frame_duration {
-                    let dt = elapsed.as_secs_f32();
-                    self.last_frame = now;
-                    self.render_terminals(dt);
+                let interval = self.frame_interval();
+                if elapsed >= interval {
+                    if self.frame_needed() {
+                        let dt = elapsed.as_secs_f32();
+                        if let Some(perf) = &mut self.perf {
+                            perf.record_frame(elapsed, interval);
+                        }
+                        self.last_frame = now;
+                        self.render_terminals(dt);
+                    }
                 } else {
-                    // Sleep for remaining time to avoid busy-waiting
-                    std::thread::sleep(self.frame_duration - elapsed);
-                }
-
-                if let Some(window) = &self.window {
-                    window.request_redraw();
+                    // Too soon: about_to_wait schedules it for the next frame slot
+                    self.needs_redraw = true;
                 }
             }
             WindowEvent::ModifiersChanged(modifiers) => {
                 self.modifiers = modifiers.state();
+                // Pressing or releasing Alt mid-drag switches between line and block selection
+                if self.selection.active {
+                    self.selection_mode = if self.modifiers.alt_key() {
+                        SelectionMode::Block
+                    } else {
+                        SelectionMode::Linear
+                    };
+                }
             }
             WindowEvent::CursorMoved { position, .. } => {
-                self.mouse_pos = (position.x, position.y);
-                if self.selection.active {
+                // Layout works in logical pixels
+                let scale = self
+                    .renderer
+                    .as_ref()
+                    .map_or(1.0, |renderer| renderer.scale_factor() as f64);
+                self.mouse_pos = (position.x / scale, position.y / scale);
+                if let Some(grab) = self.float_drag {
+                    self.drag_float(grab);
+                    return;
+                }
+                if !self.report_mouse_motion() && self.selection.active {
                     // Only update selection if pointing at valid content (not the void)
-                    if let Some(pos) = self.pixel_to_cell(position.x, position.y) {
+                    if let Some(mut pos) = self.pixel_to_cell(self.mouse_pos.0, self.mouse_pos.1) {
+                        // Below the pane the selection stops at the last
+                        // row; auto-scroll takes it further
+                        if let Some(terminal) = self.terminals.get(&self.layout.focused_pane()) {
+                            let (_, rows) = terminal.size();
+                            let last_row = rows as i32 - 1 - terminal.display_offset() as i32;
+                            pos.row = pos.row.min(last_row);
+                        }
                         self.selection.end = pos;
                     }
                 }
             }
+            // The settings overlay covers the panes: leave their scroll
+            // position and selection alone
+            WindowEvent::MouseInput { .. } | WindowEvent::MouseWheel { .. }
+                if self.config_ui.visible => {}
+            WindowEvent::MouseInput {
+                state,
+                button: button @ (MouseButton::Middle | MouseButton::Right),
+                ..
+            } => {
+                if self.report_mouse_button(button, state) {
+                    return;
+                }
+                // Middle click pastes the primary selection, like xterm
+                if button == MouseButton::Middle
+                    && state == ElementState::Pressed
+                    && self.config.behavior.use_primary_selection
+                {
+                    if let Some(clipboard) = &mut self.clipboard {
+                        let text = get_primary_selection(clipboard);
+                        if !text.is_empty() {
+                            self.paste(text);
+                        }
+                    }
+                }
+            }
+            #[allow(clippy::collapsible_match)]
             WindowEvent::MouseInput { state, button, .. } => {
                 if button == MouseButton::Left {
                     match state {
                         ElementState::Pressed => {
+                            // A floating pane's title bar drags it around
+                            if let Some(pane) =
+                                self.float_header_at(self.mouse_pos.0, self.mouse_pos.1)
+                            {
+                                self.focus_pane(pane);
+                                let rect = self.layout.float_rect();
+                                let (norm_x, norm_y) =
+                                    self.pixel_to_normalized(self.mouse_pos.0, self.mouse_pos.1);
+                                self.float_drag = Some((norm_x - rect.x, norm_y - rect.y));
+                                return;
+                            }
+
                             // Hit test to change focus
                             if let Some(renderer) = &self.renderer {
                                 let (win_width, win_height) = renderer.window_size();
@@ -1680,15 +4547,20 @@ impl ApplicationHandler for App {
                                     win_height as f32,
                                 ) {
                                     if clicked_pane != self.layout.focused_pane() {
-                                        self.layout.set_focus(clicked_pane);
+                                        self.focus_pane(clicked_pane);
                                         tracing::info!("Focus changed to pane {:?}", clicked_pane);
                                     }
                                 }
                             }
 
+                            // Programs with mouse reporting get the click instead
+                            let reported =
+                                self.report_mouse_button(MouseButton::Left, ElementState::Pressed);
+
                             // Only start selection if pointing at valid content (not the void)
-                            if let Some(pos) =
-                                self.pixel_to_cell(self.mouse_pos.0, self.mouse_pos.1)
+                            if let Some(pos) = self
+                                .pixel_to_cell(self.mouse_pos.0, self.mouse_pos.1)
+                                .filter(|_| !reported)
                             {
                                 let now = Instant::now();
 
@@ -1715,6 +4587,7 @@ impl ApplicationHandler for App {
                                             self.selection.start = start;
                                             self.selection.end = end;
                                             self.selection.active = false;
+                                            self.selection_mode = SelectionMode::Linear;
                                         }
                                     }
                                     3 => {
@@ -1723,15 +4596,21 @@ impl ApplicationHandler for App {
                                             self.selection.start = start;
                                             self.selection.end = end;
                                             self.selection.active = false;
+                                            self.selection_mode = SelectionMode::Linear;
                                         }
                                         // Reset after triple-click
                                         self.click_count = 0;
                                     }
                                     _ => {
-                                        // Single click: start normal selection
+                                        // Single click: start selection (Alt for block mode)
                                         self.selection.start = pos;
                                         self.selection.end = pos;
                                         self.selection.active = true;
+                                        self.selection_mode = if self.modifiers.alt_key() {
+                                            SelectionMode::Block
+                                        } else {
+                                            SelectionMode::Linear
+                                        };
                                     }
                                 }
 
@@ -1740,19 +4619,73 @@ impl ApplicationHandler for App {
                             }
                         }
                         ElementState::Released => {
+                            if self.float_drag.take().is_some() {
+                                return;
+                            }
+                            if self.report_mouse_button(MouseButton::Left, ElementState::Released) {
+                                return;
+                            }
                             self.selection.active = false;
+                            self.selection_autoscroll = None;
                             if self.config.behavior.auto_copy_selection {
                                 self.copy_selection();
+                            } else if self.selection.start != self.selection.end {
+                                // A plain click shouldn't replace what's in primary
+                                self.select_to_primary();
                             }
                         }
                     }
                 }
             }
+            WindowEvent::MouseWheel { delta, .. } if self.mouse_reporting() => {
+                // The program scrolls itself: one wheel press per notch
+                self.scroll_accumulator += match delta {
+                    MouseScrollDelta::LineDelta(_, y) => y as f64,
+                    MouseScrollDelta::PixelDelta(pos) => pos.y / 20.0,
+                };
+                let steps = self.scroll_accumulator as i32;
+                self.scroll_accumulator -= steps as f64;
+                let button = if steps > 0 {
+                    mouse_reporting::WHEEL_UP
+                } else {
+                    mouse_reporting::WHEEL_DOWN
+                };
+                if let Some(cell) = self.mouse_report_cell() {
+                    for _ in 0..steps.unsigned_abs() {
+                        self.send_mouse_report(button, MouseEventKind::Press, cell);
+                    }
+                }
+            }
+            WindowEvent::MouseWheel { delta, .. } if self.alternate_scroll() => {
+                // Full-screen programs like less get arrow keys to scroll with
+                self.scroll_accumulator += match delta {
+                    MouseScrollDelta::LineDelta(_, y) => {
+                        y as f64 * mouse_reporting::ALTERNATE_SCROLL_LINES
+                    }
+                    MouseScrollDelta::PixelDelta(pos) => pos.y / 20.0,
+                };
+                let lines = self.scroll_accumulator as i32;
+                self.scroll_accumulator -= lines as f64;
+                if let Some(terminal) = self.terminals.get(&self.layout.focused_pane()) {
+                    let keys = mouse_reporting::alternate_scroll_keys(lines, terminal.term_mode());
+                    if !keys.is_empty() {
+                        terminal.input(&keys);
+                    }
+                }
+            }
             WindowEvent::MouseWheel { delta, .. } => {
                 // Scroll the focused terminal
                 let focused = self.layout.focused_pane();
                 if let Some(terminal) = self.terminals.get(&focused) {
                     let lines = match delta {
+                        MouseScrollDelta::LineDelta(_, y) if self.config.behavior.smooth_scroll => {
+                            // Glide; update_wheel_scroll does the scrolling
+                            self.wheel_scroll
+                                .entry(focused)
+                                .or_insert_with(SmoothScroll::new)
+                                .add_notches(y, self.config.behavior.smooth_scroll_speed);
+                            0
+                        }
                         MouseScrollDelta::LineDelta(_, y) => {
                             // Accumulate fractional line deltas (touchpads often send these)
                             self.scroll_accumulator += y as f64 * 3.0;
@@ -1760,6 +4693,24 @@ impl ApplicationHandler for App {
                             self.scroll_accumulator -= lines as f64;
                             lines
                         }
+                        MouseScrollDelta::PixelDelta(pos) if self.config.behavior.smooth_scroll => {
+                            // Move the content by the exact distance; whole
+                            // lines scroll the terminal, the rest is drawn offset
+                            let font_size = pane_font_size(
+                                &self.config,
+                                &self.layout,
+                                focused,
+                                self.display_scale,
+                            );
+                            let (scale, cell_h) = self.renderer.as_ref().map_or((1.0, 1.0), |r| {
+                                (r.scale_factor() as f64, r.cell_size_for(font_size).1)
+                            });
+                            let offset = self.scroll_px_offset.entry(focused).or_insert(0.0);
+                            *offset += (pos.y / scale) as f32;
+                            let lines = (*offset / cell_h).trunc();
+                            *offset -= lines * cell_h;
+                            lines as i32
+                        }
                         MouseScrollDelta::PixelDelta(pos) => {
                             // Touchpad pixel mode: accumulate and convert
                             self.scroll_accumulator += pos.y / 20.0;
@@ -1770,6 +4721,17 @@ impl ApplicationHandler for App {
                     };
                     if lines != 0 {
                         terminal.scroll(lines);
+                    }
+                    // Nothing lies beyond either end of the scrollback
+                    if let Some(offset) = self.scroll_px_offset.get_mut(&focused) {
+                        let display_offset = terminal.display_offset();
+                        if (*offset < 0.0 && display_offset == 0)
+                            || (*offset > 0.0 && display_offset >= terminal.history_size())
+                        {
+                            self.scroll_px_offset.remove(&focused);
+                        }
+                    }
+                    if lines != 0 || self.scroll_px_offset.contains_key(&focused) {
                         self.last_scroll.insert(focused, Instant::now());
 
                         // Update selection end if actively selecting while scrolling
@@ -1783,129 +4745,65 @@ impl ApplicationHandler for App {
                     }
                 }
             }
+            WindowEvent::Ime(ime) => match ime {
+                Ime::Preedit(text, _cursor) => {
+                    self.ime_preedit = (!text.is_empty()).then_some(text);
+                }
+                Ime::Commit(text) => {
+                    self.ime_preedit = None;
+                    let focused = self.layout.focused_pane();
+                    if let Some(terminal) = self.terminals.get(&focused) {
+                        terminal.scroll_to_bottom();
+                        terminal.input(text.as_bytes());
+                        self.cursor_blink_reset = Instant::now();
+                    }
+                }
+                Ime::Disabled => {
+                    self.ime_preedit = None;
+                }
+                Ime::Enabled => {}
+            },
             WindowEvent::KeyboardInput { event, .. } => {
                 if event.state == ElementState::Pressed {
                     let ctrl = self.modifiers.control_key();
-                    let shift = self.modifiers.shift_key();
-                    let super_key = self.modifiers.super_key();
-
-                    // Shift+Ctrl+Enter: Add new pane
-                    if ctrl && shift && event.logical_key == Key::Named(NamedKey::Enter) {
-                        self.add_pane();
-                        return;
-                    }
 
-                    // Ctrl+, or Ctrl+Shift+P: Open config UI
-                    if (ctrl && event.logical_key == Key::Character(",".into()))
-                        || (ctrl && shift && event.logical_key == Key::Character("P".into()))
+                    // Configurable shortcuts take priority over the PTY
+                    if let Some(action) = key_combo(&event.logical_key, self.modifiers)
+                        .and_then(|combo| self.keybindings.lookup(&combo))
                     {
-                        if self.config_ui.visible {
-                            self.config_ui.hide();
-                        } else {
-                            self.config_ui.show(&self.config);
-                        }
-                        return;
-                    }
-
-                    // Ctrl+Shift+G: Toggle debug grid
-                    if ctrl && shift && event.logical_key == Key::Character("G".into()) {
-                        self.debug_grid = !self.debug_grid;
-                        tracing::info!("Debug grid: {}", self.debug_grid);
-                        return;
-                    }
-
-                    // Ctrl+Shift+B: Toggle beam pause (freeze beam position for debugging)
-                    if ctrl && shift && event.logical_key == Key::Character("B".into()) {
-                        self.beam_paused = !self.beam_paused;
-                        tracing::info!("Beam paused: {}", self.beam_paused);
-                        return;
-                    }
-
-                    // Ctrl+Shift+N: Hold to step frames forward (when beam is paused)
-                    if ctrl && shift && event.logical_key == Key::Character("N".into()) {
-                        if self.beam_paused {
-                            self.beam_step_held = true;
-                            // Immediate first step
-                            self.beam_step_last = Instant::now()
-                                - Duration::from_millis(self.beam_step_delay_ms as u64);
+                        if self.run_action(action) {
+                            return;
                         }
-                        return;
-                    }
-
-                    // Ctrl+Shift+=: Decrease step delay (faster stepping)
-                    if ctrl
-                        && shift
-                        && (event.logical_key == Key::Character("=".into())
-                            || event.logical_key == Key::Character("+".into()))
-                    {
-                        self.beam_step_delay_ms =
-                            (self.beam_step_delay_ms.saturating_sub(10)).max(4);
-                        tracing::info!(
-                            "Beam step delay: {}ms ({:.1} fps)",
-                            self.beam_step_delay_ms,
-                            1000.0 / self.beam_step_delay_ms as f32
-                        );
-                        return;
-                    }
-
-                    // Ctrl+Shift+-: Increase step delay (slower stepping)
-                    if ctrl && shift && event.logical_key == Key::Character("-".into()) {
-                        self.beam_step_delay_ms = (self.beam_step_delay_ms + 10).min(500);
-                        tracing::info!(
-                            "Beam step delay: {}ms ({:.1} fps)",
-                            self.beam_step_delay_ms,
-                            1000.0 / self.beam_step_delay_ms as f32
-                        );
-                        return;
-                    }
-
-                    // Ctrl+Shift+C or Cmd+C: Copy selection
-                    if (ctrl && shift && event.logical_key == Key::Character("C".into()))
-                        || (super_key && event.logical_key == Key::Character("c".into()))
-                    {
-                        self.copy_selection();
-                        return;
                     }
 
-                    // Ctrl+Shift+V or Cmd+V: Paste from clipboard
-                    if (ctrl && shift && event.logical_key == Key::Character("V".into()))
-                        || (super_key && event.logical_key == Key::Character("v".into()))
-                    {
-                        if let Some(clipboard) = &mut self.clipboard {
-                            if let Ok(text) = clipboard.get_text() {
-                                let focused = self.layout.focused_pane();
-                                if let Some(terminal) = self.terminals.get(&focused) {
-                                    terminal.input(text.as_bytes());
-                                }
+                    // The color scheme path prompt takes every key while it's open
+                    if self.config_ui.visible && self.config_ui.editing_scheme_path() {
+                        match &event.logical_key {
+                            Key::Named(NamedKey::Escape) => self.config_ui.cancel_scheme_path(),
+                            Key::Named(NamedKey::Enter) => self.config_ui.accept_scheme_path(),
+                            Key::Named(NamedKey::Backspace) => {
+                                self.config_ui.scheme_path_backspace()
                             }
+                            Key::Named(NamedKey::Space) => self.config_ui.type_scheme_path(" "),
+                            Key::Character(s) if !ctrl => self.config_ui.type_scheme_path(s),
+                            _ => {}
                         }
                         return;
                     }
 
-                    // Ctrl+Shift+T: Replay CRT power-on animation
-                    if ctrl && shift && event.logical_key == Key::Character("T".into()) {
-                        if let Some(renderer) = &mut self.renderer {
-                            renderer.replay_power_on();
-                        }
-                        return;
-                    }
-
-                    // Shift+PageUp/PageDown: Scroll history
-                    if shift && !ctrl && event.logical_key == Key::Named(NamedKey::PageUp) {
-                        let focused = self.layout.focused_pane();
-                        if let Some(terminal) = self.terminals.get(&focused) {
-                            terminal.scroll_page_up();
-                            self.last_scroll.insert(focused, Instant::now());
-                        }
-                        return;
-                    }
-                    if shift && !ctrl && event.logical_key == Key::Named(NamedKey::PageDown) {
-                        let focused = self.layout.focused_pane();
-                        if let Some(terminal) = self.terminals.get(&focused) {
-                            terminal.scroll_page_down();
-                            self.last_scroll.insert(focused, Instant::now());
+                    // The open color editor takes Tab and Escape for itself
+                    if self.config_ui.visible && self.config_ui.color_editor_open() {
+                        match &event.logical_key {
+                            Key::Named(NamedKey::Escape) => {
+                                self.config_ui.close_color_editor();
+                                return;
+                            }
+                            Key::Named(NamedKey::Tab) => {
+                                self.config_ui.next_color(!self.modifiers.shift_key());
+                                return;
+                            }
+                            _ => {}
                         }
-                        return;
                     }
 
                     // Handle config UI navigation when visible
@@ -1913,6 +4811,7 @@ impl ApplicationHandler for App {
                         match &event.logical_key {
                             Key::Named(NamedKey::Escape) => {
                                 self.config = self.config_ui.cancel();
+                                self.settings_closed();
                             }
                             Key::Named(NamedKey::ArrowUp) => {
                                 self.config_ui.move_up();
@@ -1951,6 +4850,16 @@ impl ApplicationHandler for App {
                                     match action {
                                         ConfigAction::Save => {
                                             let new_config = self.config_ui.save();
+                                            // Spacing, the status bar and pane font sizes
+                                            // change the grid size too
+                                            let spacing_changed = new_config.line_height_mult
+                                                != self.config.line_height_mult
+                                                || new_config.letter_spacing
+                                                    != self.config.letter_spacing
+                                                || new_config.behavior.status_bar
+                                                    != self.config.behavior.status_bar
+                                                || new_config.per_pane_font_size
+                                                    != self.config.per_pane_font_size;
                                             // Update font if changed
                                             if let Some(renderer) = &mut self.renderer {
                                                 let font_changed = new_config.bdf_font
@@ -1962,7 +4871,15 @@ impl ApplicationHandler for App {
                                                         > 0.1
                                                     || (new_config.ui_scale - self.config.ui_scale)
                                                         .abs()
-                                                        > 0.01;
+                                                        > 0.01
+                                                    || new_config.auto_scale
+                                                        != self.config.auto_scale;
+                                                if spacing_changed {
+                                                    renderer.set_cell_spacing(
+                                                        new_config.line_height_mult,
+                                                        new_config.letter_spacing,
+                                                    );
+                                                }
 
                                                 if font_changed {
                                                     // Apply the appropriate font type
@@ -1984,7 +4901,10 @@ impl ApplicationHandler for App {
                                                         }
                                                     } else if let Err(e) = renderer.set_font(
                                                         new_config.font,
-                                                        new_config.font_size * new_config.ui_scale,
+                                                        new_config.font_size
+                                                            * new_config.effective_scale(
+                                                                self.display_scale,
+                                                            ),
                                                     ) {
                                                         tracing::error!(
                                                             "Failed to change font: {}",
@@ -2002,7 +4922,8 @@ impl ApplicationHandler for App {
                                                 }
                                             }
                                             self.config = new_config;
-                                            if let Err(e) = self.config.save_to_default() {
+                                            self.settings_closed();
+                                            if let Err(e) = self.save_config() {
                                                 tracing::error!("Failed to save config: {}", e);
                                             } else {
                                                 tracing::info!("Config saved");
@@ -2010,6 +4931,7 @@ impl ApplicationHandler for App {
                                         }
                                         ConfigAction::Cancel => {
                                             self.config = self.config_ui.cancel();
+                                            self.settings_closed();
                                         }
                                     }
                                 }
@@ -2019,16 +4941,119 @@ impl ApplicationHandler for App {
                         return;
                     }
 
-                    // Send input to focused terminal
+                    // After swap pane, an arrow picks the other pane; anything else cancels
+                    if self.swap_pending {
+                        self.swap_pending = false;
+                        let direction = match &event.logical_key {
+                            Key::Named(NamedKey::ArrowLeft) => Some(Direction::Left),
+                            Key::Named(NamedKey::ArrowRight) => Some(Direction::Right),
+                            Key::Named(NamedKey::ArrowUp) => Some(Direction::Up),
+                            Key::Named(NamedKey::ArrowDown) => Some(Direction::Down),
+                            _ => None,
+                        };
+                        if let Some(direction) = direction {
+                            self.swap_focused_pane(direction);
+                        }
+                        return;
+                    }
+
+                    // The command palette takes every key while it's open
+                    if self.command_palette.visible {
+                        self.handle_palette_key(&event.logical_key);
+                        return;
+                    }
+
+                    // So does the search bar, except for bound shortcuts
+                    if self.search_bar.is_some() {
+                        self.handle_search_key(&event.logical_key);
+                        return;
+                    }
+
+                    // A paste waiting for confirmation takes every key
+                    if self.pending_paste.is_some() {
+                        self.handle_pending_paste_key(&event.logical_key);
+                        return;
+                    }
+
+                    // Hint mode takes every key until a label is chosen
+                    if self.hint_mode.is_some() {
+                        self.handle_hint_key(&event.logical_key);
+                        return;
+                    }
+
+                    // Copy mode takes every key; unmapped ones do nothing
+                    if self.copy_mode.is_some() {
+                        if let Some(command) = copy_mode::command_for_key(&event.logical_key, ctrl)
+                        {
+                            self.handle_copy_mode_key(command);
+                        }
+                        return;
+                    }
+
+                    // While composing, keys belong to the IME; the result arrives as Ime::Commit
+                    if self.ime_preedit.is_some() {
+                        return;
+                    }
+
+                    // A held pane has no process left; Enter closes it
                     let focused = self.layout.focused_pane();
+                    if self.held_panes.contains_key(&focused) {
+                        if event.logical_key == Key::Named(NamedKey::Enter) {
+                            self.close_pane(focused);
+                            if self.layout.panes().is_empty() {
+                                tracing::info!("All panes closed, exiting");
+                                event_loop.exit();
+                            }
+                        }
+                        return;
+                    }
+
+                    // Escape puts a floating pane away, unless a full-screen
+                    // program in it (an editor, a pager) wants the key
+                    if event.logical_key == Key::Named(NamedKey::Escape)
+                        && self.modifiers.is_empty()
+                        && self.layout.is_floating(focused)
+                        && self.terminals.get(&focused).is_some_and(|terminal| {
+                            !terminal.term_mode().contains(TermMode::ALT_SCREEN)
+                        })
+                    {
+                        self.hide_floating();
+                        return;
+                    }
+
+                    // Send input to focused terminal
                     if let Some(terminal) = self.terminals.get(&focused) {
                         let mode = terminal.term_mode();
-                        let use_kitty = mode.contains(TermMode::DISAMBIGUATE_ESC_CODES);
+                        let use_kitty = terminal.kitty_keyboard_flags() != 0;
 
                         // Convert key to bytes and send to terminal
+                        let numpad = event.location == KeyLocation::Numpad;
                         let bytes: Option<Vec<u8>> = if use_kitty {
                             // Use Kitty keyboard protocol
-                            kitty_keyboard::encode(&event.logical_key, self.modifiers, mode)
+                            let event_type = if event.repeat {
+                                KeyEventType::Repeat
+                            } else {
+                                KeyEventType::Press
+                            };
+                            let encode = if numpad {
+                                keypad::encode_kitty
+                            } else {
+                                kitty_keyboard::encode
+                            };
+                            encode(&event.logical_key, self.modifiers, mode, event_type)
+                        } else if let Some(bytes) = numpad
+                            .then(|| {
+                                keypad::encode_legacy(&event.logical_key, self.modifiers, mode)
+                            })
+                            .flatten()
+                        {
+                            Some(bytes)
+                        } else if let Some(bytes) = modify_other_keys::encode(
+                            &event.logical_key,
+                            self.modifiers,
+                            terminal.modify_other_keys(),
+                        ) {
+                            Some(bytes)
                         } else {
                             // Legacy escape sequence encoding
                             let alt = self.modifiers.alt_key();
@@ -2118,7 +5143,8 @@ impl ApplicationHandler for App {
                                             Some(vec![b' '])
                                         }
                                     }
-                                    _ => None,
+                                    // F-keys and Menu, or nothing
+                                    _ => function_keys::encode_legacy(named, self.modifiers),
                                 },
                                 _ => None,
                             }
@@ -2126,17 +5152,43 @@ impl ApplicationHandler for App {
 
                         if let Some(ref bytes) = bytes {
                             // Auto-scroll to bottom when typing
+                            self.wheel_scroll.remove(&focused);
                             terminal.scroll_to_bottom();
                             terminal.input(bytes);
+                            self.cursor_blink_reset = Instant::now();
+                            if use_kitty {
+                                self.kitty_pressed_keys.insert(event.physical_key);
+                            }
                         }
                     }
                 } else if event.state == ElementState::Released {
-                    // Handle key releases
-                    if event.logical_key == Key::Character("N".into())
-                        || event.logical_key == Key::Character("n".into())
-                    {
+                    // Releasing the step key (with or without modifiers) stops stepping
+                    let step_released = key_name(&event.logical_key)
+                        .is_some_and(|key| self.keybindings.uses_key(Action::BeamStep, &key));
+                    if step_released {
                         self.beam_step_held = false;
                     }
+
+                    // Report releases of keys whose press went to the application
+                    if self.kitty_pressed_keys.remove(&event.physical_key) {
+                        let focused = self.layout.focused_pane();
+                        if let Some(terminal) = self.terminals.get(&focused) {
+                            let encode = if event.location == KeyLocation::Numpad {
+                                keypad::encode_kitty
+                            } else {
+                                kitty_keyboard::encode
+                            };
+                            let bytes = encode(
+                                &event.logical_key,
+                                self.modifiers,
+                                terminal.term_mode(),
+                                KeyEventType::Release,
+                            );
+                            if let Some(bytes) = bytes {
+                                terminal.input(&bytes);
+                            }
+                        }
+                    }
                 }
             }
             _ => {}
@@ -2144,6 +5196,58 @@ impl ApplicationHandler for App {
     }
 }
 
+/// Canonical keybinding name for a key (see `crt_core::KeyCombo`)
+fn key_name(key: &Key) -> Option<String> {
+    let name = match key {
+        Key::Character(s) => return Some(s.to_lowercase()),
+        Key::Named(named) => match named {
+            NamedKey::Enter => "enter",
+            NamedKey::Tab => "tab",
+            NamedKey::Space => "space",
+            NamedKey::Escape => "escape",
+            NamedKey::Backspace => "backspace",
+            NamedKey::Delete => "delete",
+            NamedKey::Insert => "insert",
+            NamedKey::Home => "home",
+            NamedKey::End => "end",
+            NamedKey::PageUp => "pageup",
+            NamedKey::PageDown => "pagedown",
+            NamedKey::ArrowUp => "up",
+            NamedKey::ArrowDown => "down",
+            NamedKey::ArrowLeft => "left",
+            NamedKey::ArrowRight => "right",
+            NamedKey::F1 => "f1",
+            NamedKey::F2 => "f2",
+            NamedKey::F3 => "f3",
+            NamedKey::F4 => "f4",
+            NamedKey::F5 => "f5",
+            NamedKey::F6 => "f6",
+            NamedKey::F7 => "f7",
+            NamedKey::F8 => "f8",
+            NamedKey::F9 => "f9",
+            NamedKey::F10 => "f10",
+            NamedKey::F11 => "f11",
+            NamedKey::F12 => "f12",
+            _ => return None,
+        },
+        _ => return None,
+    };
+    Some(name.to_string())
+}
+
+/// The key combo for a key press, for looking up keybindings
+fn key_combo(key: &Key, modifiers: ModifiersState) -> Option<KeyCombo> {
+    key_name(key).map(|name| {
+        KeyCombo::new(
+            &name,
+            modifiers.control_key(),
+            modifiers.shift_key(),
+            modifiers.alt_key(),
+            modifiers.super_key(),
+        )
+    })
+}
+
 fn load_icon() -> Option<Icon> {
     let icon_bytes = include_bytes!("../../../assets/icon.png");
     let image = image::load_from_memory(icon_bytes).ok()?.into_rgba8();
@@ -2152,16 +5256,42 @@ fn load_icon() -> Option<Icon> {
 }
 
 fn main() -> Result<()> {
-    // Force 1:1 pixel scaling on X11 (winit guesses wrong sometimes)
-    // TODO: Make this configurable for high-DPI displays
-    std::env::set_var("WINIT_X11_SCALE_FACTOR", "1");
+    let cli = match cli::parse(std::env::args().skip(1)) {
+        Ok(CliAction::Run(cli)) => cli,
+        Ok(CliAction::Print(text)) => {
+            println!("{}", text);
+            return Ok(());
+        }
+        Err(e) => {
+            eprintln!("cool-rust-term: {}\n\n{}", e, cli::USAGE);
+            std::process::exit(2);
+        }
+    };
 
     tracing_subscriber::fmt::init();
 
     tracing::info!("Starting cool-rust-term");
 
-    let event_loop = EventLoop::new()?;
-    let mut app = App::new();
+    // An explicit config file that exists must parse; a missing one starts
+    // from defaults and is created on save
+    let config = match &cli.config_path {
+        Some(path) if path.exists() => match Config::load(path) {
+            Ok(config) => config,
+            Err(e) => {
+                eprintln!("cool-rust-term: {}: {}", path.display(), e);
+                std::process::exit(1);
+            }
+        },
+        Some(_) => Config::default(),
+        None => Config::load_or_default(),
+    };
+
+    if config.behavior.scrollback_lines == usize::MAX {
+        tracing::warn!("Scrollback is unlimited; long-running output will keep using more memory");
+    }
+
+    let event_loop = EventLoop::<AppEvent>::with_user_event().build()?;
+    let mut app = App::new(event_loop.create_proxy(), config, cli);
 
     event_loop.run_app(&mut app)?;
 
@@ -0,0 +1,152 @@
+// ABOUTME: xterm modifyOtherKeys encoder: CSI 27 ; modifier ; codepoint ~ for modified keys.
+// ABOUTME: Lets apps like emacs tell Ctrl+; from ; without the Kitty protocol.
+
+use winit::keyboard::{Key, ModifiersState, NamedKey};
+
+const SHIFT: u8 = 1;
+const ALT: u8 = 2;
+const CTRL: u8 = 4;
+
+/// Encode a key press for modifyOtherKeys `level` (1 or 2).
+/// Returns None when the key should keep its legacy encoding: at level 0,
+/// for unmodified keys and shift-only characters, for keys with their own
+/// modified forms (arrows, function keys), and at level 1 for combos that
+/// legacy encoding already tells apart (Ctrl+letter, Alt+key, Shift+key).
+pub fn encode(key: &Key, modifiers: ModifiersState, level: u8) -> Option<Vec<u8>> {
+    if level == 0 {
+        return None;
+    }
+    let mods = modifier_flags(modifiers);
+    if mods == 0 {
+        return None;
+    }
+
+    let codepoint = match key {
+        Key::Character(s) => {
+            // Shift alone already changes the character typed
+            if mods == SHIFT {
+                return None;
+            }
+            let mut chars = s.chars();
+            let c = chars.next()?;
+            if chars.next().is_some() {
+                return None;
+            }
+            if level == 1 && well_defined_char(c, mods) {
+                return None;
+            }
+            c as u32
+        }
+        Key::Named(named) => {
+            let codepoint = match named {
+                NamedKey::Enter => 13,
+                NamedKey::Tab => 9,
+                NamedKey::Backspace => 127,
+                NamedKey::Escape => 27,
+                NamedKey::Space => 32,
+                _ => return None,
+            };
+            if level == 1 && (mods == ALT || mods == SHIFT) {
+                return None;
+            }
+            codepoint
+        }
+        _ => return None,
+    };
+    Some(format!("\x1b[27;{};{}~", mods + 1, codepoint).into_bytes())
+}
+
+/// Whether legacy encoding already gives this combo its own bytes:
+/// Ctrl+letter is a control code, Alt+key is ESC followed by the key
+fn well_defined_char(c: char, mods: u8) -> bool {
+    match mods {
+        ALT => true,
+        CTRL => c.is_ascii_lowercase(),
+        m if m == CTRL | ALT => c.is_ascii_lowercase(),
+        _ => false,
+    }
+}
+
+fn modifier_flags(modifiers: ModifiersState) -> u8 {
+    let mut flags = 0u8;
+    if modifiers.shift_key() {
+        flags |= SHIFT;
+    }
+    if modifiers.alt_key() {
+        flags |= ALT;
+    }
+    if modifiers.control_key() {
+        flags |= CTRL;
+    }
+    if modifiers.super_key() {
+        flags |= 8;
+    }
+    flags
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn encode_str(key: &str, modifiers: ModifiersState, level: u8) -> Option<String> {
+        encode(&Key::Character(key.into()), modifiers, level)
+            .map(|bytes| String::from_utf8(bytes).unwrap())
+    }
+
+    #[test]
+    fn off_leaves_everything_to_legacy() {
+        assert_eq!(encode_str(";", ModifiersState::CONTROL, 0), None);
+        assert_eq!(encode_str("a", ModifiersState::CONTROL, 0), None);
+    }
+
+    #[test]
+    fn level_one_keeps_ctrl_letter_control_codes() {
+        let ctrl = ModifiersState::CONTROL;
+        assert_eq!(encode_str("a", ctrl, 1), None);
+        assert_eq!(encode_str("c", ctrl | ModifiersState::ALT, 1), None);
+        assert_eq!(encode_str("x", ModifiersState::ALT, 1), None);
+        // Punctuation has no control code of its own
+        assert_eq!(encode_str(";", ctrl, 1).unwrap(), "\x1b[27;5;59~");
+        assert_eq!(encode_str(",", ctrl, 1).unwrap(), "\x1b[27;5;44~");
+        // Ctrl+Shift+A would send the same code as Ctrl+A
+        let ctrl_shift = ctrl | ModifiersState::SHIFT;
+        assert_eq!(encode_str("A", ctrl_shift, 1).unwrap(), "\x1b[27;6;65~");
+    }
+
+    #[test]
+    fn level_two_reports_every_modified_key() {
+        let ctrl = ModifiersState::CONTROL;
+        assert_eq!(encode_str("a", ctrl, 2).unwrap(), "\x1b[27;5;97~");
+        assert_eq!(
+            encode_str("x", ModifiersState::ALT, 2).unwrap(),
+            "\x1b[27;3;120~"
+        );
+        assert_eq!(encode_str(";", ctrl, 2).unwrap(), "\x1b[27;5;59~");
+    }
+
+    #[test]
+    fn shift_only_and_plain_keys_type_text() {
+        for level in [1, 2] {
+            assert_eq!(encode_str("a", ModifiersState::empty(), level), None);
+            assert_eq!(encode_str("A", ModifiersState::SHIFT, level), None);
+            assert_eq!(encode_str(":", ModifiersState::SHIFT, level), None);
+        }
+    }
+
+    #[test]
+    fn named_keys() {
+        let enter = Key::Named(NamedKey::Enter);
+        let tab = Key::Named(NamedKey::Tab);
+        let ctrl = ModifiersState::CONTROL;
+        assert_eq!(encode(&enter, ModifiersState::empty(), 2), None);
+        assert_eq!(encode(&enter, ctrl, 1).unwrap(), b"\x1b[27;5;13~");
+        assert_eq!(encode(&enter, ModifiersState::ALT, 1), None);
+        assert_eq!(encode(&tab, ModifiersState::SHIFT, 1), None);
+        assert_eq!(
+            encode(&tab, ModifiersState::SHIFT, 2).unwrap(),
+            b"\x1b[27;2;9~"
+        );
+        let left = Key::Named(NamedKey::ArrowLeft);
+        assert_eq!(encode(&left, ctrl, 2), None);
+    }
+}
@@ -0,0 +1,87 @@
+// ABOUTME: Per-pane activity and silence monitoring.
+// ABOUTME: Flags background panes that start producing output or go quiet.
+
+use std::time::{Duration, Instant};
+
+/// What a pane is being watched for
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MonitorMode {
+    #[default]
+    Off,
+    /// Flag when output arrives while the pane is unfocused
+    Activity,
+    /// Flag after a period without output while the pane is unfocused
+    Silence,
+}
+
+impl MonitorMode {
+    /// Next mode in the Off -> Activity -> Silence cycle
+    pub fn next(self) -> Self {
+        match self {
+            MonitorMode::Off => MonitorMode::Activity,
+            MonitorMode::Activity => MonitorMode::Silence,
+            MonitorMode::Silence => MonitorMode::Off,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            MonitorMode::Off => "Monitoring off",
+            MonitorMode::Activity => "Monitoring activity",
+            MonitorMode::Silence => "Monitoring silence",
+        }
+    }
+}
+
+/// Monitoring state for one pane
+#[derive(Debug)]
+pub struct PaneMonitor {
+    pub mode: MonitorMode,
+    /// Terminal output generation seen at the last update
+    generation: u64,
+    last_output: Instant,
+    pub flagged: bool,
+}
+
+impl PaneMonitor {
+    pub fn new(mode: MonitorMode, generation: u64) -> Self {
+        Self {
+            mode,
+            generation,
+            last_output: Instant::now(),
+            flagged: false,
+        }
+    }
+
+    /// Feed the pane's current output generation. Returns true if the pane
+    /// became flagged by this update.
+    pub fn update(&mut self, generation: u64, focused: bool, silence: Duration) -> bool {
+        let output = generation != self.generation;
+        self.generation = generation;
+        let now = Instant::now();
+        if output || focused {
+            self.last_output = now;
+        }
+
+        // Looking at the pane acknowledges it and restarts the silence timer
+        if focused {
+            self.flagged = false;
+            return false;
+        }
+        if self.flagged {
+            return false;
+        }
+
+        self.flagged = match self.mode {
+            MonitorMode::Off => false,
+            MonitorMode::Activity => output,
+            MonitorMode::Silence => now.duration_since(self.last_output) >= silence,
+        };
+        self.flagged
+    }
+
+    /// When a silence check would next flag the pane, if it is waiting for one
+    pub fn silence_deadline(&self, silence: Duration) -> Option<Instant> {
+        (self.mode == MonitorMode::Silence && !self.flagged).then(|| self.last_output + silence)
+    }
+}
@@ -0,0 +1,209 @@
+// ABOUTME: Mouse reporting for programs that request it (DECSET 1000/1002/1003).
+// ABOUTME: Encodes clicks, drags and wheel turns as SGR (1006), UTF-8 (1005) or X10 sequences,
+// ABOUTME: or wheel turns as arrow keys on the alternate screen (1007).
+
+use crt_terminal::TermMode;
+use winit::event::MouseButton;
+use winit::keyboard::ModifiersState;
+
+/// Button codes for the wheel, reported as presses
+pub const WHEEL_UP: u8 = 64;
+pub const WHEEL_DOWN: u8 = 65;
+/// Button code for motion with no button held
+pub const NO_BUTTON: u8 = 3;
+
+/// Arrow key presses sent per wheel notch on the alternate screen
+pub const ALTERNATE_SCROLL_LINES: f64 = 3.0;
+
+/// Largest coordinate the X10 encoding can carry in one byte
+const X10_MAX: usize = 223;
+/// Largest coordinate the UTF-8 encoding can carry in two bytes
+const UTF8_MAX: usize = 2015;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MouseEventKind {
+    Press,
+    Release,
+    Motion,
+}
+
+/// SGR button code for a mouse button, if it can be reported
+pub fn button_code(button: MouseButton) -> Option<u8> {
+    match button {
+        MouseButton::Left => Some(0),
+        MouseButton::Middle => Some(1),
+        MouseButton::Right => Some(2),
+        _ => None,
+    }
+}
+
+/// Whether the program asked for any mouse events
+pub fn wants_mouse(mode: TermMode) -> bool {
+    mode.intersects(TermMode::MOUSE_MODE)
+}
+
+/// Whether pointer movement is reported: always in any-motion mode
+/// (1003), only while a button is held in drag mode (1002)
+pub fn reports_motion(mode: TermMode, button_held: bool) -> bool {
+    mode.contains(TermMode::MOUSE_MOTION) || (button_held && mode.contains(TermMode::MOUSE_DRAG))
+}
+
+/// Whether the wheel sends arrow keys instead of scrolling: on the alternate
+/// screen, which has no scrollback, unless the program turned that off
+/// (DECRST 1007). Programs that asked for mouse events get those instead.
+pub fn alternate_scroll(mode: TermMode) -> bool {
+    mode.contains(TermMode::ALT_SCREEN | TermMode::ALTERNATE_SCROLL) && !wants_mouse(mode)
+}
+
+/// Up (positive `lines`) or down arrow presses for a wheel turn, in the
+/// cursor key format the program selected (DECCKM)
+pub fn alternate_scroll_keys(lines: i32, mode: TermMode) -> Vec<u8> {
+    let arrow: &[u8] = match (lines > 0, mode.contains(TermMode::APP_CURSOR)) {
+        (true, false) => b"\x1b[A",
+        (false, false) => b"\x1b[B",
+        (true, true) => b"\x1bOA",
+        (false, true) => b"\x1bOB",
+    };
+    arrow.repeat(lines.unsigned_abs() as usize)
+}
+
+/// Encode a mouse event at a 0-based cell. Modifiers add 4 (Shift),
+/// 8 (Alt) and 16 (Ctrl) to the button code, motion adds 32.
+/// Returns None when the position can't be expressed in the active encoding.
+pub fn encode(
+    button: u8,
+    kind: MouseEventKind,
+    col: usize,
+    row: usize,
+    modifiers: ModifiersState,
+    mode: TermMode,
+) -> Option<Vec<u8>> {
+    let mut mods = 0;
+    if modifiers.shift_key() {
+        mods |= 4;
+    }
+    if modifiers.alt_key() {
+        mods |= 8;
+    }
+    if modifiers.control_key() {
+        mods |= 16;
+    }
+    let motion = if kind == MouseEventKind::Motion {
+        32
+    } else {
+        0
+    };
+    let (col, row) = (col + 1, row + 1);
+
+    if mode.contains(TermMode::SGR_MOUSE) {
+        let final_byte = if kind == MouseEventKind::Release {
+            'm'
+        } else {
+            'M'
+        };
+        let code = button + mods + motion;
+        return Some(format!("\x1b[<{};{};{}{}", code, col, row, final_byte).into_bytes());
+    }
+
+    // The older encodings can't say which button was released
+    let button = if kind == MouseEventKind::Release {
+        NO_BUTTON
+    } else {
+        button
+    };
+    let code = button + mods + motion;
+    let mut bytes = vec![0x1b, b'[', b'M', 32 + code];
+    if mode.contains(TermMode::UTF8_MOUSE) {
+        if col > UTF8_MAX || row > UTF8_MAX {
+            return None;
+        }
+        for value in [col, row] {
+            let c = char::from_u32(32 + value as u32)?;
+            let mut buf = [0; 4];
+            bytes.extend_from_slice(c.encode_utf8(&mut buf).as_bytes());
+        }
+    } else {
+        if col > X10_MAX || row > X10_MAX {
+            return None;
+        }
+        bytes.push(32 + col as u8);
+        bytes.push(32 + row as u8);
+    }
+    Some(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sgr(button: u8, kind: MouseEventKind, modifiers: ModifiersState) -> String {
+        let mode = TermMode::MOUSE_REPORT_CLICK | TermMode::SGR_MOUSE;
+        String::from_utf8(encode(button, kind, 4, 9, modifiers, mode).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn sgr_press_and_release() {
+        let none = ModifiersState::empty();
+        assert_eq!(sgr(0, MouseEventKind::Press, none), "\x1b[<0;5;10M");
+        assert_eq!(sgr(0, MouseEventKind::Release, none), "\x1b[<0;5;10m");
+        assert_eq!(sgr(2, MouseEventKind::Press, none), "\x1b[<2;5;10M");
+        assert_eq!(
+            sgr(WHEEL_DOWN, MouseEventKind::Press, none),
+            "\x1b[<65;5;10M"
+        );
+    }
+
+    #[test]
+    fn sgr_modifiers_and_motion() {
+        let ctrl_alt = ModifiersState::CONTROL | ModifiersState::ALT;
+        assert_eq!(sgr(1, MouseEventKind::Press, ctrl_alt), "\x1b[<25;5;10M");
+        assert_eq!(
+            sgr(0, MouseEventKind::Motion, ModifiersState::SHIFT),
+            "\x1b[<36;5;10M"
+        );
+        assert_eq!(
+            sgr(NO_BUTTON, MouseEventKind::Motion, ModifiersState::empty()),
+            "\x1b[<35;5;10M"
+        );
+    }
+
+    #[test]
+    fn x10_encoding_and_limits() {
+        let mode = TermMode::MOUSE_REPORT_CLICK;
+        let none = ModifiersState::empty();
+        let press = encode(0, MouseEventKind::Press, 0, 0, none, mode).unwrap();
+        assert_eq!(press, b"\x1b[M !!");
+        let release = encode(0, MouseEventKind::Release, 0, 0, none, mode).unwrap();
+        assert_eq!(release, b"\x1b[M#!!");
+        assert_eq!(encode(0, MouseEventKind::Press, 300, 0, none, mode), None);
+        // UTF-8 mode reaches further
+        let utf8 = mode | TermMode::UTF8_MOUSE;
+        let far = encode(0, MouseEventKind::Press, 300, 0, none, utf8).unwrap();
+        assert_eq!(far, "\x1b[M \u{14d}!".as_bytes());
+    }
+
+    #[test]
+    fn wheel_sends_arrows_on_the_alternate_screen() {
+        let alt = TermMode::ALT_SCREEN | TermMode::ALTERNATE_SCROLL;
+        assert!(alternate_scroll(alt));
+        assert!(!alternate_scroll(TermMode::ALTERNATE_SCROLL));
+        assert!(!alternate_scroll(TermMode::ALT_SCREEN));
+        assert!(!alternate_scroll(alt | TermMode::MOUSE_REPORT_CLICK));
+
+        assert_eq!(alternate_scroll_keys(2, alt), b"\x1b[A\x1b[A");
+        assert_eq!(alternate_scroll_keys(-1, alt), b"\x1b[B");
+        assert_eq!(
+            alternate_scroll_keys(-2, alt | TermMode::APP_CURSOR),
+            b"\x1bOB\x1bOB"
+        );
+        assert!(alternate_scroll_keys(0, alt).is_empty());
+    }
+
+    #[test]
+    fn motion_depends_on_mode() {
+        assert!(!reports_motion(TermMode::MOUSE_REPORT_CLICK, true));
+        assert!(!reports_motion(TermMode::MOUSE_DRAG, false));
+        assert!(reports_motion(TermMode::MOUSE_DRAG, true));
+        assert!(reports_motion(TermMode::MOUSE_MOTION, false));
+    }
+}
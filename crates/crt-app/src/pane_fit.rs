@@ -0,0 +1,158 @@
+// ABOUTME: Fits each pane's terminal grid to its share of the window.
+// ABOUTME: Holds off while the settings overlay previews a font, so panes keep their scroll position.
+
+use std::collections::HashMap;
+
+use crt_layout::{PaneId, Rect};
+use crt_renderer::Renderer;
+use crt_terminal::Terminal;
+
+/// Cell measurements of the loaded fonts
+pub trait CellMetrics {
+    /// Cell size of the main font in logical pixels
+    fn cell_size(&self) -> (f32, f32);
+    /// Columns and rows that fit a region in logical pixels, at a pane's own
+    /// font size or the main one
+    fn grid_size_for_region(&self, width: u32, height: u32, font_size: Option<f32>) -> (u16, u16);
+    /// Cell size in physical pixels, as reported to programs through the PTY
+    fn cell_pixel_size_for(&self, font_size: Option<f32>) -> (u16, u16);
+}
+
+impl CellMetrics for Renderer {
+    fn cell_size(&self) -> (f32, f32) {
+        Renderer::cell_size(self)
+    }
+
+    fn grid_size_for_region(&self, width: u32, height: u32, font_size: Option<f32>) -> (u16, u16) {
+        Renderer::grid_size_for_region(self, width, height, font_size)
+    }
+
+    fn cell_pixel_size_for(&self, font_size: Option<f32>) -> (u16, u16) {
+        Renderer::cell_pixel_size_for(self, font_size)
+    }
+}
+
+/// The window the panes are fitted into
+pub struct PaneFit {
+    /// Window size in logical pixels
+    pub window: (u32, u32),
+    pub padding: f32,
+    pub status_bar: bool,
+}
+
+impl PaneFit {
+    /// Columns and rows of terminal that fit in a layout rectangle of the
+    /// window, inside the pane padding, at the pane's font size (None = the
+    /// main font). Panes along the bottom of the window also give up a row to
+    /// the status bar when it's shown.
+    pub fn grid_size(
+        &self,
+        metrics: &impl CellMetrics,
+        rect: &Rect,
+        font_size: Option<f32>,
+    ) -> (u16, u16) {
+        let width = ((rect.width * self.window.0 as f32) - self.padding * 2.0).max(1.0) as u32;
+        let mut height = (rect.height * self.window.1 as f32) - self.padding * 2.0;
+        if self.status_bar && rect.y + rect.height > 0.999 {
+            height -= metrics.cell_size().1;
+        }
+        metrics.grid_size_for_region(width, height.max(1.0) as u32, font_size)
+    }
+}
+
+/// Resize each pane's terminal to fit its rect. Nothing is resized while
+/// the settings overlay is open: it measures cells with the previewed font,
+/// and panes are fitted again once it closes. Panes that come out the same
+/// size are left alone, keeping their scroll position and selection.
+pub fn fit_terminals(
+    metrics: &impl CellMetrics,
+    fit: &PaneFit,
+    terminals: &HashMap<PaneId, Terminal>,
+    rects: &HashMap<PaneId, Rect>,
+    font_size: impl Fn(PaneId) -> Option<f32>,
+    settings_open: bool,
+) {
+    if settings_open {
+        return;
+    }
+    for (pane_id, terminal) in terminals {
+        if let Some(rect) = rects.get(pane_id) {
+            let font_size = font_size(*pane_id);
+            let (cols, rows) = fit.grid_size(metrics, rect, font_size);
+            let (cell_width, cell_height) = metrics.cell_pixel_size_for(font_size);
+            terminal.resize(cols, rows, cell_width, cell_height);
+        }
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use crt_terminal::{RegexSearch, ShellCommand};
+    use std::time::{Duration, Instant};
+
+    /// Fixed-size cells, as a font would measure them
+    struct Cells(f32, f32);
+
+    impl CellMetrics for Cells {
+        fn cell_size(&self) -> (f32, f32) {
+            (self.0, self.1)
+        }
+
+        fn grid_size_for_region(&self, width: u32, height: u32, _: Option<f32>) -> (u16, u16) {
+            (
+                (width as f32 / self.0) as u16,
+                (height as f32 / self.1) as u16,
+            )
+        }
+
+        fn cell_pixel_size_for(&self, _: Option<f32>) -> (u16, u16) {
+            (self.0 as u16, self.1 as u16)
+        }
+    }
+
+    #[test]
+    fn previewing_a_font_keeps_the_scroll_position() {
+        let shell = ShellCommand {
+            program: Some("/bin/sh".to_string()),
+            args: vec![
+                "-c".to_string(),
+                "for i in $(seq 1 100); do printf '%070d\\n' $i; done; sleep 5".to_string(),
+            ],
+            login: false,
+        };
+        let pane = PaneId(1);
+        let terminals = HashMap::from([(
+            pane,
+            Terminal::with_shell(80, 24, None, &shell, 1000, None).unwrap(),
+        )]);
+        let rects = HashMap::from([(pane, Rect::full())]);
+        let fit = PaneFit {
+            window: (800, 480),
+            padding: 0.0,
+            status_bar: false,
+        };
+        let saved = Cells(10.0, 20.0);
+        fit_terminals(&saved, &fit, &terminals, &rects, |_| None, false);
+
+        let terminal = &terminals[&pane];
+        let mut last = RegexSearch::new("0{67}100").unwrap();
+        let deadline = Instant::now() + Duration::from_secs(10);
+        while terminal.visible_matches(&mut last).is_empty() {
+            assert!(Instant::now() < deadline, "output never arrived");
+            std::thread::sleep(Duration::from_millis(10));
+        }
+        terminal.scroll(30);
+        let offset = terminal.display_offset();
+        assert_eq!(offset, 30);
+
+        // Open settings and preview a bigger font, which would rewrap the
+        // lines, then cancel
+        let previewed = Cells(16.0, 32.0);
+        fit_terminals(&previewed, &fit, &terminals, &rects, |_| None, true);
+        fit_terminals(&saved, &fit, &terminals, &rects, |_| None, false);
+
+        assert_eq!(terminal.size(), (80, 24));
+        assert_eq!(terminal.display_offset(), offset);
+    }
+}
@@ -0,0 +1,144 @@
+// ABOUTME: Paste protection: decides when a paste needs confirmation.
+// ABOUTME: Builds the confirmation preview, the single-line variant and quoted dropped paths.
+
+use crt_layout::PaneId;
+
+/// Lines of the paste shown in the confirmation overlay
+const PREVIEW_LINES: usize = 3;
+/// Characters shown per preview line before truncating
+const PREVIEW_WIDTH: usize = 60;
+
+/// A paste waiting for the user to confirm it
+#[derive(Debug, Clone)]
+pub struct PendingPaste {
+    pub pane: PaneId,
+    pub text: String,
+}
+
+/// Whether the text could run commands the moment it is pasted
+pub fn needs_confirmation(text: &str) -> bool {
+    text.chars().any(|c| c.is_ascii_control() && c != '\t')
+}
+
+/// The paste with line breaks turned into spaces and other control
+/// characters dropped
+pub fn single_line(text: &str) -> String {
+    text.trim_end_matches(['\r', '\n'])
+        .replace("\r\n", " ")
+        .chars()
+        .filter_map(|c| match c {
+            '\r' | '\n' => Some(' '),
+            '\t' => Some('\t'),
+            c if c.is_ascii_control() => None,
+            c => Some(c),
+        })
+        .collect()
+}
+
+/// A path in single quotes if the shell would otherwise split or expand
+/// it, with embedded single quotes written as '\''. A leading `-` gets a
+/// `./` so the path isn't taken for an option.
+pub fn shell_quote(path: &str) -> String {
+    let path = if path.starts_with('-') {
+        format!("./{path}")
+    } else {
+        path.to_string()
+    };
+    let plain = |c: char| c.is_alphanumeric() || "_-./+,:@%=~".contains(c);
+    if !path.is_empty() && path.chars().all(plain) {
+        return path;
+    }
+    format!("'{}'", path.replace('\'', r"'\''"))
+}
+
+/// The overlay text: the first few lines and how many there are in total
+pub fn preview(text: &str) -> Vec<String> {
+    let lines: Vec<&str> = text.lines().collect();
+    let mut preview: Vec<String> = lines
+        .iter()
+        .take(PREVIEW_LINES)
+        .map(|line| {
+            let visible: String = line
+                .chars()
+                .map(|c| if c.is_control() { '?' } else { c })
+                .take(PREVIEW_WIDTH)
+                .collect();
+            if line.chars().count() > PREVIEW_WIDTH {
+                format!("{visible}...")
+            } else {
+                visible
+            }
+        })
+        .collect();
+    if lines.len() > PREVIEW_LINES {
+        preview.push("...".to_string());
+    }
+    let noun = if lines.len() == 1 { "line" } else { "lines" };
+    preview.push(format!("Paste {} {noun}?", lines.len()));
+    preview.push("Enter: paste  S: as single line  Esc: cancel".to_string());
+    preview
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_control_characters_need_confirmation() {
+        assert!(!needs_confirmation("ls -la"));
+        assert!(!needs_confirmation("a\tb"));
+        assert!(needs_confirmation("rm -rf ~\n"));
+        assert!(needs_confirmation("echo hi\r"));
+        assert!(needs_confirmation("\x1b[201~"));
+    }
+
+    #[test]
+    fn single_line_joins_lines_with_spaces() {
+        assert_eq!(single_line("make\r\nmake install\n"), "make make install");
+        assert_eq!(single_line("a\nb\rc"), "a b c");
+        assert_eq!(single_line("a\tb\x1b[1mc\x07"), "a\tb[1mc");
+        assert!(!needs_confirmation(&single_line("one\ntwo\n")));
+    }
+
+    #[test]
+    fn preview_shows_the_first_lines() {
+        assert_eq!(
+            preview("echo hi\n"),
+            [
+                "echo hi",
+                "Paste 1 line?",
+                "Enter: paste  S: as single line  Esc: cancel"
+            ]
+        );
+
+        let long = "x".repeat(PREVIEW_WIDTH + 5);
+        let text = format!("{long}\nb\x1bc\nd\ne\n");
+        let lines = preview(&text);
+        assert_eq!(lines[0], format!("{}...", "x".repeat(PREVIEW_WIDTH)));
+        assert_eq!(lines[1], "b?c");
+        assert_eq!(lines[2], "d");
+        assert_eq!(lines[3], "...");
+        assert_eq!(lines[4], "Paste 4 lines?");
+    }
+
+    #[test]
+    fn plain_paths_are_left_alone() {
+        assert_eq!(shell_quote("/home/me/notes.txt"), "/home/me/notes.txt");
+        assert_eq!(shell_quote("~/a-b_c+d,e:f@g%h=i"), "~/a-b_c+d,e:f@g%h=i");
+    }
+
+    #[test]
+    fn special_paths_are_quoted() {
+        assert_eq!(shell_quote("/tmp/my file"), "'/tmp/my file'");
+        assert_eq!(shell_quote("/tmp/it's"), r"'/tmp/it'\''s'");
+        assert_eq!(shell_quote("/tmp/a\nb"), "'/tmp/a\nb'");
+        assert_eq!(shell_quote("/tmp/$HOME;*"), "'/tmp/$HOME;*'");
+        assert_eq!(shell_quote(""), "''");
+    }
+
+    #[test]
+    fn leading_dash_is_not_an_option() {
+        assert_eq!(shell_quote("-rf"), "./-rf");
+        assert_eq!(shell_quote("-my file"), "'./-my file'");
+    }
+}
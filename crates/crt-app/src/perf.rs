@@ -0,0 +1,128 @@
+// ABOUTME: Performance overlay: frame and GPU times, glyph cache and atlas usage, dropped frames.
+// ABOUTME: Formats the overlay lines and decides when to repeat the numbers in the log.
+
+use std::time::{Duration, Instant};
+
+/// How often the overlay's numbers are also written to the log
+const LOG_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Counters kept while the overlay is on
+pub struct PerfStats {
+    dropped_frames: u64,
+    last_log: Instant,
+}
+
+/// One frame's numbers, as shown in the overlay
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PerfSnapshot {
+    pub fps: f32,
+    pub frame_ms: f32,
+    /// None when the adapter can't time passes, or before the first readback
+    pub gpu_ms: Option<f32>,
+    pub glyphs: usize,
+    pub atlas_size: (u32, u32),
+    /// Fraction of the atlas in use (0.0-1.0)
+    pub atlas_fill: f32,
+    pub dropped_frames: u64,
+}
+
+impl PerfStats {
+    pub fn new() -> Self {
+        Self {
+            dropped_frames: 0,
+            last_log: Instant::now(),
+        }
+    }
+
+    /// Count a frame that took at least two frame slots, meaning one was
+    /// skipped. Only meaningful while frames are being drawn continuously.
+    pub fn record_frame(&mut self, dt: Duration, frame_duration: Duration) {
+        if dt >= frame_duration * 2 {
+            self.dropped_frames += 1;
+        }
+    }
+
+    pub fn dropped_frames(&self) -> u64 {
+        self.dropped_frames
+    }
+
+    /// Whether it's time to log the numbers again
+    pub fn should_log(&mut self, now: Instant) -> bool {
+        if now.duration_since(self.last_log) < LOG_INTERVAL {
+            return false;
+        }
+        self.last_log = now;
+        true
+    }
+}
+
+impl PerfSnapshot {
+    /// Overlay text, one entry per line
+    pub fn lines(&self) -> Vec<String> {
+        let gpu = match self.gpu_ms {
+            Some(ms) => format!("{:.2}ms", ms),
+            None => "n/a".to_string(),
+        };
+        vec![
+            format!(
+                "frame {:.2}ms ({:.0} FPS)  gpu {}",
+                self.frame_ms, self.fps, gpu
+            ),
+            format!(
+                "glyphs {}  atlas {}x{} {:.0}% full",
+                self.glyphs,
+                self.atlas_size.0,
+                self.atlas_size.1,
+                self.atlas_fill * 100.0
+            ),
+            format!("dropped frames {}", self.dropped_frames),
+        ]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn frames_skipping_a_slot_are_dropped() {
+        let mut stats = PerfStats::new();
+        let slot = Duration::from_millis(8);
+        stats.record_frame(Duration::from_millis(8), slot);
+        stats.record_frame(Duration::from_millis(12), slot);
+        assert_eq!(stats.dropped_frames(), 0);
+        stats.record_frame(Duration::from_millis(16), slot);
+        stats.record_frame(Duration::from_millis(40), slot);
+        assert_eq!(stats.dropped_frames(), 2);
+    }
+
+    #[test]
+    fn logs_at_most_every_interval() {
+        let mut stats = PerfStats::new();
+        let start = Instant::now();
+        assert!(!stats.should_log(start));
+        assert!(stats.should_log(start + LOG_INTERVAL));
+        assert!(!stats.should_log(start + LOG_INTERVAL + Duration::from_secs(1)));
+    }
+
+    #[test]
+    fn overlay_lines() {
+        let snapshot = PerfSnapshot {
+            fps: 120.0,
+            frame_ms: 8.33,
+            gpu_ms: None,
+            glyphs: 210,
+            atlas_size: (1024, 1024),
+            atlas_fill: 0.25,
+            dropped_frames: 3,
+        };
+        assert_eq!(
+            snapshot.lines(),
+            [
+                "frame 8.33ms (120 FPS)  gpu n/a",
+                "glyphs 210  atlas 1024x1024 25% full",
+                "dropped frames 3",
+            ]
+        );
+    }
+}
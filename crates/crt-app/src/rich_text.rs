@@ -0,0 +1,185 @@
+// ABOUTME: Styled copies of selected text: HTML with inline styles, or ANSI escapes.
+// ABOUTME: Works on cells whose colors are already resolved against the color scheme.
+
+/// One copied character with the colors it was drawn in
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct StyledChar {
+    pub c: char,
+    pub fg: [f32; 4],
+    /// None for the default background
+    pub bg: Option<[f32; 4]>,
+    pub bold: bool,
+    pub italic: bool,
+    pub underline: bool,
+}
+
+impl StyledChar {
+    fn same_style(&self, other: &StyledChar) -> bool {
+        self.fg == other.fg
+            && self.bg == other.bg
+            && self.bold == other.bold
+            && self.italic == other.italic
+            && self.underline == other.underline
+    }
+}
+
+/// Drop trailing blanks from each line, which are just unused columns
+pub fn trim_lines(lines: &mut [Vec<StyledChar>]) {
+    for line in lines {
+        while line.last().is_some_and(|s| s.c.is_whitespace()) {
+            line.pop();
+        }
+    }
+}
+
+pub fn to_plain(lines: &[Vec<StyledChar>]) -> String {
+    lines
+        .iter()
+        .map(|line| line.iter().map(|s| s.c).collect::<String>())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// An HTML fragment: a <pre> in the scheme's colors with a span per run
+/// of identically styled characters
+pub fn to_html(lines: &[Vec<StyledChar>], foreground: [f32; 4], background: [f32; 4]) -> String {
+    let mut html = format!(
+        "<pre style=\"color:{};background-color:{};font-family:monospace\">",
+        hex(foreground),
+        hex(background)
+    );
+    for (i, line) in lines.iter().enumerate() {
+        if i > 0 {
+            html.push('\n');
+        }
+        for run in line.chunk_by(StyledChar::same_style) {
+            let first = &run[0];
+            let mut style = format!("color:{}", hex(first.fg));
+            if let Some(bg) = first.bg {
+                style.push_str(&format!(";background-color:{}", hex(bg)));
+            }
+            if first.bold {
+                style.push_str(";font-weight:bold");
+            }
+            if first.italic {
+                style.push_str(";font-style:italic");
+            }
+            if first.underline {
+                style.push_str(";text-decoration:underline");
+            }
+            html.push_str(&format!("<span style=\"{}\">", style));
+            for s in run {
+                match s.c {
+                    '&' => html.push_str("&amp;"),
+                    '<' => html.push_str("&lt;"),
+                    '>' => html.push_str("&gt;"),
+                    c => html.push(c),
+                }
+            }
+            html.push_str("</span>");
+        }
+    }
+    html.push_str("</pre>");
+    html
+}
+
+/// Text with 24-bit SGR sequences, reset at the end of every line so each
+/// line can be pasted on its own
+pub fn to_ansi(lines: &[Vec<StyledChar>]) -> String {
+    let mut ansi = String::new();
+    for (i, line) in lines.iter().enumerate() {
+        if i > 0 {
+            ansi.push('\n');
+        }
+        for run in line.chunk_by(StyledChar::same_style) {
+            let first = &run[0];
+            let [r, g, b] = rgb(first.fg);
+            let mut sgr = format!("\x1b[0;38;2;{};{};{}", r, g, b);
+            if let Some(bg) = first.bg {
+                let [r, g, b] = rgb(bg);
+                sgr.push_str(&format!(";48;2;{};{};{}", r, g, b));
+            }
+            if first.bold {
+                sgr.push_str(";1");
+            }
+            if first.italic {
+                sgr.push_str(";3");
+            }
+            if first.underline {
+                sgr.push_str(";4");
+            }
+            ansi.push_str(&sgr);
+            ansi.push('m');
+            ansi.extend(run.iter().map(|s| s.c));
+        }
+        if !line.is_empty() {
+            ansi.push_str("\x1b[0m");
+        }
+    }
+    ansi
+}
+
+fn rgb(color: [f32; 4]) -> [u8; 3] {
+    let channel = |v: f32| (v.clamp(0.0, 1.0) * 255.0).round() as u8;
+    [channel(color[0]), channel(color[1]), channel(color[2])]
+}
+
+fn hex(color: [f32; 4]) -> String {
+    let [r, g, b] = rgb(color);
+    format!("#{:02x}{:02x}{:02x}", r, g, b)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const WHITE: [f32; 4] = [1.0, 1.0, 1.0, 1.0];
+    const RED: [f32; 4] = [1.0, 0.0, 0.0, 1.0];
+    const BLACK: [f32; 4] = [0.0, 0.0, 0.0, 1.0];
+
+    fn styled(text: &str, fg: [f32; 4], bold: bool) -> Vec<StyledChar> {
+        text.chars()
+            .map(|c| StyledChar {
+                c,
+                fg,
+                bg: None,
+                bold,
+                italic: false,
+                underline: false,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn html_groups_runs_and_escapes() {
+        let mut line = styled("a<b", WHITE, false);
+        line.extend(styled("!", RED, true));
+        let html = to_html(&[line], WHITE, BLACK);
+        assert_eq!(
+            html,
+            "<pre style=\"color:#ffffff;background-color:#000000;font-family:monospace\">\
+             <span style=\"color:#ffffff\">a&lt;b</span>\
+             <span style=\"color:#ff0000;font-weight:bold\">!</span></pre>"
+        );
+    }
+
+    #[test]
+    fn ansi_resets_each_line() {
+        let lines = [
+            styled("hi", RED, true),
+            Vec::new(),
+            styled("x", WHITE, false),
+        ];
+        assert_eq!(
+            to_ansi(&lines),
+            "\x1b[0;38;2;255;0;0;1mhi\x1b[0m\n\n\x1b[0;38;2;255;255;255mx\x1b[0m"
+        );
+    }
+
+    #[test]
+    fn plain_text_drops_trailing_blanks() {
+        let mut lines = vec![styled("a b   ", WHITE, false), styled("  ", WHITE, false)];
+        trim_lines(&mut lines);
+        assert_eq!(to_plain(&lines), "a b\n");
+    }
+}
@@ -0,0 +1,83 @@
+// ABOUTME: Saves captured frames as PNG files and names session recordings.
+// ABOUTME: Files go to the user's Pictures or Videos directory with a UTC timestamp name.
+
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use anyhow::{Context, Result};
+
+/// Save a captured frame and return where it was written
+pub fn save(image: &image::RgbaImage) -> Result<PathBuf> {
+    let dir = dirs::picture_dir()
+        .or_else(|| dirs::home_dir().map(|home| home.join("Pictures")))
+        .context("No Pictures directory")?;
+    std::fs::create_dir_all(&dir).with_context(|| format!("Creating {}", dir.display()))?;
+
+    let path = dir.join(format!(
+        "cool-rust-term-{}.png",
+        timestamp(SystemTime::now())
+    ));
+    image::save_buffer(
+        &path,
+        image.as_raw(),
+        image.width(),
+        image.height(),
+        image::ExtendedColorType::Rgba8,
+    )
+    .with_context(|| format!("Writing {}", path.display()))?;
+    Ok(path)
+}
+
+/// Where to write a new asciicast recording
+pub fn recording_path() -> Result<PathBuf> {
+    let dir = dirs::video_dir()
+        .or_else(|| dirs::home_dir().map(|home| home.join("Videos")))
+        .context("No Videos directory")?;
+    std::fs::create_dir_all(&dir).with_context(|| format!("Creating {}", dir.display()))?;
+    Ok(dir.join(format!(
+        "cool-rust-term-{}.cast",
+        timestamp(SystemTime::now())
+    )))
+}
+
+/// UTC time as YYYYMMDD-HHMMSS-mmm, so file names sort chronologically and
+/// two screenshots in the same second don't overwrite each other
+fn timestamp(time: SystemTime) -> String {
+    let since_epoch = time.duration_since(UNIX_EPOCH).unwrap_or_default();
+    let secs = since_epoch.as_secs();
+    let (days, secs_of_day) = (secs / 86_400, secs % 86_400);
+
+    // Civil date from days since 1970-01-01 (Howard Hinnant's algorithm)
+    let z = days as i64 + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+
+    format!(
+        "{:04}{:02}{:02}-{:02}{:02}{:02}-{:03}",
+        year,
+        month,
+        day,
+        secs_of_day / 3600,
+        secs_of_day / 60 % 60,
+        secs_of_day % 60,
+        since_epoch.subsec_millis()
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn timestamps_include_milliseconds() {
+        let time = UNIX_EPOCH + Duration::from_millis(1_700_000_000_042);
+        assert_eq!(timestamp(time), "20231114-221320-042");
+    }
+}
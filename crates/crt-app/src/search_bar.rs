@@ -0,0 +1,242 @@
+// ABOUTME: Search bar: finds a literal string in a pane's scrollback and steps through the matches.
+// ABOUTME: Drawn as a single row along the bottom of the window while it's open.
+
+use crt_core::{ColorScheme, LineSize};
+use crt_layout::PaneId;
+use crt_renderer::{GlyphStyle, RenderCell};
+use crt_terminal::TextMatch;
+
+/// Characters with a meaning in a regex, escaped so the query matches literally
+const REGEX_META: &[char] = &[
+    '\\', '.', '+', '*', '?', '(', ')', '|', '[', ']', '{', '}', '^', '$', '#', '&', '-', '~',
+];
+
+/// Cells a match covers, as (column, buffer line) of its first and last cell
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MatchSpan {
+    pub start: (usize, i32),
+    pub end: (usize, i32),
+}
+
+impl MatchSpan {
+    pub fn contains(&self, col: usize, line: i32) -> bool {
+        let after_start = line > self.start.1 || (line == self.start.1 && col >= self.start.0);
+        let before_end = line < self.end.1 || (line == self.end.1 && col <= self.end.0);
+        after_start && before_end
+    }
+}
+
+/// Search state for one pane
+#[derive(Debug)]
+pub struct SearchBar {
+    pub pane: PaneId,
+    pub query: String,
+    /// Index into `matches` of the one being shown
+    pub current_match: usize,
+    pub total_matches: usize,
+    /// Newest first, so stepping forward goes up through the history
+    pub matches: Vec<MatchSpan>,
+    /// Terminal generation the matches were found in
+    pub generation: u64,
+}
+
+impl SearchBar {
+    pub fn new(pane: PaneId) -> Self {
+        Self {
+            pane,
+            query: String::new(),
+            current_match: 0,
+            total_matches: 0,
+            matches: Vec::new(),
+            generation: 0,
+        }
+    }
+
+    pub fn type_text(&mut self, text: &str) {
+        self.query.extend(text.chars().filter(|c| !c.is_control()));
+    }
+
+    pub fn backspace(&mut self) {
+        self.query.pop();
+    }
+
+    /// The query as a regex matching it literally; None while it's empty
+    pub fn pattern(&self) -> Option<String> {
+        if self.query.is_empty() {
+            return None;
+        }
+        let mut pattern = String::with_capacity(self.query.len());
+        for c in self.query.chars() {
+            if REGEX_META.contains(&c) {
+                pattern.push('\\');
+            }
+            pattern.push(c);
+        }
+        Some(pattern)
+    }
+
+    /// Replace the matches with a new search's, given oldest first. With
+    /// `keep_current` the same match index stays selected (for output
+    /// arriving), otherwise the newest match is.
+    pub fn set_matches(&mut self, found: Vec<TextMatch>, keep_current: bool) {
+        self.matches = found
+            .into_iter()
+            .rev()
+            .map(|m| MatchSpan {
+                start: m.start,
+                end: m.end,
+            })
+            .collect();
+        self.total_matches = self.matches.len();
+        if !keep_current || self.current_match >= self.total_matches {
+            self.current_match = 0;
+        }
+    }
+
+    /// Step to the next older match, wrapping around to the newest
+    pub fn next(&mut self) {
+        if self.total_matches > 0 {
+            self.current_match = (self.current_match + 1) % self.total_matches;
+        }
+    }
+
+    /// Step to the next newer match, wrapping around to the oldest
+    pub fn previous(&mut self) {
+        if self.total_matches > 0 {
+            self.current_match = (self.current_match + self.total_matches - 1) % self.total_matches;
+        }
+    }
+
+    pub fn current(&self) -> Option<MatchSpan> {
+        self.matches.get(self.current_match).copied()
+    }
+
+    /// The bar's row: `/ query  (current/total)`, `columns` cells wide
+    pub fn render(&self, columns: usize, scheme: &ColorScheme) -> Vec<RenderCell> {
+        let fg = scheme.foreground;
+        let bg = [
+            scheme.background[0],
+            scheme.background[1],
+            scheme.background[2],
+            1.0,
+        ];
+        let dim = scheme.dim_color();
+        let current = if self.total_matches > 0 {
+            self.current_match + 1
+        } else {
+            0
+        };
+        let query_fg = if self.total_matches > 0 || self.query.is_empty() {
+            scheme.colors[15]
+        } else {
+            dim
+        };
+
+        let mut row: Vec<RenderCell> = (0..columns).map(|_| cell(' ', fg, bg)).collect();
+        let counter = format!("  ({}/{})", current, self.total_matches);
+        let text = ["/ ", &self.query, &counter];
+        let colors = [fg, query_fg, dim];
+        let chars = text
+            .iter()
+            .zip(colors)
+            .flat_map(|(text, color)| text.chars().map(move |c| (c, color)));
+        for (cell, (c, color)) in row.iter_mut().zip(chars) {
+            cell.c = c;
+            cell.fg = color;
+        }
+        row
+    }
+}
+
+fn cell(c: char, fg: [f32; 4], bg: [f32; 4]) -> RenderCell {
+    RenderCell {
+        c,
+        fg,
+        bg,
+        is_wide: false,
+        style: GlyphStyle::Regular,
+        line_size: LineSize::Normal,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn found(lines: &[i32]) -> Vec<TextMatch> {
+        lines
+            .iter()
+            .map(|&line| TextMatch {
+                start: (0, line),
+                end: (3, line),
+                text: "text".to_string(),
+            })
+            .collect()
+    }
+
+    fn bar(query: &str) -> SearchBar {
+        let mut bar = SearchBar::new(PaneId(0));
+        bar.type_text(query);
+        bar
+    }
+
+    #[test]
+    fn query_matches_literally() {
+        assert_eq!(bar("").pattern(), None);
+        assert_eq!(bar("foo").pattern().as_deref(), Some("foo"));
+        assert_eq!(bar("a.b*(c)").pattern().as_deref(), Some(r"a\.b\*\(c\)"));
+        assert_eq!(bar(r"C:\x").pattern().as_deref(), Some(r"C:\\x"));
+    }
+
+    #[test]
+    fn stepping_starts_at_the_newest_match_and_wraps() {
+        let mut bar = bar("text");
+        bar.set_matches(found(&[-20, -5, 3]), false);
+        assert_eq!(bar.total_matches, 3);
+        assert_eq!(bar.current().unwrap().start, (0, 3));
+        bar.next();
+        assert_eq!(bar.current().unwrap().start, (0, -5));
+        bar.next();
+        bar.next();
+        assert_eq!(bar.current().unwrap().start, (0, 3));
+        bar.previous();
+        assert_eq!(bar.current().unwrap().start, (0, -20));
+    }
+
+    #[test]
+    fn new_output_keeps_the_current_match_index() {
+        let mut bar = bar("text");
+        bar.set_matches(found(&[-20, -5, 3]), false);
+        bar.next();
+        bar.set_matches(found(&[-21, -6, 2, 4]), true);
+        assert_eq!(bar.current_match, 1);
+        bar.set_matches(found(&[4]), true);
+        assert_eq!(bar.current_match, 0);
+        bar.set_matches(Vec::new(), false);
+        assert_eq!(bar.current(), None);
+    }
+
+    #[test]
+    fn span_covers_wrapped_lines() {
+        let span = MatchSpan {
+            start: (78, -1),
+            end: (2, 0),
+        };
+        assert!(span.contains(79, -1));
+        assert!(span.contains(0, 0));
+        assert!(!span.contains(77, -1));
+        assert!(!span.contains(3, 0));
+    }
+
+    #[test]
+    fn bar_text() {
+        let mut bar = bar("needle");
+        bar.set_matches(found(&[-5, 3]), false);
+        let text: String = bar
+            .render(30, &ColorScheme::amber())
+            .iter()
+            .map(|cell| cell.c)
+            .collect();
+        assert_eq!(text.trim_end(), "/ needle  (1/2)");
+    }
+}
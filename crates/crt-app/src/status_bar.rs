@@ -0,0 +1,182 @@
+// ABOUTME: Status bar: the focused pane's program, directory and size, the color scheme and font.
+// ABOUTME: Drawn as one row along the bottom of the window; process info is read at most once a second.
+
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use crt_core::{ColorScheme, LineSize};
+use crt_layout::PaneId;
+use crt_renderer::{GlyphStyle, RenderCell};
+
+/// How often the foreground program and directory are looked up again
+pub const REFRESH_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Separator between the bar's fields
+const SEPARATOR: &str = " │ ";
+
+#[derive(Default)]
+pub struct StatusBar {
+    /// Pane the process info was read from
+    pane: Option<PaneId>,
+    /// Its output generation at that time
+    generation: u64,
+    refreshed: Option<Instant>,
+    process: Option<String>,
+    cwd: Option<PathBuf>,
+}
+
+impl StatusBar {
+    /// Whether the process info should be read again: right away for a newly
+    /// focused pane, and after output only once a second has passed
+    pub fn is_due(&self, pane: PaneId, generation: u64, now: Instant) -> bool {
+        match self.refreshed {
+            Some(refreshed) if self.pane == Some(pane) => {
+                generation != self.generation && now >= refreshed + REFRESH_INTERVAL
+            }
+            _ => true,
+        }
+    }
+
+    /// When a refresh held back by the once-a-second limit becomes due
+    pub fn next_refresh(&self, pane: PaneId, generation: u64) -> Option<Instant> {
+        let refreshed = self.refreshed?;
+        (self.pane == Some(pane) && generation != self.generation)
+            .then(|| refreshed + REFRESH_INTERVAL)
+    }
+
+    pub fn update(
+        &mut self,
+        pane: PaneId,
+        generation: u64,
+        process: Option<String>,
+        cwd: Option<PathBuf>,
+        now: Instant,
+    ) {
+        self.pane = Some(pane);
+        self.generation = generation;
+        self.refreshed = Some(now);
+        self.process = process;
+        self.cwd = cwd;
+    }
+
+    /// The bar, `columns` cells wide, for a pane of `size` columns and rows.
+    /// Fields that don't fit are cut off at the right.
+    pub fn render(
+        &self,
+        columns: usize,
+        size: (u16, u16),
+        scheme: &ColorScheme,
+        font: &str,
+        home: Option<&Path>,
+    ) -> Vec<RenderCell> {
+        let bg = [
+            scheme.background[0],
+            scheme.background[1],
+            scheme.background[2],
+            1.0,
+        ];
+        let dim = scheme.dim_color();
+
+        let mut fields = Vec::new();
+        if let Some(process) = &self.process {
+            fields.push(process.clone());
+        }
+        if let Some(cwd) = &self.cwd {
+            fields.push(display_path(cwd, home));
+        }
+        fields.push(format!("{}×{}", size.0, size.1));
+        fields.push(scheme.name.clone());
+        fields.push(font.to_string());
+        let text = format!(" {}", fields.join(SEPARATOR));
+
+        let mut chars = text.chars();
+        (0..columns)
+            .map(|_| RenderCell {
+                c: chars.next().unwrap_or(' '),
+                fg: dim,
+                bg,
+                is_wide: false,
+                style: GlyphStyle::Regular,
+                line_size: LineSize::Normal,
+            })
+            .collect()
+    }
+}
+
+/// A path with the home directory shortened to `~`
+fn display_path(path: &Path, home: Option<&Path>) -> String {
+    match home.and_then(|home| path.strip_prefix(home).ok()) {
+        Some(rest) if rest.as_os_str().is_empty() => "~".to_string(),
+        Some(rest) => format!("~/{}", rest.display()),
+        None => path.display().to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn text(row: &[RenderCell]) -> String {
+        row.iter().map(|cell| cell.c).collect()
+    }
+
+    #[test]
+    fn shows_every_field() {
+        let mut bar = StatusBar::default();
+        bar.update(
+            PaneId(1),
+            0,
+            Some("vim".to_string()),
+            Some(PathBuf::from("/home/me/src")),
+            Instant::now(),
+        );
+        let scheme = ColorScheme::amber();
+        let row = bar.render(
+            80,
+            (120, 40),
+            &scheme,
+            "IBM VGA",
+            Some(Path::new("/home/me")),
+        );
+        assert_eq!(row.len(), 80);
+        assert_eq!(
+            text(&row).trim_end(),
+            format!(" vim │ ~/src │ 120×40 │ {} │ IBM VGA", scheme.name)
+        );
+        let short = bar.render(6, (120, 40), &scheme, "IBM VGA", None);
+        assert_eq!(text(&short), " vim │");
+    }
+
+    #[test]
+    fn home_is_shortened() {
+        let home = Some(Path::new("/home/me"));
+        assert_eq!(display_path(Path::new("/home/me"), home), "~");
+        assert_eq!(display_path(Path::new("/home/me/a/b"), home), "~/a/b");
+        assert_eq!(display_path(Path::new("/home/meat"), home), "/home/meat");
+        assert_eq!(display_path(Path::new("/tmp"), None), "/tmp");
+    }
+
+    #[test]
+    fn refreshes_at_most_once_a_second() {
+        let start = Instant::now();
+        let mut bar = StatusBar::default();
+        assert!(bar.is_due(PaneId(1), 0, start));
+        bar.update(PaneId(1), 0, None, None, start);
+
+        // Nothing new from the pane
+        assert!(!bar.is_due(PaneId(1), 0, start + Duration::from_secs(5)));
+        assert_eq!(bar.next_refresh(PaneId(1), 0), None);
+
+        // Output arrived, but too soon after the last look
+        let soon = start + Duration::from_millis(200);
+        assert!(!bar.is_due(PaneId(1), 1, soon));
+        assert_eq!(
+            bar.next_refresh(PaneId(1), 1),
+            Some(start + REFRESH_INTERVAL)
+        );
+        assert!(bar.is_due(PaneId(1), 1, start + REFRESH_INTERVAL));
+
+        // Focusing another pane refreshes right away
+        assert!(bar.is_due(PaneId(2), 0, soon));
+    }
+}
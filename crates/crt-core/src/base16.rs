@@ -0,0 +1,160 @@
+// ABOUTME: Loads Base16 color schemes from their YAML files (scheme, author, base00-base0F).
+// ABOUTME: Reads just the flat key: "value" subset of YAML those files use, without a YAML crate.
+
+use std::collections::HashMap;
+
+use crate::config::hex_color;
+use crate::ColorScheme;
+
+#[derive(Debug, thiserror::Error)]
+pub enum Base16Error {
+    #[error("missing color {0}")]
+    MissingColor(&'static str),
+
+    #[error("{key} is not a hex color: {value:?}")]
+    InvalidColor { key: &'static str, value: String },
+}
+
+/// The 16 Base16 slots in order, base00 to base0F
+const SLOTS: [&str; 16] = [
+    "base00", "base01", "base02", "base03", "base04", "base05", "base06", "base07", "base08",
+    "base09", "base0A", "base0B", "base0C", "base0D", "base0E", "base0F",
+];
+
+/// Base16 slot for each ANSI color, as in base16-shell: base08 red,
+/// base0B green, base0A yellow, base0D blue, base0E magenta, base0C cyan.
+/// The bright colors repeat them, with base03 as bright black and base07
+/// as bright white.
+const ANSI_SLOTS: [usize; 16] = [
+    0x00, 0x08, 0x0B, 0x0A, 0x0D, 0x0E, 0x0C, 0x05, 0x03, 0x08, 0x0B, 0x0A, 0x0D, 0x0E, 0x0C, 0x07,
+];
+
+/// Build a color scheme from a Base16 YAML file. Both the original layout
+/// (top-level `scheme:` and `base00: "181818"`) and the newer one (`name:`
+/// and a `palette:` block with `"#181818"`) are accepted.
+pub fn load_base16_scheme(yaml: &str) -> Result<ColorScheme, Base16Error> {
+    let values = parse_flat_yaml(yaml);
+
+    let mut palette = [[0.0; 4]; 16];
+    for (slot, key) in SLOTS.iter().enumerate() {
+        let value = values
+            .iter()
+            .find(|(k, _)| k.eq_ignore_ascii_case(key))
+            .map(|(_, v)| v.as_str())
+            .ok_or(Base16Error::MissingColor(key))?;
+        palette[slot] = parse_hex(value).ok_or_else(|| Base16Error::InvalidColor {
+            key,
+            value: value.to_string(),
+        })?;
+    }
+
+    let name = values
+        .get("scheme")
+        .or_else(|| values.get("name"))
+        .cloned()
+        .unwrap_or_else(|| "Base16".to_string());
+    Ok(ColorScheme {
+        name,
+        foreground: palette[0x05],
+        background: palette[0x00],
+        colors: ANSI_SLOTS.map(|slot| palette[slot]),
+        highlight_color: None,
+    })
+}
+
+/// Every `key: value` line, ignoring nesting, comments and quotes
+fn parse_flat_yaml(yaml: &str) -> HashMap<String, String> {
+    let mut values = HashMap::new();
+    for line in yaml.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let Some((key, value)) = line.split_once(':') else {
+            continue;
+        };
+        let value = value.trim();
+        let value = match value.chars().next() {
+            Some(quote @ ('"' | '\'')) => value[1..].split(quote).next().unwrap_or(""),
+            _ => value.split(" #").next().unwrap_or("").trim(),
+        };
+        values.insert(key.trim().to_string(), value.to_string());
+    }
+    values
+}
+
+fn parse_hex(value: &str) -> Option<[f32; 4]> {
+    let digits = value.strip_prefix('#').unwrap_or(value);
+    if digits.len() != 6 {
+        return None;
+    }
+    u32::from_str_radix(digits, 16).ok().map(hex_color)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DEFAULT_DARK: &str = r#"
+scheme: "Default Dark"
+author: "Chris Kempson (http://chriskempson.com)"
+base00: "181818"
+base01: "282828"
+base02: "383838"
+base03: "585858"
+base04: "b8b8b8"
+base05: "d8d8d8"
+base06: "e8e8e8"
+base07: "f8f8f8"
+base08: "ab4642"
+base09: "dc9656"
+base0A: "f7ca88"
+base0B: "a1b56c"
+base0C: "86c1b9"
+base0D: "7cafc2"
+base0E: "ba8baf"
+base0F: "a16946"
+"#;
+
+    #[test]
+    fn default_dark_colors() {
+        let scheme = load_base16_scheme(DEFAULT_DARK).unwrap();
+        assert_eq!(scheme.name, "Default Dark");
+        assert_eq!(scheme.foreground, hex_color(0xd8d8d8));
+        assert_eq!(scheme.background, hex_color(0x181818));
+        assert_eq!(scheme.colors[1], hex_color(0xab4642));
+        assert_eq!(scheme.colors[2], hex_color(0xa1b56c));
+        assert_eq!(scheme.colors[8], hex_color(0x585858));
+        assert_eq!(scheme.colors[15], hex_color(0xf8f8f8));
+    }
+
+    #[test]
+    fn palette_block_layout() {
+        let palette: String = DEFAULT_DARK
+            .lines()
+            .filter(|line| line.starts_with("base"))
+            .map(|line| format!("  {}\n", line.replace(": \"", ": \"#")))
+            .collect();
+        let yaml = format!(
+            "system: \"base16\"\nname: \"Default Dark\"\npalette:\n{}",
+            palette
+        );
+        let scheme = load_base16_scheme(&yaml).unwrap();
+        assert_eq!(scheme.name, "Default Dark");
+        assert_eq!(scheme.background, hex_color(0x181818));
+    }
+
+    #[test]
+    fn missing_or_bad_colors_are_errors() {
+        let missing = DEFAULT_DARK.replace("base0F: \"a16946\"", "");
+        assert!(matches!(
+            load_base16_scheme(&missing),
+            Err(Base16Error::MissingColor("base0F"))
+        ));
+        let bad = DEFAULT_DARK.replace("a16946", "zz");
+        assert!(matches!(
+            load_base16_scheme(&bad),
+            Err(Base16Error::InvalidColor { key: "base0F", .. })
+        ));
+    }
+}
@@ -2,9 +2,11 @@
 // ABOUTME: Loads and saves settings from TOML config files.
 
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::path::PathBuf;
 
-use crate::EffectSettings;
+use crate::base16::{load_base16_scheme, Base16Error};
+use crate::{EffectSettings, KeySpecs};
 
 /// A 16-color terminal palette plus foreground/background
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
@@ -14,6 +16,9 @@ pub struct ColorScheme {
     pub background: [f32; 4],
     /// ANSI colors 0-15 (8 normal + 8 bright)
     pub colors: [[f32; 4]; 16],
+    /// Background of search matches (None = yellow, color 3)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub highlight_color: Option<[f32; 4]>,
 }
 
 impl ColorScheme {
@@ -50,6 +55,7 @@ impl ColorScheme {
                 bright, // 14: bright cyan
                 full,   // 15: bright white
             ],
+            highlight_color: None,
         }
     }
 
@@ -70,6 +76,7 @@ impl ColorScheme {
                 bg, dark, medium, medium, dark, dark, medium, bright, dark, medium, bright, bright,
                 medium, medium, bright, full,
             ],
+            highlight_color: None,
         }
     }
 
@@ -89,6 +96,7 @@ impl ColorScheme {
                 bg, dark, medium, medium, dark, dark, medium, bright, dark, medium, bright, bright,
                 medium, medium, bright, full,
             ],
+            highlight_color: None,
         }
     }
 
@@ -116,12 +124,150 @@ impl ColorScheme {
                 [0.4, 1.0, 1.0, 1.0],    // 14: bright cyan
                 [1.0, 1.0, 1.0, 1.0],    // 15: bright white
             ],
+            highlight_color: None,
         }
     }
 
+    /// Solarized Dark. Colors 8-15 are Solarized's extra accents and
+    /// base tones, not brighter versions of 0-7.
+    pub fn solarized_dark() -> Self {
+        Self::from_hex("Solarized Dark", 0x839496, 0x002b36, SOLARIZED_PALETTE)
+    }
+
+    /// Solarized Light: the Solarized palette on its light base tones
+    pub fn solarized_light() -> Self {
+        Self::from_hex("Solarized Light", 0x657b83, 0xfdf6e3, SOLARIZED_PALETTE)
+    }
+
+    pub fn gruvbox_dark() -> Self {
+        Self::from_hex(
+            "Gruvbox Dark",
+            0xebdbb2,
+            0x282828,
+            [
+                0x282828, 0xcc241d, 0x98971a, 0xd79921, 0x458588, 0xb16286, 0x689d6a, 0xa89984,
+                0x928374, 0xfb4934, 0xb8bb26, 0xfabd2f, 0x83a598, 0xd3869b, 0x8ec07c, 0xebdbb2,
+            ],
+        )
+    }
+
+    pub fn gruvbox_light() -> Self {
+        Self::from_hex(
+            "Gruvbox Light",
+            0x3c3836,
+            0xfbf1c7,
+            [
+                0xfbf1c7, 0xcc241d, 0x98971a, 0xd79921, 0x458588, 0xb16286, 0x689d6a, 0x7c6f64,
+                0x928374, 0x9d0006, 0x79740e, 0xb57614, 0x076678, 0x8f3f71, 0x427b58, 0x3c3836,
+            ],
+        )
+    }
+
+    pub fn dracula() -> Self {
+        Self::from_hex(
+            "Dracula",
+            0xf8f8f2,
+            0x282a36,
+            [
+                0x21222c, 0xff5555, 0x50fa7b, 0xf1fa8c, 0xbd93f9, 0xff79c6, 0x8be9fd, 0xf8f8f2,
+                0x6272a4, 0xff6e6e, 0x69ff94, 0xffffa5, 0xd6acff, 0xff92df, 0xa4ffff, 0xffffff,
+            ],
+        )
+    }
+
+    pub fn nord() -> Self {
+        Self::from_hex(
+            "Nord",
+            0xd8dee9,
+            0x2e3440,
+            [
+                0x3b4252, 0xbf616a, 0xa3be8c, 0xebcb8b, 0x81a1c1, 0xb48ead, 0x88c0d0, 0xe5e9f0,
+                0x4c566a, 0xbf616a, 0xa3be8c, 0xebcb8b, 0x81a1c1, 0xb48ead, 0x8fbcbb, 0xeceff4,
+            ],
+        )
+    }
+
+    pub fn monokai() -> Self {
+        Self::from_hex(
+            "Monokai",
+            0xf8f8f2,
+            0x272822,
+            [
+                0x272822, 0xf92672, 0xa6e22e, 0xf4bf75, 0x66d9ef, 0xae81ff, 0xa1efe4, 0xf8f8f2,
+                0x75715e, 0xf92672, 0xa6e22e, 0xf4bf75, 0x66d9ef, 0xae81ff, 0xa1efe4, 0xf9f8f5,
+            ],
+        )
+    }
+
     pub fn presets() -> Vec<ColorScheme> {
-        vec![Self::amber(), Self::green(), Self::white(), Self::ansi()]
+        vec![
+            Self::amber(),
+            Self::green(),
+            Self::white(),
+            Self::ansi(),
+            Self::solarized_dark(),
+            Self::solarized_light(),
+            Self::gruvbox_dark(),
+            Self::gruvbox_light(),
+            Self::dracula(),
+            Self::nord(),
+            Self::monokai(),
+        ]
     }
+
+    /// Read a scheme from a file: a Base16 scheme if it ends in `.yaml` or
+    /// `.yml`, otherwise TOML with `name`, `foreground`, `background` and
+    /// all 16 `colors`
+    pub fn load(path: &std::path::Path) -> Result<Self, ConfigError> {
+        let content = std::fs::read_to_string(path)?;
+        let is_yaml = path
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("yaml") || ext.eq_ignore_ascii_case("yml"));
+        if is_yaml {
+            return Ok(load_base16_scheme(&content)?);
+        }
+        Ok(toml::from_str(&content)?)
+    }
+
+    /// A scheme from 0xRRGGBB values, as theme authors publish them
+    fn from_hex(name: &str, foreground: u32, background: u32, colors: [u32; 16]) -> Self {
+        Self {
+            name: name.to_string(),
+            foreground: hex_color(foreground),
+            background: hex_color(background),
+            colors: colors.map(hex_color),
+            highlight_color: None,
+        }
+    }
+
+    /// Background of search matches
+    pub fn search_highlight(&self) -> [f32; 4] {
+        self.highlight_color.unwrap_or(self.colors[3])
+    }
+
+    /// A color at 60% brightness, as faint (SGR 2) text is drawn
+    pub fn dim(color: [f32; 4]) -> [f32; 4] {
+        [color[0] * 0.6, color[1] * 0.6, color[2] * 0.6, color[3]]
+    }
+
+    /// The dimmed foreground, for secondary text in overlays
+    pub fn dim_color(&self) -> [f32; 4] {
+        Self::dim(self.foreground)
+    }
+}
+
+/// Solarized's terminal mapping: base02, red, green, yellow, blue, magenta,
+/// cyan, base2, then base03, orange, base01, base00, base0, violet, base1, base3
+const SOLARIZED_PALETTE: [u32; 16] = [
+    0x073642, 0xdc322f, 0x859900, 0xb58900, 0x268bd2, 0xd33682, 0x2aa198, 0xeee8d5, 0x002b36,
+    0xcb4b16, 0x586e75, 0x657b83, 0x839496, 0x6c71c4, 0x93a1a1, 0xfdf6e3,
+];
+
+/// 0xRRGGBB as opaque RGBA
+pub(crate) fn hex_color(rgb: u32) -> [f32; 4] {
+    let channel = |shift: u32| ((rgb >> shift) & 0xff) as f32 / 255.0;
+    [channel(16), channel(8), channel(0), 1.0]
 }
 
 impl Default for ColorScheme {
@@ -269,6 +415,158 @@ impl Font {
     }
 }
 
+/// Bundled fallback fonts for characters missing from the primary font.
+/// They are tried in the configured order; Unifont is always tried last.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FallbackFont {
+    /// Hack - monospace text with broad Latin/Greek/Cyrillic coverage
+    Hack,
+    /// Noto Sans Symbols 2 - technical symbols, arrows, dingbats
+    Symbols,
+    /// Noto Emoji (monochrome)
+    Emoji,
+    /// Symbols Nerd Font - Powerline separators and developer icons (PUA).
+    /// Loaded from the system font directories when installed.
+    NerdSymbols,
+}
+
+impl FallbackFont {
+    /// The built-in chain: Hack -> Symbols -> Nerd Font symbols -> Emoji
+    pub fn default_chain() -> Vec<FallbackFont> {
+        vec![
+            FallbackFont::Hack,
+            FallbackFont::Symbols,
+            FallbackFont::NerdSymbols,
+            FallbackFont::Emoji,
+        ]
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            FallbackFont::Hack => "Hack",
+            FallbackFont::Symbols => "Noto Sans Symbols 2",
+            FallbackFont::Emoji => "Noto Emoji",
+            FallbackFont::NerdSymbols => "Symbols Nerd Font",
+        }
+    }
+
+    /// Whether to use this fallback behind a BDF font. Text faces are
+    /// skipped there to keep the bitmap look; Unifont covers text instead.
+    pub fn suits_bitmap_fonts(&self) -> bool {
+        matches!(self, FallbackFont::Emoji | FallbackFont::NerdSymbols)
+    }
+}
+
+/// What the glyph atlas does when it runs out of room for a new glyph
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum AtlasEvictionPolicy {
+    /// Double the atlas up to `max_atlas_size`, then evict old glyphs
+    #[default]
+    Grow,
+    /// Keep the atlas at its initial size and reuse the slots of
+    /// least-recently-used glyphs (less memory, more re-rasterizing)
+    Lru,
+    /// Keep the atlas at its initial size; glyphs that don't fit aren't drawn
+    Error,
+}
+
+/// Which OSC 52 clipboard requests programs in a pane may make
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum Osc52Clipboard {
+    /// Ignore OSC 52 entirely
+    Disabled,
+    /// Programs may set the clipboard but not read it
+    #[default]
+    WriteOnly,
+    /// Programs may also read the clipboard, e.g. to paste into a remote vim
+    ReadWrite,
+}
+
+/// What copying a selection puts on the clipboard
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum CopyFormat {
+    /// Plain text only
+    #[default]
+    Plain,
+    /// An HTML fragment with the text's colors, plus plain text for
+    /// programs that don't take HTML
+    Html,
+    /// Text with ANSI color escapes, for pasting into another terminal
+    Ansi,
+}
+
+/// What happens to a pane when its process exits
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum ExitBehavior {
+    /// Close the pane immediately
+    #[default]
+    Close,
+    /// Keep the final screen until Enter is pressed in the pane
+    Hold,
+    /// Hold only if the process failed (non-zero code or killed by a signal)
+    HoldOnError,
+}
+
+impl ExitBehavior {
+    /// Whether a pane whose process exited with `code` should stay open
+    pub fn holds(self, code: Option<i32>) -> bool {
+        match self {
+            ExitBehavior::Close => false,
+            ExitBehavior::Hold => true,
+            ExitBehavior::HoldOnError => code != Some(0),
+        }
+    }
+}
+
+/// How the lines between panes are drawn
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum PaneBorderStyle {
+    /// No lines; panes are only set apart by their padding
+    None,
+    #[default]
+    Thin,
+    Thick,
+    Double,
+}
+
+impl PaneBorderStyle {
+    pub fn all() -> &'static [PaneBorderStyle] {
+        &[
+            PaneBorderStyle::None,
+            PaneBorderStyle::Thin,
+            PaneBorderStyle::Thick,
+            PaneBorderStyle::Double,
+        ]
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            PaneBorderStyle::None => "None",
+            PaneBorderStyle::Thin => "Thin",
+            PaneBorderStyle::Thick => "Thick",
+            PaneBorderStyle::Double => "Double",
+        }
+    }
+
+    pub fn next(&self) -> PaneBorderStyle {
+        let all = PaneBorderStyle::all();
+        let idx = all.iter().position(|s| s == self).unwrap_or(0);
+        all[(idx + 1) % all.len()]
+    }
+
+    pub fn prev(&self) -> PaneBorderStyle {
+        let all = PaneBorderStyle::all();
+        let idx = all.iter().position(|s| s == self).unwrap_or(0);
+        all[(idx + all.len() - 1) % all.len()]
+    }
+}
+
 /// Bundled BDF (bitmap) font options - pixel-perfect, no scaling
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
@@ -399,6 +697,112 @@ pub struct BehaviorSettings {
     pub show_kitty_message: bool,
     /// Restore terminal session (scrollback + working directory) on startup
     pub restore_session: bool,
+    /// Re-run the program each pane had in the foreground when the session
+    /// was saved, instead of starting a shell
+    pub restore_commands: bool,
+    /// Only draw frames when something on screen changed or is animating,
+    /// instead of continuously at the frame rate
+    pub power_save: bool,
+    /// Draw bold text in colors 0-7 using the bright colors 8-15
+    pub bold_is_bright: bool,
+    /// Program to run in new panes (None = $SHELL)
+    pub shell: Option<String>,
+    /// Arguments passed to `shell`
+    pub shell_args: Vec<String>,
+    /// Start the shell as a login shell
+    pub login_shell: bool,
+    /// What happens to a pane when its process exits
+    pub exit_behavior: ExitBehavior,
+    /// Seconds without output before a silence-monitored pane is flagged
+    pub silence_timeout_secs: u64,
+    /// Ask the window manager for attention when a monitored pane is flagged
+    pub monitor_requests_attention: bool,
+    /// Ask before pasting text with newlines or control characters when the
+    /// application has not enabled bracketed paste
+    pub confirm_multiline_paste: bool,
+    /// Flash the screen when an application rings the bell (BEL)
+    pub visual_bell: bool,
+    /// Color of the bell flash; alpha is its starting opacity
+    pub visual_bell_color: [f32; 4],
+    /// Clipboard access for programs using OSC 52 (e.g. vim over SSH)
+    pub allow_osc52_clipboard: Osc52Clipboard,
+    /// Command that opens file:line:col hints, e.g. "code --goto {file}:{line}:{col}"
+    /// (None = the system opener, without line numbers)
+    pub hint_editor_command: Option<String>,
+    /// Window title when the focused pane's program set one; supports
+    /// `{title}`, `{pane}` (1-based pane number) and `{cwd}`
+    pub title_format: String,
+    /// Quote paths dropped onto the window so the shell sees each as one word
+    pub quote_dropped_paths: bool,
+    /// Lines of history kept per pane; "unlimited" in the file (usize::MAX)
+    /// keeps everything, at the cost of memory
+    #[serde(with = "scrollback_lines")]
+    pub scrollback_lines: usize,
+    /// Scrollback overrides keyed by 1-based pane number ("1", "2", ...)
+    pub per_pane_scrollback: BTreeMap<String, usize>,
+    /// Page history past the scrollback limit out to a file instead of
+    /// dropping it, for unlimited history without the memory use
+    pub disk_scrollback: bool,
+    /// Directory for the scrollback files (None = the system temp directory)
+    pub disk_scrollback_path: Option<PathBuf>,
+    /// Scroll touchpad gestures by the pixel instead of by whole lines, and
+    /// let mouse wheel scrolling glide to a stop instead of jumping
+    pub smooth_scroll: bool,
+    /// Speed one mouse wheel notch adds to the glide, in lines per second
+    pub smooth_scroll_speed: f32,
+    /// Keep colors when copying, as HTML or ANSI escapes
+    pub copy_format: CopyFormat,
+    /// Put selected text in the X11/Wayland primary selection and paste it
+    /// with the middle button
+    pub use_primary_selection: bool,
+    /// Show frame and GPU times, glyph cache and atlas usage and dropped
+    /// frames in a corner, and log them every few seconds
+    pub show_perf_overlay: bool,
+    /// Show a row at the bottom of the window with the focused pane's
+    /// program, directory and size, and the color scheme and font
+    pub status_bar: bool,
+}
+
+impl BehaviorSettings {
+    /// Scrollback limit for the pane with this 1-based number
+    pub fn scrollback_for_pane(&self, number: usize) -> usize {
+        self.per_pane_scrollback
+            .get(&number.to_string())
+            .copied()
+            .unwrap_or(self.scrollback_lines)
+    }
+}
+
+/// Reads and writes `scrollback_lines` as a count or "unlimited"
+mod scrollback_lines {
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    const UNLIMITED: &str = "unlimited";
+
+    pub fn serialize<S: Serializer>(lines: &usize, serializer: S) -> Result<S::Ok, S::Error> {
+        if *lines == usize::MAX {
+            serializer.serialize_str(UNLIMITED)
+        } else {
+            serializer.serialize_u64(*lines as u64)
+        }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<usize, D::Error> {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum Lines {
+            Count(usize),
+            Word(String),
+        }
+        match Lines::deserialize(deserializer)? {
+            Lines::Count(lines) => Ok(lines),
+            Lines::Word(word) if word == UNLIMITED => Ok(usize::MAX),
+            Lines::Word(word) => Err(serde::de::Error::custom(format!(
+                "expected a number of lines or \"unlimited\", got \"{}\"",
+                word
+            ))),
+        }
+    }
 }
 
 impl Default for BehaviorSettings {
@@ -408,6 +812,53 @@ impl Default for BehaviorSettings {
             show_startup_hint: true,
             show_kitty_message: true,
             restore_session: true,
+            restore_commands: false,
+            power_save: true,
+            bold_is_bright: false,
+            shell: None,
+            shell_args: Vec::new(),
+            login_shell: false,
+            exit_behavior: ExitBehavior::default(),
+            silence_timeout_secs: 10,
+            monitor_requests_attention: true,
+            confirm_multiline_paste: true,
+            visual_bell: true,
+            visual_bell_color: [1.0, 1.0, 1.0, 0.3],
+            allow_osc52_clipboard: Osc52Clipboard::default(),
+            hint_editor_command: None,
+            title_format: "{title} — cool-rust-term".to_string(),
+            quote_dropped_paths: true,
+            scrollback_lines: 10_000,
+            per_pane_scrollback: BTreeMap::new(),
+            disk_scrollback: false,
+            disk_scrollback_path: None,
+            smooth_scroll: true,
+            smooth_scroll_speed: 30.0,
+            copy_format: CopyFormat::default(),
+            use_primary_selection: true,
+            show_perf_overlay: false,
+            status_bar: false,
+        }
+    }
+}
+
+/// Where a floating pane sits, as fractions of the window size
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FloatConfig {
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+}
+
+impl Default for FloatConfig {
+    fn default() -> Self {
+        Self {
+            x: 0.1,
+            y: 0.05,
+            width: 0.8,
+            height: 0.45,
         }
     }
 }
@@ -424,18 +875,49 @@ pub struct Config {
     /// Selected TTF font (used when bdf_font is None)
     pub font: Font,
 
+    /// TTF/OTF file used instead of the bundled `font` when set
+    pub font_path: Option<PathBuf>,
+
     /// Font size in pixels (used for TTF fonts; BDF fonts use their native size)
     pub font_size: f32,
 
-    /// UI scaling factor (1.0-3.0), only applied to TTF fonts for high-DPI displays
+    /// Font size overrides keyed by 1-based pane number ("1", "2", ...)
+    pub per_pane_font_size: BTreeMap<String, f32>,
+
+    /// UI scaling factor (0.5-3.0), only applied to TTF fonts; multiplies
+    /// the display's scale factor when auto_scale is on
     pub ui_scale: f32,
 
+    /// Follow the display's scale factor, also when the window moves to
+    /// another display
+    pub auto_scale: bool,
+
+    /// Row height as a multiple of the font's cell height (1.0 = tight)
+    pub line_height_mult: f32,
+
+    /// Extra pixels between columns
+    pub letter_spacing: f32,
+
     /// Optional BDF bitmap font (overrides TTF `font` if set)
     pub bdf_font: Option<BdfFont>,
 
+    /// BDF file used instead of the bundled `bdf_font`; setting it selects
+    /// BDF on its own
+    pub bdf_font_path: Option<PathBuf>,
+
+    /// Directory searched at startup for BDF files to offer in the settings
+    pub bdf_font_dir: Option<PathBuf>,
+
+    /// Fallback fonts tried in order for characters the primary font lacks
+    pub fallback_fonts: Vec<FallbackFont>,
+
     /// Color scheme (16 ANSI colors + fg/bg)
     pub color_scheme: ColorScheme,
 
+    /// TOML file with a color scheme that replaces `color_scheme` on load.
+    /// Relative paths are resolved against the config file's directory.
+    pub color_scheme_file: Option<PathBuf>,
+
     /// Window dimensions
     pub window_width: u32,
     pub window_height: u32,
@@ -447,8 +929,40 @@ pub struct Config {
     /// Number of panes to restore on startup
     pub pane_count: u32,
 
+    /// Smallest pane, in cells, that adding a pane may leave behind
+    pub min_pane_cols: u16,
+    pub min_pane_rows: u16,
+
+    /// Position and size of floating panes, kept from the last time one was moved
+    pub floating_pane: FloatConfig,
+
+    /// Pixels of space around each pane's content
+    pub pane_padding: f32,
+
+    /// Lines drawn between panes
+    pub pane_border_style: PaneBorderStyle,
+
     /// Per-pane CRT effects (each pane is its own "monitor")
     pub per_pane_crt: bool,
+
+    /// Maximum number of glyphs kept in the atlas before least-recently-used
+    /// ones are evicted (None = unbounded)
+    pub max_cached_glyphs: Option<usize>,
+
+    /// Largest glyph atlas texture size in pixels per side. The atlas starts
+    /// at 1024 and doubles when full up to this size.
+    pub max_atlas_size: u32,
+
+    /// What to do when the glyph atlas is full: grow, evict or give up
+    pub atlas_eviction_policy: AtlasEvictionPolicy,
+
+    /// Store the glyph atlas BC4-compressed on GPUs that support it. Halves
+    /// the atlas memory but blurs glyph edges slightly, so it is off by default
+    pub compress_glyph_atlas: bool,
+
+    /// Shortcut overrides: action name -> key spec(s), e.g. `copy = "ctrl+shift+c"`.
+    /// Actions not listed keep their default bindings.
+    pub keybindings: BTreeMap<String, KeySpecs>,
 }
 
 impl Default for Config {
@@ -458,15 +972,34 @@ impl Default for Config {
             behavior: BehaviorSettings::default(),
             font: Font::default(),
             font_size: 18.0,
+            per_pane_font_size: BTreeMap::new(),
             ui_scale: 1.0,
+            auto_scale: true,
+            line_height_mult: 1.0,
+            letter_spacing: 0.0,
             bdf_font: None,
+            bdf_font_path: None,
+            bdf_font_dir: None,
+            font_path: None,
+            fallback_fonts: FallbackFont::default_chain(),
             color_scheme: ColorScheme::default(),
+            color_scheme_file: None,
             window_width: 1200,
             window_height: 800,
             window_x: None,
             window_y: None,
             pane_count: 1,
+            min_pane_cols: 20,
+            min_pane_rows: 6,
+            floating_pane: FloatConfig::default(),
+            pane_padding: 8.0,
+            pane_border_style: PaneBorderStyle::default(),
             per_pane_crt: false,
+            max_cached_glyphs: None,
+            max_atlas_size: 4096,
+            atlas_eviction_policy: AtlasEvictionPolicy::default(),
+            compress_glyph_atlas: false,
+            keybindings: BTreeMap::new(),
         }
     }
 }
@@ -481,9 +1014,55 @@ pub enum ConfigError {
 
     #[error("Failed to serialize config: {0}")]
     SerializeError(#[from] toml::ser::Error),
+
+    #[error("Failed to parse Base16 scheme: {0}")]
+    Base16Error(#[from] Base16Error),
 }
 
 impl Config {
+    /// Font size for the pane with this 1-based number
+    pub fn font_size_for_pane(&self, number: usize) -> f32 {
+        self.per_pane_font_size
+            .get(&number.to_string())
+            .copied()
+            .unwrap_or(self.font_size)
+    }
+
+    /// File name of the custom TTF/OTF font, if one is set
+    pub fn custom_font_name(&self) -> Option<String> {
+        let path = self.font_path.as_ref()?;
+        let name = path.file_name().unwrap_or(path.as_os_str());
+        Some(name.to_string_lossy().into_owned())
+    }
+
+    /// File name of the custom BDF font, if one is set
+    pub fn custom_bdf_font_name(&self) -> Option<String> {
+        let path = self.bdf_font_path.as_ref()?;
+        let name = path.file_name().unwrap_or(path.as_os_str());
+        Some(name.to_string_lossy().into_owned())
+    }
+
+    /// Name of the font in use, for display
+    pub fn font_label(&self) -> String {
+        match self.bdf_font {
+            Some(bdf_font) => self
+                .custom_bdf_font_name()
+                .unwrap_or_else(|| bdf_font.label().to_string()),
+            None => self
+                .custom_font_name()
+                .unwrap_or_else(|| self.font.label().to_string()),
+        }
+    }
+
+    /// Scale for fonts and the UI on a display with this scale factor
+    pub fn effective_scale(&self, display_scale: f32) -> f32 {
+        if self.auto_scale {
+            self.ui_scale * display_scale
+        } else {
+            self.ui_scale
+        }
+    }
+
     /// Get the default config file path (~/.config/cool-rust-term/config.toml)
     pub fn default_path() -> Option<PathBuf> {
         dirs::config_dir().map(|p| p.join("cool-rust-term").join("config.toml"))
@@ -492,7 +1071,34 @@ impl Config {
     /// Load config from a path
     pub fn load(path: &std::path::Path) -> Result<Self, ConfigError> {
         let content = std::fs::read_to_string(path)?;
-        let config = toml::from_str(&content)?;
+        let mut config: Config = toml::from_str(&content)?;
+        // A ui_scale saved before auto_scale existed already makes up for
+        // the display, so don't multiply it by the display's factor too
+        let raw: toml::Table = toml::from_str(&content)?;
+        if config.ui_scale != 1.0 && !raw.contains_key("auto_scale") {
+            config.auto_scale = false;
+        }
+        // A BDF file alone selects BDF, with Fixed 9x18 standing in
+        // if it doesn't load
+        if config.bdf_font_path.is_some() && config.bdf_font.is_none() {
+            config.bdf_font = Some(BdfFont::Fixed9x18);
+        }
+        config.pane_padding = config.pane_padding.max(0.0);
+        // A chain saved before auto_scale existed also predates the Nerd
+        // Font symbols; a newer one without them had them taken out
+        if raw.contains_key("fallback_fonts")
+            && !raw.contains_key("auto_scale")
+            && !config.fallback_fonts.contains(&FallbackFont::NerdSymbols)
+        {
+            config.fallback_fonts.push(FallbackFont::NerdSymbols);
+        }
+        if let Some(file) = &config.color_scheme_file {
+            let file = path.parent().map_or(file.clone(), |dir| dir.join(file));
+            match ColorScheme::load(&file) {
+                Ok(scheme) => config.color_scheme = scheme,
+                Err(e) => tracing::warn!("Ignoring color scheme file {}: {}", file.display(), e),
+            }
+        }
         Ok(config)
     }
 
@@ -526,3 +1132,68 @@ impl Config {
         Ok(path)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn load(name: &str, toml: &str) -> Config {
+        let temp_path = std::env::temp_dir().join(format!("test_config_{}.toml", name));
+        std::fs::write(&temp_path, toml).unwrap();
+        let config = Config::load(&temp_path).unwrap();
+        let _ = std::fs::remove_file(&temp_path);
+        config
+    }
+
+    #[test]
+    fn test_effective_scale_multiplies_display_factor() {
+        let mut config = Config {
+            ui_scale: 1.25,
+            ..Config::default()
+        };
+        assert_eq!(config.effective_scale(2.0), 2.5);
+        assert_eq!(config.effective_scale(1.5), 1.875);
+        config.auto_scale = false;
+        assert_eq!(config.effective_scale(1.5), 1.25);
+    }
+
+    #[test]
+    fn test_old_ui_scale_not_scaled_again() {
+        // Set for a 2x display before auto_scale existed: stays 2x, not 4x
+        let config = load("old_scale", "ui_scale = 2.0\n");
+        assert!(!config.auto_scale);
+        assert_eq!(config.effective_scale(2.0), 2.0);
+
+        let config = load("auto_scale", "ui_scale = 2.0\nauto_scale = true\n");
+        assert_eq!(config.effective_scale(2.0), 4.0);
+        let config = load("unit_scale", "ui_scale = 1.0\n");
+        assert_eq!(config.effective_scale(1.5), 1.5);
+    }
+
+    #[test]
+    fn test_nerd_symbols_added_to_old_chains_only() {
+        let old = load("old_chain", "fallback_fonts = [\"hack\", \"emoji\"]\n");
+        assert_eq!(
+            old.fallback_fonts,
+            [
+                FallbackFont::Hack,
+                FallbackFont::Emoji,
+                FallbackFont::NerdSymbols
+            ]
+        );
+
+        // Saved by this version with nerd_symbols taken out
+        let mut config = Config {
+            fallback_fonts: vec![FallbackFont::Hack, FallbackFont::Emoji],
+            ..Config::default()
+        };
+        let path = std::env::temp_dir().join("test_config_new_chain.toml");
+        config.save(&path).unwrap();
+        config = Config::load(&path).unwrap();
+        let _ = std::fs::remove_file(&path);
+        assert_eq!(
+            config.fallback_fonts,
+            [FallbackFont::Hack, FallbackFont::Emoji]
+        );
+    }
+}
@@ -5,6 +5,9 @@ use serde::{Deserialize, Serialize};
 
 use crate::Color;
 
+/// Color temperature that leaves colors unchanged (D65 white)
+pub const NEUTRAL_TEMPERATURE: f32 = 6500.0;
+
 /// Scanline rendering mode
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
 #[serde(rename_all = "snake_case")]
@@ -19,6 +22,10 @@ pub enum ScanlineMode {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(default)]
 pub struct EffectSettings {
+    /// Master switch for the CRT look. When off, the burn-in and CRT passes
+    /// are skipped and text is shown crisp, with no post-processing at all.
+    pub enabled: bool,
+
     /// Font/text color (phosphor color)
     pub font_color: Color,
 
@@ -34,6 +41,23 @@ pub struct EffectSettings {
     /// Scanline rendering mode (row-based for TTF, pixel for BDF bitmap fonts)
     pub scanline_mode: ScanlineMode,
 
+    /// Composite video color bleed: colors smear a few pixels to the right
+    /// while brightness edges stay sharp (0.0 = off, 1.0 = strong)
+    pub composite_bleed: f32,
+
+    /// White point of the screen in Kelvin (2000-10000): 6500 is neutral,
+    /// lower is warmer, higher is the blue-white of office monitors
+    pub color_temperature: f32,
+
+    /// RGB multiplier used instead of `color_temperature` when set, for
+    /// manual hue shifts
+    pub color_tint: Option<[f32; 3]>,
+
+    /// How much brightness shields a pixel from scanline darkening, like
+    /// bright phosphor blooming over the gaps (0.0 = uniform scanlines,
+    /// higher = bright text keeps more of its punch)
+    pub scanline_gamma: f32,
+
     /// Bloom/glow amount (0.0 = none, 1.0 = strong)
     pub bloom: f32,
 
@@ -49,9 +73,13 @@ pub struct EffectSettings {
     /// Horizontal sync jitter
     pub horizontal_sync: f32,
 
-    /// RGB color shift/chromatic aberration
+    /// Chromatic aberration: how far red and blue separate toward the
+    /// screen edges, in pixels (0.0 = none)
     pub rgb_shift: f32,
 
+    /// RGB phosphor mask strength (0.0 = none, 1.0 = full aperture grille)
+    pub rgb_mask: f32,
+
     /// Ambient light reflection
     pub ambient_light: f32,
 
@@ -61,6 +89,15 @@ pub struct EffectSettings {
     /// Vignette intensity - darkening toward screen edges (0.0 = none, 1.0 = strong)
     pub vignette: f32,
 
+    /// Rounded screen corners, as a fraction of the screen's shorter side
+    /// (each pane's with per-pane CRT); content past them is masked to black
+    /// (0.0 = square, 0.25 = very round)
+    pub corner_radius: f32,
+
+    /// Opacity of the window background (1.0 = opaque, 0.0 = the desktop shows
+    /// through). Text and glow stay opaque. Going below 1.0 needs a restart.
+    pub background_opacity: f32,
+
     /// Focus glow corner radius (0.0 = sharp corners, 0.2 = very rounded)
     pub focus_glow_radius: f32,
 
@@ -88,6 +125,12 @@ pub struct EffectSettings {
     /// Enable interlaced rendering (odd/even scanline fields)
     /// Only applies when beam_simulation_enabled is true
     pub interlace_enabled: bool,
+
+    /// Use `phosphor_tint` for the glow instead of the foreground color
+    pub use_custom_glow: bool,
+
+    /// Custom glow color, e.g. a green halo around white text
+    pub phosphor_tint: [f32; 4],
 }
 
 impl Default for EffectSettings {
@@ -100,20 +143,28 @@ impl EffectSettings {
     /// Amber preset - warm CRT monitor look
     pub fn amber() -> Self {
         Self {
+            enabled: true,
             font_color: Color::AMBER,
             background_color: Color::rgba(0.0, 0.0, 0.0, 1.0),
             screen_curvature: 0.1,
             scanline_intensity: 0.45,
             scanline_mode: ScanlineMode::RowBased,
+            composite_bleed: 0.0,
+            color_temperature: NEUTRAL_TEMPERATURE,
+            color_tint: None,
+            scanline_gamma: 0.0,
             bloom: 0.4,
             burn_in: 0.4,
             static_noise: 0.02,
             flicker: 0.25,
             horizontal_sync: 0.0,
             rgb_shift: 0.0,
+            rgb_mask: 0.0,
             ambient_light: 0.1,
             brightness: 1.0,
             vignette: 0.25,
+            corner_radius: 0.0,
+            background_opacity: 1.0,
             focus_glow_radius: 0.01,
             focus_glow_width: 0.005,
             focus_glow_intensity: 0.4,
@@ -122,6 +173,54 @@ impl EffectSettings {
             content_scale_y: 1.0,
             beam_simulation_enabled: false,
             interlace_enabled: true, // Default on when beam sim is enabled
+            use_custom_glow: false,
+            phosphor_tint: [0.2, 1.0, 0.3, 1.0],
         }
     }
+
+    /// RGB multiplier the CRT pass applies to the picture: `color_tint` if
+    /// set, otherwise the white of `color_temperature` relative to 6500K
+    pub fn white_point(&self) -> [f32; 3] {
+        if let Some(tint) = self.color_tint {
+            return tint;
+        }
+        let [r, g, b] = kelvin_to_rgb(self.color_temperature);
+        let [nr, ng, nb] = kelvin_to_rgb(NEUTRAL_TEMPERATURE);
+        [r / nr, g / ng, b / nb]
+    }
+
+    /// Glow color for the CRT pass. Alpha is how strongly bloom takes on the
+    /// color: 0 keeps the text's own color, as without a custom tint.
+    pub fn glow_color(&self, foreground: [f32; 4]) -> [f32; 4] {
+        if self.use_custom_glow {
+            let [r, g, b, _] = self.phosphor_tint;
+            [r, g, b, 1.0]
+        } else {
+            [foreground[0], foreground[1], foreground[2], 0.0]
+        }
+    }
+}
+
+/// Color of a blackbody at `kelvin`, 0 to 1 per channel, after Tanner
+/// Helland's curve fit to the Planckian locus
+fn kelvin_to_rgb(kelvin: f32) -> [f32; 3] {
+    let t = kelvin.clamp(1000.0, 40000.0) / 100.0;
+    let red = if t <= 66.0 {
+        255.0
+    } else {
+        329.698_73 * (t - 60.0).powf(-0.133_204_76)
+    };
+    let green = if t <= 66.0 {
+        99.470_8 * t.ln() - 161.119_57
+    } else {
+        288.122_17 * (t - 60.0).powf(-0.075_514_85)
+    };
+    let blue = if t >= 66.0 {
+        255.0
+    } else if t <= 19.0 {
+        0.0
+    } else {
+        138.517_73 * (t - 10.0).ln() - 305.044_8
+    };
+    [red, green, blue].map(|c| c.clamp(0.0, 255.0) / 255.0)
 }
@@ -0,0 +1,372 @@
+// ABOUTME: Keyboard shortcut configuration.
+// ABOUTME: Parses key specs like "ctrl+shift+c" into a lookup table of app actions.
+
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+
+/// An application command that can be bound to a key
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Action {
+    NewPane,
+    OpenSettings,
+    ToggleDebugGrid,
+    ToggleBeamPause,
+    BeamStep,
+    BeamStepFaster,
+    BeamStepSlower,
+    Copy,
+    CopyFormatted,
+    Paste,
+    ReplayPowerOn,
+    ScrollPageUp,
+    ScrollPageDown,
+    PreviousPrompt,
+    NextPrompt,
+    CycleMonitor,
+    Screenshot,
+    CopyMode,
+    HintMode,
+    ToggleRecording,
+    ToggleEffects,
+    CommandPalette,
+    Search,
+    SelectAll,
+    ClearSelection,
+    ScrollToBottom,
+    SwapPane,
+    ToggleFloating,
+}
+
+impl Action {
+    pub fn all() -> &'static [Action] {
+        &[
+            Action::NewPane,
+            Action::OpenSettings,
+            Action::ToggleDebugGrid,
+            Action::ToggleBeamPause,
+            Action::BeamStep,
+            Action::BeamStepFaster,
+            Action::BeamStepSlower,
+            Action::Copy,
+            Action::CopyFormatted,
+            Action::Paste,
+            Action::ReplayPowerOn,
+            Action::ScrollPageUp,
+            Action::ScrollPageDown,
+            Action::PreviousPrompt,
+            Action::NextPrompt,
+            Action::CycleMonitor,
+            Action::Screenshot,
+            Action::CopyMode,
+            Action::HintMode,
+            Action::ToggleRecording,
+            Action::ToggleEffects,
+            Action::CommandPalette,
+            Action::Search,
+            Action::SelectAll,
+            Action::ClearSelection,
+            Action::ScrollToBottom,
+            Action::SwapPane,
+            Action::ToggleFloating,
+        ]
+    }
+
+    /// Name used in the `[keybindings]` config section
+    pub fn name(&self) -> &'static str {
+        match self {
+            Action::NewPane => "new_pane",
+            Action::OpenSettings => "open_settings",
+            Action::ToggleDebugGrid => "toggle_debug_grid",
+            Action::ToggleBeamPause => "toggle_beam_pause",
+            Action::BeamStep => "beam_step",
+            Action::BeamStepFaster => "beam_step_faster",
+            Action::BeamStepSlower => "beam_step_slower",
+            Action::Copy => "copy",
+            Action::CopyFormatted => "copy_formatted",
+            Action::Paste => "paste",
+            Action::ReplayPowerOn => "replay_power_on",
+            Action::ScrollPageUp => "scroll_page_up",
+            Action::ScrollPageDown => "scroll_page_down",
+            Action::PreviousPrompt => "previous_prompt",
+            Action::NextPrompt => "next_prompt",
+            Action::CycleMonitor => "cycle_monitor",
+            Action::Screenshot => "screenshot",
+            Action::CopyMode => "copy_mode",
+            Action::HintMode => "hint_mode",
+            Action::ToggleRecording => "toggle_recording",
+            Action::ToggleEffects => "toggle_effects",
+            Action::CommandPalette => "command_palette",
+            Action::Search => "search",
+            Action::SelectAll => "select_all",
+            Action::ClearSelection => "clear_selection",
+            Action::ScrollToBottom => "scroll_to_bottom",
+            Action::SwapPane => "swap_pane",
+            Action::ToggleFloating => "toggle_floating",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Action> {
+        Action::all().iter().copied().find(|a| a.name() == name)
+    }
+
+    /// Built-in bindings, used unless the config overrides the action
+    pub fn default_specs(&self) -> &'static [&'static str] {
+        match self {
+            Action::NewPane => &["ctrl+shift+enter"],
+            Action::OpenSettings => &["ctrl+,"],
+            Action::ToggleDebugGrid => &["ctrl+shift+g"],
+            Action::ToggleBeamPause => &["ctrl+shift+b"],
+            Action::BeamStep => &["ctrl+shift+n"],
+            Action::BeamStepFaster => &["ctrl+shift+=", "ctrl+shift+plus"],
+            Action::BeamStepSlower => &["ctrl+shift+-"],
+            Action::Copy => &["ctrl+shift+c", "super+c"],
+            Action::CopyFormatted => &["ctrl+shift+alt+c"],
+            Action::Paste => &["ctrl+shift+v", "super+v"],
+            Action::ReplayPowerOn => &["ctrl+shift+t"],
+            Action::ScrollPageUp => &["shift+pageup"],
+            Action::ScrollPageDown => &["shift+pagedown"],
+            Action::PreviousPrompt => &["ctrl+shift+up"],
+            Action::NextPrompt => &["ctrl+shift+down"],
+            Action::CycleMonitor => &["ctrl+shift+m"],
+            Action::Screenshot => &["ctrl+shift+s"],
+            Action::CopyMode => &["ctrl+shift+space", "ctrl+shift+[", "ctrl+shift+{"],
+            Action::HintMode => &["ctrl+shift+j"],
+            Action::ToggleRecording => &["ctrl+shift+r"],
+            Action::ToggleEffects => &["ctrl+shift+e"],
+            Action::CommandPalette => &["ctrl+shift+p"],
+            Action::Search => &["ctrl+shift+f"],
+            Action::SelectAll => &["ctrl+shift+a"],
+            Action::ClearSelection => &["escape"],
+            Action::ScrollToBottom => &["shift+end", "end"],
+            Action::SwapPane => &["ctrl+shift+x"],
+            Action::ToggleFloating => &["ctrl+shift+o"],
+        }
+    }
+}
+
+/// A key plus the exact set of modifiers that must be held
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct KeyCombo {
+    /// Canonical key name: a lowercase character, or a name like "enter" or "f5"
+    pub key: String,
+    pub ctrl: bool,
+    pub shift: bool,
+    pub alt: bool,
+    pub super_key: bool,
+}
+
+impl KeyCombo {
+    pub fn new(key: &str, ctrl: bool, shift: bool, alt: bool, super_key: bool) -> Self {
+        Self {
+            key: canonical_key(key),
+            ctrl,
+            shift,
+            alt,
+            super_key,
+        }
+    }
+
+    /// Parse a spec such as "ctrl+shift+c", "super+v" or "shift+pageup"
+    pub fn parse(spec: &str) -> Result<Self, KeybindingError> {
+        let invalid = |reason| KeybindingError::InvalidSpec(spec.to_string(), reason);
+
+        // A trailing "+" is the plus key itself ("ctrl++")
+        let spec_lower = spec.trim().to_lowercase();
+        let (mods, key) = match spec_lower.strip_suffix("++") {
+            Some(mods) => (mods, "+"),
+            None => match spec_lower.rsplit_once('+') {
+                Some((mods, key)) => (mods, key),
+                None => ("", spec_lower.as_str()),
+            },
+        };
+        if key.is_empty() {
+            return Err(invalid("missing key"));
+        }
+
+        let mut combo = KeyCombo::new(key, false, false, false, false);
+        for modifier in mods.split('+').filter(|m| !m.is_empty()) {
+            match modifier {
+                "ctrl" | "control" => combo.ctrl = true,
+                "shift" => combo.shift = true,
+                "alt" | "option" => combo.alt = true,
+                "super" | "cmd" | "command" | "logo" => combo.super_key = true,
+                _ => return Err(invalid("unknown modifier")),
+            }
+        }
+        Ok(combo)
+    }
+}
+
+/// Normalize key names so config specs and live key events compare equal
+fn canonical_key(key: &str) -> String {
+    let key = key.to_lowercase();
+    match key.as_str() {
+        "return" => "enter".to_string(),
+        "esc" => "escape".to_string(),
+        "pgup" => "pageup".to_string(),
+        "pgdn" => "pagedown".to_string(),
+        "arrowup" => "up".to_string(),
+        "arrowdown" => "down".to_string(),
+        "arrowleft" => "left".to_string(),
+        "arrowright" => "right".to_string(),
+        "plus" => "+".to_string(),
+        "minus" => "-".to_string(),
+        "comma" => ",".to_string(),
+        _ => key,
+    }
+}
+
+/// One spec or a list of specs for a single action
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum KeySpecs {
+    One(String),
+    Many(Vec<String>),
+}
+
+impl KeySpecs {
+    pub fn specs(&self) -> &[String] {
+        match self {
+            KeySpecs::One(spec) => std::slice::from_ref(spec),
+            KeySpecs::Many(specs) => specs,
+        }
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum KeybindingError {
+    #[error("Unknown keybinding action '{0}'")]
+    UnknownAction(String),
+
+    #[error("Invalid key spec '{0}': {1}")]
+    InvalidSpec(String, &'static str),
+}
+
+/// Lookup table from key combos to actions
+#[derive(Debug, Clone)]
+pub struct Keybindings {
+    table: HashMap<KeyCombo, Action>,
+}
+
+impl Default for Keybindings {
+    fn default() -> Self {
+        Self::from_config(&BTreeMap::new()).0
+    }
+}
+
+impl Keybindings {
+    /// Build the table from the `[keybindings]` section. Actions listed there
+    /// replace their defaults (an empty list unbinds); others keep the
+    /// defaults. Problems are returned alongside the usable table.
+    pub fn from_config(overrides: &BTreeMap<String, KeySpecs>) -> (Self, Vec<KeybindingError>) {
+        let mut errors = Vec::new();
+        let mut specs: HashMap<Action, Vec<String>> = Action::all()
+            .iter()
+            .map(|a| {
+                (
+                    *a,
+                    a.default_specs().iter().map(|s| s.to_string()).collect(),
+                )
+            })
+            .collect();
+
+        for (name, value) in overrides {
+            match Action::from_name(name) {
+                Some(action) => {
+                    specs.insert(action, value.specs().to_vec());
+                }
+                None => errors.push(KeybindingError::UnknownAction(name.clone())),
+            }
+        }
+
+        let mut table = HashMap::new();
+        // Walk actions in a fixed order so conflicts resolve deterministically
+        for action in Action::all() {
+            for spec in &specs[action] {
+                match KeyCombo::parse(spec) {
+                    Ok(combo) => {
+                        table.entry(combo).or_insert(*action);
+                    }
+                    Err(e) => errors.push(e),
+                }
+            }
+        }
+
+        (Self { table }, errors)
+    }
+
+    pub fn lookup(&self, combo: &KeyCombo) -> Option<Action> {
+        self.table.get(combo).copied()
+    }
+
+    /// Whether any binding of `action` uses `key`, ignoring modifiers
+    pub fn uses_key(&self, action: Action, key: &str) -> bool {
+        let key = canonical_key(key);
+        self.table
+            .iter()
+            .any(|(combo, a)| *a == action && combo.key == key)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_specs() {
+        let combo = KeyCombo::parse("Ctrl+Shift+C").unwrap();
+        assert_eq!(combo, KeyCombo::new("c", true, true, false, false));
+
+        assert_eq!(
+            KeyCombo::parse("ctrl++").unwrap(),
+            KeyCombo::new("+", true, false, false, false)
+        );
+        assert_eq!(
+            KeyCombo::parse("cmd+return").unwrap(),
+            KeyCombo::new("enter", false, false, false, true)
+        );
+        assert!(KeyCombo::parse("hyper+c").is_err());
+        assert!(KeyCombo::parse("ctrl+").is_err());
+    }
+
+    #[test]
+    fn test_defaults_match_builtin_shortcuts() {
+        let bindings = Keybindings::default();
+        let lookup = |spec| bindings.lookup(&KeyCombo::parse(spec).unwrap());
+
+        assert_eq!(lookup("ctrl+,"), Some(Action::OpenSettings));
+        assert_eq!(lookup("ctrl+shift+P"), Some(Action::CommandPalette));
+        // Ctrl+P is left to the shell
+        assert_eq!(lookup("ctrl+p"), None);
+        assert_eq!(lookup("super+v"), Some(Action::Paste));
+        assert_eq!(lookup("shift+pageup"), Some(Action::ScrollPageUp));
+        // Modifiers must match exactly
+        assert_eq!(lookup("ctrl+shift+pageup"), None);
+    }
+
+    #[test]
+    fn test_overrides_and_unknown_actions() {
+        let mut overrides = BTreeMap::new();
+        overrides.insert("copy".to_string(), KeySpecs::One("alt+c".to_string()));
+        overrides.insert("paste".to_string(), KeySpecs::Many(Vec::new()));
+        overrides.insert(
+            "launch_rockets".to_string(),
+            KeySpecs::One("f1".to_string()),
+        );
+
+        let (bindings, errors) = Keybindings::from_config(&overrides);
+        let lookup = |spec| bindings.lookup(&KeyCombo::parse(spec).unwrap());
+
+        assert_eq!(lookup("alt+c"), Some(Action::Copy));
+        assert_eq!(lookup("ctrl+shift+c"), None);
+        assert_eq!(lookup("ctrl+shift+v"), None);
+        assert_eq!(lookup("ctrl+shift+enter"), Some(Action::NewPane));
+        assert!(bindings.uses_key(Action::BeamStep, "N"));
+
+        assert_eq!(errors.len(), 1);
+        assert!(matches!(
+            &errors[0],
+            KeybindingError::UnknownAction(name) if name == "launch_rockets"
+        ));
+    }
+}
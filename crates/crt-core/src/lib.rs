@@ -1,12 +1,21 @@
 // ABOUTME: Shared types and configuration for cool-rust-term.
 // ABOUTME: Defines colors, effect settings, and config file handling.
 
+pub mod base16;
 pub mod color;
 pub mod config;
 pub mod effects;
+pub mod keybindings;
+pub mod line_size;
 pub mod session;
 
+pub use base16::{load_base16_scheme, Base16Error};
 pub use color::Color;
-pub use config::{BdfFont, BehaviorSettings, ColorScheme, Config, Font};
+pub use config::{
+    AtlasEvictionPolicy, BdfFont, BehaviorSettings, ColorScheme, Config, ConfigError, CopyFormat,
+    ExitBehavior, FallbackFont, FloatConfig, Font, Osc52Clipboard, PaneBorderStyle,
+};
 pub use effects::{EffectSettings, ScanlineMode};
-pub use session::{PaneSession, SessionData, SessionError};
+pub use keybindings::{Action, KeyCombo, KeySpecs, KeybindingError, Keybindings};
+pub use line_size::LineSize;
+pub use session::{LayoutSnapshot, PaneSession, SessionData, SessionError};
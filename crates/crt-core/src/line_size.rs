@@ -0,0 +1,22 @@
+// ABOUTME: DEC line size attributes (DECDWL/DECDHL).
+// ABOUTME: Shared between the terminal, which tracks them, and the renderer.
+
+/// How the glyphs of a whole line are scaled
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum LineSize {
+    #[default]
+    Normal,
+    /// `ESC # 6`: each character spans two cells
+    DoubleWidth,
+    /// `ESC # 3`: top half of double-width, double-height text
+    DoubleHeightTop,
+    /// `ESC # 4`: bottom half of double-width, double-height text
+    DoubleHeightBottom,
+}
+
+impl LineSize {
+    /// Whether each character covers two cells horizontally
+    pub fn is_double_width(&self) -> bool {
+        !matches!(self, LineSize::Normal)
+    }
+}
@@ -1,5 +1,5 @@
 // ABOUTME: Session state persistence for terminal restoration.
-// ABOUTME: Saves pane scrollback and working directories to disk.
+// ABOUTME: Saves the pane layout, scrollback and working directories to disk.
 
 use serde::{Deserialize, Serialize};
 use std::io::{Read, Write};
@@ -12,8 +12,20 @@ pub struct PaneSession {
     pub scrollback: Vec<u8>,
     /// Working directory at close time
     pub cwd: Option<PathBuf>,
-    /// Pane position in layout (for potential future layout restoration)
+    /// Pane position in layout
     pub layout_index: usize,
+    /// Program and arguments running in the foreground at close time, if
+    /// it wasn't the shell itself
+    #[serde(default)]
+    pub command: Option<Vec<String>>,
+}
+
+/// Serializable arrangement of the panes, separate from the live layout
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LayoutSnapshot {
+    pub pane_count: usize,
+    /// Layout index of the focused pane
+    pub focused: usize,
 }
 
 /// Complete session data for the terminal
@@ -21,27 +33,43 @@ pub struct PaneSession {
 pub struct SessionData {
     pub version: u32,
     pub panes: Vec<PaneSession>,
+    /// Missing in version 1 sessions, which only restored `pane_count`
+    #[serde(default)]
+    pub layout: Option<LayoutSnapshot>,
 }
 
 impl SessionData {
-    pub const CURRENT_VERSION: u32 = 1;
+    pub const CURRENT_VERSION: u32 = 2;
 
     pub fn new() -> Self {
         Self {
             version: Self::CURRENT_VERSION,
             panes: Vec::new(),
+            layout: None,
         }
     }
 
     /// Add a pane's session data
-    pub fn add_pane(&mut self, scrollback: Vec<u8>, cwd: Option<PathBuf>, layout_index: usize) {
+    pub fn add_pane(
+        &mut self,
+        scrollback: Vec<u8>,
+        cwd: Option<PathBuf>,
+        layout_index: usize,
+        command: Option<Vec<String>>,
+    ) {
         self.panes.push(PaneSession {
             scrollback,
             cwd,
             layout_index,
+            command,
         });
     }
 
+    /// Saved data for the pane at a layout position
+    pub fn pane_at(&self, layout_index: usize) -> Option<&PaneSession> {
+        self.panes.iter().find(|p| p.layout_index == layout_index)
+    }
+
     /// Get the default session file path (~/.local/state/cool-rust-term/session.bin)
     pub fn default_path() -> Option<PathBuf> {
         // Use state_dir on macOS/Linux, fall back to data_local_dir
@@ -137,8 +165,17 @@ mod tests {
     #[test]
     fn test_session_roundtrip() {
         let mut session = SessionData::new();
-        session.add_pane(vec![1, 2, 3], Some(PathBuf::from("/home/test")), 0);
-        session.add_pane(vec![4, 5, 6], None, 1);
+        session.add_pane(vec![1, 2, 3], Some(PathBuf::from("/home/test")), 0, None);
+        session.add_pane(
+            vec![4, 5, 6],
+            None,
+            1,
+            Some(vec!["htop".to_string(), "-d".to_string(), "10".to_string()]),
+        );
+        session.layout = Some(LayoutSnapshot {
+            pane_count: 2,
+            focused: 1,
+        });
 
         // Save to temp file
         let temp_dir = std::env::temp_dir();
@@ -155,11 +192,25 @@ mod tests {
         assert_eq!(loaded.panes[0].cwd, Some(PathBuf::from("/home/test")));
         assert_eq!(loaded.panes[1].scrollback, vec![4, 5, 6]);
         assert_eq!(loaded.panes[1].cwd, None);
+        assert_eq!(loaded.panes[0].command, None);
+        assert_eq!(
+            loaded.pane_at(1).and_then(|p| p.command.clone()),
+            Some(vec!["htop".to_string(), "-d".to_string(), "10".to_string()])
+        );
+        assert_eq!(loaded.layout, session.layout);
 
         // Cleanup
         let _ = std::fs::remove_file(&temp_path);
     }
 
+    #[test]
+    fn test_version_one_session_loads_without_layout() {
+        let json = r#"{"version":1,"panes":[{"scrollback":[],"cwd":null,"layout_index":0}]}"#;
+        let session: SessionData = serde_json::from_str(json).unwrap();
+        assert_eq!(session.layout, None);
+        assert_eq!(session.panes[0].command, None);
+    }
+
     #[test]
     fn test_default_path() {
         // Should return Some on most systems
@@ -3,4 +3,4 @@
 
 mod tree;
 
-pub use tree::{LayoutTree, PaneId, Rect};
+pub use tree::{Direction, LayoutError, LayoutTree, PaneId, Rect, MAX_PANES};
@@ -1,11 +1,34 @@
 // ABOUTME: Automatic grid layout for terminal panes.
 // ABOUTME: Arranges N panes in a near-square grid, adapting to window aspect ratio.
 
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+
+use crt_core::LayoutSnapshot;
+
+/// Most panes a window can hold, however large
+pub const MAX_PANES: usize = 16;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, thiserror::Error)]
+pub enum LayoutError {
+    #[error("maximum of {0} panes reached")]
+    PaneLimit(usize),
+
+    #[error("no room for another pane of at least {cols}x{rows} cells")]
+    TooSmall { cols: u16, rows: u16 },
+}
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub struct PaneId(pub u64);
 
+/// A side of a pane, for finding the pane next to it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    Left,
+    Right,
+    Up,
+    Down,
+}
+
 /// Rectangle in normalized coordinates (0.0 to 1.0)
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub struct Rect {
@@ -26,11 +49,26 @@ impl Rect {
     }
 }
 
+/// Where floating panes go until told otherwise: the top half of the window
+const DEFAULT_FLOAT_RECT: Rect = Rect {
+    x: 0.1,
+    y: 0.05,
+    width: 0.8,
+    height: 0.45,
+};
+
 #[derive(Debug)]
 pub struct LayoutTree {
     panes: Vec<PaneId>,
     focused: PaneId,
     next_id: u64,
+    /// Smallest pane `try_add_pane` may leave, as (columns, rows)
+    min_size: (u16, u16),
+    /// Panes left out of the grid and drawn over it at `float_rect`
+    floating: HashSet<PaneId>,
+    /// Floating panes put away; they keep running but aren't shown
+    hidden: HashSet<PaneId>,
+    float_rect: Rect,
 }
 
 impl LayoutTree {
@@ -40,6 +78,10 @@ impl LayoutTree {
             panes: vec![id],
             focused: id,
             next_id: 1,
+            min_size: (1, 1),
+            floating: HashSet::new(),
+            hidden: HashSet::new(),
+            float_rect: DEFAULT_FLOAT_RECT,
         }
     }
 
@@ -48,7 +90,7 @@ impl LayoutTree {
     }
 
     pub fn set_focus(&mut self, pane: PaneId) {
-        if self.panes.contains(&pane) {
+        if self.panes.contains(&pane) && !self.hidden.contains(&pane) {
             self.focused = pane;
         }
     }
@@ -56,16 +98,15 @@ impl LayoutTree {
     /// Hit test: given normalized coordinates (0.0-1.0), return the pane at that position
     pub fn hit_test(&self, norm_x: f32, norm_y: f32, width: f32, height: f32) -> Option<PaneId> {
         let rects = self.pane_rects(width, height);
-        for (pane_id, rect) in rects {
-            if norm_x >= rect.x
-                && norm_x < rect.x + rect.width
-                && norm_y >= rect.y
-                && norm_y < rect.y + rect.height
-            {
-                return Some(pane_id);
-            }
-        }
-        None
+        // Topmost first, so floating panes win over the grid below them
+        self.draw_order().into_iter().rev().find(|pane_id| {
+            rects.get(pane_id).is_some_and(|rect| {
+                norm_x >= rect.x
+                    && norm_x < rect.x + rect.width
+                    && norm_y >= rect.y
+                    && norm_y < rect.y + rect.height
+            })
+        })
     }
 
     /// Add a new pane, returns its ID. New pane gets focus.
@@ -77,31 +118,243 @@ impl LayoutTree {
         id
     }
 
+    /// Add a pane if every pane still gets at least the minimum size, where
+    /// `grid_size` gives the columns and rows that fit in a rectangle of a
+    /// `width` x `height` window
+    pub fn try_add_pane(
+        &mut self,
+        width: f32,
+        height: f32,
+        grid_size: impl Fn(&Rect) -> (u16, u16),
+    ) -> Result<PaneId, LayoutError> {
+        if self.panes.len() >= MAX_PANES {
+            return Err(LayoutError::PaneLimit(MAX_PANES));
+        }
+        if self.max_panes(width, height, grid_size) <= self.tiled().count() {
+            let (cols, rows) = self.min_size;
+            return Err(LayoutError::TooSmall { cols, rows });
+        }
+        Ok(self.add_pane())
+    }
+
+    /// Smallest pane size `try_add_pane` allows, in cells
+    pub fn set_min_pane_size(&mut self, cols: u16, rows: u16) {
+        self.min_size = (cols.max(1), rows.max(1));
+    }
+
+    /// How many panes fit in the window at the minimum size (at least one,
+    /// at most `MAX_PANES`)
+    pub fn max_panes(
+        &self,
+        width: f32,
+        height: f32,
+        grid_size: impl Fn(&Rect) -> (u16, u16),
+    ) -> usize {
+        let (min_cols, min_rows) = self.min_size;
+        let landscape = width >= height;
+        (2..=MAX_PANES)
+            .take_while(|&n| {
+                compute_grid_rects(n, landscape).iter().all(|rect| {
+                    let (cols, rows) = grid_size(rect);
+                    cols >= min_cols && rows >= min_rows
+                })
+            })
+            .last()
+            .unwrap_or(1)
+    }
+
     /// Close a pane, returns the pane that should receive focus (if any remain)
     pub fn close(&mut self, pane: PaneId) -> Option<PaneId> {
         if let Some(idx) = self.panes.iter().position(|&p| p == pane) {
             self.panes.remove(idx);
+            self.floating.remove(&pane);
+            self.hidden.remove(&pane);
             if self.panes.is_empty() {
                 return None;
             }
-            // Focus previous pane, or first if we removed index 0
-            let new_focus_idx = if idx > 0 { idx - 1 } else { 0 };
-            self.focused = self.panes[new_focus_idx];
+            // Floating panes can't be left without a grid under them
+            if self.tiled().next().is_none() {
+                self.floating.clear();
+                self.hidden.clear();
+            }
+            // Focus the shown pane before it, or the first if there's none
+            self.focused = self.panes[..idx]
+                .iter()
+                .rev()
+                .chain(&self.panes[idx..])
+                .copied()
+                .find(|p| !self.hidden.contains(p))
+                .unwrap_or(self.panes[0]);
             Some(self.focused)
         } else {
             None
         }
     }
 
+    /// Float a pane over the grid, or put it back in. The last pane in the
+    /// grid can't float; returns whether the pane changed.
+    pub fn set_floating(&mut self, pane: PaneId, floating: bool) -> bool {
+        if !self.panes.contains(&pane) || self.is_floating(pane) == floating {
+            return false;
+        }
+        if floating {
+            if self.tiled().count() < 2 {
+                return false;
+            }
+            self.floating.insert(pane);
+        } else {
+            self.floating.remove(&pane);
+            self.hidden.remove(&pane);
+        }
+        true
+    }
+
+    pub fn is_floating(&self, pane: PaneId) -> bool {
+        self.floating.contains(&pane)
+    }
+
+    /// Where floating panes are drawn, in normalized coordinates
+    pub fn float_rect(&self) -> Rect {
+        self.float_rect
+    }
+
+    pub fn set_float_rect(&mut self, rect: Rect) {
+        self.float_rect = rect;
+    }
+
+    /// Put a floating pane away without closing it. Focus moves to the grid.
+    pub fn hide_floating(&mut self, pane: PaneId) {
+        if !self.is_floating(pane) {
+            return;
+        }
+        self.hidden.insert(pane);
+        if self.focused == pane {
+            if let Some(tiled) = self.tiled().last() {
+                self.focused = tiled;
+            }
+        }
+    }
+
+    /// Show a hidden floating pane again and focus it
+    pub fn show_floating(&mut self, pane: PaneId) {
+        if self.hidden.remove(&pane) {
+            self.focused = pane;
+        }
+    }
+
+    /// A floating pane that is currently put away
+    pub fn hidden_floating(&self) -> Option<PaneId> {
+        self.panes.iter().copied().find(|p| self.hidden.contains(p))
+    }
+
+    /// Shown panes in the order they're drawn: the grid, then floating panes on top
+    pub fn draw_order(&self) -> Vec<PaneId> {
+        let shown_floating = self
+            .panes
+            .iter()
+            .copied()
+            .filter(|p| self.is_floating(*p) && !self.hidden.contains(p));
+        self.tiled().chain(shown_floating).collect()
+    }
+
+    /// Panes in the grid, in layout order
+    fn tiled(&self) -> impl Iterator<Item = PaneId> + '_ {
+        self.panes
+            .iter()
+            .copied()
+            .filter(|p| !self.floating.contains(p))
+    }
+
+    /// Swap two panes' places in the layout; focus stays on the same pane.
+    /// Returns false if either isn't in the grid.
+    pub fn swap_panes(&mut self, a: PaneId, b: PaneId) -> bool {
+        if self.is_floating(a) || self.is_floating(b) {
+            return false;
+        }
+        let find = |pane| self.panes.iter().position(|&p| p == pane);
+        let (Some(a), Some(b)) = (find(a), find(b)) else {
+            return false;
+        };
+        self.panes.swap(a, b);
+        true
+    }
+
+    /// The pane directly beside `pane` in `direction`, preferring the one
+    /// most in line with it
+    pub fn neighbor(
+        &self,
+        pane: PaneId,
+        direction: Direction,
+        width: f32,
+        height: f32,
+    ) -> Option<PaneId> {
+        const EPSILON: f32 = 1e-4;
+        if self.is_floating(pane) {
+            return None;
+        }
+        let rects = self.pane_rects(width, height);
+        let from = *rects.get(&pane)?;
+        let center = |rect: &Rect| (rect.x + rect.width / 2.0, rect.y + rect.height / 2.0);
+        let (from_x, from_y) = center(&from);
+
+        // Ties go to the pane earlier in the layout
+        self.tiled()
+            .filter(|&id| id != pane)
+            .filter_map(|id| {
+                let rect = &rects[&id];
+                let (x, y) = center(rect);
+                let overlaps_x = rect.x < from.x + from.width && rect.x + rect.width > from.x;
+                let overlaps_y = rect.y < from.y + from.height && rect.y + rect.height > from.y;
+                let (gap, offset) = match direction {
+                    Direction::Left if overlaps_y => (from.x - (rect.x + rect.width), y - from_y),
+                    Direction::Right if overlaps_y => (rect.x - (from.x + from.width), y - from_y),
+                    Direction::Up if overlaps_x => (from.y - (rect.y + rect.height), x - from_x),
+                    Direction::Down if overlaps_x => (rect.y - (from.y + from.height), x - from_x),
+                    _ => return None,
+                };
+                (gap > -EPSILON).then_some((id, gap, offset.abs()))
+            })
+            .min_by(|a, b| (a.1, a.2).partial_cmp(&(b.1, b.2)).unwrap())
+            .map(|(id, _, _)| id)
+    }
+
     /// Get all pane IDs
     pub fn panes(&self) -> &[PaneId] {
         &self.panes
     }
 
-    /// Get all panes with their layout rectangles.
+    /// Capture the pane arrangement for session restore
+    pub fn snapshot(&self) -> LayoutSnapshot {
+        LayoutSnapshot {
+            pane_count: self.panes.len(),
+            focused: self
+                .panes
+                .iter()
+                .position(|&p| p == self.focused)
+                .unwrap_or(0),
+        }
+    }
+
+    /// Rebuild a layout from a snapshot. Panes get fresh IDs in layout order.
+    pub fn from_snapshot(snapshot: &LayoutSnapshot) -> Self {
+        let count = snapshot.pane_count.max(1);
+        let panes: Vec<PaneId> = (0..count as u64).map(PaneId).collect();
+        Self {
+            focused: panes[snapshot.focused.min(count - 1)],
+            panes,
+            next_id: count as u64,
+            min_size: (1, 1),
+            floating: HashSet::new(),
+            hidden: HashSet::new(),
+            float_rect: DEFAULT_FLOAT_RECT,
+        }
+    }
+
+    /// Get all shown panes with their layout rectangles.
     /// Layout adapts to aspect ratio: landscape = columns side-by-side, portrait = rows stacked.
+    /// Floating panes are left out of the grid and all get `float_rect`.
     pub fn pane_rects(&self, width: f32, height: f32) -> HashMap<PaneId, Rect> {
-        let n = self.panes.len();
+        let n = self.tiled().count();
         if n == 0 {
             return HashMap::new();
         }
@@ -109,11 +362,12 @@ impl LayoutTree {
         let landscape = width >= height;
         let rects = compute_grid_rects(n, landscape);
 
-        self.panes
+        let floating = self
+            .floating
             .iter()
-            .zip(rects)
-            .map(|(&id, rect)| (id, rect))
-            .collect()
+            .filter(|p| !self.hidden.contains(p))
+            .map(|&id| (id, self.float_rect));
+        self.tiled().zip(rects).chain(floating).collect()
     }
 }
 
@@ -461,6 +715,89 @@ mod tests {
         assert_eq!(tree.hit_test(0.75, 0.5, 800.0, 600.0), Some(second));
     }
 
+    #[test]
+    fn swapping_moves_panes_but_not_focus() {
+        let mut tree = LayoutTree::new();
+        let first = tree.focused_pane();
+        let second = tree.add_pane();
+        tree.set_focus(first);
+
+        assert!(tree.swap_panes(first, second));
+        assert_eq!(tree.panes(), &[second, first]);
+        assert_eq!(tree.focused_pane(), first);
+        assert_eq!(tree.hit_test(0.75, 0.5, 800.0, 600.0), Some(first));
+        assert!(!tree.swap_panes(first, PaneId(99)));
+    }
+
+    #[test]
+    fn neighbors_in_each_direction() {
+        // 3 panes in landscape: one on the left, two stacked on the right
+        let mut tree = LayoutTree::new();
+        let left = tree.focused_pane();
+        let top_right = tree.add_pane();
+        let bottom_right = tree.add_pane();
+        let neighbor = |pane, direction| tree.neighbor(pane, direction, 800.0, 600.0);
+
+        assert_eq!(neighbor(left, Direction::Right), Some(top_right));
+        assert_eq!(neighbor(left, Direction::Left), None);
+        assert_eq!(neighbor(left, Direction::Up), None);
+        assert_eq!(neighbor(bottom_right, Direction::Left), Some(left));
+        assert_eq!(neighbor(bottom_right, Direction::Up), Some(top_right));
+        assert_eq!(neighbor(top_right, Direction::Down), Some(bottom_right));
+        assert_eq!(neighbor(top_right, Direction::Right), None);
+    }
+
+    #[test]
+    fn floating_pane_leaves_the_grid() {
+        let mut tree = LayoutTree::new();
+        let first = tree.focused_pane();
+        let second = tree.add_pane();
+        let third = tree.add_pane();
+        tree.set_float_rect(Rect {
+            x: 0.5,
+            y: 0.5,
+            width: 0.25,
+            height: 0.25,
+        });
+
+        assert!(tree.set_floating(third, true));
+        let rects = tree.pane_rects(800.0, 600.0);
+        assert!(approx_eq(rects[&first].width, 0.5));
+        assert!(approx_eq(rects[&second].height, 1.0));
+        assert_eq!(rects[&third], tree.float_rect());
+        assert_eq!(tree.draw_order(), vec![first, second, third]);
+        // The floating pane is hit first where it overlaps the grid
+        assert_eq!(tree.hit_test(0.6, 0.6, 800.0, 600.0), Some(third));
+        assert_eq!(tree.hit_test(0.9, 0.9, 800.0, 600.0), Some(second));
+
+        // The last pane left in the grid has to stay there
+        assert!(tree.set_floating(second, true));
+        assert!(!tree.set_floating(first, true));
+    }
+
+    #[test]
+    fn hidden_floating_pane_keeps_running() {
+        let mut tree = LayoutTree::new();
+        let first = tree.focused_pane();
+        let float = tree.add_pane();
+        tree.set_floating(float, true);
+
+        tree.hide_floating(float);
+        assert_eq!(tree.focused_pane(), first);
+        assert_eq!(tree.hidden_floating(), Some(float));
+        assert!(!tree.pane_rects(800.0, 600.0).contains_key(&float));
+        assert_eq!(tree.panes().len(), 2);
+
+        tree.show_floating(float);
+        assert_eq!(tree.focused_pane(), float);
+        assert!(tree.pane_rects(800.0, 600.0).contains_key(&float));
+
+        // Closing the grid's last pane brings the floating one back into it
+        tree.close(first);
+        assert!(!tree.is_floating(float));
+        assert_eq!(tree.pane_rects(800.0, 600.0)[&float], Rect::full());
+    }
+
     #[test]
     fn hit_test_out_of_bounds() {
         let tree = LayoutTree::new();
@@ -469,4 +806,77 @@ mod tests {
         assert_eq!(tree.hit_test(1.5, 0.5, 800.0, 600.0), None);
         assert_eq!(tree.hit_test(-0.1, 0.5, 800.0, 600.0), None);
     }
+
+    /// Cells in a rectangle of a 160x48 cell window
+    fn cells(rect: &Rect) -> (u16, u16) {
+        ((rect.width * 160.0) as u16, (rect.height * 48.0) as u16)
+    }
+
+    #[test]
+    fn max_panes_follows_the_minimum_size() {
+        let mut tree = LayoutTree::new();
+        assert_eq!(tree.max_panes(1600.0, 960.0, cells), MAX_PANES);
+
+        // 40 columns allow four columns of panes, 12 rows four panes in each
+        tree.set_min_pane_size(40, 12);
+        assert_eq!(tree.max_panes(1600.0, 960.0, cells), 16);
+        // With 16 rows a 13th pane would need a column of four
+        tree.set_min_pane_size(40, 16);
+        assert_eq!(tree.max_panes(1600.0, 960.0, cells), 12);
+        tree.set_min_pane_size(100, 6);
+        assert_eq!(tree.max_panes(1600.0, 960.0, cells), 1);
+    }
+
+    #[test]
+    fn try_add_pane_refuses_panes_below_the_minimum() {
+        let mut tree = LayoutTree::new();
+        // Two columns of 80 fit, three of 53 don't
+        tree.set_min_pane_size(60, 6);
+        for _ in 0..3 {
+            tree.try_add_pane(1600.0, 960.0, cells).unwrap();
+        }
+        let focused = tree.focused_pane();
+        assert_eq!(
+            tree.try_add_pane(1600.0, 960.0, cells),
+            Err(LayoutError::TooSmall { cols: 60, rows: 6 })
+        );
+        assert_eq!(tree.panes().len(), 4);
+        assert_eq!(tree.focused_pane(), focused);
+    }
+
+    #[test]
+    fn try_add_pane_stops_at_the_pane_limit() {
+        let mut tree = LayoutTree::new();
+        for _ in 1..MAX_PANES {
+            tree.try_add_pane(1600.0, 960.0, cells).unwrap();
+        }
+        assert_eq!(
+            tree.try_add_pane(1600.0, 960.0, cells),
+            Err(LayoutError::PaneLimit(MAX_PANES))
+        );
+    }
+
+    #[test]
+    fn snapshot_restores_panes_rects_and_focus() {
+        let mut tree = LayoutTree::new();
+        tree.add_pane();
+        tree.add_pane();
+        let second = tree.panes()[1];
+        tree.set_focus(second);
+
+        let restored = LayoutTree::from_snapshot(&tree.snapshot());
+
+        assert_eq!(restored.panes().len(), 3);
+        assert_eq!(restored.focused_pane(), restored.panes()[1]);
+        let before = tree.pane_rects(800.0, 600.0);
+        let after = restored.pane_rects(800.0, 600.0);
+        for (old, new) in tree.panes().iter().zip(restored.panes()) {
+            assert!(rect_approx_eq(&before[old], &after[new]));
+        }
+
+        // New panes don't reuse restored IDs
+        let mut restored = restored;
+        let added = restored.add_pane();
+        assert_eq!(restored.panes().iter().filter(|&&p| p == added).count(), 1);
+    }
 }
@@ -2,10 +2,72 @@
 // ABOUTME: Rasterizes font glyphs and packs them into a texture atlas.
 // ABOUTME: Supports both TTF (via fontdue) and BDF bitmap fonts.
 
+use crt_core::AtlasEvictionPolicy;
 use fontdue::{Font, FontSettings};
 use std::collections::HashMap;
 
 use crate::bdf::BdfFont;
+use crate::box_drawing;
+
+/// Initial atlas texture size (square)
+const INITIAL_ATLAS_SIZE: u32 = 1024;
+
+/// Default largest size the atlas grows to before evicting glyphs
+pub const DEFAULT_MAX_ATLAS_SIZE: u32 = 4096;
+
+/// Powerline separators (solid and thin arrows, half circles), stretched to
+/// the cell so prompt segments join without seams
+const POWERLINE_SEPARATORS: std::ops::RangeInclusive<char> = '\u{E0B0}'..='\u{E0B7}';
+
+/// Horizontal shear applied to synthesized italics (pixels per pixel of height)
+const SYNTHETIC_ITALIC_SLANT: f32 = 0.2;
+
+/// BC4 stores each 4x4 texel block in 8 bytes
+const BC4_BLOCK_SIZE: usize = 4;
+const BC4_BLOCK_BYTES: usize = 8;
+
+/// How the atlas texture is stored on the GPU
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum AtlasCompressionMode {
+    /// 8 bits per texel (R8Unorm)
+    #[default]
+    Uncompressed,
+    /// BC4 block compression, 4 bits per texel. Only used when the device
+    /// supports BC formats; otherwise the atlas is uploaded uncompressed.
+    Bc4,
+}
+
+/// Font style of a cell's text (SGR bold / italic)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum GlyphStyle {
+    #[default]
+    Regular,
+    Bold,
+    Italic,
+    BoldItalic,
+}
+
+impl GlyphStyle {
+    pub fn from_flags(bold: bool, italic: bool) -> Self {
+        match (bold, italic) {
+            (false, false) => Self::Regular,
+            (true, false) => Self::Bold,
+            (false, true) => Self::Italic,
+            (true, true) => Self::BoldItalic,
+        }
+    }
+
+    pub fn is_bold(self) -> bool {
+        matches!(self, Self::Bold | Self::BoldItalic)
+    }
+
+    pub fn is_italic(self) -> bool {
+        matches!(self, Self::Italic | Self::BoldItalic)
+    }
+}
+
+/// Cache key: character, style and whether it is drawn double-width
+type GlyphKey = (char, GlyphStyle, bool);
 
 /// The font source - either a rasterized TTF or a pixel-perfect BDF
 enum FontSource {
@@ -20,20 +82,46 @@ pub struct GlyphAtlas {
     ascent: f32,
     cell_width: f32,
     cell_height: f32,
-    fallback_font: Option<Font>,
-    fallback_font_size: f32,
-    symbols_font: Option<Font>,
-    symbols_font_size: f32,
-    emoji_font: Option<Font>,
-    emoji_font_size: f32,
+    /// TTF fallbacks, tried in order for characters the primary font lacks
+    fallback_chain: Vec<FallbackFace>,
+    /// Comprehensive bitmap fallback (Unifont), tried after the chain
     bdf_fallback: Option<BdfFallback>,
-    glyphs: HashMap<char, GlyphInfo>,
+    glyphs: HashMap<GlyphKey, GlyphInfo>,
     atlas_data: Vec<u8>,
     atlas_width: u32,
     atlas_height: u32,
     next_x: u32,
     next_y: u32,
     row_height: u32,
+    /// Regions released by evicted glyphs, reused before shelf packing
+    free_slots: Vec<AtlasSlot>,
+    /// Glyphs left in place when the shelves were last reset, which shelf
+    /// packing steps around until it has passed them
+    pinned: Vec<AtlasSlot>,
+    /// Nothing was packed since the shelves were last reset
+    fresh_shelves: bool,
+    /// Current frame number, advanced by `begin_frame`
+    frame: u64,
+    /// Evict least-recently-used glyphs beyond this many (None = unbounded)
+    max_cached_glyphs: Option<usize>,
+    compression: AtlasCompressionMode,
+    /// The atlas doubles in size when full, up to this many pixels per side
+    max_atlas_size: u32,
+    /// Whether a full atlas grows, evicts or reports an error
+    eviction_policy: AtlasEvictionPolicy,
+    /// Cell height multiplier for extra space between rows
+    line_height_mult: f32,
+    /// Extra pixels between columns (may be negative)
+    letter_spacing: f32,
+    /// Pixels changed since the last upload, so only they are sent to the GPU
+    dirty: Option<AtlasSlot>,
+}
+
+/// TTF fallback font, sized to match the primary cell height
+struct FallbackFace {
+    name: &'static str,
+    font: Font,
+    font_size: f32,
 }
 
 /// BDF font used as fallback, with its native cell dimensions for scaling
@@ -54,6 +142,41 @@ pub struct GlyphInfo {
     pub advance: f32,
     pub offset_x: f32,
     pub offset_y: f32,
+    /// Frame in which this glyph was last requested (for LRU eviction)
+    pub last_used: u64,
+    /// Atlas region owned by this glyph (None for empty glyphs like space)
+    slot: Option<AtlasSlot>,
+}
+
+/// A rectangle of atlas pixels, including the 1px gap to its neighbours
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct AtlasSlot {
+    pub(crate) x: u32,
+    pub(crate) y: u32,
+    pub(crate) width: u32,
+    pub(crate) height: u32,
+}
+
+impl AtlasSlot {
+    /// Whether the two overlap or are closer than the 1px gap
+    fn touches(self, other: AtlasSlot) -> bool {
+        self.x < other.x + other.width + 1
+            && other.x < self.x + self.width + 1
+            && self.y < other.y + other.height + 1
+            && other.y < self.y + self.height + 1
+    }
+
+    /// Smallest rectangle covering both
+    fn union(self, other: AtlasSlot) -> AtlasSlot {
+        let x = self.x.min(other.x);
+        let y = self.y.min(other.y);
+        AtlasSlot {
+            x,
+            y,
+            width: (self.x + self.width).max(other.x + other.width) - x,
+            height: (self.y + self.height).max(other.y + other.height) - y,
+        }
+    }
 }
 
 #[derive(Debug, thiserror::Error)]
@@ -86,8 +209,8 @@ impl GlyphAtlas {
         let cell_width = metrics.advance_width;
         let cell_height = font_size;
 
-        let atlas_width = 1024;
-        let atlas_height = 1024;
+        let atlas_width = INITIAL_ATLAS_SIZE;
+        let atlas_height = INITIAL_ATLAS_SIZE;
         let atlas_data = vec![0u8; (atlas_width * atlas_height) as usize];
 
         Ok(Self {
@@ -95,12 +218,7 @@ impl GlyphAtlas {
             ascent: line_metrics.ascent,
             cell_width,
             cell_height,
-            fallback_font: None,
-            fallback_font_size: font_size,
-            symbols_font: None,
-            symbols_font_size: font_size,
-            emoji_font: None,
-            emoji_font_size: font_size,
+            fallback_chain: Vec::new(),
             bdf_fallback: None,
             glyphs: HashMap::new(),
             atlas_data,
@@ -109,6 +227,17 @@ impl GlyphAtlas {
             next_x: 0,
             next_y: 0,
             row_height: 0,
+            free_slots: Vec::new(),
+            pinned: Vec::new(),
+            fresh_shelves: false,
+            frame: 0,
+            max_cached_glyphs: None,
+            eviction_policy: AtlasEvictionPolicy::default(),
+            line_height_mult: 1.0,
+            letter_spacing: 0.0,
+            compression: AtlasCompressionMode::default(),
+            max_atlas_size: DEFAULT_MAX_ATLAS_SIZE,
+            dirty: None,
         })
     }
 
@@ -121,12 +250,8 @@ impl GlyphAtlas {
         let cell_height = font.cell_height() as f32;
         let ascent = font.ascent as f32;
 
-        // BDF fonts typically have limited character sets, so use a smaller default font size
-        // for fallback scaling
-        let fallback_font_size = cell_height;
-
-        let atlas_width = 1024;
-        let atlas_height = 1024;
+        let atlas_width = INITIAL_ATLAS_SIZE;
+        let atlas_height = INITIAL_ATLAS_SIZE;
         let atlas_data = vec![0u8; (atlas_width * atlas_height) as usize];
 
         tracing::info!(
@@ -143,12 +268,7 @@ impl GlyphAtlas {
             ascent,
             cell_width,
             cell_height,
-            fallback_font: None,
-            fallback_font_size,
-            symbols_font: None,
-            symbols_font_size: fallback_font_size,
-            emoji_font: None,
-            emoji_font_size: fallback_font_size,
+            fallback_chain: Vec::new(),
             bdf_fallback: None,
             glyphs: HashMap::new(),
             atlas_data,
@@ -157,6 +277,17 @@ impl GlyphAtlas {
             next_x: 0,
             next_y: 0,
             row_height: 0,
+            free_slots: Vec::new(),
+            pinned: Vec::new(),
+            fresh_shelves: false,
+            frame: 0,
+            max_cached_glyphs: None,
+            eviction_policy: AtlasEvictionPolicy::default(),
+            line_height_mult: 1.0,
+            letter_spacing: 0.0,
+            compression: AtlasCompressionMode::default(),
+            max_atlas_size: DEFAULT_MAX_ATLAS_SIZE,
+            dirty: None,
         })
     }
 
@@ -168,106 +299,48 @@ impl GlyphAtlas {
         }
     }
 
-    /// Set a fallback font for characters missing from the primary font.
-    /// The fallback font size is calculated to match the primary font's cell height.
-    pub fn set_fallback(&mut self, fallback_data: &[u8]) -> Result<(), AtlasError> {
-        let fallback = Font::from_bytes(fallback_data, FontSettings::default())
-            .map_err(|e| AtlasError::FontLoadError(format!("fallback: {}", e)))?;
-
-        let base_size = self.primary_font_size();
-
-        // Calculate font size for fallback to match primary cell height
-        let fallback_line_metrics =
-            fallback
-                .horizontal_line_metrics(base_size)
-                .unwrap_or(fontdue::LineMetrics {
-                    ascent: base_size * 0.8,
-                    descent: base_size * -0.2,
-                    line_gap: 0.0,
-                    new_line_size: base_size,
-                });
-
-        // Scale fallback to match primary cell height
-        let fallback_natural_height = fallback_line_metrics.ascent - fallback_line_metrics.descent;
-        let scale = self.cell_height / fallback_natural_height;
-        let fallback_font_size = base_size * scale;
-
-        self.fallback_font = Some(fallback);
-        self.fallback_font_size = fallback_font_size;
-
-        tracing::info!(
-            "Fallback font configured: size={:.1} (primary cell: {:.1}x{:.1})",
-            fallback_font_size,
-            self.cell_width,
-            self.cell_height
-        );
-
-        Ok(())
-    }
-
-    /// Set a symbols fallback font for technical symbols.
-    pub fn set_symbols_fallback(&mut self, symbols_data: &[u8]) -> Result<(), AtlasError> {
-        let symbols = Font::from_bytes(symbols_data, FontSettings::default())
-            .map_err(|e| AtlasError::FontLoadError(format!("symbols: {}", e)))?;
-
-        let base_size = self.primary_font_size();
-
-        // Calculate font size for symbols to match primary cell height
-        let symbols_line_metrics =
-            symbols
-                .horizontal_line_metrics(base_size)
-                .unwrap_or(fontdue::LineMetrics {
-                    ascent: base_size * 0.8,
-                    descent: base_size * -0.2,
-                    line_gap: 0.0,
-                    new_line_size: base_size,
-                });
-
-        let symbols_natural_height = symbols_line_metrics.ascent - symbols_line_metrics.descent;
-        let scale = self.cell_height / symbols_natural_height;
-        let symbols_font_size = base_size * scale;
-
-        self.symbols_font = Some(symbols);
-        self.symbols_font_size = symbols_font_size;
-
-        tracing::info!(
-            "Symbols fallback font configured: size={:.1}",
-            symbols_font_size
-        );
-
-        Ok(())
-    }
-
-    /// Set an emoji fallback font for emoji characters.
-    pub fn set_emoji_fallback(&mut self, emoji_data: &[u8]) -> Result<(), AtlasError> {
-        let emoji = Font::from_bytes(emoji_data, FontSettings::default())
-            .map_err(|e| AtlasError::FontLoadError(format!("emoji: {}", e)))?;
-
+    /// Set the TTF fallback fonts for characters missing from the primary
+    /// font, as (name, font data) in the order they should be tried. Each is
+    /// sized to match the primary font's cell height. The BDF fallback, if
+    /// any, is still tried after all of these.
+    pub fn set_fallback_chain(
+        &mut self,
+        fonts: &[(&'static str, &[u8])],
+    ) -> Result<(), AtlasError> {
         let base_size = self.primary_font_size();
+        let mut chain = Vec::with_capacity(fonts.len());
+
+        for &(name, data) in fonts {
+            let font = Font::from_bytes(data, FontSettings::default())
+                .map_err(|e| AtlasError::FontLoadError(format!("{}: {}", name, e)))?;
+
+            // Calculate font size for the fallback to match primary cell height
+            let line_metrics =
+                font.horizontal_line_metrics(base_size)
+                    .unwrap_or(fontdue::LineMetrics {
+                        ascent: base_size * 0.8,
+                        descent: base_size * -0.2,
+                        line_gap: 0.0,
+                        new_line_size: base_size,
+                    });
+            let natural_height = line_metrics.ascent - line_metrics.descent;
+            let font_size = base_size * (self.cell_height / natural_height);
+
+            tracing::info!(
+                "Fallback font {} configured: size={:.1} (primary cell: {:.1}x{:.1})",
+                name,
+                font_size,
+                self.cell_width,
+                self.cell_height
+            );
+            chain.push(FallbackFace {
+                name,
+                font,
+                font_size,
+            });
+        }
 
-        // Calculate font size for emoji to match primary cell height
-        let emoji_line_metrics =
-            emoji
-                .horizontal_line_metrics(base_size)
-                .unwrap_or(fontdue::LineMetrics {
-                    ascent: base_size * 0.8,
-                    descent: base_size * -0.2,
-                    line_gap: 0.0,
-                    new_line_size: base_size,
-                });
-
-        let emoji_natural_height = emoji_line_metrics.ascent - emoji_line_metrics.descent;
-        let scale = self.cell_height / emoji_natural_height;
-        let emoji_font_size = base_size * scale;
-
-        self.emoji_font = Some(emoji);
-        self.emoji_font_size = emoji_font_size;
-
-        tracing::info!(
-            "Emoji fallback font configured: size={:.1}",
-            emoji_font_size
-        );
-
+        self.fallback_chain = chain;
         Ok(())
     }
 
@@ -298,8 +371,11 @@ impl GlyphAtlas {
         Ok(())
     }
 
+    /// Baseline offset from the top of a cell. With extra line height the
+    /// glyphs sit in the middle of the taller cell.
     pub fn ascent(&self) -> f32 {
-        self.ascent
+        let (_, spaced_height) = self.cell_size();
+        self.ascent + ((spaced_height - self.cell_height) / 2.0).floor()
     }
 
     /// Check if primary font has a glyph (not .notdef)
@@ -310,60 +386,142 @@ impl GlyphAtlas {
         }
     }
 
-    /// Check if fallback font has a glyph (not .notdef)
-    fn fallback_has_glyph(&self, c: char) -> bool {
-        self.fallback_font
+    /// Check if BDF fallback font has a glyph
+    fn bdf_fallback_has_glyph(&self, c: char) -> bool {
+        self.bdf_fallback
             .as_ref()
-            .map(|f| f.lookup_glyph_index(c) != 0)
+            .map(|fb| fb.font.get_char(c).is_some())
             .unwrap_or(false)
     }
 
-    /// Check if symbols font has a glyph (not .notdef)
-    fn symbols_has_glyph(&self, c: char) -> bool {
-        self.symbols_font
-            .as_ref()
-            .map(|f| f.lookup_glyph_index(c) != 0)
-            .unwrap_or(false)
-    }
+    /// Rasterize `c` from the first fallback that has it: the TTF chain in
+    /// order, then the BDF fallback.
+    /// Returns (width, height, xmin, ymin, advance, bitmap, source_name)
+    #[allow(clippy::type_complexity)]
+    fn rasterize_fallback(
+        &self,
+        c: char,
+        is_wide: bool,
+    ) -> Option<(usize, usize, i32, i32, f32, Vec<u8>, &'static str)> {
+        if let Some(face) = self
+            .fallback_chain
+            .iter()
+            .find(|face| face.font.lookup_glyph_index(c) != 0)
+        {
+            let (m, b) = face.font.rasterize(c, face.font_size);
+            let span = if is_wide {
+                self.cell_width * 2.0
+            } else {
+                self.cell_width
+            };
 
-    /// Check if emoji font has a glyph (not .notdef)
-    fn emoji_has_glyph(&self, c: char) -> bool {
-        self.emoji_font
-            .as_ref()
-            .map(|f| f.lookup_glyph_index(c) != 0)
-            .unwrap_or(false)
+            // Icons sized by line metrics can be bigger than the cell; shrink
+            // those to fit and center them in it
+            if m.width as f32 > span.ceil() || m.height as f32 > self.cell_height.ceil() {
+                let scale = (span / m.width as f32).min(self.cell_height / m.height as f32);
+                let width = ((m.width as f32 * scale).floor() as usize).max(1);
+                let height = ((m.height as f32 * scale).floor() as usize).max(1);
+                let bitmap = scale_bitmap(m.width, m.height, &b, width, height);
+                let xmin = ((span - width as f32) / 2.0).round() as i32;
+                let ymin =
+                    (self.ascent - self.cell_height / 2.0 - height as f32 / 2.0).round() as i32;
+                return Some((width, height, xmin, ymin, span, bitmap, face.name));
+            }
+
+            // Wide characters take two cells; center the glyph across both
+            let xmin = if is_wide {
+                m.xmin + ((span - m.advance_width) / 2.0).round().max(0.0) as i32
+            } else {
+                m.xmin
+            };
+            return Some((m.width, m.height, xmin, m.ymin, span, b, face.name));
+        }
+
+        if self.bdf_fallback_has_glyph(c) {
+            // Unifont: comprehensive Unicode coverage as the catch-all
+            return Some(self.render_bdf_fallback_glyph(c, is_wide, "bdf fallback"));
+        }
+
+        None
     }
 
-    /// Check if BDF fallback font has a glyph
-    fn bdf_fallback_has_glyph(&self, c: char) -> bool {
-        self.bdf_fallback
-            .as_ref()
-            .map(|fb| fb.font.get_char(c).is_some())
-            .unwrap_or(false)
+    /// Rasterize `c` from the first TTF face that has it, primary first, as
+    /// (width, height, bitmap) of its ink
+    fn rasterize_outline(&self, c: char) -> Option<(usize, usize, Vec<u8>)> {
+        let primary = match &self.source {
+            FontSource::Ttf { font, font_size } => Some((font, *font_size)),
+            FontSource::Bdf { .. } => None,
+        };
+        let (font, font_size) = primary
+            .into_iter()
+            .chain(self.fallback_chain.iter().map(|f| (&f.font, f.font_size)))
+            .find(|(font, _)| font.lookup_glyph_index(c) != 0)?;
+        let (m, b) = font.rasterize(c, font_size);
+        (m.width > 0 && m.height > 0).then_some((m.width, m.height, b))
     }
 
-    /// Get glyph info, rasterizing if needed. Falls back to fallback font if available,
-    /// or '?' if neither font has the character.
+    /// Get glyph info, rasterizing if needed. Tries the primary font, then
+    /// the fallback chain, or '?' if no font has the character.
     /// is_wide indicates if this is a double-width character (CJK, etc.)
     pub fn get_glyph(&mut self, c: char, is_wide: bool) -> Result<GlyphInfo, AtlasError> {
+        self.get_styled_glyph(c, is_wide, GlyphStyle::Regular)
+    }
+
+    /// Get glyph info for a bold and/or italic character, emboldening and
+    /// slanting the regular glyph.
+    pub fn get_styled_glyph(
+        &mut self,
+        c: char,
+        is_wide: bool,
+        style: GlyphStyle,
+    ) -> Result<GlyphInfo, AtlasError> {
         // Cache key includes is_wide to handle rare cases where same char might be rendered differently
-        let cache_key = if is_wide {
-            // Use private use area to differentiate wide glyphs in cache
-            char::from_u32(c as u32 | 0x100000).unwrap_or(c)
-        } else {
-            c
-        };
+        let cache_key = (c, style, is_wide);
 
-        if let Some(info) = self.glyphs.get(&cache_key) {
+        if let Some(info) = self.glyphs.get_mut(&cache_key) {
+            info.last_used = self.frame;
             return Ok(*info);
         }
 
-        // Try fonts in order: primary -> fallback -> symbols -> bdf_fallback -> emoji -> '?'
+        // Keep the cache bounded: make room before rasterizing a new glyph
+        if let Some(max) = self.max_cached_glyphs {
+            while self.glyphs.len() >= max.max(1) {
+                if !self.evict_lru() {
+                    break;
+                }
+            }
+        }
+
+        // Box drawing and block elements are drawn to fill the cell exactly,
+        // so lines and blocks in neighbouring cells join up without gaps
+        if box_drawing::is_procedural(c) {
+            let (cell_w, cell_h) = self.cell_size();
+            let width = cell_w.ceil() as usize * if is_wide { 2 } else { 1 };
+            let height = cell_h as usize;
+            let bitmap = box_drawing::render(c, width, height).unwrap_or_default();
+            let offset_y = self.ascent() - height as f32;
+            return self.insert_glyph(cache_key, width, height, &bitmap, cell_w, (0.0, offset_y));
+        }
+        if POWERLINE_SEPARATORS.contains(&c) {
+            if let Some((glyph_w, glyph_h, bitmap)) = self.rasterize_outline(c) {
+                let (cell_w, cell_h) = self.cell_size();
+                let width = cell_w.ceil() as usize * if is_wide { 2 } else { 1 };
+                let height = cell_h as usize;
+                let bitmap = scale_bitmap(glyph_w, glyph_h, &bitmap, width, height);
+                let offset_y = self.ascent() - height as f32;
+                return self.insert_glyph(
+                    cache_key,
+                    width,
+                    height,
+                    &bitmap,
+                    cell_w,
+                    (0.0, offset_y),
+                );
+            }
+        }
+
+        // Try fonts in order: primary -> fallback chain -> bdf_fallback -> '?'
         let primary_has = self.primary_has_glyph(c);
-        let fallback_has = self.fallback_has_glyph(c);
-        let symbols_has = self.symbols_has_glyph(c);
-        let bdf_fallback_has = self.bdf_fallback_has_glyph(c);
-        let emoji_has = self.emoji_has_glyph(c);
 
         // Rasterize glyph from appropriate font
         // Returns (width, height, xmin, ymin, advance, bitmap, source_name)
@@ -380,71 +538,20 @@ impl GlyphAtlas {
                 FontSource::Ttf { font, font_size } => {
                     let (m, b) = font.rasterize(c, *font_size);
                     // If primary returned empty bitmap, try fallbacks
-                    if (m.width == 0 || m.height == 0) && c != ' ' {
-                        if fallback_has {
-                            let fallback = self.fallback_font.as_ref().unwrap();
-                            let (fm, fb) = fallback.rasterize(c, self.fallback_font_size);
-                            (
-                                fm.width,
-                                fm.height,
-                                fm.xmin,
-                                fm.ymin,
-                                self.cell_width,
-                                fb,
-                                "fallback (primary empty)",
-                            )
-                        } else if symbols_has {
-                            let symbols = self.symbols_font.as_ref().unwrap();
-                            let (sm, sb) = symbols.rasterize(c, self.symbols_font_size);
-                            (
-                                sm.width,
-                                sm.height,
-                                sm.xmin,
-                                sm.ymin,
-                                self.cell_width,
-                                sb,
-                                "symbols (primary empty)",
-                            )
-                        } else if bdf_fallback_has {
-                            self.render_bdf_fallback_glyph(
-                                c,
-                                is_wide,
-                                "bdf fallback (primary empty)",
-                            )
-                        } else if emoji_has {
-                            let emoji = self.emoji_font.as_ref().unwrap();
-                            let (em, eb) = emoji.rasterize(c, self.emoji_font_size);
-                            (
-                                em.width,
-                                em.height,
-                                em.xmin,
-                                em.ymin,
-                                self.cell_width,
-                                eb,
-                                "emoji (primary empty)",
-                            )
-                        } else {
-                            (
-                                m.width,
-                                m.height,
-                                m.xmin,
-                                m.ymin,
-                                m.advance_width,
-                                b,
-                                "primary",
-                            )
-                        }
+                    let fallback = if (m.width == 0 || m.height == 0) && c != ' ' {
+                        self.rasterize_fallback(c, is_wide)
                     } else {
-                        (
-                            m.width,
-                            m.height,
-                            m.xmin,
-                            m.ymin,
-                            m.advance_width,
-                            b,
-                            "primary",
-                        )
-                    }
+                        None
+                    };
+                    fallback.unwrap_or((
+                        m.width,
+                        m.height,
+                        m.xmin,
+                        m.ymin,
+                        m.advance_width,
+                        b,
+                        "primary",
+                    ))
                 }
                 FontSource::Bdf { font } => {
                     let glyph = font.get_char(c).unwrap();
@@ -461,48 +568,9 @@ impl GlyphAtlas {
                     )
                 }
             }
-        } else if fallback_has {
-            // Primary doesn't have it, try fallback
-            let fallback = self.fallback_font.as_ref().unwrap();
-            let (m, b) = fallback.rasterize(c, self.fallback_font_size);
-            (
-                m.width,
-                m.height,
-                m.xmin,
-                m.ymin,
-                self.cell_width,
-                b,
-                "fallback",
-            )
-        } else if symbols_has {
-            // Try symbols font
-            let symbols = self.symbols_font.as_ref().unwrap();
-            let (m, b) = symbols.rasterize(c, self.symbols_font_size);
-            (
-                m.width,
-                m.height,
-                m.xmin,
-                m.ymin,
-                self.cell_width,
-                b,
-                "symbols",
-            )
-        } else if bdf_fallback_has {
-            // Try BDF fallback (e.g., Unifont for comprehensive Unicode coverage)
-            self.render_bdf_fallback_glyph(c, is_wide, "bdf fallback")
-        } else if emoji_has {
-            // Try emoji font
-            let emoji = self.emoji_font.as_ref().unwrap();
-            let (m, b) = emoji.rasterize(c, self.emoji_font_size);
-            (
-                m.width,
-                m.height,
-                m.xmin,
-                m.ymin,
-                self.cell_width,
-                b,
-                "emoji",
-            )
+        } else if let Some(fallback) = self.rasterize_fallback(c, is_wide) {
+            // Primary doesn't have it, use the first fallback that does
+            fallback
         } else {
             // No font has this glyph - use '?' from primary or fallback
             match &self.source {
@@ -531,8 +599,8 @@ impl GlyphAtlas {
                             bitmap,
                             "? (bdf)",
                         )
-                    } else if let Some(fallback) = &self.fallback_font {
-                        let (m, b) = fallback.rasterize('?', self.fallback_font_size);
+                    } else if let Some(fallback) = self.fallback_chain.first() {
+                        let (m, b) = fallback.font.rasterize('?', fallback.font_size);
                         (
                             m.width,
                             m.height,
@@ -550,6 +618,19 @@ impl GlyphAtlas {
             }
         };
 
+        // Styles are synthesized from the regular glyph
+        let synthesize = width > 0 && height > 0;
+        let (width, xmin, bitmap) = if synthesize && style.is_italic() {
+            slant(width, height, xmin, ymin, &bitmap)
+        } else {
+            (width, xmin, bitmap)
+        };
+        let (width, bitmap) = if synthesize && style.is_bold() {
+            embolden(width, height, &bitmap)
+        } else {
+            (width, bitmap)
+        };
+
         // Log non-ASCII glyph resolution (only on first rasterization, not cached)
         if !c.is_ascii() {
             tracing::debug!(
@@ -566,6 +647,26 @@ impl GlyphAtlas {
             );
         }
 
+        self.insert_glyph(
+            cache_key,
+            width,
+            height,
+            &bitmap,
+            advance,
+            (xmin as f32, ymin as f32),
+        )
+    }
+
+    /// Copy a glyph's coverage bitmap into the atlas and cache it
+    fn insert_glyph(
+        &mut self,
+        cache_key: GlyphKey,
+        width: usize,
+        height: usize,
+        bitmap: &[u8],
+        advance: f32,
+        offset: (f32, f32),
+    ) -> Result<GlyphInfo, AtlasError> {
         if width == 0 || height == 0 {
             // Space or empty glyph
             let info = GlyphInfo {
@@ -576,55 +677,161 @@ impl GlyphAtlas {
                 width: 0,
                 height: 0,
                 advance,
-                offset_x: xmin as f32,
-                offset_y: ymin as f32,
+                offset_x: offset.0,
+                offset_y: offset.1,
+                last_used: self.frame,
+                slot: None,
             };
             self.glyphs.insert(cache_key, info);
             return Ok(info);
         }
 
-        // Check if we need to wrap to next row
-        if self.next_x + width as u32 > self.atlas_width {
-            self.next_x = 0;
-            self.next_y += self.row_height + 1;
-            self.row_height = 0;
-        }
+        let slot = self.allocate(width as u32, height as u32)?;
 
-        // Check if atlas is full
-        if self.next_y + height as u32 > self.atlas_height {
-            return Err(AtlasError::AtlasFull);
+        // Copy glyph bitmap to atlas (clearing leftovers from a reused slot first)
+        for y in slot.y..slot.y + slot.height {
+            let row_start = (y * self.atlas_width + slot.x) as usize;
+            let row_end = row_start + slot.width as usize;
+            self.atlas_data[row_start..row_end].fill(0);
         }
-
-        // Copy glyph bitmap to atlas
         for y in 0..height {
             for x in 0..width {
                 let src_idx = y * width + x;
-                let dst_x = self.next_x + x as u32;
-                let dst_y = self.next_y + y as u32;
+                let dst_x = slot.x + x as u32;
+                let dst_y = slot.y + y as u32;
                 let dst_idx = (dst_y * self.atlas_width + dst_x) as usize;
                 self.atlas_data[dst_idx] = bitmap[src_idx];
             }
         }
+        self.mark_dirty(slot);
 
         let info = GlyphInfo {
-            uv_x: self.next_x as f32 / self.atlas_width as f32,
-            uv_y: self.next_y as f32 / self.atlas_height as f32,
+            uv_x: slot.x as f32 / self.atlas_width as f32,
+            uv_y: slot.y as f32 / self.atlas_height as f32,
             uv_width: width as f32 / self.atlas_width as f32,
             uv_height: height as f32 / self.atlas_height as f32,
             width: width as u32,
             height: height as u32,
             advance,
-            offset_x: xmin as f32,
-            offset_y: ymin as f32,
+            offset_x: offset.0,
+            offset_y: offset.1,
+            last_used: self.frame,
+            slot: Some(slot),
         };
 
-        self.next_x += width as u32 + 1;
-        self.row_height = self.row_height.max(height as u32);
-
         self.glyphs.insert(cache_key, info);
         Ok(info)
     }
 
+    /// Find space for a `width` x `height` bitmap: reuse a freed slot, then
+    /// shelf-pack, then grow the atlas and/or evict old glyphs, depending on
+    /// the eviction policy. Once every glyph that can go is gone and still
+    /// no freed slot fits, the shelves start over from the top.
+    fn allocate(&mut self, width: u32, height: u32) -> Result<AtlasSlot, AtlasError> {
+        loop {
+            if let Some(slot) = self.take_free_slot(width, height) {
+                return Ok(slot);
+            }
+
+            // Check if we need to wrap to next row
+            if self.next_x + width > self.atlas_width {
+                self.next_x = 0;
+                self.next_y += self.row_height + 1;
+                self.row_height = 0;
+                let top = self.next_y;
+                self.pinned.retain(|pin| pin.y + pin.height + 1 > top);
+            }
+
+            if self.next_y + height <= self.atlas_height {
+                let slot = AtlasSlot {
+                    x: self.next_x,
+                    y: self.next_y,
+                    width: width.min(self.atlas_width - self.next_x),
+                    height,
+                };
+                self.row_height = self.row_height.max(height);
+                if let Some(pin) = self.pinned.iter().find(|pin| pin.touches(slot)) {
+                    self.next_x = pin.x + pin.width + 1;
+                    continue;
+                }
+                self.next_x += width + 1;
+                self.fresh_shelves = false;
+                return Ok(slot);
+            }
+
+            // Out of rows: grow the atlas or reclaim space, as the policy allows
+            let made_room = match self.eviction_policy {
+                AtlasEvictionPolicy::Grow => {
+                    self.grow() || self.evict_lru() || self.reset_shelves()
+                }
+                AtlasEvictionPolicy::Lru => self.evict_lru() || self.reset_shelves(),
+                AtlasEvictionPolicy::Error => false,
+            };
+            if !made_room {
+                return Err(AtlasError::AtlasFull);
+            }
+        }
+    }
+
+    /// Remove the smallest free slot that fits the requested size
+    fn take_free_slot(&mut self, width: u32, height: u32) -> Option<AtlasSlot> {
+        let index = self
+            .free_slots
+            .iter()
+            .enumerate()
+            .filter(|(_, slot)| slot.width >= width && slot.height >= height)
+            .min_by_key(|(_, slot)| slot.width * slot.height)
+            .map(|(i, _)| i)?;
+        Some(self.free_slots.swap_remove(index))
+    }
+
+    /// Evict the least-recently-used glyph, releasing its atlas region.
+    /// Glyphs used in the current frame are never evicted, since quads for
+    /// them may already have been built. Returns false if nothing was evicted.
+    fn evict_lru(&mut self) -> bool {
+        let victim = self
+            .glyphs
+            .iter()
+            .filter(|(_, info)| info.last_used < self.frame)
+            .min_by_key(|(_, info)| info.last_used)
+            .map(|(key, _)| *key);
+
+        let Some(key) = victim else {
+            return false;
+        };
+        if let Some(slot) = self.glyphs.remove(&key).and_then(|info| info.slot) {
+            self.free_slots.push(slot);
+        }
+        true
+    }
+
+    /// Restart shelf packing from the top of the atlas, around the glyphs
+    /// used this frame. Freed slots are forgotten, as the shelves now cover
+    /// them. Returns false if nothing was packed since the last reset.
+    fn reset_shelves(&mut self) -> bool {
+        if self.fresh_shelves {
+            return false;
+        }
+        self.pinned = self.glyphs.values().filter_map(|info| info.slot).collect();
+        self.free_slots.clear();
+        self.next_x = 0;
+        self.next_y = 0;
+        self.row_height = 0;
+        self.fresh_shelves = true;
+        true
+    }
+
+    /// Start a new frame for LRU bookkeeping. Glyphs requested after this
+    /// call are protected from eviction until the next frame begins.
+    pub fn begin_frame(&mut self) {
+        self.frame += 1;
+    }
+
+    /// Limit the number of cached glyphs (None = unbounded)
+    pub fn set_max_cached_glyphs(&mut self, max: Option<usize>) {
+        self.max_cached_glyphs = max;
+    }
+
     /// Render a glyph from the BDF fallback font, scaling to match primary cell size.
     /// For wide characters (CJK, etc.), scales to 2x cell width.
     /// Returns (width, height, xmin, ymin, advance, bitmap, source_name).
@@ -668,15 +875,714 @@ impl GlyphAtlas {
         )
     }
 
+    /// Double the atlas dimensions, keeping existing glyphs at the same pixel
+    /// positions. Cached UVs are rescaled to the new size.
+    /// Returns false if doubling would exceed `max_atlas_size`.
+    fn grow(&mut self) -> bool {
+        if !self.can_grow() {
+            return false;
+        }
+
+        let new_width = self.atlas_width * 2;
+        let new_height = self.atlas_height * 2;
+        let mut new_data = vec![0u8; (new_width * new_height) as usize];
+        for (y, row) in self
+            .atlas_data
+            .chunks_exact(self.atlas_width as usize)
+            .enumerate()
+        {
+            let dst = y * new_width as usize;
+            new_data[dst..dst + row.len()].copy_from_slice(row);
+        }
+
+        let scale_x = self.atlas_width as f32 / new_width as f32;
+        let scale_y = self.atlas_height as f32 / new_height as f32;
+        for info in self.glyphs.values_mut() {
+            info.uv_x *= scale_x;
+            info.uv_y *= scale_y;
+            info.uv_width *= scale_x;
+            info.uv_height *= scale_y;
+        }
+
+        tracing::info!(
+            "Glyph atlas full with {} glyphs, growing {}x{} -> {}x{}",
+            self.glyphs.len(),
+            self.atlas_width,
+            self.atlas_height,
+            new_width,
+            new_height
+        );
+
+        self.atlas_data = new_data;
+        self.atlas_width = new_width;
+        self.atlas_height = new_height;
+        self.mark_dirty(AtlasSlot {
+            x: 0,
+            y: 0,
+            width: new_width,
+            height: new_height,
+        });
+        true
+    }
+
     pub fn atlas_data(&self) -> &[u8] {
         &self.atlas_data
     }
 
+    fn can_grow(&self) -> bool {
+        self.eviction_policy == AtlasEvictionPolicy::Grow
+            && self.atlas_width * 2 <= self.max_atlas_size
+            && self.atlas_height * 2 <= self.max_atlas_size
+    }
+
+    pub fn eviction_policy(&self) -> AtlasEvictionPolicy {
+        self.eviction_policy
+    }
+
+    /// Choose what happens when the atlas runs out of room. Only affects
+    /// future allocations; an atlas that already grew keeps its size.
+    pub fn set_eviction_policy(&mut self, policy: AtlasEvictionPolicy) {
+        self.eviction_policy = policy;
+    }
+
+    /// Cap atlas growth at `size` pixels per side. An atlas that is already
+    /// larger keeps its size but won't grow further.
+    pub fn set_max_atlas_size(&mut self, size: u32) {
+        self.max_atlas_size = size.max(INITIAL_ATLAS_SIZE);
+    }
+
+    /// Whether the atlas can't grow and has no room for another cell-sized
+    /// glyph. New glyphs then replace least-recently-used ones, or fail
+    /// under `AtlasEvictionPolicy::Error`.
+    pub fn is_full(&self) -> bool {
+        if self.can_grow() {
+            return false;
+        }
+        let width = self.cell_width.ceil() as u32;
+        let height = self.cell_height.ceil() as u32;
+        let fits_free_slot = self
+            .free_slots
+            .iter()
+            .any(|slot| slot.width >= width && slot.height >= height);
+        let fits_row = self.next_x + width <= self.atlas_width
+            && self.next_y + height.max(self.row_height) <= self.atlas_height;
+        let fits_next_row = self.next_y + self.row_height + 1 + height <= self.atlas_height;
+        !(fits_free_slot || fits_row || fits_next_row)
+    }
+
+    fn mark_dirty(&mut self, slot: AtlasSlot) {
+        self.dirty = Some(match self.dirty {
+            Some(dirty) => dirty.union(slot),
+            None => slot,
+        });
+    }
+
+    /// Take the region changed since the last call, widened to whole 4x4
+    /// blocks so it can be uploaded BC4-compressed
+    pub(crate) fn take_dirty_region(&mut self) -> Option<AtlasSlot> {
+        let dirty = self.dirty.take()?;
+        let block = BC4_BLOCK_SIZE as u32;
+        let x = dirty.x / block * block;
+        let y = dirty.y / block * block;
+        let right = (dirty.x + dirty.width).div_ceil(block) * block;
+        let bottom = (dirty.y + dirty.height).div_ceil(block) * block;
+        Some(AtlasSlot {
+            x,
+            y,
+            width: right.min(self.atlas_width) - x,
+            height: bottom.min(self.atlas_height) - y,
+        })
+    }
+
+    pub fn compression_mode(&self) -> AtlasCompressionMode {
+        self.compression
+    }
+
+    pub fn set_compression_mode(&mut self, mode: AtlasCompressionMode) {
+        self.compression = mode;
+    }
+
+    /// Encode a block-aligned region of the atlas as BC4 blocks (row-major,
+    /// 8 bytes per 4x4 block). Atlas dimensions are always multiples of 4.
+    pub(crate) fn to_compressed_bc4(&self, region: AtlasSlot) -> Vec<u8> {
+        let width = self.atlas_width as usize;
+        let blocks_x = region.width as usize / BC4_BLOCK_SIZE;
+        let blocks_y = region.height as usize / BC4_BLOCK_SIZE;
+        let first_x = region.x as usize / BC4_BLOCK_SIZE;
+        let first_y = region.y as usize / BC4_BLOCK_SIZE;
+        let mut out = Vec::with_capacity(blocks_x * blocks_y * BC4_BLOCK_BYTES);
+
+        let mut texels = [0u8; BC4_BLOCK_SIZE * BC4_BLOCK_SIZE];
+        for by in first_y..first_y + blocks_y {
+            for bx in first_x..first_x + blocks_x {
+                for row in 0..BC4_BLOCK_SIZE {
+                    let start = (by * BC4_BLOCK_SIZE + row) * width + bx * BC4_BLOCK_SIZE;
+                    texels[row * BC4_BLOCK_SIZE..(row + 1) * BC4_BLOCK_SIZE]
+                        .copy_from_slice(&self.atlas_data[start..start + BC4_BLOCK_SIZE]);
+                }
+                out.extend_from_slice(&encode_bc4_block(&texels));
+            }
+        }
+        out
+    }
+
     pub fn atlas_dimensions(&self) -> (u32, u32) {
         (self.atlas_width, self.atlas_height)
     }
 
+    /// Number of cached glyphs, including empty ones like space
+    pub fn glyph_count(&self) -> usize {
+        self.glyphs.len()
+    }
+
+    /// Fraction of the atlas texture occupied by cached glyphs (0.0-1.0)
+    pub fn fill_ratio(&self) -> f32 {
+        let used: u64 = self
+            .glyphs
+            .values()
+            .filter_map(|glyph| glyph.slot)
+            .map(|slot| slot.width as u64 * slot.height as u64)
+            .sum();
+        let total = self.atlas_width as u64 * self.atlas_height as u64;
+        used as f32 / total.max(1) as f32
+    }
+
+    /// Size of a grid cell, including line height and letter spacing
     pub fn cell_size(&self) -> (f32, f32) {
-        (self.cell_width, self.cell_height)
+        (
+            (self.cell_width + self.letter_spacing).max(1.0),
+            (self.cell_height * self.line_height_mult).round().max(1.0),
+        )
+    }
+
+    /// Add space around glyphs: `line_height_mult` scales the cell height
+    /// (rounded to whole pixels so rows stay aligned), `letter_spacing`
+    /// adds pixels to the cell width. Glyph bitmaps are unaffected, except
+    /// box drawing and Powerline separators, which are redrawn at the new
+    /// cell size.
+    pub fn set_spacing(&mut self, line_height_mult: f32, letter_spacing: f32) {
+        self.line_height_mult = line_height_mult.max(0.5);
+        self.letter_spacing = letter_spacing;
+
+        let stale: Vec<GlyphKey> = self
+            .glyphs
+            .keys()
+            .filter(|(c, _, _)| box_drawing::is_procedural(*c) || POWERLINE_SEPARATORS.contains(c))
+            .copied()
+            .collect();
+        for key in stale {
+            if let Some(slot) = self.glyphs.remove(&key).and_then(|info| info.slot) {
+                self.free_slots.push(slot);
+            }
+        }
+    }
+}
+
+/// Resample a coverage bitmap to another size, averaging the source area
+/// under each destination pixel
+fn scale_bitmap(
+    width: usize,
+    height: usize,
+    bitmap: &[u8],
+    new_width: usize,
+    new_height: usize,
+) -> Vec<u8> {
+    let sx = width as f32 / new_width as f32;
+    let sy = height as f32 / new_height as f32;
+    // Source pixels under [start, end) with how much of each is covered
+    let spans = |start: f32, end: f32, limit: usize| {
+        let first = start.floor() as usize;
+        let last = (end.ceil() as usize).min(limit);
+        (first..last).map(move |i| {
+            let weight = (end.min(i as f32 + 1.0) - start.max(i as f32)).max(0.0);
+            (i, weight)
+        })
+    };
+
+    let mut out = vec![0u8; new_width * new_height];
+    for y in 0..new_height {
+        let (y0, y1) = (y as f32 * sy, (y + 1) as f32 * sy);
+        for x in 0..new_width {
+            let (x0, x1) = (x as f32 * sx, (x + 1) as f32 * sx);
+            let mut sum = 0.0;
+            for (src_y, wy) in spans(y0, y1, height) {
+                for (src_x, wx) in spans(x0, x1, width) {
+                    sum += bitmap[src_y * width + src_x] as f32 * wx * wy;
+                }
+            }
+            out[y * new_width + x] = (sum / (sx * sy)).round().min(255.0) as u8;
+        }
+    }
+    out
+}
+
+/// Encode 16 texels as one BC4 block. Uses the 8-level mode with the block's
+/// max and min as endpoints, so blocks of pure 0/255 (bitmap fonts) and flat
+/// blocks are exact; antialiased edges are quantized to 8 levels.
+fn encode_bc4_block(texels: &[u8; 16]) -> [u8; BC4_BLOCK_BYTES] {
+    let max = *texels.iter().max().unwrap();
+    let min = *texels.iter().min().unwrap();
+
+    let mut block = [0u8; BC4_BLOCK_BYTES];
+    block[0] = max;
+    block[1] = min;
+    if max == min {
+        // All indices 0 -> red0
+        return block;
+    }
+
+    // Palette: index 0 = max, 1 = min, 2..=7 step from max toward min
+    let range = (max - min) as u32;
+    let mut indices: u64 = 0;
+    for (i, &value) in texels.iter().enumerate() {
+        let step = (((max - value) as u32 * 7 + range / 2) / range) as u64;
+        let index = match step {
+            0 => 0,
+            7 => 1,
+            s => s + 1,
+        };
+        indices |= index << (3 * i);
+    }
+    block[2..].copy_from_slice(&indices.to_le_bytes()[..6]);
+    block
+}
+
+/// Synthetic bold: double-strike the bitmap one pixel to the right
+fn embolden(width: usize, height: usize, bitmap: &[u8]) -> (usize, Vec<u8>) {
+    let new_width = width + 1;
+    let mut out = vec![0u8; new_width * height];
+    for y in 0..height {
+        for x in 0..new_width {
+            let here = if x < width { bitmap[y * width + x] } else { 0 };
+            let left = if x > 0 { bitmap[y * width + x - 1] } else { 0 };
+            out[y * new_width + x] = here.max(left);
+        }
+    }
+    (new_width, out)
+}
+
+/// Synthetic italic: shear rows to the right in proportion to their height
+/// above the baseline. Returns the new width, xmin and bitmap.
+fn slant(
+    width: usize,
+    height: usize,
+    xmin: i32,
+    ymin: i32,
+    bitmap: &[u8],
+) -> (usize, i32, Vec<u8>) {
+    // Row 0 is the top of the glyph, which sits `ymin + height - 1` above the baseline
+    let shift = |y: usize| {
+        ((ymin + (height - 1 - y) as i32) as f32 * SYNTHETIC_ITALIC_SLANT).round() as i32
+    };
+    let min_shift = shift(height - 1).min(shift(0));
+    let max_shift = shift(height - 1).max(shift(0));
+    let new_width = width + (max_shift - min_shift) as usize;
+
+    let mut out = vec![0u8; new_width * height];
+    for y in 0..height {
+        let offset = (shift(y) - min_shift) as usize;
+        let src = &bitmap[y * width..(y + 1) * width];
+        out[y * new_width + offset..y * new_width + offset + width].copy_from_slice(src);
+    }
+    (new_width, xmin + min_shift, out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fonts::{get_font_data, get_unifont_fallback_data};
+    use crt_core::Font as BundledFont;
+
+    #[test]
+    fn test_atlas_grows_for_many_cjk_glyphs() {
+        let mut atlas = GlyphAtlas::new(get_font_data(BundledFont::default()), 32.0).unwrap();
+        atlas.set_bdf_fallback(get_unifont_fallback_data()).unwrap();
+
+        let first = atlas.get_glyph('\u{4E00}', true).unwrap();
+        let first_pixel = (first.uv_x * 1024.0, first.uv_y * 1024.0);
+
+        for cp in 0x4E00..0x4E00 + 5000 {
+            let c = char::from_u32(cp).unwrap();
+            let info = atlas.get_glyph(c, true).unwrap();
+            assert!(info.width > 0 && info.height > 0, "empty glyph for {:?}", c);
+        }
+
+        let (width, height) = atlas.atlas_dimensions();
+        assert!(width > INITIAL_ATLAS_SIZE && height > INITIAL_ATLAS_SIZE);
+        assert_eq!(atlas.atlas_data().len(), (width * height) as usize);
+
+        // Glyphs cached before growth keep their pixels; only the UVs are rescaled
+        let again = atlas.get_glyph('\u{4E00}', true).unwrap();
+        assert_eq!(
+            (again.uv_x * width as f32, again.uv_y * height as f32),
+            first_pixel
+        );
+    }
+
+    #[test]
+    fn test_lru_eviction_respects_limit() {
+        let mut atlas = GlyphAtlas::new(get_font_data(BundledFont::default()), 16.0).unwrap();
+        atlas.set_max_cached_glyphs(Some(4));
+
+        for c in ['A', 'B', 'C', 'D'] {
+            atlas.begin_frame();
+            atlas.get_glyph(c, false).unwrap();
+        }
+        let oldest = atlas.glyphs[&('A', GlyphStyle::Regular, false)].slot;
+
+        // Touch 'A' so 'B' becomes the least recently used
+        atlas.begin_frame();
+        atlas.get_glyph('A', false).unwrap();
+        atlas.begin_frame();
+        atlas.get_glyph('E', false).unwrap();
+
+        assert_eq!(atlas.glyphs.len(), 4);
+        assert!(atlas
+            .glyphs
+            .contains_key(&('A', GlyphStyle::Regular, false)));
+        assert!(!atlas
+            .glyphs
+            .contains_key(&('B', GlyphStyle::Regular, false)));
+        assert_eq!(
+            atlas.glyphs[&('A', GlyphStyle::Regular, false)].slot,
+            oldest
+        );
+
+        // Everything used in the current frame survives, even past the limit
+        atlas.begin_frame();
+        for c in ['F', 'G', 'H', 'I', 'J'] {
+            atlas.get_glyph(c, false).unwrap();
+        }
+        assert_eq!(atlas.glyphs.len(), 5);
+        assert!("FGHIJ".chars().all(|c| atlas.glyphs.contains_key(&(
+            c,
+            GlyphStyle::Regular,
+            false
+        ))));
+    }
+
+    #[test]
+    fn test_wide_glyph_fits_after_narrow_ones_fill_the_atlas() {
+        let mut atlas = GlyphAtlas::new(get_font_data(BundledFont::default()), 64.0).unwrap();
+        atlas.set_bdf_fallback(get_unifont_fallback_data()).unwrap();
+        atlas.set_eviction_policy(AtlasEvictionPolicy::Lru);
+
+        let mut narrow = (0x21..0x3000).filter_map(char::from_u32);
+        while !atlas.is_full() {
+            atlas.get_glyph(narrow.next().unwrap(), false).unwrap();
+        }
+
+        // 'A' stays where it is while it's in use
+        atlas.begin_frame();
+        let kept = atlas.get_glyph('A', false).unwrap().slot.unwrap();
+        let wide = atlas.get_glyph('\u{4E00}', true).unwrap().slot.unwrap();
+        assert!(wide.width > kept.width);
+        assert!(!wide.touches(kept));
+        assert_eq!(
+            atlas.glyphs[&('A', GlyphStyle::Regular, false)].slot,
+            Some(kept)
+        );
+    }
+
+    #[test]
+    fn test_fill_ratio_follows_cached_glyphs() {
+        let mut atlas = GlyphAtlas::new(get_font_data(BundledFont::default()), 16.0).unwrap();
+        let empty = atlas.fill_ratio();
+        let count = atlas.glyph_count();
+        for c in 'a'..='z' {
+            atlas.get_glyph(c, false).unwrap();
+        }
+        assert_eq!(atlas.glyph_count(), count + 26);
+        let filled = atlas.fill_ratio();
+        assert!(filled > empty && filled < 1.0);
+    }
+
+    #[test]
+    fn test_synthesized_styles() {
+        let mut atlas = GlyphAtlas::new(get_font_data(BundledFont::default()), 16.0).unwrap();
+        let regular = atlas.get_glyph('l', false).unwrap();
+        let bold = atlas
+            .get_styled_glyph('l', false, GlyphStyle::Bold)
+            .unwrap();
+        let italic = atlas
+            .get_styled_glyph('l', false, GlyphStyle::Italic)
+            .unwrap();
+
+        // Styles are cached separately from the regular glyph
+        assert_eq!(atlas.glyphs.len(), 3);
+        assert_eq!(bold.width, regular.width + 1);
+        assert_eq!(bold.height, regular.height);
+        assert!(italic.width > regular.width);
+        assert_eq!(italic.height, regular.height);
+    }
+
+    #[test]
+    fn test_slant_shifts_top_right() {
+        // 1px wide vertical bar, 6 rows, sitting on the baseline
+        let bitmap = vec![255u8; 6];
+        let (width, xmin, out) = slant(1, 6, 3, 0, &bitmap);
+        assert_eq!(xmin, 3);
+        assert_eq!(width, 2);
+        // Top row leans right, bottom row stays put
+        assert_eq!(&out[0..2], &[0, 255]);
+        assert_eq!(&out[10..12], &[255, 0]);
+    }
+
+    #[test]
+    fn test_fallback_chain_order() {
+        use crate::fonts::get_fallback_font_data;
+        use crt_core::FallbackFont;
+
+        let hack = ("Hack", get_fallback_font_data(FallbackFont::Hack).unwrap());
+        let symbols = (
+            "Symbols",
+            get_fallback_font_data(FallbackFont::Symbols).unwrap(),
+        );
+        let mut atlas = GlyphAtlas::new(get_font_data(BundledFont::IbmVga), 16.0).unwrap();
+        atlas.set_bdf_fallback(get_unifont_fallback_data()).unwrap();
+
+        // U+21E6 (leftwards white arrow) is in both Hack and Noto Sans Symbols 2
+        let source = |atlas: &GlyphAtlas| atlas.rasterize_fallback('\u{21E6}', false).unwrap().6;
+
+        atlas.set_fallback_chain(&[hack, symbols]).unwrap();
+        assert_eq!(source(&atlas), "Hack");
+        atlas.set_fallback_chain(&[symbols, hack]).unwrap();
+        assert_eq!(source(&atlas), "Symbols");
+
+        // With no TTF fallbacks, Unifont catches it
+        atlas.set_fallback_chain(&[]).unwrap();
+        assert_eq!(source(&atlas), "bdf fallback");
+    }
+
+    #[test]
+    fn test_wide_fallback_spans_two_cells() {
+        use crate::fonts::get_fallback_font_data;
+        use crt_core::FallbackFont;
+
+        let mut atlas = GlyphAtlas::new(get_font_data(BundledFont::IbmVga), 16.0).unwrap();
+        let hack = get_fallback_font_data(FallbackFont::Hack).unwrap();
+        atlas.set_fallback_chain(&[("Hack", hack)]).unwrap();
+        let cell_width = atlas.cell_width;
+
+        let narrow = atlas.rasterize_fallback('\u{21E6}', false).unwrap();
+        let wide = atlas.rasterize_fallback('\u{21E6}', true).unwrap();
+        assert_eq!(narrow.4, cell_width);
+        assert_eq!(wide.4, cell_width * 2.0);
+        // Same bitmap, shifted right to sit in the middle of both cells
+        assert_eq!(wide.5, narrow.5);
+        assert!(wide.2 > narrow.2);
+    }
+
+    #[test]
+    fn test_oversized_fallback_fits_the_cell() {
+        use crate::fonts::get_fallback_font_data;
+        use crt_core::FallbackFont;
+
+        let mut atlas = GlyphAtlas::new(get_font_data(BundledFont::IbmVga), 16.0).unwrap();
+        let hack = get_fallback_font_data(FallbackFont::Hack).unwrap();
+        atlas.set_fallback_chain(&[("Hack", hack)]).unwrap();
+        let (cell_w, cell_h) = (atlas.cell_width, atlas.cell_height);
+
+        // Hack's Powerline arrow is taller and wider than an IBM VGA cell
+        let (width, height, xmin, ymin, ..) = atlas.rasterize_fallback('\u{E0B0}', false).unwrap();
+        assert!(width as f32 <= cell_w && height as f32 <= cell_h);
+        assert!(xmin >= 0 && xmin as f32 + width as f32 <= cell_w);
+        // Inside the cell: no higher than the ascent, no lower than the descent
+        assert!((ymin + height as i32) as f32 <= atlas.ascent);
+        assert!(ymin as f32 >= atlas.ascent - cell_h);
+    }
+
+    #[test]
+    fn test_powerline_separators_fill_the_cell() {
+        use crate::fonts::get_fallback_font_data;
+        use crt_core::FallbackFont;
+
+        let mut atlas = GlyphAtlas::new(get_font_data(BundledFont::IbmVga), 16.0).unwrap();
+        let hack = get_fallback_font_data(FallbackFont::Hack).unwrap();
+        atlas.set_fallback_chain(&[("Hack", hack)]).unwrap();
+
+        for line_height in [1.0, 1.25] {
+            atlas.set_spacing(line_height, 0.0);
+            let (cell_w, cell_h) = atlas.cell_size();
+            let glyph = atlas.get_glyph('\u{E0B0}', false).unwrap();
+            assert_eq!((glyph.width, glyph.height), (cell_w as u32, cell_h as u32));
+            // Top of the quad at the top of the cell
+            assert_eq!(glyph.offset_y, atlas.ascent() - cell_h);
+        }
+    }
+
+    #[test]
+    fn test_scale_bitmap_averages_coverage() {
+        assert_eq!(scale_bitmap(4, 4, &[255; 16], 2, 2), vec![255; 4]);
+        assert_eq!(scale_bitmap(2, 1, &[0, 255], 4, 1), vec![0, 0, 255, 255]);
+        assert_eq!(scale_bitmap(2, 1, &[0, 255], 1, 1), vec![128]);
+    }
+
+    /// Reference BC4 decoder (8-level mode only, as produced by the encoder)
+    fn decode_bc4_block(block: &[u8]) -> [u8; 16] {
+        let (red0, red1) = (block[0] as u32, block[1] as u32);
+        let mut palette = [red0, red1, 0, 0, 0, 0, 0, 0];
+        for (k, entry) in palette.iter_mut().enumerate().skip(2) {
+            *entry = ((8 - k as u32) * red0 + (k as u32 - 1) * red1) / 7;
+        }
+        let mut bits = [0u8; 8];
+        bits[..6].copy_from_slice(&block[2..]);
+        let indices = u64::from_le_bytes(bits);
+        std::array::from_fn(|i| palette[((indices >> (3 * i)) & 7) as usize] as u8)
+    }
+
+    #[test]
+    fn test_bc4_encoding() {
+        // Flat and binary blocks round-trip exactly
+        assert_eq!(decode_bc4_block(&encode_bc4_block(&[0; 16])), [0; 16]);
+        assert_eq!(decode_bc4_block(&encode_bc4_block(&[77; 16])), [77; 16]);
+        let binary: [u8; 16] = std::array::from_fn(|i| if i % 3 == 0 { 255 } else { 0 });
+        assert_eq!(decode_bc4_block(&encode_bc4_block(&binary)), binary);
+
+        // Gradients stay within half a palette step
+        let gradient: [u8; 16] = std::array::from_fn(|i| (i * 17) as u8);
+        let decoded = decode_bc4_block(&encode_bc4_block(&gradient));
+        for (a, b) in gradient.iter().zip(decoded.iter()) {
+            assert!((*a as i32 - *b as i32).abs() <= 19, "{} vs {}", a, b);
+        }
+
+        let atlas = GlyphAtlas::from_bdf(crate::fonts::get_bdf_font_data(
+            crt_core::BdfFont::Fixed6x13,
+        ))
+        .unwrap();
+        let (width, height) = atlas.atlas_dimensions();
+        let whole = AtlasSlot {
+            x: 0,
+            y: 0,
+            width,
+            height,
+        };
+        assert_eq!(
+            atlas.to_compressed_bc4(whole).len(),
+            (width * height / 2) as usize
+        );
+    }
+
+    #[test]
+    fn test_only_new_glyphs_are_dirty() {
+        let mut atlas = GlyphAtlas::from_bdf(crate::fonts::get_bdf_font_data(
+            crt_core::BdfFont::Fixed6x13,
+        ))
+        .unwrap();
+        atlas.take_dirty_region();
+        assert_eq!(atlas.take_dirty_region(), None);
+
+        atlas.get_glyph('A', false).unwrap();
+        atlas.get_glyph('B', false).unwrap();
+        let dirty = atlas.take_dirty_region().unwrap();
+        assert_eq!(atlas.take_dirty_region(), None);
+        // Whole 4x4 blocks covering both glyphs, not the whole atlas
+        assert_eq!((dirty.x % 4, dirty.y % 4), (0, 0));
+        assert_eq!((dirty.width % 4, dirty.height % 4), (0, 0));
+        assert!(dirty.width >= 12 && dirty.height >= 12);
+        assert!(dirty.width < atlas.atlas_width);
+        let blocks = (dirty.width / 4 * dirty.height / 4) as usize;
+        assert_eq!(atlas.to_compressed_bc4(dirty).len(), blocks * 8);
+
+        atlas.get_glyph('A', false).unwrap();
+        assert_eq!(atlas.take_dirty_region(), None);
+    }
+
+    #[test]
+    fn test_atlas_full_at_size_cap() {
+        let mut atlas = GlyphAtlas::new(get_font_data(BundledFont::default()), 32.0).unwrap();
+        atlas.set_bdf_fallback(get_unifont_fallback_data()).unwrap();
+        atlas.set_max_atlas_size(INITIAL_ATLAS_SIZE);
+        assert!(!atlas.is_full());
+
+        // Far more glyphs than fit: once full, old glyphs are evicted instead
+        for cp in 0x4E00..0x4E00 + 3000 {
+            atlas.begin_frame();
+            let c = char::from_u32(cp).unwrap();
+            let info = atlas.get_glyph(c, true).unwrap();
+            assert!(info.width > 0 && info.height > 0, "empty glyph for {:?}", c);
+        }
+
+        assert!(atlas.is_full());
+        assert_eq!(
+            atlas.atlas_dimensions(),
+            (INITIAL_ATLAS_SIZE, INITIAL_ATLAS_SIZE)
+        );
+    }
+
+    #[test]
+    fn test_spacing_changes_cell_size_not_glyphs() {
+        let mut atlas = GlyphAtlas::from_bdf(crate::fonts::get_bdf_font_data(
+            crt_core::BdfFont::Fixed8x13,
+        ))
+        .unwrap();
+        let glyph = atlas.get_glyph('A', false).unwrap();
+        let ascent = atlas.ascent();
+        assert_eq!(atlas.cell_size(), (8.0, 13.0));
+
+        atlas.set_spacing(1.5, 2.0);
+        // 13 * 1.5 = 19.5 rounds to a whole pixel row height
+        assert_eq!(atlas.cell_size(), (10.0, 20.0));
+        // The 7 extra pixels are split above and below the glyphs
+        assert_eq!(atlas.ascent(), ascent + 3.0);
+        assert_eq!(atlas.get_glyph('A', false).unwrap().width, glyph.width);
+    }
+
+    #[test]
+    fn test_box_drawing_fills_the_cell() {
+        let mut atlas = GlyphAtlas::from_bdf(crate::fonts::get_bdf_font_data(
+            crt_core::BdfFont::Fixed8x13,
+        ))
+        .unwrap();
+        let glyph = atlas.get_glyph('┼', false).unwrap();
+        assert_eq!((glyph.width, glyph.height), (8, 13));
+        // The quad's top (baseline - height - offset_y) is the cell's top
+        assert_eq!(atlas.ascent() - glyph.height as f32 - glyph.offset_y, 0.0);
+        assert_eq!(atlas.get_glyph('█', true).unwrap().width, 16);
+
+        // Redrawn to fit when the cell grows
+        atlas.set_spacing(1.5, 2.0);
+        let glyph = atlas.get_glyph('┼', false).unwrap();
+        assert_eq!((glyph.width, glyph.height), (10, 20));
+        assert_eq!(atlas.ascent() - glyph.height as f32 - glyph.offset_y, 0.0);
+    }
+
+    #[test]
+    fn test_eviction_policies() {
+        let fill = |policy| {
+            let mut atlas = GlyphAtlas::new(get_font_data(BundledFont::default()), 32.0).unwrap();
+            atlas.set_bdf_fallback(get_unifont_fallback_data()).unwrap();
+            atlas.set_eviction_policy(policy);
+            let results: Vec<_> = (0x4E00..0x4E00 + 1000)
+                .map(|cp| {
+                    atlas.begin_frame();
+                    atlas.get_glyph(char::from_u32(cp).unwrap(), true)
+                })
+                .collect();
+            (atlas, results)
+        };
+
+        // LRU never grows, but every glyph still gets a slot
+        let (atlas, results) = fill(AtlasEvictionPolicy::Lru);
+        assert!(results.iter().all(|r| r.is_ok()));
+        assert!(atlas.is_full());
+        assert_eq!(
+            atlas.atlas_dimensions(),
+            (INITIAL_ATLAS_SIZE, INITIAL_ATLAS_SIZE)
+        );
+
+        // Error keeps the first glyphs and rejects the rest
+        let (atlas, results) = fill(AtlasEvictionPolicy::Error);
+        assert!(results[0].is_ok());
+        assert!(matches!(results.last(), Some(Err(AtlasError::AtlasFull))));
+        assert_eq!(
+            atlas.atlas_dimensions(),
+            (INITIAL_ATLAS_SIZE, INITIAL_ATLAS_SIZE)
+        );
+
+        // Grow makes room by growing instead
+        let (atlas, results) = fill(AtlasEvictionPolicy::Grow);
+        assert!(results.iter().all(|r| r.is_ok()));
+        assert!(atlas.atlas_dimensions().0 > INITIAL_ATLAS_SIZE);
     }
 }
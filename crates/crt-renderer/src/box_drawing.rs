@@ -0,0 +1,574 @@
+// ABOUTME: Box-drawing characters and block elements drawn procedurally at the exact cell size.
+// ABOUTME: Lines run to the cell edges so borders in neighbouring cells join without gaps.
+
+/// Line weight of one arm of a box-drawing character
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Weight {
+    None,
+    Light,
+    Heavy,
+    Double,
+}
+
+use Weight::{Double as D, Heavy as H, Light as L, None as N};
+
+/// Arms of U+2500 to U+257F that are plain lines: up, right, down, left.
+/// Dashed lines, arcs and diagonals are drawn separately.
+fn arms(c: char) -> Option<[Weight; 4]> {
+    Some(match c {
+        '─' => [N, L, N, L],
+        '━' => [N, H, N, H],
+        '│' => [L, N, L, N],
+        '┃' => [H, N, H, N],
+        '┌' => [N, L, L, N],
+        '┍' => [N, H, L, N],
+        '┎' => [N, L, H, N],
+        '┏' => [N, H, H, N],
+        '┐' => [N, N, L, L],
+        '┑' => [N, N, L, H],
+        '┒' => [N, N, H, L],
+        '┓' => [N, N, H, H],
+        '└' => [L, L, N, N],
+        '┕' => [L, H, N, N],
+        '┖' => [H, L, N, N],
+        '┗' => [H, H, N, N],
+        '┘' => [L, N, N, L],
+        '┙' => [L, N, N, H],
+        '┚' => [H, N, N, L],
+        '┛' => [H, N, N, H],
+        '├' => [L, L, L, N],
+        '┝' => [L, H, L, N],
+        '┞' => [H, L, L, N],
+        '┟' => [L, L, H, N],
+        '┠' => [H, L, H, N],
+        '┡' => [H, H, L, N],
+        '┢' => [L, H, H, N],
+        '┣' => [H, H, H, N],
+        '┤' => [L, N, L, L],
+        '┥' => [L, N, L, H],
+        '┦' => [H, N, L, L],
+        '┧' => [L, N, H, L],
+        '┨' => [H, N, H, L],
+        '┩' => [H, N, L, H],
+        '┪' => [L, N, H, H],
+        '┫' => [H, N, H, H],
+        '┬' => [N, L, L, L],
+        '┭' => [N, L, L, H],
+        '┮' => [N, H, L, L],
+        '┯' => [N, H, L, H],
+        '┰' => [N, L, H, L],
+        '┱' => [N, L, H, H],
+        '┲' => [N, H, H, L],
+        '┳' => [N, H, H, H],
+        '┴' => [L, L, N, L],
+        '┵' => [L, L, N, H],
+        '┶' => [L, H, N, L],
+        '┷' => [L, H, N, H],
+        '┸' => [H, L, N, L],
+        '┹' => [H, L, N, H],
+        '┺' => [H, H, N, L],
+        '┻' => [H, H, N, H],
+        '┼' => [L, L, L, L],
+        '┽' => [L, L, L, H],
+        '┾' => [L, H, L, L],
+        '┿' => [L, H, L, H],
+        '╀' => [H, L, L, L],
+        '╁' => [L, L, H, L],
+        '╂' => [H, L, H, L],
+        '╃' => [H, L, L, H],
+        '╄' => [H, H, L, L],
+        '╅' => [L, L, H, H],
+        '╆' => [L, H, H, L],
+        '╇' => [H, H, L, H],
+        '╈' => [L, H, H, H],
+        '╉' => [H, L, H, H],
+        '╊' => [H, H, H, L],
+        '╋' => [H, H, H, H],
+        '═' => [N, D, N, D],
+        '║' => [D, N, D, N],
+        '╒' => [N, D, L, N],
+        '╓' => [N, L, D, N],
+        '╔' => [N, D, D, N],
+        '╕' => [N, N, L, D],
+        '╖' => [N, N, D, L],
+        '╗' => [N, N, D, D],
+        '╘' => [L, D, N, N],
+        '╙' => [D, L, N, N],
+        '╚' => [D, D, N, N],
+        '╛' => [L, N, N, D],
+        '╜' => [D, N, N, L],
+        '╝' => [D, N, N, D],
+        '╞' => [L, D, L, N],
+        '╟' => [D, L, D, N],
+        '╠' => [D, D, D, N],
+        '╡' => [L, N, L, D],
+        '╢' => [D, N, D, L],
+        '╣' => [D, N, D, D],
+        '╤' => [N, D, L, D],
+        '╥' => [N, L, D, L],
+        '╦' => [N, D, D, D],
+        '╧' => [L, D, N, D],
+        '╨' => [D, L, N, L],
+        '╩' => [D, D, N, D],
+        '╪' => [L, D, L, D],
+        '╫' => [D, L, D, L],
+        '╬' => [D, D, D, D],
+        '╴' => [N, N, N, L],
+        '╵' => [L, N, N, N],
+        '╶' => [N, L, N, N],
+        '╷' => [N, N, L, N],
+        '╸' => [N, N, N, H],
+        '╹' => [H, N, N, N],
+        '╺' => [N, H, N, N],
+        '╻' => [N, N, H, N],
+        '╼' => [N, H, N, L],
+        '╽' => [L, N, H, N],
+        '╾' => [N, L, N, H],
+        '╿' => [H, N, L, N],
+        _ => return None,
+    })
+}
+
+/// Whether `c` is drawn here instead of taken from a font
+pub fn is_procedural(c: char) -> bool {
+    matches!(c, '\u{2500}'..='\u{259F}')
+}
+
+/// Coverage bitmap of `c` filling a `width` x `height` cell, or None for
+/// characters that aren't drawn here
+pub fn render(c: char, width: usize, height: usize) -> Option<Vec<u8>> {
+    if !is_procedural(c) || width == 0 || height == 0 {
+        return None;
+    }
+    let mut canvas = Canvas::new(width, height);
+    if let Some(arms) = arms(c) {
+        canvas.lines(arms);
+    } else {
+        match c {
+            '┄' => canvas.dashes(false, 3, L),
+            '┅' => canvas.dashes(false, 3, H),
+            '┆' => canvas.dashes(true, 3, L),
+            '┇' => canvas.dashes(true, 3, H),
+            '┈' => canvas.dashes(false, 4, L),
+            '┉' => canvas.dashes(false, 4, H),
+            '┊' => canvas.dashes(true, 4, L),
+            '┋' => canvas.dashes(true, 4, H),
+            '╌' => canvas.dashes(false, 2, L),
+            '╍' => canvas.dashes(false, 2, H),
+            '╎' => canvas.dashes(true, 2, L),
+            '╏' => canvas.dashes(true, 2, H),
+            '╭' => canvas.arc(1.0, 1.0),
+            '╮' => canvas.arc(-1.0, 1.0),
+            '╯' => canvas.arc(-1.0, -1.0),
+            '╰' => canvas.arc(1.0, -1.0),
+            '╱' => canvas.diagonal(true),
+            '╲' => canvas.diagonal(false),
+            '╳' => {
+                canvas.diagonal(true);
+                canvas.diagonal(false);
+            }
+            _ => canvas.block(c),
+        }
+    }
+    Some(canvas.pixels)
+}
+
+struct Canvas {
+    width: i32,
+    height: i32,
+    pixels: Vec<u8>,
+    /// Thickness of a light line; heavy lines are twice this, and the two
+    /// strokes of a double line are this far apart
+    light: i32,
+}
+
+impl Canvas {
+    fn new(width: usize, height: usize) -> Self {
+        let light = ((height as f32 / 16.0).round() as i32).max(1);
+        Self {
+            width: width as i32,
+            height: height as i32,
+            pixels: vec![0; width * height],
+            light,
+        }
+    }
+
+    fn thickness(&self, weight: Weight) -> i32 {
+        match weight {
+            N => 0,
+            L | D => self.light,
+            H => self.light * 2,
+        }
+    }
+
+    /// Fill x0..x1 by y0..y1 with `alpha`, clipped to the canvas
+    fn fill(&mut self, x: (i32, i32), y: (i32, i32), alpha: u8) {
+        for py in y.0.max(0)..y.1.min(self.height) {
+            for px in x.0.max(0)..x.1.min(self.width) {
+                self.pixels[(py * self.width + px) as usize] = alpha;
+            }
+        }
+    }
+
+    /// Raise one pixel's coverage to `coverage` (0 to 1)
+    fn plot(&mut self, px: i32, py: i32, coverage: f32) {
+        if px < 0 || py < 0 || px >= self.width || py >= self.height {
+            return;
+        }
+        let pixel = &mut self.pixels[(py * self.width + px) as usize];
+        *pixel = (*pixel).max((coverage.clamp(0.0, 1.0) * 255.0).round() as u8);
+    }
+
+    /// Straight arms from the middle of the cell to its edges
+    fn lines(&mut self, arms: [Weight; 4]) {
+        let [up, right, down, left] = arms;
+        // Each arm: its weight, the arm opposite it, the arms either side of
+        // it (on the negative side first) and whether it runs toward the
+        // bottom/right edge
+        for (weight, back, sides, positive, vertical) in [
+            (up, down, [left, right], false, true),
+            (right, left, [up, down], true, false),
+            (down, up, [left, right], true, true),
+            (left, right, [up, down], false, false),
+        ] {
+            if weight != N {
+                self.arm(weight, back, sides, positive, vertical);
+            }
+        }
+    }
+
+    fn arm(
+        &mut self,
+        weight: Weight,
+        back: Weight,
+        sides: [Weight; 2],
+        positive: bool,
+        vertical: bool,
+    ) {
+        let (along, across) = if vertical {
+            (self.height, self.width)
+        } else {
+            (self.width, self.height)
+        };
+        let sign = if positive { 1 } else { -1 };
+        let gap = self.light;
+        let light = self.light;
+        // The near or far edge of a band of lines running across this arm,
+        // whichever faces the arm
+        let edge = |thickness: i32, offset: i32| {
+            let start = (along - thickness) / 2 + offset;
+            if positive {
+                start
+            } else {
+                start + thickness
+            }
+        };
+        let double_side = sides.contains(&D);
+
+        // Where each stroke leaves the middle of the cell, and its offset
+        // across the arm
+        let strokes: Vec<(i32, i32)> = if weight == D {
+            [(0, -gap), (1, gap)]
+                .into_iter()
+                .map(|(side, offset)| {
+                    let near = sides[side];
+                    let far = sides[1 - side];
+                    let start = if near == D {
+                        edge(light, sign * gap)
+                    } else if near != N {
+                        edge(light, 0)
+                    } else if back != N {
+                        along / 2
+                    } else if far == D {
+                        edge(light, -sign * gap)
+                    } else if far != N {
+                        edge(light, 0)
+                    } else {
+                        along / 2
+                    };
+                    (start, offset)
+                })
+                .collect()
+        } else {
+            let start = if double_side {
+                if back != N {
+                    along / 2
+                } else {
+                    edge(light, sign * gap)
+                }
+            } else {
+                let crossing = sides
+                    .iter()
+                    .map(|&side| self.thickness(side))
+                    .max()
+                    .unwrap_or(0);
+                if crossing > 0 {
+                    edge(crossing, 0)
+                } else {
+                    along / 2
+                }
+            };
+            vec![(start, 0)]
+        };
+
+        let thickness = self.thickness(weight);
+        for (start, offset) in strokes {
+            let span = if positive { (start, along) } else { (0, start) };
+            let band_start = (across - thickness) / 2 + offset;
+            let band = (band_start, band_start + thickness);
+            if vertical {
+                self.fill(band, span, 255);
+            } else {
+                self.fill(span, band, 255);
+            }
+        }
+    }
+
+    /// A line broken into `count` dashes, one centered in each equal part
+    fn dashes(&mut self, vertical: bool, count: i32, weight: Weight) {
+        let (along, across) = if vertical {
+            (self.height, self.width)
+        } else {
+            (self.width, self.height)
+        };
+        let thickness = self.thickness(weight);
+        let band_start = (across - thickness) / 2;
+        let band = (band_start, band_start + thickness);
+        for i in 0..count {
+            let start = i * along / count;
+            let end = (i + 1) * along / count;
+            let gap = ((end - start) / 2).max(1);
+            let span = (start + gap / 2, end - (gap - gap / 2));
+            if vertical {
+                self.fill(band, span, 255);
+            } else {
+                self.fill(span, band, 255);
+            }
+        }
+    }
+
+    /// Rounded corner joining the middle of the edge `sx` points at (right
+    /// for 1.0) to the middle of the edge `sy` points at (down for 1.0)
+    fn arc(&mut self, sx: f32, sy: f32) {
+        let t = self.light;
+        let band_x = (self.width - t) / 2;
+        let band_y = (self.height - t) / 2;
+        let cx = band_x as f32 + t as f32 / 2.0;
+        let cy = band_y as f32 + t as f32 / 2.0;
+        let room_x = if sx > 0.0 { self.width as f32 - cx } else { cx };
+        let room_y = if sy > 0.0 {
+            self.height as f32 - cy
+        } else {
+            cy
+        };
+        let radius = room_x.min(room_y);
+        let center = (cx + sx * radius, cy + sy * radius);
+
+        // Straight runs from where the arc ends to the edges
+        let center_x = center.0.round() as i32;
+        let center_y = center.1.round() as i32;
+        let x_span = if sx > 0.0 {
+            (center_x, self.width)
+        } else {
+            (0, center_x)
+        };
+        let y_span = if sy > 0.0 {
+            (center_y, self.height)
+        } else {
+            (0, center_y)
+        };
+        self.fill(x_span, (band_y, band_y + t), 255);
+        self.fill((band_x, band_x + t), y_span, 255);
+
+        // The quarter circle, antialiased
+        let half = t as f32 / 2.0;
+        for py in 0..self.height {
+            for px in 0..self.width {
+                let (x, y) = (px as f32 + 0.5, py as f32 + 0.5);
+                if (x - center.0) * sx > 0.0 || (y - center.1) * sy > 0.0 {
+                    continue;
+                }
+                let distance = ((x - center.0).powi(2) + (y - center.1).powi(2)).sqrt();
+                self.plot(px, py, half + 0.5 - (distance - radius).abs());
+            }
+        }
+    }
+
+    /// Corner to corner line, rising to the right when `rising`
+    fn diagonal(&mut self, rising: bool) {
+        let (w, h) = (self.width as f32, self.height as f32);
+        let length = (w * w + h * h).sqrt();
+        let half = self.light as f32 / 2.0;
+        for py in 0..self.height {
+            for px in 0..self.width {
+                let x = px as f32 + 0.5;
+                let y = py as f32 + 0.5;
+                let y = if rising { h - y } else { y };
+                // Distance from the line through (0, 0) and (w, h)
+                let distance = (x * h - y * w).abs() / length;
+                self.plot(px, py, half + 0.5 - distance);
+            }
+        }
+    }
+
+    /// Block elements and shades, U+2580 to U+259F
+    fn block(&mut self, c: char) {
+        let (w, h) = (self.width, self.height);
+        let eighths_down = |n: i32| (h * n + 4) / 8;
+        let eighths_across = |n: i32| (w * n + 4) / 8;
+        let (half_w, half_h) = (w / 2, h / 2);
+        match c {
+            '▀' => self.fill((0, w), (0, half_h), 255),
+            '▁'..='▇' => {
+                let n = c as i32 - '▀' as i32;
+                self.fill((0, w), (h - eighths_down(n), h), 255);
+            }
+            '█' => self.fill((0, w), (0, h), 255),
+            '▉'..='▏' => {
+                let n = '▐' as i32 - c as i32;
+                self.fill((0, eighths_across(n)), (0, h), 255);
+            }
+            '▐' => self.fill((half_w, w), (0, h), 255),
+            '░' => self.fill((0, w), (0, h), 64),
+            '▒' => self.fill((0, w), (0, h), 128),
+            '▓' => self.fill((0, w), (0, h), 192),
+            '▔' => self.fill((0, w), (0, eighths_down(1)), 255),
+            '▕' => self.fill((w - eighths_across(1), w), (0, h), 255),
+            _ => {
+                // Quadrants: upper left, upper right, lower left, lower right
+                let quadrants = match c {
+                    '▖' => [false, false, true, false],
+                    '▗' => [false, false, false, true],
+                    '▘' => [true, false, false, false],
+                    '▙' => [true, false, true, true],
+                    '▚' => [true, false, false, true],
+                    '▛' => [true, true, true, false],
+                    '▜' => [true, true, false, true],
+                    '▝' => [false, true, false, false],
+                    '▞' => [false, true, true, false],
+                    '▟' => [false, true, true, true],
+                    _ => [false; 4],
+                };
+                let spans = [
+                    ((0, half_w), (0, half_h)),
+                    ((half_w, w), (0, half_h)),
+                    ((0, half_w), (half_h, h)),
+                    ((half_w, w), (half_h, h)),
+                ];
+                for (on, (x, y)) in quadrants.into_iter().zip(spans) {
+                    if on {
+                        self.fill(x, y, 255);
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const W: usize = 10;
+    const H: usize = 20;
+
+    fn at(bitmap: &[u8], x: usize, y: usize) -> u8 {
+        bitmap[y * W + x]
+    }
+
+    fn row_filled(bitmap: &[u8], y: usize) -> bool {
+        (0..W).all(|x| at(bitmap, x, y) == 255)
+    }
+
+    fn column_filled(bitmap: &[u8], x: usize) -> bool {
+        (0..H).all(|y| at(bitmap, x, y) == 255)
+    }
+
+    #[test]
+    fn only_box_and_block_ranges_are_drawn() {
+        assert!(is_procedural('─'));
+        assert!(is_procedural('▟'));
+        assert!(!is_procedural('a'));
+        assert!(!is_procedural('\u{25A0}'));
+        assert_eq!(render('a', W, H), None);
+        for c in '\u{2500}'..='\u{259F}' {
+            let bitmap = render(c, W, H).unwrap();
+            assert_eq!(bitmap.len(), W * H);
+            assert!(bitmap.iter().any(|&a| a > 0), "{:?} is blank", c);
+        }
+    }
+
+    #[test]
+    fn lines_reach_the_cell_edges() {
+        let horizontal = render('─', W, H).unwrap();
+        let rows: Vec<_> = (0..H).filter(|&y| row_filled(&horizontal, y)).collect();
+        assert!(!rows.is_empty());
+        let vertical = render('│', W, H).unwrap();
+        let columns: Vec<_> = (0..W).filter(|&x| column_filled(&vertical, x)).collect();
+        assert!(!columns.is_empty());
+
+        // A cross uses the same rows and columns, so it joins both
+        let cross = render('┼', W, H).unwrap();
+        assert!(rows.iter().all(|&y| row_filled(&cross, y)));
+        assert!(columns.iter().all(|&x| column_filled(&cross, x)));
+
+        // Heavy lines are thicker and still centered on the same rows
+        let heavy = render('━', W, H).unwrap();
+        let heavy_rows: Vec<_> = (0..H).filter(|&y| row_filled(&heavy, y)).collect();
+        assert!(heavy_rows.len() > rows.len());
+        assert!(rows.iter().all(|y| heavy_rows.contains(y)));
+    }
+
+    #[test]
+    fn corners_join_their_arms() {
+        let corner = render('┌', W, H).unwrap();
+        let horizontal = render('─', W, H).unwrap();
+        let vertical = render('│', W, H).unwrap();
+        // Right edge matches the horizontal line, bottom edge the vertical one
+        for y in 0..H {
+            assert_eq!(at(&corner, W - 1, y), at(&horizontal, W - 1, y));
+        }
+        for x in 0..W {
+            assert_eq!(at(&corner, x, H - 1), at(&vertical, x, H - 1));
+        }
+        // Nothing above or left of the corner
+        assert!((0..W).all(|x| at(&corner, x, 0) == 0));
+        assert!((0..H).all(|y| at(&corner, 0, y) == 0));
+    }
+
+    #[test]
+    fn double_lines_have_two_strokes() {
+        let double = render('═', W, H).unwrap();
+        let rows: Vec<_> = (0..H).filter(|&y| row_filled(&double, y)).collect();
+        assert_eq!(rows.len(), 2);
+        assert!(rows[1] - rows[0] > 1, "strokes should have a gap");
+
+        // A double corner's outer stroke meets the edges like the line does
+        let corner = render('╔', W, H).unwrap();
+        for &y in &rows {
+            assert_eq!(at(&corner, W - 1, y), 255);
+        }
+    }
+
+    #[test]
+    fn blocks_fill_their_share_of_the_cell() {
+        let coverage = |c| {
+            render(c, W, H)
+                .unwrap()
+                .iter()
+                .map(|&a| a as usize)
+                .sum::<usize>()
+        };
+        let full = W * H * 255;
+        assert_eq!(coverage('█'), full);
+        assert_eq!(coverage('▄'), full / 2);
+        assert_eq!(coverage('▌'), full / 2);
+        assert_eq!(coverage('▖'), full / 4);
+        assert_eq!(coverage('▏'), H * ((W + 4) / 8) * 255);
+        assert_eq!(coverage('▁'), W * ((H + 4) / 8) * 255);
+        let lower = render('▄', W, H).unwrap();
+        assert!(row_filled(&lower, H - 1) && !row_filled(&lower, 0));
+        let shade = render('▒', W, H).unwrap();
+        assert!(shade.iter().all(|&a| a == 128));
+    }
+}
@@ -4,6 +4,12 @@
 use bytemuck::{Pod, Zeroable};
 use wgpu::util::DeviceExt;
 
+#[cfg(any(debug_assertions, feature = "shader-reload"))]
+use crate::shader_reload::ShaderWatcher;
+use crate::shader_reload::{fullscreen_pipeline, shader_source};
+
+const SHADER_FILE: &str = "burnin.wgsl";
+
 #[repr(C)]
 #[derive(Copy, Clone, Debug, Pod, Zeroable)]
 struct BurnInUniforms {
@@ -20,6 +26,8 @@ struct BurnInUniforms {
 
 pub struct BurnInPipeline {
     pipeline: wgpu::RenderPipeline,
+    #[cfg(any(debug_assertions, feature = "shader-reload"))]
+    pipeline_layout: wgpu::PipelineLayout,
     bind_group_layout: wgpu::BindGroupLayout,
     uniform_buffer: wgpu::Buffer,
     sampler: wgpu::Sampler,
@@ -28,6 +36,8 @@ pub struct BurnInPipeline {
     views: [wgpu::TextureView; 2],
     current_target: usize, // Which texture to write to (0 or 1)
     bind_groups: [Option<wgpu::BindGroup>; 2],
+    #[cfg(any(debug_assertions, feature = "shader-reload"))]
+    watcher: Option<ShaderWatcher>,
 }
 
 impl BurnInPipeline {
@@ -39,7 +49,10 @@ impl BurnInPipeline {
     ) -> Self {
         let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
             label: Some("Burn-in Shader"),
-            source: wgpu::ShaderSource::Wgsl(include_str!("../../../shaders/burnin.wgsl").into()),
+            source: wgpu::ShaderSource::Wgsl(shader_source(
+                SHADER_FILE,
+                include_str!("../../../shaders/burnin.wgsl"),
+            )),
         });
 
         let uniform_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
@@ -119,45 +132,24 @@ impl BurnInPipeline {
             push_constant_ranges: &[],
         });
 
-        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
-            label: Some("Burn-in Pipeline"),
-            layout: Some(&pipeline_layout),
-            vertex: wgpu::VertexState {
-                module: &shader,
-                entry_point: Some("vs_main"),
-                buffers: &[],
-                compilation_options: wgpu::PipelineCompilationOptions::default(),
-            },
-            fragment: Some(wgpu::FragmentState {
-                module: &shader,
-                entry_point: Some("fs_main"),
-                targets: &[Some(wgpu::ColorTargetState {
-                    format,
-                    blend: None,
-                    write_mask: wgpu::ColorWrites::ALL,
-                })],
-                compilation_options: wgpu::PipelineCompilationOptions::default(),
-            }),
-            primitive: wgpu::PrimitiveState {
-                topology: wgpu::PrimitiveTopology::TriangleList,
-                strip_index_format: None,
-                front_face: wgpu::FrontFace::Ccw,
-                cull_mode: None,
-                polygon_mode: wgpu::PolygonMode::Fill,
-                unclipped_depth: false,
-                conservative: false,
-            },
-            depth_stencil: None,
-            multisample: wgpu::MultisampleState::default(),
-            multiview: None,
-            cache: None,
-        });
+        let pipeline = fullscreen_pipeline(
+            device,
+            "Burn-in Pipeline",
+            &pipeline_layout,
+            &shader,
+            format,
+        );
+
+        #[cfg(any(debug_assertions, feature = "shader-reload"))]
+        let watcher = ShaderWatcher::new(SHADER_FILE);
 
         // Create ping-pong textures
         let (textures, views) = Self::create_textures(device, format, width, height);
 
         Self {
             pipeline,
+            #[cfg(any(debug_assertions, feature = "shader-reload"))]
+            pipeline_layout,
             bind_group_layout,
             uniform_buffer,
             sampler,
@@ -165,6 +157,23 @@ impl BurnInPipeline {
             views,
             current_target: 0,
             bind_groups: [None, None],
+            #[cfg(any(debug_assertions, feature = "shader-reload"))]
+            watcher,
+        }
+    }
+
+    /// Rebuild the pipeline if `shaders/burnin.wgsl` was edited, keeping the
+    /// old one on a compile error
+    #[cfg(any(debug_assertions, feature = "shader-reload"))]
+    pub fn maybe_reload(&mut self, device: &wgpu::Device, format: wgpu::TextureFormat) {
+        let Some(watcher) = &self.watcher else {
+            return;
+        };
+        let layout = &self.pipeline_layout;
+        if let Some(pipeline) = watcher.reload(device, |shader| {
+            fullscreen_pipeline(device, "Burn-in Pipeline", layout, shader, format)
+        }) {
+            self.pipeline = pipeline;
         }
     }
 
@@ -5,8 +5,65 @@
 use bytemuck::{Pod, Zeroable};
 use wgpu::util::DeviceExt;
 
+#[cfg(any(debug_assertions, feature = "shader-reload"))]
+use crate::shader_reload::ShaderWatcher;
+use crate::shader_reload::{fullscreen_pipeline, shader_source};
+
 const MAX_PANES: usize = 16;
 
+const SHADER_FILE: &str = "crt.wgsl";
+
+/// Length of the power-on animation in seconds (POWERON_TOTAL in crt.wgsl)
+const POWER_ON_DURATION: f64 = 1.05;
+
+/// Shader time wraps after this many seconds to keep f32 noise precise
+const TIME_WRAP: f64 = 600.0;
+/// Wrapped time restarts here, safely past the power-on animation
+const TIME_WRAP_RESTART: f64 = 2.0;
+
+/// Animation clock for time-varying CRT effects.
+///
+/// Accumulates wall-clock frame deltas in f64, so flicker, noise and drift
+/// advance at the same speed regardless of frame rate or dropped frames.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct AnimationClock {
+    seconds: f64,
+}
+
+impl AnimationClock {
+    /// Advance by `dt` wall-clock seconds. A paused clock stands still.
+    pub fn advance(&mut self, dt: f32, paused: bool) {
+        if paused || !dt.is_finite() || dt <= 0.0 {
+            return;
+        }
+        self.seconds += dt as f64;
+    }
+
+    /// Restart from zero (replays the power-on animation)
+    pub fn reset(&mut self) {
+        self.seconds = 0.0;
+    }
+
+    /// Seconds elapsed since the last reset
+    pub fn seconds(&self) -> f64 {
+        self.seconds
+    }
+
+    /// Time value for the shader, wrapped to avoid float precision loss
+    pub fn shader_time(&self) -> f32 {
+        if self.seconds <= TIME_WRAP {
+            self.seconds as f32
+        } else {
+            let period = TIME_WRAP - TIME_WRAP_RESTART;
+            (TIME_WRAP_RESTART + (self.seconds - TIME_WRAP_RESTART) % period) as f32
+        }
+    }
+
+    pub fn power_on_active(&self) -> bool {
+        self.seconds <= POWER_ON_DURATION
+    }
+}
+
 // Embedded bezel image
 const BEZEL_IMAGE_BYTES: &[u8] = include_bytes!("../../../fallout.png");
 
@@ -50,31 +107,51 @@ struct CrtUniforms {
     // Content scale - adjusts how big the content is drawn (like H-SIZE/V-SIZE knobs)
     content_scale_x: f32,
     content_scale_y: f32,
-    // Cell height for scanline alignment (one scanline per text row)
+    // Cell height for scanline alignment (one scanline per text row) and how
+    // much brightness shields pixels from scanline darkening
     cell_height: f32,
-    _pad1: f32, // Padding for vec4 alignment
-    // Focus glow color (follows font color) - uses vec4 for alignment (w ignored)
+    scanline_gamma: f32,
+    // Focus glow color (font color or custom tint); w = bloom tint amount
     glow_color: [f32; 4],
+    // RGB phosphor mask strength, chromatic aberration (pixels), rounded
+    // corner radius (fraction of the shorter side) and composite chroma bleed
+    mask_strength: f32,
+    aberration: f32,
+    corner_radius: f32,
+    composite_bleed: f32,
+    // Visual bell flash color; w = current strength (0 = no flash)
+    flash_color: [f32; 4],
+    // Window background color; w = its opacity (1 = opaque)
+    background: [f32; 4],
+    // RGB multiplier for the color temperature; w unused
+    white_point: [f32; 4],
     // Pane rects (max 16 panes)
     panes: [PaneRect; MAX_PANES],
 }
 
 pub struct CrtPipeline {
     pipeline: wgpu::RenderPipeline,
+    #[cfg(any(debug_assertions, feature = "shader-reload"))]
+    pipeline_layout: wgpu::PipelineLayout,
     bind_group_layout: wgpu::BindGroupLayout,
     uniform_buffer: wgpu::Buffer,
     sampler: wgpu::Sampler,
     #[allow(dead_code)] // Kept alive for bezel_view
     bezel_texture: wgpu::Texture,
     bezel_view: wgpu::TextureView,
-    time: f32,
+    clock: AnimationClock,
+    #[cfg(any(debug_assertions, feature = "shader-reload"))]
+    watcher: Option<ShaderWatcher>,
 }
 
 impl CrtPipeline {
     pub fn new(device: &wgpu::Device, queue: &wgpu::Queue, format: wgpu::TextureFormat) -> Self {
         let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
             label: Some("CRT Shader"),
-            source: wgpu::ShaderSource::Wgsl(include_str!("../../../shaders/crt.wgsl").into()),
+            source: wgpu::ShaderSource::Wgsl(shader_source(
+                SHADER_FILE,
+                include_str!("../../../shaders/crt.wgsl"),
+            )),
         });
 
         // Load bezel image
@@ -148,8 +225,15 @@ impl CrtPipeline {
                 content_scale_x: 1.0,
                 content_scale_y: 1.0,
                 cell_height: 18.0, // Default font size
-                _pad1: 0.0,
-                glow_color: [1.0, 0.7, 0.0, 1.0], // Default amber
+                scanline_gamma: 0.0,
+                glow_color: [1.0, 0.7, 0.0, 0.0], // Default amber, bloom untinted
+                mask_strength: 0.0,
+                aberration: 0.0,
+                corner_radius: 0.0,
+                composite_bleed: 0.0,
+                flash_color: [0.0; 4],
+                background: [0.0, 0.0, 0.0, 1.0],
+                white_point: [1.0; 4],
                 panes: [PaneRect {
                     x: 0.0,
                     y: 0.0,
@@ -219,48 +303,45 @@ impl CrtPipeline {
             push_constant_ranges: &[],
         });
 
-        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
-            label: Some("CRT Pipeline"),
-            layout: Some(&pipeline_layout),
-            vertex: wgpu::VertexState {
-                module: &shader,
-                entry_point: Some("vs_main"),
-                buffers: &[],
-                compilation_options: wgpu::PipelineCompilationOptions::default(),
-            },
-            fragment: Some(wgpu::FragmentState {
-                module: &shader,
-                entry_point: Some("fs_main"),
-                targets: &[Some(wgpu::ColorTargetState {
-                    format,
-                    blend: None,
-                    write_mask: wgpu::ColorWrites::ALL,
-                })],
-                compilation_options: wgpu::PipelineCompilationOptions::default(),
-            }),
-            primitive: wgpu::PrimitiveState {
-                topology: wgpu::PrimitiveTopology::TriangleList,
-                strip_index_format: None,
-                front_face: wgpu::FrontFace::Ccw,
-                cull_mode: None,
-                polygon_mode: wgpu::PolygonMode::Fill,
-                unclipped_depth: false,
-                conservative: false,
-            },
-            depth_stencil: None,
-            multisample: wgpu::MultisampleState::default(),
-            multiview: None,
-            cache: None,
-        });
+        let pipeline =
+            fullscreen_pipeline(device, "CRT Pipeline", &pipeline_layout, &shader, format);
+
+        #[cfg(any(debug_assertions, feature = "shader-reload"))]
+        let watcher = ShaderWatcher::new(SHADER_FILE);
 
         Self {
             pipeline,
+            #[cfg(any(debug_assertions, feature = "shader-reload"))]
+            pipeline_layout,
             bind_group_layout,
             uniform_buffer,
             sampler,
             bezel_texture,
             bezel_view,
-            time: 0.0,
+            clock: AnimationClock::default(),
+            #[cfg(any(debug_assertions, feature = "shader-reload"))]
+            watcher,
+        }
+    }
+
+    /// Rebuild the pipeline if `shaders/crt.wgsl` was edited. On a compile
+    /// error the old pipeline is kept. The uniform layout can't change
+    /// without a rebuild, so edits are limited to shader code.
+    #[cfg(any(debug_assertions, feature = "shader-reload"))]
+    pub fn maybe_reload(
+        &mut self,
+        device: &wgpu::Device,
+        _queue: &wgpu::Queue,
+        format: wgpu::TextureFormat,
+    ) {
+        let Some(watcher) = &self.watcher else {
+            return;
+        };
+        let layout = &self.pipeline_layout;
+        if let Some(pipeline) = watcher.reload(device, |shader| {
+            fullscreen_pipeline(device, "CRT Pipeline", layout, shader, format)
+        }) {
+            self.pipeline = pipeline;
         }
     }
 
@@ -305,6 +386,7 @@ impl CrtPipeline {
         width: f32,
         height: f32,
         dt: f32,
+        paused: bool,
         per_pane_mode: bool,
         pane_rects: &[(f32, f32, f32, f32)],
         focused_pane: i32,
@@ -312,6 +394,7 @@ impl CrtPipeline {
         curvature: f32,
         scanline_intensity: f32,
         scanline_mode: u32,
+        scanline_gamma: f32,
         bloom_intensity: f32,
         focus_glow_radius: f32,
         focus_glow_width: f32,
@@ -320,17 +403,19 @@ impl CrtPipeline {
         flicker: f32,
         brightness: f32,
         vignette: f32,
+        mask_strength: f32,
+        aberration: f32,
+        corner_radius: f32,
+        composite_bleed: f32,
         bezel_enabled: bool,
         content_scale_x: f32,
         content_scale_y: f32,
         glow_color: [f32; 4],
+        flash_color: [f32; 4],
+        background: [f32; 4],
+        white_point: [f32; 3],
     ) {
-        self.time += dt;
-        // Wrap time to prevent float precision loss (keeps noise working)
-        // Power-on animation completes at ~1.05s, so wrap after 600s back to 2.0s
-        if self.time > 600.0 {
-            self.time = 2.0;
-        }
+        self.clock.advance(dt, paused);
 
         let mut panes = [PaneRect {
             x: 0.0,
@@ -349,7 +434,7 @@ impl CrtPipeline {
             0,
             bytemuck::cast_slice(&[CrtUniforms {
                 screen_size: [width, height],
-                time: self.time,
+                time: self.clock.shader_time(),
                 curvature,
                 scanline_intensity,
                 bloom_intensity,
@@ -373,8 +458,15 @@ impl CrtPipeline {
                 content_scale_x,
                 content_scale_y,
                 cell_height,
-                _pad1: 0.0,
+                scanline_gamma,
                 glow_color,
+                mask_strength,
+                aberration,
+                corner_radius,
+                composite_bleed,
+                flash_color,
+                background,
+                white_point: [white_point[0], white_point[1], white_point[2], 1.0],
                 panes,
             }]),
         );
@@ -382,7 +474,17 @@ impl CrtPipeline {
 
     /// Reset the time to replay the power-on animation
     pub fn reset_time(&mut self) {
-        self.time = 0.0;
+        self.clock.reset();
+    }
+
+    /// Whether the power-on animation is still playing
+    pub fn power_on_active(&self) -> bool {
+        self.clock.power_on_active()
+    }
+
+    /// Seconds of animation time elapsed, for effects driven outside the shader
+    pub fn time(&self) -> f64 {
+        self.clock.seconds()
     }
 
     pub fn render<'a>(
@@ -395,3 +497,53 @@ impl CrtPipeline {
         render_pass.draw(0..3, 0..1); // Fullscreen triangle
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_clock_irregular_dt_matches_wall_clock() {
+        let mut steady = AnimationClock::default();
+        for _ in 0..240 {
+            steady.advance(1.0 / 240.0, false);
+        }
+
+        // Same second of wall-clock time with jitter and a dropped frame
+        let mut irregular = AnimationClock::default();
+        for dt in [0.004, 0.021, 0.0005, 0.25, 0.0125, 0.1, 0.312, 0.3, 0.0] {
+            irregular.advance(dt, false);
+        }
+
+        assert!((steady.seconds() - 1.0).abs() < 1e-6);
+        assert!((irregular.seconds() - 1.0).abs() < 1e-6);
+        assert!((steady.shader_time() - irregular.shader_time()).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_clock_pause_and_invalid_dt() {
+        let mut clock = AnimationClock::default();
+        clock.advance(0.5, false);
+        clock.advance(0.5, true);
+        clock.advance(-1.0, false);
+        clock.advance(f32::NAN, false);
+        assert_eq!(clock.seconds(), 0.5);
+        assert!(clock.power_on_active());
+
+        clock.advance(1.0, false);
+        assert!(!clock.power_on_active());
+        clock.reset();
+        assert_eq!(clock.seconds(), 0.0);
+    }
+
+    #[test]
+    fn test_clock_wraps_continuously() {
+        let mut clock = AnimationClock::default();
+        clock.advance(TIME_WRAP as f32, false);
+        assert_eq!(clock.shader_time(), TIME_WRAP as f32);
+
+        clock.advance(1.5, false);
+        let wrapped = clock.shader_time();
+        assert!((wrapped - (TIME_WRAP_RESTART as f32 + 1.5)).abs() < 1e-3);
+    }
+}
@@ -1,7 +1,10 @@
 // ABOUTME: Embedded font data for bundled fonts.
 // ABOUTME: All fonts are compiled into the binary for easy distribution.
 
-use crt_core::{BdfFont, Font};
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+use crt_core::{BdfFont, FallbackFont, Font};
 
 // Embed all TTF fonts at compile time
 static IBM_VGA: &[u8] = include_bytes!("../../../assets/fonts/1985-ibm-pc-vga/PxPlus_IBM_VGA8.ttf");
@@ -68,22 +71,118 @@ pub fn get_font_data(font: Font) -> &'static [u8] {
     }
 }
 
-/// Get fallback font data for characters missing from the primary font.
-/// Returns the rectangular (tall) fallback font - Hack.
-pub fn get_fallback_font_data() -> &'static [u8] {
-    FALLBACK_HACK
+/// Get the data for a TTF fallback font. Bundled fonts are always
+/// available; the Nerd Font symbols are looked up on the system and may be
+/// missing.
+pub fn get_fallback_font_data(font: FallbackFont) -> Option<&'static [u8]> {
+    match font {
+        FallbackFont::Hack => Some(FALLBACK_HACK),
+        FallbackFont::Symbols => Some(FALLBACK_SYMBOLS),
+        FallbackFont::Emoji => Some(FALLBACK_EMOJI),
+        FallbackFont::NerdSymbols => system_nerd_symbols_data(),
+    }
+}
+
+/// File name prefixes of the Nerd Font symbol-only faces, preferred first.
+/// The Mono variant keeps icons within a single cell.
+const NERD_SYMBOLS_FILES: &[&str] = &["SymbolsNerdFontMono-Regular", "SymbolsNerdFont-Regular"];
+
+/// Find and load the Symbols Nerd Font from the system font directories.
+/// The search runs once; the result is kept for the life of the process.
+fn system_nerd_symbols_data() -> Option<&'static [u8]> {
+    static DATA: OnceLock<Option<Vec<u8>>> = OnceLock::new();
+    DATA.get_or_init(|| {
+        let path = NERD_SYMBOLS_FILES.iter().find_map(|name| {
+            font_dirs()
+                .iter()
+                .find_map(|dir| find_font_file(dir, name, 3))
+        })?;
+        match std::fs::read(&path) {
+            Ok(data) => {
+                tracing::info!("Found Nerd Font symbols at {}", path.display());
+                Some(data)
+            }
+            Err(e) => {
+                tracing::warn!("Failed to read {}: {}", path.display(), e);
+                None
+            }
+        }
+    })
+    .as_deref()
+}
+
+/// Directories fonts are commonly installed to on this platform
+fn font_dirs() -> Vec<PathBuf> {
+    let mut dirs: Vec<PathBuf> = dirs::font_dir().into_iter().collect();
+    if let Some(data) = dirs::data_dir() {
+        dirs.push(data.join("fonts"));
+    }
+    if let Some(home) = dirs::home_dir() {
+        dirs.push(home.join(".fonts"));
+    }
+    if cfg!(target_os = "macos") {
+        dirs.push(PathBuf::from("/Library/Fonts"));
+    } else if cfg!(windows) {
+        if let Some(windir) = std::env::var_os("WINDIR") {
+            dirs.push(PathBuf::from(windir).join("Fonts"));
+        }
+    } else {
+        dirs.push(PathBuf::from("/usr/local/share/fonts"));
+        dirs.push(PathBuf::from("/usr/share/fonts"));
+    }
+    dirs
 }
 
-/// Get symbols fallback font data for technical symbols.
-/// Returns Noto Sans Symbols.
-pub fn get_symbols_fallback_font_data() -> &'static [u8] {
-    FALLBACK_SYMBOLS
+/// Search `dir` (up to `depth` levels deep) for a .ttf/.otf whose file name
+/// starts with `prefix`
+fn find_font_file(dir: &Path, prefix: &str, depth: u32) -> Option<PathBuf> {
+    let mut subdirs = Vec::new();
+    for entry in std::fs::read_dir(dir).ok()?.flatten() {
+        let path = entry.path();
+        if path.is_dir() {
+            subdirs.push(path);
+            continue;
+        }
+        let name = entry.file_name();
+        let name = name.to_string_lossy();
+        let is_font = matches!(
+            path.extension().and_then(|e| e.to_str()),
+            Some("ttf" | "otf" | "TTF" | "OTF")
+        );
+        if is_font && name.starts_with(prefix) {
+            return Some(path);
+        }
+    }
+    if depth == 0 {
+        return None;
+    }
+    subdirs
+        .iter()
+        .find_map(|sub| find_font_file(sub, prefix, depth - 1))
 }
 
-/// Get emoji fallback font data for emoji characters.
-/// Returns Noto Emoji (monochrome).
-pub fn get_emoji_fallback_font_data() -> &'static [u8] {
-    FALLBACK_EMOJI
+/// BDF files in `dir` and its subdirectories (up to `depth` levels deep),
+/// sorted by path
+pub fn find_bdf_fonts(dir: &Path, depth: u32) -> Vec<PathBuf> {
+    let mut fonts = Vec::new();
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return fonts;
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.is_dir() {
+            if depth > 0 {
+                fonts.extend(find_bdf_fonts(&path, depth - 1));
+            }
+        } else if path
+            .extension()
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("bdf"))
+        {
+            fonts.push(path);
+        }
+    }
+    fonts.sort();
+    fonts
 }
 
 /// Get Unifont BDF data for comprehensive Unicode coverage.
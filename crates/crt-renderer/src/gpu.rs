@@ -1,20 +1,27 @@
 // ABOUTME: Low-level wgpu initialization and state management.
 // ABOUTME: Handles device, queue, surface, and swap chain configuration.
 
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 use wgpu::{Device, Queue, Surface, SurfaceConfiguration};
 use winit::window::Window;
 
+use crate::renderer::RenderError;
+
 pub struct GpuState {
     pub surface: Surface<'static>,
     pub device: Device,
     pub queue: Queue,
     pub config: SurfaceConfiguration,
     pub size: (u32, u32),
+    /// Whether the compositor blends the surface's alpha with the desktop
+    pub transparent: bool,
+    /// Set by the driver when the device stops working
+    lost: Arc<AtomicBool>,
 }
 
 impl GpuState {
-    pub async fn new(window: Arc<Window>) -> Result<Self, wgpu::CreateSurfaceError> {
+    pub async fn new(window: Arc<Window>) -> Result<Self, RenderError> {
         let size = window.inner_size();
         let instance = wgpu::Instance::new(wgpu::InstanceDescriptor {
             backends: wgpu::Backends::all(),
@@ -30,20 +37,35 @@ impl GpuState {
                 force_fallback_adapter: false,
             })
             .await
-            .expect("Failed to find an appropriate adapter");
+            .ok_or(RenderError::NoAdapter)?;
+
+        // Enable block-compressed textures (glyph atlas) and timestamp queries
+        // (pass timings in the debug overlay) where available
+        let required_features = adapter.features()
+            & (wgpu::Features::TEXTURE_COMPRESSION_BC | wgpu::Features::TIMESTAMP_QUERY);
 
         let (device, queue) = adapter
             .request_device(
                 &wgpu::DeviceDescriptor {
                     label: Some("Main Device"),
-                    required_features: wgpu::Features::empty(),
+                    required_features,
                     required_limits: wgpu::Limits::default(),
                     memory_hints: wgpu::MemoryHints::default(),
                 },
                 None,
             )
-            .await
-            .expect("Failed to create device");
+            .await?;
+
+        // A driver reset or resume from sleep can take the device away; the
+        // renderer starts over on a new one when it sees this
+        let lost = Arc::new(AtomicBool::new(false));
+        let lost_flag = Arc::clone(&lost);
+        device.set_device_lost_callback(move |reason, message| {
+            if reason == wgpu::DeviceLostReason::Unknown {
+                tracing::warn!("GPU device lost: {}", message);
+                lost_flag.store(true, Ordering::Relaxed);
+            }
+        });
 
         let surface_caps = surface.get_capabilities(&adapter);
         let surface_format = surface_caps
@@ -75,13 +97,26 @@ impl GpuState {
             wgpu::PresentMode::AutoVsync
         };
 
+        // Premultiplied alpha lets a transparent window show the desktop
+        // behind its background; opaque frames look the same either way
+        let alpha_mode = if surface_caps
+            .alpha_modes
+            .contains(&wgpu::CompositeAlphaMode::PreMultiplied)
+        {
+            wgpu::CompositeAlphaMode::PreMultiplied
+        } else {
+            surface_caps.alpha_modes[0]
+        };
+        let transparent = alpha_mode == wgpu::CompositeAlphaMode::PreMultiplied;
+        tracing::info!("Surface alpha mode: {:?}", alpha_mode);
+
         let config = SurfaceConfiguration {
             usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
             format: surface_format,
             width: size.width.max(1),
             height: size.height.max(1),
             present_mode,
-            alpha_mode: surface_caps.alpha_modes[0],
+            alpha_mode,
             view_formats: vec![],
             desired_maximum_frame_latency: 2,
         };
@@ -93,9 +128,21 @@ impl GpuState {
             queue,
             config,
             size: (size.width, size.height),
+            transparent,
+            lost,
         })
     }
 
+    /// Whether the device was lost and everything on it must be recreated
+    pub fn is_lost(&self) -> bool {
+        self.lost.load(Ordering::Relaxed)
+    }
+
+    /// Configure the surface again after it went out of date or was lost
+    pub fn reconfigure(&self) {
+        self.surface.configure(&self.device, &self.config);
+    }
+
     pub fn resize(&mut self, width: u32, height: u32) {
         if width > 0 && height > 0 {
             self.size = (width, height);
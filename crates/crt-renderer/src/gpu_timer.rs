@@ -0,0 +1,183 @@
+// ABOUTME: GPU timestamp queries for per-pass render timings.
+// ABOUTME: Times the text, burn-in and CRT passes when the adapter supports it.
+
+use std::sync::atomic::{AtomicU8, Ordering};
+use std::sync::Arc;
+
+/// Render passes timed per frame, in submission order
+const PASS_COUNT: u32 = 3;
+const QUERY_COUNT: u32 = PASS_COUNT * 2;
+const QUERY_BYTES: u64 = QUERY_COUNT as u64 * std::mem::size_of::<u64>() as u64;
+
+/// Readback states shared with the map callback
+const MAP_PENDING: u8 = 0;
+const MAP_DONE: u8 = 1;
+const MAP_FAILED: u8 = 2;
+
+/// GPU time spent in each render pass of a frame
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct PassTimings {
+    pub text_ms: f32,
+    pub burnin_ms: f32,
+    pub crt_ms: f32,
+}
+
+impl PassTimings {
+    /// GPU time of the whole frame
+    pub fn total_ms(&self) -> f32 {
+        self.text_ms + self.burnin_ms + self.crt_ms
+    }
+}
+
+/// Which render pass a timestamp pair belongs to
+#[derive(Debug, Clone, Copy)]
+pub(crate) enum TimedPass {
+    Text = 0,
+    BurnIn = 1,
+    /// The CRT pass, or the plain blit when effects are off
+    Crt = 2,
+}
+
+/// Timestamp queries around the render passes, read back without stalling:
+/// results are copied to a mappable buffer and picked up a frame or more
+/// later, skipping frames while a readback is still in flight.
+pub(crate) struct GpuTimer {
+    query_set: wgpu::QuerySet,
+    resolve_buffer: wgpu::Buffer,
+    readback_buffer: wgpu::Buffer,
+    /// Nanoseconds per timestamp tick
+    period_ns: f32,
+    /// Set by the map callback; Some while a readback is in flight
+    mapped: Option<Arc<AtomicU8>>,
+    /// Whether this frame's results were copied for readback
+    copied: bool,
+    /// Bit per pass not drawn this frame, and in the frame being read back
+    skipped: u8,
+    readback_skipped: u8,
+    last: Option<PassTimings>,
+}
+
+impl GpuTimer {
+    /// None if the device was created without timestamp query support
+    pub(crate) fn new(device: &wgpu::Device, queue: &wgpu::Queue) -> Option<Self> {
+        if !device.features().contains(wgpu::Features::TIMESTAMP_QUERY) {
+            return None;
+        }
+        let query_set = device.create_query_set(&wgpu::QuerySetDescriptor {
+            label: Some("Pass Timestamps"),
+            ty: wgpu::QueryType::Timestamp,
+            count: QUERY_COUNT,
+        });
+        let resolve_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Timestamp Resolve Buffer"),
+            size: QUERY_BYTES,
+            usage: wgpu::BufferUsages::QUERY_RESOLVE | wgpu::BufferUsages::COPY_SRC,
+            mapped_at_creation: false,
+        });
+        let readback_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Timestamp Readback Buffer"),
+            size: QUERY_BYTES,
+            usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+            mapped_at_creation: false,
+        });
+        Some(Self {
+            query_set,
+            resolve_buffer,
+            readback_buffer,
+            period_ns: queue.get_timestamp_period(),
+            mapped: None,
+            copied: false,
+            skipped: 0,
+            readback_skipped: 0,
+            last: None,
+        })
+    }
+
+    /// Timestamp writes for the start and end of a pass
+    pub(crate) fn pass_writes(&self, pass: TimedPass) -> wgpu::RenderPassTimestampWrites<'_> {
+        let first = pass as u32 * 2;
+        wgpu::RenderPassTimestampWrites {
+            query_set: &self.query_set,
+            beginning_of_pass_write_index: Some(first),
+            end_of_pass_write_index: Some(first + 1),
+        }
+    }
+
+    /// Mark a pass as not drawn this frame, so it reads as zero time
+    pub(crate) fn skip(&mut self, pass: TimedPass) {
+        self.skipped |= 1 << pass as u8;
+    }
+
+    /// Resolve this frame's queries; copy them for readback unless the
+    /// previous readback is still pending
+    pub(crate) fn resolve(&mut self, encoder: &mut wgpu::CommandEncoder) {
+        encoder.resolve_query_set(&self.query_set, 0..QUERY_COUNT, &self.resolve_buffer, 0);
+        self.copied = self.mapped.is_none();
+        let skipped = std::mem::take(&mut self.skipped);
+        if self.copied {
+            self.readback_skipped = skipped;
+            encoder.copy_buffer_to_buffer(
+                &self.resolve_buffer,
+                0,
+                &self.readback_buffer,
+                0,
+                QUERY_BYTES,
+            );
+        }
+    }
+
+    /// Start mapping the copied results; call after the frame is submitted
+    pub(crate) fn after_submit(&mut self) {
+        if !std::mem::take(&mut self.copied) {
+            return;
+        }
+        let state = Arc::new(AtomicU8::new(MAP_PENDING));
+        let callback_state = Arc::clone(&state);
+        self.readback_buffer
+            .slice(..)
+            .map_async(wgpu::MapMode::Read, move |result| {
+                let done = if result.is_ok() { MAP_DONE } else { MAP_FAILED };
+                callback_state.store(done, Ordering::Release);
+            });
+        self.mapped = Some(state);
+    }
+
+    /// Pick up results from an earlier frame if the GPU has finished them
+    pub(crate) fn collect(&mut self, device: &wgpu::Device) {
+        let Some(state) = &self.mapped else {
+            return;
+        };
+        device.poll(wgpu::Maintain::Poll);
+        match state.load(Ordering::Acquire) {
+            MAP_PENDING => {}
+            MAP_FAILED => self.mapped = None,
+            _ => {
+                let ticks: Vec<u64> = {
+                    let data = self.readback_buffer.slice(..).get_mapped_range();
+                    bytemuck::cast_slice(&data).to_vec()
+                };
+                self.readback_buffer.unmap();
+                self.mapped = None;
+
+                let ms = |pass: TimedPass| {
+                    if self.readback_skipped & (1 << pass as u8) != 0 {
+                        return 0.0;
+                    }
+                    let first = pass as usize * 2;
+                    let elapsed = ticks[first + 1].wrapping_sub(ticks[first]);
+                    elapsed as f32 * self.period_ns / 1_000_000.0
+                };
+                self.last = Some(PassTimings {
+                    text_ms: ms(TimedPass::Text),
+                    burnin_ms: ms(TimedPass::BurnIn),
+                    crt_ms: ms(TimedPass::Crt),
+                });
+            }
+        }
+    }
+
+    /// Timings from the most recent frame that has been read back
+    pub(crate) fn last(&self) -> Option<PassTimings> {
+        self.last
+    }
+}
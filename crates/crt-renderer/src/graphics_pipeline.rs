@@ -0,0 +1,327 @@
+// ABOUTME: Image pipeline for sixel graphics, drawn into the text pass as textured quads.
+// ABOUTME: Keeps one texture per image, keyed by its id, until it goes unused for a while.
+
+use std::collections::HashMap;
+use std::ops::Range;
+
+use bytemuck::{Pod, Zeroable};
+use wgpu::util::DeviceExt;
+
+/// Frames an image texture is kept without being drawn
+const MAX_IDLE_FRAMES: u64 = 600;
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug, Pod, Zeroable)]
+pub struct ImageInstance {
+    /// x, y, width, height in pixels
+    pub rect: [f32; 4],
+    /// Texture coordinates of the top left and bottom right corners
+    pub uv: [f32; 4],
+}
+
+impl ImageInstance {
+    const ATTRIBS: [wgpu::VertexAttribute; 2] = wgpu::vertex_attr_array![
+        0 => Float32x4,
+        1 => Float32x4,
+    ];
+
+    fn desc() -> wgpu::VertexBufferLayout<'static> {
+        wgpu::VertexBufferLayout {
+            array_stride: std::mem::size_of::<ImageInstance>() as wgpu::BufferAddress,
+            step_mode: wgpu::VertexStepMode::Instance,
+            attributes: &Self::ATTRIBS,
+        }
+    }
+}
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug, Pod, Zeroable)]
+struct Uniforms {
+    screen_size: [f32; 2],
+    _padding: [f32; 2],
+}
+
+struct ImageTexture {
+    bind_group: wgpu::BindGroup,
+    /// Frame the image was last drawn in
+    last_used: u64,
+}
+
+pub struct GraphicsPipeline {
+    pipeline: wgpu::RenderPipeline,
+    uniform_bind_group: wgpu::BindGroup,
+    texture_bind_group_layout: wgpu::BindGroupLayout,
+    uniform_buffer: wgpu::Buffer,
+    sampler: wgpu::Sampler,
+    instance_buffer: Option<wgpu::Buffer>,
+    textures: HashMap<u64, ImageTexture>,
+    /// Instances to draw from each image, in order
+    draws: Vec<(u64, Range<u32>)>,
+    frame: u64,
+}
+
+impl GraphicsPipeline {
+    pub fn new(device: &wgpu::Device, format: wgpu::TextureFormat) -> Self {
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Graphics Shader"),
+            source: wgpu::ShaderSource::Wgsl(include_str!("../../../shaders/graphics.wgsl").into()),
+        });
+
+        let uniform_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Graphics Uniform Buffer"),
+            contents: bytemuck::cast_slice(&[Uniforms {
+                screen_size: [800.0, 600.0],
+                _padding: [0.0, 0.0],
+            }]),
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        });
+
+        let uniform_bind_group_layout =
+            device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                label: Some("Graphics Uniform Bind Group Layout"),
+                entries: &[wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::VERTEX,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                }],
+            });
+
+        let uniform_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Graphics Uniform Bind Group"),
+            layout: &uniform_bind_group_layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: uniform_buffer.as_entire_binding(),
+            }],
+        });
+
+        let texture_bind_group_layout =
+            device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                label: Some("Graphics Texture Bind Group Layout"),
+                entries: &[
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 0,
+                        visibility: wgpu::ShaderStages::FRAGMENT,
+                        ty: wgpu::BindingType::Texture {
+                            sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                            view_dimension: wgpu::TextureViewDimension::D2,
+                            multisampled: false,
+                        },
+                        count: None,
+                    },
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 1,
+                        visibility: wgpu::ShaderStages::FRAGMENT,
+                        ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                        count: None,
+                    },
+                ],
+            });
+
+        // Images are drawn at their own pixel size, so nearest keeps them sharp
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            label: Some("Graphics Sampler"),
+            address_mode_u: wgpu::AddressMode::ClampToEdge,
+            address_mode_v: wgpu::AddressMode::ClampToEdge,
+            address_mode_w: wgpu::AddressMode::ClampToEdge,
+            mag_filter: wgpu::FilterMode::Nearest,
+            min_filter: wgpu::FilterMode::Linear,
+            ..Default::default()
+        });
+
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Graphics Pipeline Layout"),
+            bind_group_layouts: &[&uniform_bind_group_layout, &texture_bind_group_layout],
+            push_constant_ranges: &[],
+        });
+
+        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Graphics Pipeline"),
+            layout: Some(&pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: Some("vs_main"),
+                buffers: &[ImageInstance::desc()],
+                compilation_options: wgpu::PipelineCompilationOptions::default(),
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: Some("fs_main"),
+                targets: &[Some(wgpu::ColorTargetState {
+                    format,
+                    blend: Some(wgpu::BlendState::ALPHA_BLENDING),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+                compilation_options: wgpu::PipelineCompilationOptions::default(),
+            }),
+            primitive: wgpu::PrimitiveState {
+                topology: wgpu::PrimitiveTopology::TriangleList,
+                strip_index_format: None,
+                front_face: wgpu::FrontFace::Ccw,
+                cull_mode: None,
+                polygon_mode: wgpu::PolygonMode::Fill,
+                unclipped_depth: false,
+                conservative: false,
+            },
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState::default(),
+            multiview: None,
+            cache: None,
+        });
+
+        Self {
+            pipeline,
+            uniform_bind_group,
+            texture_bind_group_layout,
+            uniform_buffer,
+            sampler,
+            instance_buffer: None,
+            textures: HashMap::new(),
+            draws: Vec::new(),
+            frame: 0,
+        }
+    }
+
+    pub fn update_screen_size(&self, queue: &wgpu::Queue, width: f32, height: f32) {
+        queue.write_buffer(
+            &self.uniform_buffer,
+            0,
+            bytemuck::cast_slice(&[Uniforms {
+                screen_size: [width, height],
+                _padding: [0.0, 0.0],
+            }]),
+        );
+    }
+
+    pub fn has_image(&self, id: u64) -> bool {
+        self.textures.contains_key(&id)
+    }
+
+    /// Upload an image's straight RGBA pixels as texture `id`
+    pub fn upload(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        id: u64,
+        size: (u32, u32),
+        rgba: &[u8],
+    ) {
+        let extent = wgpu::Extent3d {
+            width: size.0.max(1),
+            height: size.1.max(1),
+            depth_or_array_layers: 1,
+        };
+        let texture = device.create_texture_with_data(
+            queue,
+            &wgpu::TextureDescriptor {
+                label: Some("Sixel Image"),
+                size: extent,
+                mip_level_count: 1,
+                sample_count: 1,
+                dimension: wgpu::TextureDimension::D2,
+                format: wgpu::TextureFormat::Rgba8UnormSrgb,
+                usage: wgpu::TextureUsages::TEXTURE_BINDING,
+                view_formats: &[],
+            },
+            wgpu::util::TextureDataOrder::LayerMajor,
+            rgba,
+        );
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Sixel Image Bind Group"),
+            layout: &self.texture_bind_group_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(&view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::Sampler(&self.sampler),
+                },
+            ],
+        });
+        self.textures.insert(
+            id,
+            ImageTexture {
+                bind_group,
+                last_used: self.frame,
+            },
+        );
+    }
+
+    /// Set up this frame's quads, each from the image with the given id.
+    /// Images that haven't been uploaded are skipped; ones left unused for a
+    /// while are dropped.
+    pub fn prepare(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        quads: &[(u64, ImageInstance)],
+    ) {
+        self.frame += 1;
+        self.draws.clear();
+
+        let mut instances = Vec::with_capacity(quads.len());
+        for &(id, instance) in quads {
+            let Some(texture) = self.textures.get_mut(&id) else {
+                continue;
+            };
+            texture.last_used = self.frame;
+            let index = instances.len() as u32;
+            instances.push(instance);
+            match self.draws.last_mut() {
+                Some((last, range)) if *last == id => range.end = index + 1,
+                _ => self.draws.push((id, index..index + 1)),
+            }
+        }
+
+        let frame = self.frame;
+        self.textures
+            .retain(|_, texture| frame - texture.last_used < MAX_IDLE_FRAMES);
+
+        if instances.is_empty() {
+            return;
+        }
+        let bytes: &[u8] = bytemuck::cast_slice(&instances);
+        let fits = self
+            .instance_buffer
+            .as_ref()
+            .is_some_and(|buffer| buffer.size() >= bytes.len() as u64);
+        if !fits {
+            self.instance_buffer = Some(device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some("Graphics Instance Buffer"),
+                size: (bytes.len() as u64).next_power_of_two(),
+                usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+                mapped_at_creation: false,
+            }));
+        }
+        if let Some(buffer) = &self.instance_buffer {
+            queue.write_buffer(buffer, 0, bytes);
+        }
+    }
+
+    pub fn render<'a>(&'a self, render_pass: &mut wgpu::RenderPass<'a>) {
+        let Some(buffer) = &self.instance_buffer else {
+            return;
+        };
+        if self.draws.is_empty() {
+            return;
+        }
+
+        render_pass.set_pipeline(&self.pipeline);
+        render_pass.set_bind_group(0, &self.uniform_bind_group, &[]);
+        render_pass.set_vertex_buffer(0, buffer.slice(..));
+        for (id, range) in &self.draws {
+            if let Some(texture) = self.textures.get(id) {
+                render_pass.set_bind_group(1, &texture.bind_group, &[]);
+                render_pass.draw(0..6, range.clone());
+            }
+        }
+    }
+}
@@ -3,15 +3,23 @@
 
 pub mod atlas;
 pub mod bdf;
+mod blit_pipeline;
+mod box_drawing;
 mod burnin_pipeline;
 mod crt_pipeline;
 pub mod fonts;
 mod gpu;
+mod gpu_timer;
+mod graphics_pipeline;
 mod line_pipeline;
 pub mod renderer;
+mod shader_reload;
 mod text_pipeline;
 
-pub use atlas::GlyphAtlas;
+pub use atlas::{AtlasCompressionMode, GlyphAtlas, GlyphStyle};
 pub use bdf::BdfFont;
-pub use fonts::{get_bdf_font_data, get_font_data};
-pub use renderer::{EffectParams, RenderCell, Renderer};
+pub use fonts::{find_bdf_fonts, get_bdf_font_data, get_font_data};
+pub use gpu_timer::PassTimings;
+pub use renderer::{
+    burn_in_fade_time, EffectParams, ImageStrip, RenderCell, RenderError, Renderer,
+};
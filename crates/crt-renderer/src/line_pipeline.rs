@@ -41,6 +41,8 @@ pub struct LinePipeline {
     index_buffer: wgpu::Buffer,
     max_lines: usize,
     num_indices: u32,
+    /// Input of the last prepare, to skip rebuilding identical frames
+    last_lines: Vec<(f32, f32, f32, f32, f32, [f32; 4])>,
 }
 
 impl LinePipeline {
@@ -146,6 +148,7 @@ impl LinePipeline {
             index_buffer,
             max_lines,
             num_indices: 0,
+            last_lines: Vec::new(),
         }
     }
 
@@ -164,6 +167,10 @@ impl LinePipeline {
     /// Each line is (x0, y0, x1, y1, thickness, color)
     #[allow(clippy::type_complexity)]
     pub fn prepare(&mut self, queue: &wgpu::Queue, lines: &[(f32, f32, f32, f32, f32, [f32; 4])]) {
+        if lines == self.last_lines.as_slice() {
+            return;
+        }
+
         let mut vertices = Vec::with_capacity(lines.len() * 4);
         let mut indices = Vec::with_capacity(lines.len() * 6);
 
@@ -219,6 +226,8 @@ impl LinePipeline {
         }
 
         self.num_indices = indices.len() as u32;
+        self.last_lines.clear();
+        self.last_lines.extend_from_slice(lines);
     }
 
     pub fn render<'a>(&'a self, render_pass: &mut wgpu::RenderPass<'a>) {
@@ -1,22 +1,24 @@
 // ABOUTME: Main GPU renderer using wgpu.
 // ABOUTME: Renders terminal panes with CRT shader effects.
 
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
-use std::time::Instant;
+use std::time::{Duration, Instant};
 use winit::window::Window;
 
-use crt_core::Font;
+use crt_core::{AtlasEvictionPolicy, FallbackFont, Font, LineSize, PaneBorderStyle};
 
-use crate::atlas::GlyphAtlas;
+use crate::atlas::{AtlasCompressionMode, GlyphAtlas, GlyphStyle, DEFAULT_MAX_ATLAS_SIZE};
+use crate::blit_pipeline::BlitPipeline;
 use crate::burnin_pipeline::BurnInPipeline;
 use crate::crt_pipeline::CrtPipeline;
-use crate::fonts::{
-    get_emoji_fallback_font_data, get_fallback_font_data, get_font_data,
-    get_symbols_fallback_font_data, get_unifont_fallback_data,
-};
+use crate::fonts::{get_fallback_font_data, get_font_data, get_unifont_fallback_data};
 use crate::gpu::GpuState;
+use crate::gpu_timer::{GpuTimer, PassTimings, TimedPass};
+use crate::graphics_pipeline::{GraphicsPipeline, ImageInstance};
 use crate::line_pipeline::LinePipeline;
-use crate::text_pipeline::TextPipeline;
+use crate::text_pipeline::{TextChar, TextPipeline};
 
 #[derive(Debug, thiserror::Error)]
 pub enum RenderError {
@@ -26,8 +28,48 @@ pub enum RenderError {
     #[error("Failed to create surface: {0}")]
     CreateSurface(#[from] wgpu::CreateSurfaceError),
 
+    #[error("No suitable GPU adapter found")]
+    NoAdapter,
+
+    #[error("Failed to create GPU device: {0}")]
+    RequestDevice(#[from] wgpu::RequestDeviceError),
+
+    /// The GPU device is gone; `Renderer::recover` starts over on a new one
+    #[error("GPU device lost")]
+    DeviceLost,
+
     #[error("Atlas error: {0}")]
     Atlas(#[from] crate::atlas::AtlasError),
+
+    #[error("Invalid BDF font: {0}")]
+    Bdf(#[from] crate::bdf::BdfError),
+
+    #[error("Failed to read font {}: {source}", path.display())]
+    FontFile {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+}
+
+/// Read a TTF/OTF file and check that it parses as a font
+fn read_font_file(path: &Path) -> Result<Vec<u8>, RenderError> {
+    let data = std::fs::read(path).map_err(|source| RenderError::FontFile {
+        path: path.to_path_buf(),
+        source,
+    })?;
+    fontdue::Font::from_bytes(data.as_slice(), fontdue::FontSettings::default())
+        .map_err(|e| crate::atlas::AtlasError::FontLoadError(e.to_string()))?;
+    Ok(data)
+}
+
+/// Read a BDF file and check that it parses
+fn read_bdf_file(path: &Path) -> Result<Vec<u8>, RenderError> {
+    let data = std::fs::read(path).map_err(|source| RenderError::FontFile {
+        path: path.to_path_buf(),
+        source,
+    })?;
+    crate::bdf::BdfFont::parse(&data)?;
+    Ok(data)
 }
 
 /// A single cell to render
@@ -36,13 +78,34 @@ pub struct RenderCell {
     pub fg: [f32; 4],
     pub bg: [f32; 4],
     pub is_wide: bool,
+    pub style: GlyphStyle,
+    /// Size of the line this cell is on (the same for the whole row)
+    pub line_size: LineSize,
+}
+
+/// One cell row of an uploaded image (see `Renderer::upload_image`), in
+/// logical pixels like the panes
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ImageStrip {
+    pub image: u64,
+    /// Index of the pane it's in, so panes drawn over it hide it
+    pub pane: usize,
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+    /// Texture coordinates of the top left and bottom right corners
+    pub uv: [f32; 4],
 }
 
 /// Effect settings for CRT shader
 pub struct EffectParams {
+    /// Run the burn-in and CRT passes; when off the text pass is shown as is
+    pub enabled: bool,
     pub curvature: f32,
     pub scanline_intensity: f32,
     pub scanline_mode: u32, // 0 = row-based, 1 = pixel-level
+    pub scanline_gamma: f32,
     pub bloom: f32,
     pub burn_in: f32,
     pub focus_glow_radius: f32,
@@ -52,10 +115,24 @@ pub struct EffectParams {
     pub flicker: f32,
     pub brightness: f32,
     pub vignette: f32,
+    /// RGB phosphor mask strength (0 = off)
+    pub rgb_mask: f32,
+    /// Chromatic aberration in pixels at the screen edges (0 = off)
+    pub aberration: f32,
+    /// Rounded screen corners as a fraction of the shorter side (0 = square)
+    pub corner_radius: f32,
+    pub composite_bleed: f32,
+    /// RGB multiplier for the screen's color temperature (1 = unchanged)
+    pub white_point: [f32; 3],
     pub bezel_enabled: bool,
     pub content_scale_x: f32,
     pub content_scale_y: f32,
+    /// Focus glow color; alpha is how much bloom is tinted with it
     pub glow_color: [f32; 4],
+    /// Visual bell flash color; alpha is its current strength (0 = none)
+    pub flash_color: [f32; 4],
+    /// Opacity of the window background; 1 unless the surface supports alpha
+    pub background_opacity: f32,
     // Beam sweep / interlacing simulation
     pub interlace_enabled: bool,
     pub beam_speed_divisor: u32, // How many frames per beam slice (e.g., 4 for 240Hz -> 60 fields/sec)
@@ -63,28 +140,114 @@ pub struct EffectParams {
     pub beam_step_count: u32,    // Advance N frames when paused (0 = no step)
 }
 
+impl EffectParams {
+    /// Moderate amber CRT look: light curvature, scanlines, bloom, noise
+    /// and flicker, no bezel. What `Renderer::render_grid` draws with.
+    pub fn crt_demo() -> Self {
+        Self {
+            enabled: true,
+            curvature: 0.03,
+            scanline_intensity: 0.3,
+            scanline_mode: 0,
+            scanline_gamma: 0.0,
+            bloom: 0.3,
+            burn_in: 0.0,
+            focus_glow_radius: 0.05,
+            focus_glow_width: 0.06,
+            focus_glow_intensity: 0.6,
+            static_noise: 0.05,
+            flicker: 0.05,
+            brightness: 1.0,
+            vignette: 0.2,
+            rgb_mask: 0.0,
+            aberration: 0.0,
+            corner_radius: 0.0,
+            composite_bleed: 0.0,
+            white_point: [1.0; 3],
+            bezel_enabled: false,
+            content_scale_x: 1.0,
+            content_scale_y: 1.0,
+            glow_color: [1.0, 0.7, 0.0, 1.0],
+            flash_color: [0.0; 4],
+            background_opacity: 1.0,
+            interlace_enabled: false,
+            beam_speed_divisor: 0,
+            beam_paused: false,
+            beam_step_count: 0,
+        }
+    }
+}
+
+/// Glyph atlas and text pipeline for panes set to their own font size
+struct PaneFont {
+    atlas: GlyphAtlas,
+    text_pipeline: TextPipeline,
+}
+
+/// Key for a font size, in half-pixel steps so nearly equal sizes share an atlas
+fn font_size_key(font_size: f32) -> u32 {
+    (font_size * 2.0).round() as u32
+}
+
 pub struct Renderer {
+    window: Arc<Window>,
     gpu: GpuState,
     clear_color: wgpu::Color,
     text_pipeline: TextPipeline,
     line_pipeline: LinePipeline,
+    graphics_pipeline: GraphicsPipeline,
     atlas: GlyphAtlas,
     font_color: [f32; 4],
     current_font: Font,
     current_font_size: f32,
     current_bdf_font: Option<crt_core::BdfFont>,
+    /// Custom font file asked for, and its contents if it loaded; used in
+    /// place of `current_font`
+    font_path: Option<PathBuf>,
+    custom_font: Option<Vec<u8>>,
+    /// The same for a BDF file, used in place of `current_bdf_font`
+    bdf_path: Option<PathBuf>,
+    custom_bdf: Option<Vec<u8>>,
+    /// Atlases for pane font sizes other than the main one, keyed by
+    /// `font_size_key`
+    pane_fonts: HashMap<u32, PaneFont>,
+    fallback_fonts: Vec<FallbackFont>,
+    max_cached_glyphs: Option<usize>,
+    atlas_compression: AtlasCompressionMode,
+    max_atlas_size: u32,
+    atlas_eviction_policy: AtlasEvictionPolicy,
+    /// (line height multiplier, letter spacing px) applied to every atlas
+    cell_spacing: (f32, f32),
+    /// How the lines between panes are drawn
+    pane_border_style: PaneBorderStyle,
+    /// Physical pixels per logical pixel. Callers work in logical pixels;
+    /// the surface, textures and glyphs are physical.
+    scale_factor: f32,
     crt_pipeline: CrtPipeline,
     burnin_pipeline: BurnInPipeline,
     offscreen_texture: wgpu::Texture,
     offscreen_view: wgpu::TextureView,
     crt_bind_group: wgpu::BindGroup,
+    blit_pipeline: BlitPipeline,
+    blit_bind_group: wgpu::BindGroup,
+    /// Whether the last `render_panes` frame went through the CRT pass
+    effects_enabled: bool,
     last_frame: Instant,
     frame_count: u64, // For beam sweep / interlacing timing
+    /// Whether per-pass GPU timing was requested
+    pass_timing: bool,
+    /// Timestamp queries; None when timing is off or unsupported
+    gpu_timer: Option<GpuTimer>,
 }
 
 impl Renderer {
-    pub async fn new(window: Arc<Window>, font: Font, font_size: f32) -> Result<Self, RenderError> {
-        let gpu = GpuState::new(window).await?;
+    pub async fn new(
+        window: Arc<Window>,
+        font: Font,
+        font_size: f32,
+        fallback_fonts: &[FallbackFont],
+    ) -> Result<Self, RenderError> {
+        let gpu = GpuState::new(Arc::clone(&window)).await?;
 
         // Dark background color
         let clear_color = wgpu::Color {
@@ -99,41 +262,13 @@ impl Renderer {
         let mut atlas = GlyphAtlas::new(font_data, font_size)?;
 
         // Set up fallback fonts for characters missing from primary (TTF)
-        // Chain: Hack -> Symbols -> Unifont -> Emoji
-        if let Err(e) = atlas.set_fallback(get_fallback_font_data()) {
-            tracing::warn!("Failed to load fallback font: {}", e);
-        }
-        if let Err(e) = atlas.set_symbols_fallback(get_symbols_fallback_font_data()) {
-            tracing::warn!("Failed to load symbols fallback font: {}", e);
-        }
-        if let Err(e) = atlas.set_bdf_fallback(get_unifont_fallback_data()) {
-            tracing::warn!("Failed to load Unifont fallback: {}", e);
-        }
-        if let Err(e) = atlas.set_emoji_fallback(get_emoji_fallback_font_data()) {
-            tracing::warn!("Failed to load emoji fallback font: {}", e);
-        }
-
-        // Pre-populate common ASCII characters
-        for c in ' '..='~' {
-            let _ = atlas.get_glyph(c, false);
-        }
-        // Block characters for cursor
-        let _ = atlas.get_glyph('█', false);
-        let _ = atlas.get_glyph('▌', false);
-        let _ = atlas.get_glyph('▐', false);
-        let _ = atlas.get_glyph('▀', false);
-        let _ = atlas.get_glyph('▄', false);
-        // Box drawing for separators
-        let _ = atlas.get_glyph('│', false);
-        let _ = atlas.get_glyph('─', false);
-        // Corner brackets for focus indicator
-        let _ = atlas.get_glyph('┌', false);
-        let _ = atlas.get_glyph('┐', false);
-        let _ = atlas.get_glyph('└', false);
-        let _ = atlas.get_glyph('┘', false);
+        install_fallbacks(&mut atlas, fallback_fonts, false);
+
+        prepopulate_glyphs(&mut atlas);
 
         let text_pipeline = TextPipeline::new(&gpu.device, &gpu.queue, gpu.config.format, &atlas);
         let line_pipeline = LinePipeline::new(&gpu.device, gpu.config.format);
+        let graphics_pipeline = GraphicsPipeline::new(&gpu.device, gpu.config.format);
 
         // Amber color
         let font_color = [1.0, 0.7, 0.0, 1.0];
@@ -153,26 +288,114 @@ impl Renderer {
         let crt_bind_group =
             crt_pipeline.create_bind_group(&gpu.device, burnin_pipeline.output_view());
 
+        // Plain mode shows the text pass directly
+        let blit_pipeline = BlitPipeline::new(&gpu.device, gpu.config.format);
+        let blit_bind_group = blit_pipeline.create_bind_group(&gpu.device, &offscreen_view);
+
         Ok(Self {
+            window,
             gpu,
             clear_color,
             text_pipeline,
             line_pipeline,
+            graphics_pipeline,
             atlas,
             font_color,
             current_font: font,
             current_font_size: font_size,
             current_bdf_font: None,
+            font_path: None,
+            custom_font: None,
+            bdf_path: None,
+            custom_bdf: None,
+            pane_fonts: HashMap::new(),
+            fallback_fonts: fallback_fonts.to_vec(),
+            max_cached_glyphs: None,
+            atlas_compression: AtlasCompressionMode::default(),
+            max_atlas_size: DEFAULT_MAX_ATLAS_SIZE,
+            atlas_eviction_policy: AtlasEvictionPolicy::default(),
+            cell_spacing: (1.0, 0.0),
+            pane_border_style: PaneBorderStyle::default(),
+            scale_factor: 1.0,
             crt_pipeline,
             burnin_pipeline,
             offscreen_texture,
             offscreen_view,
             crt_bind_group,
+            blit_pipeline,
+            blit_bind_group,
+            effects_enabled: true,
             last_frame: Instant::now(),
             frame_count: 0,
+            pass_timing: false,
+            gpu_timer: None,
         })
     }
 
+    /// Start over on a new GPU device after the old one was lost. Fonts,
+    /// atlas limits, spacing and pass timing carry over; images need
+    /// uploading again.
+    pub async fn recover(&mut self) -> Result<(), RenderError> {
+        let mut fresh = Self::new(
+            Arc::clone(&self.window),
+            self.current_font,
+            self.current_font_size,
+            &self.fallback_fonts,
+        )
+        .await?;
+        fresh.clear_color = self.clear_color;
+        fresh.font_color = self.font_color;
+        fresh.set_max_cached_glyphs(self.max_cached_glyphs);
+        fresh.set_max_atlas_size(self.max_atlas_size);
+        fresh.set_atlas_eviction_policy(self.atlas_eviction_policy);
+        fresh.set_atlas_compression(self.atlas_compression);
+        fresh.set_scale_factor(self.scale_factor);
+        fresh.set_cell_spacing(self.cell_spacing.0, self.cell_spacing.1);
+        fresh.set_pane_border_style(self.pane_border_style);
+        fresh.set_pass_timing(self.pass_timing);
+        fresh.font_path = self.font_path.clone();
+        fresh.custom_font = self.custom_font.clone();
+        fresh.bdf_path = self.bdf_path.clone();
+        fresh.custom_bdf = self.custom_bdf.clone();
+        if let Some(bdf_font) = self.current_bdf_font {
+            fresh.load_bdf_font(bdf_font)?;
+        } else if fresh.custom_font.is_some() {
+            let (atlas, text_pipeline) =
+                fresh.load_ttf_font(fresh.current_font, fresh.current_font_size)?;
+            fresh.atlas = atlas;
+            fresh.text_pipeline = text_pipeline;
+        }
+        let sizes: Vec<f32> = self
+            .pane_fonts
+            .keys()
+            .map(|&key| key as f32 / 2.0)
+            .collect();
+        fresh.set_pane_font_sizes(&sizes)?;
+        *self = fresh;
+        tracing::info!("Renderer recreated on a new GPU device");
+        Ok(())
+    }
+
+    /// The next surface texture to draw into. An outdated or lost swap chain
+    /// (resume from sleep, display changes) is configured again and retried
+    /// once; if that fails too, or the driver reported the device lost,
+    /// this returns `DeviceLost`.
+    fn acquire_frame(&mut self) -> Result<wgpu::SurfaceTexture, RenderError> {
+        if self.gpu.is_lost() {
+            return Err(RenderError::DeviceLost);
+        }
+        match self.gpu.surface.get_current_texture() {
+            Err(wgpu::SurfaceError::Lost | wgpu::SurfaceError::Outdated) => {
+                self.gpu.reconfigure();
+                self.gpu.surface.get_current_texture().map_err(|e| match e {
+                    wgpu::SurfaceError::Lost => RenderError::DeviceLost,
+                    e => RenderError::Surface(e),
+                })
+            }
+            result => Ok(result?),
+        }
+    }
+
     /// Change the font and/or size. Recreates the atlas and text pipeline.
     pub fn set_font(&mut self, font: Font, font_size: f32) -> Result<(), RenderError> {
         if self.current_bdf_font.is_none()
@@ -182,45 +405,66 @@ impl Renderer {
             return Ok(()); // No change needed
         }
 
-        // Create new atlas with new font
-        let font_data = get_font_data(font);
+        let (atlas, text_pipeline) = self.load_ttf_font(font, font_size)?;
+        if font != self.current_font || self.current_bdf_font.is_some() {
+            // Pane sizes were rasterized from the old font
+            self.pane_fonts.clear();
+        }
+        self.atlas = atlas;
+        self.text_pipeline = text_pipeline;
+        self.current_font = font;
+        self.current_font_size = font_size;
+        self.current_bdf_font = None; // Switching to TTF clears BDF
+
+        Ok(())
+    }
+
+    /// Use a TTF/OTF file in place of the bundled TTF font, or go back to
+    /// the bundled one with None. The file is read once per path; if it
+    /// can't be read or parsed the bundled font stays in use and the error
+    /// is returned.
+    pub fn set_font_path(&mut self, path: Option<&Path>) -> Result<(), RenderError> {
+        if self.font_path.as_deref() == path {
+            return Ok(());
+        }
+        self.font_path = path.map(Path::to_path_buf);
+        let (custom_font, result) = match path.map(read_font_file) {
+            Some(Ok(data)) => (Some(data), Ok(())),
+            Some(Err(e)) => (None, Err(e)),
+            None => (None, Ok(())),
+        };
+        let changed = self.custom_font.is_some() || custom_font.is_some();
+        self.custom_font = custom_font;
+        if self.current_bdf_font.is_none() && changed {
+            let (atlas, text_pipeline) =
+                self.load_ttf_font(self.current_font, self.current_font_size)?;
+            self.atlas = atlas;
+            self.text_pipeline = text_pipeline;
+            self.pane_fonts.clear();
+        }
+        result
+    }
+
+    /// Rasterize a TTF font at a size (physical pixels) into a new atlas with
+    /// the current fallbacks, limits and spacing, and a text pipeline for it.
+    /// A custom font file takes the place of `font`.
+    fn load_ttf_font(
+        &self,
+        font: Font,
+        font_size: f32,
+    ) -> Result<(GlyphAtlas, TextPipeline), RenderError> {
+        let font_data = self
+            .custom_font
+            .as_deref()
+            .unwrap_or_else(|| get_font_data(font));
         let mut atlas = GlyphAtlas::new(font_data, font_size)?;
 
         // Set up fallback fonts for characters missing from primary (TTF)
-        // Chain: Hack -> Symbols -> Unifont -> Emoji
-        if let Err(e) = atlas.set_fallback(get_fallback_font_data()) {
-            tracing::warn!("Failed to load fallback font: {}", e);
-        }
-        if let Err(e) = atlas.set_symbols_fallback(get_symbols_fallback_font_data()) {
-            tracing::warn!("Failed to load symbols fallback font: {}", e);
-        }
-        if let Err(e) = atlas.set_bdf_fallback(get_unifont_fallback_data()) {
-            tracing::warn!("Failed to load Unifont fallback: {}", e);
-        }
-        if let Err(e) = atlas.set_emoji_fallback(get_emoji_fallback_font_data()) {
-            tracing::warn!("Failed to load emoji fallback font: {}", e);
-        }
-
-        // Pre-populate common ASCII characters
-        for c in ' '..='~' {
-            let _ = atlas.get_glyph(c, false);
-        }
-        // Block characters for cursor
-        let _ = atlas.get_glyph('█', false);
-        let _ = atlas.get_glyph('▌', false);
-        let _ = atlas.get_glyph('▐', false);
-        let _ = atlas.get_glyph('▀', false);
-        let _ = atlas.get_glyph('▄', false);
-        // Box drawing for separators
-        let _ = atlas.get_glyph('│', false);
-        let _ = atlas.get_glyph('─', false);
-        // Corner brackets for focus indicator
-        let _ = atlas.get_glyph('┌', false);
-        let _ = atlas.get_glyph('┐', false);
-        let _ = atlas.get_glyph('└', false);
-        let _ = atlas.get_glyph('┘', false);
+        install_fallbacks(&mut atlas, &self.fallback_fonts, false);
 
-        // Recreate text pipeline with new atlas
+        prepopulate_glyphs(&mut atlas);
+
+        atlas.set_compression_mode(self.atlas_compression);
         let text_pipeline = TextPipeline::new(
             &self.gpu.device,
             &self.gpu.queue,
@@ -228,13 +472,32 @@ impl Renderer {
             &atlas,
         );
 
-        self.atlas = atlas;
-        self.text_pipeline = text_pipeline;
-        self.current_font = font;
-        self.current_font_size = font_size;
-        self.current_bdf_font = None; // Switching to TTF clears BDF
+        atlas.set_max_cached_glyphs(self.max_cached_glyphs);
+        atlas.set_max_atlas_size(self.max_atlas_size);
+        atlas.set_eviction_policy(self.atlas_eviction_policy);
+        atlas.set_spacing(self.cell_spacing.0, self.cell_spacing.1 * self.scale_factor);
+        Ok((atlas, text_pipeline))
+    }
 
-        Ok(())
+    /// Use a BDF file in place of the bundled BDF font, or go back to the
+    /// bundled one with None. Like `set_font_path`, the file is read once
+    /// per path and the bundled font stays in use if it doesn't load.
+    pub fn set_bdf_font_path(&mut self, path: Option<&Path>) -> Result<(), RenderError> {
+        if self.bdf_path.as_deref() == path {
+            return Ok(());
+        }
+        self.bdf_path = path.map(Path::to_path_buf);
+        let (custom_bdf, result) = match path.map(read_bdf_file) {
+            Some(Ok(data)) => (Some(data), Ok(())),
+            Some(Err(e)) => (None, Err(e)),
+            None => (None, Ok(())),
+        };
+        let changed = self.custom_bdf.is_some() || custom_bdf.is_some();
+        self.custom_bdf = custom_bdf;
+        if let (Some(bdf_font), true) = (self.current_bdf_font, changed) {
+            self.load_bdf_font(bdf_font)?;
+        }
+        result
     }
 
     /// Change to a BDF bitmap font. Recreates the atlas and text pipeline.
@@ -244,44 +507,30 @@ impl Renderer {
         if self.current_bdf_font == Some(bdf_font) {
             return Ok(()); // No change needed
         }
+        self.load_bdf_font(bdf_font)
+    }
 
+    /// Build the atlas and text pipeline for a BDF font, or the custom BDF
+    /// file standing in for it
+    fn load_bdf_font(&mut self, bdf_font: crt_core::BdfFont) -> Result<(), RenderError> {
         // Create new atlas from BDF
-        let bdf_data = crate::fonts::get_bdf_font_data(bdf_font);
+        let bdf_data = self
+            .custom_bdf
+            .as_deref()
+            .unwrap_or_else(|| crate::fonts::get_bdf_font_data(bdf_font));
         let mut atlas = GlyphAtlas::from_bdf(bdf_data)?;
 
         // Set up fallback fonts for characters missing from BDF
-        // Chain: Unifont (BDF) -> Emoji (skip TTF fallbacks to maintain bitmap aesthetic)
-        if let Err(e) = atlas.set_bdf_fallback(get_unifont_fallback_data()) {
-            tracing::warn!("Failed to load Unifont fallback: {}", e);
-        }
-        if let Err(e) = atlas.set_emoji_fallback(get_emoji_fallback_font_data()) {
-            tracing::warn!("Failed to load emoji fallback font: {}", e);
-        }
-
-        // Pre-populate common ASCII characters
-        for c in ' '..='~' {
-            let _ = atlas.get_glyph(c, false);
-        }
-        // Block characters for cursor
-        let _ = atlas.get_glyph('█', false);
-        let _ = atlas.get_glyph('▌', false);
-        let _ = atlas.get_glyph('▐', false);
-        let _ = atlas.get_glyph('▀', false);
-        let _ = atlas.get_glyph('▄', false);
-        // Box drawing for separators
-        let _ = atlas.get_glyph('│', false);
-        let _ = atlas.get_glyph('─', false);
-        // Corner brackets for focus indicator
-        let _ = atlas.get_glyph('┌', false);
-        let _ = atlas.get_glyph('┐', false);
-        let _ = atlas.get_glyph('└', false);
-        let _ = atlas.get_glyph('┘', false);
+        install_fallbacks(&mut atlas, &self.fallback_fonts, true);
+
+        prepopulate_glyphs(&mut atlas);
 
         // Get BDF cell size for tracking
         let (cell_w, cell_h) = atlas.cell_size();
         tracing::info!("BDF font loaded: cell size = {}x{}", cell_w, cell_h);
 
         // Recreate text pipeline with new atlas
+        atlas.set_compression_mode(self.atlas_compression);
         let text_pipeline = TextPipeline::new(
             &self.gpu.device,
             &self.gpu.queue,
@@ -289,14 +538,152 @@ impl Renderer {
             &atlas,
         );
 
+        atlas.set_max_cached_glyphs(self.max_cached_glyphs);
+        atlas.set_max_atlas_size(self.max_atlas_size);
+        atlas.set_eviction_policy(self.atlas_eviction_policy);
+        atlas.set_spacing(self.cell_spacing.0, self.cell_spacing.1 * self.scale_factor);
         self.atlas = atlas;
         self.text_pipeline = text_pipeline;
         self.current_font_size = cell_h;
         self.current_bdf_font = Some(bdf_font);
+        // BDF fonts come in one size, so every pane uses it
+        self.pane_fonts.clear();
 
         Ok(())
     }
 
+    /// Load atlases for the font sizes (physical pixels) panes use besides
+    /// the main one, and drop ones no pane uses anymore. BDF fonts have a
+    /// single size, so with one loaded every pane keeps it.
+    pub fn set_pane_font_sizes(&mut self, font_sizes: &[f32]) -> Result<(), RenderError> {
+        if self.current_bdf_font.is_some() {
+            self.pane_fonts.clear();
+            return Ok(());
+        }
+        let main_key = font_size_key(self.current_font_size);
+        let keys: Vec<u32> = font_sizes
+            .iter()
+            .map(|&size| font_size_key(size))
+            .filter(|&key| key != main_key)
+            .collect();
+        self.pane_fonts.retain(|key, _| keys.contains(key));
+        for key in keys {
+            if self.pane_fonts.contains_key(&key) {
+                continue;
+            }
+            let (atlas, text_pipeline) = self.load_ttf_font(self.current_font, key as f32 / 2.0)?;
+            self.pane_fonts.insert(
+                key,
+                PaneFont {
+                    atlas,
+                    text_pipeline,
+                },
+            );
+        }
+        Ok(())
+    }
+
+    /// The atlas for a pane font size, or the main one
+    fn atlas_for(&self, font_size: Option<f32>) -> &GlyphAtlas {
+        font_size
+            .and_then(|size| self.pane_fonts.get(&font_size_key(size)))
+            .map_or(&self.atlas, |font| &font.atlas)
+    }
+
+    fn atlases_mut(&mut self) -> impl Iterator<Item = &mut GlyphAtlas> {
+        std::iter::once(&mut self.atlas).chain(self.pane_fonts.values_mut().map(|f| &mut f.atlas))
+    }
+
+    /// Limit how many glyphs the atlas caches before evicting old ones
+    pub fn set_max_cached_glyphs(&mut self, max: Option<usize>) {
+        self.max_cached_glyphs = max;
+        for atlas in self.atlases_mut() {
+            atlas.set_max_cached_glyphs(max);
+        }
+    }
+
+    /// Limit glyph atlas growth, clamped to what the GPU supports
+    pub fn set_max_atlas_size(&mut self, size: u32) {
+        let limit = self.gpu.device.limits().max_texture_dimension_2d;
+        self.max_atlas_size = size.min(limit);
+        let size = self.max_atlas_size;
+        for atlas in self.atlases_mut() {
+            atlas.set_max_atlas_size(size);
+        }
+    }
+
+    /// Set the row height multiplier and extra column spacing. Grid sizes
+    /// derived from `cell_size` change, so callers should resize terminals.
+    /// Returns true if the spacing changed.
+    pub fn set_cell_spacing(&mut self, line_height_mult: f32, letter_spacing: f32) -> bool {
+        if self.cell_spacing == (line_height_mult, letter_spacing) {
+            return false;
+        }
+        self.cell_spacing = (line_height_mult, letter_spacing);
+        let letter_spacing = letter_spacing * self.scale_factor;
+        for atlas in self.atlases_mut() {
+            atlas.set_spacing(line_height_mult, letter_spacing);
+        }
+        true
+    }
+
+    pub fn set_pane_border_style(&mut self, style: PaneBorderStyle) {
+        self.pane_border_style = style;
+    }
+
+    /// Set the physical pixels per logical pixel (the display scale factor).
+    /// Fonts are not re-rasterized here: pass `set_font` the size multiplied
+    /// by the same factor. Returns true if the factor changed.
+    pub fn set_scale_factor(&mut self, scale_factor: f32) -> bool {
+        let scale_factor = scale_factor.max(0.25);
+        if (scale_factor - self.scale_factor).abs() < f32::EPSILON {
+            return false;
+        }
+        self.scale_factor = scale_factor;
+        let (line_height_mult, letter_spacing) = self.cell_spacing;
+        for atlas in self.atlases_mut() {
+            atlas.set_spacing(line_height_mult, letter_spacing * scale_factor);
+        }
+        true
+    }
+
+    pub fn scale_factor(&self) -> f32 {
+        self.scale_factor
+    }
+
+    /// Choose whether a full glyph atlas grows, evicts old glyphs or fails
+    pub fn set_atlas_eviction_policy(&mut self, policy: AtlasEvictionPolicy) {
+        self.atlas_eviction_policy = policy;
+        for atlas in self.atlases_mut() {
+            atlas.set_eviction_policy(policy);
+        }
+    }
+
+    /// Choose how the glyph atlas texture is stored. Recreates the text
+    /// pipeline, since the texture format changes.
+    pub fn set_atlas_compression(&mut self, mode: AtlasCompressionMode) {
+        if mode == self.atlas_compression {
+            return;
+        }
+        self.atlas_compression = mode;
+        self.atlas.set_compression_mode(mode);
+        self.text_pipeline = TextPipeline::new(
+            &self.gpu.device,
+            &self.gpu.queue,
+            self.gpu.config.format,
+            &self.atlas,
+        );
+        for font in self.pane_fonts.values_mut() {
+            font.atlas.set_compression_mode(mode);
+            font.text_pipeline = TextPipeline::new(
+                &self.gpu.device,
+                &self.gpu.queue,
+                self.gpu.config.format,
+                &font.atlas,
+            );
+        }
+    }
+
     fn create_offscreen_texture(
         device: &wgpu::Device,
         width: u32,
@@ -325,12 +712,19 @@ impl Renderer {
         self.gpu.resize(width, height);
         self.text_pipeline
             .update_screen_size(&self.gpu.queue, width as f32, height as f32);
+        for font in self.pane_fonts.values() {
+            font.text_pipeline
+                .update_screen_size(&self.gpu.queue, width as f32, height as f32);
+        }
 
         // Recreate off-screen texture at new size
         let (offscreen_texture, offscreen_view) =
             Self::create_offscreen_texture(&self.gpu.device, width, height, self.gpu.config.format);
         self.offscreen_texture = offscreen_texture;
         self.offscreen_view = offscreen_view;
+        self.blit_bind_group = self
+            .blit_pipeline
+            .create_bind_group(&self.gpu.device, &self.offscreen_view);
 
         // Resize burn-in textures
         self.burnin_pipeline
@@ -342,8 +736,27 @@ impl Renderer {
             .create_bind_group(&self.gpu.device, self.burnin_pipeline.output_view());
     }
 
+    /// Cell size in logical pixels
     pub fn cell_size(&self) -> (f32, f32) {
-        self.atlas.cell_size()
+        self.cell_size_for(None)
+    }
+
+    /// Cell size in logical pixels of a pane with its own font size
+    /// (physical pixels, as given to `set_pane_font_sizes`)
+    pub fn cell_size_for(&self, font_size: Option<f32>) -> (f32, f32) {
+        let (cell_w, cell_h) = self.atlas_for(font_size).cell_size();
+        (cell_w / self.scale_factor, cell_h / self.scale_factor)
+    }
+
+    /// Cell size in physical pixels, as reported to programs through the PTY
+    pub fn cell_pixel_size(&self) -> (u16, u16) {
+        self.cell_pixel_size_for(None)
+    }
+
+    /// Cell size in physical pixels of a pane with its own font size
+    pub fn cell_pixel_size_for(&self, font_size: Option<f32>) -> (u16, u16) {
+        let (cell_w, cell_h) = self.atlas_for(font_size).cell_size();
+        (cell_w.round() as u16, cell_h.round() as u16)
     }
 
     /// Reset CRT time to replay the power-on animation
@@ -351,30 +764,84 @@ impl Renderer {
         self.crt_pipeline.reset_time();
     }
 
+    /// Time the text, burn-in and CRT passes on the GPU. Does nothing if the
+    /// adapter lacks timestamp queries.
+    pub fn set_pass_timing(&mut self, enabled: bool) {
+        if enabled == self.pass_timing {
+            return;
+        }
+        self.pass_timing = enabled;
+        self.gpu_timer = if enabled {
+            let timer = GpuTimer::new(&self.gpu.device, &self.gpu.queue);
+            if timer.is_none() {
+                tracing::info!("GPU pass timing unavailable: no timestamp query support");
+            }
+            timer
+        } else {
+            None
+        };
+    }
+
+    /// GPU time of each pass in a recent frame, once timing is on and the
+    /// first results have been read back
+    pub fn last_pass_timings(&self) -> Option<PassTimings> {
+        self.gpu_timer.as_ref().and_then(GpuTimer::last)
+    }
+
+    /// The glyph atlas, for reporting its size and how full it is
+    pub fn atlas(&self) -> &GlyphAtlas {
+        &self.atlas
+    }
+
+    /// Whether the power-on animation still needs frames. It is part of the
+    /// CRT pass, so it doesn't play while effects are off.
+    pub fn power_on_active(&self) -> bool {
+        self.effects_enabled && self.crt_pipeline.power_on_active()
+    }
+
     /// Calculate how many columns and rows fit in the current window
     pub fn grid_size(&self) -> (u16, u16) {
-        let (cell_w, cell_h) = self.atlas.cell_size();
         let (width, height) = self.gpu.size;
-        let cols = (width as f32 / cell_w).floor() as u16;
-        let rows = (height as f32 / cell_h).floor() as u16;
-        (cols.max(1), rows.max(1))
+        grid_dimensions(width as f32, height as f32, self.atlas.cell_size())
     }
 
-    /// Calculate grid size for a region (in pixels)
-    pub fn grid_size_for_region(&self, width_px: u32, height_px: u32) -> (u16, u16) {
-        let (cell_w, cell_h) = self.atlas.cell_size();
-        let cols = (width_px as f32 / cell_w).floor() as u16;
-        let rows = (height_px as f32 / cell_h).floor() as u16;
-        (cols.max(1), rows.max(1))
+    /// Calculate grid size for a region (in logical pixels), for a pane with
+    /// the given font size or the main one
+    pub fn grid_size_for_region(
+        &self,
+        width_px: u32,
+        height_px: u32,
+        font_size: Option<f32>,
+    ) -> (u16, u16) {
+        grid_dimensions(
+            width_px as f32,
+            height_px as f32,
+            self.cell_size_for(font_size),
+        )
     }
 
-    /// Get window size in pixels
+    /// Get window size in logical pixels
     pub fn window_size(&self) -> (u32, u32) {
-        self.gpu.size
+        let (width, height) = self.gpu.size;
+        (
+            (width as f32 / self.scale_factor).round() as u32,
+            (height as f32 / self.scale_factor).round() as u32,
+        )
     }
 
-    /// Render a grid of cells with CRT post-processing
+    /// Render a full-window grid with the `EffectParams::crt_demo` look
     pub fn render_grid(&mut self, cells: &[Vec<RenderCell>]) -> Result<(), RenderError> {
+        self.render_grid_with(cells, &EffectParams::crt_demo())
+    }
+
+    /// Render a full-window grid as a single pane with the given effects.
+    /// There is no burn-in pass here, so `burn_in` and the beam simulation
+    /// settings are ignored.
+    pub fn render_grid_with(
+        &mut self,
+        cells: &[Vec<RenderCell>],
+        effects: &EffectParams,
+    ) -> Result<(), RenderError> {
         let (width, height) = self.gpu.size;
         let (cell_w, cell_h) = self.atlas.cell_size();
         let ascent = self.atlas.ascent();
@@ -384,7 +851,7 @@ impl Renderer {
         let dt = now.duration_since(self.last_frame).as_secs_f32();
         self.last_frame = now;
 
-        let mut chars: Vec<(char, f32, f32, [f32; 4], bool)> = Vec::new();
+        let mut chars: Vec<TextChar> = Vec::new();
 
         for (row_idx, row) in cells.iter().enumerate() {
             let baseline_y = (row_idx as f32 * cell_h) + ascent;
@@ -394,15 +861,26 @@ impl Renderer {
                     continue;
                 }
 
-                let x = col_idx as f32 * cell_w;
-                chars.push((cell.c, x, baseline_y, cell.fg, cell.is_wide));
+                // Double-size lines show only the first half of the row
+                let Some(x) = column_x(col_idx, row.len(), cell_w, cell.line_size) else {
+                    break;
+                };
+                chars.push((
+                    cell.c,
+                    x,
+                    baseline_y,
+                    cell.fg,
+                    cell.is_wide,
+                    cell.style,
+                    cell.line_size,
+                ));
             }
         }
 
         self.text_pipeline
             .update_screen_size(&self.gpu.queue, width as f32, height as f32);
         self.text_pipeline
-            .prepare(&self.gpu.queue, &mut self.atlas, &chars);
+            .prepare(&self.gpu.device, &self.gpu.queue, &mut self.atlas, &chars);
 
         // Update CRT uniforms (whole-screen mode for simple grid render)
         let (_, cell_height) = self.atlas.cell_size();
@@ -411,28 +889,43 @@ impl Renderer {
             width as f32,
             height as f32,
             dt,
+            effects.beam_paused && effects.beam_step_count == 0,
             false,                   // whole-screen mode
             &[(0.0, 0.0, 1.0, 1.0)], // single full-screen pane
             -1,                      // no focused pane
             cell_height,
-            0.03,                 // default curvature
-            0.3,                  // default scanlines
-            0,                    // row-based scanlines (default)
-            0.3,                  // default bloom
-            0.05,                 // default glow radius
-            0.06,                 // default glow width
-            0.6,                  // default glow intensity
-            0.05,                 // default static noise
-            0.05,                 // default flicker
-            1.0,                  // default brightness
-            0.2,                  // default vignette
-            false,                // bezel disabled for simple render
-            1.0,                  // default content scale x
-            1.0,                  // default content scale y
-            [1.0, 0.7, 0.0, 1.0], // default amber glow
+            effects.curvature,
+            effects.scanline_intensity,
+            effects.scanline_mode,
+            effects.scanline_gamma,
+            effects.bloom,
+            effects.focus_glow_radius,
+            effects.focus_glow_width,
+            effects.focus_glow_intensity,
+            effects.static_noise,
+            effects.flicker,
+            effects.brightness,
+            effects.vignette,
+            effects.rgb_mask,
+            effects.aberration,
+            effects.corner_radius,
+            effects.composite_bleed,
+            effects.bezel_enabled,
+            effects.content_scale_x,
+            effects.content_scale_y,
+            effects.glow_color,
+            effects.flash_color,
+            self.background(effects.background_opacity),
+            effects.white_point,
         );
 
-        let output = self.gpu.surface.get_current_texture()?;
+        self.blit_pipeline.update(
+            &self.gpu.queue,
+            effects.flash_color,
+            self.background(effects.background_opacity),
+        );
+
+        let output = self.acquire_frame()?;
         let screen_view = output
             .texture
             .create_view(&wgpu::TextureViewDescriptor::default());
@@ -464,10 +957,14 @@ impl Renderer {
             self.text_pipeline.render(&mut render_pass);
         }
 
-        // Pass 2: Apply CRT effect to screen
+        // Pass 2: Apply CRT effect to screen, or show the text as is
         {
             let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
-                label: Some("CRT Pass"),
+                label: Some(if effects.enabled {
+                    "CRT Pass"
+                } else {
+                    "Blit Pass"
+                }),
                 color_attachments: &[Some(wgpu::RenderPassColorAttachment {
                     view: &screen_view,
                     resolve_target: None,
@@ -481,8 +978,13 @@ impl Renderer {
                 occlusion_query_set: None,
             });
 
-            self.crt_pipeline
-                .render(&mut render_pass, &self.crt_bind_group);
+            if effects.enabled {
+                self.crt_pipeline
+                    .render(&mut render_pass, &self.crt_bind_group);
+            } else {
+                self.blit_pipeline
+                    .render(&mut render_pass, &self.blit_bind_group);
+            }
         }
 
         self.gpu.queue.submit(std::iter::once(encoder.finish()));
@@ -492,9 +994,14 @@ impl Renderer {
     }
 
     /// Render multiple panes, each with its pixel region and cells
+    /// All positions and sizes are in logical pixels
     /// Each pane is (x_offset, y_offset, cells)
+    /// images are strips of uploaded images drawn over the text of their pane
+    /// font_sizes gives each pane's font size (physical pixels) as passed to
+    /// `set_pane_font_sizes`; None or missing entries use the main font
     /// Separators are (x, y, length, is_vertical) in pixels
     /// focus_rect is (x, y, width, height) in pixels for the focused pane
+    /// cursor_lines are (x1, y1, x2, y2, thickness, color) for underline/beam/hollow cursors
     /// size_indicators are (center_x, center_y, text) for each pane's size display
     /// scrollbars are (x, y, height, thumb_start, thumb_height, opacity) in pixels
     /// pane_rects_normalized are (x, y, width, height) in normalized coords (0-1) for CRT
@@ -507,8 +1014,11 @@ impl Renderer {
     pub fn render_panes(
         &mut self,
         panes: &[(f32, f32, &[Vec<RenderCell>])],
+        font_sizes: &[Option<f32>],
+        images: &[ImageStrip],
         separators: &[(f32, f32, f32, bool)],
         focus_rect: Option<(f32, f32, f32, f32)>,
+        cursor_lines: &[(f32, f32, f32, f32, f32, [f32; 4])],
         size_indicators: &[(f32, f32, String)],
         scrollbars: &[(f32, f32, f32, f32, f32, f32)],
         pane_rects_normalized: &[(f32, f32, f32, f32)],
@@ -519,29 +1029,129 @@ impl Renderer {
         effects: EffectParams,
     ) -> Result<(), RenderError> {
         let (width, height) = self.gpu.size;
-        let (cell_w, cell_h) = self.atlas.cell_size();
+        // Size indicators use the main font
+        let (cell_w, _) = self.atlas.cell_size();
         let ascent = self.atlas.ascent();
 
+        if let Some(timer) = &mut self.gpu_timer {
+            timer.collect(&self.gpu.device);
+        }
+
+        // Everything below works in physical pixels, like the glyph atlas
+        let s = self.scale_factor;
+        let scale_line =
+            |&(x1, y1, x2, y2, thickness, color): &(f32, f32, f32, f32, f32, [f32; 4])| {
+                (x1 * s, y1 * s, x2 * s, y2 * s, thickness * s, color)
+            };
+        let panes: Vec<_> = panes
+            .iter()
+            .map(|&(x, y, cells)| (x * s, y * s, cells))
+            .collect();
+        let separators: Vec<_> = separators
+            .iter()
+            .map(|&(x, y, length, vertical)| (x * s, y * s, length * s, vertical))
+            .collect();
+        let focus_rect = focus_rect.map(|(x, y, w, h)| (x * s, y * s, w * s, h * s));
+        let cursor_lines: Vec<_> = cursor_lines.iter().map(scale_line).collect();
+        let size_indicators: Vec<_> = size_indicators
+            .iter()
+            .map(|(x, y, text)| (x * s, y * s, text.as_str()))
+            .collect();
+        let scrollbars: Vec<_> = scrollbars
+            .iter()
+            .map(|&(x, y, height, thumb_start, thumb_height, opacity)| {
+                (
+                    x * s,
+                    y * s,
+                    height * s,
+                    thumb_start * s,
+                    thumb_height * s,
+                    opacity,
+                )
+            })
+            .collect();
+        let debug_lines: Vec<_> = debug_lines.iter().map(scale_line).collect();
+
+        // Pick up edits to the post-processing shaders while developing
+        #[cfg(any(debug_assertions, feature = "shader-reload"))]
+        {
+            let format = self.gpu.config.format;
+            self.crt_pipeline
+                .maybe_reload(&self.gpu.device, &self.gpu.queue, format);
+            self.burnin_pipeline.maybe_reload(&self.gpu.device, format);
+        }
+
         // Calculate delta time for animations
         let now = Instant::now();
         let dt = now.duration_since(self.last_frame).as_secs_f32();
         self.last_frame = now;
 
-        let mut chars: Vec<(char, f32, f32, [f32; 4], bool)> = Vec::new();
+        let mut chars: Vec<TextChar> = Vec::new();
+        // Text of panes with their own font size, by font size key
+        let mut pane_chars: HashMap<u32, Vec<TextChar>> = HashMap::new();
         let mut cell_backgrounds: Vec<(f32, f32, f32, f32, f32, [f32; 4])> = Vec::new();
 
-        // Render pane contents
-        for &(x_offset, y_offset, cells) in panes {
+        // Font size key of each pane that has an atlas of its own
+        let font_keys: Vec<Option<u32>> = (0..panes.len())
+            .map(|i| {
+                font_sizes
+                    .get(i)
+                    .copied()
+                    .flatten()
+                    .map(font_size_key)
+                    .filter(|key| self.pane_fonts.contains_key(key))
+            })
+            .collect();
+        // Cell size and ascent of each pane's font
+        let metrics: Vec<_> = font_keys
+            .iter()
+            .map(|key| {
+                let atlas = key
+                    .and_then(|key| self.pane_fonts.get(&key))
+                    .map_or(&self.atlas, |font| &font.atlas);
+                (atlas.cell_size(), atlas.ascent())
+            })
+            .collect();
+
+        // Render pane contents. Later panes are drawn over earlier ones, so
+        // cells under them are left out, text included.
+        let extents: Vec<_> = panes
+            .iter()
+            .zip(&metrics)
+            .map(|(&(x, y, cells), &(cell_size, _))| pane_extent(x, y, cells, cell_size))
+            .collect();
+        for (pane_idx, &(x_offset, y_offset, cells)) in panes.iter().enumerate() {
+            let ((cell_w, cell_h), ascent) = metrics[pane_idx];
+            let pane_text = match font_keys[pane_idx] {
+                Some(key) => pane_chars.entry(key).or_default(),
+                None => &mut chars,
+            };
+            let covers: Vec<_> = extents[pane_idx + 1..]
+                .iter()
+                .filter(|cover| overlaps(cover, &extents[pane_idx]))
+                .collect();
             for (row_idx, row) in cells.iter().enumerate() {
                 let baseline_y = y_offset + (row_idx as f32 * cell_h) + ascent;
                 let cell_y = y_offset + (row_idx as f32 * cell_h);
 
                 for (col_idx, cell) in row.iter().enumerate() {
-                    let x = x_offset + col_idx as f32 * cell_w;
+                    // Double-size lines show only the first half of the row
+                    let Some(x) = column_x(col_idx, row.len(), cell_w, cell.line_size) else {
+                        break;
+                    };
+                    let x = x_offset + x;
+                    let center = (x + cell_w / 2.0, cell_y + cell_h / 2.0);
+                    if covers.iter().any(|cover| contains(cover, center)) {
+                        continue;
+                    }
 
                     // Collect cells with non-transparent backgrounds
-                    // Wide chars need 2x cell width for background
-                    let bg_width = if cell.is_wide { cell_w * 2.0 } else { cell_w };
+                    // Wide chars and double-width lines need 2x cell width
+                    let bg_width = match (cell.is_wide, cell.line_size.is_double_width()) {
+                        (true, true) => cell_w * 4.0,
+                        (true, false) | (false, true) => cell_w * 2.0,
+                        (false, false) => cell_w,
+                    };
                     if cell.bg[3] > 0.01 {
                         // Draw as horizontal line with thickness = cell_h
                         let y_center = cell_y + cell_h / 2.0;
@@ -559,34 +1169,80 @@ impl Renderer {
                         continue;
                     }
 
-                    chars.push((cell.c, x, baseline_y, cell.fg, cell.is_wide));
+                    pane_text.push((
+                        cell.c,
+                        x,
+                        baseline_y,
+                        cell.fg,
+                        cell.is_wide,
+                        cell.style,
+                        cell.line_size,
+                    ));
                 }
             }
         }
 
+        // Image strips, left out where a later pane covers them
+        let image_quads: Vec<_> = images
+            .iter()
+            .filter_map(|strip| {
+                let (x, y) = (strip.x * s, strip.y * s);
+                let (w, h) = (strip.width * s, strip.height * s);
+                let covered = extents
+                    .get(strip.pane + 1..)?
+                    .iter()
+                    .any(|cover| overlaps(cover, &(x, y, x + w, y + h)));
+                let instance = ImageInstance {
+                    rect: [x, y, w, h],
+                    uv: strip.uv,
+                };
+                (!covered).then_some((strip.image, instance))
+            })
+            .collect();
+        self.graphics_pipeline
+            .update_screen_size(&self.gpu.queue, width as f32, height as f32);
+        self.graphics_pipeline
+            .prepare(&self.gpu.device, &self.gpu.queue, &image_quads);
+
         // Separators will be drawn via line_pipeline (see below)
 
         // Render size indicators (centered in each pane)
         let size_color = [1.0, 1.0, 1.0, 0.9]; // Bright white
-        for (center_x, center_y, text) in size_indicators {
-            let text_width = text.len() as f32 * cell_w;
+        for &(center_x, center_y, text) in &size_indicators {
+            let text_width = text.chars().count() as f32 * cell_w;
             let start_x = center_x - text_width / 2.0;
             let y = center_y + ascent / 2.0;
 
             for (i, c) in text.chars().enumerate() {
-                chars.push((c, start_x + i as f32 * cell_w, y, size_color, false));
+                chars.push((
+                    c,
+                    start_x + i as f32 * cell_w,
+                    y,
+                    size_color,
+                    false,
+                    GlyphStyle::Regular,
+                    LineSize::Normal,
+                ));
             }
         }
 
         self.text_pipeline
             .update_screen_size(&self.gpu.queue, width as f32, height as f32);
         self.text_pipeline
-            .prepare(&self.gpu.queue, &mut self.atlas, &chars);
+            .prepare(&self.gpu.device, &self.gpu.queue, &mut self.atlas, &chars);
+        for (key, font) in &mut self.pane_fonts {
+            let chars = pane_chars.get(key).map_or(&[][..], Vec::as_slice);
+            font.text_pipeline
+                .update_screen_size(&self.gpu.queue, width as f32, height as f32);
+            font.text_pipeline
+                .prepare(&self.gpu.device, &self.gpu.queue, &mut font.atlas, chars);
+        }
 
         // Prepare lines for rendering (cell backgrounds + separators + focus borders + debug grid)
         // Cell backgrounds are drawn first (underneath text)
         // In per-pane CRT mode, skip separator/focus lines (use shader glow instead)
         let mut all_lines: Vec<(f32, f32, f32, f32, f32, [f32; 4])> = cell_backgrounds;
+        all_lines.extend_from_slice(&cursor_lines);
 
         if !per_pane_crt {
             // Draw separators as lines - use glow color with transparency
@@ -596,12 +1252,18 @@ impl Renderer {
                 effects.glow_color[2],
                 0.6,
             ];
-            let separator_thickness = 1.0;
-            for &(x, y, length, is_vertical) in separators {
-                if is_vertical {
-                    all_lines.push((x, y, x, y + length, separator_thickness, separator_color));
-                } else {
-                    all_lines.push((x, y, x + length, y, separator_thickness, separator_color));
+            let strokes = separator_strokes(self.pane_border_style);
+            for &(x, y, length, is_vertical) in &separators {
+                for &(offset, thickness) in strokes {
+                    let (offset, thickness) =
+                        (offset * self.scale_factor, thickness * self.scale_factor);
+                    if is_vertical {
+                        let x = x + offset;
+                        all_lines.push((x, y, x, y + length, thickness, separator_color));
+                    } else {
+                        let y = y + offset;
+                        all_lines.push((x, y, x + length, y, thickness, separator_color));
+                    }
                 }
             }
 
@@ -642,7 +1304,8 @@ impl Renderer {
             let line_thickness = 1.0;
 
             // Draw grid for each pane
-            for &(x_offset, y_offset, cells) in panes {
+            for (&(x_offset, y_offset, cells), &((cell_w, cell_h), _)) in panes.iter().zip(&metrics)
+            {
                 let num_rows = cells.len();
                 let num_cols = if num_rows > 0 { cells[0].len() } else { 0 };
 
@@ -665,14 +1328,14 @@ impl Renderer {
         }
 
         // Add custom debug lines
-        for &(x1, y1, x2, y2, thickness, color) in debug_lines {
+        for &(x1, y1, x2, y2, thickness, color) in &debug_lines {
             all_lines.push((x1, y1, x2, y2, thickness, color));
         }
 
         // Draw scrollbars
         // Each scrollbar is (x, y, height, thumb_start, thumb_height, opacity)
-        let scrollbar_width = 4.0;
-        for &(x, y, track_height, thumb_start, thumb_height, opacity) in scrollbars {
+        let scrollbar_width = 4.0 * s;
+        for &(x, y, track_height, thumb_start, thumb_height, opacity) in &scrollbars {
             let track_color = [
                 effects.glow_color[0] * 0.2,
                 effects.glow_color[1] * 0.2,
@@ -702,120 +1365,139 @@ impl Renderer {
             .update_screen_size(&self.gpu.queue, width as f32, height as f32);
         self.line_pipeline.prepare(&self.gpu.queue, &all_lines);
 
-        // Update CRT uniforms
-        let (_, cell_height) = self.atlas.cell_size();
-        self.crt_pipeline.update(
-            &self.gpu.queue,
-            width as f32,
-            height as f32,
-            dt,
-            per_pane_crt,
-            pane_rects_normalized,
-            focused_pane_index,
-            cell_height,
-            effects.curvature,
-            effects.scanline_intensity,
-            effects.scanline_mode,
-            effects.bloom,
-            effects.focus_glow_radius,
-            effects.focus_glow_width,
-            effects.focus_glow_intensity,
-            effects.static_noise,
-            effects.flicker,
-            effects.brightness,
-            effects.vignette,
-            effects.bezel_enabled,
-            effects.content_scale_x,
-            effects.content_scale_y,
-            effects.glow_color,
-        );
-
-        // Update burn-in uniforms
-        // Map burn_in (0-1 persistence strength) to decay rate (0 = no persistence, 0.95 = max)
-        // Adjust for frame rate: decay is calibrated for 60fps, so we need decay^(dt * 60)
-        // This ensures consistent burn-in persistence regardless of frame rate
-        let base_decay = effects.burn_in * 0.95;
-        let decay = base_decay.powf(dt * 60.0);
-
-        // When paused, freeze decay (set to 1.0 = no change) unless stepping
-        let effective_decay = if effects.beam_paused && effects.beam_step_count == 0 {
-            1.0 // Freeze - no decay
-        } else {
-            decay
-        };
-
-        // Calculate beam position for sweep simulation
-        // beam_speed_divisor = frames per beam slice (e.g., 4 for 240Hz -> 60 fields/sec)
-        // Uses beam_phase as a drift offset to prevent fixed band positions
-        // Beam simulation runs when beam_speed_divisor > 0, interlacing is a separate option
-        let (beam_y_start, beam_y_end, current_field) = if effects.beam_speed_divisor > 0 {
-            let slices_per_field = effects.beam_speed_divisor as u64;
-            let slice_height = 1.0 / slices_per_field as f64;
-
-            // Base position from integer slice counting (ensures full coverage, no gaps)
-            // With interlacing: cycle is 2x longer (odd field, then even field)
-            // Without interlacing: just cycle through slices
-            let cycle_length = if effects.interlace_enabled {
-                slices_per_field * 2
+        self.effects_enabled = effects.enabled;
+        if effects.enabled {
+            // Update CRT uniforms
+            let (_, cell_height) = self.atlas.cell_size();
+            self.crt_pipeline.update(
+                &self.gpu.queue,
+                width as f32,
+                height as f32,
+                dt,
+                effects.beam_paused && effects.beam_step_count == 0,
+                per_pane_crt,
+                pane_rects_normalized,
+                focused_pane_index,
+                cell_height,
+                effects.curvature,
+                effects.scanline_intensity,
+                effects.scanline_mode,
+                effects.scanline_gamma,
+                effects.bloom,
+                effects.focus_glow_radius,
+                effects.focus_glow_width,
+                effects.focus_glow_intensity,
+                effects.static_noise,
+                effects.flicker,
+                effects.brightness,
+                effects.vignette,
+                effects.rgb_mask,
+                effects.aberration,
+                effects.corner_radius,
+                effects.composite_bleed,
+                effects.bezel_enabled,
+                effects.content_scale_x,
+                effects.content_scale_y,
+                effects.glow_color,
+                effects.flash_color,
+                self.background(effects.background_opacity),
+                effects.white_point,
+            );
+
+            // Update burn-in uniforms
+            // Map burn_in (0-1 persistence strength) to decay rate (0 = no persistence, 0.95 = max)
+            // Adjust for frame rate: decay is calibrated for 60fps, so we need decay^(dt * 60)
+            // This ensures consistent burn-in persistence regardless of frame rate
+            let base_decay = burn_in_decay(effects.burn_in);
+            let decay = base_decay.powf(dt * 60.0);
+
+            // When paused, freeze decay (set to 1.0 = no change) unless stepping
+            let effective_decay = if effects.beam_paused && effects.beam_step_count == 0 {
+                1.0 // Freeze - no decay
             } else {
-                slices_per_field
+                decay
             };
-            let frame_within_cycle = self.frame_count % cycle_length;
-            let current_field = if effects.interlace_enabled {
-                (frame_within_cycle / slices_per_field) as u32
+
+            // Calculate beam position for sweep simulation
+            // beam_speed_divisor = frames per beam slice (e.g., 4 for 240Hz -> 60 fields/sec)
+            // Uses beam_phase as a drift offset to prevent fixed band positions
+            // Beam simulation runs when beam_speed_divisor > 0, interlacing is a separate option
+            let (beam_y_start, beam_y_end, current_field) = if effects.beam_speed_divisor > 0 {
+                let slices_per_field = effects.beam_speed_divisor as u64;
+                let slice_height = 1.0 / slices_per_field as f64;
+
+                // Base position from integer slice counting (ensures full coverage, no gaps)
+                // With interlacing: cycle is 2x longer (odd field, then even field)
+                // Without interlacing: just cycle through slices
+                let cycle_length = if effects.interlace_enabled {
+                    slices_per_field * 2
+                } else {
+                    slices_per_field
+                };
+                let frame_within_cycle = self.frame_count % cycle_length;
+                let current_field = if effects.interlace_enabled {
+                    (frame_within_cycle / slices_per_field) as u32
+                } else {
+                    0 // Always field 0 when not interlacing (paints all lines)
+                };
+                let slice_within_field = frame_within_cycle % slices_per_field;
+                let base_start = slice_within_field as f64 * slice_height;
+
+                // Oscillating drift offset - shifts bands back and forth rather than constant scroll
+                // Multiple sine waves at different frequencies create irregular, less noticeable pattern
+                // Driven by animation time (in 240Hz frame units) so the speed ignores frame rate
+                let t = self.crt_pipeline.time() * 240.0;
+                let drift_offset = 0.05 * (t * 0.007).sin()      // slow primary oscillation
+                                 + 0.03 * (t * 0.023).sin()      // medium secondary
+                                 + 0.02 * (t * 0.047).sin(); // faster tertiary
+                                                             // drift_offset ranges roughly ±0.10, center around 0.5 to keep positive
+                let drift_offset = (drift_offset + 0.5).rem_euclid(1.0);
+
+                // Combine base position with drift (wrapping at 1.0)
+                let beam_y_start = ((base_start + drift_offset) % 1.0) as f32;
+                let beam_y_end = beam_y_start + slice_height as f32;
+
+                (beam_y_start, beam_y_end, current_field)
             } else {
-                0 // Always field 0 when not interlacing (paints all lines)
+                // No beam simulation - paint entire screen
+                (0.0, 1.0, 0)
             };
-            let slice_within_field = frame_within_cycle % slices_per_field;
-            let base_start = slice_within_field as f64 * slice_height;
-
-            // Oscillating drift offset - shifts bands back and forth rather than constant scroll
-            // Multiple sine waves at different frequencies create irregular, less noticeable pattern
-            let t = self.frame_count as f64;
-            let drift_offset = 0.05 * (t * 0.007).sin()      // slow primary oscillation
-                             + 0.03 * (t * 0.023).sin()      // medium secondary
-                             + 0.02 * (t * 0.047).sin(); // faster tertiary
-                                                         // drift_offset ranges roughly ±0.10, center around 0.5 to keep positive
-            let drift_offset = (drift_offset + 0.5).rem_euclid(1.0);
-
-            // Combine base position with drift (wrapping at 1.0)
-            let beam_y_start = ((base_start + drift_offset) % 1.0) as f32;
-            let beam_y_end = beam_y_start + slice_height as f32;
-
-            (beam_y_start, beam_y_end, current_field)
-        } else {
-            // No beam simulation - paint entire screen
-            (0.0, 1.0, 0)
-        };
-
-        // Keep frame_count for other timing needs
-        if !effects.beam_paused {
-            self.frame_count += 1;
-        } else if effects.beam_step_count > 0 {
-            self.frame_count += effects.beam_step_count as u64;
-        }
-
-        self.burnin_pipeline.update(
-            &self.gpu.queue,
-            effective_decay,
-            1.0,
-            beam_y_start,
-            beam_y_end,
-            current_field,
-            effects.interlace_enabled,
-            height as f32,
-        );
 
-        // Prepare burn-in bind groups (needs current frame texture)
-        self.burnin_pipeline
-            .prepare_bind_groups(&self.gpu.device, &self.offscreen_view);
+            // Keep frame_count for other timing needs
+            if !effects.beam_paused {
+                self.frame_count += 1;
+            } else if effects.beam_step_count > 0 {
+                self.frame_count += effects.beam_step_count as u64;
+            }
 
-        // Update CRT bind group to read from burn-in output
-        self.crt_bind_group = self
-            .crt_pipeline
-            .create_bind_group(&self.gpu.device, self.burnin_pipeline.output_view());
+            self.burnin_pipeline.update(
+                &self.gpu.queue,
+                effective_decay,
+                1.0,
+                beam_y_start,
+                beam_y_end,
+                current_field,
+                effects.interlace_enabled,
+                height as f32,
+            );
+
+            // Prepare burn-in bind groups (needs current frame texture)
+            self.burnin_pipeline
+                .prepare_bind_groups(&self.gpu.device, &self.offscreen_view);
+
+            // Update CRT bind group to read from burn-in output
+            self.crt_bind_group = self
+                .crt_pipeline
+                .create_bind_group(&self.gpu.device, self.burnin_pipeline.output_view());
+        } else {
+            self.blit_pipeline.update(
+                &self.gpu.queue,
+                effects.flash_color,
+                self.background(effects.background_opacity),
+            );
+        }
 
-        let output = self.gpu.surface.get_current_texture()?;
+        let output = self.acquire_frame()?;
         let screen_view = output
             .texture
             .create_view(&wgpu::TextureViewDescriptor::default());
@@ -840,23 +1522,80 @@ impl Renderer {
                     },
                 })],
                 depth_stencil_attachment: None,
-                timestamp_writes: None,
+                timestamp_writes: self
+                    .gpu_timer
+                    .as_ref()
+                    .map(|timer| timer.pass_writes(TimedPass::Text)),
                 occlusion_query_set: None,
             });
 
             // Render lines first (cell backgrounds, then separators, focus borders, debug grid)
             self.line_pipeline.render(&mut render_pass);
 
-            // Render text on top
+            // Render text on top, then images over it
             self.text_pipeline.render(&mut render_pass);
+            for font in self.pane_fonts.values() {
+                font.text_pipeline.render(&mut render_pass);
+            }
+            self.graphics_pipeline.render(&mut render_pass);
         }
 
-        // Pass 2: Apply burn-in effect (blend current frame with decayed previous)
-        {
+        if effects.enabled {
+            // Pass 2: Apply burn-in effect (blend current frame with decayed previous)
+            {
+                let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                    label: Some("Burn-in Pass"),
+                    color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                        view: self.burnin_pipeline.target_view(),
+                        resolve_target: None,
+                        ops: wgpu::Operations {
+                            load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
+                            store: wgpu::StoreOp::Store,
+                        },
+                    })],
+                    depth_stencil_attachment: None,
+                    timestamp_writes: self
+                        .gpu_timer
+                        .as_ref()
+                        .map(|timer| timer.pass_writes(TimedPass::BurnIn)),
+                    occlusion_query_set: None,
+                });
+
+                self.burnin_pipeline.render(&mut render_pass);
+            }
+
+            // Pass 3: Apply CRT effect to screen
+            {
+                let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                    label: Some("CRT Pass"),
+                    color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                        view: &screen_view,
+                        resolve_target: None,
+                        ops: wgpu::Operations {
+                            load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
+                            store: wgpu::StoreOp::Store,
+                        },
+                    })],
+                    depth_stencil_attachment: None,
+                    timestamp_writes: self
+                        .gpu_timer
+                        .as_ref()
+                        .map(|timer| timer.pass_writes(TimedPass::Crt)),
+                    occlusion_query_set: None,
+                });
+
+                self.crt_pipeline
+                    .render(&mut render_pass, &self.crt_bind_group);
+            }
+        } else {
+            // Plain mode: present the text pass without post-processing
+            if let Some(timer) = &mut self.gpu_timer {
+                timer.skip(TimedPass::BurnIn);
+            }
             let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
-                label: Some("Burn-in Pass"),
+                label: Some("Blit Pass"),
                 color_attachments: &[Some(wgpu::RenderPassColorAttachment {
-                    view: self.burnin_pipeline.target_view(),
+                    view: &screen_view,
                     resolve_target: None,
                     ops: wgpu::Operations {
                         load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
@@ -864,19 +1603,118 @@ impl Renderer {
                     },
                 })],
                 depth_stencil_attachment: None,
-                timestamp_writes: None,
+                timestamp_writes: self
+                    .gpu_timer
+                    .as_ref()
+                    .map(|timer| timer.pass_writes(TimedPass::Crt)),
                 occlusion_query_set: None,
             });
 
-            self.burnin_pipeline.render(&mut render_pass);
+            self.blit_pipeline
+                .render(&mut render_pass, &self.blit_bind_group);
+        }
+
+        if let Some(timer) = &mut self.gpu_timer {
+            timer.resolve(&mut encoder);
+        }
+        self.gpu.queue.submit(std::iter::once(encoder.finish()));
+        if let Some(timer) = &mut self.gpu_timer {
+            timer.after_submit();
         }
+        output.present();
+
+        // Swap burn-in buffers for next frame
+        if effects.enabled {
+            self.burnin_pipeline.swap();
+        }
+
+        Ok(())
+    }
+
+    /// Whether image `id` is uploaded and can be drawn with an `ImageStrip`
+    pub fn has_image(&self, id: u64) -> bool {
+        self.graphics_pipeline.has_image(id)
+    }
+
+    /// Upload an image (straight RGBA, `width` x `height`) to draw as `id`.
+    /// It's dropped again after going undrawn for a while.
+    pub fn upload_image(&mut self, id: u64, width: u32, height: u32, rgba: &[u8]) {
+        self.graphics_pipeline
+            .upload(&self.gpu.device, &self.gpu.queue, id, (width, height), rgba);
+    }
+
+    /// Whether `background_opacity` below 1 can show the desktop through
+    pub fn supports_transparency(&self) -> bool {
+        self.gpu.transparent
+    }
+
+    /// Window background color for the final pass; alpha is its opacity,
+    /// kept at 1 when the surface can't be composited with the desktop
+    fn background(&self, opacity: f32) -> [f32; 4] {
+        let c = self.clear_color;
+        let opacity = if self.gpu.transparent {
+            opacity.clamp(0.0, 1.0)
+        } else {
+            1.0
+        };
+        [c.r as f32, c.g as f32, c.b as f32, opacity]
+    }
+
+    /// Capture the last frame drawn by `render_panes`, CRT effects included.
+    /// The CRT (or plain blit) pass is replayed into a readable texture with
+    /// the same inputs, so the image matches what is on screen. None if the surface format
+    /// isn't 8-bit RGBA/BGRA or the readback fails.
+    pub fn screenshot(&self) -> Option<image::RgbaImage> {
+        let format = self.gpu.config.format;
+        let swap_red_blue = match format {
+            wgpu::TextureFormat::Rgba8Unorm | wgpu::TextureFormat::Rgba8UnormSrgb => false,
+            wgpu::TextureFormat::Bgra8Unorm | wgpu::TextureFormat::Bgra8UnormSrgb => true,
+            _ => {
+                tracing::warn!("Screenshots are not supported for {:?} surfaces", format);
+                return None;
+            }
+        };
 
-        // Pass 3: Apply CRT effect to screen
+        let (width, height) = self.gpu.size;
+        let size = wgpu::Extent3d {
+            width,
+            height,
+            depth_or_array_layers: 1,
+        };
+        let texture = self.gpu.device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("Screenshot Texture"),
+            size,
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::COPY_SRC,
+            view_formats: &[],
+        });
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+
+        // Rows in a texture-to-buffer copy must be padded to 256 bytes
+        let row_bytes = width * 4;
+        let padded_row_bytes = row_bytes.div_ceil(wgpu::COPY_BYTES_PER_ROW_ALIGNMENT)
+            * wgpu::COPY_BYTES_PER_ROW_ALIGNMENT;
+        let buffer = self.gpu.device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Screenshot Buffer"),
+            size: padded_row_bytes as u64 * height as u64,
+            usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+            mapped_at_creation: false,
+        });
+
+        let mut encoder = self
+            .gpu
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                label: Some("Screenshot Encoder"),
+            });
         {
             let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
-                label: Some("CRT Pass"),
+                label: Some("Screenshot Pass"),
                 color_attachments: &[Some(wgpu::RenderPassColorAttachment {
-                    view: &screen_view,
+                    view: &view,
                     resolve_target: None,
                     ops: wgpu::Operations {
                         load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
@@ -887,18 +1725,56 @@ impl Renderer {
                 timestamp_writes: None,
                 occlusion_query_set: None,
             });
-
-            self.crt_pipeline
-                .render(&mut render_pass, &self.crt_bind_group);
+            if self.effects_enabled {
+                self.crt_pipeline
+                    .render(&mut render_pass, &self.crt_bind_group);
+            } else {
+                self.blit_pipeline
+                    .render(&mut render_pass, &self.blit_bind_group);
+            }
         }
-
+        encoder.copy_texture_to_buffer(
+            texture.as_image_copy(),
+            wgpu::ImageCopyBuffer {
+                buffer: &buffer,
+                layout: wgpu::ImageDataLayout {
+                    offset: 0,
+                    bytes_per_row: Some(padded_row_bytes),
+                    rows_per_image: Some(height),
+                },
+            },
+            size,
+        );
         self.gpu.queue.submit(std::iter::once(encoder.finish()));
-        output.present();
 
-        // Swap burn-in buffers for next frame
-        self.burnin_pipeline.swap();
+        // Block until the copy is done and the buffer is mapped
+        let slice = buffer.slice(..);
+        let (tx, rx) = std::sync::mpsc::channel();
+        slice.map_async(wgpu::MapMode::Read, move |result| {
+            let _ = tx.send(result);
+        });
+        self.gpu.device.poll(wgpu::Maintain::Wait);
+        if let Err(e) = rx.recv().ok()? {
+            tracing::error!("Failed to read back screenshot: {}", e);
+            return None;
+        }
 
-        Ok(())
+        let mut pixels = Vec::with_capacity((row_bytes * height) as usize);
+        for row in slice.get_mapped_range().chunks(padded_row_bytes as usize) {
+            pixels.extend_from_slice(&row[..row_bytes as usize]);
+        }
+        buffer.unmap();
+
+        if swap_red_blue {
+            for pixel in pixels.chunks_exact_mut(4) {
+                pixel.swap(0, 2);
+            }
+        }
+        // The surface may carry any alpha; screenshots are opaque
+        for pixel in pixels.chunks_exact_mut(4) {
+            pixel[3] = 255;
+        }
+        image::RgbaImage::from_raw(width, height, pixels)
     }
 
     /// Render test text (for debugging)
@@ -907,7 +1783,7 @@ impl Renderer {
         let (cell_w, cell_h) = self.atlas.cell_size();
         let ascent = self.atlas.ascent();
         let line_height = cell_h;
-        let mut chars: Vec<(char, f32, f32, [f32; 4], bool)> = Vec::new();
+        let mut chars: Vec<TextChar> = Vec::new();
 
         let mut x = 10.0;
         let mut baseline_y = 10.0 + ascent;
@@ -919,7 +1795,15 @@ impl Renderer {
                 continue;
             }
 
-            chars.push((c, x, baseline_y, self.font_color, false));
+            chars.push((
+                c,
+                x,
+                baseline_y,
+                self.font_color,
+                false,
+                GlyphStyle::Regular,
+                LineSize::Normal,
+            ));
             x += cell_w;
         }
 
@@ -927,9 +1811,9 @@ impl Renderer {
         self.text_pipeline
             .update_screen_size(&self.gpu.queue, width as f32, height as f32);
         self.text_pipeline
-            .prepare(&self.gpu.queue, &mut self.atlas, &chars);
+            .prepare(&self.gpu.device, &self.gpu.queue, &mut self.atlas, &chars);
 
-        let output = self.gpu.surface.get_current_texture()?;
+        let output = self.acquire_frame()?;
         let view = output
             .texture
             .create_view(&wgpu::TextureViewDescriptor::default());
@@ -966,3 +1850,216 @@ impl Renderer {
         Ok(())
     }
 }
+
+/// Rasterize the glyphs nearly every frame needs up front: printable ASCII,
+/// the cursor's block characters and the focus indicator's corners
+fn prepopulate_glyphs(atlas: &mut GlyphAtlas) {
+    let blocks = ['█', '▌', '▐', '▀', '▄'];
+    let corners = ['┌', '┐', '└', '┘'];
+    for c in (' '..='~').chain(blocks).chain(corners) {
+        let _ = atlas.get_glyph(c, false);
+    }
+}
+
+/// Lines making up a pane separator in this style, as (offset across the
+/// separator, thickness) in logical pixels
+fn separator_strokes(style: PaneBorderStyle) -> &'static [(f32, f32)] {
+    match style {
+        PaneBorderStyle::None => &[],
+        PaneBorderStyle::Thin => &[(0.0, 1.0)],
+        PaneBorderStyle::Thick => &[(0.0, 3.0)],
+        PaneBorderStyle::Double => &[(-1.5, 1.0), (1.5, 1.0)],
+    }
+}
+
+/// Fraction of a burn-in trail left after one 60 Hz frame
+fn burn_in_decay(burn_in: f32) -> f32 {
+    burn_in * 0.95
+}
+
+/// How long burn-in trails stay visible after the screen changes: until
+/// they're fainter than half an 8-bit color step
+pub fn burn_in_fade_time(burn_in: f32) -> Duration {
+    const INVISIBLE: f32 = 0.5 / 255.0;
+    let decay = burn_in_decay(burn_in.min(1.0));
+    if decay <= INVISIBLE {
+        return Duration::ZERO;
+    }
+    let frames = INVISIBLE.ln() / decay.ln();
+    Duration::from_secs_f32(frames / 60.0)
+}
+
+/// X offset of a column within its row, or None when a double-width line
+/// pushes it past the end of the row
+fn column_x(col: usize, row_len: usize, cell_w: f32, line_size: LineSize) -> Option<f32> {
+    if line_size.is_double_width() {
+        (col * 2 < row_len).then_some(col as f32 * cell_w * 2.0)
+    } else {
+        Some(col as f32 * cell_w)
+    }
+}
+
+/// Area a pane's cells cover, as (left, top, right, bottom) pixels
+fn pane_extent(
+    x: f32,
+    y: f32,
+    cells: &[Vec<RenderCell>],
+    (cell_w, cell_h): (f32, f32),
+) -> (f32, f32, f32, f32) {
+    let columns = cells.iter().map(Vec::len).max().unwrap_or(0);
+    (
+        x,
+        y,
+        x + columns as f32 * cell_w,
+        y + cells.len() as f32 * cell_h,
+    )
+}
+
+fn overlaps(a: &(f32, f32, f32, f32), b: &(f32, f32, f32, f32)) -> bool {
+    a.0 < b.2 && b.0 < a.2 && a.1 < b.3 && b.1 < a.3
+}
+
+fn contains(extent: &(f32, f32, f32, f32), (x, y): (f32, f32)) -> bool {
+    x >= extent.0 && x < extent.2 && y >= extent.1 && y < extent.3
+}
+
+/// Whole cells that fit in a region. Sizes scaled by a fractional factor
+/// land a hair under exact multiples, so tiny float error is ignored.
+fn grid_dimensions(width: f32, height: f32, (cell_w, cell_h): (f32, f32)) -> (u16, u16) {
+    const EPSILON: f32 = 1e-3;
+    let cols = (width / cell_w + EPSILON).floor() as u16;
+    let rows = (height / cell_h + EPSILON).floor() as u16;
+    (cols.max(1), rows.max(1))
+}
+
+/// Configure the atlas fallback chain, with Unifont last as the catch-all.
+/// Behind BDF fonts only fallbacks that suit bitmap fonts are used.
+fn install_fallbacks(atlas: &mut GlyphAtlas, fonts: &[FallbackFont], bitmap_primary: bool) {
+    let chain: Vec<(&'static str, &'static [u8])> = fonts
+        .iter()
+        .filter(|font| !bitmap_primary || font.suits_bitmap_fonts())
+        .filter_map(|font| match get_fallback_font_data(*font) {
+            Some(data) => Some((font.label(), data)),
+            None => {
+                tracing::info!("Fallback font {} is not installed, skipping", font.label());
+                None
+            }
+        })
+        .collect();
+    if let Err(e) = atlas.set_fallback_chain(&chain) {
+        tracing::warn!("Failed to load fallback fonts: {}", e);
+    }
+    if let Err(e) = atlas.set_bdf_fallback(get_unifont_fallback_data()) {
+        tracing::warn!("Failed to load Unifont fallback: {}", e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_read_bdf_file() {
+        let dir = std::env::temp_dir().join(format!("crt-bdf-test-{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("sub")).unwrap();
+        let good = dir.join("sub/good.bdf");
+        std::fs::write(
+            &good,
+            crate::fonts::get_bdf_font_data(crt_core::BdfFont::Fixed9x18),
+        )
+        .unwrap();
+        let bad = dir.join("bad.BDF");
+        std::fs::write(&bad, "not a font\n").unwrap();
+        std::fs::write(dir.join("notes.txt"), "").unwrap();
+
+        assert_eq!(
+            crate::fonts::find_bdf_fonts(&dir, 1),
+            vec![bad.clone(), good.clone()]
+        );
+        assert_eq!(crate::fonts::find_bdf_fonts(&dir, 0), vec![bad.clone()]);
+        assert!(read_bdf_file(&good).is_ok());
+        assert!(matches!(read_bdf_file(&bad), Err(RenderError::Bdf(_))));
+        assert!(matches!(
+            read_bdf_file(&dir.join("missing.bdf")),
+            Err(RenderError::FontFile { .. })
+        ));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_read_font_file() {
+        let font = Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("../../assets/fonts/modern-inconsolata/Inconsolata.otf");
+        assert!(!read_font_file(&font).unwrap().is_empty());
+        assert!(matches!(
+            read_font_file(Path::new("/nonexistent/font.ttf")),
+            Err(RenderError::FontFile { .. })
+        ));
+        // Not a font
+        let manifest = Path::new(env!("CARGO_MANIFEST_DIR")).join("Cargo.toml");
+        assert!(matches!(
+            read_font_file(&manifest),
+            Err(RenderError::Atlas(_))
+        ));
+    }
+
+    #[test]
+    fn test_burn_in_fade_time() {
+        assert_eq!(burn_in_fade_time(0.0), Duration::ZERO);
+        let strong = burn_in_fade_time(1.0);
+        assert!(burn_in_fade_time(0.3) < strong);
+        // 0.95 per frame takes about two seconds to drop below half a step
+        assert!((strong.as_secs_f32() - 2.0).abs() < 0.1);
+    }
+
+    #[test]
+    fn test_separator_strokes() {
+        assert!(separator_strokes(PaneBorderStyle::None).is_empty());
+        assert_eq!(separator_strokes(PaneBorderStyle::Thin), &[(0.0, 1.0)]);
+        // Double lines straddle the boundary, a pixel of gap between them
+        let double = separator_strokes(PaneBorderStyle::Double);
+        assert_eq!(double.len(), 2);
+        assert_eq!(double[0].0, -double[1].0);
+        assert!(double[1].0 - double[0].0 - double[0].1 >= 1.0);
+    }
+
+    #[test]
+    fn test_later_panes_cover_earlier_ones() {
+        let cells = |columns: usize, rows: usize| -> Vec<Vec<RenderCell>> {
+            (0..rows)
+                .map(|_| {
+                    (0..columns)
+                        .map(|_| RenderCell {
+                            c: 'x',
+                            fg: [1.0; 4],
+                            bg: [0.0; 4],
+                            is_wide: false,
+                            style: GlyphStyle::Regular,
+                            line_size: LineSize::Normal,
+                        })
+                        .collect()
+                })
+                .collect()
+        };
+        let grid = cells(80, 24);
+        let float = cells(20, 5);
+        let below = pane_extent(0.0, 0.0, &grid, (10.0, 20.0));
+        let above = pane_extent(100.0, 40.0, &float, (10.0, 20.0));
+        assert_eq!(above, (100.0, 40.0, 300.0, 140.0));
+        assert!(overlaps(&above, &below));
+        assert!(contains(&above, (105.0, 50.0)));
+        assert!(!contains(&above, (95.0, 50.0)));
+        assert!(!contains(&above, (105.0, 150.0)));
+        assert!(!overlaps(&above, &(300.0, 0.0, 400.0, 40.0)));
+    }
+
+    #[test]
+    fn test_close_font_sizes_share_an_atlas() {
+        assert_eq!(font_size_key(14.0), 28);
+        assert_eq!(font_size_key(14.1), font_size_key(14.0));
+        assert_eq!(font_size_key(14.5), 29);
+        assert_ne!(font_size_key(14.5), font_size_key(14.0));
+        // Scaled sizes land between steps
+        assert_eq!(font_size_key(12.0 * 1.25), 30);
+    }
+}
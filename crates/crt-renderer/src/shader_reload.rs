@@ -0,0 +1,154 @@
+// ABOUTME: Shader source loading and hot-reload for post-processing pipelines.
+// ABOUTME: Debug builds read shaders from disk and watch them for edits; shader-reload watches in release.
+
+use std::borrow::Cow;
+use std::path::{Path, PathBuf};
+#[cfg(any(debug_assertions, feature = "shader-reload"))]
+use std::sync::mpsc::{self, Receiver};
+
+#[cfg(any(debug_assertions, feature = "shader-reload"))]
+use notify::{RecommendedWatcher, RecursiveMode, Watcher};
+
+/// Path of a shader in the source tree's `shaders/` directory
+fn shader_path(file_name: &str) -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("../../shaders")
+        .join(file_name)
+}
+
+/// WGSL source for a shader. Debug builds prefer the file on disk so edits
+/// are picked up; release builds (or a missing file) use the embedded copy.
+pub(crate) fn shader_source(file_name: &str, embedded: &'static str) -> Cow<'static, str> {
+    if cfg!(debug_assertions) {
+        if let Ok(source) = std::fs::read_to_string(shader_path(file_name)) {
+            return Cow::Owned(source);
+        }
+    }
+    Cow::Borrowed(embedded)
+}
+
+/// Fullscreen-triangle pipeline shared by the CRT, burn-in and blit passes
+pub(crate) fn fullscreen_pipeline(
+    device: &wgpu::Device,
+    label: &str,
+    layout: &wgpu::PipelineLayout,
+    shader: &wgpu::ShaderModule,
+    format: wgpu::TextureFormat,
+) -> wgpu::RenderPipeline {
+    device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+        label: Some(label),
+        layout: Some(layout),
+        vertex: wgpu::VertexState {
+            module: shader,
+            entry_point: Some("vs_main"),
+            buffers: &[],
+            compilation_options: wgpu::PipelineCompilationOptions::default(),
+        },
+        fragment: Some(wgpu::FragmentState {
+            module: shader,
+            entry_point: Some("fs_main"),
+            targets: &[Some(wgpu::ColorTargetState {
+                format,
+                blend: None,
+                write_mask: wgpu::ColorWrites::ALL,
+            })],
+            compilation_options: wgpu::PipelineCompilationOptions::default(),
+        }),
+        primitive: wgpu::PrimitiveState {
+            topology: wgpu::PrimitiveTopology::TriangleList,
+            strip_index_format: None,
+            front_face: wgpu::FrontFace::Ccw,
+            cull_mode: None,
+            polygon_mode: wgpu::PolygonMode::Fill,
+            unclipped_depth: false,
+            conservative: false,
+        },
+        depth_stencil: None,
+        multisample: wgpu::MultisampleState::default(),
+        multiview: None,
+        cache: None,
+    })
+}
+
+/// Watches one shader file and rebuilds its pipeline when it changes
+#[cfg(any(debug_assertions, feature = "shader-reload"))]
+pub(crate) struct ShaderWatcher {
+    file_name: &'static str,
+    path: PathBuf,
+    events: Receiver<notify::Result<notify::Event>>,
+    // Dropping the watcher stops the notifications
+    _watcher: RecommendedWatcher,
+}
+
+#[cfg(any(debug_assertions, feature = "shader-reload"))]
+impl ShaderWatcher {
+    /// Start watching `shaders/<file_name>`. None if the source tree isn't
+    /// available (e.g. the binary was moved) or watching isn't supported.
+    pub(crate) fn new(file_name: &'static str) -> Option<Self> {
+        let path = shader_path(file_name).canonicalize().ok()?;
+        let (tx, events) = mpsc::channel();
+        let mut watcher = notify::recommended_watcher(tx)
+            .map_err(|e| tracing::warn!("Shader hot-reload unavailable: {}", e))
+            .ok()?;
+        // Watch the directory: editors often replace the file on save,
+        // which would end a watch on the file itself
+        let dir = path.parent()?;
+        watcher
+            .watch(dir, RecursiveMode::NonRecursive)
+            .map_err(|e| tracing::warn!("Failed to watch {}: {}", dir.display(), e))
+            .ok()?;
+        Some(Self {
+            file_name,
+            path,
+            events,
+            _watcher: watcher,
+        })
+    }
+
+    /// Whether the shader file was modified since the last call
+    fn changed(&self) -> bool {
+        // Drain every pending event so a burst of writes triggers one reload
+        self.events
+            .try_iter()
+            .flatten()
+            .fold(false, |changed, event| {
+                let edited = event.kind.is_modify() || event.kind.is_create();
+                changed || (edited && event.paths.contains(&self.path))
+            })
+    }
+
+    /// If the shader changed, compile it and build a new pipeline with
+    /// `build`. Compile errors are logged and None is returned, so the
+    /// caller keeps its current pipeline.
+    pub(crate) fn reload(
+        &self,
+        device: &wgpu::Device,
+        build: impl FnOnce(&wgpu::ShaderModule) -> wgpu::RenderPipeline,
+    ) -> Option<wgpu::RenderPipeline> {
+        if !self.changed() {
+            return None;
+        }
+        let source = match std::fs::read_to_string(&self.path) {
+            Ok(source) => source,
+            Err(e) => {
+                tracing::error!("Failed to read {}: {}", self.path.display(), e);
+                return None;
+            }
+        };
+
+        // Capture validation errors instead of letting wgpu panic on them
+        device.push_error_scope(wgpu::ErrorFilter::Validation);
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some(self.file_name),
+            source: wgpu::ShaderSource::Wgsl(source.into()),
+        });
+        let pipeline = build(&shader);
+        if let Some(error) = pollster::block_on(device.pop_error_scope()) {
+            tracing::error!("Failed to reload {}:\n{}", self.file_name, error);
+            return None;
+        }
+
+        tracing::info!("Reloaded {}", self.file_name);
+        Some(pipeline)
+    }
+}
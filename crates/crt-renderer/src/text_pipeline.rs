@@ -4,27 +4,50 @@
 use bytemuck::{Pod, Zeroable};
 use wgpu::util::DeviceExt;
 
-use crate::atlas::GlyphAtlas;
+use crt_core::{AtlasEvictionPolicy, LineSize};
 
+use crate::atlas::{AtlasCompressionMode, AtlasSlot, GlyphAtlas, GlyphStyle};
+
+/// Per-glyph instance data; the vertex shader expands each into a quad
 #[repr(C)]
-#[derive(Copy, Clone, Debug, Pod, Zeroable)]
-pub struct Vertex {
+#[derive(Copy, Clone, Debug, PartialEq, Pod, Zeroable)]
+pub struct GlyphInstance {
+    /// Pen position: cell x and baseline y, in pixels
     pub position: [f32; 2],
-    pub tex_coords: [f32; 2],
+    /// Glyph bearing (offset_x, offset_y) from the pen position
+    pub offset: [f32; 2],
+    /// Glyph bitmap size in pixels
+    pub size: [f32; 2],
+    /// Atlas UV origin
+    pub uv_origin: [f32; 2],
+    /// Atlas UV extent
+    pub uv_size: [f32; 2],
     pub color: [f32; 4],
+    /// Vertical range (top, bottom) in pixels the glyph is cut to
+    pub clip: [f32; 2],
 }
 
-impl Vertex {
-    const ATTRIBS: [wgpu::VertexAttribute; 3] = wgpu::vertex_attr_array![
+/// A glyph to draw: char, x, baseline_y, color, is_wide, style, line_size
+pub type TextChar = (char, f32, f32, [f32; 4], bool, GlyphStyle, LineSize);
+
+/// Clip range for glyphs that may draw anywhere
+const NO_CLIP: [f32; 2] = [-1.0e9, 1.0e9];
+
+impl GlyphInstance {
+    const ATTRIBS: [wgpu::VertexAttribute; 7] = wgpu::vertex_attr_array![
         0 => Float32x2,
         1 => Float32x2,
-        2 => Float32x4,
+        2 => Float32x2,
+        3 => Float32x2,
+        4 => Float32x2,
+        5 => Float32x4,
+        6 => Float32x2,
     ];
 
     pub fn desc() -> wgpu::VertexBufferLayout<'static> {
         wgpu::VertexBufferLayout {
-            array_stride: std::mem::size_of::<Vertex>() as wgpu::BufferAddress,
-            step_mode: wgpu::VertexStepMode::Vertex,
+            array_stride: std::mem::size_of::<GlyphInstance>() as wgpu::BufferAddress,
+            step_mode: wgpu::VertexStepMode::Instance,
             attributes: &Self::ATTRIBS,
         }
     }
@@ -39,15 +62,21 @@ struct Uniforms {
 
 pub struct TextPipeline {
     pipeline: wgpu::RenderPipeline,
+    bind_group_layout: wgpu::BindGroupLayout,
     bind_group: wgpu::BindGroup,
     uniform_buffer: wgpu::Buffer,
-    vertex_buffer: wgpu::Buffer,
-    index_buffer: wgpu::Buffer,
+    atlas_sampler: wgpu::Sampler,
+    instance_buffer: wgpu::Buffer,
     atlas_texture: wgpu::Texture,
+    atlas_format: wgpu::TextureFormat,
     atlas_width: u32,
     atlas_height: u32,
+    /// Whether we've already reported the atlas reaching its size cap
+    reported_full: bool,
     max_chars: usize,
-    num_indices: u32,
+    num_instances: u32,
+    /// Input of the last prepare, to skip rebuilding identical frames
+    last_chars: Vec<TextChar>,
 }
 
 impl TextPipeline {
@@ -62,44 +91,28 @@ impl TextPipeline {
             source: wgpu::ShaderSource::Wgsl(include_str!("../../../shaders/text.wgsl").into()),
         });
 
-        // Create atlas texture
+        // Create atlas texture, block-compressed if requested and supported
+        let bc4 = atlas.compression_mode() == AtlasCompressionMode::Bc4
+            && device
+                .features()
+                .contains(wgpu::Features::TEXTURE_COMPRESSION_BC);
+        let atlas_format = if bc4 {
+            wgpu::TextureFormat::Bc4RUnorm
+        } else {
+            wgpu::TextureFormat::R8Unorm
+        };
+        tracing::info!("Glyph atlas texture format: {:?}", atlas_format);
         let (atlas_width, atlas_height) = atlas.atlas_dimensions();
-        let atlas_texture = device.create_texture(&wgpu::TextureDescriptor {
-            label: Some("Glyph Atlas"),
-            size: wgpu::Extent3d {
-                width: atlas_width,
-                height: atlas_height,
-                depth_or_array_layers: 1,
-            },
-            mip_level_count: 1,
-            sample_count: 1,
-            dimension: wgpu::TextureDimension::D2,
-            format: wgpu::TextureFormat::R8Unorm,
-            usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
-            view_formats: &[],
-        });
+        let atlas_texture = create_atlas_texture(device, atlas_format, atlas_width, atlas_height);
+        let (width, height) = atlas.atlas_dimensions();
+        let whole = AtlasSlot {
+            x: 0,
+            y: 0,
+            width,
+            height,
+        };
+        upload_atlas(queue, &atlas_texture, atlas_format, atlas, whole);
 
-        queue.write_texture(
-            wgpu::ImageCopyTexture {
-                texture: &atlas_texture,
-                mip_level: 0,
-                origin: wgpu::Origin3d::ZERO,
-                aspect: wgpu::TextureAspect::All,
-            },
-            atlas.atlas_data(),
-            wgpu::ImageDataLayout {
-                offset: 0,
-                bytes_per_row: Some(atlas_width),
-                rows_per_image: Some(atlas_height),
-            },
-            wgpu::Extent3d {
-                width: atlas_width,
-                height: atlas_height,
-                depth_or_array_layers: 1,
-            },
-        );
-
-        let atlas_view = atlas_texture.create_view(&wgpu::TextureViewDescriptor::default());
         let atlas_sampler = device.create_sampler(&wgpu::SamplerDescriptor {
             label: Some("Atlas Sampler"),
             address_mode_u: wgpu::AddressMode::ClampToEdge,
@@ -151,24 +164,13 @@ impl TextPipeline {
             ],
         });
 
-        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
-            label: Some("Text Bind Group"),
-            layout: &bind_group_layout,
-            entries: &[
-                wgpu::BindGroupEntry {
-                    binding: 0,
-                    resource: uniform_buffer.as_entire_binding(),
-                },
-                wgpu::BindGroupEntry {
-                    binding: 1,
-                    resource: wgpu::BindingResource::TextureView(&atlas_view),
-                },
-                wgpu::BindGroupEntry {
-                    binding: 2,
-                    resource: wgpu::BindingResource::Sampler(&atlas_sampler),
-                },
-            ],
-        });
+        let bind_group = create_bind_group(
+            device,
+            &bind_group_layout,
+            &uniform_buffer,
+            &atlas_texture,
+            &atlas_sampler,
+        );
 
         let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
             label: Some("Text Pipeline Layout"),
@@ -182,7 +184,7 @@ impl TextPipeline {
             vertex: wgpu::VertexState {
                 module: &shader,
                 entry_point: Some("vs_main"),
-                buffers: &[Vertex::desc()],
+                buffers: &[GlyphInstance::desc()],
                 compilation_options: wgpu::PipelineCompilationOptions::default(),
             },
             fragment: Some(wgpu::FragmentState {
@@ -210,33 +212,30 @@ impl TextPipeline {
             cache: None,
         });
 
-        // Pre-allocate buffers for up to 10000 characters
+        // Pre-allocate one instance per character
         let max_chars = 50000; // Support large terminals with many characters
-        let vertex_buffer = device.create_buffer(&wgpu::BufferDescriptor {
-            label: Some("Text Vertex Buffer"),
-            size: (max_chars * 4 * std::mem::size_of::<Vertex>()) as u64,
+        let instance_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Text Instance Buffer"),
+            size: (max_chars * std::mem::size_of::<GlyphInstance>()) as u64,
             usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
             mapped_at_creation: false,
         });
 
-        let index_buffer = device.create_buffer(&wgpu::BufferDescriptor {
-            label: Some("Text Index Buffer"),
-            size: (max_chars * 6 * std::mem::size_of::<u32>()) as u64,
-            usage: wgpu::BufferUsages::INDEX | wgpu::BufferUsages::COPY_DST,
-            mapped_at_creation: false,
-        });
-
         Self {
             pipeline,
+            bind_group_layout,
             bind_group,
             uniform_buffer,
-            vertex_buffer,
-            index_buffer,
+            atlas_sampler,
+            instance_buffer,
             atlas_texture,
+            atlas_format,
             atlas_width,
             atlas_height,
+            reported_full: false,
             max_chars,
-            num_indices: 0,
+            num_instances: 0,
+            last_chars: Vec::new(),
         }
     }
 
@@ -253,109 +252,282 @@ impl TextPipeline {
 
     pub fn prepare(
         &mut self,
+        device: &wgpu::Device,
         queue: &wgpu::Queue,
         atlas: &mut GlyphAtlas,
-        chars: &[(char, f32, f32, [f32; 4], bool)], // char, x, baseline_y, color, is_wide
+        chars: &[TextChar],
     ) {
-        // Update atlas texture with latest glyph data (new glyphs may have been added)
-        queue.write_texture(
-            wgpu::ImageCopyTexture {
-                texture: &self.atlas_texture,
-                mip_level: 0,
-                origin: wgpu::Origin3d::ZERO,
-                aspect: wgpu::TextureAspect::All,
-            },
-            atlas.atlas_data(),
-            wgpu::ImageDataLayout {
-                offset: 0,
-                bytes_per_row: Some(self.atlas_width),
-                rows_per_image: Some(self.atlas_height),
-            },
-            wgpu::Extent3d {
-                width: self.atlas_width,
-                height: self.atlas_height,
-                depth_or_array_layers: 1,
-            },
-        );
-
-        let mut vertices = Vec::with_capacity(chars.len() * 4);
-        let mut indices = Vec::with_capacity(chars.len() * 6);
+        // Unchanged text: the buffers and atlas texture already hold this frame
+        if chars == self.last_chars.as_slice() {
+            return;
+        }
 
-        for (i, &(c, x, baseline_y, color, is_wide)) in chars.iter().enumerate() {
-            let glyph = match atlas.get_glyph(c, is_wide) {
-                Ok(g) => g,
-                Err(_) => continue,
+        // A full atlas still works, but new glyphs now evict older ones
+        // (or, with the Error policy, are skipped)
+        if atlas.is_full() && !self.reported_full {
+            let (width, height) = atlas.atlas_dimensions();
+            let consequence = match atlas.eviction_policy() {
+                AtlasEvictionPolicy::Error => "new glyphs will not be drawn",
+                _ => "evicting least-recently-used glyphs",
             };
+            tracing::warn!("Glyph atlas full at {}x{}; {}", width, height, consequence);
+        }
+        self.reported_full = atlas.is_full();
+
+        // Glyphs touched from here on count as used this frame and are
+        // protected from eviction until the next prepare
+        atlas.begin_frame();
+        let mut instances = build_instances(atlas, chars, self.max_chars);
+
+        // New glyphs may have grown the atlas: swap in a larger texture and
+        // rebuild the quads, since UVs computed before the growth are stale
+        if atlas.atlas_dimensions() != (self.atlas_width, self.atlas_height) {
+            let (atlas_width, atlas_height) = atlas.atlas_dimensions();
+            self.atlas_texture =
+                create_atlas_texture(device, self.atlas_format, atlas_width, atlas_height);
+            self.atlas_width = atlas_width;
+            self.atlas_height = atlas_height;
+            self.bind_group = create_bind_group(
+                device,
+                &self.bind_group_layout,
+                &self.uniform_buffer,
+                &self.atlas_texture,
+                &self.atlas_sampler,
+            );
+            instances = build_instances(atlas, chars, self.max_chars);
+        }
 
-            if glyph.width == 0 || glyph.height == 0 {
-                continue;
-            }
-
-            // offset_x is xmin (horizontal bearing)
-            // offset_y is ymin - distance from baseline to bottom of glyph
-            // In screen coords (Y down), glyph top is at baseline_y - (height + ymin)
-            let x0 = x + glyph.offset_x;
-            let y0 = baseline_y - glyph.height as f32 - glyph.offset_y;
-            let x1 = x0 + glyph.width as f32;
-            let y1 = y0 + glyph.height as f32;
-
-            let u0 = glyph.uv_x;
-            let v0 = glyph.uv_y;
-            let u1 = glyph.uv_x + glyph.uv_width;
-            let v1 = glyph.uv_y + glyph.uv_height;
-
-            let base = (vertices.len() / 4) as u32 * 4;
-
-            vertices.push(Vertex {
-                position: [x0, y0],
-                tex_coords: [u0, v0],
-                color,
-            });
-            vertices.push(Vertex {
-                position: [x1, y0],
-                tex_coords: [u1, v0],
-                color,
-            });
-            vertices.push(Vertex {
-                position: [x1, y1],
-                tex_coords: [u1, v1],
-                color,
-            });
-            vertices.push(Vertex {
-                position: [x0, y1],
-                tex_coords: [u0, v1],
-                color,
-            });
-
-            indices.push(base);
-            indices.push(base + 1);
-            indices.push(base + 2);
-            indices.push(base);
-            indices.push(base + 2);
-            indices.push(base + 3);
-
-            if i >= self.max_chars - 1 {
-                break;
-            }
+        // Upload after building instances so glyphs rasterized this frame are
+        // included. A grown atlas is dirty all over.
+        if let Some(region) = atlas.take_dirty_region() {
+            upload_atlas(queue, &self.atlas_texture, self.atlas_format, atlas, region);
         }
 
-        if !vertices.is_empty() {
-            queue.write_buffer(&self.vertex_buffer, 0, bytemuck::cast_slice(&vertices));
-            queue.write_buffer(&self.index_buffer, 0, bytemuck::cast_slice(&indices));
+        if !instances.is_empty() {
+            queue.write_buffer(&self.instance_buffer, 0, bytemuck::cast_slice(&instances));
         }
 
-        self.num_indices = indices.len() as u32;
+        self.num_instances = instances.len() as u32;
+        self.last_chars.clear();
+        self.last_chars.extend_from_slice(chars);
     }
 
     pub fn render<'a>(&'a self, render_pass: &mut wgpu::RenderPass<'a>) {
-        if self.num_indices == 0 {
+        if self.num_instances == 0 {
             return;
         }
 
         render_pass.set_pipeline(&self.pipeline);
         render_pass.set_bind_group(0, &self.bind_group, &[]);
-        render_pass.set_vertex_buffer(0, self.vertex_buffer.slice(..));
-        render_pass.set_index_buffer(self.index_buffer.slice(..), wgpu::IndexFormat::Uint32);
-        render_pass.draw_indexed(0..self.num_indices, 0, 0..1);
+        render_pass.set_vertex_buffer(0, self.instance_buffer.slice(..));
+        // Two triangles per glyph, one instance per glyph
+        render_pass.draw(0..6, 0..self.num_instances);
+    }
+}
+
+/// Build one instance per visible glyph, up to `max_chars`
+fn build_instances(
+    atlas: &mut GlyphAtlas,
+    chars: &[TextChar],
+    max_chars: usize,
+) -> Vec<GlyphInstance> {
+    let mut instances = Vec::with_capacity(chars.len().min(max_chars));
+    let (_, cell_h) = atlas.cell_size();
+    let ascent = atlas.ascent();
+
+    for &(c, x, baseline_y, color, is_wide, style, line_size) in chars {
+        if instances.len() >= max_chars {
+            break;
+        }
+
+        let glyph = match atlas.get_styled_glyph(c, is_wide, style) {
+            Ok(g) => g,
+            Err(_) => continue,
+        };
+
+        if glyph.width == 0 || glyph.height == 0 {
+            continue;
+        }
+
+        // Double-height lines draw the glyph at 2x over two rows; each
+        // row shows its half, clipped to the row's own bounds
+        let row_top = baseline_y - ascent;
+        let (scale, baseline_y, clip) = match line_size {
+            LineSize::Normal => ([1.0, 1.0], baseline_y, NO_CLIP),
+            LineSize::DoubleWidth => ([2.0, 1.0], baseline_y, NO_CLIP),
+            LineSize::DoubleHeightTop => (
+                [2.0, 2.0],
+                row_top + ascent * 2.0,
+                [row_top, row_top + cell_h],
+            ),
+            LineSize::DoubleHeightBottom => (
+                [2.0, 2.0],
+                row_top - cell_h + ascent * 2.0,
+                [row_top, row_top + cell_h],
+            ),
+        };
+
+        instances.push(GlyphInstance {
+            position: [x, baseline_y],
+            offset: [glyph.offset_x * scale[0], glyph.offset_y * scale[1]],
+            size: [
+                glyph.width as f32 * scale[0],
+                glyph.height as f32 * scale[1],
+            ],
+            uv_origin: [glyph.uv_x, glyph.uv_y],
+            uv_size: [glyph.uv_width, glyph.uv_height],
+            color,
+            clip,
+        });
+    }
+
+    instances
+}
+
+fn create_atlas_texture(
+    device: &wgpu::Device,
+    format: wgpu::TextureFormat,
+    width: u32,
+    height: u32,
+) -> wgpu::Texture {
+    device.create_texture(&wgpu::TextureDescriptor {
+        label: Some("Glyph Atlas"),
+        size: wgpu::Extent3d {
+            width,
+            height,
+            depth_or_array_layers: 1,
+        },
+        mip_level_count: 1,
+        sample_count: 1,
+        dimension: wgpu::TextureDimension::D2,
+        format,
+        usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+        view_formats: &[],
+    })
+}
+
+/// Copy a block-aligned region of the atlas into the texture
+fn upload_atlas(
+    queue: &wgpu::Queue,
+    texture: &wgpu::Texture,
+    format: wgpu::TextureFormat,
+    atlas: &GlyphAtlas,
+    region: AtlasSlot,
+) {
+    let (atlas_width, _) = atlas.atlas_dimensions();
+    // BC4 rows are rows of 4x4 blocks, 8 bytes each. Uncompressed regions
+    // are read in place from the full atlas rows.
+    let compressed;
+    let (data, offset, bytes_per_row, rows) = if format == wgpu::TextureFormat::Bc4RUnorm {
+        compressed = atlas.to_compressed_bc4(region);
+        (
+            compressed.as_slice(),
+            0,
+            region.width / 4 * 8,
+            region.height / 4,
+        )
+    } else {
+        (
+            atlas.atlas_data(),
+            (region.y * atlas_width + region.x) as u64,
+            atlas_width,
+            region.height,
+        )
+    };
+    queue.write_texture(
+        wgpu::ImageCopyTexture {
+            texture,
+            mip_level: 0,
+            origin: wgpu::Origin3d {
+                x: region.x,
+                y: region.y,
+                z: 0,
+            },
+            aspect: wgpu::TextureAspect::All,
+        },
+        data,
+        wgpu::ImageDataLayout {
+            offset,
+            bytes_per_row: Some(bytes_per_row),
+            rows_per_image: Some(rows),
+        },
+        wgpu::Extent3d {
+            width: region.width,
+            height: region.height,
+            depth_or_array_layers: 1,
+        },
+    );
+}
+
+fn create_bind_group(
+    device: &wgpu::Device,
+    layout: &wgpu::BindGroupLayout,
+    uniform_buffer: &wgpu::Buffer,
+    atlas_texture: &wgpu::Texture,
+    atlas_sampler: &wgpu::Sampler,
+) -> wgpu::BindGroup {
+    let atlas_view = atlas_texture.create_view(&wgpu::TextureViewDescriptor::default());
+    device.create_bind_group(&wgpu::BindGroupDescriptor {
+        label: Some("Text Bind Group"),
+        layout,
+        entries: &[
+            wgpu::BindGroupEntry {
+                binding: 0,
+                resource: uniform_buffer.as_entire_binding(),
+            },
+            wgpu::BindGroupEntry {
+                binding: 1,
+                resource: wgpu::BindingResource::TextureView(&atlas_view),
+            },
+            wgpu::BindGroupEntry {
+                binding: 2,
+                resource: wgpu::BindingResource::Sampler(atlas_sampler),
+            },
+        ],
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fonts::{get_font_data, get_unifont_fallback_data};
+
+    #[test]
+    fn test_many_distinct_glyphs_in_one_frame() {
+        let mut atlas = GlyphAtlas::new(get_font_data(crt_core::Font::default()), 32.0).unwrap();
+        atlas.set_bdf_fallback(get_unifont_fallback_data()).unwrap();
+        let initial = atlas.atlas_dimensions();
+        let chars: Vec<TextChar> = (0x4E00..0x4E00 + 5000)
+            .map(|cp| {
+                let c = char::from_u32(cp).unwrap();
+                let x = (cp % 100) as f32 * 64.0;
+                let y = (cp / 100) as f32 * 32.0;
+                (
+                    c,
+                    x,
+                    y,
+                    [1.0; 4],
+                    true,
+                    GlyphStyle::Regular,
+                    LineSize::Normal,
+                )
+            })
+            .collect();
+
+        // As in `prepare`: growth mid-frame makes the first pass stale, and
+        // the second pass must fit without growing again
+        atlas.begin_frame();
+        build_instances(&mut atlas, &chars, 50000);
+        let grown = atlas.atlas_dimensions();
+        assert_ne!(grown, initial);
+        let instances = build_instances(&mut atlas, &chars, 50000);
+        assert_eq!(atlas.atlas_dimensions(), grown);
+
+        assert_eq!(instances.len(), chars.len());
+        for instance in &instances {
+            let [u, v] = instance.uv_origin;
+            let [w, h] = instance.uv_size;
+            assert!(u >= 0.0 && v >= 0.0 && u + w <= 1.0 && v + h <= 1.0);
+        }
     }
 }
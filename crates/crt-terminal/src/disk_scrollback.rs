@@ -0,0 +1,307 @@
+// ABOUTME: Disk-backed scrollback: pages the oldest history rows out to a memory-mapped file.
+// ABOUTME: Cells are stored fixed-width, so any row is read back without parsing the others.
+
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+
+use alacritty_terminal::grid::Dimensions;
+use alacritty_terminal::index::Line;
+use alacritty_terminal::term::Term;
+use memmap2::Mmap;
+
+use crate::scrollback::{SerializedCell, SerializedColor, SerializedLine};
+
+/// Bytes per cell: char (4), fg (4), bg (4), flags (2) and 2 of padding
+const CELL_BYTES: usize = 16;
+
+/// Files created by this process so far, for unique names
+static FILES_CREATED: AtomicU64 = AtomicU64::new(0);
+
+/// Rows paged out of a terminal's history, oldest first. Clones share the
+/// file, which is deleted when the last of them is dropped.
+#[derive(Debug, Clone)]
+pub struct DiskScrollback {
+    rows: Arc<Mutex<RowFile>>,
+}
+
+#[derive(Debug)]
+struct RowFile {
+    file: File,
+    path: PathBuf,
+    /// Offset in the file where each row ends
+    ends: Vec<u64>,
+    /// The file as mapped when last read; remapped once reads go past its end
+    map: Option<Mmap>,
+}
+
+impl Drop for RowFile {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
+
+impl DiskScrollback {
+    /// Create an empty scrollback file in `dir`
+    pub fn create(dir: &Path) -> std::io::Result<Self> {
+        let name = format!(
+            "crt-scrollback-{}-{}",
+            std::process::id(),
+            FILES_CREATED.fetch_add(1, Ordering::Relaxed)
+        );
+        let path = dir.join(name);
+        let file = OpenOptions::new()
+            .read(true)
+            .append(true)
+            .create_new(true)
+            .open(&path)?;
+        Ok(Self {
+            rows: Arc::new(Mutex::new(RowFile {
+                file,
+                path,
+                ends: Vec::new(),
+                map: None,
+            })),
+        })
+    }
+
+    pub fn path(&self) -> PathBuf {
+        self.rows.lock().unwrap().path.clone()
+    }
+
+    pub fn len(&self) -> usize {
+        self.rows.lock().unwrap().ends.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Add rows after the newest one
+    pub fn append(&self, lines: &[SerializedLine]) -> std::io::Result<()> {
+        let mut rows = self.rows.lock().unwrap();
+        let start = rows.ends.last().copied().unwrap_or(0);
+
+        let mut bytes = Vec::new();
+        let mut ends = Vec::with_capacity(lines.len());
+        for line in lines {
+            for cell in &line.cells {
+                encode_cell(cell, &mut bytes);
+            }
+            ends.push(start + bytes.len() as u64);
+        }
+
+        if let Err(e) = rows.file.write_all(&bytes) {
+            // Drop a partial write so the next rows start where `ends` says
+            let _ = rows.file.set_len(start);
+            return Err(e);
+        }
+        rows.ends.extend(ends);
+        Ok(())
+    }
+
+    /// Rows in `range`, where 0 is the oldest. The range is cut off at the newest row.
+    pub fn lines(&self, range: Range<usize>) -> std::io::Result<Vec<SerializedLine>> {
+        let mut rows = self.rows.lock().unwrap();
+        let range = range.start..range.end.min(rows.ends.len());
+        if range.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let needed = rows.ends[range.end - 1];
+        if rows
+            .map
+            .as_ref()
+            .is_none_or(|map| (map.len() as u64) < needed)
+        {
+            // SAFETY: the file has a unique name and is only changed through
+            // this type, which appends past the mapped bytes and drops the map
+            // before truncating.
+            rows.map = Some(unsafe { Mmap::map(&rows.file)? });
+        }
+
+        let RowFile { ends, map, .. } = &*rows;
+        let map = map.as_ref().expect("mapped above");
+        Ok(range
+            .map(|row| {
+                let start = if row == 0 { 0 } else { ends[row - 1] as usize };
+                let cells = map[start..ends[row] as usize]
+                    .chunks_exact(CELL_BYTES)
+                    .map(decode_cell)
+                    .collect();
+                SerializedLine { cells }
+            })
+            .collect())
+    }
+
+    /// Drop every row, e.g. after the application cleared its scrollback
+    pub fn clear(&self) -> std::io::Result<()> {
+        let mut rows = self.rows.lock().unwrap();
+        // Mapped pages past the end of a file can't be read, so unmap first
+        rows.map = None;
+        rows.ends.clear();
+        rows.file.set_len(0)
+    }
+}
+
+/// Move the history rows older than the newest `keep` to `disk`, then let
+/// the history grow back to `limit` rows. Returns how many rows moved.
+pub fn page_out<T>(
+    term: &mut Term<T>,
+    disk: &DiskScrollback,
+    keep: usize,
+    limit: usize,
+) -> std::io::Result<usize> {
+    let grid = term.grid();
+    let history = grid.history_size();
+    if history <= keep {
+        return Ok(0);
+    }
+
+    let count = history - keep;
+    let top = -(history as i32);
+    let lines: Vec<SerializedLine> = (top..top + count as i32)
+        .map(|line| SerializedLine::from_row(&grid[Line(line)]))
+        .collect();
+    disk.append(&lines)?;
+
+    // Shrinking the history drops its oldest rows
+    let grid = term.grid_mut();
+    grid.update_history(keep);
+    grid.update_history(limit);
+    Ok(count)
+}
+
+fn encode_color(color: SerializedColor) -> [u8; 4] {
+    match color {
+        SerializedColor::Named(named) => [0, named, 0, 0],
+        SerializedColor::Indexed(index) => [1, index, 0, 0],
+        SerializedColor::Rgb(r, g, b) => [2, r, g, b],
+    }
+}
+
+fn decode_color(bytes: &[u8]) -> SerializedColor {
+    match bytes[0] {
+        1 => SerializedColor::Indexed(bytes[1]),
+        2 => SerializedColor::Rgb(bytes[1], bytes[2], bytes[3]),
+        _ => SerializedColor::Named(bytes[1]),
+    }
+}
+
+fn encode_cell(cell: &SerializedCell, bytes: &mut Vec<u8>) {
+    bytes.extend_from_slice(&(cell.c as u32).to_le_bytes());
+    bytes.extend_from_slice(&encode_color(cell.fg));
+    bytes.extend_from_slice(&encode_color(cell.bg));
+    bytes.extend_from_slice(&cell.flags.to_le_bytes());
+    bytes.extend_from_slice(&[0, 0]);
+}
+
+fn decode_cell(bytes: &[u8]) -> SerializedCell {
+    let c = u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+    SerializedCell {
+        c: char::from_u32(c).unwrap_or(' '),
+        fg: decode_color(&bytes[4..8]),
+        bg: decode_color(&bytes[8..12]),
+        flags: u16::from_le_bytes([bytes[12], bytes[13]]),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alacritty_terminal::event::VoidListener;
+    use alacritty_terminal::term::test::TermSize;
+    use alacritty_terminal::term::Config;
+    use alacritty_terminal::vte::ansi::Processor;
+
+    fn line(text: &str) -> SerializedLine {
+        SerializedLine {
+            cells: text
+                .chars()
+                .map(|c| SerializedCell {
+                    c,
+                    fg: SerializedColor::Rgb(255, 128, 0),
+                    bg: SerializedColor::Indexed(17),
+                    flags: 0x0101,
+                })
+                .collect(),
+        }
+    }
+
+    fn text(line: &SerializedLine) -> String {
+        line.cells.iter().map(|cell| cell.c).collect()
+    }
+
+    #[test]
+    fn rows_read_back_as_written() {
+        let disk = DiskScrollback::create(&std::env::temp_dir()).unwrap();
+        disk.append(&[line("first"), line("second row")]).unwrap();
+        disk.append(&[line("é ❯ 中")]).unwrap();
+        assert_eq!(disk.len(), 3);
+
+        let lines = disk.lines(0..10).unwrap();
+        let texts: Vec<String> = lines.iter().map(text).collect();
+        assert_eq!(texts, ["first", "second row", "é ❯ 中"]);
+        let cell = &lines[2].cells[0];
+        assert!(matches!(cell.fg, SerializedColor::Rgb(255, 128, 0)));
+        assert!(matches!(cell.bg, SerializedColor::Indexed(17)));
+        assert_eq!(cell.flags, 0x0101);
+
+        // Rows appended after a read are mapped in too
+        disk.append(&[line("later")]).unwrap();
+        assert_eq!(text(&disk.lines(3..4).unwrap()[0]), "later");
+    }
+
+    #[test]
+    fn clearing_empties_the_file() {
+        let disk = DiskScrollback::create(&std::env::temp_dir()).unwrap();
+        disk.append(&[line("old")]).unwrap();
+        disk.lines(0..1).unwrap();
+        disk.clear().unwrap();
+        assert!(disk.is_empty());
+        disk.append(&[line("new")]).unwrap();
+        assert_eq!(text(&disk.lines(0..1).unwrap()[0]), "new");
+    }
+
+    #[test]
+    fn file_is_deleted_with_the_last_clone() {
+        let disk = DiskScrollback::create(&std::env::temp_dir()).unwrap();
+        let path = disk.path();
+        let clone = disk.clone();
+        drop(disk);
+        assert!(path.exists());
+        drop(clone);
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn page_out_moves_the_oldest_history() {
+        let config = Config {
+            scrolling_history: 100,
+            ..Default::default()
+        };
+        let mut term = Term::new(config, &TermSize::new(10, 2), VoidListener);
+        let output: Vec<String> = (1..=12).map(|n| n.to_string()).collect();
+        let mut parser: Processor = Processor::new();
+        parser.advance(&mut term, output.join("\r\n").as_bytes());
+        assert_eq!(term.grid().history_size(), 10);
+
+        let disk = DiskScrollback::create(&std::env::temp_dir()).unwrap();
+        assert_eq!(page_out(&mut term, &disk, 4, 100).unwrap(), 6);
+        assert_eq!(term.grid().history_size(), 4);
+        let texts: Vec<String> = disk.lines(0..6).unwrap().iter().map(text).collect();
+        let texts: Vec<&str> = texts.iter().map(|t| t.trim_end()).collect();
+        assert_eq!(texts, ["1", "2", "3", "4", "5", "6"]);
+        assert_eq!(
+            term.grid()[Line(-4)][alacritty_terminal::index::Column(0)].c,
+            '7'
+        );
+
+        // The history can fill up to the limit again
+        parser.advance(&mut term, "\r\n".repeat(50).as_bytes());
+        assert_eq!(term.grid().history_size(), 54);
+    }
+}
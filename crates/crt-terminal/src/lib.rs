@@ -1,11 +1,23 @@
 // ABOUTME: Terminal emulation and PTY handling.
 // ABOUTME: Wraps alacritty_terminal to provide terminal state and I/O.
 
+pub mod disk_scrollback;
+pub mod line_size;
+pub mod line_tags;
+pub mod modify_other_keys;
 pub mod process_info;
+pub mod recorder;
+pub mod scroll_anchor;
 pub mod scrollback;
+pub mod shell_integration;
+pub mod sixel;
 pub mod terminal;
 
-pub use alacritty_terminal::term::TermMode;
+pub use alacritty_terminal::term::search::RegexSearch;
+pub use alacritty_terminal::term::{ClipboardType, Osc52, TermMode};
+pub use alacritty_terminal::vi_mode::ViMotion;
+pub use alacritty_terminal::vte::ansi::{CursorShape, CursorStyle};
 pub use process_info::get_process_cwd;
-pub use scrollback::ScrollbackData;
-pub use terminal::Terminal;
+pub use recorder::SessionRecorder;
+pub use scrollback::{ScrollbackData, ScrollbackSource};
+pub use terminal::{ClipboardRequest, ShellCommand, Terminal, TextMatch};
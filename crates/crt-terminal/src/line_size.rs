@@ -0,0 +1,125 @@
+// ABOUTME: DEC double-width/double-height lines (ESC # 3/4/5/6) in PTY output.
+// ABOUTME: Tags each line size escape so the line's size is recorded for the renderer.
+
+use crt_core::LineSize;
+
+use crate::line_tags::Tag;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ScanState {
+    Ground,
+    Escape,
+    /// After `ESC #`
+    Hash,
+}
+
+/// Streaming scanner that tags DEC line size escapes for the parser.
+///
+/// alacritty_terminal ignores `ESC # 3`, `# 4`, `# 5` and `# 6`, so each
+/// is followed by a `Tag` naming the size, which `TagHandler` records for
+/// the cursor's line. All input bytes are passed through unchanged.
+#[derive(Debug)]
+pub struct LineSizeScanner {
+    state: ScanState,
+}
+
+impl Default for LineSizeScanner {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl LineSizeScanner {
+    pub fn new() -> Self {
+        Self {
+            state: ScanState::Ground,
+        }
+    }
+
+    /// Process a chunk of PTY output, appending the rewritten bytes to `out`
+    pub fn process(&mut self, input: &[u8], out: &mut Vec<u8>) {
+        for &byte in input {
+            out.push(byte);
+            self.advance(byte, out);
+        }
+    }
+
+    fn advance(&mut self, byte: u8, out: &mut Vec<u8>) {
+        self.state = match (self.state, byte) {
+            (_, 0x1b) => ScanState::Escape,
+            (ScanState::Escape, b'#') => ScanState::Hash,
+            (ScanState::Hash, size) => {
+                let size = match size {
+                    b'3' => Some(LineSize::DoubleHeightTop),
+                    b'4' => Some(LineSize::DoubleHeightBottom),
+                    b'5' => Some(LineSize::Normal),
+                    b'6' => Some(LineSize::DoubleWidth),
+                    _ => None,
+                };
+                if let Some(size) = size {
+                    out.extend_from_slice(Tag::LineSize(size).escape().as_bytes());
+                }
+                ScanState::Ground
+            }
+            _ => ScanState::Ground,
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::line_tags::{LineTags, TagHandler};
+    use alacritty_terminal::event::VoidListener;
+    use alacritty_terminal::index::Line;
+    use alacritty_terminal::term::test::TermSize;
+    use alacritty_terminal::term::{Config, Term};
+    use alacritty_terminal::vte::ansi::Processor;
+
+    fn sizes(chunks: &[&[u8]]) -> Vec<LineSize> {
+        let mut term = Term::new(Config::default(), &TermSize::new(20, 5), VoidListener);
+        let mut tags = LineTags::default();
+        let mut scanner = LineSizeScanner::new();
+        let mut parser: Processor = Processor::new();
+        for chunk in chunks {
+            let mut out = Vec::new();
+            scanner.process(chunk, &mut out);
+            parser.advance(&mut TagHandler::new(&mut term, &mut tags), &out);
+        }
+        (0..5)
+            .map(|line| tags.screen(&term).line_size(Line(line)))
+            .collect()
+    }
+
+    #[test]
+    fn test_line_sizes_tagged() {
+        let sizes = sizes(&[b"\x1b#3Big\r\n\x1b#4Big\r\n\x1b#6Wide\r\nplain\r\n\x1b#6x\x1b#5y"]);
+        assert_eq!(
+            sizes,
+            vec![
+                LineSize::DoubleHeightTop,
+                LineSize::DoubleHeightBottom,
+                LineSize::DoubleWidth,
+                LineSize::Normal,
+                // ESC # 5 makes the line single width again
+                LineSize::Normal,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_escape_split_across_reads_and_cursor_move() {
+        let sizes = sizes(&[b"\x1b#", b"6ab\x1b[3;1Hc"]);
+        assert_eq!(sizes[0], LineSize::DoubleWidth);
+        // The size belongs to the line, not to the cursor
+        assert_eq!(sizes[2], LineSize::Normal);
+    }
+
+    #[test]
+    fn test_other_escapes_untouched() {
+        let mut scanner = LineSizeScanner::new();
+        let mut out = Vec::new();
+        scanner.process(b"\x1b]8;;http://x\x1b\\\x1b#8a", &mut out);
+        assert_eq!(out, b"\x1b]8;;http://x\x1b\\\x1b#8a");
+    }
+}
@@ -0,0 +1,1181 @@
+// ABOUTME: Side tables for prompt marks, DEC line sizes and sixel image rows.
+// ABOUTME: A parser handler records them and moves them with the rows the term scrolls.
+
+use std::collections::BTreeMap;
+use std::ops::Range;
+
+use alacritty_terminal::event::EventListener;
+use alacritty_terminal::grid::{Dimensions, GridCell};
+use alacritty_terminal::index::{Column, Line};
+use alacritty_terminal::term::cell::{Cell, Flags};
+use alacritty_terminal::term::{Term, TermMode};
+use alacritty_terminal::vte::ansi::{
+    cursor_icon::CursorIcon, Attr, CharsetIndex, ClearMode, CursorShape, CursorStyle, Handler,
+    Hyperlink, KeyboardModes, KeyboardModesApplyBehavior, LineClearMode, Mode, ModifyOtherKeys,
+    NamedPrivateMode, PrivateMode, Rgb, ScpCharPath, ScpUpdateMode, StandardCharset,
+    TabulationClearMode,
+};
+use alacritty_terminal::Grid;
+use crt_core::LineSize;
+
+use crate::shell_integration::PromptSpan;
+
+/// URI prefix of the OSC 8 hyperlinks the PTY scanners insert as tags.
+///
+/// alacritty_terminal ignores OSC 133, the DEC line size escapes and sixel
+/// graphics, so the scanners put a hyperlink with a private URI in the
+/// output where each one was. `TagHandler` takes those out before they reach
+/// the term and records them in `LineTags`, so they never touch the cells or
+/// a link the program set itself. `TagLinkFilter` closes links with this
+/// prefix in the program's own output before the scanners see it.
+pub const TAG_URI_PREFIX: &str = "cool-rust-term:";
+
+/// Something the scanners found in the output, recorded at the cursor
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Tag {
+    /// OSC 133;A: a shell prompt starts
+    PromptStart,
+    /// OSC 133;B or C: the prompt ends
+    PromptEnd,
+    /// `ESC # 3/4/5/6` sets the size of the cursor's line
+    LineSize(LineSize),
+    /// Row `row` (in cell heights) of sixel image `id` starts at the cursor
+    Image { id: u64, row: usize },
+}
+
+fn line_size_name(size: LineSize) -> &'static str {
+    match size {
+        LineSize::Normal => "normal",
+        LineSize::DoubleWidth => "double-width",
+        LineSize::DoubleHeightTop => "double-height-top",
+        LineSize::DoubleHeightBottom => "double-height-bottom",
+    }
+}
+
+impl Tag {
+    /// The hyperlink escape that carries this tag through the parser
+    pub fn escape(self) -> String {
+        let name = match self {
+            Tag::PromptStart => "prompt-start".to_string(),
+            Tag::PromptEnd => "prompt-end".to_string(),
+            Tag::LineSize(size) => format!("line-size:{}", line_size_name(size)),
+            Tag::Image { id, row } => format!("sixel:{id}:{row}"),
+        };
+        format!("\x1b]8;;{TAG_URI_PREFIX}{name}\x1b\\")
+    }
+
+    fn parse(uri: &str) -> Option<Self> {
+        let name = uri.strip_prefix(TAG_URI_PREFIX)?;
+        if let Some(size) = name.strip_prefix("line-size:") {
+            return [
+                LineSize::Normal,
+                LineSize::DoubleWidth,
+                LineSize::DoubleHeightTop,
+                LineSize::DoubleHeightBottom,
+            ]
+            .into_iter()
+            .find(|candidate| line_size_name(*candidate) == size)
+            .map(Tag::LineSize);
+        }
+        if let Some(image) = name.strip_prefix("sixel:") {
+            let (id, row) = image.split_once(':')?;
+            return Some(Tag::Image {
+                id: id.parse().ok()?,
+                row: row.parse().ok()?,
+            });
+        }
+        match name {
+            "prompt-start" => Some(Tag::PromptStart),
+            "prompt-end" => Some(Tag::PromptEnd),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum FilterState {
+    Ground,
+    Escape,
+    /// In an OSC, having matched this many bytes of `8;`
+    OscCommand(usize),
+    /// In the parameters of an OSC 8 link
+    LinkParams,
+    /// At the start of an OSC 8 link's URI, holding it back
+    LinkUri,
+    /// In an OSC that can't be a tag
+    OscOther,
+    /// Swallowing the rest of a link the program tried to pass off as a tag
+    Forged,
+}
+
+/// Streaming filter that ends any OSC 8 link with a `TAG_URI_PREFIX` URI
+/// the program sends, so output can't forge the tags the scanners add.
+///
+/// Everything else passes through unchanged. The start of each link's URI
+/// is held back until it either differs from the prefix or matches it, in
+/// which case the link is closed with an empty URI and the rest dropped.
+/// Control bytes the parser ignores inside an OSC are ignored here too.
+#[derive(Debug)]
+pub struct TagLinkFilter {
+    state: FilterState,
+    held: Vec<u8>,
+}
+
+impl Default for TagLinkFilter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TagLinkFilter {
+    pub fn new() -> Self {
+        Self {
+            state: FilterState::Ground,
+            held: Vec::with_capacity(TAG_URI_PREFIX.len()),
+        }
+    }
+
+    /// Process a chunk of PTY output, appending the filtered bytes to `out`
+    pub fn process(&mut self, input: &[u8], out: &mut Vec<u8>) {
+        for &byte in input {
+            self.advance(byte, out);
+        }
+    }
+
+    fn advance(&mut self, byte: u8, out: &mut Vec<u8>) {
+        use FilterState::*;
+
+        if !matches!(self.state, Ground | Escape) {
+            match byte {
+                // BEL, CAN and SUB end the OSC
+                0x07 | 0x18 | 0x1a => {
+                    out.append(&mut self.held);
+                    if self.state != Forged {
+                        out.push(byte);
+                    }
+                    self.state = Ground;
+                    return;
+                }
+                0x1b => {
+                    out.append(&mut self.held);
+                    out.push(byte);
+                    self.state = Escape;
+                    return;
+                }
+                // Ignored by the parser inside an OSC
+                0x00..=0x1f => {
+                    if self.state != Forged {
+                        out.push(byte);
+                    }
+                    return;
+                }
+                _ => {}
+            }
+        }
+
+        self.state = match (self.state, byte) {
+            (Forged, _) => Forged,
+            (LinkUri, _) => {
+                self.held.push(byte);
+                if !TAG_URI_PREFIX.as_bytes().starts_with(&self.held) {
+                    out.append(&mut self.held);
+                    OscOther
+                } else if self.held.len() == TAG_URI_PREFIX.len() {
+                    // An empty URI closes the link instead
+                    self.held.clear();
+                    out.push(0x07);
+                    Forged
+                } else {
+                    LinkUri
+                }
+            }
+            (Ground | Escape, 0x1b) => {
+                out.push(byte);
+                Escape
+            }
+            (Escape, b']') => {
+                out.push(byte);
+                OscCommand(0)
+            }
+            // Other C0 controls run without leaving the escape
+            (Escape, 0x00..=0x17 | 0x19 | 0x1c..=0x1f | 0x7f) => {
+                out.push(byte);
+                Escape
+            }
+            (OscCommand(matched), _) => {
+                out.push(byte);
+                if byte != b"8;"[matched] {
+                    OscOther
+                } else if matched == 0 {
+                    OscCommand(1)
+                } else {
+                    LinkParams
+                }
+            }
+            (LinkParams, b';') => {
+                out.push(byte);
+                LinkUri
+            }
+            (state @ (LinkParams | OscOther), _) => {
+                out.push(byte);
+                state
+            }
+            (Ground | Escape, _) => {
+                out.push(byte);
+                Ground
+            }
+        };
+    }
+}
+
+/// One row of a sixel image, drawn from `column` on
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ImageRow {
+    pub column: usize,
+    pub id: u64,
+    /// Row of the image in cell heights
+    pub row: usize,
+}
+
+/// Tags on one screen, keyed by row.
+///
+/// A row's key is its line plus the number of rows that scrolled off the top
+/// of the screen before it, so scrolling the whole screen up only bumps
+/// `scrolled` and rows in the history never need rekeying.
+#[derive(Debug, Default)]
+pub struct ScreenTags {
+    scrolled: i64,
+    /// Rows a prompt starts on, and how many rows further down it ends
+    prompts: BTreeMap<i64, i64>,
+    sizes: BTreeMap<i64, LineSize>,
+    images: BTreeMap<i64, Vec<ImageRow>>,
+}
+
+impl ScreenTags {
+    fn key(&self, line: Line) -> i64 {
+        self.scrolled + line.0 as i64
+    }
+
+    fn line(&self, key: i64) -> Line {
+        Line((key - self.scrolled) as i32)
+    }
+
+    fn is_empty(&self) -> bool {
+        self.prompts.is_empty() && self.sizes.is_empty() && self.images.is_empty()
+    }
+
+    /// Prompts still in the grid, oldest first
+    pub fn prompt_spans(&self, grid: &Grid<Cell>) -> Vec<PromptSpan> {
+        let top = self.key(grid.topmost_line());
+        let bottom = grid.bottommost_line();
+        self.prompts
+            .range(top..=self.key(bottom))
+            .map(|(&start, &rows)| PromptSpan {
+                start: self.line(start),
+                end: self.line(start + rows).min(bottom),
+            })
+            .collect()
+    }
+
+    /// Size of the row on `line`
+    pub fn line_size(&self, line: Line) -> LineSize {
+        self.sizes.get(&self.key(line)).copied().unwrap_or_default()
+    }
+
+    /// Sixel image rows drawn on `line`
+    pub fn images(&self, line: Line) -> &[ImageRow] {
+        self.images.get(&self.key(line)).map_or(&[], Vec::as_slice)
+    }
+
+    /// Forget the tags on rows with keys in `keys`
+    fn remove(&mut self, keys: Range<i64>) {
+        remove_rows(&mut self.prompts, keys.clone());
+        remove_rows(&mut self.sizes, keys.clone());
+        remove_rows(&mut self.images, keys);
+    }
+
+    /// Move the tags on rows with keys in `keys` by `delta` rows
+    fn shift(&mut self, keys: Range<i64>, delta: i64) {
+        shift_rows(&mut self.prompts, keys.clone(), delta);
+        shift_rows(&mut self.sizes, keys.clone(), delta);
+        shift_rows(&mut self.images, keys, delta);
+    }
+
+    /// Forget the tags on screen lines `lines`
+    fn clear(&mut self, lines: Range<i32>) {
+        let keys = self.key(Line(lines.start))..self.key(Line(lines.end));
+        self.remove(keys);
+    }
+
+    /// Forget the images drawn from `columns` of `line`
+    fn clear_cells(&mut self, line: Line, columns: Range<usize>) {
+        let key = self.key(line);
+        if let Some(rows) = self.images.get_mut(&key) {
+            rows.retain(|image| !columns.contains(&image.column));
+            if rows.is_empty() {
+                self.images.remove(&key);
+            }
+        }
+    }
+
+    /// Follow `Grid::scroll_up`: rows in `region` move up by `lines`
+    fn scroll_up(&mut self, region: Range<i32>, lines: usize, screen_lines: usize) {
+        let lines = lines as i64;
+        let start = self.key(Line(region.start));
+        let end = self.key(Line(region.end));
+        if region.start == 0 {
+            // Everything rotates up into the history; rows below the region
+            // are swapped back to where they were
+            self.shift(end..self.key(Line(screen_lines as i32)), lines);
+            self.scrolled += lines;
+        } else if end - start <= lines {
+            self.remove(start..end);
+        } else {
+            self.remove(start..start + lines);
+            self.shift(start + lines..end, -lines);
+        }
+    }
+
+    /// Follow `Grid::scroll_down`: rows in `region` move down by `lines`
+    fn scroll_down(&mut self, region: Range<i32>, lines: usize) {
+        let lines = lines as i64;
+        let start = self.key(Line(region.start));
+        let end = self.key(Line(region.end));
+        if end - start <= lines {
+            self.remove(start..end);
+        } else {
+            self.remove(end - lines..end);
+            self.shift(start..end - lines, lines);
+        }
+    }
+
+    /// Forget rows that dropped off the top of the history
+    fn prune(&mut self, grid: &Grid<Cell>) {
+        let top = self.key(grid.topmost_line());
+        self.remove(i64::MIN..top);
+    }
+
+    /// Move every tag to where `place` puts its (line, column), dropping
+    /// the ones it has no place for
+    fn remap(&mut self, place: impl Fn(Line, usize) -> Option<(Line, usize)>) {
+        for (start, rows) in std::mem::take(&mut self.prompts) {
+            let Some((line, _)) = place(self.line(start), 0) else {
+                continue;
+            };
+            let end = place(self.line(start + rows), 0).map_or(line, |(end, _)| end.max(line));
+            self.prompts.insert(self.key(line), (end - line).0 as i64);
+        }
+        for (key, size) in std::mem::take(&mut self.sizes) {
+            if let Some((line, _)) = place(self.line(key), 0) {
+                self.sizes.insert(self.key(line), size);
+            }
+        }
+        for (key, rows) in std::mem::take(&mut self.images) {
+            for image in rows {
+                if let Some((line, column)) = place(self.line(key), image.column) {
+                    let key = self.key(line);
+                    self.images
+                        .entry(key)
+                        .or_default()
+                        .push(ImageRow { column, ..image });
+                }
+            }
+        }
+    }
+}
+
+fn remove_rows<V>(map: &mut BTreeMap<i64, V>, keys: Range<i64>) {
+    if keys.is_empty() {
+        return;
+    }
+    let mut removed = map.split_off(&keys.start);
+    let mut after = removed.split_off(&keys.end);
+    map.append(&mut after);
+}
+
+fn shift_rows<V>(map: &mut BTreeMap<i64, V>, keys: Range<i64>, delta: i64) {
+    if keys.is_empty() {
+        return;
+    }
+    let mut moved = map.split_off(&keys.start);
+    let mut after = moved.split_off(&keys.end);
+    map.append(&mut after);
+    map.extend(moved.into_iter().map(|(key, value)| (key + delta, value)));
+}
+
+/// Where the rows of a grid sit in its unwrapped lines, to follow tags
+/// through a reflow. Lines are counted from the cursor's, which alacritty
+/// keeps in place while it rewraps everything around it.
+struct Wrapping {
+    /// First row of each unwrapped line, top to bottom
+    starts: Vec<Line>,
+    /// Index of the cursor's line in `starts`
+    cursor: usize,
+    bottom: Line,
+    columns: usize,
+}
+
+impl Wrapping {
+    fn of(grid: &Grid<Cell>) -> Self {
+        let last = Column(grid.columns() - 1);
+        let mut starts = Vec::new();
+        let mut wrapped = false;
+        for line in grid.topmost_line().0..=grid.bottommost_line().0 {
+            if !wrapped {
+                starts.push(Line(line));
+            }
+            wrapped = grid[Line(line)][last].flags.contains(Flags::WRAPLINE);
+        }
+        let cursor = starts
+            .partition_point(|&start| start <= grid.cursor.point.line)
+            .saturating_sub(1);
+        Self {
+            starts,
+            cursor,
+            bottom: grid.bottommost_line(),
+            columns: grid.columns(),
+        }
+    }
+
+    /// Unwrapped line of a cell relative to the cursor's, and how far into it the cell is
+    fn locate(&self, line: Line, column: usize) -> Option<(isize, usize)> {
+        let index = self
+            .starts
+            .partition_point(|&start| start <= line)
+            .checked_sub(1)?;
+        let row = (line - self.starts[index]).0 as usize;
+        Some((
+            index as isize - self.cursor as isize,
+            row * self.columns + column,
+        ))
+    }
+
+    /// The cell at `offset` into the unwrapped line `line` (relative to the cursor's)
+    fn place(&self, (line, offset): (isize, usize)) -> Option<(Line, usize)> {
+        let index = usize::try_from(self.cursor as isize + line).ok()?;
+        let start = *self.starts.get(index)?;
+        let last = self
+            .starts
+            .get(index + 1)
+            .map_or(self.bottom, |&next| next - 1);
+        let row = Line((offset / self.columns) as i32);
+        Some(((start + row).min(last), offset % self.columns))
+    }
+}
+
+/// Prompt marks, line sizes and sixel image rows on both screens
+#[derive(Debug, Default)]
+pub struct LineTags {
+    primary: ScreenTags,
+    alternate: ScreenTags,
+    /// Scroll region set by the application, None for the whole screen
+    region: Option<Range<i32>>,
+    /// A prompt started and hasn't ended yet
+    prompt_open: bool,
+}
+
+impl LineTags {
+    /// Tags on the screen `term` is showing
+    pub fn screen<T>(&self, term: &Term<T>) -> &ScreenTags {
+        if term.mode().contains(TermMode::ALT_SCREEN) {
+            &self.alternate
+        } else {
+            &self.primary
+        }
+    }
+
+    fn screen_mut<T>(&mut self, term: &Term<T>) -> &mut ScreenTags {
+        if term.mode().contains(TermMode::ALT_SCREEN) {
+            &mut self.alternate
+        } else {
+            &mut self.primary
+        }
+    }
+
+    /// Forget the rows `term` dropped off the top of its history
+    pub fn prune<T>(&mut self, term: &Term<T>) {
+        self.screen_mut(term).prune(term.grid());
+    }
+
+    /// Resize `term`, keeping the tags with their text: through the reflow
+    /// on the primary screen, and by following the cursor on the alternate
+    /// one. The primary screen can't be read while the alternate one is up,
+    /// so its tags stay where they were until it comes back.
+    pub fn resize<T, S: Dimensions>(&mut self, term: &mut Term<T>, size: S) {
+        let alt = term.mode().contains(TermMode::ALT_SCREEN);
+        let reflow = !alt && size.columns() != term.columns() && !self.primary.is_empty();
+        let before = reflow.then(|| Wrapping::of(term.grid()));
+        let cursor = term.grid().cursor.point.line;
+
+        term.resize(size);
+        self.region = None;
+
+        let grid = term.grid();
+        let screen = self.screen_mut(term);
+        match before {
+            Some(before) => {
+                let after = Wrapping::of(grid);
+                screen.remap(|line, column| after.place(before.locate(line, column)?));
+            }
+            None => {
+                let delta = grid.cursor.point.line - cursor;
+                let (lines, columns) = (grid.screen_lines() as i32, grid.columns());
+                screen.remap(|line, column| {
+                    let line = line + delta;
+                    (line.0 < lines && column < columns).then_some((line, column))
+                });
+            }
+        }
+        screen.prune(grid);
+    }
+}
+
+/// Parser handler that passes everything on to a term, taking the tags out
+/// of the stream and keeping `LineTags` in step with the rows the term moves
+pub struct TagHandler<'a, T> {
+    term: &'a mut Term<T>,
+    tags: &'a mut LineTags,
+}
+
+impl<'a, T: EventListener> TagHandler<'a, T> {
+    pub fn new(term: &'a mut Term<T>, tags: &'a mut LineTags) -> Self {
+        Self { term, tags }
+    }
+
+    fn screen_lines(&self) -> i32 {
+        self.term.screen_lines() as i32
+    }
+
+    fn region(&self) -> Range<i32> {
+        self.tags.region.clone().unwrap_or(0..self.screen_lines())
+    }
+
+    fn screen(&mut self) -> &mut ScreenTags {
+        self.tags.screen_mut(self.term)
+    }
+
+    fn cursor_line(&self) -> i32 {
+        self.term.grid().cursor.point.line.0
+    }
+
+    /// Follow the term's `scroll_up_relative`
+    fn scroll_up_from(&mut self, origin: i32, lines: usize) {
+        let region = self.region();
+        let lines = lines.min((region.end - region.start) as usize);
+        let screen_lines = self.term.screen_lines();
+        let grid = self.term.grid();
+        let screen = self.tags.screen_mut(self.term);
+        screen.scroll_up(origin..region.end, lines, screen_lines);
+        if origin == 0 {
+            screen.prune(grid);
+        }
+    }
+
+    /// Follow the term's `scroll_down_relative`
+    fn scroll_down_from(&mut self, origin: i32, lines: usize) {
+        let region = self.region();
+        let lines = lines
+            .min((region.end - region.start) as usize)
+            .min((region.end - origin) as usize);
+        self.screen().scroll_down(origin..region.end, lines);
+    }
+
+    /// Follow a line feed from `line`
+    fn line_fed(&mut self, line: i32) {
+        let region = self.region();
+        if line + 1 == region.end {
+            self.scroll_up_from(region.start, 1);
+        }
+    }
+
+    /// Follow a private mode change made while `was_alt` said which screen was up
+    fn private_mode_changed(&mut self, mode: PrivateMode, was_alt: bool) {
+        if mode == PrivateMode::Named(NamedPrivateMode::ColumnMode) {
+            // DECCOLM resets the scroll region and clears the screen
+            self.tags.region = None;
+            let lines = self.screen_lines();
+            self.screen().clear(0..lines);
+        }
+        if self.term.mode().contains(TermMode::ALT_SCREEN) && !was_alt {
+            // The alternate screen starts out blank
+            self.tags.alternate = ScreenTags::default();
+        }
+    }
+
+    fn record(&mut self, tag: Tag) {
+        let point = self.term.grid().cursor.point;
+        let alt = self.term.mode().contains(TermMode::ALT_SCREEN);
+        let tags = &mut *self.tags;
+        let screen = if alt {
+            &mut tags.alternate
+        } else {
+            &mut tags.primary
+        };
+        let key = screen.key(point.line);
+        match tag {
+            Tag::PromptStart => {
+                screen.prompts.insert(key, 0);
+                tags.prompt_open = true;
+            }
+            Tag::PromptEnd => {
+                if std::mem::take(&mut tags.prompt_open) {
+                    if let Some(mut prompt) = screen.prompts.last_entry() {
+                        *prompt.get_mut() = (key - *prompt.key()).max(0);
+                    }
+                }
+            }
+            Tag::LineSize(LineSize::Normal) => {
+                screen.sizes.remove(&key);
+            }
+            Tag::LineSize(size) => {
+                screen.sizes.insert(key, size);
+            }
+            Tag::Image { id, row } => {
+                let column = point.column.0;
+                let rows = screen.images.entry(key).or_default();
+                rows.retain(|image| image.column != column);
+                rows.push(ImageRow { column, id, row });
+            }
+        }
+    }
+}
+
+impl<T: EventListener> Handler for TagHandler<'_, T> {
+    fn input(&mut self, c: char) {
+        // Nothing to move; `scrolled` only matters relative to existing keys
+        if self.screen().is_empty() {
+            self.term.input(c);
+            return;
+        }
+
+        let before = &self.term.grid().cursor;
+        let (point, needs_wrap) = (before.point, before.input_needs_wrap);
+        self.term.input(c);
+        let after = &self.term.grid().cursor;
+
+        // A pending wrap or a wide char in the last column moves to the next line
+        let wrapped = (needs_wrap && (after.point, after.input_needs_wrap) != (point, true))
+            || after.point.column < point.column;
+        if wrapped {
+            self.line_fed(point.line.0);
+        }
+    }
+
+    fn linefeed(&mut self) {
+        let line = self.cursor_line();
+        self.term.linefeed();
+        self.line_fed(line);
+    }
+
+    fn newline(&mut self) {
+        let line = self.cursor_line();
+        self.term.newline();
+        self.line_fed(line);
+    }
+
+    fn reverse_index(&mut self) {
+        let line = self.cursor_line();
+        self.term.reverse_index();
+        let region = self.region();
+        if line == region.start {
+            self.scroll_down_from(region.start, 1);
+        }
+    }
+
+    fn scroll_up(&mut self, lines: usize) {
+        self.term.scroll_up(lines);
+        self.scroll_up_from(self.region().start, lines);
+    }
+
+    fn scroll_down(&mut self, lines: usize) {
+        self.term.scroll_down(lines);
+        self.scroll_down_from(self.region().start, lines);
+    }
+
+    fn insert_blank_lines(&mut self, lines: usize) {
+        let origin = self.cursor_line();
+        self.term.insert_blank_lines(lines);
+        if self.region().contains(&origin) {
+            self.scroll_down_from(origin, lines);
+        }
+    }
+
+    fn delete_lines(&mut self, lines: usize) {
+        let origin = self.cursor_line();
+        self.term.delete_lines(lines);
+        let lines = lines.min((self.screen_lines() - origin) as usize);
+        if lines > 0 && self.region().contains(&origin) {
+            self.scroll_up_from(origin, lines);
+        }
+    }
+
+    fn set_scrolling_region(&mut self, top: usize, bottom: Option<usize>) {
+        self.term.set_scrolling_region(top, bottom);
+        let screen_lines = self.screen_lines();
+        let bottom = bottom.map_or(screen_lines, |bottom| bottom as i32);
+        if (top as i32) < bottom {
+            let start = (top as i32 - 1).min(screen_lines);
+            self.tags.region = Some(start..bottom.min(screen_lines));
+        }
+    }
+
+    fn clear_screen(&mut self, mode: ClearMode) {
+        let point = self.term.grid().cursor.point;
+        let screen_lines = self.screen_lines();
+        let (lines, columns) = match mode {
+            ClearMode::Above => (0..point.line.0, Some(0..point.column.0 + 1)),
+            ClearMode::Below => (
+                point.line.0 + 1..screen_lines,
+                Some(point.column.0..usize::MAX),
+            ),
+            ClearMode::All => (0..screen_lines, None),
+            ClearMode::Saved => (0..0, None),
+        };
+        // Clearing the primary screen scrolls its text up into the history
+        let scrolled = match mode {
+            ClearMode::All if !self.term.mode().contains(TermMode::ALT_SCREEN) => {
+                last_used_line(self.term.grid()).map_or(0, |line| line as usize + 1)
+            }
+            _ => 0,
+        };
+        self.term.clear_screen(mode);
+
+        let grid = self.term.grid();
+        let screen = self.tags.screen_mut(self.term);
+        if scrolled > 0 {
+            screen.scroll_up(0..screen_lines, scrolled, screen_lines as usize);
+        }
+        screen.clear(lines);
+        if let Some(columns) = columns {
+            screen.clear_cells(point.line, columns);
+        }
+        screen.prune(grid);
+    }
+
+    fn clear_line(&mut self, mode: LineClearMode) {
+        let cursor = &self.term.grid().cursor;
+        let point = cursor.point;
+        let columns = match mode {
+            LineClearMode::Right if cursor.input_needs_wrap => None,
+            LineClearMode::Right => Some(point.column.0..usize::MAX),
+            LineClearMode::Left => Some(0..point.column.0 + 1),
+            LineClearMode::All => Some(0..usize::MAX),
+        };
+        self.term.clear_line(mode);
+        if let Some(columns) = columns {
+            self.screen().clear_cells(point.line, columns);
+        }
+    }
+
+    fn reset_state(&mut self) {
+        self.term.reset_state();
+        *self.tags = LineTags::default();
+    }
+
+    fn decaln(&mut self) {
+        self.term.decaln();
+        let lines = self.screen_lines();
+        self.screen().clear(0..lines);
+    }
+
+    fn set_private_mode(&mut self, mode: PrivateMode) {
+        let alt = self.term.mode().contains(TermMode::ALT_SCREEN);
+        self.term.set_private_mode(mode);
+        self.private_mode_changed(mode, alt);
+    }
+
+    fn unset_private_mode(&mut self, mode: PrivateMode) {
+        let alt = self.term.mode().contains(TermMode::ALT_SCREEN);
+        self.term.unset_private_mode(mode);
+        self.private_mode_changed(mode, alt);
+    }
+
+    fn set_hyperlink(&mut self, hyperlink: Option<Hyperlink>) {
+        match hyperlink.as_ref().and_then(|link| Tag::parse(&link.uri)) {
+            Some(tag) => self.record(tag),
+            None => self.term.set_hyperlink(hyperlink),
+        }
+    }
+
+    fn set_title(&mut self, title: Option<String>) {
+        self.term.set_title(title);
+    }
+
+    fn set_cursor_style(&mut self, style: Option<CursorStyle>) {
+        self.term.set_cursor_style(style);
+    }
+
+    fn set_cursor_shape(&mut self, shape: CursorShape) {
+        self.term.set_cursor_shape(shape);
+    }
+
+    fn goto(&mut self, line: i32, col: usize) {
+        self.term.goto(line, col);
+    }
+
+    fn goto_line(&mut self, line: i32) {
+        self.term.goto_line(line);
+    }
+
+    fn goto_col(&mut self, col: usize) {
+        self.term.goto_col(col);
+    }
+
+    fn insert_blank(&mut self, count: usize) {
+        self.term.insert_blank(count);
+    }
+
+    fn move_up(&mut self, lines: usize) {
+        self.term.move_up(lines);
+    }
+
+    fn move_down(&mut self, lines: usize) {
+        self.term.move_down(lines);
+    }
+
+    fn identify_terminal(&mut self, intermediate: Option<char>) {
+        self.term.identify_terminal(intermediate);
+    }
+
+    fn device_status(&mut self, arg: usize) {
+        self.term.device_status(arg);
+    }
+
+    fn move_forward(&mut self, col: usize) {
+        self.term.move_forward(col);
+    }
+
+    fn move_backward(&mut self, col: usize) {
+        self.term.move_backward(col);
+    }
+
+    fn move_down_and_cr(&mut self, row: usize) {
+        self.term.move_down_and_cr(row);
+    }
+
+    fn move_up_and_cr(&mut self, row: usize) {
+        self.term.move_up_and_cr(row);
+    }
+
+    fn put_tab(&mut self, count: u16) {
+        self.term.put_tab(count);
+    }
+
+    fn backspace(&mut self) {
+        self.term.backspace();
+    }
+
+    fn carriage_return(&mut self) {
+        self.term.carriage_return();
+    }
+
+    fn bell(&mut self) {
+        self.term.bell();
+    }
+
+    fn substitute(&mut self) {
+        self.term.substitute();
+    }
+
+    fn set_horizontal_tabstop(&mut self) {
+        self.term.set_horizontal_tabstop();
+    }
+
+    fn erase_chars(&mut self, count: usize) {
+        self.term.erase_chars(count);
+    }
+
+    fn delete_chars(&mut self, count: usize) {
+        self.term.delete_chars(count);
+    }
+
+    fn move_backward_tabs(&mut self, count: u16) {
+        self.term.move_backward_tabs(count);
+    }
+
+    fn move_forward_tabs(&mut self, count: u16) {
+        self.term.move_forward_tabs(count);
+    }
+
+    fn save_cursor_position(&mut self) {
+        self.term.save_cursor_position();
+    }
+
+    fn restore_cursor_position(&mut self) {
+        self.term.restore_cursor_position();
+    }
+
+    fn clear_tabs(&mut self, mode: TabulationClearMode) {
+        self.term.clear_tabs(mode);
+    }
+
+    fn set_tabs(&mut self, interval: u16) {
+        self.term.set_tabs(interval);
+    }
+
+    fn terminal_attribute(&mut self, attr: Attr) {
+        self.term.terminal_attribute(attr);
+    }
+
+    fn set_mode(&mut self, mode: Mode) {
+        self.term.set_mode(mode);
+    }
+
+    fn unset_mode(&mut self, mode: Mode) {
+        self.term.unset_mode(mode);
+    }
+
+    fn report_mode(&mut self, mode: Mode) {
+        self.term.report_mode(mode);
+    }
+
+    fn report_private_mode(&mut self, mode: PrivateMode) {
+        self.term.report_private_mode(mode);
+    }
+
+    fn set_keypad_application_mode(&mut self) {
+        self.term.set_keypad_application_mode();
+    }
+
+    fn unset_keypad_application_mode(&mut self) {
+        self.term.unset_keypad_application_mode();
+    }
+
+    fn set_active_charset(&mut self, index: CharsetIndex) {
+        self.term.set_active_charset(index);
+    }
+
+    fn configure_charset(&mut self, index: CharsetIndex, charset: StandardCharset) {
+        self.term.configure_charset(index, charset);
+    }
+
+    fn set_color(&mut self, index: usize, color: Rgb) {
+        self.term.set_color(index, color);
+    }
+
+    fn dynamic_color_sequence(&mut self, prefix: String, index: usize, terminator: &str) {
+        self.term.dynamic_color_sequence(prefix, index, terminator);
+    }
+
+    fn reset_color(&mut self, index: usize) {
+        self.term.reset_color(index);
+    }
+
+    fn clipboard_store(&mut self, clipboard: u8, base64: &[u8]) {
+        self.term.clipboard_store(clipboard, base64);
+    }
+
+    fn clipboard_load(&mut self, clipboard: u8, terminator: &str) {
+        self.term.clipboard_load(clipboard, terminator);
+    }
+
+    fn push_title(&mut self) {
+        self.term.push_title();
+    }
+
+    fn pop_title(&mut self) {
+        self.term.pop_title();
+    }
+
+    fn text_area_size_pixels(&mut self) {
+        self.term.text_area_size_pixels();
+    }
+
+    fn text_area_size_chars(&mut self) {
+        self.term.text_area_size_chars();
+    }
+
+    fn set_mouse_cursor_icon(&mut self, icon: CursorIcon) {
+        self.term.set_mouse_cursor_icon(icon);
+    }
+
+    fn report_keyboard_mode(&mut self) {
+        self.term.report_keyboard_mode();
+    }
+
+    fn push_keyboard_mode(&mut self, mode: KeyboardModes) {
+        self.term.push_keyboard_mode(mode);
+    }
+
+    fn pop_keyboard_modes(&mut self, to_pop: u16) {
+        self.term.pop_keyboard_modes(to_pop);
+    }
+
+    fn set_keyboard_mode(&mut self, mode: KeyboardModes, behavior: KeyboardModesApplyBehavior) {
+        self.term.set_keyboard_mode(mode, behavior);
+    }
+
+    fn set_modify_other_keys(&mut self, mode: ModifyOtherKeys) {
+        self.term.set_modify_other_keys(mode);
+    }
+
+    fn report_modify_other_keys(&mut self) {
+        self.term.report_modify_other_keys();
+    }
+
+    fn set_scp(&mut self, char_path: ScpCharPath, update_mode: ScpUpdateMode) {
+        self.term.set_scp(char_path, update_mode);
+    }
+}
+
+/// Last screen line with anything on it, as `Grid::clear_viewport` finds it
+fn last_used_line(grid: &Grid<Cell>) -> Option<i32> {
+    (0..grid.screen_lines() as i32)
+        .rev()
+        .find(|&line| !grid[Line(line)].into_iter().all(GridCell::is_empty))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alacritty_terminal::event::VoidListener;
+    use alacritty_terminal::term::test::TermSize;
+    use alacritty_terminal::term::Config;
+    use alacritty_terminal::vte::ansi::Processor;
+
+    fn term(history: usize) -> Term<VoidListener> {
+        let config = Config {
+            scrolling_history: history,
+            ..Default::default()
+        };
+        Term::new(config, &TermSize::new(10, 4), VoidListener)
+    }
+
+    fn feed(term: &mut Term<VoidListener>, tags: &mut LineTags, output: &str) {
+        let mut parser: Processor = Processor::new();
+        parser.advance(&mut TagHandler::new(term, tags), output.as_bytes());
+    }
+
+    fn sizes(term: &Term<VoidListener>, tags: &LineTags) -> Vec<LineSize> {
+        let grid = term.grid();
+        (grid.topmost_line().0..=grid.bottommost_line().0)
+            .map(|line| tags.screen(term).line_size(Line(line)))
+            .collect()
+    }
+
+    fn wide(term: &mut Term<VoidListener>, tags: &mut LineTags) {
+        let tag = Tag::LineSize(LineSize::DoubleWidth).escape();
+        feed(term, tags, &tag);
+    }
+
+    #[test]
+    fn tags_follow_scrolling_into_history() {
+        let (mut term, mut tags) = (term(2), LineTags::default());
+        wide(&mut term, &mut tags);
+        feed(&mut term, &mut tags, "a\r\nb\r\nc\r\nd\r\ne");
+        assert_eq!(
+            tags.screen(&term).line_size(Line(-1)),
+            LineSize::DoubleWidth
+        );
+
+        // Two more lines push it past the history
+        feed(&mut term, &mut tags, "\r\nf\r\ng");
+        assert!(sizes(&term, &tags)
+            .iter()
+            .all(|size| *size == LineSize::Normal));
+        assert!(tags.primary.is_empty());
+    }
+
+    #[test]
+    fn tags_follow_scroll_regions() {
+        let (mut term, mut tags) = (term(100), LineTags::default());
+        feed(&mut term, &mut tags, "\x1b[3;1H");
+        wide(&mut term, &mut tags);
+        // Region of lines 2-4: deleting line 2 moves the tag up
+        feed(&mut term, &mut tags, "\x1b[2;4r\x1b[2;1H\x1b[M");
+        assert_eq!(sizes(&term, &tags)[1], LineSize::DoubleWidth);
+        // Reverse index at the top of the region moves it back down
+        feed(&mut term, &mut tags, "\x1b[2;1H\x1bM");
+        assert_eq!(sizes(&term, &tags)[2], LineSize::DoubleWidth);
+        // Nothing entered the history
+        assert_eq!(term.grid().history_size(), 0);
+    }
+
+    #[test]
+    fn wrapping_at_the_bottom_scrolls_tags() {
+        let (mut term, mut tags) = (term(100), LineTags::default());
+        feed(&mut term, &mut tags, "\x1b[4;1H");
+        wide(&mut term, &mut tags);
+        feed(&mut term, &mut tags, "0123456789x");
+        assert_eq!(tags.screen(&term).line_size(Line(2)), LineSize::DoubleWidth);
+    }
+
+    #[test]
+    fn program_hyperlinks_are_left_alone() {
+        let (mut term, mut tags) = (term(100), LineTags::default());
+        let start = Tag::PromptStart.escape();
+        feed(
+            &mut term,
+            &mut tags,
+            &format!("\x1b]8;;http://x\x1b\\{start}$ a"),
+        );
+        let link = term.grid()[Line(0)][Column(2)].hyperlink().unwrap();
+        assert_eq!(link.uri(), "http://x");
+        assert_eq!(tags.screen(&term).prompt_spans(term.grid()).len(), 1);
+    }
+
+    #[test]
+    fn forged_tag_links_are_closed() {
+        let (mut term, mut tags) = (term(100), LineTags::default());
+        let mut filter = TagLinkFilter::new();
+        let mut out = Vec::new();
+        for chunk in [
+            &b"\x1b]8;;http://x\x07a\x1b]8;;cool-rust-te"[..],
+            b"rm:prompt-start\x1b\\b\x1b]8;id=1;cool\x01-rust-term:",
+            b"line-size:double-width\x07c\x1b]8;;cool-rust-terminal.org\x07d",
+        ] {
+            filter.process(chunk, &mut out);
+        }
+        feed(&mut term, &mut tags, &String::from_utf8(out).unwrap());
+
+        assert!(tags.primary.is_empty());
+        let row = &term.grid()[Line(0)];
+        assert_eq!(row[Column(0)].hyperlink().unwrap().uri(), "http://x");
+        // The forged link ended the program's own
+        assert!(row[Column(1)].hyperlink().is_none());
+        assert!(row[Column(2)].hyperlink().is_none());
+        assert_eq!(
+            row[Column(3)].hyperlink().unwrap().uri(),
+            "cool-rust-terminal.org"
+        );
+        assert_eq!(row[Column(3)].c, 'd');
+    }
+
+    #[test]
+    fn clearing_moves_text_to_history_with_its_tags() {
+        let (mut term, mut tags) = (term(100), LineTags::default());
+        feed(&mut term, &mut tags, "one\r\n");
+        wide(&mut term, &mut tags);
+        feed(&mut term, &mut tags, "two\x1b[2J");
+        assert_eq!(
+            sizes(&term, &tags)[..2],
+            [LineSize::Normal, LineSize::DoubleWidth]
+        );
+        assert_eq!(term.grid().history_size(), 2);
+    }
+
+    #[test]
+    fn reflow_keeps_tags_on_their_text() {
+        let (mut term, mut tags) = (term(100), LineTags::default());
+        // A line wrapped over two rows, then a tagged one
+        feed(&mut term, &mut tags, "0123456789abc\r\n");
+        wide(&mut term, &mut tags);
+        feed(&mut term, &mut tags, "x");
+        assert_eq!(tags.screen(&term).line_size(Line(2)), LineSize::DoubleWidth);
+
+        // Wide enough to unwrap the first line
+        tags.resize(&mut term, TermSize::new(20, 4));
+        assert_eq!(tags.screen(&term).line_size(Line(1)), LineSize::DoubleWidth);
+        assert_eq!(term.grid()[Line(1)][Column(0)].c, 'x');
+    }
+
+    #[test]
+    fn alternate_screen_has_its_own_tags() {
+        let (mut term, mut tags) = (term(100), LineTags::default());
+        wide(&mut term, &mut tags);
+        feed(&mut term, &mut tags, "\x1b[?1049h");
+        assert_eq!(tags.screen(&term).line_size(Line(0)), LineSize::Normal);
+        feed(&mut term, &mut tags, "\x1b[?1049l");
+        assert_eq!(tags.screen(&term).line_size(Line(0)), LineSize::DoubleWidth);
+    }
+}
@@ -0,0 +1,130 @@
+// ABOUTME: Tracks xterm's modifyOtherKeys level (CSI > 4 ; N m) in PTY output.
+// ABOUTME: alacritty_terminal parses but ignores it, so the PTY reader watches for it.
+
+use std::sync::atomic::{AtomicU8, Ordering};
+use std::sync::Arc;
+
+/// Longest CSI parameter string worth keeping; ours are a few bytes
+const MAX_PARAMS: usize = 16;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ScanState {
+    Ground,
+    Escape,
+    Csi,
+}
+
+/// Streaming scanner that stores the modifyOtherKeys level in `level`.
+///
+/// `CSI > 4 ; 1 m` and `CSI > 4 ; 2 m` set levels 1 and 2; `CSI > 4 m`,
+/// `CSI > 4 ; 0 m`, `CSI > 4 n` and a full reset (`ESC c`) turn it off.
+/// The scanner only watches; output passes through untouched.
+#[derive(Debug)]
+pub struct ModifyOtherKeysScanner {
+    state: ScanState,
+    params: Vec<u8>,
+    level: Arc<AtomicU8>,
+}
+
+impl ModifyOtherKeysScanner {
+    pub fn new(level: Arc<AtomicU8>) -> Self {
+        Self {
+            state: ScanState::Ground,
+            params: Vec::with_capacity(MAX_PARAMS),
+            level,
+        }
+    }
+
+    /// Scan a chunk of PTY output
+    pub fn process(&mut self, input: &[u8]) {
+        for &byte in input {
+            self.advance(byte);
+        }
+    }
+
+    fn advance(&mut self, byte: u8) {
+        match self.state {
+            ScanState::Ground => {
+                if byte == 0x1b {
+                    self.state = ScanState::Escape;
+                }
+            }
+            ScanState::Escape => match byte {
+                b'[' => {
+                    self.state = ScanState::Csi;
+                    self.params.clear();
+                }
+                b'c' => {
+                    self.state = ScanState::Ground;
+                    self.set(0);
+                }
+                0x1b => {}
+                _ => self.state = ScanState::Ground,
+            },
+            ScanState::Csi => match byte {
+                // Parameter and intermediate bytes
+                0x20..=0x3f => {
+                    if self.params.len() < MAX_PARAMS {
+                        self.params.push(byte);
+                    }
+                }
+                0x40..=0x7e => {
+                    self.state = ScanState::Ground;
+                    self.finish_csi(byte);
+                }
+                0x1b => self.state = ScanState::Escape,
+                _ => self.state = ScanState::Ground,
+            },
+        }
+    }
+
+    fn finish_csi(&mut self, final_byte: u8) {
+        let Some(rest) = self.params.strip_prefix(b">4") else {
+            return;
+        };
+        match (final_byte, rest) {
+            (b'm', b"" | b";" | b";0") | (b'n', _) => self.set(0),
+            (b'm', b";1") => self.set(1),
+            (b'm', b";2") => self.set(2),
+            _ => {}
+        }
+    }
+
+    fn set(&self, level: u8) {
+        self.level.store(level, Ordering::SeqCst);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn level_after(chunks: &[&[u8]]) -> u8 {
+        let level = Arc::new(AtomicU8::new(0));
+        let mut scanner = ModifyOtherKeysScanner::new(Arc::clone(&level));
+        for chunk in chunks {
+            scanner.process(chunk);
+        }
+        level.load(Ordering::SeqCst)
+    }
+
+    #[test]
+    fn test_sets_and_resets_level() {
+        assert_eq!(level_after(&[b"\x1b[>4;1m"]), 1);
+        assert_eq!(level_after(&[b"\x1b[>4;2m"]), 2);
+        assert_eq!(level_after(&[b"\x1b[>4;2m", b"\x1b[>4m"]), 0);
+        assert_eq!(level_after(&[b"\x1b[>4;2m", b"\x1b[>4n"]), 0);
+        assert_eq!(level_after(&[b"\x1b[>4;2m", b"text\x1bc"]), 0);
+    }
+
+    #[test]
+    fn test_sequence_split_across_reads() {
+        assert_eq!(level_after(&[b"abc\x1b[>", b"4;", b"2m"]), 2);
+    }
+
+    #[test]
+    fn test_ignores_other_sequences() {
+        // SGR and the Kitty keyboard push look similar but aren't ours
+        assert_eq!(level_after(&[b"\x1b[4;2m\x1b[>1u\x1b[>5;2m"]), 0);
+    }
+}
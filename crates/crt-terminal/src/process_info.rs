@@ -1,5 +1,5 @@
 // ABOUTME: Platform-specific process information queries.
-// ABOUTME: Used to get shell working directory for session restoration.
+// ABOUTME: Used to get the shell's working directory and foreground command for session restoration.
 
 use std::path::PathBuf;
 
@@ -70,6 +70,45 @@ pub fn get_process_cwd(_pid: u32) -> Option<PathBuf> {
     None
 }
 
+/// Get the command line of the foreground job on a shell's terminal.
+/// Returns None when the shell itself is in the foreground.
+#[cfg(target_os = "linux")]
+pub fn get_foreground_command(shell_pid: u32) -> Option<Vec<String>> {
+    let stat = std::fs::read_to_string(format!("/proc/{}/stat", shell_pid)).ok()?;
+    // The command name is parenthesized and may contain spaces; fields after
+    // it are state, ppid, pgrp, session, tty_nr, tpgid
+    let fields: Vec<&str> = stat.rsplit_once(')')?.1.split_whitespace().collect();
+    let tpgid: i32 = fields.get(5)?.parse().ok()?;
+    if tpgid <= 0 || tpgid as u32 == shell_pid {
+        return None;
+    }
+    let cmdline = std::fs::read(format!("/proc/{}/cmdline", tpgid)).ok()?;
+    let args: Vec<String> = cmdline
+        .split(|&b| b == 0)
+        .filter(|arg| !arg.is_empty())
+        .map(|arg| String::from_utf8_lossy(arg).into_owned())
+        .collect();
+    (!args.is_empty()).then_some(args)
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn get_foreground_command(_shell_pid: u32) -> Option<Vec<String>> {
+    None
+}
+
+/// Get a process's short name, as `ps` shows it (Linux only)
+#[cfg(target_os = "linux")]
+pub fn get_process_name(pid: u32) -> Option<String> {
+    let comm = std::fs::read_to_string(format!("/proc/{}/comm", pid)).ok()?;
+    let name = comm.trim_end();
+    (!name.is_empty()).then(|| name.to_string())
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn get_process_name(_pid: u32) -> Option<String> {
+    None
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -88,6 +127,19 @@ mod tests {
         assert_eq!(cwd.unwrap(), expected);
     }
 
+    #[test]
+    fn test_foreground_command_of_missing_process() {
+        assert_eq!(get_foreground_command(99999999), None);
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_get_current_process_name() {
+        let name = get_process_name(std::process::id()).unwrap();
+        assert!(!name.is_empty());
+        assert_eq!(get_process_name(99999999), None);
+    }
+
     #[test]
     fn test_nonexistent_process() {
         // PID 0 is typically kernel/init and we shouldn't have access,
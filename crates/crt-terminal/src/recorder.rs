@@ -0,0 +1,114 @@
+// ABOUTME: Records a pane's PTY output as an asciicast v2 file.
+// ABOUTME: Writes the JSON header, then timestamped output and resize events.
+
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+
+/// An asciicast v2 recording in progress
+pub struct SessionRecorder {
+    writer: BufWriter<File>,
+    start_time: Instant,
+    size: (u16, u16),
+    /// Trailing bytes of a UTF-8 sequence split across reads
+    partial: Vec<u8>,
+}
+
+impl SessionRecorder {
+    /// Create the file and write the header for a `cols` x `rows` terminal
+    pub fn new(path: &Path, cols: u16, rows: u16) -> io::Result<Self> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+        let header = serde_json::json!({
+            "version": 2,
+            "width": cols,
+            "height": rows,
+            "timestamp": timestamp,
+            "env": { "TERM": std::env::var("TERM").unwrap_or_default() },
+        });
+        writeln!(writer, "{}", header)?;
+        Ok(Self {
+            writer,
+            start_time: Instant::now(),
+            size: (cols, rows),
+            partial: Vec::new(),
+        })
+    }
+
+    /// Size of the terminal as last recorded
+    pub fn size(&self) -> (u16, u16) {
+        self.size
+    }
+
+    /// Record raw PTY output. An incomplete UTF-8 sequence at the end is
+    /// held back until the rest of it arrives.
+    pub fn record_output(&mut self, data: &[u8]) -> io::Result<()> {
+        self.partial.extend_from_slice(data);
+        let valid = match std::str::from_utf8(&self.partial) {
+            Ok(_) => self.partial.len(),
+            Err(e) if e.error_len().is_none() => e.valid_up_to(),
+            // Invalid bytes mid-stream: write them replaced rather than stall
+            Err(_) => self.partial.len(),
+        };
+        if valid == 0 {
+            return Ok(());
+        }
+        let text = String::from_utf8_lossy(&self.partial[..valid]).into_owned();
+        self.partial.drain(..valid);
+        self.write_event("o", &text)
+    }
+
+    /// Record the terminal changing size
+    pub fn record_resize(&mut self, cols: u16, rows: u16) -> io::Result<()> {
+        self.size = (cols, rows);
+        self.write_event("r", &format!("{}x{}", cols, rows))
+    }
+
+    /// Flush buffered events to disk
+    pub fn finish(mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+
+    fn write_event(&mut self, kind: &str, data: &str) -> io::Result<()> {
+        let elapsed = self.start_time.elapsed().as_secs_f64();
+        let event = serde_json::json!([elapsed, kind, data]);
+        writeln!(self.writer, "{}", event)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_records_header_output_and_resize() {
+        let path = std::env::temp_dir().join("test_recording.cast");
+        let mut recorder = SessionRecorder::new(&path, 80, 24).unwrap();
+        // "é" split across two reads
+        recorder.record_output(b"caf\xc3").unwrap();
+        recorder.record_output(b"\xa9\r\n").unwrap();
+        recorder.record_resize(100, 30).unwrap();
+        assert_eq!(recorder.size(), (100, 30));
+        recorder.finish().unwrap();
+
+        let contents = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).ok();
+        let lines: Vec<serde_json::Value> = contents
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(lines.len(), 4);
+        assert_eq!(lines[0]["version"], 2);
+        assert_eq!(lines[0]["width"], 80);
+        assert_eq!(lines[0]["height"], 24);
+        assert_eq!(lines[1][1], "o");
+        assert_eq!(lines[1][2], "caf");
+        assert_eq!(lines[2][2], "é\r\n");
+        assert_eq!(lines[3][1], "r");
+        assert_eq!(lines[3][2], "100x30");
+    }
+}
@@ -0,0 +1,77 @@
+// ABOUTME: Follows one line of text as output scrolls it up into the history.
+// ABOUTME: Keeps a one-cell alacritty selection there, which the terminal moves with the text.
+
+use alacritty_terminal::index::{Column, Line, Point, Side};
+use alacritty_terminal::selection::{Selection, SelectionType};
+use alacritty_terminal::term::Term;
+
+/// Start following the text on buffer line `line` (negative = history).
+/// This takes over the term's own selection, which the app doesn't use.
+pub fn set_anchor<T>(term: &mut Term<T>, line: i32) {
+    let point = Point::new(Line(line), Column(0));
+    let mut selection = Selection::new(SelectionType::Simple, point, Side::Left);
+    selection.update(point, Side::Right);
+    term.selection = Some(selection);
+}
+
+/// Buffer line the anchored text is on now. None without an anchor, or
+/// once that text scrolled past the top of the history or was erased,
+/// e.g. by clearing the screen, a reset or a change in width.
+pub fn anchor_line<T>(term: &Term<T>) -> Option<i32> {
+    let range = term.selection.as_ref()?.to_range(term)?;
+    Some(range.start.line.0)
+}
+
+pub fn clear_anchor<T>(term: &mut Term<T>) {
+    term.selection = None;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alacritty_terminal::event::VoidListener;
+    use alacritty_terminal::term::test::TermSize;
+    use alacritty_terminal::term::Config;
+    use alacritty_terminal::vte::ansi::Processor;
+
+    fn term(history: usize) -> Term<VoidListener> {
+        let config = Config {
+            scrolling_history: history,
+            ..Default::default()
+        };
+        Term::new(config, &TermSize::new(20, 4), VoidListener)
+    }
+
+    fn feed(term: &mut Term<VoidListener>, output: &str) {
+        let mut parser: Processor = Processor::new();
+        parser.advance(term, output.as_bytes());
+    }
+
+    #[test]
+    fn anchor_moves_up_with_output() {
+        let mut term = term(100);
+        feed(&mut term, "one\r\ntwo\r\nthree");
+        set_anchor(&mut term, 1);
+        feed(&mut term, "\r\nfour\r\nfive\r\nsix");
+        assert_eq!(anchor_line(&term), Some(-1));
+    }
+
+    #[test]
+    fn anchor_is_lost_past_the_history_limit() {
+        let mut term = term(2);
+        set_anchor(&mut term, 0);
+        feed(&mut term, "\r\n\r\n\r\n\r\n\r\n");
+        assert_eq!(anchor_line(&term), Some(-2));
+        feed(&mut term, "\r\n");
+        assert_eq!(anchor_line(&term), None);
+    }
+
+    #[test]
+    fn clearing_the_screen_drops_the_anchor() {
+        let mut term = term(100);
+        feed(&mut term, "text");
+        set_anchor(&mut term, 0);
+        feed(&mut term, "\x1b[2J");
+        assert_eq!(anchor_line(&term), None);
+    }
+}
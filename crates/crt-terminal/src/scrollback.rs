@@ -1,14 +1,17 @@
 // ABOUTME: Scrollback buffer serialization for session restoration.
 // ABOUTME: Captures terminal grid content as compressed data.
 
-use alacritty_terminal::grid::Dimensions;
-use alacritty_terminal::index::{Column, Line};
-use alacritty_terminal::term::cell::Cell;
+use alacritty_terminal::grid::{Dimensions, Row};
+use alacritty_terminal::index::Line;
+use alacritty_terminal::term::cell::{Cell, Flags};
 use alacritty_terminal::vte::ansi::{Color, NamedColor};
 use alacritty_terminal::Grid;
 use serde::{Deserialize, Serialize};
 use std::io::{Read, Write};
 
+use crate::disk_scrollback::DiskScrollback;
+use crate::shell_integration::PromptSpan;
+
 /// Serialized representation of a single cell
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SerializedCell {
@@ -18,6 +21,30 @@ pub struct SerializedCell {
     pub flags: u16,
 }
 
+impl From<&Cell> for SerializedCell {
+    fn from(cell: &Cell) -> Self {
+        SerializedCell {
+            c: cell.c,
+            fg: cell.fg.into(),
+            bg: cell.bg.into(),
+            flags: cell.flags.bits(),
+        }
+    }
+}
+
+impl SerializedCell {
+    /// Cell with this content; extras like hyperlinks and combining marks are not kept
+    pub fn to_cell(&self) -> Cell {
+        Cell {
+            c: self.c,
+            fg: self.fg.into(),
+            bg: self.bg.into(),
+            flags: Flags::from_bits_truncate(self.flags),
+            extra: None,
+        }
+    }
+}
+
 /// Simplified color representation for serialization
 #[derive(Debug, Clone, Copy, Serialize, Deserialize)]
 pub enum SerializedColor {
@@ -89,48 +116,69 @@ pub struct SerializedLine {
     pub cells: Vec<SerializedCell>,
 }
 
+impl SerializedLine {
+    pub fn from_row(row: &Row<Cell>) -> Self {
+        SerializedLine {
+            cells: row[..].iter().map(SerializedCell::from).collect(),
+        }
+    }
+
+    /// Grid row with this content, for drawing lines that are no longer in the grid
+    pub fn to_row(&self) -> Row<Cell> {
+        let cells: Vec<Cell> = self.cells.iter().map(SerializedCell::to_cell).collect();
+        let occupied = cells.len();
+        Row::from_vec(cells, occupied)
+    }
+}
+
+/// Where history older than a pane's in-memory scrollback is
+#[derive(Debug, Clone, Default)]
+pub enum ScrollbackSource {
+    /// Nowhere: lines past the scrollback limit are dropped
+    #[default]
+    Memory,
+    /// Paged out to a file, which is not saved with the session
+    Disk(DiskScrollback),
+}
+
 /// Complete scrollback data for a pane
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ScrollbackData {
     pub version: u32,
     pub columns: usize,
     pub lines: Vec<SerializedLine>,
+    /// Indices into `lines` where a shell prompt (OSC 133;A) starts
+    #[serde(default)]
+    pub prompt_lines: Vec<usize>,
+    /// Where older lines than `lines` went
+    #[serde(skip)]
+    pub source: ScrollbackSource,
 }
 
 impl ScrollbackData {
     pub const CURRENT_VERSION: u32 = 1;
 
-    /// Extract scrollback data from a terminal grid
-    pub fn from_grid(grid: &Grid<Cell>) -> Self {
-        let columns = grid.columns();
+    /// Extract scrollback data from a terminal grid and the prompts on it
+    pub fn from_grid(grid: &Grid<Cell>, prompts: &[PromptSpan]) -> Self {
         let topmost = grid.topmost_line();
         let bottommost = grid.bottommost_line();
 
-        let mut lines = Vec::new();
-
         // Iterate from topmost (oldest history) to bottommost (newest)
-        for line_idx in topmost.0..=bottommost.0 {
-            let line = Line(line_idx);
-            let row = &grid[line];
-
-            let mut cells = Vec::with_capacity(columns);
-            for col_idx in 0..columns {
-                let cell = &row[Column(col_idx)];
-                cells.push(SerializedCell {
-                    c: cell.c,
-                    fg: cell.fg.into(),
-                    bg: cell.bg.into(),
-                    flags: cell.flags.bits(),
-                });
-            }
+        let lines = (topmost.0..=bottommost.0)
+            .map(|line| SerializedLine::from_row(&grid[Line(line)]))
+            .collect();
 
-            lines.push(SerializedLine { cells });
-        }
+        let prompt_lines = prompts
+            .iter()
+            .map(|span| (span.start.0 - topmost.0) as usize)
+            .collect();
 
         ScrollbackData {
             version: Self::CURRENT_VERSION,
-            columns,
+            columns: grid.columns(),
             lines,
+            prompt_lines,
+            source: ScrollbackSource::Memory,
         }
     }
 
@@ -158,7 +206,13 @@ impl ScrollbackData {
     pub fn to_ansi_output(&self) -> Vec<u8> {
         let mut output = Vec::new();
 
-        for line in &self.lines {
+        for (idx, line) in self.lines.iter().enumerate() {
+            // Re-emit prompt marks so jumping works on restored content too
+            let is_prompt = self.prompt_lines.binary_search(&idx).is_ok();
+            if is_prompt {
+                output.extend_from_slice(b"\x1b]133;A\x07");
+            }
+
             let mut line_str = String::new();
             for cell in &line.cells {
                 line_str.push(cell.c);
@@ -166,6 +220,9 @@ impl ScrollbackData {
             // Trim trailing spaces
             let trimmed = line_str.trim_end();
             output.extend_from_slice(trimmed.as_bytes());
+            if is_prompt {
+                output.extend_from_slice(b"\x1b]133;B\x07");
+            }
             output.push(b'\n');
         }
 
@@ -222,6 +279,8 @@ mod tests {
                     }],
                 },
             ],
+            prompt_lines: vec![1],
+            source: ScrollbackSource::Memory,
         };
 
         let compressed = data.compress().unwrap();
@@ -230,5 +289,6 @@ mod tests {
         assert_eq!(data.version, restored.version);
         assert_eq!(data.columns, restored.columns);
         assert_eq!(data.lines.len(), restored.lines.len());
+        assert_eq!(data.prompt_lines, restored.prompt_lines);
     }
 }
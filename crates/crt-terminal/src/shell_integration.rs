@@ -0,0 +1,513 @@
+// ABOUTME: OSC 133 shell integration: detects prompt markers in PTY output.
+// ABOUTME: Tags where prompts start and end so their lines can be found later.
+
+use alacritty_terminal::grid::Dimensions;
+use alacritty_terminal::index::{Column, Line};
+use alacritty_terminal::term::cell::{Cell, Flags};
+use alacritty_terminal::Grid;
+
+use crate::line_tags::Tag;
+
+/// Longest OSC payload we bother collecting; OSC 133 markers are tiny.
+const MAX_OSC_PAYLOAD: usize = 32;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ScanState {
+    Ground,
+    Escape,
+    Osc,
+    OscEscape,
+}
+
+/// Streaming scanner that tags OSC 133 prompt markers for the parser.
+///
+/// `133;A` (prompt start) and `133;B` / `133;C` (prompt end) are followed by
+/// the matching `Tag`, which `TagHandler` records where the cursor is.
+/// All input bytes are passed through unchanged.
+#[derive(Debug)]
+pub struct PromptMarkScanner {
+    state: ScanState,
+    payload: Vec<u8>,
+    overflow: bool,
+}
+
+impl Default for PromptMarkScanner {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl PromptMarkScanner {
+    pub fn new() -> Self {
+        Self {
+            state: ScanState::Ground,
+            payload: Vec::with_capacity(MAX_OSC_PAYLOAD),
+            overflow: false,
+        }
+    }
+
+    /// Process a chunk of PTY output, appending the rewritten bytes to `out`
+    pub fn process(&mut self, input: &[u8], out: &mut Vec<u8>) {
+        for &byte in input {
+            out.push(byte);
+            self.advance(byte, out);
+        }
+    }
+
+    fn advance(&mut self, byte: u8, out: &mut Vec<u8>) {
+        match self.state {
+            ScanState::Ground => {
+                if byte == 0x1b {
+                    self.state = ScanState::Escape;
+                }
+            }
+            ScanState::Escape => match byte {
+                b']' => {
+                    self.state = ScanState::Osc;
+                    self.payload.clear();
+                    self.overflow = false;
+                }
+                0x1b => {}
+                _ => self.state = ScanState::Ground,
+            },
+            ScanState::Osc => match byte {
+                0x07 => self.finish_osc(out),
+                0x1b => self.state = ScanState::OscEscape,
+                // CAN and SUB abort the sequence
+                0x18 | 0x1a => self.state = ScanState::Ground,
+                _ => {
+                    if self.payload.len() < MAX_OSC_PAYLOAD {
+                        self.payload.push(byte);
+                    } else {
+                        self.overflow = true;
+                    }
+                }
+            },
+            ScanState::OscEscape => {
+                if byte == b'\\' {
+                    self.finish_osc(out);
+                } else if byte == b']' {
+                    // Unterminated OSC followed directly by a new one
+                    self.state = ScanState::Osc;
+                    self.payload.clear();
+                    self.overflow = false;
+                } else {
+                    self.state = ScanState::Ground;
+                }
+            }
+        }
+    }
+
+    fn finish_osc(&mut self, out: &mut Vec<u8>) {
+        self.state = ScanState::Ground;
+        if self.overflow {
+            return;
+        }
+
+        let Some(rest) = self.payload.strip_prefix(b"133;") else {
+            return;
+        };
+
+        let tag = match rest.first() {
+            Some(b'A') => Tag::PromptStart,
+            Some(b'B') | Some(b'C') => Tag::PromptEnd,
+            _ => return,
+        };
+        out.extend_from_slice(tag.escape().as_bytes());
+    }
+}
+
+/// A prompt in the grid, in buffer-relative lines
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PromptSpan {
+    /// First line containing prompt text
+    pub start: Line,
+    /// Last line containing prompt text (where the command is typed)
+    pub end: Line,
+}
+
+/// Range of lines holding the output of the most recent finished command.
+///
+/// This is everything between the input line of the second-to-last prompt
+/// (following soft wraps of a long command) and the start of the last prompt.
+pub fn last_command_output_range(grid: &Grid<Cell>, spans: &[PromptSpan]) -> Option<(Line, Line)> {
+    let [.., previous, current] = spans else {
+        return None;
+    };
+
+    let last_column = Column(grid.columns().saturating_sub(1));
+    let mut input_end = previous.end;
+    while input_end < current.start && grid[input_end][last_column].flags.contains(Flags::WRAPLINE)
+    {
+        input_end += 1;
+    }
+
+    let start = input_end + 1;
+    let end = current.start - 1;
+    (start <= end).then_some((start, end))
+}
+
+#[cfg(unix)]
+pub use self::pty::{OutputCapture, ParseTarget, ShellIntegrationPty};
+
+#[cfg(unix)]
+mod pty {
+    use super::PromptMarkScanner;
+    use crate::line_size::LineSizeScanner;
+    use crate::line_tags::{LineTags, TagHandler, TagLinkFilter};
+    use crate::modify_other_keys::ModifyOtherKeysScanner;
+    use crate::sixel::{CellPixelSize, SixelImages, SixelScanner};
+    use alacritty_terminal::event::{Event as TermEvent, EventListener, OnResize, WindowSize};
+    use alacritty_terminal::sync::FairMutex;
+    use alacritty_terminal::term::Term;
+    use alacritty_terminal::tty::{self, ChildEvent, EventedPty, EventedReadWrite};
+    use alacritty_terminal::vte::ansi::Processor;
+    use polling::{Event, PollMode, Poller};
+    use std::fs::File;
+    use std::io::{self, Read};
+    use std::sync::atomic::AtomicU8;
+    use std::sync::{Arc, Condvar, Mutex};
+    use std::time::Instant;
+
+    /// Raw output collected for a recording, when one is running
+    pub type OutputCapture = Arc<Mutex<Option<Vec<u8>>>>;
+
+    /// Where the reader parses output to: the term, the tags recorded
+    /// alongside it, and the listener told when there's something to draw
+    #[derive(Clone)]
+    pub struct ParseTarget<T> {
+        pub term: Arc<FairMutex<Term<T>>>,
+        pub tags: Arc<Mutex<LineTags>>,
+        pub listener: T,
+    }
+
+    /// PTY wrapper whose reader tags OSC 133 markers, DEC line size escapes
+    /// and sixel images and parses the output itself, so the tags end up in
+    /// `LineTags` at the cursor position they were found at. It also copies
+    /// the untouched bytes to `capture` and keeps `modify_other_keys` at the
+    /// level the application asked for.
+    pub struct ShellIntegrationPty<T> {
+        inner: tty::Pty,
+        reader: MarkReader<T>,
+    }
+
+    impl<T: EventListener + Clone + Send + 'static> ShellIntegrationPty<T> {
+        pub fn new(
+            mut inner: tty::Pty,
+            target: ParseTarget<T>,
+            capture: OutputCapture,
+            modify_other_keys: Arc<AtomicU8>,
+            images: SixelImages,
+            cell_size: CellPixelSize,
+        ) -> io::Result<Self> {
+            // The clone shares the file description (and its O_NONBLOCK flag)
+            // with the registered fd, so polling keeps working unchanged.
+            let file = inner.reader().try_clone()?;
+            let parser = Arc::new(Mutex::new(Processor::new()));
+            let sync_timer = spawn_sync_timer(target.clone(), Arc::clone(&parser))?;
+            Ok(Self {
+                inner,
+                reader: MarkReader {
+                    file,
+                    target,
+                    parser,
+                    sync_timer,
+                    capture,
+                    modify_other_keys: ModifyOtherKeysScanner::new(modify_other_keys),
+                    forgeries: TagLinkFilter::new(),
+                    sixels: SixelScanner::new(images, cell_size),
+                    scanner: PromptMarkScanner::new(),
+                    line_sizes: LineSizeScanner::new(),
+                    scratch: Vec::new(),
+                    filtered: Vec::new(),
+                    unpacked: Vec::new(),
+                    marked: Vec::new(),
+                    tagged: Vec::new(),
+                },
+            })
+        }
+
+        pub fn child(&self) -> &std::process::Child {
+            self.inner.child()
+        }
+    }
+
+    /// When the program's unfinished synchronized update (DEC 2026) times out
+    #[derive(Default)]
+    struct SyncDeadline {
+        at: Option<Instant>,
+        closed: bool,
+    }
+
+    type SyncTimer = Arc<(Mutex<SyncDeadline>, Condvar)>;
+
+    /// Start a thread that flushes a synchronized update once it times out,
+    /// as the program may not write anything else that would get it read
+    fn spawn_sync_timer<T: EventListener + Send + 'static>(
+        target: ParseTarget<T>,
+        parser: Arc<Mutex<Processor>>,
+    ) -> io::Result<SyncTimer> {
+        let timer = SyncTimer::default();
+        let shared = Arc::clone(&timer);
+        std::thread::Builder::new()
+            .name("sync-timeout".into())
+            .spawn(move || {
+                let (deadline, changed) = &*shared;
+                let mut state = deadline.lock().unwrap();
+                while !state.closed {
+                    let Some(at) = state.at else {
+                        state = changed.wait(state).unwrap();
+                        continue;
+                    };
+                    let now = Instant::now();
+                    if now < at {
+                        state = changed.wait_timeout(state, at - now).unwrap().0;
+                        continue;
+                    }
+                    state.at = None;
+                    drop(state);
+
+                    // Same lock order as the reader: parser, term, tags
+                    let mut parser = parser.lock().unwrap();
+                    let timeout = parser.sync_timeout().sync_timeout();
+                    if timeout.is_some_and(|timeout| timeout <= Instant::now()) {
+                        let mut term = target.term.lock_unfair();
+                        let mut tags = target.tags.lock().unwrap();
+                        parser.stop_sync(&mut TagHandler::new(&mut term, &mut tags));
+                        target.listener.send_event(TermEvent::Wakeup);
+                    }
+                    drop(parser);
+                    state = deadline.lock().unwrap();
+                }
+            })?;
+        Ok(timer)
+    }
+
+    pub struct MarkReader<T> {
+        file: File,
+        target: ParseTarget<T>,
+        parser: Arc<Mutex<Processor>>,
+        sync_timer: SyncTimer,
+        capture: OutputCapture,
+        modify_other_keys: ModifyOtherKeysScanner,
+        forgeries: TagLinkFilter,
+        sixels: SixelScanner,
+        scanner: PromptMarkScanner,
+        line_sizes: LineSizeScanner,
+        scratch: Vec<u8>,
+        /// Output with forged tag links removed, fed to the sixel scanner
+        filtered: Vec<u8>,
+        /// Output of the sixel scanner, fed to the prompt scanner
+        unpacked: Vec<u8>,
+        /// Output of the prompt scanner, fed to the line size scanner
+        marked: Vec<u8>,
+        /// Output of the line size scanner, fed to the parser
+        tagged: Vec<u8>,
+    }
+
+    impl<T: EventListener> Read for MarkReader<T> {
+        /// Read and parse one chunk of output. This always hands the event
+        /// loop 0 bytes, which sends it back to polling: its own parser never
+        /// sees the output, as it can't record the tags.
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            self.scratch.resize(buf.len().max(1), 0);
+            let got = self.file.read(&mut self.scratch)?;
+            if got == 0 {
+                return Ok(0);
+            }
+            let output = &self.scratch[..got];
+            if let Some(captured) = self.capture.lock().unwrap().as_mut() {
+                captured.extend_from_slice(output);
+            }
+            self.modify_other_keys.process(output);
+            self.filtered.clear();
+            self.forgeries.process(output, &mut self.filtered);
+            self.unpacked.clear();
+            self.sixels.process(&self.filtered, &mut self.unpacked);
+            self.marked.clear();
+            self.scanner.process(&self.unpacked, &mut self.marked);
+            self.tagged.clear();
+            self.line_sizes.process(&self.marked, &mut self.tagged);
+
+            let mut parser = self.parser.lock().unwrap();
+            // The event loop holds the fair lock while reading
+            let mut term = self.target.term.lock_unfair();
+            let mut tags = self.target.tags.lock().unwrap();
+            let mut handler = TagHandler::new(&mut term, &mut tags);
+            // The timer may not have got to an expired update yet
+            let timeout = parser.sync_timeout().sync_timeout();
+            if timeout.is_some_and(|timeout| timeout <= Instant::now()) {
+                parser.stop_sync(&mut handler);
+            }
+            parser.advance(&mut handler, &self.tagged);
+
+            if parser.sync_bytes_count() < self.tagged.len() {
+                self.target.listener.send_event(TermEvent::Wakeup);
+            }
+
+            // The event loop only wakes up for its own parser's sync timeout
+            let (deadline, changed) = &*self.sync_timer;
+            let mut state = deadline.lock().unwrap();
+            let timeout = parser.sync_timeout().sync_timeout();
+            if state.at != timeout {
+                state.at = timeout;
+                changed.notify_one();
+            }
+            Ok(0)
+        }
+    }
+
+    impl<T> Drop for MarkReader<T> {
+        fn drop(&mut self) {
+            let (deadline, changed) = &*self.sync_timer;
+            deadline.lock().unwrap().closed = true;
+            changed.notify_one();
+        }
+    }
+
+    impl<T: EventListener> EventedReadWrite for ShellIntegrationPty<T> {
+        type Reader = MarkReader<T>;
+        type Writer = File;
+
+        unsafe fn register(
+            &mut self,
+            poll: &Arc<Poller>,
+            interest: Event,
+            mode: PollMode,
+        ) -> io::Result<()> {
+            unsafe { self.inner.register(poll, interest, mode) }
+        }
+
+        fn reregister(
+            &mut self,
+            poll: &Arc<Poller>,
+            interest: Event,
+            mode: PollMode,
+        ) -> io::Result<()> {
+            self.inner.reregister(poll, interest, mode)
+        }
+
+        fn deregister(&mut self, poll: &Arc<Poller>) -> io::Result<()> {
+            self.inner.deregister(poll)
+        }
+
+        fn reader(&mut self) -> &mut MarkReader<T> {
+            &mut self.reader
+        }
+
+        fn writer(&mut self) -> &mut File {
+            self.inner.writer()
+        }
+    }
+
+    impl<T: EventListener> EventedPty for ShellIntegrationPty<T> {
+        fn next_child_event(&mut self) -> Option<ChildEvent> {
+            self.inner.next_child_event()
+        }
+    }
+
+    impl<T> OnResize for ShellIntegrationPty<T> {
+        fn on_resize(&mut self, window_size: WindowSize) {
+            self.inner.on_resize(window_size);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::line_tags::{LineTags, TagHandler};
+    use alacritty_terminal::event::VoidListener;
+    use alacritty_terminal::term::{Config, Term};
+    use alacritty_terminal::vte::ansi::Processor;
+
+    struct Size(usize, usize);
+
+    impl Dimensions for Size {
+        fn columns(&self) -> usize {
+            self.0
+        }
+        fn screen_lines(&self) -> usize {
+            self.1
+        }
+        fn total_lines(&self) -> usize {
+            self.1
+        }
+    }
+
+    fn feed(term: &mut Term<VoidListener>, tags: &mut LineTags, chunks: &[&[u8]]) {
+        let mut scanner = PromptMarkScanner::new();
+        let mut parser: Processor = Processor::new();
+        for chunk in chunks {
+            let mut out = Vec::new();
+            scanner.process(chunk, &mut out);
+            parser.advance(&mut TagHandler::new(term, tags), &out);
+        }
+    }
+
+    fn term(history: usize) -> (Term<VoidListener>, LineTags) {
+        let config = Config {
+            scrolling_history: history,
+            ..Default::default()
+        };
+        (
+            Term::new(config, &Size(20, 4), VoidListener),
+            LineTags::default(),
+        )
+    }
+
+    fn spans(term: &Term<VoidListener>, tags: &LineTags) -> Vec<PromptSpan> {
+        tags.screen(term).prompt_spans(term.grid())
+    }
+
+    fn starts(term: &Term<VoidListener>, tags: &LineTags) -> Vec<i32> {
+        spans(term, tags).iter().map(|span| span.start.0).collect()
+    }
+
+    #[test]
+    fn test_prompt_marks_recorded() {
+        let (mut term, mut tags) = term(100);
+        feed(
+            &mut term,
+            &mut tags,
+            &[b"\x1b]133;A\x07$ \x1b]133;B\x07ls\r\n\x1b]133;C\x07a\r\nb\r\n\x1b]133;D;0\x07\x1b]133;A\x07$ \x1b]133;B\x07"],
+        );
+        assert_eq!(starts(&term, &tags), vec![0, 3]);
+        assert_eq!(
+            last_command_output_range(term.grid(), &spans(&term, &tags)),
+            Some((Line(1), Line(2)))
+        );
+    }
+
+    #[test]
+    fn test_marker_split_across_reads() {
+        let (mut term, mut tags) = term(100);
+        feed(
+            &mut term,
+            &mut tags,
+            &[b"\x1b]13", b"3;A\x1b", b"\\$ \x1b]133;B\x07"],
+        );
+        assert_eq!(starts(&term, &tags), vec![0]);
+    }
+
+    #[test]
+    fn test_marks_shift_into_history_and_drop() {
+        let (mut term, mut tags) = term(2);
+        let prompt: &[u8] = b"\x1b]133;A\x07$ \x1b]133;B\x07\r\n";
+        feed(&mut term, &mut tags, &[prompt, b"1\r\n2\r\n3\r\n"]);
+        // Prompt line scrolled one line into history
+        assert_eq!(starts(&term, &tags), vec![-1]);
+
+        feed(&mut term, &mut tags, &[b"4\r\n5\r\n"]);
+        // Pushed past the two-line history: mark is gone
+        assert!(starts(&term, &tags).is_empty());
+    }
+
+    #[test]
+    fn test_non_prompt_osc_passthrough() {
+        let mut scanner = PromptMarkScanner::new();
+        let mut out = Vec::new();
+        scanner.process(b"\x1b]0;title\x07\x1b]133;D\x07", &mut out);
+        assert_eq!(out, b"\x1b]0;title\x07\x1b]133;D\x07");
+    }
+}
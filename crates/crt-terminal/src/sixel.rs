@@ -0,0 +1,562 @@
+// ABOUTME: Sixel graphics in PTY output: decodes DCS q streams into RGBA images.
+// ABOUTME: alacritty_terminal drops them, so the scanner keeps the image and tags the rows it covers.
+
+use std::collections::hash_map::DefaultHasher;
+use std::collections::VecDeque;
+use std::hash::{Hash, Hasher};
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::{Arc, Mutex};
+
+use crate::line_tags::Tag;
+
+/// Longest sixel stream decoded; bigger ones are dropped
+const MAX_SIXEL_BYTES: usize = 16 * 1024 * 1024;
+
+/// Largest image side in pixels
+const MAX_IMAGE_SIDE: usize = 4096;
+
+/// Images kept per terminal; the oldest are dropped first
+const MAX_STORED_IMAGES: usize = 64;
+
+/// Longest DCS parameter string before it can't be a sixel introducer
+const MAX_PARAMS: usize = 16;
+
+/// Cell size assumed before the terminal has been told its pixel size
+const DEFAULT_CELL_SIZE: (u32, u32) = (8, 16);
+
+/// VT340 default color registers, in percent
+const DEFAULT_PALETTE: [[u8; 3]; 16] = [
+    [0, 0, 0],
+    [20, 20, 80],
+    [80, 13, 13],
+    [20, 80, 20],
+    [80, 20, 80],
+    [20, 80, 80],
+    [80, 80, 20],
+    [53, 53, 53],
+    [26, 26, 26],
+    [33, 33, 60],
+    [60, 26, 26],
+    [33, 60, 33],
+    [60, 33, 60],
+    [33, 60, 60],
+    [60, 60, 33],
+    [80, 80, 80],
+];
+
+/// A decoded image
+#[derive(Debug, PartialEq)]
+pub struct SixelImage {
+    /// Hash of the sixel stream, also naming the image in cell tags
+    pub id: u64,
+    pub width: u32,
+    pub height: u32,
+    /// Straight RGBA, row by row
+    pub rgba: Vec<u8>,
+}
+
+/// Images decoded from a terminal's output, newest last. Clones share them.
+#[derive(Debug, Clone, Default)]
+pub struct SixelImages {
+    images: Arc<Mutex<VecDeque<Arc<SixelImage>>>>,
+}
+
+impl SixelImages {
+    pub fn insert(&self, image: SixelImage) {
+        let mut images = self.images.lock().unwrap();
+        images.retain(|stored| stored.id != image.id);
+        if images.len() >= MAX_STORED_IMAGES {
+            images.pop_front();
+        }
+        images.push_back(Arc::new(image));
+    }
+
+    pub fn get(&self, id: u64) -> Option<Arc<SixelImage>> {
+        let images = self.images.lock().unwrap();
+        images.iter().find(|image| image.id == id).cloned()
+    }
+}
+
+/// Pixel size of a cell, shared with the PTY reader so it knows how many
+/// rows an image covers
+#[derive(Debug, Clone, Default)]
+pub struct CellPixelSize(Arc<AtomicU32>);
+
+impl CellPixelSize {
+    pub fn set(&self, width: u16, height: u16) {
+        self.0
+            .store((width as u32) << 16 | height as u32, Ordering::Relaxed);
+    }
+
+    /// Whether the terminal was last told exactly this size
+    pub fn is(&self, width: u16, height: u16) -> bool {
+        self.0.load(Ordering::Relaxed) == ((width as u32) << 16 | height as u32)
+    }
+
+    pub fn get(&self) -> (u32, u32) {
+        match self.0.load(Ordering::Relaxed) {
+            0 => DEFAULT_CELL_SIZE,
+            packed => ((packed >> 16).max(1), (packed & 0xffff).max(1)),
+        }
+    }
+}
+
+/// Decode a sixel stream: `params` are the DCS parameters before `q` and
+/// `data` is everything after it, up to the string terminator. None if it
+/// draws nothing.
+pub fn decode(params: &[u8], data: &[u8]) -> Option<SixelImage> {
+    // P2 = 1 leaves pixels that aren't drawn transparent
+    let transparent = params.split(|&b| b == b';').nth(1) == Some(b"1");
+
+    let mut palette = [[0u8; 4]; 256];
+    for (register, color) in palette.iter_mut().zip(DEFAULT_PALETTE) {
+        *register = [percent(color[0]), percent(color[1]), percent(color[2]), 255];
+    }
+
+    let mut canvas = Canvas::default();
+    let mut color = 0;
+    let (mut x, mut band) = (0, 0);
+    let mut i = 0;
+    while i < data.len() {
+        let byte = data[i];
+        i += 1;
+        match byte {
+            b'"' => {
+                // Raster attributes: aspect ratio, then the image size
+                let (values, next) = numbers(data, i);
+                i = next;
+                if let [_, _, width, height, ..] = values[..] {
+                    canvas.reserve(width as usize, height as usize);
+                }
+            }
+            b'#' => {
+                let (values, next) = numbers(data, i);
+                i = next;
+                let Some(&register) = values.first() else {
+                    continue;
+                };
+                color = register.min(255) as usize;
+                match values[..] {
+                    [_, 1, h, l, s] => palette[color] = hls(h, l, s),
+                    [_, 2, r, g, b] => {
+                        palette[color] = [
+                            percent(r.min(100) as u8),
+                            percent(g.min(100) as u8),
+                            percent(b.min(100) as u8),
+                            255,
+                        ]
+                    }
+                    _ => {}
+                }
+            }
+            b'!' => {
+                let (values, next) = numbers(data, i);
+                i = next;
+                let count = values.first().copied().unwrap_or(1).max(1) as usize;
+                if let Some(&sixel @ 0x3f..=0x7e) = data.get(i) {
+                    i += 1;
+                    for _ in 0..count.min(MAX_IMAGE_SIDE) {
+                        canvas.paint(x, band * 6, sixel - 0x3f, palette[color]);
+                        x += 1;
+                    }
+                }
+            }
+            b'$' => x = 0,
+            b'-' => {
+                x = 0;
+                band += 1;
+            }
+            0x3f..=0x7e => {
+                canvas.paint(x, band * 6, byte - 0x3f, palette[color]);
+                x += 1;
+            }
+            _ => {}
+        }
+    }
+
+    let background = (!transparent).then_some(palette[0]);
+    let (width, height, rgba) = canvas.finish(background)?;
+    let mut hasher = DefaultHasher::new();
+    params.hash(&mut hasher);
+    data.hash(&mut hasher);
+    Some(SixelImage {
+        id: hasher.finish(),
+        width: width as u32,
+        height: height as u32,
+        rgba,
+    })
+}
+
+fn percent(value: u8) -> u8 {
+    ((value as u32 * 255 + 50) / 100) as u8
+}
+
+/// Semicolon-separated numbers starting at `start`, and where they end
+fn numbers(data: &[u8], start: usize) -> (Vec<u32>, usize) {
+    let mut values = Vec::new();
+    let mut current: Option<u32> = None;
+    let mut i = start;
+    while let Some(&byte) = data.get(i) {
+        match byte {
+            b'0'..=b'9' => {
+                let digit = (byte - b'0') as u32;
+                current = Some(
+                    current
+                        .unwrap_or(0)
+                        .saturating_mul(10)
+                        .saturating_add(digit),
+                );
+            }
+            b';' => values.push(current.take().unwrap_or(0)),
+            _ => break,
+        }
+        i += 1;
+    }
+    if let Some(value) = current {
+        values.push(value);
+    }
+    (values, i)
+}
+
+/// A sixel HLS color; hue 0 is blue, as on the VT340
+fn hls(hue: u32, lightness: u32, saturation: u32) -> [u8; 4] {
+    let h = ((hue + 240) % 360) as f32 / 360.0;
+    let l = lightness.min(100) as f32 / 100.0;
+    let s = saturation.min(100) as f32 / 100.0;
+    let q = if l < 0.5 {
+        l * (1.0 + s)
+    } else {
+        l + s - l * s
+    };
+    let p = 2.0 * l - q;
+    let channel = |t: f32| {
+        let t = t.rem_euclid(1.0);
+        let value = if t < 1.0 / 6.0 {
+            p + (q - p) * 6.0 * t
+        } else if t < 0.5 {
+            q
+        } else if t < 2.0 / 3.0 {
+            p + (q - p) * (2.0 / 3.0 - t) * 6.0
+        } else {
+            p
+        };
+        (value * 255.0).round() as u8
+    };
+    [
+        channel(h + 1.0 / 3.0),
+        channel(h),
+        channel(h - 1.0 / 3.0),
+        255,
+    ]
+}
+
+/// Pixels drawn so far; alpha 0 marks ones that weren't
+#[derive(Default)]
+struct Canvas {
+    width: usize,
+    height: usize,
+    rgba: Vec<u8>,
+    /// Size given by the raster attributes, if larger than what's drawn
+    declared: (usize, usize),
+    /// Extent of the drawn pixels
+    used: (usize, usize),
+}
+
+impl Canvas {
+    fn reserve(&mut self, width: usize, height: usize) {
+        let width = width.min(MAX_IMAGE_SIDE);
+        let height = height.min(MAX_IMAGE_SIDE);
+        self.declared = (width, height);
+        self.grow(width, height);
+    }
+
+    fn grow(&mut self, width: usize, height: usize) {
+        if width <= self.width && height <= self.height {
+            return;
+        }
+        let new_width = width.max(self.width);
+        let new_height = height.max(self.height);
+        let mut rgba = vec![0; new_width * new_height * 4];
+        for row in 0..self.height {
+            let old = &self.rgba[row * self.width * 4..(row + 1) * self.width * 4];
+            rgba[row * new_width * 4..][..old.len()].copy_from_slice(old);
+        }
+        self.width = new_width;
+        self.height = new_height;
+        self.rgba = rgba;
+    }
+
+    /// Paint the pixels set in `bits` in the six-pixel column at (x, top)
+    fn paint(&mut self, x: usize, top: usize, bits: u8, color: [u8; 4]) {
+        if bits == 0 || x >= MAX_IMAGE_SIDE || top >= MAX_IMAGE_SIDE {
+            return;
+        }
+        let bottom = (top + 6).min(MAX_IMAGE_SIDE);
+        if x >= self.width || bottom > self.height {
+            // Grow in steps so long lines don't copy the canvas per column
+            let width = (x + 1).max(self.width * 2).min(MAX_IMAGE_SIDE);
+            let height = bottom.max(self.height * 2).min(MAX_IMAGE_SIDE);
+            self.grow(width.max(x + 1), height.max(bottom));
+        }
+        for bit in 0..(bottom - top) {
+            if bits & (1 << bit) != 0 {
+                let offset = ((top + bit) * self.width + x) * 4;
+                self.rgba[offset..offset + 4].copy_from_slice(&color);
+                self.used.1 = self.used.1.max(top + bit + 1);
+            }
+        }
+        self.used.0 = self.used.0.max(x + 1);
+    }
+
+    /// The image cut to its size, with undrawn pixels set to `background`
+    fn finish(self, background: Option<[u8; 4]>) -> Option<(usize, usize, Vec<u8>)> {
+        let width = self.used.0.max(self.declared.0);
+        let height = self.used.1.max(self.declared.1);
+        if width == 0 || height == 0 {
+            return None;
+        }
+        let mut rgba = Vec::with_capacity(width * height * 4);
+        for row in self.rgba.chunks_exact(self.width * 4).take(height) {
+            rgba.extend_from_slice(&row[..width * 4]);
+        }
+        if let Some(background) = background {
+            for pixel in rgba.chunks_exact_mut(4) {
+                if pixel[3] == 0 {
+                    pixel.copy_from_slice(&background);
+                }
+            }
+        }
+        Some((width, height, rgba))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ScanState {
+    Ground,
+    Escape,
+    /// After `ESC P`, reading parameters
+    Params,
+    /// After the `q`, collecting sixel data
+    Data,
+    DataEscape,
+}
+
+/// Streaming scanner that takes sixel images out of PTY output.
+///
+/// A `DCS P1;P2 q ... ST` sequence is decoded and replaced by a tagged
+/// blank in the image's first column on each row it covers; the cursor ends
+/// up on the line below the image. Other bytes pass through unchanged.
+#[derive(Debug)]
+pub struct SixelScanner {
+    state: ScanState,
+    params: Vec<u8>,
+    data: Vec<u8>,
+    overflow: bool,
+    images: SixelImages,
+    cell_size: CellPixelSize,
+}
+
+impl SixelScanner {
+    pub fn new(images: SixelImages, cell_size: CellPixelSize) -> Self {
+        Self {
+            state: ScanState::Ground,
+            params: Vec::new(),
+            data: Vec::new(),
+            overflow: false,
+            images,
+            cell_size,
+        }
+    }
+
+    /// Process a chunk of PTY output, appending the rewritten bytes to `out`
+    pub fn process(&mut self, input: &[u8], out: &mut Vec<u8>) {
+        for &byte in input {
+            self.advance(byte, out);
+        }
+    }
+
+    fn advance(&mut self, byte: u8, out: &mut Vec<u8>) {
+        match self.state {
+            ScanState::Ground => {
+                if byte == 0x1b {
+                    self.state = ScanState::Escape;
+                } else {
+                    out.push(byte);
+                }
+            }
+            ScanState::Escape => {
+                if byte == b'P' {
+                    self.state = ScanState::Params;
+                    self.params.clear();
+                } else {
+                    out.push(0x1b);
+                    self.state = ScanState::Ground;
+                    self.advance(byte, out);
+                }
+            }
+            ScanState::Params => match byte {
+                b'0'..=b'9' | b';' if self.params.len() < MAX_PARAMS => self.params.push(byte),
+                b'q' => {
+                    self.state = ScanState::Data;
+                    self.data.clear();
+                    self.overflow = false;
+                }
+                // Some other DCS: hand it on as it came
+                _ => {
+                    out.extend_from_slice(b"\x1bP");
+                    out.extend_from_slice(&self.params);
+                    self.state = ScanState::Ground;
+                    self.advance(byte, out);
+                }
+            },
+            ScanState::Data => match byte {
+                0x1b => self.state = ScanState::DataEscape,
+                // CAN and SUB abort the sequence
+                0x18 | 0x1a => self.state = ScanState::Ground,
+                _ => {
+                    if self.data.len() < MAX_SIXEL_BYTES {
+                        self.data.push(byte);
+                    } else {
+                        self.overflow = true;
+                    }
+                }
+            },
+            ScanState::DataEscape => {
+                self.finish(out);
+                // Any escape ends the string; only ST is swallowed with it
+                if byte != b'\\' {
+                    self.state = ScanState::Escape;
+                    self.advance(byte, out);
+                }
+            }
+        }
+    }
+
+    fn finish(&mut self, out: &mut Vec<u8>) {
+        self.state = ScanState::Ground;
+        if self.overflow {
+            return;
+        }
+        let Some(image) = decode(&self.params, &self.data) else {
+            return;
+        };
+
+        let (_, cell_height) = self.cell_size.get();
+        let rows = image.height.div_ceil(cell_height);
+        let id = image.id;
+        self.images.insert(image);
+
+        for row in 0..rows {
+            // Tag the row at the cursor, then go down a line
+            out.extend_from_slice(
+                Tag::Image {
+                    id,
+                    row: row as usize,
+                }
+                .escape()
+                .as_bytes(),
+            );
+            out.push(b'\n');
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::line_tags::{ImageRow, LineTags, TagHandler};
+    use alacritty_terminal::event::VoidListener;
+    use alacritty_terminal::index::{Column, Line, Point};
+    use alacritty_terminal::term::test::TermSize;
+    use alacritty_terminal::term::{Config, Term};
+    use alacritty_terminal::vte::ansi::Processor;
+
+    fn pixel(image: &SixelImage, x: usize, y: usize) -> [u8; 4] {
+        let offset = (y * image.width as usize + x) * 4;
+        image.rgba[offset..offset + 4].try_into().unwrap()
+    }
+
+    #[test]
+    fn decodes_colors_repeats_and_bands() {
+        // Red 2x6 block, then a green pixel at the top of the next band
+        let image = decode(b"0;1", b"#1;2;100;0;0#1!2~-#2;2;0;100;0@").unwrap();
+        assert_eq!((image.width, image.height), (2, 7));
+        assert_eq!(pixel(&image, 1, 5), [255, 0, 0, 255]);
+        assert_eq!(pixel(&image, 0, 6), [0, 255, 0, 255]);
+        // Transparent background with P2 = 1
+        assert_eq!(pixel(&image, 1, 6), [0, 0, 0, 0]);
+    }
+
+    #[test]
+    fn raster_attributes_size_the_image() {
+        let image = decode(b"", b"\"1;1;10;12#0;2;0;0;100?").unwrap();
+        assert_eq!((image.width, image.height), (10, 12));
+        // Undrawn pixels take color 0 without P2 = 1
+        assert_eq!(pixel(&image, 9, 11), [0, 0, 255, 255]);
+        assert_eq!(decode(b"", b"#1"), None);
+    }
+
+    #[test]
+    fn hls_hue_starts_at_blue() {
+        assert_eq!(hls(0, 50, 100), [0, 0, 255, 255]);
+        assert_eq!(hls(120, 50, 100), [255, 0, 0, 255]);
+        assert_eq!(hls(240, 50, 100), [0, 255, 0, 255]);
+    }
+
+    #[test]
+    fn scanner_tags_the_rows_an_image_covers() {
+        let images = SixelImages::default();
+        let cell_size = CellPixelSize::default();
+        cell_size.set(8, 4);
+        let mut scanner = SixelScanner::new(images.clone(), cell_size);
+
+        // A 1x12 image is three rows of 4px cells; split across reads
+        let stream: &[u8] = b"ab\x1bPq#1~-~\x1b\\c\x1bP$qm\x1b\\";
+        let mut out = Vec::new();
+        for chunk in stream.chunks(5) {
+            scanner.process(chunk, &mut out);
+        }
+
+        let mut term = Term::new(Config::default(), &TermSize::new(10, 5), VoidListener);
+        let mut tags = LineTags::default();
+        let mut parser: Processor = Processor::new();
+        parser.advance(&mut TagHandler::new(&mut term, &mut tags), &out);
+
+        let grid = term.grid();
+        let screen = tags.screen(&term);
+        let [first] = screen.images(Line(0)) else {
+            panic!("one image row on the first line");
+        };
+        assert_eq!((first.column, first.row), (2, 0));
+        let id = first.id;
+        let third = ImageRow {
+            column: 2,
+            id,
+            row: 2,
+        };
+        assert_eq!(screen.images(Line(2)), [third]);
+        assert!(screen.images(Line(3)).is_empty());
+        assert_eq!(images.get(id).unwrap().height, 12);
+        // Text after the image goes below it, in the same column
+        assert_eq!(grid[Line(3)][Column(2)].c, 'c');
+        assert_eq!(grid.cursor.point, Point::new(Line(3), Column(3)));
+
+        // Other DCS strings pass through untouched
+        assert!(out.ends_with(b"\x1bP$qm\x1b\\"));
+    }
+
+    #[test]
+    fn store_drops_the_oldest_images() {
+        let images = SixelImages::default();
+        for id in 0..=MAX_STORED_IMAGES as u64 {
+            images.insert(SixelImage {
+                id,
+                width: 1,
+                height: 1,
+                rgba: vec![0; 4],
+            });
+        }
+        assert!(images.get(0).is_none());
+        assert!(images.get(MAX_STORED_IMAGES as u64).is_some());
+    }
+}
@@ -3,49 +3,234 @@
 
 use alacritty_terminal::event::{Event, WindowSize};
 use alacritty_terminal::event_loop::{EventLoop, EventLoopSender, Msg};
-use alacritty_terminal::grid::{Dimensions, Scroll};
+use alacritty_terminal::grid::{Dimensions, Row, Scroll};
+use alacritty_terminal::index::{Column, Direction, Line, Point};
 use alacritty_terminal::sync::FairMutex;
-use alacritty_terminal::term::Term;
+use alacritty_terminal::term::cell::Cell;
+use alacritty_terminal::term::search::{RegexIter, RegexSearch};
+use alacritty_terminal::term::{ClipboardType, Osc52, Term, TermDamage};
 use alacritty_terminal::tty;
+use alacritty_terminal::vi_mode::ViMotion;
+use alacritty_terminal::vte::ansi::{CursorShape, CursorStyle};
 use alacritty_terminal::Grid;
-use std::path::PathBuf;
-use std::sync::atomic::{AtomicBool, Ordering};
-use std::sync::Arc;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicU8, Ordering};
+use std::sync::{Arc, Mutex};
+
+use crate::disk_scrollback::{page_out, DiskScrollback};
+use crate::line_tags::{LineTags, ScreenTags};
+use crate::scrollback::{ScrollbackSource, SerializedLine};
+use crate::shell_integration::PromptSpan;
+use crate::sixel::{CellPixelSize, SixelImage, SixelImages};
 
 /// Default scrollback history size (number of lines)
 const SCROLLBACK_LINES: usize = 10_000;
 
-/// Terminal instance with PTY and terminal state
+/// History kept for "unlimited" scrollback (`usize::MAX`). alacritty adds
+/// the screen height to the limit when resizing, so it can't be usize::MAX.
+const UNLIMITED_SCROLLBACK_LINES: usize = u32::MAX as usize;
+
+/// History kept in memory beyond the scrollback limit when paging out to
+/// disk, so output arriving between page-outs isn't dropped
+const DISK_PAGING_HEADROOM: usize = 10_000;
+
+/// Callback run on the PTY thread when new output or an exit needs a redraw
+type WakeupCallback = Box<dyn Fn() + Send + Sync>;
+
+/// Clipboard access requested by the application with OSC 52
+pub enum ClipboardRequest {
+    Store(ClipboardType, String),
+    /// Reply with the clipboard contents, formatted into a response by the closure
+    Load(ClipboardType, Arc<dyn Fn(&str) -> String + Sync + Send>),
+}
+
+/// Terminal instance with PTY and terminal state.
+///
+/// PTY reading and parsing run on alacritty's event loop thread; methods here
+/// only take the term lock briefly and never block on PTY I/O.
 pub struct Terminal {
     term: Arc<FairMutex<Term<EventProxy>>>,
+    /// Prompt marks, line sizes and sixel image rows recorded by the PTY reader
+    tags: Arc<Mutex<LineTags>>,
     sender: EventLoopSender,
     exited: Arc<AtomicBool>,
+    /// Exit code of the child, once known (None if killed by a signal)
+    exit_code: Arc<Mutex<Option<i32>>>,
+    /// Bumped each time the PTY reader parses new output
+    generation: Arc<AtomicU64>,
+    /// Generation last observed by `take_damage`
+    damage_generation: AtomicU64,
+    wakeup: Arc<Mutex<Option<WakeupCallback>>>,
     /// PID of the shell process (Unix only, 0 on Windows)
     child_pid: u32,
+    /// Why the configured shell couldn't be started, if we fell back to the default
+    spawn_error: Option<String>,
+    /// History size when copy mode last pinned the view (None outside copy mode)
+    copy_mode_history: Mutex<Option<usize>>,
+    /// Buffer line of the text followed by `take_anchor_scroll`, as last seen
+    scroll_anchor: Mutex<Option<i32>>,
+    /// Set when the application rings the bell, cleared by `take_bell`
+    bell_pending: Arc<AtomicBool>,
+    /// OSC 52 requests waiting for `take_clipboard_requests`
+    clipboard_requests: Arc<Mutex<Vec<ClipboardRequest>>>,
+    /// Title set by the application with OSC 0 or OSC 2
+    title: Arc<Mutex<Option<String>>>,
+    /// Raw PTY output since the last `take_output`, while capturing (Unix only)
+    output_capture: Arc<Mutex<Option<Vec<u8>>>>,
+    /// xterm modifyOtherKeys level set by the application (Unix only)
+    modify_other_keys: Arc<AtomicU8>,
+    /// Sixel images drawn by the application (Unix only)
+    images: SixelImages,
+    /// Cell size in pixels, for sizing sixel images in cells
+    cell_size: CellPixelSize,
+    /// Most lines of history kept above the screen
+    scrollback_lines: usize,
+    /// History past `scrollback_lines`, paged out to disk when enabled
+    disk: Option<Mutex<DiskPaging>>,
+}
+
+/// Scrollback paged out to disk, and how far the view is scrolled into it
+struct DiskPaging {
+    rows: DiskScrollback,
+    /// Rows past the top of the in-memory history scrolled into view
+    view_offset: usize,
+    /// History size at the last page-out, to notice the application clearing it
+    last_history: usize,
+    /// Set once writing failed; the history then just stops at its limit
+    failed: bool,
+}
+
+/// Text on screen matching a regex. Positions are (column, line) with
+/// lines relative to the bottom of history, like `copy_mode_cursor`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TextMatch {
+    pub start: (usize, i32),
+    /// Last cell of the match (inclusive)
+    pub end: (usize, i32),
+    pub text: String,
+}
+
+/// Program to run in the PTY instead of the user's default shell
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ShellCommand {
+    /// Program to exec (None = $SHELL)
+    pub program: Option<String>,
+    pub args: Vec<String>,
+    /// Start as a login shell (passes `-l`)
+    pub login: bool,
+}
+
+impl ShellCommand {
+    /// Resolve to a program and argv, or None to let the PTY pick the default shell
+    fn to_tty_shell(&self) -> Option<tty::Shell> {
+        if self.program.is_none() && !self.login {
+            return None;
+        }
+        let program = self
+            .program
+            .clone()
+            .or_else(|| std::env::var("SHELL").ok())?;
+        let mut args = self.args.clone();
+        if self.login {
+            args.insert(0, "-l".to_string());
+        }
+        Some(tty::Shell::new(program, args))
+    }
 }
 
 /// Proxy for terminal events
 #[derive(Clone)]
 struct EventProxy {
     exited: Arc<AtomicBool>,
+    exit_code: Arc<Mutex<Option<i32>>>,
+    generation: Arc<AtomicU64>,
+    wakeup: Arc<Mutex<Option<WakeupCallback>>>,
     sender: std::sync::mpsc::Sender<String>,
+    bell_pending: Arc<AtomicBool>,
+    clipboard_requests: Arc<Mutex<Vec<ClipboardRequest>>>,
+    title: Arc<Mutex<Option<String>>>,
+}
+
+impl EventProxy {
+    fn wake(&self) {
+        if let Some(callback) = self.wakeup.lock().unwrap().as_ref() {
+            callback();
+        }
+    }
 }
 
 impl alacritty_terminal::event::EventListener for EventProxy {
     fn send_event(&self, event: Event) {
         match event {
+            Event::ChildExit(code) => {
+                // Sent just before Exit when the child reported a status
+                *self.exit_code.lock().unwrap() = Some(code);
+            }
             Event::Exit => {
                 self.exited.store(true, Ordering::SeqCst);
+                self.wake();
             }
             Event::PtyWrite(text) => {
                 // Send response back to PTY (e.g., cursor position query response)
                 let _ = self.sender.send(text);
             }
+            Event::ClipboardStore(kind, text) => {
+                let request = ClipboardRequest::Store(kind, text);
+                self.clipboard_requests.lock().unwrap().push(request);
+                self.wake();
+            }
+            Event::ClipboardLoad(kind, format) => {
+                let request = ClipboardRequest::Load(kind, format);
+                self.clipboard_requests.lock().unwrap().push(request);
+                self.wake();
+            }
+            Event::Title(title) => {
+                *self.title.lock().unwrap() = Some(title);
+                self.wake();
+            }
+            Event::ResetTitle => {
+                *self.title.lock().unwrap() = None;
+                self.wake();
+            }
+            Event::Bell => {
+                self.bell_pending.store(true, Ordering::SeqCst);
+                self.wake();
+            }
+            Event::Wakeup => {
+                // The event loop parsed a batch of output into the grid
+                self.generation.fetch_add(1, Ordering::SeqCst);
+                self.wake();
+            }
             _ => {}
         }
     }
 }
 
+/// Kitty protocol flag bits for the keyboard modes set in `mode`
+fn kitty_flags(mode: alacritty_terminal::term::TermMode) -> u8 {
+    use alacritty_terminal::term::TermMode;
+    [
+        TermMode::DISAMBIGUATE_ESC_CODES,
+        TermMode::REPORT_EVENT_TYPES,
+        TermMode::REPORT_ALTERNATE_KEYS,
+        TermMode::REPORT_ALL_KEYS_AS_ESC,
+        TermMode::REPORT_ASSOCIATED_TEXT,
+    ]
+    .iter()
+    .enumerate()
+    .filter(|(_, flag)| mode.contains(**flag))
+    .fold(0, |flags, (bit, _)| flags | 1 << bit)
+}
+
+fn term_config(osc52: Osc52, history_lines: usize) -> alacritty_terminal::term::Config {
+    alacritty_terminal::term::Config {
+        scrolling_history: history_lines.min(UNLIMITED_SCROLLBACK_LINES),
+        kitty_keyboard: true,
+        osc52,
+        ..Default::default()
+    }
+}
+
 /// Simple size type that implements Dimensions
 struct TermSize {
     columns: usize,
@@ -78,6 +263,29 @@ pub enum TerminalError {
     PtyError(#[from] std::io::Error),
 }
 
+/// History alacritty keeps: the scrollback, plus headroom when paging out to disk
+fn history_lines(scrollback_lines: usize, disk: bool) -> usize {
+    if disk {
+        scrollback_lines.saturating_add(DISK_PAGING_HEADROOM)
+    } else {
+        scrollback_lines
+    }
+}
+
+/// The disk view offset, or 0 once something else moved the view off the
+/// top of the history, e.g. jumping to a prompt
+fn disk_view_offset<T>(term: &Term<T>, disk: &mut DiskPaging) -> usize {
+    let grid = term.grid();
+    if grid.display_offset() < grid.history_size()
+        || term
+            .mode()
+            .contains(alacritty_terminal::term::TermMode::ALT_SCREEN)
+    {
+        disk.view_offset = 0;
+    }
+    disk.view_offset
+}
+
 impl Terminal {
     /// Create a new terminal with the given dimensions
     pub fn new(columns: u16, rows: u16) -> Result<Self, TerminalError> {
@@ -89,6 +297,29 @@ impl Terminal {
         columns: u16,
         rows: u16,
         working_directory: Option<PathBuf>,
+    ) -> Result<Self, TerminalError> {
+        Self::with_shell(
+            columns,
+            rows,
+            working_directory,
+            &ShellCommand::default(),
+            SCROLLBACK_LINES,
+            None,
+        )
+    }
+
+    /// Create a new terminal running `shell`, keeping up to `scrollback_lines`
+    /// of history (`usize::MAX` for unlimited). With `disk_scrollback` set,
+    /// older history is paged out to a file in that directory instead of
+    /// dropped. If the shell fails to spawn, the default shell is started
+    /// instead and the error is kept in `spawn_error`.
+    pub fn with_shell(
+        columns: u16,
+        rows: u16,
+        working_directory: Option<PathBuf>,
+        shell: &ShellCommand,
+        scrollback_lines: usize,
+        disk_scrollback: Option<&Path>,
     ) -> Result<Self, TerminalError> {
         // Set TERM and COLORTERM in the process environment before spawning the shell.
         // This is required for GUI apps launched from Finder which have no parent terminal.
@@ -97,16 +328,16 @@ impl Terminal {
         let cwd = working_directory.or_else(dirs::home_dir);
 
         #[cfg(not(windows))]
-        let pty_config = tty::Options {
-            shell: None,
+        let mut pty_config = tty::Options {
+            shell: shell.to_tty_shell(),
             working_directory: cwd,
             drain_on_exit: true,
             env: std::collections::HashMap::new(),
         };
 
         #[cfg(windows)]
-        let pty_config = tty::Options {
-            shell: None,
+        let mut pty_config = tty::Options {
+            shell: shell.to_tty_shell(),
             working_directory: cwd,
             drain_on_exit: true,
             env: std::collections::HashMap::new(),
@@ -120,32 +351,92 @@ impl Terminal {
             cell_height: 1,
         };
 
-        let pty = tty::new(&pty_config, window_size, 0)?;
-
-        // Capture PID before pty is moved into EventLoop
-        #[cfg(not(windows))]
-        let child_pid = pty.child().id();
-        #[cfg(windows)]
-        let child_pid = 0;
+        let mut spawn_error = None;
+        let pty = match tty::new(&pty_config, window_size, 0) {
+            Ok(pty) => pty,
+            Err(e) if pty_config.shell.is_some() => {
+                let program = shell.program.as_deref().unwrap_or("login shell");
+                let message = format!("Failed to start {}: {}", program, e);
+                tracing::warn!("{}, falling back to default shell", message);
+                spawn_error = Some(message);
+                pty_config.shell = None;
+                tty::new(&pty_config, window_size, 0)?
+            }
+            Err(e) => return Err(e.into()),
+        };
 
         let exited = Arc::new(AtomicBool::new(false));
+        let exit_code = Arc::new(Mutex::new(None));
+        let generation = Arc::new(AtomicU64::new(0));
+        let wakeup = Arc::new(Mutex::new(None));
+        let bell_pending = Arc::new(AtomicBool::new(false));
+        let clipboard_requests = Arc::new(Mutex::new(Vec::new()));
+        let title = Arc::new(Mutex::new(None));
 
         // Channel for PtyWrite events (cursor position queries, etc.)
         let (pty_write_tx, pty_write_rx) = std::sync::mpsc::channel::<String>();
 
         let event_proxy = EventProxy {
             exited: Arc::clone(&exited),
+            exit_code: Arc::clone(&exit_code),
+            generation: Arc::clone(&generation),
+            wakeup: Arc::clone(&wakeup),
             sender: pty_write_tx,
+            bell_pending: Arc::clone(&bell_pending),
+            clipboard_requests: Arc::clone(&clipboard_requests),
+            title: Arc::clone(&title),
         };
 
+        // Unlimited scrollback never pages anything out
+        let disk = disk_scrollback
+            .filter(|_| scrollback_lines < UNLIMITED_SCROLLBACK_LINES)
+            .and_then(|dir| match DiskScrollback::create(dir) {
+                Ok(rows) => Some(Mutex::new(DiskPaging {
+                    rows,
+                    view_offset: 0,
+                    last_history: 0,
+                    failed: false,
+                })),
+                Err(e) => {
+                    tracing::warn!("Can't create disk scrollback in {}: {}", dir.display(), e);
+                    None
+                }
+            });
+        let history_lines = history_lines(scrollback_lines, disk.is_some());
+
         let term_size = TermSize::new(columns as usize, rows as usize);
-        let term_config = alacritty_terminal::term::Config {
-            scrolling_history: SCROLLBACK_LINES,
-            kitty_keyboard: true,
-            ..Default::default()
-        };
-        let term = Term::new(term_config, &term_size, event_proxy.clone());
+        let term = Term::new(
+            term_config(Osc52::default(), history_lines),
+            &term_size,
+            event_proxy.clone(),
+        );
         let term = Arc::new(FairMutex::new(term));
+        let tags = Arc::new(Mutex::new(LineTags::default()));
+
+        // Tag OSC 133 prompt markers, line sizes and sixel images on their way in
+        let output_capture = Arc::new(Mutex::new(None));
+        let modify_other_keys = Arc::new(AtomicU8::new(0));
+        let images = SixelImages::default();
+        let cell_size = CellPixelSize::default();
+        #[cfg(unix)]
+        let pty = crate::shell_integration::ShellIntegrationPty::new(
+            pty,
+            crate::shell_integration::ParseTarget {
+                term: Arc::clone(&term),
+                tags: Arc::clone(&tags),
+                listener: event_proxy.clone(),
+            },
+            Arc::clone(&output_capture),
+            Arc::clone(&modify_other_keys),
+            images.clone(),
+            cell_size.clone(),
+        )?;
+
+        // Capture PID before pty is moved into EventLoop
+        #[cfg(not(windows))]
+        let child_pid = pty.child().id();
+        #[cfg(windows)]
+        let child_pid = 0;
 
         let event_loop = EventLoop::new(Arc::clone(&term), event_proxy, pty, false, false)?;
 
@@ -166,17 +457,121 @@ impl Terminal {
 
         Ok(Self {
             term,
+            tags,
             sender,
             exited,
+            exit_code,
+            generation,
+            damage_generation: AtomicU64::new(u64::MAX),
+            wakeup,
             child_pid,
+            spawn_error,
+            copy_mode_history: Mutex::new(None),
+            scroll_anchor: Mutex::new(None),
+            bell_pending,
+            clipboard_requests,
+            title,
+            output_capture,
+            modify_other_keys,
+            images,
+            cell_size,
+            scrollback_lines,
+            disk,
         })
     }
 
+    /// Register a callback invoked from the PTY thread whenever new output
+    /// has been parsed or the shell exits. Replaces any previous callback.
+    pub fn set_wakeup_callback(&self, callback: impl Fn() + Send + Sync + 'static) {
+        *self.wakeup.lock().unwrap() = Some(Box::new(callback));
+    }
+
+    /// Error from the configured shell if the default shell was started instead
+    pub fn spawn_error(&self) -> Option<&str> {
+        self.spawn_error.as_deref()
+    }
+
     /// Check if the shell has exited
     pub fn has_exited(&self) -> bool {
         self.exited.load(Ordering::SeqCst)
     }
 
+    /// Exit code of the shell after it has exited. None while it is running
+    /// or if it was killed by a signal.
+    pub fn exit_code(&self) -> Option<i32> {
+        *self.exit_code.lock().unwrap()
+    }
+
+    /// Check whether the application rang the bell (BEL) since the last
+    /// call, and reset the flag
+    pub fn take_bell(&self) -> bool {
+        self.bell_pending.swap(false, Ordering::SeqCst)
+    }
+
+    /// Window title the application set with OSC 0 or OSC 2, if any
+    pub fn title(&self) -> Option<String> {
+        self.title.lock().unwrap().clone()
+    }
+
+    /// Sixel image named by a cell tag, while it's still kept
+    pub fn image(&self, id: u64) -> Option<Arc<SixelImage>> {
+        self.images.get(id)
+    }
+
+    /// xterm modifyOtherKeys level: 0 (off), 1 or 2
+    pub fn modify_other_keys(&self) -> u8 {
+        self.modify_other_keys.load(Ordering::SeqCst)
+    }
+
+    /// Start or stop collecting raw PTY output for `take_output`, e.g. to
+    /// record the session. Only supported on Unix.
+    pub fn set_output_capture(&self, enabled: bool) {
+        *self.output_capture.lock().unwrap() = enabled.then(Vec::new);
+    }
+
+    /// Raw PTY output collected since the last call, before any parsing
+    pub fn take_output(&self) -> Vec<u8> {
+        self.output_capture
+            .lock()
+            .unwrap()
+            .as_mut()
+            .map(std::mem::take)
+            .unwrap_or_default()
+    }
+
+    /// Which OSC 52 clipboard requests the application may make
+    pub fn set_osc52(&self, osc52: Osc52) {
+        let config = term_config(
+            osc52,
+            history_lines(self.scrollback_lines, self.disk.is_some()),
+        );
+        self.term.lock().set_options(config);
+    }
+
+    /// OSC 52 clipboard requests made since the last call
+    pub fn take_clipboard_requests(&self) -> Vec<ClipboardRequest> {
+        std::mem::take(&mut *self.clipboard_requests.lock().unwrap())
+    }
+
+    /// Counter that increases whenever new PTY output has been processed
+    pub fn generation(&self) -> u64 {
+        self.generation.load(Ordering::SeqCst)
+    }
+
+    /// Check whether the visible content changed since the last call, and reset
+    /// the damage state. Covers both PTY output and full redraws caused by
+    /// scrolling, resizing or clearing the screen.
+    pub fn take_damage(&self) -> bool {
+        let generation = self.generation();
+        let output = self.damage_generation.swap(generation, Ordering::SeqCst) != generation;
+        let mut term = self.term.lock();
+        // Partial damage always includes the cursor cell, so it says nothing
+        // on its own; real edits from the PTY are reported via `output`.
+        let full = matches!(term.damage(), TermDamage::Full);
+        term.reset_damage();
+        output || full
+    }
+
     /// Get the PID of the shell process (Unix only, returns 0 on Windows)
     pub fn child_pid(&self) -> u32 {
         self.child_pid
@@ -187,10 +582,31 @@ impl Terminal {
         crate::process_info::get_process_cwd(self.child_pid)
     }
 
+    /// Program and arguments of the job in the foreground, if the shell is
+    /// running one (Linux only)
+    pub fn foreground_command(&self) -> Option<Vec<String>> {
+        crate::process_info::get_foreground_command(self.child_pid)
+    }
+
+    /// Name of the program in the foreground: the running job, or the shell
+    /// itself when it's waiting at a prompt
+    pub fn foreground_name(&self) -> Option<String> {
+        let job = self.foreground_command().and_then(|args| {
+            let program = std::path::Path::new(args.first()?).file_name()?;
+            Some(program.to_string_lossy().into_owned())
+        });
+        job.or_else(|| crate::process_info::get_process_name(self.child_pid))
+    }
+
     /// Capture scrollback data for session restoration
     pub fn capture_scrollback(&self) -> crate::scrollback::ScrollbackData {
         let term = self.term.lock();
-        crate::scrollback::ScrollbackData::from_grid(term.grid())
+        let prompts = self.prompt_spans(&term);
+        let mut data = crate::scrollback::ScrollbackData::from_grid(term.grid(), &prompts);
+        if let Some(disk) = &self.disk {
+            data.source = ScrollbackSource::Disk(disk.lock().unwrap().rows.clone());
+        }
+        data
     }
 
     /// Send input bytes to the terminal
@@ -198,19 +614,30 @@ impl Terminal {
         let _ = self.sender.send(Msg::Input(bytes.to_vec().into()));
     }
 
-    /// Resize the terminal
-    pub fn resize(&self, columns: u16, rows: u16) {
+    /// Resize the grid and tell the PTY, including its size in pixels
+    /// (`cell_width` x `cell_height` per cell) for programs that draw images.
+    /// Nothing changes if the size is the same, so the view stays put.
+    pub fn resize(&self, columns: u16, rows: u16, cell_width: u16, cell_height: u16) {
+        if self.size() == (columns, rows)
+            && self.cell_size.is(cell_width.max(1), cell_height.max(1))
+        {
+            return;
+        }
+        // The PTY reports columns * cell_width as ws_xpixel, and likewise rows
         let window_size = WindowSize {
             num_cols: columns,
             num_lines: rows,
-            cell_width: 1,
-            cell_height: 1,
+            cell_width: cell_width.max(1),
+            cell_height: cell_height.max(1),
         };
 
         let term_size = TermSize::new(columns as usize, rows as usize);
 
+        self.cell_size
+            .set(window_size.cell_width, window_size.cell_height);
         let _ = self.sender.send(Msg::Resize(window_size));
-        self.term.lock().resize(term_size);
+        let mut term = self.term.lock();
+        self.tags.lock().unwrap().resize(&mut term, term_size);
     }
 
     /// Access the terminal grid for rendering
@@ -222,6 +649,17 @@ impl Terminal {
         f(term.grid())
     }
 
+    /// Access the terminal grid for rendering, with the line sizes and
+    /// sixel images recorded for it
+    pub fn with_tagged_grid<F, R>(&self, f: F) -> R
+    where
+        F: FnOnce(&Grid<alacritty_terminal::term::cell::Cell>, &ScreenTags) -> R,
+    {
+        let term = self.term.lock();
+        let tags = self.tags.lock().unwrap();
+        f(term.grid(), tags.screen(&term))
+    }
+
     /// Access terminal content including cursor for rendering
     pub fn with_content<F, R>(&self, f: F) -> R
     where
@@ -255,30 +693,151 @@ impl Terminal {
         Some((cursor.column.0, line as usize))
     }
 
-    /// Scroll the display by a number of lines (negative = up, positive = down)
+    /// Get the cursor style requested by the application (DECSCUSR).
+    /// Reports `CursorShape::Hidden` while the cursor is hidden (DECTCEM).
+    pub fn cursor_style(&self) -> CursorStyle {
+        use alacritty_terminal::term::TermMode;
+        let term = self.term.lock();
+        if term.mode().contains(TermMode::SHOW_CURSOR) {
+            term.cursor_style()
+        } else {
+            CursorStyle {
+                shape: CursorShape::Hidden,
+                blinking: false,
+            }
+        }
+    }
+
+    /// Scroll the display by a number of lines (positive = up, negative = down).
+    /// With disk scrollback, scrolling up past the history pages rows in from disk.
     pub fn scroll(&self, delta: i32) {
         let mut term = self.term.lock();
-        term.scroll_display(Scroll::Delta(delta));
+        self.scroll_locked(&mut term, delta);
     }
 
     /// Scroll up by one page
     pub fn scroll_page_up(&self) {
         let mut term = self.term.lock();
-        term.scroll_display(Scroll::PageUp);
+        let lines = term.screen_lines() as i32;
+        self.scroll_locked(&mut term, lines);
     }
 
     /// Scroll down by one page
     pub fn scroll_page_down(&self) {
         let mut term = self.term.lock();
-        term.scroll_display(Scroll::PageDown);
+        let lines = term.screen_lines() as i32;
+        self.scroll_locked(&mut term, -lines);
     }
 
     /// Scroll to the bottom (most recent output)
     pub fn scroll_to_bottom(&self) {
         let mut term = self.term.lock();
+        if let Some(disk) = &self.disk {
+            disk.lock().unwrap().view_offset = 0;
+        }
         term.scroll_display(Scroll::Bottom);
     }
 
+    fn scroll_locked(&self, term: &mut Term<EventProxy>, delta: i32) {
+        let disk = self.disk.as_ref().filter(|_| {
+            !term
+                .mode()
+                .contains(alacritty_terminal::term::TermMode::ALT_SCREEN)
+        });
+        let Some(disk) = disk else {
+            term.scroll_display(Scroll::Delta(delta));
+            return;
+        };
+
+        // Scrolling down leaves the disk rows before moving the history
+        let mut disk = disk.lock().unwrap();
+        let view_offset = disk_view_offset(term, &mut disk);
+        let leave = (-delta).clamp(0, view_offset as i32);
+        disk.view_offset -= leave as usize;
+        let delta = delta + leave;
+
+        let before = term.grid().display_offset() as i32;
+        term.scroll_display(Scroll::Delta(delta));
+        let past_top = delta - (term.grid().display_offset() as i32 - before);
+        if past_top > 0 {
+            disk.view_offset = (disk.view_offset + past_top as usize).min(disk.rows.len());
+        }
+    }
+
+    /// Lines scrolled past the top of the in-memory history into the rows
+    /// paged out to disk (0 without disk scrollback)
+    pub fn disk_view_offset(&self) -> usize {
+        let Some(disk) = &self.disk else {
+            return 0;
+        };
+        let term = self.term.lock();
+        disk_view_offset(&term, &mut disk.lock().unwrap())
+    }
+
+    /// Rows read back from disk for the top of the screen while scrolled
+    /// past the in-memory history, top first; the grid's rows follow them
+    pub fn paged_in_rows(&self) -> Vec<Row<Cell>> {
+        let Some(disk) = &self.disk else {
+            return Vec::new();
+        };
+        let term = self.term.lock();
+        let mut disk = disk.lock().unwrap();
+        let view_offset = disk_view_offset(&term, &mut disk);
+        let first = disk.rows.len() - view_offset;
+        let count = view_offset.min(term.screen_lines());
+        match disk.rows.lines(first..first + count) {
+            Ok(lines) => lines.iter().map(SerializedLine::to_row).collect(),
+            Err(e) => {
+                tracing::warn!("Can't read disk scrollback: {}", e);
+                Vec::new()
+            }
+        }
+    }
+
+    /// Move history past the scrollback limit to disk. Call this regularly
+    /// while output arrives: history beyond the limit plus some headroom is
+    /// dropped if paging out falls behind.
+    pub fn page_out_scrollback(&self) {
+        let Some(disk) = &self.disk else {
+            return;
+        };
+        let mut term = self.term.lock();
+        let mut disk = disk.lock().unwrap();
+        // The alternate screen has no history of its own
+        if disk.failed
+            || term
+                .mode()
+                .contains(alacritty_terminal::term::TermMode::ALT_SCREEN)
+        {
+            return;
+        }
+
+        // Only clearing the scrollback empties a full history at once
+        let history = term.grid().history_size();
+        if history == 0 && disk.last_history >= self.scrollback_lines && !disk.rows.is_empty() {
+            disk.view_offset = 0;
+            if let Err(e) = disk.rows.clear() {
+                tracing::warn!("Can't clear disk scrollback: {}", e);
+            }
+        }
+
+        let limit = history_lines(self.scrollback_lines, true);
+        match page_out(&mut term, &disk.rows, self.scrollback_lines, limit) {
+            Ok(moved) => {
+                // Stay on the same rows while looking at the disk scrollback
+                if disk.view_offset > 0 {
+                    disk.view_offset += moved;
+                }
+            }
+            Err(e) => {
+                tracing::warn!("Can't write disk scrollback, no longer paging out: {}", e);
+                disk.failed = true;
+            }
+        }
+        disk.last_history = term.grid().history_size();
+        self.tags.lock().unwrap().prune(&term);
+    }
+
     /// Get current scroll position (0 = at bottom, positive = scrolled up)
     pub fn display_offset(&self) -> usize {
         let term = self.term.lock();
@@ -291,11 +850,241 @@ impl Terminal {
         term.grid().history_size()
     }
 
-    /// Check if Kitty keyboard protocol is enabled
-    pub fn kitty_keyboard_enabled(&self) -> bool {
+    /// Lines above the screen, including those paged out to disk
+    pub fn scrollback_len(&self) -> usize {
+        let disk_rows = self
+            .disk
+            .as_ref()
+            .map_or(0, |disk| disk.lock().unwrap().rows.len());
+        self.history_size() + disk_rows
+    }
+
+    /// Follow the text on buffer line `line` as output scrolls it, for
+    /// `take_anchor_scroll`. Replaces any earlier anchor.
+    pub fn set_scroll_anchor(&self, line: i32) {
+        let mut term = self.term.lock();
+        crate::scroll_anchor::set_anchor(&mut term, line);
+        *self.scroll_anchor.lock().unwrap() = Some(line);
+    }
+
+    /// Lines the anchored text moved up into the history since the anchor
+    /// was set or last checked. None without an anchor, or once the text
+    /// scrolled out of the history or was erased; the anchor is gone then.
+    pub fn take_anchor_scroll(&self) -> Option<i32> {
+        let mut anchor = self.scroll_anchor.lock().unwrap();
+        let seen = (*anchor)?;
+        let term = self.term.lock();
+        let Some(line) = crate::scroll_anchor::anchor_line(&term) else {
+            *anchor = None;
+            return None;
+        };
+        *anchor = Some(line);
+        Some(seen - line)
+    }
+
+    pub fn clear_scroll_anchor(&self) {
+        let mut term = self.term.lock();
+        crate::scroll_anchor::clear_anchor(&mut term);
+        *self.scroll_anchor.lock().unwrap() = None;
+    }
+
+    fn prompt_spans(&self, term: &Term<EventProxy>) -> Vec<PromptSpan> {
+        self.tags
+            .lock()
+            .unwrap()
+            .screen(term)
+            .prompt_spans(term.grid())
+    }
+
+    /// Buffer lines where shell prompts start (OSC 133), oldest first
+    pub fn prompt_lines(&self) -> Vec<i32> {
+        let term = self.term.lock();
+        self.prompt_spans(&term)
+            .iter()
+            .map(|span| span.start.0)
+            .collect()
+    }
+
+    /// Scroll so the nearest prompt above the top of the viewport is at the top.
+    /// Returns false if there is no earlier prompt.
+    pub fn scroll_to_previous_prompt(&self) -> bool {
+        let mut term = self.term.lock();
+        let top = -(term.grid().display_offset() as i32);
+        let target = self
+            .prompt_spans(&term)
+            .iter()
+            .rev()
+            .map(|span| span.start.0)
+            .find(|&line| line < top);
+
+        match target {
+            Some(line) => {
+                term.scroll_display(Scroll::Delta(top - line));
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Scroll so the next prompt below the top of the viewport is at the top,
+    /// or to the bottom once that prompt is on the live screen.
+    /// Returns false if there is no later prompt.
+    pub fn scroll_to_next_prompt(&self) -> bool {
+        let mut term = self.term.lock();
+        let top = -(term.grid().display_offset() as i32);
+        let target = self
+            .prompt_spans(&term)
+            .iter()
+            .map(|span| span.start.0)
+            .find(|&line| line > top);
+
+        match target {
+            Some(line) => {
+                term.scroll_display(Scroll::Delta(top - line.min(0)));
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Buffer lines (inclusive) holding the output of the most recent command,
+    /// based on OSC 133 prompt marks. None if fewer than two prompts are known
+    /// or the command printed nothing.
+    pub fn last_command_output_range(&self) -> Option<(i32, i32)> {
+        let term = self.term.lock();
+        let prompts = self.prompt_spans(&term);
+        crate::shell_integration::last_command_output_range(term.grid(), &prompts)
+            .map(|(start, end)| (start.0, end.0))
+    }
+
+    /// Enter or leave copy mode: a keyboard-driven cursor (alacritty's vi
+    /// mode) that moves through the screen and scrollback independently of
+    /// the shell's cursor
+    pub fn set_copy_mode(&self, enabled: bool) {
         use alacritty_terminal::term::TermMode;
+        let mut term = self.term.lock();
+        if term.mode().contains(TermMode::VI) != enabled {
+            term.toggle_vi_mode();
+        }
+        *self.copy_mode_history.lock().unwrap() = enabled.then(|| term.grid().history_size());
+    }
+
+    /// Copy mode cursor as (column, buffer line), or None outside copy mode
+    pub fn copy_mode_cursor(&self) -> Option<(usize, i32)> {
+        use alacritty_terminal::term::TermMode;
+        let term = self.term.lock();
+        let point = term.vi_mode_cursor.point;
+        term.mode()
+            .contains(TermMode::VI)
+            .then_some((point.column.0, point.line.0))
+    }
+
+    /// Move the copy mode cursor, scrolling to keep it in view
+    pub fn copy_mode_motion(&self, motion: ViMotion) {
+        let mut term = self.term.lock();
+        term.vi_motion(motion);
+        let point = term.vi_mode_cursor.point;
+        term.scroll_to_point(point);
+    }
+
+    /// Put the copy mode cursor on a cell, given as (column, buffer line),
+    /// scrolling to keep it in view
+    pub fn set_copy_mode_cursor(&self, col: usize, line: i32) {
+        let mut term = self.term.lock();
+        let grid = term.grid();
+        let line = line.clamp(grid.topmost_line().0, grid.bottommost_line().0);
+        let point = Point::new(Line(line), Column(col.min(grid.columns() - 1)));
+        term.vi_mode_cursor.point = point;
+        term.scroll_to_point(point);
+    }
+
+    /// Scroll the view by `lines` (positive = up) and move the copy mode
+    /// cursor with it, like Ctrl+U/Ctrl+D in vi
+    pub fn copy_mode_scroll(&self, lines: i32) {
+        let mut term = self.term.lock();
+        let cursor = term.vi_mode_cursor.scroll(&*term, lines);
+        term.scroll_display(Scroll::Delta(lines));
+        term.vi_mode_cursor = cursor;
+        let point = cursor.point;
+        term.scroll_to_point(point);
+    }
+
+    /// Keep the copy mode view still while output arrives. Alacritty only
+    /// holds a scrolled-up view in place, so at the bottom we scroll up by
+    /// however many lines were pushed into history (until it is full).
+    /// Returns that number of lines: how far buffer rows moved up since the
+    /// last call.
+    pub fn hold_copy_mode_view(&self) -> usize {
+        let mut history = self.copy_mode_history.lock().unwrap();
+        let Some(seen) = *history else {
+            return 0;
+        };
+        let mut term = self.term.lock();
+        let now = term.grid().history_size();
+        let pushed = now.saturating_sub(seen);
+        if pushed > 0 && term.grid().display_offset() == 0 {
+            // The copy cursor already moved up with its text
+            term.scroll_display(Scroll::Delta(pushed as i32));
+        }
+        *history = Some(now);
+        pushed
+    }
+
+    /// Report a focus change to the application if it enabled focus
+    /// reporting (DECSET 1004)
+    pub fn report_focus(&self, focused: bool) {
+        let enabled = self
+            .term
+            .lock()
+            .mode()
+            .contains(alacritty_terminal::term::TermMode::FOCUS_IN_OUT);
+        if enabled {
+            self.input(if focused { b"\x1b[I" } else { b"\x1b[O" });
+        }
+    }
+
+    /// Matches of `regex` in the part of the buffer currently on screen
+    pub fn visible_matches(&self, regex: &mut RegexSearch) -> Vec<TextMatch> {
+        let term = self.term.lock();
+        let top = Line(-(term.grid().display_offset() as i32));
+        let start = Point::new(top, Column(0));
+        let end = Point::new(top + (term.screen_lines() - 1), term.last_column());
+        RegexIter::new(start, end, Direction::Right, &term, regex)
+            .map(|m| TextMatch {
+                start: (m.start().column.0, m.start().line.0),
+                end: (m.end().column.0, m.end().line.0),
+                text: term.bounds_to_string(*m.start(), *m.end()),
+            })
+            .collect()
+    }
+
+    /// Matches of `regex` anywhere in the in-memory buffer, oldest first
+    pub fn search(&self, regex: &mut RegexSearch) -> Vec<TextMatch> {
         let term = self.term.lock();
-        term.mode().contains(TermMode::DISAMBIGUATE_ESC_CODES)
+        let grid = term.grid();
+        let start = Point::new(grid.topmost_line(), Column(0));
+        let end = Point::new(grid.bottommost_line(), term.last_column());
+        RegexIter::new(start, end, Direction::Right, &term, regex)
+            .map(|m| TextMatch {
+                start: (m.start().column.0, m.start().line.0),
+                end: (m.end().column.0, m.end().line.0),
+                text: term.bounds_to_string(*m.start(), *m.end()),
+            })
+            .collect()
+    }
+
+    /// Scroll just far enough to bring buffer line `line` on screen
+    pub fn scroll_to_line(&self, line: i32) {
+        let mut term = self.term.lock();
+        term.scroll_to_point(Point::new(Line(line), Column(0)));
+    }
+
+    /// Active Kitty keyboard protocol flags, as reported to `CSI ? u`.
+    ///
+    /// alacritty keeps the stack pushed with `CSI > flags u` and popped with
+    /// `CSI < u` (one per screen) and mirrors its top entry into the mode.
+    pub fn kitty_keyboard_flags(&self) -> u8 {
+        kitty_flags(*self.term.lock().mode())
     }
 
     /// Get the full terminal mode flags for keyboard handling
@@ -304,3 +1093,304 @@ impl Terminal {
         *term.mode()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_shell_command_resolution() {
+        assert_eq!(ShellCommand::default().to_tty_shell(), None);
+
+        let shell = ShellCommand {
+            program: Some("/bin/zsh".to_string()),
+            args: vec!["-i".to_string()],
+            login: true,
+        };
+        assert_eq!(
+            shell.to_tty_shell(),
+            Some(tty::Shell::new(
+                "/bin/zsh".to_string(),
+                vec!["-l".to_string(), "-i".to_string()]
+            ))
+        );
+    }
+
+    #[test]
+    fn test_copy_mode_cursor() {
+        let terminal = Terminal::new(80, 24).unwrap();
+        assert_eq!(terminal.copy_mode_cursor(), None);
+
+        terminal.set_copy_mode(true);
+        let (col, line) = terminal.copy_mode_cursor().unwrap();
+        terminal.copy_mode_motion(ViMotion::Right);
+        assert_eq!(terminal.copy_mode_cursor(), Some((col + 1, line)));
+        terminal.copy_mode_motion(ViMotion::First);
+        assert_eq!(terminal.copy_mode_cursor(), Some((0, line)));
+        terminal.set_copy_mode_cursor(500, line);
+        assert_eq!(terminal.copy_mode_cursor(), Some((79, line)));
+
+        // Scrolling with no history keeps the cursor on screen
+        terminal.copy_mode_scroll(12);
+        let (_, line) = terminal.copy_mode_cursor().unwrap();
+        assert!((0..24).contains(&line));
+
+        terminal.set_copy_mode(false);
+        assert_eq!(terminal.copy_mode_cursor(), None);
+    }
+
+    /// A terminal running `script` with `/bin/sh -c`
+    #[cfg(unix)]
+    fn sh(script: &str, history: usize, disk: Option<&Path>) -> Terminal {
+        let shell = ShellCommand {
+            program: Some("/bin/sh".to_string()),
+            args: vec!["-c".to_string(), script.to_string()],
+            login: false,
+        };
+        Terminal::with_shell(80, 24, None, &shell, history, disk).unwrap()
+    }
+
+    /// Poll `done` until it returns true or ten seconds pass
+    #[cfg(unix)]
+    fn wait_until(mut done: impl FnMut() -> bool) -> bool {
+        let deadline = std::time::Instant::now() + std::time::Duration::from_secs(10);
+        while std::time::Instant::now() < deadline {
+            if done() {
+                return true;
+            }
+            std::thread::sleep(std::time::Duration::from_millis(10));
+        }
+        done()
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_exit_code_reported() {
+        let terminal = sh("exit 3", SCROLLBACK_LINES, None);
+        assert!(wait_until(|| terminal.has_exited()));
+        assert_eq!(terminal.exit_code(), Some(3));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_bell_sets_pending_flag() {
+        let terminal = sh("printf '\\007'; sleep 5", SCROLLBACK_LINES, None);
+        assert!(wait_until(|| terminal.take_bell()));
+        // Taking the bell clears it
+        assert!(!terminal.take_bell());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_scrollback_limit_caps_history() {
+        let terminal = sh("seq 1 200; sleep 5", 50, None);
+        let mut last = RegexSearch::new("200").unwrap();
+        assert!(wait_until(|| !terminal
+            .visible_matches(&mut last)
+            .is_empty()));
+        assert_eq!(terminal.history_size(), 50);
+        // Changing another option keeps the limit
+        terminal.set_osc52(Osc52::CopyPaste);
+        assert_eq!(terminal.history_size(), 50);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_same_size_resize_keeps_scroll_position() {
+        use alacritty_terminal::vte::ansi::Processor;
+
+        // A silent program, so nothing but the lines below reaches the grid
+        let terminal = sh("sleep 5", 1000, None);
+        let lines: String = (1..=100).map(|i| format!("{i}\r\n")).collect();
+        let mut parser: Processor = Processor::new();
+        parser.advance(&mut *terminal.term.lock(), lines.as_bytes());
+        terminal.resize(80, 24, 8, 16);
+        terminal.scroll(10);
+        assert_eq!(terminal.display_offset(), 10);
+
+        // Closing the settings overlay fits the panes again at the same size
+        terminal.resize(80, 24, 8, 16);
+        assert_eq!(terminal.display_offset(), 10);
+        assert_eq!(terminal.size(), (80, 24));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_disk_scrollback_pages_in_old_history() {
+        let dir = std::env::temp_dir();
+        let terminal = sh("seq 1 200; sleep 5", 50, Some(&dir));
+        let mut paged_out = 0;
+        // 201 lines with the one after the last newline, 24 of them on screen
+        wait_until(|| {
+            terminal.page_out_scrollback();
+            paged_out = terminal.disk.as_ref().unwrap().lock().unwrap().rows.len();
+            paged_out >= 201 - 24 - 50
+        });
+        assert_eq!(terminal.history_size(), 50);
+        assert_eq!(paged_out, 127);
+
+        // Scrolling past the top of the history continues into the disk rows
+        terminal.scroll(60);
+        assert_eq!(terminal.disk_view_offset(), 10);
+        let rows = terminal.paged_in_rows();
+        assert_eq!(rows.len(), 10);
+        let first: String = rows[0][..].iter().map(|cell| cell.c).collect();
+        assert_eq!(first.trim_end(), "118");
+        terminal.scroll(-5);
+        assert_eq!(terminal.disk_view_offset(), 5);
+        terminal.scroll_to_bottom();
+        assert_eq!(terminal.disk_view_offset(), 0);
+        assert_eq!(terminal.display_offset(), 0);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_output_capture_sees_raw_bytes() {
+        let terminal = sh(
+            "sleep 0.5; printf '\\033]133;A\\007recorded'; sleep 5",
+            SCROLLBACK_LINES,
+            None,
+        );
+        terminal.set_output_capture(true);
+        let mut output = Vec::new();
+        wait_until(|| {
+            output.extend(terminal.take_output());
+            output.ends_with(b"recorded")
+        });
+        // The prompt marker arrives as sent, not rewritten for the grid
+        assert!(output.ends_with(b"\x1b]133;A\x07recorded"));
+        terminal.set_output_capture(false);
+        assert!(terminal.take_output().is_empty());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_osc_title_is_stored() {
+        let terminal = sh(
+            "printf '\\033]2;vim notes.txt\\007'; sleep 5",
+            SCROLLBACK_LINES,
+            None,
+        );
+        assert_eq!(terminal.title(), None);
+        wait_until(|| terminal.title().is_some());
+        assert_eq!(terminal.title().as_deref(), Some("vim notes.txt"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_unfinished_sync_update_shows_after_timeout() {
+        // Nothing else is written to flush the update before the wait ends
+        let terminal = sh(
+            "printf '\\033[?2026hsynced'; sleep 15",
+            SCROLLBACK_LINES,
+            None,
+        );
+        let mut regex = RegexSearch::new("synced").unwrap();
+        assert!(wait_until(|| !terminal
+            .visible_matches(&mut regex)
+            .is_empty()));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_focus_report_once_enabled() {
+        let terminal = sh(
+            "printf '\\033[?1004h'; IFS= read -r line; printf '%s' \"$line\" | od -An -c; sleep 5",
+            SCROLLBACK_LINES,
+            None,
+        );
+        wait_until(|| {
+            terminal
+                .term_mode()
+                .contains(alacritty_terminal::term::TermMode::FOCUS_IN_OUT)
+        });
+        terminal.report_focus(true);
+        terminal.input(b"\r");
+        // od shows the bytes the shell read: ESC [ I
+        let mut regex = RegexSearch::new(r"033 +\[ +I").unwrap();
+        let mut matches = Vec::new();
+        wait_until(|| {
+            matches = terminal.visible_matches(&mut regex);
+            !matches.is_empty()
+        });
+        assert_eq!(matches.len(), 1);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_osc52_store_is_queued() {
+        let terminal = sh(
+            "printf '\\033]52;c;aGVsbG8=\\007'; sleep 5",
+            SCROLLBACK_LINES,
+            None,
+        );
+        let mut requests = Vec::new();
+        wait_until(|| {
+            requests = terminal.take_clipboard_requests();
+            !requests.is_empty()
+        });
+        match requests.as_slice() {
+            [ClipboardRequest::Store(ClipboardType::Clipboard, text)] => {
+                assert_eq!(text, "hello")
+            }
+            _ => panic!("expected one clipboard store"),
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_kitty_flags_follow_push_and_pop() {
+        // Push disambiguate + event types, push disambiguate, pop one
+        let terminal = sh(
+            "printf '\\033[>3u\\033[>1u\\033[<u'; sleep 5",
+            SCROLLBACK_LINES,
+            None,
+        );
+        assert_eq!(terminal.kitty_keyboard_flags(), 0);
+        wait_until(|| terminal.kitty_keyboard_flags() == 3);
+        assert_eq!(terminal.kitty_keyboard_flags(), 3);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_visible_matches() {
+        let terminal = sh(
+            "printf 'see https://example.com/a and src/main.rs:12:5'; sleep 5",
+            SCROLLBACK_LINES,
+            None,
+        );
+        let mut regex = RegexSearch::new(r"https?://\S+|\S+\.rs:[0-9]+").unwrap();
+        let mut matches = Vec::new();
+        wait_until(|| {
+            matches = terminal.visible_matches(&mut regex);
+            matches.len() >= 2
+        });
+        assert_eq!(matches.len(), 2);
+        assert_eq!(matches[0].text, "https://example.com/a");
+        assert_eq!(matches[0].start, (4, 0));
+        assert_eq!(matches[0].end, (24, 0));
+        assert_eq!(matches[1].text, "src/main.rs:12");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_search_covers_history() {
+        let terminal = sh(
+            "echo needle; seq 1 50; echo needle; sleep 5",
+            SCROLLBACK_LINES,
+            None,
+        );
+        let mut regex = RegexSearch::new("needle").unwrap();
+        let mut matches = Vec::new();
+        wait_until(|| {
+            matches = terminal.search(&mut regex);
+            matches.len() >= 2
+        });
+        assert_eq!(matches.len(), 2);
+        // The first one scrolled off the screen into the history
+        let first = matches[0].start.1;
+        assert!(first < 0);
+        terminal.scroll_to_line(first);
+        assert_eq!(terminal.display_offset(), -first as usize);
+    }
+}
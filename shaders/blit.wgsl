@@ -0,0 +1,52 @@
+// ABOUTME: Plain blit shader for rendering without CRT effects.
+// ABOUTME: Copies the text pass output to the screen, adding only the visual bell flash.
+
+struct VertexOutput {
+    @builtin(position) position: vec4<f32>,
+    @location(0) uv: vec2<f32>,
+}
+
+struct BlitUniforms {
+    flash_color: vec4<f32>, // Visual bell flash; alpha is its strength
+    background: vec4<f32>,  // Window background color; alpha is its opacity
+}
+
+@group(0) @binding(0)
+var<uniform> uniforms: BlitUniforms;
+
+@group(0) @binding(1)
+var source_texture: texture_2d<f32>;
+
+@group(0) @binding(2)
+var tex_sampler: sampler;
+
+// Fullscreen triangle vertices (more efficient than quad)
+@vertex
+fn vs_main(@builtin(vertex_index) vertex_index: u32) -> VertexOutput {
+    var out: VertexOutput;
+
+    let x = f32(i32(vertex_index & 1u) * 4 - 1);
+    let y = f32(i32(vertex_index >> 1u) * 4 - 1);
+
+    out.position = vec4<f32>(x, y, 0.0, 1.0);
+    out.uv = vec2<f32>((x + 1.0) * 0.5, (1.0 - y) * 0.5);
+
+    return out;
+}
+
+// Final pixel, premultiplied: the bare background takes its opacity and
+// anything brighter than it stays opaque (same as the CRT shader)
+fn composite(color: vec3<f32>) -> vec4<f32> {
+    let bg = uniforms.background;
+    let lit = max(color - bg.rgb, vec3<f32>(0.0));
+    let coverage = clamp(max(lit.r, max(lit.g, lit.b)) * 4.0, 0.0, 1.0);
+    let alpha = mix(bg.a, 1.0, coverage);
+    return vec4<f32>(max(color - bg.rgb * (1.0 - alpha), vec3<f32>(0.0)), alpha);
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    let color = textureSample(source_texture, tex_sampler, in.uv).rgb;
+    let flashed = mix(color, uniforms.flash_color.rgb, uniforms.flash_color.a);
+    return composite(flashed);
+}
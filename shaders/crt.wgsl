@@ -45,9 +45,17 @@ struct CrtUniforms {
     content_scale_y: f32,
     // Cell height in pixels for scanline alignment (one scanline per text row)
     cell_height: f32,
-    _pad1: f32,
-    // Focus glow color (follows font color) - vec4 for alignment (w ignored)
+    scanline_gamma: f32,      // 0 = uniform scanlines, higher = bright pixels darken less
+    // Focus glow color (font color or custom phosphor tint)
+    // w = how much bloom is tinted with it (1 with a custom tint)
     glow_color: vec4<f32>,
+    mask_strength: f32,       // RGB phosphor mask (0 = off, 1 = full aperture grille)
+    aberration: f32,          // Red/blue separation at the screen edges, in pixels
+    corner_radius: f32,       // Rounded screen corners, fraction of the shorter side
+    composite_bleed: f32,     // Composite video chroma smear to the right (0 = off)
+    flash_color: vec4<f32>,   // Visual bell flash; w = current strength (0 = none)
+    background: vec4<f32>,    // Window background color; w = its opacity (1 = opaque)
+    white_point: vec4<f32>,   // RGB multiplier for the color temperature; w unused
     // Pane rects (max 16 panes)
     panes: array<PaneRect, 16>,
 }
@@ -130,6 +138,29 @@ fn texture_bicubic(uv: vec2<f32>) -> vec3<f32> {
     return color;
 }
 
+// Sample the content with chromatic aberration: red and blue are pulled
+// apart along the direction from the center of the glass, growing toward
+// the edges like a misconverged tube
+fn sample_content(uv: vec2<f32>, glass_uv: vec2<f32>) -> vec3<f32> {
+    let color = texture_bicubic(uv);
+    if (uniforms.aberration <= 0.0) {
+        return color;
+    }
+    let offset = (glass_uv * 2.0 - 1.0) * uniforms.aberration / uniforms.screen_size;
+    let red = texture_bicubic(uv + offset).r;
+    let blue = texture_bicubic(uv - offset).b;
+    return vec3<f32>(red, color.g, blue);
+}
+
+// Aperture grille: repeating red/green/blue phosphor stripes, one pixel
+// each, laid out on the glass so they follow the curvature
+fn rgb_mask(glass_uv: vec2<f32>, region_size: vec2<f32>) -> vec3<f32> {
+    let column = u32(floor(glass_uv.x * region_size.x)) % 3u;
+    var stripe = vec3<f32>(0.25);
+    stripe[column] = 1.0;
+    return mix(vec3<f32>(1.0), stripe, uniforms.mask_strength);
+}
+
 // Check if UV is outside [0,1] range (for vignette/border)
 fn is_outside(uv: vec2<f32>) -> bool {
     return uv.x < 0.0 || uv.x > 1.0 || uv.y < 0.0 || uv.y > 1.0;
@@ -159,7 +190,9 @@ fn temporal_noise(screen_pos: vec2<f32>, time: f32) -> f32 {
 // Scanline effect with two modes:
 // - Row-based (mode 0): One scanline cycle per text row, works with any font
 // - Pixel-level (mode 1): Real CRT scanlines, best with BDF bitmap fonts
-fn scanline(uv: vec2<f32>, intensity: f32, region_height: f32, time: f32) -> f32 {
+// Bright phosphor bleeds over the gaps, so with scanline_gamma the darkening
+// fades with the pixel's luminance: text keeps its punch, background goes darker
+fn scanline(uv: vec2<f32>, intensity: f32, region_height: f32, time: f32, color: vec3<f32>) -> f32 {
     // Slow drift - moves in whole scanline increments to avoid moiré
     let drift = time * 0.3; // Slow roll speed
 
@@ -181,7 +214,10 @@ fn scanline(uv: vec2<f32>, intensity: f32, region_height: f32, time: f32) -> f32
     // Triangle wave: brightest in middle of scanline, darkest at edges
     let line_mask = 1.0 - abs(frac_y * 2.0 - 1.0);
 
-    return 1.0 - intensity * (1.0 - line_mask);
+    let luma = clamp(dot(color, vec3<f32>(0.299, 0.587, 0.114)), 0.0, 1.0);
+    let shield = pow(max(1.0 - luma, 1e-4), uniforms.scanline_gamma);
+
+    return 1.0 - intensity * shield * (1.0 - line_mask);
 }
 
 // Flicker effect - realistic power supply fluctuation (scaled by flicker uniform)
@@ -312,7 +348,43 @@ fn bloom(uv: vec2<f32>, texel_size: vec2<f32>) -> vec3<f32> {
         color = color + textureSample(input_texture, input_sampler, sample_uv).rgb * weights[i];
     }
 
-    return color;
+    // A custom phosphor tint recolors the halo, keeping its brightness
+    let luma = dot(color, vec3<f32>(0.299, 0.587, 0.114));
+    return mix(color, uniforms.glow_color.rgb * luma, uniforms.glow_color.w);
+}
+
+// BT.601 conversion between RGB and luma + blue/red color difference
+fn rgb_to_ycbcr(color: vec3<f32>) -> vec3<f32> {
+    let y = dot(color, vec3<f32>(0.299, 0.587, 0.114));
+    return vec3<f32>(y, (color.b - y) * 0.564, (color.r - y) * 0.713);
+}
+
+fn ycbcr_to_rgb(ycc: vec3<f32>) -> vec3<f32> {
+    let y = ycc.x;
+    return vec3<f32>(
+        y + 1.403 * ycc.z,
+        y - 0.344 * ycc.y - 0.714 * ycc.z,
+        y + 1.773 * ycc.y,
+    );
+}
+
+// Composite video carries color on a narrow band next to the brightness, so
+// color smears to the right of where it starts while edges stay sharp.
+// Low-passes the chroma over the pixels to the left and keeps this pixel's luma.
+fn composite_bleed(color: vec3<f32>, uv: vec2<f32>, texel_size: vec2<f32>) -> vec3<f32> {
+    if (uniforms.composite_bleed <= 0.0) {
+        return color;
+    }
+    let weights = array<f32, 5>(0.35, 0.25, 0.18, 0.13, 0.09);
+    let ycc = rgb_to_ycbcr(color);
+    var chroma = ycc.yz * weights[0];
+    for (var i = 1; i < 5; i = i + 1) {
+        let tap_uv = uv - vec2<f32>(f32(i) * texel_size.x, 0.0);
+        let tap = textureSample(input_texture, input_sampler, tap_uv).rgb;
+        chroma = chroma + rgb_to_ycbcr(tap).yz * weights[i];
+    }
+    let bled = mix(ycc.yz, chroma, uniforms.composite_bleed);
+    return ycbcr_to_rgb(vec3<f32>(ycc.x, bled));
 }
 
 // Find which pane contains this UV, returns pane index or -1 if none
@@ -349,21 +421,24 @@ fn apply_whole_screen_crt(uv: vec2<f32>) -> vec4<f32> {
     let distorted_uv = barrel_distort(uv, uniforms.curvature);
 
     // Anti-aliased edge mask instead of hard cutoff
-    let edge_alpha = edge_mask_aa(distorted_uv);
+    let edge_alpha = edge_mask_aa(distorted_uv, uniforms.screen_size);
     if (edge_alpha <= 0.0) {
         return vec4<f32>(0.0, 0.0, 0.0, 1.0);
     }
 
-    var color = texture_bicubic(distorted_uv);
+    var color = sample_content(distorted_uv, distorted_uv);
 
     if (uniforms.bloom_intensity > 0.0) {
         let texel_size = 1.0 / uniforms.screen_size;
         let bloomed = bloom(distorted_uv, texel_size);
         color = mix(color, bloomed + color * 0.5, uniforms.bloom_intensity * 0.5);
     }
+    color = composite_bleed(color, distorted_uv, 1.0 / uniforms.screen_size);
+    color *= uniforms.white_point.rgb;
 
-    let scan = scanline(distorted_uv, uniforms.scanline_intensity, uniforms.screen_size.y, uniforms.time);
+    let scan = scanline(distorted_uv, uniforms.scanline_intensity, uniforms.screen_size.y, uniforms.time, color);
     color = color * scan;
+    color = color * rgb_mask(distorted_uv, uniforms.screen_size);
     color = color * flicker(uniforms.time, uniforms.flicker);
 
     // Static noise - use temporal noise to avoid moiré with scanlines
@@ -417,13 +492,13 @@ fn edge_glow(local_uv: vec2<f32>, is_focused: bool) -> vec3<f32> {
     return uniforms.glow_color.rgb * glow_intensity * uniforms.focus_glow_intensity;
 }
 
-// Anti-aliased edge mask for CRT border (smooth transition to black)
-fn edge_mask_aa(uv: vec2<f32>) -> f32 {
-    // Distance from edge (negative inside 0-1 range, positive outside)
-    let edge_dist = max(
-        max(-uv.x, uv.x - 1.0),
-        max(-uv.y, uv.y - 1.0)
-    );
+// Anti-aliased edge mask for CRT border (smooth transition to black).
+// region_size is the screen or pane in pixels; its corners are rounded by
+// corner_radius, so what curves past them falls into the black void.
+fn edge_mask_aa(uv: vec2<f32>, region_size: vec2<f32>) -> f32 {
+    // Distance from the rounded edge in pixels (negative inside)
+    let radius = clamp(uniforms.corner_radius, 0.0, 0.5) * min(region_size.x, region_size.y);
+    let edge_dist = sd_rounded_box((uv - 0.5) * region_size, region_size * 0.5, radius);
 
     // Use fwidth for screen-space anti-aliasing
     let aa = fwidth(edge_dist) * 1.5;
@@ -445,7 +520,7 @@ fn apply_per_pane_crt(uv: vec2<f32>, pane_idx: i32) -> vec4<f32> {
     let distorted_local = barrel_distort(local_uv, uniforms.curvature);
 
     // Anti-aliased edge mask instead of hard cutoff
-    let edge_alpha = edge_mask_aa(distorted_local);
+    let edge_alpha = edge_mask_aa(distorted_local, pane_size);
     if (edge_alpha <= 0.0) {
         return vec4<f32>(0.0, 0.0, 0.0, 1.0);
     }
@@ -453,7 +528,7 @@ fn apply_per_pane_crt(uv: vec2<f32>, pane_idx: i32) -> vec4<f32> {
     // Convert back to global UV for sampling
     let sample_uv = local_to_global_uv(distorted_local, pane_idx);
 
-    var color = texture_bicubic(sample_uv);
+    var color = sample_content(sample_uv, distorted_local);
 
     // Add edge glow for focused pane BEFORE CRT effects so it gets processed too
     color = color + edge_glow(distorted_local, is_focused);
@@ -464,10 +539,13 @@ fn apply_per_pane_crt(uv: vec2<f32>, pane_idx: i32) -> vec4<f32> {
         let bloomed = bloom(sample_uv, texel_size);
         color = mix(color, bloomed + color * 0.5, uniforms.bloom_intensity * 0.5);
     }
+    color = composite_bleed(color, sample_uv, 1.0 / uniforms.screen_size);
+    color *= uniforms.white_point.rgb;
 
     // Scanlines relative to pane height
-    let scan = scanline(distorted_local, uniforms.scanline_intensity, pane_size.y, uniforms.time);
+    let scan = scanline(distorted_local, uniforms.scanline_intensity, pane_size.y, uniforms.time, color);
     color = color * scan;
+    color = color * rgb_mask(distorted_local, pane_size);
 
     // Flicker (same for all panes, but could vary per-pane with pane_idx)
     color = color * flicker(uniforms.time + f32(pane_idx) * 0.1, uniforms.flicker);
@@ -633,7 +711,7 @@ fn apply_bezel_mode_crt(screen_uv: vec2<f32>) -> vec4<f32> {
     let distorted_uv = barrel_distort(screen_uv, uniforms.curvature);
 
     // Anti-aliased edge mask based on fixed screen shape
-    let edge_alpha = edge_mask_aa(distorted_uv);
+    let edge_alpha = edge_mask_aa(distorted_uv, uniforms.screen_size);
     if (edge_alpha <= 0.0) {
         return vec4<f32>(0.0, 0.0, 0.0, 1.0);
     }
@@ -645,7 +723,7 @@ fn apply_bezel_mode_crt(screen_uv: vec2<f32>) -> vec4<f32> {
 
     // Sample the input texture with bicubic filtering for sharper text
     // The screen shape is defined ONLY by the barrel distortion edge above
-    var color = texture_bicubic(sample_uv);
+    var color = sample_content(sample_uv, distorted_uv);
 
     // Bloom
     if (uniforms.bloom_intensity > 0.0) {
@@ -653,10 +731,13 @@ fn apply_bezel_mode_crt(screen_uv: vec2<f32>) -> vec4<f32> {
         let bloomed = bloom(sample_uv, texel_size);
         color = mix(color, bloomed + color * 0.5, uniforms.bloom_intensity * 0.5);
     }
+    color = composite_bleed(color, sample_uv, 1.0 / uniforms.screen_size);
+    color *= uniforms.white_point.rgb;
 
     // Scanlines relative to FIXED screen (not scaled) - like real CRT phosphor lines
-    let scan = scanline(distorted_uv, uniforms.scanline_intensity, uniforms.screen_size.y, uniforms.time);
+    let scan = scanline(distorted_uv, uniforms.scanline_intensity, uniforms.screen_size.y, uniforms.time, color);
     color = color * scan;
+    color = color * rgb_mask(distorted_uv, uniforms.screen_size);
 
     // Flicker
     color = color * flicker(uniforms.time, uniforms.flicker);
@@ -752,7 +833,7 @@ fn apply_pane_bezel_crt(screen_uv: vec2<f32>, pane_idx: i32) -> vec4<f32> {
     let distorted_local = barrel_distort(local_uv, uniforms.curvature);
 
     // Edge mask based on fixed pane shape - THIS is the only screen boundary
-    let edge_alpha = edge_mask_aa(distorted_local);
+    let edge_alpha = edge_mask_aa(distorted_local, pane_size);
     if (edge_alpha <= 0.0) {
         return vec4<f32>(0.0, 0.0, 0.0, 1.0);
     }
@@ -765,7 +846,7 @@ fn apply_pane_bezel_crt(screen_uv: vec2<f32>, pane_idx: i32) -> vec4<f32> {
 
     // Sample the input texture with bicubic filtering for sharper text
     // The screen shape is defined ONLY by the barrel distortion edge above
-    var color = texture_bicubic(sample_uv);
+    var color = sample_content(sample_uv, distorted_local);
 
     // Add edge glow for focused pane (uses FIXED distorted_local coordinates)
     color = color + edge_glow(distorted_local, is_focused);
@@ -776,10 +857,13 @@ fn apply_pane_bezel_crt(screen_uv: vec2<f32>, pane_idx: i32) -> vec4<f32> {
         let bloomed = bloom(sample_uv, texel_size);
         color = mix(color, bloomed + color * 0.5, uniforms.bloom_intensity * 0.5);
     }
+    color = composite_bleed(color, sample_uv, 1.0 / uniforms.screen_size);
+    color *= uniforms.white_point.rgb;
 
     // Scanlines relative to FIXED pane shape (not scaled)
-    let scan = scanline(distorted_local, uniforms.scanline_intensity, pane_size.y, uniforms.time);
+    let scan = scanline(distorted_local, uniforms.scanline_intensity, pane_size.y, uniforms.time, color);
     color = color * scan;
+    color = color * rgb_mask(distorted_local, pane_size);
 
     // Flicker
     color = color * flicker(uniforms.time + f32(pane_idx) * 0.1, uniforms.flicker);
@@ -799,6 +883,17 @@ fn apply_pane_bezel_crt(screen_uv: vec2<f32>, pane_idx: i32) -> vec4<f32> {
     return vec4<f32>(color, 1.0);
 }
 
+// Final pixel, premultiplied. Light above the background (text, glow,
+// bloom) stays opaque while the bare background takes its opacity, so the
+// desktop shows through without dimming the phosphor.
+fn composite(color: vec3<f32>) -> vec4<f32> {
+    let bg = uniforms.background;
+    let lit = max(color - bg.rgb, vec3<f32>(0.0));
+    let coverage = clamp(max(lit.r, max(lit.g, lit.b)) * 4.0, 0.0, 1.0);
+    let alpha = mix(bg.a, 1.0, coverage);
+    return vec4<f32>(max(color - bg.rgb * (1.0 - alpha), vec3<f32>(0.0)), alpha);
+}
+
 @fragment
 fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
     var color: vec3<f32>;
@@ -815,7 +910,7 @@ fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
             let pane_idx = find_pane(in.uv);
             if (pane_idx < 0) {
                 // Outside all panes - black
-                return vec4<f32>(0.0, 0.0, 0.0, 1.0);
+                return composite(vec3<f32>(0.0));
             }
 
             // Get local UV within the pane for power-on effect
@@ -849,7 +944,7 @@ fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
         // Per-pane mode without bezel: each pane is its own mini-CRT
         let pane_idx = find_pane(in.uv);
         if (pane_idx < 0) {
-            return vec4<f32>(0.0, 0.0, 0.0, 1.0);
+            return composite(vec3<f32>(0.0));
         }
         screen_uv = global_to_local_uv(in.uv, pane_idx);
 
@@ -861,8 +956,11 @@ fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
         color = result.rgb;
     }
 
+    // Visual bell: wash the tube with the flash color
+    color = mix(color, uniforms.flash_color.rgb, uniforms.flash_color.a);
+
     // Apply CRT power-on effect (masking and brightness)
     color = power_on_effect(color, screen_uv, uniforms.time, uniforms.curvature);
 
-    return vec4<f32>(color, 1.0);
+    return composite(color);
 }
@@ -0,0 +1,57 @@
+// Image shader for sixel graphics
+// Draws one instanced quad per image strip, textured from that image
+
+struct ImageInstance {
+    // Top left corner and size, in pixels
+    @location(0) rect: vec4<f32>,
+    // Texture coordinates of the top left and bottom right corners
+    @location(1) uv: vec4<f32>,
+}
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) tex_coords: vec2<f32>,
+}
+
+struct Uniforms {
+    screen_size: vec2<f32>,
+}
+
+@group(0) @binding(0)
+var<uniform> uniforms: Uniforms;
+
+@group(1) @binding(0)
+var image_texture: texture_2d<f32>;
+
+@group(1) @binding(1)
+var image_sampler: sampler;
+
+@vertex
+fn vs_main(@builtin(vertex_index) vertex_index: u32, image: ImageInstance) -> VertexOutput {
+    var out: VertexOutput;
+
+    // Quad corners for two triangles: (0,0) (1,0) (1,1) / (0,0) (1,1) (0,1)
+    var corners = array<vec2<f32>, 6>(
+        vec2<f32>(0.0, 0.0),
+        vec2<f32>(1.0, 0.0),
+        vec2<f32>(1.0, 1.0),
+        vec2<f32>(0.0, 0.0),
+        vec2<f32>(1.0, 1.0),
+        vec2<f32>(0.0, 1.0),
+    );
+    let corner = corners[vertex_index];
+    let pixel = image.rect.xy + corner * image.rect.zw;
+
+    // Convert from pixel coordinates to clip space (-1 to 1)
+    let x = (pixel.x / uniforms.screen_size.x) * 2.0 - 1.0;
+    let y = 1.0 - (pixel.y / uniforms.screen_size.y) * 2.0;
+    out.clip_position = vec4<f32>(x, y, 0.0, 1.0);
+    out.tex_coords = mix(image.uv.xy, image.uv.zw, corner);
+
+    return out;
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    return textureSample(image_texture, image_sampler, in.tex_coords);
+}
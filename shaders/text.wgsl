@@ -1,10 +1,17 @@
 // Vertex shader for text/glyph rendering
-// Takes quads with position + UV and renders textured glyphs
+// Draws one instanced quad per glyph, textured from the glyph atlas
 
-struct VertexInput {
+struct GlyphInstance {
+    // Pen position: cell x and baseline y, in pixels
     @location(0) position: vec2<f32>,
-    @location(1) tex_coords: vec2<f32>,
-    @location(2) color: vec4<f32>,
+    // Horizontal bearing and distance from baseline to glyph bottom
+    @location(1) offset: vec2<f32>,
+    @location(2) size: vec2<f32>,
+    @location(3) uv_origin: vec2<f32>,
+    @location(4) uv_size: vec2<f32>,
+    @location(5) color: vec4<f32>,
+    // Vertical pixel range (top, bottom) the quad is cut to
+    @location(6) clip: vec2<f32>,
 }
 
 struct VertexOutput {
@@ -27,16 +34,38 @@ var atlas_texture: texture_2d<f32>;
 var atlas_sampler: sampler;
 
 @vertex
-fn vs_main(in: VertexInput) -> VertexOutput {
+fn vs_main(@builtin(vertex_index) vertex_index: u32, glyph: GlyphInstance) -> VertexOutput {
     var out: VertexOutput;
 
+    // Quad corners for two triangles: (0,0) (1,0) (1,1) / (0,0) (1,1) (0,1)
+    var corners = array<vec2<f32>, 6>(
+        vec2<f32>(0.0, 0.0),
+        vec2<f32>(1.0, 0.0),
+        vec2<f32>(1.0, 1.0),
+        vec2<f32>(0.0, 0.0),
+        vec2<f32>(1.0, 1.0),
+        vec2<f32>(0.0, 1.0),
+    );
+    let corner = corners[vertex_index];
+
+    // In screen coords (Y down), the glyph top is at baseline - (height + offset_y)
+    let top_left = vec2<f32>(
+        glyph.position.x + glyph.offset.x,
+        glyph.position.y - glyph.size.y - glyph.offset.y,
+    );
+    var pixel = top_left + corner * glyph.size;
+
+    // Cut the quad to the clip range, moving the texture coordinate with it
+    pixel.y = clamp(pixel.y, glyph.clip.x, glyph.clip.y);
+    let uv_t = vec2<f32>(corner.x, (pixel.y - top_left.y) / max(glyph.size.y, 1.0));
+
     // Convert from pixel coordinates to clip space (-1 to 1)
-    let x = (in.position.x / uniforms.screen_size.x) * 2.0 - 1.0;
-    let y = 1.0 - (in.position.y / uniforms.screen_size.y) * 2.0;
+    let x = (pixel.x / uniforms.screen_size.x) * 2.0 - 1.0;
+    let y = 1.0 - (pixel.y / uniforms.screen_size.y) * 2.0;
 
     out.clip_position = vec4<f32>(x, y, 0.0, 1.0);
-    out.tex_coords = in.tex_coords;
-    out.color = in.color;
+    out.tex_coords = glyph.uv_origin + uv_t * glyph.uv_size;
+    out.color = glyph.color;
 
     return out;
 }